@@ -176,6 +176,9 @@ fn bench_graph(c: &mut Criterion) {
                     direction: Some(TraversalDirection::Outgoing),
                     limit: Some(50),
                     include_start: Some(true),
+                    edge_costs: None,
+                    incoming_cost_factor: None,
+                    min_confidence: None,
                 },
             )
             .unwrap()
@@ -194,6 +197,9 @@ fn bench_graph(c: &mut Criterion) {
                     direction: Some(TraversalDirection::Both),
                     limit: Some(100),
                     include_start: Some(true),
+                    edge_costs: None,
+                    incoming_cost_factor: None,
+                    min_confidence: None,
                 },
             )
             .unwrap()
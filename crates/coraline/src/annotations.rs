@@ -0,0 +1,476 @@
+#![forbid(unsafe_code)]
+
+//! Ownership and labelling of code.
+//!
+//! `coraline annotate` attaches tags to files or symbols in the
+//! `annotations` table. A tag is a bare label (`payments`) or a
+//! `key:value` pair (`owner:@payments-team`, `domain:billing`,
+//! `stability:experimental`); filtering by `payments` matches both
+//! `payments` and `domain:payments`.
+//!
+//! Targets are either paths — a file, a directory (everything under it), or
+//! a glob — or, with `--symbol`, a qualified name such as
+//! `PaymentService` or `src/pay.ts::PaymentService::charge`; a symbol's tags
+//! also apply to the symbols nested in it.
+//!
+//! Indexing turns each CODEOWNERS rule into `owner:` tags (also on demand
+//! with `coraline annotate import-codeowners`). As in CODEOWNERS itself,
+//! only the last rule matching a file applies. Each indexed git submodule
+//! tags its files `project:<name>`, and each workspace member its files
+//! `package:<name>` (see [`crate::packages`]). Annotations are kept across
+//! `coraline index --force`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config;
+use crate::db;
+use crate::packages;
+use crate::types::{Annotation, AnnotationSource, Node};
+use crate::worktree;
+
+/// Where GitHub looks for CODEOWNERS, in order.
+pub(crate) const CODEOWNERS_PATHS: &[&str] =
+    &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Whether `tag` is `filter`, or a `key:value` tag whose value is `filter`.
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    tag == filter
+        || tag
+            .split_once(':')
+            .is_some_and(|(_, value)| value == filter)
+}
+
+/// A tag to store: non-empty, without whitespace.
+pub fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty() && !tag.chars().any(char::is_whitespace)
+}
+
+enum Matcher {
+    Path(GlobSet),
+    Symbol(String),
+}
+
+impl Matcher {
+    fn new(annotation: &Annotation) -> Option<Self> {
+        if annotation.symbol {
+            return Some(Self::Symbol(annotation.target.clone()));
+        }
+        let target = annotation
+            .target
+            .trim_start_matches("./")
+            .trim_end_matches('/');
+        // A file or directory matches itself and everything below it.
+        let mut builder = GlobSetBuilder::new();
+        for pattern in [target.to_string(), format!("{target}/**")] {
+            match GlobBuilder::new(&pattern).literal_separator(true).build() {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(err) => {
+                    warn!(pattern, error = %err, "ignoring invalid annotation target");
+                    return None;
+                }
+            }
+        }
+        builder.build().ok().map(Self::Path)
+    }
+
+    fn matches_path(&self, path: &str) -> bool {
+        matches!(self, Self::Path(set) if set.is_match(path))
+    }
+
+    fn matches(&self, node: &Node) -> bool {
+        match self {
+            Self::Path(set) => set.is_match(&node.file_path),
+            Self::Symbol(target) => {
+                let qn = &node.qualified_name;
+                qn == target
+                    || qn.ends_with(&format!("::{target}"))
+                    || qn.starts_with(&format!("{target}::"))
+                    || qn.contains(&format!("::{target}::"))
+            }
+        }
+    }
+}
+
+/// The stored annotations, compiled for matching nodes.
+#[derive(Default)]
+pub struct Annotations {
+    user: Vec<(Matcher, String)>,
+    /// CODEOWNERS rules in file order, with their owner tags.
+    codeowners: Vec<(Matcher, Vec<String>)>,
+    /// Package roots, innermost first, with their `package:` tags.
+    packages: Vec<(String, String)>,
+}
+
+impl Annotations {
+    /// Load every annotation of the project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the database cannot be queried.
+    pub fn load(conn: &Connection) -> std::io::Result<Self> {
+        let mut annotations = Self::default();
+        let mut rule: Option<(i64, String, Vec<String>)> = None;
+        for annotation in db::list_annotations(conn)? {
+            match annotation.source {
+                AnnotationSource::User | AnnotationSource::Submodule => {
+                    if let Some(matcher) = Matcher::new(&annotation) {
+                        annotations.user.push((matcher, annotation.tag));
+                    }
+                }
+                AnnotationSource::Package => {
+                    annotations
+                        .packages
+                        .push((annotation.target, annotation.tag));
+                }
+                AnnotationSource::Codeowners => match &mut rule {
+                    Some((position, _, tags)) if *position == annotation.position => {
+                        tags.push(annotation.tag);
+                    }
+                    _ => {
+                        if let Some(done) = rule.take() {
+                            annotations.push_rule(done);
+                        }
+                        rule = Some((annotation.position, annotation.target, vec![annotation.tag]));
+                    }
+                },
+            }
+        }
+        if let Some(done) = rule {
+            annotations.push_rule(done);
+        }
+        annotations
+            .packages
+            .sort_by_key(|(root, _)| std::cmp::Reverse(packages::root_depth(root)));
+        Ok(annotations)
+    }
+
+    fn push_rule(&mut self, (position, target, tags): (i64, String, Vec<String>)) {
+        let annotation = Annotation {
+            target,
+            symbol: false,
+            tag: String::new(),
+            source: AnnotationSource::Codeowners,
+            position,
+        };
+        if let Some(matcher) = Matcher::new(&annotation) {
+            self.codeowners.push((matcher, tags));
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.user.is_empty() && self.codeowners.is_empty() && self.packages.is_empty()
+    }
+
+    /// The `package:` tag of the innermost package containing `path`.
+    fn package_tag(&self, path: &str) -> Option<&String> {
+        self.packages
+            .iter()
+            .find(|(root, _)| packages::root_contains(root, path))
+            .map(|(_, tag)| tag)
+    }
+
+    /// Tags of `node`, sorted and without duplicates.
+    pub fn tags_for(&self, node: &Node) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .user
+            .iter()
+            .filter(|(matcher, _)| matcher.matches(node))
+            .map(|(_, tag)| tag.clone())
+            .collect();
+        if let Some((_, owners)) = self.codeowners.iter().rev().find(|(m, _)| m.matches(node)) {
+            tags.extend(owners.iter().filter(|tag| !tag.is_empty()).cloned());
+        }
+        tags.extend(self.package_tag(&node.file_path).cloned());
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Tags of the file at `path`, without the tags of symbols in it.
+    pub fn tags_for_path(&self, path: &str) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .user
+            .iter()
+            .filter(|(matcher, _)| matcher.matches_path(path))
+            .map(|(_, tag)| tag.clone())
+            .collect();
+        if let Some((_, owners)) = self
+            .codeowners
+            .iter()
+            .rev()
+            .find(|(m, _)| m.matches_path(path))
+        {
+            tags.extend(owners.iter().filter(|tag| !tag.is_empty()).cloned());
+        }
+        tags.extend(self.package_tag(path).cloned());
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Owners of `node`: the values of its `owner:` tags.
+    pub fn owners_for(&self, node: &Node) -> Vec<String> {
+        owners(&self.tags_for(node))
+    }
+
+    /// Group `nodes` by owner, most affected owner first. A node with
+    /// several owners counts for each; nodes without one are left out.
+    pub fn group_by_owner<'a>(&self, nodes: impl IntoIterator<Item = &'a Node>) -> Vec<OwnerGroup> {
+        let mut groups: BTreeMap<String, (usize, BTreeSet<String>)> = BTreeMap::new();
+        for node in nodes {
+            for owner in self.owners_for(node) {
+                let (count, files) = groups.entry(owner).or_default();
+                *count += 1;
+                files.insert(node.file_path.clone());
+            }
+        }
+        let mut groups: Vec<OwnerGroup> = groups
+            .into_iter()
+            .map(|(owner, (nodes, files))| OwnerGroup {
+                owner,
+                nodes,
+                files: files.into_iter().collect(),
+            })
+            .collect();
+        groups.sort_by(|a, b| b.nodes.cmp(&a.nodes).then_with(|| a.owner.cmp(&b.owner)));
+        groups
+    }
+
+    /// Group `nodes` by workspace package, most affected package first.
+    /// Nodes outside every package are left out.
+    pub fn group_by_package<'a>(
+        &self,
+        nodes: impl IntoIterator<Item = &'a Node>,
+    ) -> Vec<PackageGroup> {
+        let mut groups: BTreeMap<String, (usize, BTreeSet<String>)> = BTreeMap::new();
+        for node in nodes {
+            if let Some(tag) = self.package_tag(&node.file_path) {
+                let name = tag.strip_prefix("package:").unwrap_or(tag);
+                let (count, files) = groups.entry(name.to_string()).or_default();
+                *count += 1;
+                files.insert(node.file_path.clone());
+            }
+        }
+        let mut groups: Vec<PackageGroup> = groups
+            .into_iter()
+            .map(|(package, (nodes, files))| PackageGroup {
+                package,
+                nodes,
+                files: files.into_iter().collect(),
+            })
+            .collect();
+        groups.sort_by(|a, b| {
+            b.nodes
+                .cmp(&a.nodes)
+                .then_with(|| a.package.cmp(&b.package))
+        });
+        groups
+    }
+
+    /// Whether `node` has a tag matching every one of `filters`.
+    pub fn has_tags(&self, node: &Node, filters: &[String]) -> bool {
+        if filters.is_empty() {
+            return true;
+        }
+        let tags = self.tags_for(node);
+        filters
+            .iter()
+            .all(|filter| tags.iter().any(|tag| tag_matches(tag, filter)))
+    }
+}
+
+/// The owners named by `owner:` tags.
+pub fn owners(tags: &[String]) -> Vec<String> {
+    tags.iter()
+        .filter_map(|tag| tag.strip_prefix("owner:"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The symbols and files of one owner touched by a change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnerGroup {
+    pub owner: String,
+    pub nodes: usize,
+    pub files: Vec<String>,
+}
+
+/// The symbols and files of one workspace package touched by a change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageGroup {
+    pub package: String,
+    pub nodes: usize,
+    pub files: Vec<String>,
+}
+
+/// Owner tags for each rule of a CODEOWNERS file. A rule without owners,
+/// which un-assigns a path, is kept as one empty tag so it still overrides
+/// earlier rules.
+pub fn parse_codeowners(content: &str) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let mut fields = line
+            .split_whitespace()
+            .take_while(|field| !field.starts_with('#'));
+        let Some(pattern) = fields.next() else {
+            continue;
+        };
+        let position = i64::try_from(index + 1).unwrap_or(i64::MAX);
+        let target = codeowners_glob(pattern);
+        let owners: Vec<&str> = fields.collect();
+        if owners.is_empty() {
+            annotations.push(Annotation {
+                target,
+                symbol: false,
+                tag: String::new(),
+                source: AnnotationSource::Codeowners,
+                position,
+            });
+            continue;
+        }
+        for owner in owners {
+            annotations.push(Annotation {
+                target: target.clone(),
+                symbol: false,
+                tag: format!("owner:{owner}"),
+                source: AnnotationSource::Codeowners,
+                position,
+            });
+        }
+    }
+    annotations
+}
+
+/// A CODEOWNERS pattern as an annotation glob: patterns with a leading or
+/// inner `/` are relative to the root, others match at any depth.
+fn codeowners_glob(pattern: &str) -> String {
+    let anchored = pattern.starts_with('/') || pattern.trim_end_matches('/').contains('/');
+    let trimmed = pattern.trim_start_matches('/').trim_end_matches('/');
+    if anchored || trimmed.starts_with("**") {
+        trimmed.to_string()
+    } else {
+        format!("**/{trimmed}")
+    }
+}
+
+/// The project's CODEOWNERS file, if it has one.
+pub fn find_codeowners(project_root: &Path) -> Option<PathBuf> {
+    CODEOWNERS_PATHS
+        .iter()
+        .map(|path| project_root.join(path))
+        .find(|path| path.is_file())
+}
+
+/// Replace the CODEOWNERS annotations with the rules of the project's
+/// CODEOWNERS file, or drop them when it has none.
+///
+/// Returns the file and its number of rules, or `None` when there is no
+/// CODEOWNERS file. Every index and sync runs this.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the file cannot be read or the database
+/// cannot be updated.
+pub fn import_codeowners(
+    project_root: &Path,
+    conn: &mut Connection,
+) -> std::io::Result<Option<(PathBuf, usize)>> {
+    let path = find_codeowners(project_root);
+    let annotations = match &path {
+        Some(path) => parse_codeowners(&std::fs::read_to_string(path)?),
+        None => Vec::new(),
+    };
+    // Skip the write when nothing changed, as on most syncs. Like the
+    // table, the map keeps the last rule for a repeated pattern and owner.
+    let rows = |rows: &mut dyn Iterator<Item = &Annotation>| -> BTreeMap<(String, String), i64> {
+        rows.map(|a| ((a.target.clone(), a.tag.clone()), a.position))
+            .collect()
+    };
+    let stored = db::list_annotations(conn)?;
+    let stored = rows(
+        &mut stored
+            .iter()
+            .filter(|a| a.source == AnnotationSource::Codeowners),
+    );
+    if stored != rows(&mut annotations.iter()) {
+        db::replace_annotations(conn, AnnotationSource::Codeowners, &annotations)?;
+    }
+    let mut rules: Vec<i64> = annotations.iter().map(|a| a.position).collect();
+    rules.dedup();
+    Ok(path.map(|path| (path, rules.len())))
+}
+
+/// Tag the files of each indexed git submodule `project:<name>`,
+/// replacing the tags of the previous import. Returns the number of
+/// submodules tagged.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be updated.
+pub fn import_submodules(project_root: &Path, conn: &mut Connection) -> std::io::Result<usize> {
+    let indexed =
+        config::load_toml_config(project_root).map_or(true, |cfg| cfg.indexing.index_submodules);
+    let annotations: Vec<Annotation> = if indexed {
+        worktree::submodules(project_root)
+            .into_iter()
+            .map(|submodule| Annotation {
+                target: submodule.path,
+                symbol: false,
+                tag: format!("project:{}", submodule.name),
+                source: AnnotationSource::Submodule,
+                position: 0,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let stored: Vec<Annotation> = db::list_annotations(conn)?
+        .into_iter()
+        .filter(|a| a.source == AnnotationSource::Submodule)
+        .collect();
+    let key = |a: &Annotation| (a.target.clone(), a.tag.clone());
+    if stored.iter().map(key).collect::<BTreeSet<_>>()
+        != annotations.iter().map(key).collect::<BTreeSet<_>>()
+    {
+        db::replace_annotations(conn, AnnotationSource::Submodule, &annotations)?;
+    }
+    Ok(annotations.len())
+}
+
+/// Tag the files of each workspace member `package:<name>`, replacing the
+/// tags of the previous import. Returns the number of packages tagged.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be updated.
+pub fn import_packages(project_root: &Path, conn: &mut Connection) -> std::io::Result<usize> {
+    let annotations: Vec<Annotation> = packages::detect(project_root)
+        .into_iter()
+        .map(|package| Annotation {
+            target: package.root,
+            symbol: false,
+            tag: format!("package:{}", package.name),
+            source: AnnotationSource::Package,
+            position: 0,
+        })
+        .collect();
+    let stored: Vec<Annotation> = db::list_annotations(conn)?
+        .into_iter()
+        .filter(|a| a.source == AnnotationSource::Package)
+        .collect();
+    let key = |a: &Annotation| (a.target.clone(), a.tag.clone());
+    if stored.iter().map(key).collect::<BTreeSet<_>>()
+        != annotations.iter().map(key).collect::<BTreeSet<_>>()
+    {
+        db::replace_annotations(conn, AnnotationSource::Package, &annotations)?;
+    }
+    Ok(annotations.len())
+}
@@ -0,0 +1,323 @@
+#![forbid(unsafe_code)]
+
+//! Layering rules for `coraline analyze arch`.
+//!
+//! Each `[[architecture.rules]]` entry names the files it constrains
+//! (`from`) and the files they may not depend on (`forbid`), or the only
+//! ones they may (`allow`). A dependency is a cross-file edge of the graph
+//! (a call, reference, instantiation, inheritance, or type use) or an
+//! import statement whose module maps onto an indexed file: a Rust `use`
+//! path, a Python module, or a relative JavaScript/TypeScript specifier.
+//! Dependencies among the files a rule constrains are always allowed.
+//!
+//! ```toml
+//! [[architecture.rules]]
+//! name = "ui-not-db"
+//! from = ["src/ui/**"]
+//! forbid = ["src/db/**"]
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rusqlite::Connection;
+
+use crate::config::{ArchitectureConfig, ArchitectureRule};
+use crate::db;
+use crate::resolution::python_modules::PythonModules;
+use crate::resolution::rust_modules::RustModules;
+use crate::types::{EdgeKind, Language, Node, NodeKind};
+
+/// Extensions a relative script import may leave out, in lookup order.
+const SCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+
+/// A dependency a rule does not allow.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    /// The rule's name, or a description of it.
+    pub rule: String,
+    /// The import, or the symbol whose code depends on the target.
+    pub source: Node,
+    /// The symbol depended on, or the file node of an imported module.
+    pub target: Node,
+    /// `imports` for an import statement, else the kind of the edge.
+    pub kind: EdgeKind,
+    pub line: Option<i64>,
+}
+
+/// The output of [`check`].
+#[derive(Debug, Clone, Default)]
+pub struct ArchitectureReport {
+    /// Rules checked.
+    pub rules: usize,
+    /// Cross-file dependencies checked against them.
+    pub dependencies: usize,
+    /// In rule order, then by file and line.
+    pub violations: Vec<Violation>,
+}
+
+struct CompiledRule {
+    name: String,
+    from: GlobSet,
+    forbid: GlobSet,
+    allow: Option<GlobSet>,
+}
+
+impl CompiledRule {
+    fn new(rule: &ArchitectureRule) -> std::io::Result<Self> {
+        let name = rule.name.clone().unwrap_or_else(|| describe(rule));
+        Ok(Self {
+            from: glob_set(&name, &rule.from)?,
+            forbid: glob_set(&name, &rule.forbid)?,
+            allow: if rule.allow.is_empty() {
+                None
+            } else {
+                Some(glob_set(&name, &rule.allow)?)
+            },
+            name,
+        })
+    }
+
+    fn forbids(&self, from_file: &str, to_file: &str) -> bool {
+        self.from.is_match(from_file)
+            && !self.from.is_match(to_file)
+            && (self.forbid.is_match(to_file)
+                || self
+                    .allow
+                    .as_ref()
+                    .is_some_and(|allow| !allow.is_match(to_file)))
+    }
+}
+
+/// `src/ui/** may not depend on src/db/**`, for a rule without a name.
+fn describe(rule: &ArchitectureRule) -> String {
+    let from = rule.from.join(", ");
+    if rule.allow.is_empty() {
+        format!("{from} may not depend on {}", rule.forbid.join(", "))
+    } else {
+        format!("{from} may only depend on {}", rule.allow.join(", "))
+    }
+}
+
+fn glob_set(rule: &str, patterns: &[String]) -> std::io::Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid pattern {pattern:?} in architecture rule {rule:?}: {err}"),
+            )
+        })?;
+        builder.add(glob);
+    }
+    builder.build().map_err(std::io::Error::other)
+}
+
+/// Check every dependency in the graph against the rules of `cfg`.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if a rule has an invalid glob pattern or the
+/// database cannot be queried.
+pub fn check(conn: &Connection, cfg: &ArchitectureConfig) -> std::io::Result<ArchitectureReport> {
+    let rules = cfg
+        .rules
+        .iter()
+        .map(CompiledRule::new)
+        .collect::<std::io::Result<Vec<_>>>()?;
+    if rules.is_empty() {
+        return Ok(ArchitectureReport::default());
+    }
+
+    // (source id, target id, kind, line, source file, target file)
+    let mut dependencies: Vec<(String, String, EdgeKind, Option<i64>, String, String)> =
+        db::list_cross_file_edges(conn)?
+            .into_iter()
+            .map(|(edge, from, to)| (edge.source, edge.target, edge.kind, edge.line, from, to))
+            .collect();
+
+    let files: HashMap<String, String> = db::get_nodes_by_kind(conn, NodeKind::File)?
+        .into_iter()
+        .map(|file| (file.file_path, file.id))
+        .collect();
+    let rust = RustModules::load(conn)?;
+    let python = PythonModules::load(conn)?;
+    for import in db::get_nodes_by_kind(conn, NodeKind::Import)? {
+        for file in imported_files(&import, &rust, &python, &files) {
+            if file == import.file_path {
+                continue;
+            }
+            if let Some(file_id) = files.get(&file) {
+                dependencies.push((
+                    import.id.clone(),
+                    file_id.clone(),
+                    EdgeKind::Imports,
+                    Some(import.start_line),
+                    import.file_path.clone(),
+                    file,
+                ));
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, rule) in rules.iter().enumerate() {
+        for (source, target, kind, line, from, to) in &dependencies {
+            if rule.forbids(from, to) && seen.insert((index, source, target, *line)) {
+                found.push((index, source.clone(), target.clone(), *kind, *line));
+            }
+        }
+    }
+
+    let ids: Vec<String> = found
+        .iter()
+        .flat_map(|(_, source, target, _, _)| [source.clone(), target.clone()])
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let nodes: HashMap<String, Node> = db::get_nodes_by_ids(conn, &ids)?
+        .into_iter()
+        .map(|node| (node.id.clone(), node))
+        .collect();
+    let mut violations: Vec<(usize, Violation)> = found
+        .into_iter()
+        .filter_map(|(index, source, target, kind, line)| {
+            Some((
+                index,
+                Violation {
+                    rule: rules.get(index)?.name.clone(),
+                    source: nodes.get(&source)?.clone(),
+                    target: nodes.get(&target)?.clone(),
+                    kind,
+                    line,
+                },
+            ))
+        })
+        .collect();
+    violations.sort_by(|(a_rule, a), (b_rule, b)| {
+        (a_rule, &a.source.file_path, a.line).cmp(&(b_rule, &b.source.file_path, b.line))
+    });
+
+    Ok(ArchitectureReport {
+        rules: rules.len(),
+        dependencies: dependencies.len(),
+        violations: violations.into_iter().map(|(_, v)| v).collect(),
+    })
+}
+
+/// The indexed files the module of `import` maps onto.
+fn imported_files(
+    import: &Node,
+    rust: &RustModules,
+    python: &PythonModules,
+    files: &HashMap<String, String>,
+) -> Vec<String> {
+    let signature = import.signature.as_deref().unwrap_or_default();
+    let module = signature
+        .split_once("|export=")
+        .map_or(signature, |(module, _)| module);
+    match import.language {
+        Language::Rust => {
+            // `use crate::db::{open, Connection}` names the module before the braces.
+            let path = module.split("::{").next().unwrap_or(module);
+            let segments: Vec<&str> = path.split("::").filter(|s| !s.is_empty()).collect();
+            // The path names either a module or a symbol in one.
+            let mut found: Vec<String> = rust
+                .resolve(&import.file_path, &segments)
+                .into_iter()
+                .chain(rust.resolve(
+                    &import.file_path,
+                    segments.split_last().map_or(&[][..], |(_, module)| module),
+                ))
+                .map(|target| target.file)
+                .collect();
+            found.dedup();
+            found
+        }
+        Language::Python => python.resolve(&import.file_path, module),
+        Language::JavaScript | Language::Jsx | Language::TypeScript | Language::Tsx
+            if module.starts_with('.') =>
+        {
+            let base = normalize(
+                &Path::new(&import.file_path)
+                    .parent()
+                    .unwrap_or_else(|| Path::new(""))
+                    .join(module),
+            );
+            // ESM imports name `./db.js` for `db.ts`.
+            let stem = base
+                .strip_suffix(".js")
+                .or_else(|| base.strip_suffix(".mjs"))
+                .unwrap_or(&base);
+            std::iter::once(base.clone())
+                .chain(SCRIPT_EXTENSIONS.iter().map(|ext| format!("{stem}.{ext}")))
+                .chain(
+                    SCRIPT_EXTENSIONS
+                        .iter()
+                        .map(|ext| format!("{base}/index.{ext}")),
+                )
+                .find(|candidate| files.contains_key(candidate))
+                .into_iter()
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// `path` with `.` and `..` folded away, `/`-separated.
+fn normalize(path: &Path) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                parts.pop();
+            }
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            _ => {}
+        }
+    }
+    parts
+        .iter()
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+
+    use super::*;
+
+    fn rule(from: &[&str], forbid: &[&str], allow: &[&str]) -> CompiledRule {
+        let owned = |patterns: &[&str]| patterns.iter().map(|p| (*p).to_string()).collect();
+        CompiledRule::new(&ArchitectureRule {
+            name: None,
+            from: owned(from),
+            forbid: owned(forbid),
+            allow: owned(allow),
+        })
+        .expect("valid rule")
+    }
+
+    #[test]
+    fn test_forbid_and_allow() {
+        let forbid = rule(&["src/ui/**"], &["src/db/**"], &[]);
+        assert!(forbid.forbids("src/ui/page.ts", "src/db/query.ts"));
+        assert!(!forbid.forbids("src/ui/page.ts", "src/domain/user.ts"));
+        assert!(!forbid.forbids("src/api/route.ts", "src/db/query.ts"));
+        assert_eq!(forbid.name, "src/ui/** may not depend on src/db/**");
+
+        let allow = rule(&["src/domain/**"], &[], &["src/util/**"]);
+        assert!(allow.forbids("src/domain/user.rs", "src/db/mod.rs"));
+        assert!(!allow.forbids("src/domain/user.rs", "src/util/ids.rs"));
+        assert!(!allow.forbids("src/domain/user.rs", "src/domain/order.rs"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(Path::new("src/ui/../db/./query")), "src/db/query");
+    }
+}
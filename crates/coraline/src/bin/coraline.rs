@@ -1,25 +1,56 @@
 #![allow(clippy::multiple_crate_versions)]
 use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
 use std::time::Duration;
 
+use coraline::annotations;
+use coraline::architecture;
 use coraline::audit;
+use coraline::branches;
 use coraline::config;
 use coraline::context;
+use coraline::coverage::{self, CoverageFormat};
+use coraline::daemon;
 use coraline::db;
+use coraline::dead_code::{self, Confidence};
+use coraline::diff_context::{self, DiffContextOptions};
+use coraline::duplicates::{self, DuplicateOptions};
+use coraline::error::CoralineError;
+use coraline::export;
 use coraline::extraction;
+use coraline::graph;
+use coraline::graphql;
+use coraline::history;
+use coraline::implementations;
+use coraline::issues;
 use coraline::logging;
+use coraline::map;
 use coraline::mcp::McpServer;
+use coraline::mcp_http;
 use coraline::memory;
-use coraline::sync::GitHooksManager;
-use coraline::types::NodeKind;
-use coraline::types::{BuildContextOptions, ContextFormat, EdgeKind};
+use coraline::output::{ErrorClass, ErrorEnvelope, Mark, OutputStyle, exit};
+use coraline::remotes::{self, FederatedHit};
+use coraline::resolution::{self, ReferenceResolver};
+use coraline::rest;
+use coraline::scip;
+use coraline::sensitive::SensitiveFiles;
+use coraline::staleness::{self, Freshness};
+use coraline::sync::{self, GitHook, GitHooksManager, HookManager};
+use coraline::tour;
+use coraline::types::{
+    Annotation, AnnotationSource, BuildContextOptions, ContextFormat, EdgeKind, TaskInput,
+};
+use coraline::types::{
+    ExtractionErrorSeverity, IssueSource, Language, Node, NodeCoverage, NodeKind, SearchOptions,
+};
 use coraline::update;
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 use coraline::vectors;
+use coraline::watch;
+use coraline::worktree::{self, ParseCache};
 use indicatif::{ProgressBar, ProgressStyle};
 use tracing::{debug, info};
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Debug, Parser)]
 #[command(name = "coraline")]
@@ -28,14 +59,90 @@ use clap::{Args, Parser, Subcommand};
 struct Cli {
     #[command(subcommand)]
     command: Option<Command>,
+    /// Plain output: no colour, spinners, or emoji, and ungrouped numbers.
+    #[arg(long = "plain", global = true)]
+    plain: bool,
+    /// Spell out status marks instead of using emoji.
+    #[arg(long = "no-emoji", global = true)]
+    no_emoji: bool,
+    /// Report failures on stderr as a one-line JSON object.
+    #[arg(long = "json-errors", global = true)]
+    json_errors: bool,
+    /// Print command results as text or as JSON for scripts (implies
+    /// `--json` where a command has it).
+    #[arg(
+        long = "output-format",
+        value_enum,
+        global = true,
+        default_value_t = OutputFormat::Text
+    )]
+    output_format: OutputFormat,
+}
+
+/// Format of command results on stdout, selected by `--output-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+static OUTPUT: std::sync::OnceLock<OutputStyle> = std::sync::OnceLock::new();
+static JSON_ERRORS: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+static JSON_OUTPUT: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Whether to print results as JSON: asked for by a command's own `--json`
+/// flag or by `--output-format json`.
+fn json_output(requested: bool) -> bool {
+    requested || JSON_OUTPUT.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Print `value` as pretty JSON on stdout.
+fn print_json(value: &impl serde::Serialize) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    );
+}
+
+/// Output style selected by `--plain` / `--no-emoji` and the environment.
+fn style() -> OutputStyle {
+    OUTPUT.get().copied().unwrap_or_default()
+}
+
+/// Report a failure on stderr, as plain text or under `--json-errors` as an
+/// [`ErrorEnvelope`], and exit with the code of its class.
+fn fail(class: ErrorClass, message: impl std::fmt::Display) -> ! {
+    let message = message.to_string();
+    if JSON_ERRORS.load(std::sync::atomic::Ordering::Relaxed) {
+        let envelope = ErrorEnvelope::new(class, message.trim());
+        eprintln!(
+            "{}",
+            serde_json::to_string(&envelope).unwrap_or_else(|_| message.clone())
+        );
+    } else {
+        eprintln!("{message}");
+    }
+    std::process::exit(class.exit_code());
 }
 
 #[derive(Debug, Subcommand)]
 enum Command {
     Install,
     Init(InitArgs),
+    /// Remove Coraline from a project: `.coraline/`, its git hook, and the
+    /// shared parse cache once no other worktree uses it.
+    Uninit(UninitArgs),
     Index(IndexArgs),
     Sync(SyncArgs),
+    /// Retry unresolved references, and report the names that stay
+    /// unresolved.
+    Resolve(ResolveArgs),
+    /// Keep the graph in sync with the working tree, syncing after each
+    /// burst of file changes until interrupted.
+    Watch(WatchArgs),
+    /// Serve queries, syncs, and MCP tool calls from one warm process over
+    /// a local socket; other commands delegate to it while it runs.
+    Daemon(DaemonArgs),
     Status(StatusArgs),
     Stats(StatsArgs),
     Query(QueryArgs),
@@ -50,9 +157,41 @@ enum Command {
     Update,
     /// Audit documentation accuracy and coverage against the code graph.
     AuditDocs(AuditDocsArgs),
-    #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
+    /// Export graph changes as JSON, optionally only those since a checkpoint,
+    /// or the whole index as SCIP.
+    Export(ExportArgs),
+    /// Serve a local web graph explorer.
+    Viz(VizArgs),
+    /// Explore the graph interactively in the terminal.
+    Tui(TuiArgs),
+    /// Report where the code lives: symbols, kinds, and size per directory.
+    Map(MapArgs),
+    /// Generate an onboarding walkthrough of the codebase (Markdown).
+    Tour(TourArgs),
+    /// List logged contexts, or replay one against the current index.
+    ContextLog(ContextLogArgs),
+    /// Maintain the project database (analytical views).
+    Db(DbArgs),
+    /// Analyze the code graph (dead code, duplicates, hotspots, implementations,
+    /// architecture rules).
+    Analyze(AnalyzeArgs),
+    /// Tag files and symbols with owners, domains, or stability labels.
+    Annotate(AnnotateArgs),
+    /// Map an LCOV or Cobertura coverage report onto graph nodes.
+    IngestCoverage(IngestCoverageArgs),
+    /// Link issue ids in commit messages and comments to graph nodes.
+    LinkIssues(LinkIssuesArgs),
+    /// Show the issues linked to a symbol.
+    IssuesFor(IssuesForArgs),
+    /// Record how often files and symbols changed, from git history.
+    Churn(ChurnArgs),
+    /// List the git worktrees of the project and their shared parse cache.
+    Worktrees(WorktreesArgs),
+    /// List the graphs saved per git branch.
+    Branches(BranchesArgs),
+    /// Register other indexed projects whose graphs queries also search.
+    Remote(RemoteArgs),
     Embed(EmbedArgs),
-    #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
     Model(ModelArgs),
 }
 
@@ -71,6 +210,19 @@ struct InitArgs {
     force: bool,
 }
 
+#[derive(Debug, Args)]
+struct UninitArgs {
+    path: Option<PathBuf>,
+    #[arg(short = 'f', long = "force", help = "Remove without prompting")]
+    force: bool,
+    /// Leave the git hooks Coraline installed in place.
+    #[arg(long = "keep-hooks")]
+    keep_hooks: bool,
+    /// List what would be removed without removing anything.
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+}
+
 #[derive(Debug, Args)]
 struct IndexArgs {
     path: Option<PathBuf>,
@@ -78,6 +230,9 @@ struct IndexArgs {
     force: bool,
     #[arg(short = 'q', long = "quiet")]
     quiet: bool,
+    /// Hide progress bars but still print the summary.
+    #[arg(long = "no-progress")]
+    no_progress: bool,
 }
 
 #[derive(Debug, Args)]
@@ -85,6 +240,47 @@ struct SyncArgs {
     path: Option<PathBuf>,
     #[arg(short = 'q', long = "quiet")]
     quiet: bool,
+    /// Hide progress bars but still print the summary.
+    #[arg(long = "no-progress")]
+    no_progress: bool,
+    /// Run unobtrusively: fewer threads and lower CPU/IO priority
+    /// (`[sync] throttle_*`). Used by the git hook.
+    #[arg(long = "throttle")]
+    throttle: bool,
+    /// Only sync the files git reports changed since this revision, or in
+    /// a range such as `main..feature`, instead of scanning the project.
+    #[arg(long = "since", value_name = "REV")]
+    since: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct WatchArgs {
+    path: Option<PathBuf>,
+    /// Quiet period before syncing, in milliseconds (default: `[sync]
+    /// debounce_ms`).
+    #[arg(long = "debounce-ms")]
+    debounce_ms: Option<u64>,
+    /// Print only failures.
+    #[arg(short = 'q', long = "quiet")]
+    quiet: bool,
+}
+
+#[derive(Debug, Args)]
+struct DaemonArgs {
+    #[command(subcommand)]
+    action: DaemonAction,
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum DaemonAction {
+    /// Run the daemon in the foreground until stopped.
+    Start,
+    /// Stop the running daemon.
+    Stop,
+    /// Report whether a daemon is running.
+    Status,
 }
 
 #[derive(Debug, Args)]
@@ -99,15 +295,51 @@ struct QueryArgs {
     path: Option<PathBuf>,
     #[arg(short = 'l', long = "limit", default_value_t = 10)]
     limit: usize,
+    /// Skip this many results, for paging with --limit.
+    #[arg(long = "offset", default_value_t = 0)]
+    offset: usize,
     #[arg(short = 'k', long = "kind")]
     kind: Option<String>,
+    /// Only symbols in files matching this glob (repeatable; any may match).
+    #[arg(short = 'f', long = "file", value_name = "GLOB")]
+    files: Vec<String>,
+    /// Only symbols in this language (repeatable), e.g. `rust` or `ts`.
+    #[arg(long = "lang", value_name = "LANGUAGE")]
+    languages: Vec<String>,
+    /// Only exported symbols.
+    #[arg(long = "exported-only")]
+    exported_only: bool,
+    /// Only symbols with this annotation tag (repeatable; all must match).
+    #[arg(short = 't', long = "tag")]
+    tags: Vec<String>,
+    /// Only symbols in this workspace package (same as `--tag package:NAME`).
+    #[arg(long = "package", value_name = "NAME")]
+    package: Option<String>,
+    /// Search only this project, not its registered remotes.
+    #[arg(long = "local-only")]
+    local_only: bool,
     #[arg(short = 'j', long = "json")]
     json: bool,
 }
 
 #[derive(Debug, Args)]
 struct ContextArgs {
-    task: String,
+    #[arg(required_unless_present = "diff")]
+    task: Option<String>,
+    /// Build a review context for the diff of the working tree against REV
+    /// (default `HEAD`) instead of a task.
+    #[arg(
+        long = "diff",
+        value_name = "REV",
+        num_args = 0..=1,
+        default_missing_value = "HEAD",
+        conflicts_with = "task"
+    )]
+    diff: Option<String>,
+    /// Search an aspect of the task on its own and merge the contexts;
+    /// repeat for several.
+    #[arg(long = "query", value_name = "QUERY", conflicts_with = "diff")]
+    queries: Vec<String>,
     #[arg(short = 'p', long = "path")]
     path: Option<PathBuf>,
     #[arg(short = 'n', long = "max-nodes", default_value_t = 50)]
@@ -116,6 +348,7 @@ struct ContextArgs {
     max_code: usize,
     #[arg(long = "no-code")]
     no_code: bool,
+    /// `markdown`, `json`, `xml`, or `repo-map` (file paths and signatures).
     #[arg(short = 'f', long = "format", default_value = "markdown")]
     format: String,
 }
@@ -129,7 +362,12 @@ struct StatsArgs {
 
 #[derive(Debug, Args)]
 struct CallersArgs {
-    node_id: String,
+    /// Node ID, or a symbol name (`--file` picks between symbols sharing it).
+    #[arg(value_name = "SYMBOL")]
+    symbol: String,
+    /// File of the symbol when several share its name, e.g. `math.ts`.
+    #[arg(short = 'f', long = "file")]
+    file: Option<String>,
     #[arg(short = 'p', long = "path")]
     path: Option<PathBuf>,
     #[arg(short = 'l', long = "limit", default_value_t = 20)]
@@ -140,7 +378,12 @@ struct CallersArgs {
 
 #[derive(Debug, Args)]
 struct CalleesArgs {
-    node_id: String,
+    /// Node ID, or a symbol name (`--file` picks between symbols sharing it).
+    #[arg(value_name = "SYMBOL")]
+    symbol: String,
+    /// File of the symbol when several share its name, e.g. `math.ts`.
+    #[arg(short = 'f', long = "file")]
+    file: Option<String>,
     #[arg(short = 'p', long = "path")]
     path: Option<PathBuf>,
     #[arg(short = 'l', long = "limit", default_value_t = 20)]
@@ -151,11 +394,19 @@ struct CalleesArgs {
 
 #[derive(Debug, Args)]
 struct ImpactArgs {
-    node_id: String,
+    /// Node ID, or a symbol name (`--file` picks between symbols sharing it).
+    #[arg(value_name = "SYMBOL")]
+    symbol: String,
+    /// File of the symbol when several share its name, e.g. `math.ts`.
+    #[arg(short = 'f', long = "file")]
+    file: Option<String>,
     #[arg(short = 'p', long = "path")]
     path: Option<PathBuf>,
     #[arg(short = 'd', long = "depth", default_value_t = 3)]
     depth: usize,
+    /// Skip resolved edges whose resolution confidence (0.0-1.0) is lower.
+    #[arg(long = "min-confidence", value_name = "SCORE")]
+    min_confidence: Option<f64>,
     #[arg(short = 'j', long = "json")]
     json: bool,
 }
@@ -173,6 +424,24 @@ struct ConfigArgs {
     /// Set a value: --set section.key=value
     #[arg(long = "set")]
     set: Option<String>,
+    #[command(subcommand)]
+    action: Option<ConfigAction>,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigAction {
+    /// Print one value, e.g. `indexing.max_file_size` or `languages`.
+    Get { key: String },
+    /// Set one value and save it; the value is parsed as JSON, so
+    /// `50`, `true` and `'["rust"]'` keep their types.
+    Set {
+        key: String,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+    /// Check config.toml and config.json for parse errors, ignored keys,
+    /// invalid glob patterns, and unknown languages.
+    Validate,
 }
 
 #[derive(Debug, Args)]
@@ -185,9 +454,25 @@ struct HooksArgs {
 
 #[derive(Debug, Subcommand)]
 enum HooksAction {
-    Install,
+    /// Install the post-commit hook.
+    Install {
+        /// Also sync after merges and pulls, checkouts, and rebases or
+        /// amends (post-merge, post-checkout, post-rewrite).
+        #[arg(long = "all")]
+        all: bool,
+    },
+    /// Remove every hook Coraline installed.
     Remove,
+    /// Show which hooks are installed.
     Status,
+    /// Print hook config for a hook manager (`lefthook` or `husky`)
+    /// instead of installing scripts.
+    Snippet {
+        manager: String,
+        /// Include post-merge, post-checkout, and post-rewrite.
+        #[arg(long = "all")]
+        all: bool,
+    },
 }
 
 #[derive(Debug, Args)]
@@ -196,22 +481,47 @@ struct ServeArgs {
     path: Option<PathBuf>,
     #[arg(long = "mcp")]
     mcp: bool,
+    /// Serve MCP over streamable HTTP on this address (e.g. 127.0.0.1:7879)
+    /// instead of stdio.
+    #[arg(long = "http", value_name = "ADDR")]
+    http: Option<String>,
+    /// Serve the read-only REST API (`/search`, `/nodes/{id}`, `/callers/{id}`,
+    /// `/context`, `/stats`, `/openapi.json`) on this address instead of MCP.
+    #[arg(long = "rest", value_name = "ADDR", conflicts_with_all = ["mcp", "http"])]
+    rest: Option<String>,
+    /// Serve a read-only GraphQL endpoint (`/graphql`, schema at
+    /// `/graphql/schema`) on this address instead of MCP.
+    #[arg(
+        long = "graphql",
+        value_name = "ADDR",
+        conflicts_with_all = ["mcp", "http", "rest"]
+    )]
+    graphql: Option<String>,
     /// Refuse to start MCP unless [security].enabled = true in config.toml.
     #[arg(long = "require-security")]
     require_security: bool,
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 #[derive(Debug, Args)]
 struct EmbedArgs {
     /// Project root (defaults to current directory).
     path: Option<PathBuf>,
-    /// Number of nodes to embed per batch (for progress display).
+    /// Number of nodes to embed per committed batch.
     #[arg(long = "batch-size", default_value_t = 50)]
     batch_size: usize,
+    /// Re-embed every node instead of only nodes without a vector.
+    #[arg(long = "all")]
+    all: bool,
+    /// Delete all stored embeddings first, then re-embed every node (use after
+    /// changing the embedding model or provider).
+    #[arg(long = "rebuild")]
+    rebuild: bool,
     /// Suppress progress output.
     #[arg(short = 'q', long = "quiet")]
     quiet: bool,
+    /// Hide the progress bar but still print the summary.
+    #[arg(long = "no-progress")]
+    no_progress: bool,
     /// Download the model from `HuggingFace` if not already present.
     #[arg(long = "download")]
     download: bool,
@@ -223,7 +533,6 @@ struct EmbedArgs {
     skip_sync: bool,
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 #[derive(Debug, Args)]
 struct ModelArgs {
     #[arg(short = 'p', long = "path")]
@@ -235,7 +544,6 @@ struct ModelArgs {
     action: ModelAction,
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 #[derive(Debug, Subcommand)]
 enum ModelAction {
     /// Download model files from `HuggingFace` (tokenizer + ONNX weights).
@@ -269,8 +577,395 @@ struct AuditDocsArgs {
     json: bool,
 }
 
+#[derive(Debug, Args)]
+struct ExportArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Only export changes after this checkpoint name or millisecond timestamp.
+    #[arg(long = "since")]
+    since: Option<String>,
+    /// Record a checkpoint with this name once the export is written.
+    #[arg(long = "checkpoint")]
+    checkpoint: Option<String>,
+    /// Output format: `json`, `cypher` (Neo4j / Memgraph statements),
+    /// `parquet` (a directory of tables; needs the `parquet` feature), or
+    /// `scip` (a SCIP index file for code search and editors).
+    #[arg(short = 'f', long = "format", default_value = "json")]
+    format: String,
+    /// Write to this file (directory for `parquet`) instead of stdout.
+    #[arg(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct VizArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Address to bind (use 0.0.0.0 to share on the local network).
+    #[arg(long = "host", default_value = "127.0.0.1")]
+    host: String,
+    /// Port to listen on.
+    #[arg(long = "port", default_value_t = coraline::viz::DEFAULT_PORT)]
+    port: u16,
+}
+
+#[derive(Debug, Args)]
+struct TuiArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct MapArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Directory levels to break out; deeper directories fold into their parent.
+    #[arg(short = 'd', long = "depth", default_value_t = 3)]
+    depth: usize,
+    /// Size bars and boxes by `nodes` (symbols), `bytes`, or `lines`.
+    #[arg(long = "by", default_value = "nodes")]
+    by: String,
+    /// Output format: `text`, `html` (a treemap page), or `json`.
+    #[arg(short = 'f', long = "format", default_value = "text")]
+    format: String,
+    /// Write to this file instead of stdout.
+    #[arg(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct TourArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Most entries per section.
+    #[arg(short = 'l', long = "limit", default_value_t = 10)]
+    limit: usize,
+    /// Write to this file instead of stdout.
+    #[arg(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+    #[arg(short = 'j', long = "json")]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct ContextLogArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Rebuild entry N (as numbered in the listing) and compare its entry points.
+    #[arg(short = 'r', long = "replay")]
+    replay: Option<usize>,
+    /// Show only the most recent N entries.
+    #[arg(short = 'l', long = "limit", default_value_t = 20)]
+    limit: usize,
+    #[arg(short = 'j', long = "json")]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct IngestCoverageArgs {
+    /// Coverage report (`lcov.info` or Cobertura `coverage.xml`).
+    report: PathBuf,
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Report format: `lcov` or `cobertura` (detected when omitted).
+    #[arg(short = 'f', long = "format")]
+    format: Option<String>,
+    #[arg(short = 'j', long = "json")]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct LinkIssuesArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    #[arg(short = 'j', long = "json")]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct ResolveArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Resolve only the N oldest unresolved references.
+    #[arg(short = 'l', long = "limit")]
+    limit: Option<usize>,
+    /// Unresolved references read per batch.
+    #[arg(long = "batch-size", default_value_t = 10_000)]
+    batch_size: usize,
+    /// List the names with the most unresolved references left.
+    #[arg(short = 'r', long = "report")]
+    report: bool,
+    /// Names listed by `--report`.
+    #[arg(long = "top", default_value_t = 20)]
+    top: usize,
+    #[arg(short = 'j', long = "json")]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct ChurnArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Most recent commits to scan (default: `[history] max_commits`).
+    #[arg(short = 'n', long = "max-commits")]
+    max_commits: Option<usize>,
+    #[arg(short = 'j', long = "json")]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct IssuesForArgs {
+    /// Symbol name or node id.
+    symbol: String,
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Only consider symbols defined in this file.
+    #[arg(short = 'f', long = "file")]
+    file: Option<String>,
+    #[arg(short = 'j', long = "json")]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct WorktreesArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Delete parse cache entries not written in this many days.
+    #[arg(long = "prune-days", conflicts_with = "clear_cache")]
+    prune_days: Option<u64>,
+    /// Delete every parse cache entry.
+    #[arg(long = "clear-cache")]
+    clear_cache: bool,
+    #[arg(short = 'j', long = "json")]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct BranchesArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    /// Delete the saved graphs of branches that no longer exist.
+    #[arg(long = "prune")]
+    prune: bool,
+    #[arg(short = 'j', long = "json")]
+    json: bool,
+}
+
+#[derive(Debug, Args)]
+struct RemoteArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    #[command(subcommand)]
+    action: RemoteAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum RemoteAction {
+    /// Register an indexed project root, or the URL of its `serve --rest` API.
+    Add {
+        location: String,
+        /// Name to show on its results (default: its package, directory, or host name).
+        #[arg(long = "name")]
+        name: Option<String>,
+    },
+    /// Unregister a remote.
+    Remove { name: String },
+    /// List the registered remotes.
+    List {
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+    /// Show the local imports of packages that remotes publish.
+    Resolve {
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+struct DbArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    #[command(subcommand)]
+    action: DbAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum DbAction {
+    /// Create the `call_pairs`, `file_dependencies`, and `symbol_metrics`
+    /// views and print how to attach the database from `DuckDB`.
+    Views {
+        /// Drop the views instead of creating them.
+        #[arg(long = "drop")]
+        drop: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+struct AnalyzeArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    #[command(subcommand)]
+    action: AnalyzeAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum AnalyzeAction {
+    /// List functions, types, and constants nothing in the project uses,
+    /// most confident first.
+    DeadCode {
+        /// Lowest confidence to list: low, medium, or high.
+        #[arg(long = "min-confidence", default_value = "low")]
+        min_confidence: String,
+        /// Maximum candidates to print (0 = all).
+        #[arg(short = 'l', long = "limit", default_value_t = 0)]
+        limit: usize,
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+    /// Find clusters of copy-pasted functions and methods, across files and
+    /// languages.
+    Duplicates {
+        /// Skip bodies with fewer normalized tokens.
+        #[arg(long = "min-tokens", default_value_t = 50)]
+        min_tokens: usize,
+        /// Lowest similarity of two bodies, from 0 to 1.
+        #[arg(long = "threshold", default_value_t = 0.9)]
+        threshold: f64,
+        /// Maximum clusters to print (0 = all).
+        #[arg(short = 'l', long = "limit", default_value_t = 20)]
+        limit: usize,
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+    /// Rank symbols by churn times fan-in: code that changes often and that
+    /// much depends on. Needs `coraline churn` first.
+    Hotspots {
+        /// Maximum symbols to print (0 = all).
+        #[arg(short = 'l', long = "limit", default_value_t = 20)]
+        limit: usize,
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+    /// Check dependencies against the `[[architecture.rules]]` of
+    /// config.toml; exits non-zero on a violation.
+    Arch {
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+    /// List the types implementing a trait, interface, or base class, with
+    /// the required methods each leaves undefined.
+    Implementations {
+        /// Name of the trait, interface, protocol, or class.
+        name: String,
+        /// File defining it, when several types share the name.
+        #[arg(short = 'f', long = "file")]
+        file: Option<String>,
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+struct AnnotateArgs {
+    #[arg(short = 'p', long = "path")]
+    path: Option<PathBuf>,
+    #[command(subcommand)]
+    action: AnnotateAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum AnnotateAction {
+    /// Tag a file, directory, glob, or (with --symbol) a symbol.
+    Add {
+        target: String,
+        /// Tags such as `payments`, `owner:@team`, `stability:experimental`.
+        #[arg(required = true)]
+        tags: Vec<String>,
+        /// TARGET is a symbol's (qualified) name rather than a path.
+        #[arg(long = "symbol")]
+        symbol: bool,
+    },
+    /// Remove tags from a target (all of its tags when none are given).
+    Remove {
+        target: String,
+        tags: Vec<String>,
+        #[arg(long = "symbol")]
+        symbol: bool,
+    },
+    /// List stored annotations.
+    List {
+        #[arg(short = 'j', long = "json")]
+        json: bool,
+    },
+    /// Replace the owner tags from CODEOWNERS with its current rules.
+    ImportCodeowners,
+}
+
+impl Command {
+    /// The project path the command was given, if it takes one.
+    fn path(&self) -> Option<PathBuf> {
+        match self {
+            Self::Init(a) => a.path.clone(),
+            Self::Uninit(a) => a.path.clone(),
+            Self::Index(a) => a.path.clone(),
+            Self::Sync(a) => a.path.clone(),
+            Self::Resolve(a) => a.path.clone(),
+            Self::Watch(a) => a.path.clone(),
+            Self::Daemon(a) => a.path.clone(),
+            Self::Status(a) => a.path.clone(),
+            Self::Stats(a) => a.path.clone(),
+            Self::Query(a) => a.path.clone(),
+            Self::Context(a) => a.path.clone(),
+            Self::Callers(a) => a.path.clone(),
+            Self::Callees(a) => a.path.clone(),
+            Self::Impact(a) => a.path.clone(),
+            Self::Config(a) => a.path.clone(),
+            Self::Hooks(a) => a.path.clone(),
+            Self::Serve(a) => a.path.clone(),
+            Self::AuditDocs(a) => a.path.clone(),
+            Self::Export(a) => a.path.clone(),
+            Self::Viz(a) => a.path.clone(),
+            Self::Tui(a) => a.path.clone(),
+            Self::Map(a) => a.path.clone(),
+            Self::Tour(a) => a.path.clone(),
+            Self::ContextLog(a) => a.path.clone(),
+            Self::Db(a) => a.path.clone(),
+            Self::Analyze(a) => a.path.clone(),
+            Self::Annotate(a) => a.path.clone(),
+            Self::IngestCoverage(a) => a.path.clone(),
+            Self::LinkIssues(a) => a.path.clone(),
+            Self::IssuesFor(a) => a.path.clone(),
+            Self::Churn(a) => a.path.clone(),
+            Self::Worktrees(a) => a.path.clone(),
+            Self::Branches(a) => a.path.clone(),
+            Self::Remote(a) => a.path.clone(),
+            Self::Embed(a) => a.path.clone(),
+            Self::Model(a) => a.path.clone(),
+            Self::Install | Self::Update => None,
+        }
+    }
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let cli = Cli::try_parse().unwrap_or_else(|err| {
+        // `--help` and `--version` also arrive here, on stdout.
+        if !err.use_stderr() {
+            err.exit();
+        }
+        if std::env::args().any(|arg| arg == "--json-errors") {
+            JSON_ERRORS.store(true, std::sync::atomic::Ordering::Relaxed);
+            fail(ErrorClass::Usage, err);
+        }
+        let _ = err.print();
+        std::process::exit(exit::USAGE);
+    });
+    JSON_ERRORS.store(cli.json_errors, std::sync::atomic::Ordering::Relaxed);
+    JSON_OUTPUT.store(
+        cli.output_format == OutputFormat::Json,
+        std::sync::atomic::Ordering::Relaxed,
+    );
+    let _ = OUTPUT.set(OutputStyle::from_env(cli.plain, cli.no_emoji));
     if matches!(cli.command, None | Some(Command::Install)) {
         run_installer();
         return;
@@ -281,36 +976,17 @@ fn main() {
     };
 
     // Resolve project root early so logging can target the right directory
-    let project_root_hint = match &command {
-        Command::Init(a) => a.path.clone(),
-        Command::Index(a) => a.path.clone(),
-        Command::Sync(a) => a.path.clone(),
-        Command::Status(a) => a.path.clone(),
-        Command::Stats(a) => a.path.clone(),
-        Command::Query(a) => a.path.clone(),
-        Command::Context(a) => a.path.clone(),
-        Command::Callers(a) => a.path.clone(),
-        Command::Callees(a) => a.path.clone(),
-        Command::Impact(a) => a.path.clone(),
-        Command::Config(a) => a.path.clone(),
-        Command::Hooks(a) => a.path.clone(),
-        Command::Serve(a) => a.path.clone(),
-        Command::AuditDocs(a) => a.path.clone(),
-        #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
-        Command::Embed(a) => a.path.clone(),
-        #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
-        Command::Model(a) => a.path.clone(),
-        Command::Install | Command::Update => None,
-    };
-    let project_root = resolve_project_root(project_root_hint);
+    let project_root = resolve_project_root(command.path());
     // Don't create .coraline/logs/ before the init command runs — that would
-    // cause is_initialized() to return true and block a fresh init.
-    let log_root =
-        if matches!(command, Command::Init(_)) && !project_root.join(".coraline").is_dir() {
-            None
-        } else {
-            Some(project_root.as_path())
-        };
+    // cause is_initialized() to return true and block a fresh init — or
+    // while uninit is deleting it.
+    let log_root = if matches!(command, Command::Uninit(_))
+        || (matches!(command, Command::Init(_)) && !project_root.join(".coraline").is_dir())
+    {
+        None
+    } else {
+        Some(project_root.as_path())
+    };
     let _log_guard = logging::init(log_root);
     info!("coraline starting");
     debug!(command = ?command, "dispatching command");
@@ -318,65 +994,120 @@ fn main() {
     match command {
         Command::Install => run_installer(),
         Command::Init(args) => run_init(args),
+        Command::Uninit(args) => run_uninit(&args),
         Command::Index(args) => run_index(args),
         Command::Sync(args) => run_sync(args),
+        Command::Watch(args) => run_watch(&args),
+        Command::Daemon(args) => match args.action {
+            DaemonAction::Start => run_daemon_start(args.path),
+            DaemonAction::Stop => run_daemon_stop(args.path),
+            DaemonAction::Status => run_daemon_status(args.path),
+        },
         Command::Status(args) => run_status(args),
         Command::Stats(args) => run_stats(args),
-        Command::Query(args) => run_query(args),
+        Command::Query(args) => run_query(&args),
         Command::Context(args) => run_context(args),
         Command::Callers(args) => run_callers(args),
         Command::Callees(args) => run_callees(args),
         Command::Impact(args) => run_impact(args),
         Command::Config(args) => run_config(args),
         Command::Hooks(args) => match args.action {
-            HooksAction::Install => run_hooks_install(args.path),
+            HooksAction::Install { all } => run_hooks_install(args.path, all),
             HooksAction::Remove => run_hooks_remove(args.path),
             HooksAction::Status => run_hooks_status(args.path),
+            HooksAction::Snippet { manager, all } => run_hooks_snippet(&manager, all),
         },
-        Command::Serve(args) => {
-            if args.mcp {
-                let serve_root = resolve_project_root(args.path.clone());
-                let security_enabled =
-                    config::load_toml_config(&serve_root).is_ok_and(|cfg| cfg.security.enabled);
+        Command::Serve(args) => run_serve(args),
+        Command::Update => run_update(),
+        Command::AuditDocs(args) => run_audit_docs(args),
+        Command::Export(args) => run_export(&args),
+        Command::Viz(args) => run_viz(&args),
+        Command::Tui(args) => run_tui(&args),
+        Command::Map(args) => run_map(&args),
+        Command::Tour(args) => run_tour(&args),
+        Command::ContextLog(args) => run_context_log(&args),
+        Command::Db(args) => run_db(args),
+        Command::Analyze(args) => run_analyze(args),
+        Command::Annotate(args) => run_annotate(args),
+        Command::IngestCoverage(args) => run_ingest_coverage(&args),
+        Command::LinkIssues(args) => run_link_issues(&args),
+        Command::IssuesFor(args) => run_issues_for(&args),
+        Command::Resolve(args) => run_resolve(&args),
+        Command::Churn(args) => run_churn(&args),
+        Command::Worktrees(args) => run_worktrees(&args),
+        Command::Branches(args) => run_branches(&args),
+        Command::Remote(args) => run_remote(args),
+        Command::Embed(args) => run_embed(&args),
+        Command::Model(args) => run_model(args),
+    }
+}
 
-                if args.require_security && !security_enabled {
-                    eprintln!(
-                        "Refusing to start MCP server: security is disabled. Set [security].enabled = true or remove --require-security."
-                    );
-                    std::process::exit(2);
-                }
+fn run_serve(args: ServeArgs) {
+    if let Some(addr) = &args.rest {
+        run_serve_rest(args.path.clone(), addr);
+    } else if let Some(addr) = &args.graphql {
+        run_serve_graphql(args.path.clone(), addr);
+    } else if args.mcp || args.http.is_some() {
+        let serve_root = resolve_project_root(args.path.clone());
+        let security_enabled =
+            config::load_toml_config(&serve_root).is_ok_and(|cfg| cfg.security.enabled);
+
+        if args.require_security && !security_enabled {
+            fail(
+                ErrorClass::Config,
+                "Refusing to start MCP server: security is disabled. Set [security].enabled = true or remove --require-security.",
+            );
+        }
 
-                if !security_enabled {
-                    eprintln!(
-                        "Warning: MCP security guardrails are disabled ([security].enabled = false)."
-                    );
-                }
+        if !security_enabled {
+            eprintln!(
+                "Warning: MCP security guardrails are disabled ([security].enabled = false)."
+            );
+        }
 
-                let mut server = McpServer::new(Some(serve_root));
-                if let Err(err) = server.start() {
-                    eprintln!("Failed to start MCP server: {err}");
-                    std::process::exit(1);
-                }
-            } else {
-                println!("Use --mcp to start the MCP server.");
+        if let Some(addr) = args.http {
+            let loopback = addr
+                .parse::<std::net::SocketAddr>()
+                .is_ok_and(|a| a.ip().is_loopback())
+                || addr.starts_with("localhost:");
+            if !loopback {
+                eprintln!(
+                    "Warning: {addr} is not a loopback address; anyone who can reach it can call Coraline's tools."
+                );
+            }
+            eprintln!(
+                "MCP server listening on http://{addr}{}",
+                mcp_http::ENDPOINT_PATH
+            );
+            if let Err(err) = mcp_http::serve(&serve_root, &addr) {
+                fail(
+                    ErrorClass::Failure,
+                    format!("Failed to start MCP server: {err}"),
+                );
+            }
+        } else {
+            let mut server = McpServer::new(Some(serve_root));
+            if let Err(err) = server.start() {
+                fail(
+                    ErrorClass::Failure,
+                    format!("Failed to start MCP server: {err}"),
+                );
             }
         }
-        Command::Update => run_update(),
-        Command::AuditDocs(args) => run_audit_docs(args),
-        #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
-        Command::Embed(args) => run_embed(&args),
-        #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
-        Command::Model(args) => run_model(args),
+    } else {
+        println!(
+            "Use --mcp to start the MCP server, --rest <ADDR> for the REST API, or --graphql <ADDR> for GraphQL."
+        );
     }
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 fn run_model(args: ModelArgs) {
     let project_root = resolve_project_root(args.path);
     let cfg = config::load_toml_config(&project_root).unwrap_or_default();
     let model_dir = cfg
         .vectors
         .model_dir
+        .as_deref()
         .map_or_else(|| vectors::default_model_dir(&project_root), PathBuf::from);
 
     match args.action {
@@ -387,8 +1118,7 @@ fn run_model(args: ModelArgs) {
                     println!("Downloading {variant} into {} ...", model_dir.display());
                 }
                 if let Err(e) = vectors::download_model(&model_dir, &variant, !force, args.quiet) {
-                    eprintln!("Download failed: {e}");
-                    std::process::exit(1);
+                    fail(ErrorClass::Failure, format!("Download failed: {e}"));
                 }
                 if !args.quiet {
                     println!("Done. Run `coraline embed` to generate embeddings.");
@@ -397,27 +1127,34 @@ fn run_model(args: ModelArgs) {
             #[cfg(not(feature = "embeddings"))]
             {
                 let _ = (variant, force); // suppress unused warnings
-                eprintln!("Model download is not available in this build.");
-                eprintln!(
-                    "This binary was built with `embeddings-dynamic`, which loads ONNX Runtime at runtime."
-                );
-                eprintln!();
-                eprintln!("To use embeddings, manually download the model files:");
-                eprintln!(
-                    "  1. Download tokenizer.json from: {}",
-                    vectors::tokenizer_url()
-                );
-                eprintln!(
-                    "  2. Download model_int8.onnx from: {}",
-                    vectors::model_url("model_int8.onnx")
-                );
-                eprintln!("  3. Place both files in: {}", model_dir.display());
-                std::process::exit(1);
+                let steps = [
+                    "Model download is not available in this build.".to_string(),
+                    "This binary was built without the `embeddings` feature.".to_string(),
+                    String::new(),
+                    "To use embeddings, manually download the model files:".to_string(),
+                    format!(
+                        "  1. Download tokenizer.json from: {}",
+                        vectors::tokenizer_url()
+                    ),
+                    format!(
+                        "  2. Download model_int8.onnx from: {}",
+                        vectors::model_url("model_int8.onnx")
+                    ),
+                    format!("  3. Place both files in: {}", model_dir.display()),
+                ];
+                fail(ErrorClass::Failure, steps.join("\n"));
             }
         }
         ModelAction::Status => {
-            println!("Model directory: {}", model_dir.display());
-            println!();
+            if cfg.vectors.provider != config::EmbeddingProvider::Onnx {
+                println!(
+                    "Provider: {:?} (model `{}`); local model files are not used.",
+                    cfg.vectors.provider, cfg.vectors.model
+                );
+                println!();
+            }
+            println!("Model directory: {}", model_dir.display());
+            println!();
             for name in vectors::MODEL_PREFERENCE_ORDER {
                 let p = model_dir.join(name);
                 if let Ok(meta) = std::fs::metadata(&p) {
@@ -435,11 +1172,47 @@ fn run_model(args: ModelArgs) {
                     println!("  {name:<30}  (not present)");
                 }
             }
+            print_stored_embeddings(&project_root, &cfg.vectors);
+        }
+    }
+}
+
+/// Print the stored embeddings per model, flagging an index that does not
+/// match the configured model.
+fn print_stored_embeddings(project_root: &Path, vectors_cfg: &config::VectorsConfig) {
+    if is_initialized(project_root)
+        && let Ok(conn) = db::open_database(project_root)
+        && let Ok(models) = vectors::vector_models(&conn)
+        && !models.is_empty()
+    {
+        println!();
+        println!("Stored embeddings:");
+        for m in &models {
+            println!(
+                "  {:<30}  {:>5}-d  {} vectors",
+                m.model, m.dimension, m.vectors
+            );
+        }
+        // The ONNX backend always records its vectors under the default model.
+        let expected = if vectors_cfg.provider == config::EmbeddingProvider::Onnx {
+            vectors::DEFAULT_MODEL
+        } else {
+            vectors_cfg.model.as_str()
+        };
+        if models
+            .iter()
+            .any(|m| m.model != expected || m.dimension != vectors_cfg.dimension)
+        {
+            println!();
+            println!(
+                "Index does not match the configured model `{expected}` ({}-d); \
+                 run `coraline embed --rebuild`.",
+                vectors_cfg.dimension
+            );
         }
     }
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 fn embedding_model_dir(project_root: &Path) -> PathBuf {
     let cfg = config::load_toml_config(project_root).unwrap_or_default();
     cfg.vectors
@@ -447,13 +1220,18 @@ fn embedding_model_dir(project_root: &Path) -> PathBuf {
         .map_or_else(|| vectors::default_model_dir(project_root), PathBuf::from)
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 fn run_embed(args: &EmbedArgs) {
     let project_root = resolve_project_root(args.path.clone());
 
     if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    if let Ok(toml_cfg) = config::load_toml_config(&project_root) {
+        sync::limit_threads(toml_cfg.sync.max_threads);
     }
 
     // Auto-sync: ensure the index is up to date before embedding.
@@ -477,93 +1255,108 @@ fn run_embed(args: &EmbedArgs) {
             );
         }
         if let Err(e) = vectors::download_model(&model_dir, &args.variant, true, args.quiet) {
-            eprintln!("Download failed: {e}");
-            std::process::exit(1);
+            fail(ErrorClass::Failure, format!("Download failed: {e}"));
         }
     }
     #[cfg(not(feature = "embeddings"))]
     if args.download {
-        eprintln!("Model download is not available in this build (embeddings-dynamic).");
-        eprintln!("Please download the model files manually. See: coraline model download --help");
-        std::process::exit(1);
+        fail(
+            ErrorClass::Failure,
+            "Model download is not available in this build.\n\
+             Please download the model files manually. See: coraline model download --help",
+        );
     }
 
     let mut vm = match load_vector_manager_with_indicator(&project_root, args.quiet) {
         Ok(vm) => vm,
         Err(err) => {
             let model_dir = embedding_model_dir(&project_root);
-            // Check whether the error is due to missing model files specifically.
-            let no_model = vectors::find_model_file(&model_dir, None).is_err();
-            if no_model {
-                #[cfg(feature = "embeddings")]
-                {
-                    eprintln!("No embedding model found in {}.", model_dir.display());
-                    if prompt_yes_no("Download model now? [Y/n]") {
-                        if !args.quiet {
-                            println!(
-                                "Downloading {} into {} …",
-                                args.variant,
-                                model_dir.display()
-                            );
-                        }
-                        if let Err(e) =
-                            vectors::download_model(&model_dir, &args.variant, true, args.quiet)
-                        {
-                            eprintln!("Download failed: {e}");
-                            std::process::exit(1);
-                        }
-                    } else {
-                        eprintln!(
-                            "Hint: run `coraline embed --download` or `coraline model download` to fetch the model."
-                        );
-                        std::process::exit(1);
-                    }
-                }
-                #[cfg(not(feature = "embeddings"))]
-                {
-                    eprintln!("No embedding model found in {}.", model_dir.display());
-                    eprintln!(
-                        "This build does not support automatic download. Download the model files manually:"
-                    );
-                    eprintln!("  tokenizer.json  — {}", vectors::tokenizer_url());
-                    eprintln!(
-                        "  model_int8.onnx — {}",
-                        vectors::model_url("model_int8.onnx")
-                    );
-                    eprintln!("Place both files in: {}", model_dir.display());
-                    std::process::exit(1);
-                }
-            } else {
-                eprintln!("Failed to load model: {err}");
-                std::process::exit(1);
+            // Only a missing model is recoverable here; anything else is fatal.
+            if vectors::find_model_file(&model_dir, None).is_ok() {
+                fail(ErrorClass::Failure, format!("Failed to load model: {err}"));
             }
-            // Retry loading after download (only reached in the embeddings feature path).
+            eprintln!("No embedding model found in {}.", model_dir.display());
+            offer_model_download(&model_dir, args);
+
             match load_vector_manager_with_indicator(&project_root, args.quiet) {
                 Ok(vm) => vm,
                 Err(e) => {
-                    eprintln!("Failed to load model after download: {e}");
-                    std::process::exit(1);
+                    fail(
+                        ErrorClass::Failure,
+                        format!("Failed to load model after download: {e}"),
+                    );
                 }
             }
         }
     };
 
-    embed_all_nodes(&project_root, args, &mut vm);
+    embed_nodes(&project_root, args, &mut vm);
+}
+
+/// Ask to download the missing model and fetch it; exits when the user
+/// declines, stdin is not interactive, or the download fails.
+#[cfg(feature = "embeddings")]
+fn offer_model_download(model_dir: &Path, args: &EmbedArgs) {
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        fail(
+            ErrorClass::Failure,
+            "Hint: run `coraline embed --download` or `coraline model download` to fetch the model.",
+        );
+    }
+    if !prompt_yes_no("Download model now? [Y/n]") {
+        fail(
+            ErrorClass::Failure,
+            "Hint: run `coraline embed --download` or `coraline model download` to fetch the model.",
+        );
+    }
+    if !args.quiet {
+        println!(
+            "Downloading {} into {} …",
+            args.variant,
+            model_dir.display()
+        );
+    }
+    if let Err(e) = vectors::download_model(model_dir, &args.variant, true, args.quiet) {
+        fail(ErrorClass::Failure, format!("Download failed: {e}"));
+    }
+}
+
+/// Builds without bundled ONNX Runtime cannot download; print the manual
+/// steps and exit.
+#[cfg(not(feature = "embeddings"))]
+fn offer_model_download(model_dir: &Path, _args: &EmbedArgs) {
+    let steps = [
+        "This build does not support automatic download. Download the model files manually:"
+            .to_string(),
+        format!("  tokenizer.json  — {}", vectors::tokenizer_url()),
+        format!(
+            "  model_int8.onnx — {}",
+            vectors::model_url("model_int8.onnx")
+        ),
+        format!("Place both files in: {}", model_dir.display()),
+    ];
+    fail(ErrorClass::Failure, steps.join("\n"));
 }
 
 /// Prompt the user with a yes/no question. Returns `true` if the user answers
-/// yes (or presses Enter, accepting the default of yes).
+/// yes (or presses Enter, accepting the default of yes). End of input counts
+/// as no.
+#[cfg(feature = "embeddings")]
 fn prompt_yes_no(question: &str) -> bool {
+    use std::io::Write as _;
+
     eprint!("{question} ");
+    let _ = std::io::stderr().flush();
     let mut buf = String::new();
-    if std::io::stdin().read_line(&mut buf).is_err() {
-        return false;
+    match std::io::stdin().read_line(&mut buf) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => {
+            let answer = buf.trim().to_ascii_lowercase();
+            answer.is_empty() || answer == "y" || answer == "yes"
+        }
     }
-    let answer = buf.trim().to_ascii_lowercase();
-    answer.is_empty() || answer == "y" || answer == "yes"
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 fn load_vector_manager_from_project(
     project_root: &Path,
 ) -> std::io::Result<vectors::VectorManager> {
@@ -599,16 +1392,20 @@ fn load_vector_manager_from_project(
     }
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 fn load_vector_manager_with_indicator(
     project_root: &Path,
     quiet: bool,
 ) -> std::io::Result<vectors::VectorManager> {
-    if quiet {
+    // HTTP providers have nothing to load up front.
+    let provider = config::load_toml_config(project_root)
+        .unwrap_or_default()
+        .vectors
+        .provider;
+    if quiet || provider != config::EmbeddingProvider::Onnx {
         return load_vector_manager_from_project(project_root);
     }
 
-    if !std::io::IsTerminal::is_terminal(&std::io::stderr()) {
+    if style().plain || !std::io::IsTerminal::is_terminal(&std::io::stderr()) {
         eprintln!("Loading embedding model (ONNX runtime + tokenizer)...");
         return load_vector_manager_from_project(project_root);
     }
@@ -620,88 +1417,196 @@ fn load_vector_manager_with_indicator(
     result
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
-fn embed_all_nodes(project_root: &Path, args: &EmbedArgs, vm: &mut vectors::VectorManager) {
-    let conn = db::open_database(project_root).unwrap_or_else(|err| {
-        eprintln!("Failed to open database: {err}");
-        std::process::exit(1);
-    });
+/// The nodes `embed` should embed: those without a vector, or every node with
+/// `--all` or `--rebuild`, the latter first clearing the stored vectors.
+fn nodes_to_embed(
+    conn: &rusqlite::Connection,
+    args: &EmbedArgs,
+    vm: &vectors::VectorManager,
+) -> Vec<Node> {
+    if args.rebuild {
+        let removed = vectors::clear_embeddings(conn).unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Database,
+                format!("Failed to clear embeddings: {err}"),
+            );
+        });
+        if !args.quiet {
+            println!("Removed {} existing embeddings.", style().count(removed));
+        }
+    } else if !args.all
+        && let Err(err) = vectors::check_vector_models(conn, vm.model_name(), None)
+    {
+        // Embedding only the missing nodes would mix two models.
+        fail(ErrorClass::Failure, err);
+    }
+
+    if args.all || args.rebuild {
+        db::get_all_nodes(conn)
+    } else {
+        db::get_unembedded_nodes(conn)
+    }
+    .unwrap_or_else(|err| {
+        fail(ErrorClass::Database, format!("Failed to read nodes: {err}"));
+    })
+}
+
+/// Store the embeddings computed for `nodes`, returning how many were stored.
+fn store_embeddings(
+    tx: &rusqlite::Connection,
+    model_name: &str,
+    nodes: &[Node],
+    results: Vec<std::io::Result<Vec<f32>>>,
+) -> usize {
+    let mut stored = 0;
+    for (node, result) in nodes.iter().zip(results) {
+        match result {
+            Ok(embedding) => {
+                if vectors::store_embedding(tx, &node.id, &embedding, model_name).is_ok() {
+                    stored += 1;
+                }
+            }
+            Err(err) => debug!(node = %node.name, error = %err, "embed failed for node"),
+        }
+    }
+    stored
+}
 
-    let nodes = db::get_all_nodes(&conn).unwrap_or_else(|err| {
-        eprintln!("Failed to read nodes: {err}");
-        std::process::exit(1);
+/// Embed nodes that have no vector yet (every node with `--all` or
+/// `--rebuild`), committing one transaction per `--batch-size` nodes.
+fn embed_nodes(project_root: &Path, args: &EmbedArgs, vm: &mut vectors::VectorManager) {
+    let conn = db::open_database_for(project_root, db::Workload::Bulk).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
     });
 
+    let all = args.all || args.rebuild;
+    let nodes = nodes_to_embed(&conn, args, vm);
     let total = nodes.len();
     if total == 0 {
-        println!("No nodes found. Run `coraline index` first.");
+        if !args.quiet {
+            if all {
+                println!("No nodes found. Run `coraline index` first.");
+            } else {
+                println!("All nodes already have embeddings. Use --all to re-embed.");
+            }
+        }
         return;
     }
 
-    let bar = file_spinner(args.quiet);
+    let bar = count_bar(args.quiet || args.no_progress, total as u64);
+    bar.set_prefix("Embedding");
 
     let mut ok = 0usize;
     let mut skipped = 0usize;
 
-    for node in &nodes {
-        let target = if node.file_path.is_empty() {
-            node.qualified_name.as_str()
-        } else {
-            node.file_path.as_str()
-        };
-        bar.set_message(format!("Embedding: {target}"));
-
-        let text = vectors::node_embed_text(
-            &node.name,
-            &node.qualified_name,
-            node.docstring.as_deref(),
-            node.signature.as_deref(),
-        );
+    let mut done = 0usize;
+    let mut chunks = 0usize;
+    let mut failure = None;
+    'batches: for batch in nodes.chunks(args.batch_size.max(1)) {
+        let tx = conn.unchecked_transaction().unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Database,
+                format!("Failed to start transaction: {err}"),
+            );
+        });
 
-        match vm.embed(&text) {
-            Ok(embedding) => {
-                if let Err(_err) =
-                    vectors::store_embedding(&conn, &node.id, &embedding, vm.model_name())
-                {
-                    skipped += 1;
+        // Sub-batches of `vectors.batch_size` go to the backend together.
+        for request in batch.chunks(vm.batch_size()) {
+            let target = request.first().map_or("", |node| {
+                if node.file_path.is_empty() {
+                    node.qualified_name.as_str()
                 } else {
-                    ok += 1;
+                    node.file_path.as_str()
                 }
+            });
+            done += request.len();
+            bar.set_position(done as u64);
+            bar.set_message(target.to_string());
+
+            let results = vectors::embed_node_batch(vm, request);
+            // A batch in which every node fails means the backend itself is
+            // broken (e.g. an unreachable endpoint); stop rather than retry.
+            if let Some(Err(err)) = results.first()
+                && results.iter().all(Result::is_err)
+            {
+                failure = Some(err.to_string());
+                tx.commit().unwrap_or_else(|err| {
+                    fail(
+                        ErrorClass::Database,
+                        format!("Failed to store embeddings: {err}"),
+                    );
+                });
+                break 'batches;
             }
-            Err(err) => {
-                debug!(node = %node.name, error = %err, "embed failed for node");
-                skipped += 1;
+
+            let stored = store_embeddings(&tx, vm.model_name(), request, results);
+            ok += stored;
+            skipped += request.len() - stored;
+
+            match vectors::embed_body_chunks(project_root, &tx, vm, request) {
+                Ok(stored) => chunks += stored,
+                Err(err) => debug!(error = %err, "storing body chunks failed"),
             }
         }
+
+        tx.commit().unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Database,
+                format!("Failed to store embeddings: {err}"),
+            );
+        });
     }
 
     bar.finish_and_clear();
+    if let Some(err) = failure {
+        let out = style();
+        let class = if ok > 0 {
+            ErrorClass::Partial
+        } else {
+            ErrorClass::Failure
+        };
+        fail(
+            class,
+            format!(
+                "Embedding failed: {err}\nStored {}/{} embeddings before stopping.",
+                out.count(ok),
+                out.count(total)
+            ),
+        );
+    }
     if !args.quiet {
-        println!("Embedded {ok}/{total} nodes ({skipped} skipped)");
+        let out = style();
+        println!(
+            "Embedded {}/{} nodes and {} body chunks ({} skipped)",
+            out.count(ok),
+            out.count(total),
+            out.count(chunks),
+            out.count(skipped)
+        );
     }
 }
 
 /// Check whether the index is stale and run sync automatically before embedding.
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 fn auto_sync_before_embed(project_root: &Path, quiet: bool) {
-    let mut cfg = match config::load_config(project_root) {
+    let cfg = match config::load_config(project_root) {
         Ok(cfg) => cfg,
         Err(err) => {
-            eprintln!("Failed to load config: {err}");
-            std::process::exit(1);
+            fail(ErrorClass::Config, format!("Failed to load config: {err}"));
         }
     };
-    if let Ok(toml_cfg) = config::load_toml_config(project_root) {
-        config::apply_toml_to_code_graph(&mut cfg, &toml_cfg);
-    }
 
     if !quiet {
         print!("Checking index freshness…");
     }
 
     let status = extraction::needs_sync(project_root, &cfg).unwrap_or_else(|err| {
-        eprintln!("\nFailed to check sync status: {err}");
-        std::process::exit(1);
+        fail(
+            ErrorClass::Failure,
+            format!("\nFailed to check sync status: {err}"),
+        );
     });
 
     if !status.is_stale() {
@@ -713,41 +1618,34 @@ fn auto_sync_before_embed(project_root: &Path, quiet: bool) {
 
     if !quiet {
         let total_changes = status.files_added + status.files_modified + status.files_removed;
-        println!(" {total_changes} change(s) detected, syncing…");
+        println!(
+            " {} change(s) detected, syncing…",
+            style().count(total_changes)
+        );
     }
 
-    let bar = file_spinner(quiet);
-    let bar_cb = bar.clone();
-    let cb = move |p: extraction::IndexProgress| {
-        let phase = match p.phase {
-            extraction::IndexPhase::Scanning => "Scanning",
-            extraction::IndexPhase::Parsing => "Parsing",
-            extraction::IndexPhase::Storing => "Storing",
-            extraction::IndexPhase::Resolving => "Resolving",
-        };
-        let msg = p
-            .current_file
-            .map_or_else(|| phase.to_owned(), |f| format!("{phase}: {f}"));
-        bar_cb.set_message(msg);
-    };
+    let bar = IndexBar::new(quiet);
+    let cb = |p: extraction::IndexProgress| bar.update(p);
     let result = extraction::sync(project_root, &cfg, if quiet { None } else { Some(&cb) })
         .unwrap_or_else(|err| {
-            eprintln!("Auto-sync failed: {err}");
-            std::process::exit(1);
+            fail(ErrorClass::Failure, format!("Auto-sync failed: {err}"));
         });
 
-    bar.finish_and_clear();
+    bar.finish();
     if !quiet {
         let total_changes = result.files_added + result.files_modified + result.files_removed;
-        println!("Synced {total_changes} files before embedding.");
+        println!(
+            "Synced {} files before embedding.",
+            style().count(total_changes)
+        );
         if result.files_added > 0 {
-            println!("  Added: {}", result.files_added);
+            println!("  Added: {}", style().count(result.files_added));
         }
         if result.files_modified > 0 {
-            println!("  Modified: {}", result.files_modified);
+            println!("  Modified: {}", style().count(result.files_modified));
         }
         if result.files_removed > 0 {
-            println!("  Removed: {}", result.files_removed);
+            println!("  Removed: {}", style().count(result.files_removed));
         }
     }
 }
@@ -772,8 +1670,10 @@ fn run_installer() {
     let current_exe = match std::env::current_exe() {
         Ok(p) => p,
         Err(e) => {
-            eprintln!("Could not determine current executable path: {e}");
-            std::process::exit(1);
+            fail(
+                ErrorClass::Failure,
+                format!("Could not determine current executable path: {e}"),
+            );
         }
     };
     let current_exe = current_exe.canonicalize().unwrap_or(current_exe);
@@ -792,23 +1692,37 @@ fn run_installer() {
     // 3. Copy to cargo bin if not already there.
     let already_installed = current_exe == target.canonicalize().unwrap_or_else(|_| target.clone());
     if already_installed {
-        println!("✔  Already installed at: {}", target.display());
+        println!(
+            "{} Already installed at: {}",
+            style().mark(Mark::Ok),
+            target.display()
+        );
     } else {
         if let Err(e) = std::fs::create_dir_all(&cargo_bin) {
-            eprintln!("Error creating {}: {e}", cargo_bin.display());
-            std::process::exit(1);
+            fail(
+                ErrorClass::Failure,
+                format!("Error creating {}: {e}", cargo_bin.display()),
+            );
         }
         match std::fs::copy(&current_exe, &target) {
-            Ok(_) => println!("✔  Installed to: {}", target.display()),
+            Ok(_) => println!(
+                "{} Installed to: {}",
+                style().mark(Mark::Ok),
+                target.display()
+            ),
             Err(e) => {
-                eprintln!("Failed to copy binary to {}: {e}", target.display());
-                if cfg!(windows) {
-                    eprintln!("Try running the installer as Administrator, or install via:");
+                let advice = if cfg!(windows) {
+                    "Try running the installer as Administrator, or install via:"
                 } else {
-                    eprintln!("Try running with sudo, or install via:");
-                }
-                eprintln!("  cargo install coraline");
-                std::process::exit(1);
+                    "Try running with sudo, or install via:"
+                };
+                fail(
+                    ErrorClass::Failure,
+                    format!(
+                        "Failed to copy binary to {}: {e}\n{advice}\n  cargo install coraline",
+                        target.display()
+                    ),
+                );
             }
         }
     }
@@ -827,9 +1741,15 @@ fn run_installer() {
     // 5. PATH check.
     println!();
     if which("coraline") {
-        println!("✔  'coraline' is on PATH — run `coraline --version` to verify.");
+        println!(
+            "{} 'coraline' is on PATH — run `coraline --version` to verify.",
+            style().mark(Mark::Ok)
+        );
     } else {
-        println!("⚠  The install directory is not on PATH.");
+        println!(
+            "{} The install directory is not on PATH.",
+            style().mark(Mark::Warning)
+        );
         if cfg!(windows) {
             println!(
                 "   Add it via: System Properties → Environment Variables → PATH → add:\n   {}",
@@ -850,10 +1770,13 @@ fn run_update() {
     match update::check_for_update() {
         Ok(status) => update::print_update_status(&status),
         Err(e) => {
-            eprintln!("Failed to check for updates: {e}");
-            eprintln!();
-            eprintln!("You can manually check: https://crates.io/crates/coraline");
-            std::process::exit(1);
+            fail(
+                ErrorClass::Failure,
+                format!(
+                    "Failed to check for updates: {e}\n\n\
+                     You can manually check: https://crates.io/crates/coraline"
+                ),
+            );
         }
     }
 }
@@ -872,13 +1795,17 @@ fn run_audit_docs(args: AuditDocsArgs) {
     let report = match audit::audit_docs(&project_root) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("Failed to run doc audit: {e}");
-            eprintln!("Make sure the project has been indexed (`coraline index`).");
-            std::process::exit(1);
+            fail(
+                ErrorClass::Failure,
+                format!(
+                    "Failed to run doc audit: {e}\n\
+                     Make sure the project has been indexed (`coraline index`)."
+                ),
+            );
         }
     };
 
-    if json {
+    if json_output(json) {
         print_audit_docs_json(&report, no_stale, no_undocumented, limit);
         return;
     }
@@ -962,7 +1889,7 @@ fn print_audit_docs_human(
     if !no_stale {
         let total = report.stale_refs.len();
         if total == 0 {
-            println!("✓ No stale references found.");
+            println!("{} No stale references found.", style().mark(Mark::Ok));
         } else {
             println!(
                 "Stale references ({total} total{})\n",
@@ -985,7 +1912,10 @@ fn print_audit_docs_human(
     if !no_undocumented {
         let total = report.undocumented_exports.len();
         if total == 0 {
-            println!("✓ All exported symbols have documentation coverage.");
+            println!(
+                "{} All exported symbols have documentation coverage.",
+                style().mark(Mark::Ok)
+            );
         } else {
             println!(
                 "Undocumented exports ({total} total{})\n",
@@ -1006,21 +1936,118 @@ fn print_audit_docs_human(
 }
 
 fn file_spinner(quiet: bool) -> ProgressBar {
-    if quiet {
+    if quiet || style().plain {
         return ProgressBar::hidden();
     }
 
     let spinner = ProgressBar::new_spinner();
-    #[allow(clippy::literal_string_with_formatting_args)]
-    spinner.set_style(
-        ProgressStyle::with_template("{spinner:.cyan} {msg}")
-            .unwrap_or_else(|_| ProgressStyle::default_spinner())
-            .tick_strings(&["⠁", "⠂", "⠄", "⡀", "⢀", "⠠", "⠐", "⠈"]),
-    );
+    spinner.set_style(progress_style(SPINNER));
     spinner.enable_steady_tick(Duration::from_millis(90));
     spinner
 }
 
+/// `template` with the spinner coloured when colour is on. `{rate}` is the
+/// items per second, rounded.
+fn progress_style(template: &str) -> ProgressStyle {
+    let template = if style().color {
+        template
+            .replace("{spinner}", "{spinner:.cyan}")
+            .replace("{bar:30}", "{bar:30.cyan/blue}")
+    } else {
+        template.to_string()
+    };
+    ProgressStyle::with_template(&template)
+        .unwrap_or_else(|_| ProgressStyle::default_spinner())
+        .with_key(
+            "rate",
+            |state: &indicatif::ProgressState, w: &mut dyn std::fmt::Write| {
+                let _ = write!(w, "{:.0}/s", state.per_sec());
+            },
+        )
+        .progress_chars("=> ")
+        .tick_strings(&["⠁", "⠂", "⠄", "⡀", "⢀", "⠠", "⠐", "⠈"])
+}
+
+#[allow(clippy::literal_string_with_formatting_args)]
+const SPINNER: &str = "{spinner} {msg}";
+
+#[allow(clippy::literal_string_with_formatting_args)]
+const PHASE_SPINNER: &str = "{spinner} {prefix:<9} {wide_msg}";
+
+#[allow(clippy::literal_string_with_formatting_args)]
+const COUNT_BAR: &str =
+    "{spinner} {prefix:<9} [{bar:30}] {pos}/{len} ({rate}, ETA {eta}) {wide_msg}";
+
+/// A bar with a count, rate, and ETA for `total` items of work.
+fn count_bar(quiet: bool, total: u64) -> ProgressBar {
+    let bar = file_spinner(quiet);
+    bar.set_length(total);
+    bar.set_style(progress_style(COUNT_BAR));
+    bar
+}
+
+/// Progress of `index` and `sync`, driven by [`extraction::IndexProgress`]:
+/// a spinner while scanning, then a bar per phase that knows its total.
+/// Each finished phase is left behind as a one-line summary.
+struct IndexBar {
+    bar: ProgressBar,
+    phase: std::cell::Cell<Option<extraction::IndexPhase>>,
+}
+
+impl IndexBar {
+    fn new(quiet: bool) -> Self {
+        Self {
+            bar: file_spinner(quiet),
+            phase: std::cell::Cell::new(None),
+        }
+    }
+
+    fn update(&self, progress: extraction::IndexProgress) {
+        if self.phase.get() != Some(progress.phase) {
+            self.finish_phase();
+            self.phase.set(Some(progress.phase));
+            self.bar.set_prefix(match progress.phase {
+                extraction::IndexPhase::Scanning => "Scanning",
+                extraction::IndexPhase::Parsing => "Parsing",
+                extraction::IndexPhase::Storing => "Storing",
+                extraction::IndexPhase::Resolving => "Resolving",
+            });
+            self.bar.set_style(progress_style(if progress.total > 0 {
+                COUNT_BAR
+            } else {
+                PHASE_SPINNER
+            }));
+            self.bar.reset();
+        }
+        self.bar.set_length(progress.total as u64);
+        self.bar.set_position(progress.current as u64);
+        self.bar
+            .set_message(progress.current_file.unwrap_or_default());
+    }
+
+    fn finish_phase(&self) {
+        let Some(phase) = self.phase.get() else {
+            return;
+        };
+        let done = match phase {
+            extraction::IndexPhase::Scanning => format!("Found {} files", self.bar.position()),
+            extraction::IndexPhase::Parsing => format!("Parsed {} files", self.bar.position()),
+            extraction::IndexPhase::Storing => format!("Stored {} files", self.bar.position()),
+            extraction::IndexPhase::Resolving => "Resolved references".to_string(),
+        };
+        self.bar.println(format!(
+            "{} {done} in {:.1}s",
+            style().mark(Mark::Ok),
+            self.bar.elapsed().as_secs_f64()
+        ));
+    }
+
+    fn finish(&self) {
+        self.finish_phase();
+        self.bar.finish_and_clear();
+    }
+}
+
 fn run_init(args: InitArgs) {
     let project_root = resolve_project_root(args.path);
 
@@ -1038,6 +2065,7 @@ fn run_init(args: InitArgs) {
                 path: Some(project_root),
                 force: false,
                 quiet: false,
+                no_progress: false,
             });
             return;
         }
@@ -1066,20 +2094,23 @@ fn run_init(args: InitArgs) {
         }
         // Remove the existing .coraline directory before re-initializing.
         if let Err(err) = std::fs::remove_dir_all(project_root.join(".coraline")) {
-            eprintln!("Failed to remove existing .coraline directory: {err}");
-            std::process::exit(1);
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to remove existing .coraline directory: {err}"),
+            );
         }
     }
 
     if let Err(err) = create_coraline_dir(&project_root) {
-        eprintln!("Failed to create .coraline directory: {err}");
-        std::process::exit(1);
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to create .coraline directory: {err}"),
+        );
     }
 
     let cfg = config::create_default_config(&project_root);
     if let Err(err) = config::save_config(&project_root, &cfg) {
-        eprintln!("Failed to write config: {err}");
-        std::process::exit(1);
+        fail(ErrorClass::Config, format!("Failed to write config: {err}"));
     }
 
     if let Err(err) = config::write_toml_template(&project_root) {
@@ -1087,8 +2118,10 @@ fn run_init(args: InitArgs) {
     }
 
     if let Err(err) = db::initialize_database(&project_root) {
-        eprintln!("Failed to initialize database: {err}");
-        std::process::exit(1);
+        fail(
+            ErrorClass::Database,
+            format!("Failed to initialize database: {err}"),
+        );
     }
 
     // Create initial memory templates
@@ -1122,10 +2155,152 @@ fn run_init(args: InitArgs) {
             path: Some(project_root),
             force: false,
             quiet: false,
+            no_progress: false,
         });
     }
 }
 
+/// Ask before `uninit` removes `planned`. Only prompts when stdin is a
+/// terminal; otherwise declines safely.
+fn confirm_uninit(project_root: &Path, planned: &[String]) -> bool {
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        eprintln!(
+            "Refusing to remove Coraline from {} without confirmation. Use --force.",
+            project_root.display()
+        );
+        return false;
+    }
+    eprintln!("This removes:");
+    for item in planned {
+        eprintln!("  {item}");
+    }
+    eprint!("Remove Coraline from {}? [y/N] ", project_root.display());
+    let mut input = String::new();
+    if std::io::stdin().read_line(&mut input).is_err() || !input.trim().eq_ignore_ascii_case("y") {
+        println!("Aborted.");
+        return false;
+    }
+    true
+}
+
+/// Undo `init`: the git hook (restoring the hook it replaced), the shared
+/// parse cache when no other worktree is still initialized, and
+/// `.coraline/`. Coraline keeps no per-user record of projects, so nothing
+/// outside the repository is touched.
+fn run_uninit(args: &UninitArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+    let coraline_dir = project_root.join(".coraline");
+    let dir_meta = std::fs::symlink_metadata(&coraline_dir).ok();
+
+    let hooks = GitHooksManager::new(&project_root);
+    let remove_hooks = if args.keep_hooks {
+        Vec::new()
+    } else {
+        hooks.installed_hooks()
+    };
+
+    // The cache is shared by every worktree of the repository; leave it to
+    // the last one.
+    let shared_cache = worktree::detect(&project_root).and_then(|current| {
+        let dir = worktree::shared_cache_dir(&current.git_common_dir);
+        let in_use = worktree::list(&project_root)
+            .iter()
+            .any(|w| w.root != current.root && is_initialized(&w.root));
+        (dir.is_dir() && !in_use).then_some(dir)
+    });
+
+    let mut planned = Vec::new();
+    if dir_meta.is_some() {
+        planned.push(format!("{}", coraline_dir.display()));
+    }
+    match remove_hooks.len() {
+        0 => {}
+        1 => planned.push(format!("git {} hook", hook_names(&remove_hooks))),
+        _ => planned.push(format!("git {} hooks", hook_names(&remove_hooks))),
+    }
+    if let Some(dir) = &shared_cache {
+        planned.push(format!("shared parse cache at {}", dir.display()));
+    }
+    if planned.is_empty() {
+        println!(
+            "Coraline is not initialized in {}; nothing to remove.",
+            project_root.display()
+        );
+        return;
+    }
+
+    if args.dry_run {
+        println!("Would remove:");
+        for item in &planned {
+            println!("  {item}");
+        }
+        return;
+    }
+
+    if !args.force && !confirm_uninit(&project_root, &planned) {
+        return;
+    }
+
+    for hook in remove_hooks {
+        let result = hooks.remove(hook);
+        if !result.success {
+            fail(ErrorClass::Failure, &result.message);
+        }
+        let restored = if result.restored_from_backup {
+            " (previous hook restored)"
+        } else {
+            ""
+        };
+        println!(
+            "{} Removed git {} hook{restored}",
+            style().mark(Mark::Ok),
+            hook.name()
+        );
+    }
+
+    if let Some(dir) = shared_cache {
+        match std::fs::remove_dir_all(&dir) {
+            Ok(()) => {
+                // Drop `<git-common-dir>/coraline/` too once it is empty.
+                if let Some(parent) = dir.parent() {
+                    let _ = std::fs::remove_dir(parent);
+                }
+                println!(
+                    "{} Removed shared parse cache at {}",
+                    style().mark(Mark::Ok),
+                    dir.display()
+                );
+            }
+            Err(err) => eprintln!(
+                "Warning: Failed to remove shared parse cache at {}: {err}",
+                dir.display()
+            ),
+        }
+    }
+
+    if let Some(meta) = dir_meta {
+        // A symlinked `.coraline` loses the link, never the target.
+        let removed = if meta.is_dir() {
+            std::fs::remove_dir_all(&coraline_dir)
+        } else {
+            std::fs::remove_file(&coraline_dir)
+        };
+        if let Err(err) = removed {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to remove {}: {err}", coraline_dir.display()),
+            );
+        }
+        println!(
+            "{} Removed {}",
+            style().mark(Mark::Ok),
+            coraline_dir.display()
+        );
+    }
+
+    println!("Coraline removed from {}", project_root.display());
+}
+
 /// After a fresh `init`, offer to download the embedding model when stdin is a
 /// terminal.  If the user declines (or is non-interactive), we print a hint and
 /// continue — all non-embedding tools remain fully functional.
@@ -1134,6 +2309,9 @@ fn maybe_prompt_model_download(project_root: &Path) {
     use std::io::Write as _;
 
     let cfg = config::load_toml_config(project_root).unwrap_or_default();
+    if cfg.vectors.provider != config::EmbeddingProvider::Onnx {
+        return;
+    }
     let model_dir = cfg
         .vectors
         .model_dir
@@ -1180,6 +2358,9 @@ fn maybe_prompt_model_download(project_root: &Path) {
 #[cfg(all(feature = "embeddings-dynamic", not(feature = "embeddings")))]
 fn maybe_prompt_model_download(project_root: &Path) {
     let cfg = config::load_toml_config(project_root).unwrap_or_default();
+    if cfg.vectors.provider != config::EmbeddingProvider::Onnx {
+        return;
+    }
     let model_dir = cfg
         .vectors
         .model_dir
@@ -1210,52 +2391,65 @@ fn run_index(args: IndexArgs) {
     let project_root = resolve_project_root(args.path);
 
     if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
     }
 
-    let mut cfg = match config::load_config(&project_root) {
+    let cfg = match config::load_config(&project_root) {
         Ok(cfg) => cfg,
         Err(err) => {
-            eprintln!("Failed to load config: {err}");
-            std::process::exit(1);
+            fail(ErrorClass::Config, format!("Failed to load config: {err}"));
         }
     };
     if let Ok(toml_cfg) = config::load_toml_config(&project_root) {
-        config::apply_toml_to_code_graph(&mut cfg, &toml_cfg);
+        sync::limit_threads(toml_cfg.sync.max_threads);
     }
 
-    let bar = file_spinner(args.quiet);
-    let bar_cb = bar.clone();
-    let index_cb = move |p: extraction::IndexProgress| {
-        let phase = match p.phase {
-            extraction::IndexPhase::Scanning => "Scanning",
-            extraction::IndexPhase::Parsing => "Parsing",
-            extraction::IndexPhase::Storing => "Storing",
-            extraction::IndexPhase::Resolving => "Resolving",
-        };
-        let msg = p
-            .current_file
-            .map_or_else(|| phase.to_owned(), |f| format!("{phase}: {f}"));
-        bar_cb.set_message(msg);
-    };
+    let json = json_output(false);
+    let quiet = args.quiet || json;
+    let bar = IndexBar::new(quiet || args.no_progress);
+    let index_cb = |p: extraction::IndexProgress| bar.update(p);
 
     let result = extraction::index_all(
         &project_root,
         &cfg,
         args.force,
-        if args.quiet { None } else { Some(&index_cb) },
+        if quiet || args.no_progress {
+            None
+        } else {
+            Some(&index_cb)
+        },
     )
     .unwrap_or_else(|err| {
-        eprintln!("Indexing failed: {err}");
-        std::process::exit(1);
+        fail(ErrorClass::Failure, format!("Indexing failed: {err}"));
     });
 
-    bar.finish_and_clear();
-    if !args.quiet {
-        println!("Indexed {} files", result.files_indexed);
-        println!("Created {} nodes", result.nodes_created);
-        println!("Completed in {}ms", result.duration_ms);
+    bar.finish();
+    if json {
+        print_json(&result);
+    } else if !quiet {
+        let out = style();
+        println!("Indexed {} files", out.count(result.files_indexed));
+        println!("Created {} nodes", out.count(result.nodes_created));
+        println!("Completed in {}ms", out.count(result.duration_ms));
+    }
+    if !result.success {
+        let messages: Vec<&str> = result
+            .errors
+            .iter()
+            .filter(|e| e.severity == ExtractionErrorSeverity::Error)
+            .map(|e| e.message.as_str())
+            .collect();
+        fail(
+            ErrorClass::Partial,
+            format!(
+                "Indexing finished with {} errors:\n  {}",
+                messages.len(),
+                messages.join("\n  ")
+            ),
+        );
     }
 }
 
@@ -1263,70 +2457,223 @@ fn run_sync(args: SyncArgs) {
     let project_root = resolve_project_root(args.path);
 
     if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    // A throttled sync lowers its own priority, and the daemon only runs
+    // full syncs, so both always run here.
+    if !args.throttle
+        && args.since.is_none()
+        && let Some(answer) = daemon::delegate(&project_root, &daemon::Request::Sync)
+    {
+        let result: extraction::SyncResult = answer.unwrap_or_else(|err| {
+            fail(ErrorClass::Failure, format!("Sync failed: {}", err.message));
+        });
+        finish_sync(&result, args.quiet);
+        return;
     }
 
-    let mut cfg = match config::load_config(&project_root) {
+    let cfg = match config::load_config(&project_root) {
         Ok(cfg) => cfg,
         Err(err) => {
-            eprintln!("Failed to load config: {err}");
-            std::process::exit(1);
+            fail(ErrorClass::Config, format!("Failed to load config: {err}"));
         }
     };
     if let Ok(toml_cfg) = config::load_toml_config(&project_root) {
-        config::apply_toml_to_code_graph(&mut cfg, &toml_cfg);
+        let sync_cfg = &toml_cfg.sync;
+        if args.throttle {
+            sync::limit_threads(sync_cfg.throttle_threads.max(1));
+            sync::lower_priority(sync_cfg.throttle_nice, sync_cfg.throttle_idle_io);
+        } else {
+            sync::limit_threads(sync_cfg.max_threads);
+        }
     }
 
-    let bar = file_spinner(args.quiet);
-    let bar_cb = bar.clone();
-    let sync_cb = move |p: extraction::IndexProgress| {
-        let phase = match p.phase {
-            extraction::IndexPhase::Scanning => "Scanning",
-            extraction::IndexPhase::Parsing => "Parsing",
-            extraction::IndexPhase::Storing => "Storing",
-            extraction::IndexPhase::Resolving => "Resolving",
-        };
-        let msg = p
-            .current_file
-            .map_or_else(|| phase.to_owned(), |f| format!("{phase}: {f}"));
-        bar_cb.set_message(msg);
+    let quiet = args.quiet || json_output(false);
+    let bar = IndexBar::new(quiet || args.no_progress);
+    let sync_cb = |p: extraction::IndexProgress| bar.update(p);
+
+    let on_progress: Option<&dyn Fn(extraction::IndexProgress)> = if quiet || args.no_progress {
+        None
+    } else {
+        Some(&sync_cb)
     };
+    let result = args
+        .since
+        .as_ref()
+        .map_or_else(
+            || extraction::sync(&project_root, &cfg, on_progress),
+            |since| extraction::sync_since(&project_root, &cfg, since, on_progress),
+        )
+        .unwrap_or_else(|err| {
+            fail(ErrorClass::Failure, format!("Sync failed: {err}"));
+        });
 
-    let result = extraction::sync(
-        &project_root,
-        &cfg,
-        if args.quiet { None } else { Some(&sync_cb) },
-    )
-    .unwrap_or_else(|err| {
-        eprintln!("Sync failed: {err}");
-        std::process::exit(1);
-    });
+    bar.finish();
+    finish_sync(&result, args.quiet);
+}
 
-    bar.finish_and_clear();
+fn finish_sync(result: &extraction::SyncResult, quiet: bool) {
+    if json_output(false) {
+        print_json(result);
+    } else if !quiet {
+        print_sync_result(result);
+    }
+    if result.files_failed > 0 {
+        fail(
+            ErrorClass::Partial,
+            format!(
+                "{} files could not be synced; they are retried on the next sync",
+                result.files_failed
+            ),
+        );
+    }
+}
+
+fn print_sync_result(result: &extraction::SyncResult) {
+    let total_changes = result.files_added + result.files_modified + result.files_removed;
+    if total_changes == 0 {
+        println!("Already up to date");
+        return;
+    }
+    println!("Synced {} files", style().count(total_changes));
+    if result.files_added > 0 {
+        println!("  Added: {}", style().count(result.files_added));
+    }
+    if result.files_modified > 0 {
+        println!("  Modified: {}", style().count(result.files_modified));
+    }
+    if result.files_removed > 0 {
+        println!("  Removed: {}", style().count(result.files_removed));
+    }
+    println!("Updated {} nodes", style().count(result.nodes_updated));
+    if result.nodes_embedded > 0 {
+        println!("Embedded {} nodes", style().count(result.nodes_embedded));
+    }
+}
+
+fn run_watch(args: &WatchArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let cfg = watch::sync_config(&project_root)
+        .unwrap_or_else(|err| fail(ErrorClass::Config, format!("Failed to load config: {err}")));
+    let sync_cfg = config::load_toml_config(&project_root)
+        .map(|c| c.sync)
+        .unwrap_or_default();
+    sync::limit_threads(sync_cfg.max_threads);
+    let debounce = Duration::from_millis(args.debounce_ms.unwrap_or(sync_cfg.debounce_ms));
+
+    // Catch up on changes made while nothing was watching.
+    let result = extraction::sync(&project_root, &cfg, None).unwrap_or_else(|err| {
+        fail(ErrorClass::Failure, format!("Sync failed: {err}"));
+    });
     if !args.quiet {
-        let total_changes = result.files_added + result.files_modified + result.files_removed;
-        if total_changes == 0 {
-            println!("Already up to date");
-        } else {
-            println!("Synced {total_changes} files");
-            if result.files_added > 0 {
-                println!("  Added: {}", result.files_added);
-            }
-            if result.files_modified > 0 {
-                println!("  Modified: {}", result.files_modified);
-            }
-            if result.files_removed > 0 {
-                println!("  Removed: {}", result.files_removed);
+        print_sync_result(&result);
+        println!(
+            "Watching {} for changes (Ctrl-C to stop)",
+            project_root.display()
+        );
+    }
+
+    let quiet = args.quiet;
+    let shutdown = AtomicBool::new(false);
+    watch::watch(&project_root, debounce, &shutdown, |result| match result {
+        Ok(result) if result.files_failed > 0 => eprintln!(
+            "{} {} files could not be synced; they are retried on the next change",
+            style().mark(Mark::Warning),
+            result.files_failed
+        ),
+        Ok(result) => {
+            if !quiet {
+                print_sync_result(result);
             }
-            println!("Updated {} nodes", result.nodes_updated);
         }
+        Err(err) => eprintln!("Sync failed: {err}"),
+    })
+    .unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Failure,
+            format!("Cannot watch the project: {err}"),
+        );
+    });
+}
+
+fn run_daemon_start(path: Option<PathBuf>) {
+    let project_root = resolve_project_root(path);
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let sync_cfg = config::load_toml_config(&project_root)
+        .map(|c| c.sync)
+        .unwrap_or_default();
+    sync::limit_threads(sync_cfg.max_threads);
+
+    println!(
+        "Daemon serving {} on {} (Ctrl-C or `coraline daemon stop` to stop)",
+        project_root.display(),
+        daemon::socket_path(&project_root).display()
+    );
+    daemon::serve(&project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Failure,
+            format!("Cannot start the daemon: {err}"),
+        );
+    });
+}
+
+fn run_daemon_stop(path: Option<PathBuf>) {
+    let project_root = resolve_project_root(path);
+    if daemon::stop(&project_root) {
+        println!("Daemon stopped.");
+    } else {
+        println!("No daemon is running.");
+    }
+}
+
+fn run_daemon_status(path: Option<PathBuf>) {
+    let project_root = resolve_project_root(path);
+    let Some(info) = daemon::status(&project_root) else {
+        println!("No daemon is running.");
+        return;
+    };
+    println!(
+        "Daemon running (pid {}, v{}), started {}.",
+        info.pid,
+        info.version,
+        days_ago(info.started_at)
+    );
+    if info.version != env!("CARGO_PKG_VERSION") {
+        println!(
+            "{} This is v{}, so commands run in-process until the daemon is restarted.",
+            style().mark(Mark::Warning),
+            env!("CARGO_PKG_VERSION")
+        );
     }
 }
 
 fn run_status(args: StatusArgs) {
     let project_root = resolve_project_root(args.path);
 
+    if json_output(false) {
+        print_json(&status_json(&project_root));
+        return;
+    }
+
     if !is_initialized(&project_root) {
         println!("Coraline Status\n");
         println!("Project: {}", project_root.display());
@@ -1341,203 +2688,2187 @@ fn run_status(args: StatusArgs) {
     println!("Coraline Status\n");
     println!("Project: {}", project_root.display());
     println!("Config:  {}", cfg_path.display());
-    println!("Database: {} ({} bytes)", db_path.display(), db_size);
+    println!(
+        "Database: {} ({} bytes)",
+        db_path.display(),
+        style().count(db_size)
+    );
 
     let hooks = GitHooksManager::new(&project_root);
     if hooks.is_git_repository() {
-        if hooks.is_hook_installed() {
-            println!("Git hooks: installed");
-        } else {
+        let installed = hooks.installed_hooks();
+        if installed.is_empty() {
             println!("Git hooks: not installed");
+        } else {
+            println!("Git hooks: installed ({})", hook_names(&installed));
         }
     } else {
         println!("Git hooks: not a git repository");
     }
 }
 
-fn run_query(args: QueryArgs) {
+fn status_json(project_root: &Path) -> serde_json::Value {
+    let hooks = GitHooksManager::new(project_root);
+    let installed: Vec<&str> = hooks
+        .installed_hooks()
+        .iter()
+        .map(|hook| hook.name())
+        .collect();
+    let initialized = is_initialized(project_root);
+    let db_path = db::database_path(project_root);
+    serde_json::json!({
+        "project": project_root,
+        "initialized": initialized,
+        "config": initialized.then(|| config::config_path(project_root)),
+        "database": initialized.then_some(&db_path),
+        "database_bytes": std::fs::metadata(&db_path).map_or(0, |m| m.len()),
+        "git_repository": hooks.is_git_repository(),
+        "hooks": installed,
+    })
+}
+
+/// Query every remote, warning about (and skipping) the unreachable ones.
+fn remote_hits(
+    remotes: &[remotes::Remote],
+    search: &str,
+    options: &SearchOptions,
+    tags: &[String],
+) -> Vec<(String, Vec<daemon::QueryHit>)> {
+    remotes
+        .iter()
+        .filter_map(
+            |remote| match remotes::query(remote, search, options, tags) {
+                Ok(hits) => Some((remote.name.clone(), hits)),
+                Err(err) => {
+                    eprintln!(
+                        "{} Skipping remote {}: {err}",
+                        style().mark(Mark::Warning),
+                        remote.name
+                    );
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// The search options of `query`'s filters.
+fn query_options(args: &QueryArgs) -> SearchOptions {
+    SearchOptions {
+        kinds: args
+            .kind
+            .as_deref()
+            .and_then(parse_node_kind)
+            .map(|kind| vec![kind]),
+        languages: (!args.languages.is_empty()).then(|| {
+            args.languages
+                .iter()
+                .flat_map(|name| {
+                    parse_languages(name).unwrap_or_else(|| {
+                        fail(ErrorClass::Usage, format!("Unknown language: {name}"))
+                    })
+                })
+                .collect()
+        }),
+        include_patterns: (!args.files.is_empty()).then(|| args.files.clone()),
+        limit: Some(args.limit),
+        offset: Some(args.offset),
+        exported_only: Some(args.exported_only),
+        ..SearchOptions::default()
+    }
+}
+
+fn run_query(args: &QueryArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+    ensure_fresh_index(&project_root);
+
+    let options = query_options(args);
+    let mut tags = args.tags.clone();
+    tags.extend(args.package.iter().map(|name| format!("package:{name}")));
+    let remotes = if args.local_only {
+        Vec::new()
+    } else {
+        remotes::list(&project_root).unwrap_or_else(|err| {
+            eprintln!("{} Skipping remotes: {err}", style().mark(Mark::Warning));
+            Vec::new()
+        })
+    };
+    // With remotes, every source returns its first `offset + limit` hits
+    // and the merged list is paged.
+    let options = if remotes.is_empty() {
+        options
+    } else {
+        SearchOptions {
+            limit: Some(args.limit.saturating_add(args.offset)),
+            offset: None,
+            ..options
+        }
+    };
+    let request = daemon::Request::Query {
+        search: args.search.clone(),
+        options: options.clone(),
+        tags: tags.clone(),
+    };
+    let hits = daemon::delegate::<Vec<daemon::QueryHit>>(&project_root, &request).map_or_else(
+        || {
+            daemon::query(&project_root, &args.search, &options, &tags).unwrap_or_else(|err| {
+                fail(ErrorClass::Database, format!("Search failed: {err}"));
+            })
+        },
+        |answer| {
+            answer.unwrap_or_else(|err| {
+                fail(
+                    ErrorClass::Database,
+                    format!("Search failed: {}", err.message),
+                );
+            })
+        },
+    );
+    let remote_hits = remote_hits(&remotes, &args.search, &options, &tags);
+    let offset = if remotes.is_empty() { 0 } else { args.offset };
+    let hits = remotes::merge(hits, remote_hits, offset, args.limit);
+
+    if json_output(args.json) {
+        let json = serde_json::to_string_pretty(&hits).unwrap_or_default();
+        println!("{json}");
+        return;
+    }
+
+    if hits.is_empty() {
+        println!("No results found for \"{}\"", args.search);
+        return;
+    }
+
+    println!("Search Results for \"{}\":\n", args.search);
+    for FederatedHit {
+        remote,
+        result,
+        tags,
+    } in hits
+    {
+        let node = result.node;
+        println!(
+            "{:?} {} ({:.0}%)",
+            node.kind,
+            node.name,
+            result.score * 100.0
+        );
+        match remote {
+            Some(remote) => println!("  {remote}:{}:{}", node.file_path, node.start_line),
+            None => println!("  {}:{}", node.file_path, node.start_line),
+        }
+        if !tags.is_empty() {
+            println!("  tags: {}", tags.join(", "));
+        }
+        if let Some(signature) = node.signature {
+            println!("  {signature}");
+        }
+        println!();
+    }
+}
+
+fn run_context(args: ContextArgs) {
+    let project_root = resolve_project_root(args.path);
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+    ensure_fresh_index(&project_root);
+
+    let Some(format) = ContextFormat::parse(&args.format) else {
+        fail(
+            ErrorClass::Usage,
+            format!(
+                "Unknown context format '{}'; expected markdown, json, xml, or repo-map",
+                args.format
+            ),
+        );
+    };
+    let format = if json_output(false) {
+        ContextFormat::Json
+    } else {
+        format
+    };
+
+    if let Some(base) = args.diff {
+        if !matches!(format, ContextFormat::Markdown | ContextFormat::Json) {
+            fail(
+                ErrorClass::Usage,
+                "--diff supports the markdown and json formats",
+            );
+        }
+        let options = DiffContextOptions {
+            base: Some(base),
+            max_code_blocks: Some(args.max_code),
+            max_code_block_size: None,
+            include_code: Some(!args.no_code),
+        };
+        let context =
+            diff_context::build_diff_context(&project_root, &options).unwrap_or_else(|err| {
+                fail(
+                    ErrorClass::Failure,
+                    format!("Failed to build diff context: {err}"),
+                );
+            });
+        println!("{}", diff_context::render_diff_context(&context, format));
+        return;
+    }
+    let task = args.task.unwrap_or_default();
+
+    let options = BuildContextOptions {
+        max_nodes: Some(args.max_nodes),
+        max_code_blocks: Some(args.max_code),
+        max_code_block_size: None,
+        include_code: Some(!args.no_code),
+        format: Some(format),
+        search_limit: None,
+        traversal_depth: None,
+        min_score: None,
+    };
+
+    let request = daemon::Request::Context {
+        task: task.clone(),
+        queries: args.queries.clone(),
+        options: options.clone(),
+    };
+    let output = if let Some(answer) = daemon::delegate::<String>(&project_root, &request) {
+        answer.unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to build context: {}", err.message),
+            );
+        })
+    } else {
+        let task = TaskInput::with_queries(task, args.queries);
+        context::build_task_context(&project_root, &task, &options).unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to build context: {err}"),
+            );
+        })
+    };
+
+    println!("{output}");
+}
+
+fn run_hooks_install(path: Option<PathBuf>, all: bool) {
+    let project_root = resolve_project_root(path);
+    let hooks = GitHooksManager::new(&project_root);
+    let json = json_output(false);
+    let mut installed = Vec::new();
+    for &hook in selected_hooks(all) {
+        let result = hooks.install(hook);
+        if !result.success {
+            fail(ErrorClass::Failure, &result.message);
+        }
+        if json {
+            installed.push(serde_json::json!({
+                "hook": hook.name(),
+                "backup": result.backup_path,
+            }));
+            continue;
+        }
+        println!("{}", result.message);
+        if let Some(backup) = result.backup_path {
+            println!("Previous hook backed up at {}", backup.display());
+        }
+    }
+    if json {
+        print_json(&serde_json::json!({
+            "installed": installed,
+            "hooks_dir": hooks.hooks_dir(),
+        }));
+    } else if !hooks.is_default_hooks_dir() {
+        println!("Hooks directory: {}", hooks.hooks_dir().display());
+    }
+    if hooks.manager() == Some(HookManager::Lefthook) {
+        let all = if all { " --all" } else { "" };
+        eprintln!(
+            "{} lefthook manages this repository's hooks and may replace these on `lefthook install`; add `coraline hooks snippet lefthook{all}` to lefthook.yml instead.",
+            style().mark(Mark::Warning)
+        );
+    }
+}
+
+const fn selected_hooks(all: bool) -> &'static [GitHook] {
+    if all {
+        &GitHook::ALL
+    } else {
+        &[GitHook::PostCommit]
+    }
+}
+
+fn run_hooks_snippet(manager: &str, all: bool) {
+    let Some(manager) = HookManager::parse(manager) else {
+        fail(
+            ErrorClass::Usage,
+            format!("Unknown hook manager '{manager}'; expected lefthook or husky"),
+        );
+    };
+    let snippet = sync::hook_snippet(manager, selected_hooks(all));
+    if json_output(false) {
+        print_json(&serde_json::json!({ "snippet": snippet }));
+    } else {
+        println!("{snippet}");
+    }
+}
+
+fn run_hooks_remove(path: Option<PathBuf>) {
+    let project_root = resolve_project_root(path);
+    let hooks = GitHooksManager::new(&project_root);
+    let installed = hooks.installed_hooks();
+    let json = json_output(false);
+    if installed.is_empty() && !json {
+        println!("No Coraline hooks found.");
+        return;
+    }
+    for &hook in &installed {
+        let result = hooks.remove(hook);
+        if !result.success {
+            fail(ErrorClass::Failure, &result.message);
+        }
+        if !json {
+            println!("{}", result.message);
+        }
+    }
+    if json {
+        let removed: Vec<&str> = installed.iter().map(|hook| hook.name()).collect();
+        print_json(&serde_json::json!({ "removed": removed }));
+    }
+}
+
+fn run_hooks_status(path: Option<PathBuf>) {
+    let project_root = resolve_project_root(path);
+    let hooks = GitHooksManager::new(&project_root);
+    if json_output(false) {
+        let states: Vec<_> = GitHook::ALL
+            .iter()
+            .map(|&hook| serde_json::json!({ "hook": hook.name(), "installed": hooks.is_installed(hook) }))
+            .collect();
+        print_json(&serde_json::json!({
+            "git_repository": hooks.is_git_repository(),
+            "hooks_dir": hooks.hooks_dir(),
+            "hooks": states,
+        }));
+        return;
+    }
+    if !hooks.is_git_repository() {
+        println!("Not a git repository.");
+        return;
+    }
+    for hook in GitHook::ALL {
+        let state = if hooks.is_installed(hook) {
+            "installed"
+        } else {
+            "not installed"
+        };
+        println!("{}: {state}", hook.name());
+    }
+    if !hooks.is_default_hooks_dir() {
+        println!("Hooks directory: {}", hooks.hooks_dir().display());
+    }
+}
+
+/// `post-commit` or `post-commit, post-merge`.
+fn hook_names(hooks: &[GitHook]) -> String {
+    hooks
+        .iter()
+        .map(|hook| hook.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn run_stats(args: StatsArgs) {
     let project_root = resolve_project_root(args.path);
 
     if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
     }
 
     let conn = db::open_database(&project_root).unwrap_or_else(|err| {
-        eprintln!("Failed to open database: {err}");
-        std::process::exit(1);
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
     });
 
-    let kind = args.kind.as_deref().and_then(parse_node_kind);
-    let results = db::search_nodes(&conn, &args.search, kind, args.limit).unwrap_or_else(|err| {
-        eprintln!("Search failed: {err}");
-        std::process::exit(1);
+    let stats = db::get_db_stats(&conn).unwrap_or_else(|err| {
+        fail(ErrorClass::Database, format!("Failed to get stats: {err}"));
     });
 
-    if args.json {
-        let json = serde_json::to_string_pretty(&results).unwrap_or_default();
+    if json_output(args.json) {
+        let json = serde_json::to_string_pretty(&stats).unwrap_or_default();
         println!("{json}");
         return;
     }
 
-    if results.is_empty() {
-        println!("No results found for \"{}\"", args.search);
+    println!("Coraline Statistics\n");
+    let out = style();
+    println!("Files:     {}", out.count(stats.file_count));
+    println!("\nNodes:     {}", out.count(stats.node_count));
+    println!("Edges:     {}", out.count(stats.edge_count));
+    println!("Unresolved refs: {}", out.count(stats.unresolved_count));
+}
+
+/// The lowercased export format, after checking `--output`/`--since` suit it.
+fn export_format(args: &ExportArgs) -> String {
+    let format = args.format.to_ascii_lowercase();
+    match format.as_str() {
+        "json" | "cypher" => {}
+        "parquet" => {
+            if !cfg!(feature = "parquet") {
+                fail(
+                    ErrorClass::Usage,
+                    "Parquet export requires a build with the `parquet` feature.",
+                );
+            }
+            if args.output.is_none() {
+                fail(
+                    ErrorClass::Usage,
+                    "Parquet export needs an output directory: --output <DIR>",
+                );
+            }
+        }
+        "scip" => {
+            if args.output.is_none() {
+                fail(
+                    ErrorClass::Usage,
+                    "SCIP export needs an output file: --output <FILE>",
+                );
+            }
+            if args.since.is_some() {
+                fail(
+                    ErrorClass::Usage,
+                    "SCIP export is a full index; --since does not apply",
+                );
+            }
+        }
+        other => {
+            fail(
+                ErrorClass::Usage,
+                format!("Unknown export format: {other} (expected json, cypher, parquet, or scip)"),
+            );
+        }
+    }
+    format
+}
+
+/// The checkpoint `--since` names; a raw timestamp stands for a checkpoint
+/// without a recorded file list.
+fn export_baseline(project_root: &Path, raw: &str) -> export::Checkpoint {
+    match export::get_checkpoint(project_root, raw) {
+        Ok(Some(checkpoint)) => checkpoint,
+        Ok(None) => raw.parse::<i64>().map_or_else(
+            |_| fail(ErrorClass::Usage, format!("Unknown checkpoint: {raw}")),
+            |at| export::Checkpoint {
+                name: raw.to_string(),
+                created_at: at,
+                files: Vec::new(),
+            },
+        ),
+        Err(err) => {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to load checkpoints: {err}"),
+            );
+        }
+    }
+}
+
+fn run_export(args: &ExportArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    let format = export_format(args);
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let conn = db::open_database(&project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+
+    if format == "scip" {
+        export_scip(&project_root, &conn, args);
+        return;
+    }
+
+    let baseline = args
+        .since
+        .as_deref()
+        .map(|raw| export_baseline(&project_root, raw));
+    let since = baseline.as_ref().map_or(0, |c| c.created_at);
+    let baseline_files = baseline
+        .as_ref()
+        .filter(|c| !c.files.is_empty())
+        .map(|c| c.files.as_slice());
+
+    let sensitive = SensitiveFiles::for_project(&project_root);
+    let delta =
+        export::export_delta(&conn, since, baseline_files, &sensitive).unwrap_or_else(|err| {
+            fail(ErrorClass::Failure, format!("Export failed: {err}"));
+        });
+
+    if format == "parquet" {
+        #[cfg(feature = "parquet")]
+        if let Some(output) = &args.output {
+            if let Err(err) = export::write_parquet(&delta, output) {
+                fail(
+                    ErrorClass::Failure,
+                    format!("Failed to write Parquet to {}: {err}", output.display()),
+                );
+            }
+            println!(
+                "Wrote {} nodes, {} edges, {} files to {}",
+                delta.nodes.len(),
+                delta.edges.len(),
+                delta.files.len(),
+                output.display()
+            );
+        }
+    } else {
+        let cypher = format == "cypher";
+        let rendered = if cypher {
+            export::to_cypher(&delta)
+        } else {
+            serde_json::to_string_pretty(&delta).unwrap_or_default()
+        };
+        if let Some(output) = &args.output {
+            if let Err(err) = std::fs::write(output, &rendered) {
+                fail(
+                    ErrorClass::Failure,
+                    format!("Failed to write {}: {err}", output.display()),
+                );
+            }
+        } else if cypher {
+            print!("{rendered}");
+        } else {
+            println!("{rendered}");
+        }
+    }
+
+    if let Some(name) = &args.checkpoint {
+        if let Err(err) = export::record_checkpoint(&project_root, &conn, name, delta.until) {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to record checkpoint: {err}"),
+            );
+        }
+        if args.output.is_some() {
+            println!("Checkpoint '{name}' recorded.");
+        }
+    }
+}
+
+fn export_scip(project_root: &Path, conn: &rusqlite::Connection, args: &ExportArgs) {
+    let Some(output) = &args.output else {
+        return;
+    };
+    let sensitive = SensitiveFiles::for_project(project_root);
+    let index =
+        scip::write_scip_index(project_root, conn, &sensitive, output).unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to write SCIP index to {}: {err}", output.display()),
+            );
+        });
+    let symbols: usize = index.documents.iter().map(|d| d.symbols.len()).sum();
+    println!(
+        "Wrote {} documents, {symbols} symbols to {}",
+        index.documents.len(),
+        output.display()
+    );
+
+    if let Some(name) = &args.checkpoint {
+        if let Err(err) = export::record_checkpoint(project_root, conn, name, export::now_millis())
+        {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to record checkpoint: {err}"),
+            );
+        }
+        println!("Checkpoint '{name}' recorded.");
+    }
+}
+
+fn run_serve_rest(path: Option<PathBuf>, addr: &str) {
+    let project_root = read_api_root(path, addr);
+    eprintln!("REST API listening on http://{addr}/ (OpenAPI at /openapi.json)");
+    if let Err(err) = rest::serve(&project_root, addr) {
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to start REST API: {err}"),
+        );
+    }
+}
+
+fn run_serve_graphql(path: Option<PathBuf>, addr: &str) {
+    let project_root = read_api_root(path, addr);
+    eprintln!("GraphQL endpoint listening on http://{addr}/graphql (schema at /graphql/schema)");
+    if let Err(err) = graphql::serve(&project_root, addr) {
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to start GraphQL endpoint: {err}"),
+        );
+    }
+}
+
+/// The initialized project a read-only API serves, after warning if `addr`
+/// is reachable from other machines.
+fn read_api_root(path: Option<PathBuf>, addr: &str) -> PathBuf {
+    let project_root = resolve_project_root(path);
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let loopback = addr
+        .parse::<std::net::SocketAddr>()
+        .is_ok_and(|a| a.ip().is_loopback())
+        || addr.starts_with("localhost:");
+    if !loopback {
+        eprintln!(
+            "Warning: {addr} is not a loopback address; anyone who can reach it can query the graph."
+        );
+    }
+    project_root
+}
+
+fn run_viz(args: &VizArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let addr = format!("{}:{}", args.host, args.port);
+    println!("Graph explorer running at http://{addr}/ (Ctrl+C to stop)");
+    if let Err(err) = coraline::viz::serve(&project_root, &addr) {
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to serve graph explorer on {addr}: {err}"),
+        );
+    }
+}
+
+fn run_tui(args: &TuiArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+    ensure_fresh_index(&project_root);
+
+    if let Err(err) = coraline::tui::run(&project_root) {
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to run terminal explorer: {err}"),
+        );
+    }
+}
+
+fn run_map(args: &MapArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    let Some(metric) = map::MapMetric::parse(&args.by) else {
+        fail(
+            ErrorClass::Usage,
+            format!(
+                "Unknown map metric: {} (expected nodes, bytes, or lines)",
+                args.by
+            ),
+        );
+    };
+    let format = args.format.to_ascii_lowercase();
+    if !matches!(format.as_str(), "text" | "html" | "json") {
+        fail(
+            ErrorClass::Usage,
+            format!("Unknown map format: {format} (expected text, html, or json)"),
+        );
+    }
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let conn = db::open_database(&project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let tree = map::build_map(&conn, args.depth, metric).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to build code map: {err}"),
+        );
+    });
+
+    let rendered = match format.as_str() {
+        "html" => map::render_html(&tree, metric),
+        "json" => serde_json::to_string_pretty(&tree).unwrap_or_default(),
+        _ => map::render_text(&tree, metric, &style()),
+    };
+    if let Some(output) = &args.output {
+        if let Err(err) = std::fs::write(output, &rendered) {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to write {}: {err}", output.display()),
+            );
+        }
+        println!("Wrote code map to {}", output.display());
+    } else {
+        print!("{rendered}");
+        if format == "json" {
+            println!();
+        }
+    }
+}
+
+fn run_tour(args: &TourArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let conn = db::open_database(&project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let options = tour::TourOptions { limit: args.limit };
+    let walkthrough = tour::build_tour(&conn, &options).unwrap_or_else(|err| {
+        fail(ErrorClass::Failure, format!("Failed to build tour: {err}"));
+    });
+
+    let rendered = if json_output(args.json) {
+        serde_json::to_string_pretty(&walkthrough).unwrap_or_default() + "\n"
+    } else {
+        let name = project_root
+            .canonicalize()
+            .unwrap_or_else(|_| project_root.clone())
+            .file_name()
+            .map_or_else(
+                || "project".to_string(),
+                |n| n.to_string_lossy().into_owned(),
+            );
+        tour::render_markdown(&walkthrough, &name)
+    };
+    if let Some(output) = &args.output {
+        if let Err(err) = std::fs::write(output, &rendered) {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to write {}: {err}", output.display()),
+            );
+        }
+        println!("Wrote tour to {}", output.display());
+    } else {
+        print!("{rendered}");
+    }
+}
+
+fn run_context_log(args: &ContextLogArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let entries = context::read_context_log(&project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to read context log: {err}"),
+        );
+    });
+
+    let Some(number) = args.replay else {
+        let skip = entries.len().saturating_sub(args.limit);
+        if json_output(args.json) {
+            let shown = entries.get(skip..).unwrap_or_default();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(shown).unwrap_or_default()
+            );
+            return;
+        }
+        if entries.is_empty() {
+            println!(
+                "No contexts logged. Set [context] log_contexts = true in .coraline/config.toml."
+            );
+            return;
+        }
+        let style = style();
+        for (i, entry) in entries.iter().enumerate().skip(skip) {
+            let mut notes = Vec::new();
+            if entry.truncated_blocks > 0 {
+                notes.push(format!("{} truncated", entry.truncated_blocks));
+            }
+            if entry.omitted_blocks > 0 {
+                notes.push(format!("{} omitted", entry.omitted_blocks));
+            }
+            let notes = if notes.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", notes.join(", "))
+            };
+            println!(
+                "#{:<4} ~{} tokens, {} entry points, {} code blocks{notes}  {}",
+                i + 1,
+                style.count(entry.tokens),
+                entry.entry_points.len(),
+                entry.code_blocks.len(),
+                entry.query
+            );
+        }
+        return;
+    };
+
+    let Some(entry) = number.checked_sub(1).and_then(|i| entries.get(i)) else {
+        fail(
+            ErrorClass::Usage,
+            format!("No context log entry #{number} ({} logged)", entries.len()),
+        );
+    };
+    let replay = context::replay_context(&project_root, entry).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to replay context: {err}"),
+        );
+    });
+
+    if json_output(args.json) {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&replay).unwrap_or_default()
+        );
+        return;
+    }
+    let style = style();
+    println!("Replayed #{number}: {}", entry.query);
+    println!(
+        "  Entry points: {} -> {} ({} kept, {} added, {} removed)",
+        entry.entry_points.len(),
+        replay.current.entry_points.len(),
+        replay.kept,
+        replay.added.len(),
+        replay.removed.len()
+    );
+    println!(
+        "  Tokens: ~{} -> ~{}",
+        style.count(entry.tokens),
+        style.count(replay.current.tokens)
+    );
+    for node in &replay.added {
+        println!("  + {} ({})", node.qualified_name, node.file_path);
+    }
+    for node in &replay.removed {
+        println!("  - {} ({})", node.qualified_name, node.file_path);
+    }
+}
+
+fn run_db(args: DbArgs) {
+    let project_root = resolve_project_root(args.path);
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let conn = db::open_database(&project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+
+    match args.action {
+        DbAction::Views { drop: true } => {
+            if let Err(err) = db::drop_analytical_views(&conn) {
+                fail(ErrorClass::Database, format!("Failed to drop views: {err}"));
+            }
+            println!("Dropped views: {}", db::ANALYTICAL_VIEWS.join(", "));
+        }
+        DbAction::Views { drop: false } => {
+            if let Err(err) = db::create_analytical_views(&conn) {
+                fail(
+                    ErrorClass::Database,
+                    format!("Failed to create views: {err}"),
+                );
+            }
+            let db_path = db::database_path(&project_root);
+            let db_path = db_path.canonicalize().unwrap_or(db_path);
+            println!("Created views: {}", db::ANALYTICAL_VIEWS.join(", "));
+            println!();
+            println!("Query them from DuckDB with:");
+            println!("  INSTALL sqlite; LOAD sqlite;");
+            println!(
+                "  ATTACH '{}' AS coraline (TYPE sqlite, READ_ONLY);",
+                db_path.display()
+            );
+            println!("  SELECT * FROM coraline.symbol_metrics ORDER BY fan_in DESC LIMIT 20;");
+        }
+    }
+}
+
+fn run_annotate(args: AnnotateArgs) {
+    let project_root = resolve_project_root(args.path);
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let mut conn = db::open_database(&project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let db_fail = |err: CoralineError| -> ! {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to update annotations: {err}"),
+        )
+    };
+
+    match args.action {
+        AnnotateAction::Add {
+            target,
+            tags,
+            symbol,
+        } => {
+            if let Some(bad) = tags.iter().find(|t| !annotations::is_valid_tag(t)) {
+                fail(
+                    ErrorClass::Usage,
+                    format!("Invalid tag '{bad}': tags cannot be empty or contain whitespace"),
+                );
+            }
+            let rows: Vec<Annotation> = tags
+                .iter()
+                .map(|tag| Annotation {
+                    target: target.clone(),
+                    symbol,
+                    tag: tag.clone(),
+                    source: AnnotationSource::User,
+                    position: 0,
+                })
+                .collect();
+            db::add_annotations(&mut conn, &rows).unwrap_or_else(|err| db_fail(err));
+            println!("Tagged {target} with {}", tags.join(", "));
+        }
+        AnnotateAction::Remove {
+            target,
+            tags,
+            symbol,
+        } => {
+            let removed = db::remove_annotations(&conn, &target, symbol, &tags)
+                .unwrap_or_else(|err| db_fail(err));
+            println!("Removed {removed} tag(s) from {target}");
+        }
+        AnnotateAction::List { json } => {
+            let rows = db::list_annotations(&conn).unwrap_or_else(|err| db_fail(err));
+            let rows: Vec<Annotation> = rows.into_iter().filter(|a| !a.tag.is_empty()).collect();
+            if json_output(json) {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&rows).unwrap_or_default()
+                );
+                return;
+            }
+            if rows.is_empty() {
+                println!("No annotations.");
+                return;
+            }
+            for a in rows {
+                let kind = if a.symbol { "symbol" } else { "path" };
+                let source = match a.source {
+                    AnnotationSource::User => "",
+                    AnnotationSource::Codeowners => " (CODEOWNERS)",
+                    AnnotationSource::Submodule => " (submodule)",
+                    AnnotationSource::Package => " (package)",
+                };
+                println!("{kind} {}  {}{source}", a.target, a.tag);
+            }
+        }
+        AnnotateAction::ImportCodeowners => {
+            match annotations::import_codeowners(&project_root, &mut conn)
+                .unwrap_or_else(|err| db_fail(err.into()))
+            {
+                Some((path, rules)) => {
+                    println!("Imported {rules} rule(s) from {}", path.display());
+                }
+                None => println!("No CODEOWNERS file found."),
+            }
+        }
+    }
+}
+
+fn run_analyze(args: AnalyzeArgs) {
+    let project_root = resolve_project_root(args.path);
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    match args.action {
+        AnalyzeAction::DeadCode {
+            min_confidence,
+            limit,
+            json,
+        } => run_dead_code(&project_root, &min_confidence, limit, json_output(json)),
+        AnalyzeAction::Hotspots { limit, json } => {
+            run_hotspots(&project_root, limit, json_output(json));
+        }
+        AnalyzeAction::Duplicates {
+            min_tokens,
+            threshold,
+            limit,
+            json,
+        } => {
+            let options = DuplicateOptions {
+                min_tokens,
+                threshold,
+            };
+            run_duplicates(&project_root, options, limit, json_output(json));
+        }
+        AnalyzeAction::Arch { json } => run_arch(&project_root, json_output(json)),
+        AnalyzeAction::Implementations { name, file, json } => {
+            run_implementations(&project_root, &name, file.as_deref(), json_output(json));
+        }
+    }
+}
+
+fn run_dead_code(project_root: &Path, min_confidence: &str, limit: usize, json: bool) {
+    let min_confidence = Confidence::parse(min_confidence).unwrap_or_else(|| {
+        fail(
+            ErrorClass::Usage,
+            format!("Unknown confidence '{min_confidence}' (expected low, medium, or high)"),
+        );
+    });
+    let cfg = config::load_toml_config(project_root)
+        .unwrap_or_else(|err| fail(ErrorClass::Config, format!("Failed to load config: {err}")));
+    let conn = db::open_database(project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let report =
+        dead_code::find_dead_code(&conn, &cfg.analysis, min_confidence).unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Database,
+                format!("Dead code analysis failed: {err}"),
+            );
+        });
+
+    let total = report.candidates.len();
+    let shown = if limit == 0 { total } else { limit.min(total) };
+    let candidates = report.candidates.get(..shown).unwrap_or_default();
+
+    if json {
+        let listed: Vec<_> = candidates
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "id": c.node.id,
+                    "kind": c.node.kind,
+                    "name": c.node.name,
+                    "qualified_name": c.node.qualified_name,
+                    "file_path": c.node.file_path,
+                    "start_line": c.node.start_line,
+                    "confidence": c.confidence,
+                    "reason": c.reason,
+                })
+            })
+            .collect();
+        let out = serde_json::json!({
+            "candidates": listed,
+            "total": total,
+            "entry_points_skipped": report.entry_points_skipped,
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return;
+    }
+
+    if total == 0 {
+        println!("No dead code candidates found.");
+        return;
+    }
+    for c in candidates {
+        let confidence = match c.confidence {
+            Confidence::High => "high",
+            Confidence::Medium => "medium",
+            Confidence::Low => "low",
+        };
+        println!(
+            "{confidence:<6}  {:?} {}  {}:{}",
+            c.node.kind, c.node.name, c.node.file_path, c.node.start_line
+        );
+        println!("        {}", c.reason);
+    }
+    println!();
+    println!(
+        "{total} candidate(s){}; {} entry point(s) skipped.",
+        if shown < total {
+            format!(", {shown} shown")
+        } else {
+            String::new()
+        },
+        report.entry_points_skipped
+    );
+}
+
+fn run_duplicates(project_root: &Path, options: DuplicateOptions, limit: usize, json: bool) {
+    if !(0.0..=1.0).contains(&options.threshold) {
+        fail(
+            ErrorClass::Usage,
+            format!(
+                "--threshold must be between 0 and 1, got {}",
+                options.threshold
+            ),
+        );
+    }
+    let conn = db::open_database(project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let report = duplicates::find_duplicates(project_root, &conn, options).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Duplicate analysis failed: {err}"),
+        );
+    });
+
+    let total = report.clusters.len();
+    let shown = if limit == 0 { total } else { limit.min(total) };
+    let clusters = report.clusters.get(..shown).unwrap_or_default();
+
+    if json {
+        let listed: Vec<_> = clusters
+            .iter()
+            .map(|c| {
+                let members: Vec<_> = c
+                    .members
+                    .iter()
+                    .map(|n| {
+                        serde_json::json!({
+                            "id": n.id,
+                            "kind": n.kind,
+                            "name": n.name,
+                            "file_path": n.file_path,
+                            "start_line": n.start_line,
+                            "end_line": n.end_line,
+                            "language": n.language,
+                        })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "tokens": c.tokens,
+                    "similarity": c.similarity,
+                    "members": members,
+                })
+            })
+            .collect();
+        let out = serde_json::json!({
+            "clusters": listed,
+            "total": total,
+            "functions": report.functions,
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return;
+    }
+
+    if total == 0 {
+        println!(
+            "No duplicates among {} function(s) of {}+ tokens.",
+            report.functions, options.min_tokens
+        );
+        return;
+    }
+    for c in clusters {
+        println!(
+            "{} copies, {} tokens, {:.0}% similar",
+            c.members.len(),
+            c.tokens,
+            c.similarity * 100.0
+        );
+        for n in &c.members {
+            println!(
+                "    {}:{}-{}  {}",
+                n.file_path, n.start_line, n.end_line, n.name
+            );
+        }
+    }
+    println!();
+    println!(
+        "{total} cluster(s){} among {} function(s).",
+        if shown < total {
+            format!(", {shown} shown")
+        } else {
+            String::new()
+        },
+        report.functions
+    );
+}
+
+fn run_arch(project_root: &Path, json: bool) {
+    let cfg = config::load_toml_config(project_root)
+        .unwrap_or_else(|err| fail(ErrorClass::Config, format!("Failed to load config: {err}")));
+    let conn = db::open_database(project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let report = architecture::check(&conn, &cfg.architecture).unwrap_or_else(|err| {
+        let class = if err.kind() == std::io::ErrorKind::InvalidInput {
+            ErrorClass::Config
+        } else {
+            ErrorClass::Database
+        };
+        fail(class, format!("Architecture check failed: {err}"));
+    });
+
+    if json {
+        let listed: Vec<_> = report
+            .violations
+            .iter()
+            .map(|v| {
+                serde_json::json!({
+                    "rule": v.rule,
+                    "kind": v.kind,
+                    "line": v.line,
+                    "source": {
+                        "id": v.source.id,
+                        "kind": v.source.kind,
+                        "name": v.source.name,
+                        "file_path": v.source.file_path,
+                    },
+                    "target": {
+                        "id": v.target.id,
+                        "kind": v.target.kind,
+                        "name": v.target.name,
+                        "file_path": v.target.file_path,
+                    },
+                })
+            })
+            .collect();
+        let out = serde_json::json!({
+            "rules": report.rules,
+            "dependencies": report.dependencies,
+            "violations": listed,
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+    } else if report.rules == 0 {
+        println!("No [[architecture.rules]] in .coraline/config.toml; nothing to check.");
+        return;
+    } else {
+        let mut rule = None;
+        for v in &report.violations {
+            if rule != Some(&v.rule) {
+                println!("{}", v.rule);
+                rule = Some(&v.rule);
+            }
+            let at = v.line.map_or_else(
+                || v.source.file_path.clone(),
+                |line| format!("{}:{line}", v.source.file_path),
+            );
+            println!(
+                "    {at}  {} {:?} {} ({})",
+                v.source.name, v.kind, v.target.name, v.target.file_path
+            );
+        }
+    }
+
+    let count = report.violations.len();
+    if count > 0 {
+        fail(
+            ErrorClass::Failure,
+            format!(
+                "Found {count} architecture violation{}",
+                if count == 1 { "" } else { "s" }
+            ),
+        );
+    }
+    if !json {
+        println!(
+            "{} {} rule(s) hold across {} dependencies",
+            style().mark(Mark::Ok),
+            report.rules,
+            report.dependencies
+        );
+    }
+}
+
+/// The one trait, interface, or class `name` (in `file`, if given) names.
+fn implementation_target(conn: &rusqlite::Connection, name: &str, file: Option<&str>) -> Node {
+    let mut targets = implementations::find_targets(conn, name, file).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to find {name}: {err}"),
+        );
+    });
+    match targets.len() {
+        0 => fail(
+            ErrorClass::Usage,
+            format!("No trait, interface, or class named {name}"),
+        ),
+        1 => targets.remove(0),
+        _ => {
+            let files: Vec<&str> = targets.iter().map(|n| n.file_path.as_str()).collect();
+            fail(
+                ErrorClass::Usage,
+                format!(
+                    "{name} is defined in several files; pass --file: {}",
+                    files.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+fn run_implementations(project_root: &Path, name: &str, file: Option<&str>, json: bool) {
+    let conn = db::open_database(project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let target = implementation_target(&conn, name, file);
+    let report = implementations::find_implementations(project_root, &conn, &target)
+        .unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Database,
+                format!("Implementation analysis failed: {err}"),
+            );
+        });
+
+    if json {
+        let listed: Vec<_> = report
+            .implementations
+            .iter()
+            .map(|i| {
+                serde_json::json!({
+                    "id": i.node.id,
+                    "kind": i.node.kind,
+                    "name": i.node.name,
+                    "file_path": i.node.file_path,
+                    "start_line": i.node.start_line,
+                    "edge_kind": i.kind,
+                    "via": i.via,
+                    "missing": i.missing,
+                })
+            })
+            .collect();
+        let out = serde_json::json!({
+            "target": {
+                "id": report.target.id,
+                "kind": report.target.kind,
+                "name": report.target.name,
+                "file_path": report.target.file_path,
+                "start_line": report.target.start_line,
+            },
+            "required": report.required,
+            "implementations": listed,
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return;
+    }
+
+    println!(
+        "{:?} {}  {}:{}",
+        report.target.kind, report.target.name, report.target.file_path, report.target.start_line
+    );
+    if !report.required.is_empty() {
+        println!("required: {}", report.required.join(", "));
+    }
+    println!();
+    if report.implementations.is_empty() {
+        println!("No implementations found.");
+        return;
+    }
+    for i in &report.implementations {
+        let via = i
+            .via
+            .as_ref()
+            .map_or_else(String::new, |via| format!(" (via {via})"));
+        println!(
+            "{:?} {}{via}  {}:{}",
+            i.node.kind, i.node.name, i.node.file_path, i.node.start_line
+        );
+        if !i.missing.is_empty() {
+            println!("    missing: {}", i.missing.join(", "));
+        }
+    }
+    let incomplete = report
+        .implementations
+        .iter()
+        .filter(|i| !i.missing.is_empty())
+        .count();
+    println!();
+    println!(
+        "{} implementation(s); {incomplete} missing required methods.",
+        report.implementations.len()
+    );
+}
+
+fn run_hotspots(project_root: &Path, limit: usize, json: bool) {
+    let conn = db::open_database(project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let hotspots = history::hotspots(&conn).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Hotspot analysis failed: {err}"),
+        );
+    });
+
+    let total = hotspots.len();
+    let shown = if limit == 0 { total } else { limit.min(total) };
+    let hotspots = hotspots.get(..shown).unwrap_or_default();
+
+    if json {
+        let listed: Vec<_> = hotspots
+            .iter()
+            .map(|h| {
+                serde_json::json!({
+                    "id": h.node.id,
+                    "kind": h.node.kind,
+                    "name": h.node.name,
+                    "qualified_name": h.node.qualified_name,
+                    "file_path": h.node.file_path,
+                    "start_line": h.node.start_line,
+                    "commits": h.churn.commits,
+                    "authors": h.churn.authors,
+                    "last_modified": h.churn.last_modified,
+                    "fan_in": h.fan_in,
+                    "score": h.score,
+                })
+            })
+            .collect();
+        let out = serde_json::json!({ "hotspots": listed, "total": total });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return;
+    }
+
+    if total == 0 {
+        println!("No churn recorded. Run `coraline churn` first.");
+        return;
+    }
+    println!("{:>6}  {:>7}  {:>6}  symbol", "score", "commits", "fan-in");
+    for h in hotspots {
+        println!(
+            "{:>6}  {:>7}  {:>6}  {:?} {}  {}:{}  (last changed {})",
+            h.score,
+            h.churn.commits,
+            h.fan_in,
+            h.node.kind,
+            h.node.name,
+            h.node.file_path,
+            h.node.start_line,
+            days_ago(h.churn.last_modified)
+        );
+    }
+    if shown < total {
+        println!();
+        println!("{shown} of {total} symbol(s) with churn shown.");
+    }
+}
+
+/// `today`, `1 day ago`, or `N days ago` for a Unix time.
+fn days_ago(timestamp: i64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+        .unwrap_or_default();
+    match (now - timestamp).max(0) / 86_400 {
+        0 => "today".to_string(),
+        1 => "1 day ago".to_string(),
+        days => format!("{days} days ago"),
+    }
+}
+
+fn run_resolve(args: &ResolveArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+    if args.batch_size == 0 {
+        fail(ErrorClass::Usage, "--batch-size must be at least 1");
+    }
+
+    let mut conn = db::open_database_for(&project_root, db::Workload::Bulk).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let result = match args.limit {
+        Some(limit) => {
+            ReferenceResolver::resolve_first(&mut conn, &project_root, limit, args.batch_size)
+        }
+        None => ReferenceResolver::resolve_unresolved(&mut conn, &project_root, args.batch_size),
+    }
+    .unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to resolve references: {err}"),
+        );
+    });
+    let names = if args.report {
+        resolution::unresolved_names(&conn, args.top).unwrap_or_else(|err| {
+            fail(ErrorClass::Database, format!("Database error: {err}"));
+        })
+    } else {
+        Vec::new()
+    };
+
+    if json_output(args.json) {
+        let mut value = serde_json::json!({ "result": result });
+        if args.report
+            && let Some(object) = value.as_object_mut()
+        {
+            object.insert("unresolved".to_string(), serde_json::json!(names));
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        );
+        return;
+    }
+
+    println!(
+        "{} Resolved {} of {} reference(s) in {} pass(es); {} left unresolved",
+        style().mark(Mark::Ok),
+        result.resolved,
+        result.scanned,
+        result.passes,
+        result.remaining,
+    );
+    if !args.report {
+        return;
+    }
+    if names.is_empty() {
+        println!("\nNo unresolved references.");
+        return;
+    }
+    let width = names.iter().map(|n| n.name.len()).max().unwrap_or(0).max(4);
+    println!(
+        "\n  {:<width$}  {:<12}  {:>10}  {:>10}",
+        "NAME", "KIND", "REFERENCES", "CANDIDATES"
+    );
+    for name in &names {
+        let why = match name.candidates {
+            0 => "  (not in the index)",
+            1 => "  (no import or nearby definition)",
+            _ => "  (ambiguous)",
+        };
+        println!(
+            "  {:<width$}  {:<12}  {:>10}  {:>10}{why}",
+            name.name,
+            format!("{:?}", name.kind).to_ascii_lowercase(),
+            name.references,
+            name.candidates,
+        );
+    }
+}
+
+fn run_churn(args: &ChurnArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let mut cfg = config::load_toml_config(&project_root)
+        .unwrap_or_else(|err| fail(ErrorClass::Config, format!("Failed to load config: {err}")))
+        .history;
+    if let Some(max_commits) = args.max_commits {
+        cfg.max_commits = max_commits;
+    }
+    let summary = history::record_churn(&project_root, &cfg).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Failure,
+            format!("Failed to record churn: {err}"),
+        );
+    });
+
+    if json_output(args.json) {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summary).unwrap_or_default()
+        );
+        return;
+    }
+
+    println!(
+        "{} Recorded churn of {} file(s) and {} symbol(s) from {} commit(s)",
+        style().mark(Mark::Ok),
+        summary.files,
+        summary.nodes,
+        summary.commits_scanned,
+    );
+}
+
+fn run_ingest_coverage(args: &IngestCoverageArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    let format = args.format.as_deref().map(|value| {
+        CoverageFormat::parse(value).unwrap_or_else(|| {
+            fail(
+                ErrorClass::Usage,
+                format!("Unknown coverage format: {value} (expected lcov or cobertura)"),
+            );
+        })
+    });
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let summary =
+        coverage::ingest_coverage(&project_root, &args.report, format).unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Failure,
+                format!(
+                    "Failed to ingest coverage from {}: {err}",
+                    args.report.display()
+                ),
+            );
+        });
+
+    if json_output(args.json) {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summary).unwrap_or_default()
+        );
+        return;
+    }
+
+    println!(
+        "{} Ingested {} coverage: {}/{} files matched, {} functions measured, {} untested",
+        style().mark(Mark::Ok),
+        summary.format.as_str(),
+        summary.files_matched,
+        summary.files_in_report,
+        summary.nodes_measured,
+        summary.nodes_untested,
+    );
+    println!(
+        "  Lines covered: {}/{}",
+        summary.lines_covered, summary.lines_total
+    );
+    if !summary.unmatched_files.is_empty() {
+        println!(
+            "  {} report file(s) not in the index:",
+            summary.unmatched_files.len()
+        );
+        for path in &summary.unmatched_files {
+            println!("    {path}");
+        }
+    }
+}
+
+fn run_link_issues(args: &LinkIssuesArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let summary = issues::link_issues(&project_root).unwrap_or_else(|err| {
+        let class = if err.kind() == std::io::ErrorKind::InvalidInput {
+            ErrorClass::Config
+        } else {
+            ErrorClass::Failure
+        };
+        fail(class, format!("Failed to link issues: {err}"));
+    });
+
+    if json_output(args.json) {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summary).unwrap_or_default()
+        );
+        return;
+    }
+
+    println!(
+        "{} Linked {} issue(s) to {} symbol(s) ({} links)",
+        style().mark(Mark::Ok),
+        summary.issues,
+        summary.nodes,
+        summary.links,
+    );
+    if summary.git {
+        println!(
+            "  Commits: {} scanned, {} naming an issue",
+            summary.commits_scanned, summary.commits_with_issues
+        );
+    } else {
+        println!(
+            "  {} Not a git repository; scanned comments only",
+            style().mark(Mark::Warning)
+        );
+    }
+    println!("  Comment mentions: {}", summary.comment_mentions);
+}
+
+fn run_issues_for(args: &IssuesForArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    let conn = db::open_database(&project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+
+    let db_error = |err: CoralineError| -> ! {
+        fail(ErrorClass::Database, format!("Database error: {err}"));
+    };
+    let nodes = db::get_node_by_id(&conn, &args.symbol)
+        .unwrap_or_else(|e| db_error(e))
+        .map_or_else(
+            || {
+                db::find_nodes_by_name(&conn, &args.symbol)
+                    .unwrap_or_else(|e| db_error(e))
+                    .into_iter()
+                    .filter(|n| issues::is_linkable(n.kind))
+                    .filter(|n| {
+                        args.file
+                            .as_ref()
+                            .is_none_or(|f| n.file_path.ends_with(f.as_str()))
+                    })
+                    .collect()
+            },
+            |node| vec![node],
+        );
+    if nodes.is_empty() {
+        fail(
+            ErrorClass::Failure,
+            format!("Symbol not found: {}", args.symbol),
+        );
+    }
+
+    let linked: Vec<_> = nodes
+        .into_iter()
+        .map(|node| {
+            let mut links =
+                db::get_issue_links_for_node(&conn, &node).unwrap_or_else(|e| db_error(e));
+            // Nested nodes blamed to the same commit repeat its link.
+            links.dedup_by(|a, b| a.issue_id == b.issue_id && a.reference == b.reference);
+            (node, links)
+        })
+        .collect();
+
+    if json_output(args.json) {
+        let results: Vec<_> = linked
+            .iter()
+            .map(|(node, links)| {
+                serde_json::json!({
+                    "id": node.id,
+                    "name": node.name,
+                    "kind": node.kind,
+                    "file": node.file_path,
+                    "line": node.start_line,
+                    "issues": links,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&results).unwrap_or_default()
+        );
         return;
     }
 
-    println!("Search Results for \"{}\":\n", args.search);
-    for result in results {
-        let node = result.node;
+    for (node, links) in &linked {
         println!(
-            "{:?} {} ({:.0}%)",
-            node.kind,
-            node.name,
-            result.score * 100.0
+            "{} {:?} ({}:{})",
+            node.name, node.kind, node.file_path, node.start_line
         );
-        println!("  {}:{}", node.file_path, node.start_line);
-        if let Some(signature) = node.signature {
-            println!("  {signature}");
+        if links.is_empty() {
+            println!("  No linked issues");
+        }
+        let mut current: Option<&str> = None;
+        for link in links {
+            if current != Some(link.issue_id.as_str()) {
+                println!("  {}", link.issue_id);
+                current = Some(link.issue_id.as_str());
+            }
+            match link.source {
+                IssueSource::Commit => println!(
+                    "    commit {}  {}",
+                    &link.reference[..link.reference.len().min(10)],
+                    link.summary
+                ),
+                IssueSource::Comment => {
+                    println!("    comment {}  {}", link.reference, link.summary);
+                }
+            }
         }
-        println!();
     }
-}
 
-fn run_context(args: ContextArgs) {
-    let project_root = resolve_project_root(args.path);
-
-    if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+    if db::count_issue_links(&conn).unwrap_or(0) == 0 {
+        println!(
+            "{} No issue links stored yet; run `coraline link-issues` first",
+            style().mark(Mark::Warning)
+        );
     }
+}
 
-    let format = match args.format.to_ascii_lowercase().as_str() {
-        "json" => ContextFormat::Json,
-        _ => ContextFormat::Markdown,
+fn run_worktrees(args: &WorktreesArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+    let worktrees = worktree::list(&project_root);
+    let Some(common) = worktrees.first().map(|w| w.git_common_dir.clone()) else {
+        fail(
+            ErrorClass::Failure,
+            format!("Not a git checkout root: {}", project_root.display()),
+        );
     };
 
-    let options = BuildContextOptions {
-        max_nodes: Some(args.max_nodes),
-        max_code_blocks: Some(args.max_code),
-        max_code_block_size: None,
-        include_code: Some(!args.no_code),
-        format: Some(format),
-        search_limit: None,
-        traversal_depth: None,
-        min_score: None,
+    let cache = ParseCache::at(worktree::shared_cache_dir(&common));
+    let removed = if args.clear_cache {
+        Some(cache.prune(None))
+    } else {
+        args.prune_days
+            .map(|days| cache.prune(Some(Duration::from_secs(days * 86_400))))
     };
+    let enabled = ParseCache::for_project(&project_root).is_some();
+    let stats = cache.stats();
 
-    let output =
-        context::build_context(&project_root, &args.task, &options).unwrap_or_else(|err| {
-            eprintln!("Failed to build context: {err}");
-            std::process::exit(1);
-        });
+    let rows: Vec<_> = worktrees
+        .iter()
+        .map(|w| {
+            let db_path = db::database_path(&w.root);
+            let db_bytes = std::fs::metadata(&db_path).ok().map(|m| m.len());
+            (w, db_bytes)
+        })
+        .collect();
 
-    println!("{output}");
-}
+    if json_output(args.json) {
+        let listed: Vec<_> = rows
+            .iter()
+            .map(|(w, db_bytes)| {
+                serde_json::json!({
+                    "root": w.root,
+                    "linked": w.is_linked,
+                    "initialized": db_bytes.is_some(),
+                    "db_bytes": db_bytes,
+                })
+            })
+            .collect();
+        let out = serde_json::json!({
+            "git_common_dir": common,
+            "worktrees": listed,
+            "parse_cache": {
+                "enabled": enabled,
+                "dir": cache.dir(),
+                "entries": stats.entries,
+                "bytes": stats.bytes,
+                "removed": removed,
+            },
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        return;
+    }
 
-fn run_hooks_install(path: Option<PathBuf>) {
-    let project_root = resolve_project_root(path);
-    let hooks = GitHooksManager::new(&project_root);
-    let result = hooks.install_hook();
-    if result.success {
-        println!("{}", result.message);
-        if let Some(backup) = result.backup_path {
-            println!("Previous hook backed up at {}", backup.display());
+    for (w, db_bytes) in &rows {
+        let kind = if w.is_linked { "linked" } else { "main" };
+        match db_bytes {
+            Some(bytes) => println!(
+                "{} {} ({kind}, database {} bytes)",
+                style().mark(Mark::Ok),
+                w.root.display(),
+                style().count(bytes)
+            ),
+            None => println!("  {} ({kind}, not initialized)", w.root.display()),
         }
-    } else {
-        eprintln!("{}", result.message);
-        std::process::exit(1);
     }
-}
-
-fn run_hooks_remove(path: Option<PathBuf>) {
-    let project_root = resolve_project_root(path);
-    let hooks = GitHooksManager::new(&project_root);
-    let result = hooks.remove_hook();
-    if result.success {
-        println!("{}", result.message);
-    } else {
-        eprintln!("{}", result.message);
-        std::process::exit(1);
+    if let Some(removed) = removed {
+        println!("Removed {} parse cache entries", style().count(removed));
     }
+    println!(
+        "Parse cache: {} entries, {} bytes in {}{}",
+        style().count(stats.entries),
+        style().count(stats.bytes),
+        cache.dir().display(),
+        if enabled { "" } else { " (off)" }
+    );
 }
 
-fn run_hooks_status(path: Option<PathBuf>) {
-    let project_root = resolve_project_root(path);
-    let hooks = GitHooksManager::new(&project_root);
-    if !hooks.is_git_repository() {
-        println!("Not a git repository.");
+fn run_branches(args: &BranchesArgs) {
+    let project_root = resolve_project_root(args.path.clone());
+    if !is_initialized(&project_root) {
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+    let conn = db::open_database(&project_root).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
+    });
+    let live = db::get_graph_branch(&conn).unwrap_or_else(|err| {
+        fail(
+            ErrorClass::Database,
+            format!("Failed to read the graph branch: {err}"),
+        );
+    });
+    let removed = args.prune.then(|| {
+        branches::prune(&project_root).unwrap_or_else(|err| {
+            fail(
+                ErrorClass::Failure,
+                format!("Failed to list branches: {err}"),
+            );
+        })
+    });
+    let enabled = branches::enabled(&project_root);
+    let checked_out = branches::current_branch(&project_root);
+    let saved = branches::list(&project_root);
+
+    if json_output(args.json) {
+        let out = serde_json::json!({
+            "enabled": enabled,
+            "checked_out": checked_out,
+            "live": live,
+            "saved": saved,
+            "removed": removed,
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
         return;
     }
-    if hooks.is_hook_installed() {
-        println!("Git hook is installed.");
-    } else {
-        println!("Git hook is not installed.");
+
+    match &live {
+        Some(branch) => println!(
+            "{} {branch} (live graph{})",
+            style().mark(Mark::Ok),
+            if enabled { "" } else { ", branch graphs off" }
+        ),
+        None if enabled => println!("  No branch recorded yet; the next sync records it."),
+        None => println!("  Branch graphs are off; set [indexing] branch_graphs = true."),
+    }
+    for graph in &saved {
+        println!(
+            "  {} (saved, {} bytes)",
+            graph.branch,
+            style().count(graph.bytes)
+        );
+    }
+    if let (Some(checked_out), Some(live)) = (&checked_out, &live)
+        && enabled
+        && checked_out != live
+    {
+        println!(
+            "{} {checked_out} is checked out; the next sync switches to it.",
+            style().mark(Mark::Warning)
+        );
+    }
+    if let Some(removed) = removed {
+        println!(
+            "Removed {} saved graphs of deleted branches",
+            style().count(removed.len())
+        );
     }
 }
 
-fn run_stats(args: StatsArgs) {
+fn run_remote(args: RemoteArgs) {
     let project_root = resolve_project_root(args.path);
-
     if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
     }
+    let class = |err: &CoralineError| match err {
+        CoralineError::NotInitialized(_) => ErrorClass::NotInitialized,
+        CoralineError::InvalidInput(_) | CoralineError::NotFound(_) => ErrorClass::Usage,
+        CoralineError::Parse { .. } => ErrorClass::Config,
+        _ => ErrorClass::Failure,
+    };
 
-    let conn = db::open_database(&project_root).unwrap_or_else(|err| {
-        eprintln!("Failed to open database: {err}");
-        std::process::exit(1);
-    });
-
-    let stats = db::get_db_stats(&conn).unwrap_or_else(|err| {
-        eprintln!("Failed to get stats: {err}");
-        std::process::exit(1);
-    });
-
-    if args.json {
-        let json = serde_json::to_string_pretty(&stats).unwrap_or_default();
-        println!("{json}");
-        return;
+    match args.action {
+        RemoteAction::Add { location, name } => {
+            match remotes::add(&project_root, &location, name.as_deref()) {
+                Ok(remote) => println!(
+                    "{} Added remote {} ({})",
+                    style().mark(Mark::Ok),
+                    remote.name,
+                    remote.location
+                ),
+                Err(err) => fail(class(&err), format!("Failed to add remote: {err}")),
+            }
+        }
+        RemoteAction::Remove { name } => match remotes::remove(&project_root, &name) {
+            Ok(true) => println!("Removed remote {name}"),
+            Ok(false) => fail(ErrorClass::Usage, format!("No remote named '{name}'")),
+            Err(err) => fail(class(&err), format!("Failed to remove remote: {err}")),
+        },
+        RemoteAction::List { json } => {
+            let remotes = remotes::list(&project_root)
+                .unwrap_or_else(|err| fail(class(&err), format!("Failed to read remotes: {err}")));
+            if json_output(json) {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&remotes).unwrap_or_default()
+                );
+                return;
+            }
+            if remotes.is_empty() {
+                println!("No remotes. Add one with `coraline remote add <path|url>`.");
+            }
+            for remote in remotes {
+                println!("{}  {}", remote.name, remote.location);
+            }
+        }
+        RemoteAction::Resolve { json } => {
+            let remotes = remotes::list(&project_root)
+                .unwrap_or_else(|err| fail(class(&err), format!("Failed to read remotes: {err}")));
+            let conn = db::open_database(&project_root).unwrap_or_else(|err| {
+                fail(
+                    ErrorClass::Database,
+                    format!("Failed to open database: {err}"),
+                );
+            });
+            let links =
+                remotes::resolve_imports(&project_root, &conn, &remotes).unwrap_or_else(|err| {
+                    fail(
+                        ErrorClass::Database,
+                        format!("Failed to resolve imports: {err}"),
+                    )
+                });
+            if json_output(json) {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&links).unwrap_or_default()
+                );
+                return;
+            }
+            if links.is_empty() {
+                println!("No imports of packages published by a remote.");
+                return;
+            }
+            for link in links {
+                let imported = link.symbol.as_ref().map_or_else(
+                    || link.module.clone(),
+                    |s| format!("{s} from {}", link.module),
+                );
+                let target = match (link.target, &link.symbol) {
+                    (Some(node), _) => format!("{}:{}", node.file_path, node.start_line),
+                    (None, Some(_)) => "(symbol not found)".to_string(),
+                    (None, None) => String::new(),
+                };
+                println!(
+                    "{}:{}  {imported}  ->  {} [{}] {target}",
+                    link.file_path, link.line, link.remote, link.package
+                );
+            }
+        }
     }
-
-    println!("Coraline Statistics\n");
-    println!("Files:     {}", stats.file_count);
-    println!("\nNodes:     {}", stats.node_count);
-    println!("Edges:     {}", stats.edge_count);
-    println!("Unresolved refs: {}", stats.unresolved_count);
 }
 
 fn run_callers(args: CallersArgs) {
     let project_root = resolve_project_root(args.path);
 
     if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
     }
+    ensure_fresh_index(&project_root);
 
     let conn = db::open_database(&project_root).unwrap_or_else(|err| {
-        eprintln!("Failed to open database: {err}");
-        std::process::exit(1);
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
     });
 
-    let node = db::get_node_by_id(&conn, &args.node_id)
+    let node = resolve_symbol_arg(&conn, &project_root, &args.symbol, args.file.as_deref());
+
+    let edges = db::get_edges_by_target(&conn, &node.id, Some(EdgeKind::Calls), args.limit * 2)
         .unwrap_or_else(|err| {
-            eprintln!("Database error: {err}");
-            std::process::exit(1);
-        })
-        .unwrap_or_else(|| {
-            eprintln!("Node not found: {}", args.node_id);
-            std::process::exit(1);
+            fail(
+                ErrorClass::Database,
+                format!("Failed to get callers: {err}"),
+            );
         });
 
-    let edges =
-        db::get_edges_by_target(&conn, &args.node_id, Some(EdgeKind::Calls), args.limit * 2)
-            .unwrap_or_else(|err| {
-                eprintln!("Failed to get callers: {err}");
-                std::process::exit(1);
-            });
-
-    if args.json {
+    if json_output(args.json) {
         let results: Vec<_> = edges
             .iter()
             .filter_map(|e| {
@@ -1562,24 +4893,18 @@ fn run_callers(args: CallersArgs) {
     }
 
     println!("Callers of {} ({:?}):\n", node.name, node.kind);
-    let mut printed = 0;
-    for edge in &edges {
-        if printed >= args.limit {
-            break;
-        }
-        if let Ok(Some(caller)) = db::get_node_by_id(&conn, &edge.source) {
-            // Validate crate boundary
-            if matches!(db::is_valid_call_edge(&conn, &caller, &node), Ok(true)) {
-                println!(
-                    "  {:?} {} ({}:{})",
-                    caller.kind, caller.name, caller.file_path, caller.start_line
-                );
-                printed += 1;
-            }
-        }
-    }
-    if printed == 0 {
+    let rows: Vec<_> = edges
+        .iter()
+        .filter_map(|edge| db::get_node_by_id(&conn, &edge.source).ok().flatten())
+        // Validate crate boundary
+        .filter(|caller| matches!(db::is_valid_call_edge(&conn, caller, &node), Ok(true)))
+        .take(args.limit)
+        .map(|caller| (caller, String::new()))
+        .collect();
+    if rows.is_empty() {
         println!("  No callers found.");
+    } else {
+        print_symbol_table(&rows);
     }
 }
 
@@ -1587,33 +4912,31 @@ fn run_callees(args: CalleesArgs) {
     let project_root = resolve_project_root(args.path);
 
     if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
     }
+    ensure_fresh_index(&project_root);
 
     let conn = db::open_database(&project_root).unwrap_or_else(|err| {
-        eprintln!("Failed to open database: {err}");
-        std::process::exit(1);
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
     });
 
-    let node = db::get_node_by_id(&conn, &args.node_id)
+    let node = resolve_symbol_arg(&conn, &project_root, &args.symbol, args.file.as_deref());
+
+    let edges = db::get_edges_by_source(&conn, &node.id, Some(EdgeKind::Calls), args.limit * 2)
         .unwrap_or_else(|err| {
-            eprintln!("Database error: {err}");
-            std::process::exit(1);
-        })
-        .unwrap_or_else(|| {
-            eprintln!("Node not found: {}", args.node_id);
-            std::process::exit(1);
+            fail(
+                ErrorClass::Database,
+                format!("Failed to get callees: {err}"),
+            );
         });
 
-    let edges =
-        db::get_edges_by_source(&conn, &args.node_id, Some(EdgeKind::Calls), args.limit * 2)
-            .unwrap_or_else(|err| {
-                eprintln!("Failed to get callees: {err}");
-                std::process::exit(1);
-            });
-
-    if args.json {
+    if json_output(args.json) {
         let results: Vec<_> = edges
             .iter()
             .filter_map(|e| {
@@ -1638,24 +4961,90 @@ fn run_callees(args: CalleesArgs) {
     }
 
     println!("Callees of {} ({:?}):\n", node.name, node.kind);
-    let mut printed = 0;
-    for edge in &edges {
-        if printed >= args.limit {
-            break;
-        }
-        if let Ok(Some(callee)) = db::get_node_by_id(&conn, &edge.target) {
-            // Validate crate boundary
-            if matches!(db::is_valid_call_edge(&conn, &node, &callee), Ok(true)) {
-                println!(
-                    "  {:?} {} ({}:{})",
-                    callee.kind, callee.name, callee.file_path, callee.start_line
-                );
-                printed += 1;
-            }
+    let rows: Vec<_> = edges
+        .iter()
+        .filter_map(|edge| db::get_node_by_id(&conn, &edge.target).ok().flatten())
+        // Validate crate boundary
+        .filter(|callee| matches!(db::is_valid_call_edge(&conn, &node, callee), Ok(true)))
+        .take(args.limit)
+        .map(|callee| (callee, String::new()))
+        .collect();
+    if rows.is_empty() {
+        println!("  No callees found.");
+    } else {
+        print_symbol_table(&rows);
+    }
+}
+
+/// The node `symbol` names: a node ID, or the one symbol with that name,
+/// narrowed to `file` when given. Fails listing the candidates when the
+/// name is ambiguous.
+fn resolve_symbol_arg(
+    conn: &rusqlite::Connection,
+    project_root: &Path,
+    symbol: &str,
+    file: Option<&str>,
+) -> Node {
+    let db_error = |err: CoralineError| -> ! {
+        fail(ErrorClass::Database, format!("Database error: {err}"));
+    };
+    if file.is_none()
+        && let Some(node) = db::get_node_by_id(conn, symbol).unwrap_or_else(|err| db_error(err))
+    {
+        return node;
+    }
+    let mut candidates =
+        graph::find_symbol(conn, project_root, symbol, file).unwrap_or_else(|err| db_error(err));
+    match candidates.len() {
+        0 => fail(
+            ErrorClass::Failure,
+            format!(
+                "No symbol named '{symbol}' found{}",
+                file.map_or_else(String::new, |f| format!(" in file '{f}'"))
+            ),
+        ),
+        1 => candidates.remove(0),
+        count => {
+            let listing: Vec<String> = candidates
+                .iter()
+                .map(|n| {
+                    format!(
+                        "  {} ({:?}) — {}:{}",
+                        n.id, n.kind, n.file_path, n.start_line
+                    )
+                })
+                .collect();
+            fail(
+                ErrorClass::Usage,
+                format!(
+                    "Ambiguous: {count} symbols named '{symbol}'. Pass a node ID, or --file to pick one:\n{}",
+                    listing.join("\n")
+                ),
+            )
         }
     }
-    if printed == 0 {
-        println!("  No callees found.");
+}
+
+/// Print symbols as aligned kind, name, and location columns, each row
+/// followed by its note.
+fn print_symbol_table(rows: &[(Node, String)]) {
+    let kinds: Vec<String> = rows.iter().map(|(n, _)| format!("{:?}", n.kind)).collect();
+    let kind_width = kinds.iter().map(String::len).max().unwrap_or(0).max(4);
+    let name_width = rows
+        .iter()
+        .map(|(n, _)| n.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "  {:<kind_width$}  {:<name_width$}  location",
+        "kind", "name"
+    );
+    for ((node, note), kind) in rows.iter().zip(&kinds) {
+        println!(
+            "  {kind:<kind_width$}  {:<name_width$}  {}:{}{note}",
+            node.name, node.file_path, node.start_line
+        );
     }
 }
 
@@ -1663,35 +5052,38 @@ fn run_impact(args: ImpactArgs) {
     let project_root = resolve_project_root(args.path);
 
     if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
     }
+    ensure_fresh_index(&project_root);
 
     let conn = db::open_database(&project_root).unwrap_or_else(|err| {
-        eprintln!("Failed to open database: {err}");
-        std::process::exit(1);
+        fail(
+            ErrorClass::Database,
+            format!("Failed to open database: {err}"),
+        );
     });
 
-    let node = db::get_node_by_id(&conn, &args.node_id)
-        .unwrap_or_else(|err| {
-            eprintln!("Database error: {err}");
-            std::process::exit(1);
-        })
-        .unwrap_or_else(|| {
-            eprintln!("Node not found: {}", args.node_id);
-            std::process::exit(1);
-        });
+    let node = resolve_symbol_arg(&conn, &project_root, &args.symbol, args.file.as_deref());
 
     // BFS outward from target edges (who directly or transitively uses this node)
     let mut visited = std::collections::HashSet::new();
-    let mut frontier = vec![args.node_id.clone()];
-    visited.insert(args.node_id.clone());
+    let mut frontier = vec![node.id.clone()];
+    visited.insert(node.id.clone());
 
     for _ in 0..args.depth {
         let mut next = Vec::new();
         for id in &frontier {
             if let Ok(edges) = db::get_edges_by_target(&conn, id, None, 100) {
                 for edge in edges {
+                    if args
+                        .min_confidence
+                        .is_some_and(|min| graph::edge_confidence(&edge) < min)
+                    {
+                        continue;
+                    }
                     if visited.insert(edge.source.clone()) {
                         next.push(edge.source);
                     }
@@ -1703,13 +5095,19 @@ fn run_impact(args: ImpactArgs) {
         }
         frontier = next;
     }
-    visited.remove(&args.node_id);
+    visited.remove(&node.id);
+
+    let ids: Vec<&str> = visited.iter().map(String::as_str).collect();
+    let coverage = db::get_coverage(&conn, &ids).unwrap_or_else(|err| {
+        fail(ErrorClass::Database, format!("Database error: {err}"));
+    });
+    let is_untested = |id: &str| coverage.get(id).is_some_and(NodeCoverage::is_untested);
 
-    if args.json {
+    if json_output(args.json) {
         let results: Vec<_> = visited
             .iter()
             .filter_map(|id| db::get_node_by_id(&conn, id).ok().flatten())
-            .map(|n| serde_json::json!({ "id": n.id, "name": n.name, "kind": n.kind, "file": n.file_path }))
+            .map(|n| serde_json::json!({ "id": n.id, "name": n.name, "kind": n.kind, "file": n.file_path, "untested": is_untested(&n.id) }))
             .collect();
         println!(
             "{}",
@@ -1735,75 +5133,73 @@ fn run_impact(args: ImpactArgs) {
             .cmp(&b.file_path)
             .then(a.start_line.cmp(&b.start_line))
     });
-    for n in &affected {
-        println!(
-            "  {:?} {} ({}:{})",
-            n.kind, n.name, n.file_path, n.start_line
-        );
+    let untested = affected.iter().filter(|n| is_untested(&n.id)).count();
+    let count = affected.len();
+    let rows: Vec<_> = affected
+        .into_iter()
+        .map(|n| {
+            let marker = if is_untested(&n.id) {
+                "  (untested)"
+            } else {
+                ""
+            };
+            (n, marker.to_string())
+        })
+        .collect();
+    print_symbol_table(&rows);
+    println!("\n{count} affected symbol(s)");
+    if untested > 0 {
+        println!("{untested} affected symbol(s) have no test coverage");
     }
-    println!("\n{} affected symbol(s)", affected.len());
 }
 
 fn run_config(args: ConfigArgs) {
     let project_root = resolve_project_root(args.path);
 
     if !is_initialized(&project_root) {
-        eprintln!("Coraline not initialized in {}", project_root.display());
-        std::process::exit(1);
+        fail(
+            ErrorClass::NotInitialized,
+            format!("Coraline not initialized in {}", project_root.display()),
+        );
+    }
+
+    match &args.action {
+        Some(ConfigAction::Get { key }) => {
+            let value =
+                config::get_value(&project_root, key).unwrap_or_else(|err| fail_config_edit(&err));
+            match value {
+                serde_json::Value::String(text) => println!("{text}"),
+                value => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&value).unwrap_or_default()
+                ),
+            }
+            return;
+        }
+        Some(ConfigAction::Set { key, value }) => return run_config_set(&project_root, key, value),
+        Some(ConfigAction::Validate) => {
+            return run_config_validate(&project_root, json_output(args.json));
+        }
+        None => {}
     }
 
     // Handle --set section.key=value
     if let Some(set_expr) = &args.set {
-        let parts: Vec<&str> = set_expr.splitn(2, '=').collect();
-        let &[path_part, value_str] = parts.as_slice() else {
-            eprintln!("Invalid --set format. Expected: section.key=value");
-            std::process::exit(1);
-        };
-        let path_parts: Vec<&str> = path_part.splitn(2, '.').collect();
-        let &[section, key] = path_parts.as_slice() else {
-            eprintln!(
-                "Invalid --set path. Expected: section.key=value (e.g. indexing.batch_size=50)"
+        let Some((key, value)) = set_expr.split_once('=') else {
+            fail(
+                ErrorClass::Usage,
+                "Invalid --set format. Expected: section.key=value (e.g. indexing.batch_size=50)",
             );
-            std::process::exit(1);
         };
-
-        let mut cfg = config::load_toml_config(&project_root).unwrap_or_else(|err| {
-            eprintln!("Failed to load config: {err}");
-            std::process::exit(1);
-        });
-
-        // Parse value as JSON for type flexibility
-        let json_value: serde_json::Value = serde_json::from_str(value_str)
-            .unwrap_or_else(|_| serde_json::Value::String(value_str.to_string()));
-
-        let mut cfg_json = serde_json::to_value(&cfg).unwrap_or_default();
-        if let Some(section_obj) = cfg_json.get_mut(section).and_then(|v| v.as_object_mut()) {
-            section_obj.insert(key.to_string(), json_value.clone());
-        } else {
-            eprintln!("Unknown config section: {section}");
-            std::process::exit(1);
-        }
-
-        cfg = serde_json::from_value(cfg_json).unwrap_or_else(|err| {
-            eprintln!("Invalid value for {section}.{key}: {err}");
-            std::process::exit(1);
-        });
-
-        config::save_toml_config(&project_root, &cfg).unwrap_or_else(|err| {
-            eprintln!("Failed to save config: {err}");
-            std::process::exit(1);
-        });
-
-        println!("Updated {section}.{key} = {json_value}");
+        run_config_set(&project_root, key, value);
         return;
     }
 
     let cfg = config::load_toml_config(&project_root).unwrap_or_else(|err| {
-        eprintln!("Failed to load config: {err}");
-        std::process::exit(1);
+        fail(ErrorClass::Config, format!("Failed to load config: {err}"));
     });
 
-    if args.json {
+    if json_output(args.json) {
         let mut v = serde_json::to_value(&cfg).unwrap_or_default();
         if let Some(section) = &args.section {
             v = v
@@ -1834,13 +5230,91 @@ fn run_config(args: ConfigArgs) {
     }
 }
 
+fn run_config_set(project_root: &Path, key: &str, value: &str) {
+    let stored =
+        config::set_value(project_root, key, value).unwrap_or_else(|err| fail_config_edit(&err));
+    println!("Updated {key} = {stored}");
+}
+
+fn run_config_validate(project_root: &Path, json: bool) {
+    let problems = config::validate(project_root);
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&problems).unwrap_or_default()
+        );
+    } else {
+        let out = style();
+        for problem in &problems {
+            let at = if problem.key.is_empty() {
+                problem.file.to_string()
+            } else {
+                format!("{} {}", problem.file, problem.key)
+            };
+            println!("{} {at}: {}", out.mark(Mark::Warning), problem.message);
+        }
+    }
+    if !problems.is_empty() {
+        fail(
+            ErrorClass::Config,
+            format!(
+                "Found {} configuration problem{}",
+                problems.len(),
+                if problems.len() == 1 { "" } else { "s" }
+            ),
+        );
+    }
+    if !json {
+        println!("{} Configuration is valid", style().mark(Mark::Ok));
+    }
+}
+
+/// A failed `config get` or `config set`: a bad key or value is a usage
+/// error, anything else a config error.
+fn fail_config_edit(err: &std::io::Error) -> ! {
+    let class = if err.kind() == std::io::ErrorKind::InvalidInput {
+        ErrorClass::Usage
+    } else {
+        ErrorClass::Config
+    };
+    fail(class, err)
+}
+
+/// Apply `[sync] on_stale_index` before a graph query: warn on stderr,
+/// sync, or fail when the index is behind the working tree.
+fn ensure_fresh_index(project_root: &Path) {
+    match staleness::ensure_fresh(project_root) {
+        Ok(Freshness::Fresh) => {}
+        Ok(Freshness::Stale(status)) => eprintln!(
+            "{} {} Run `coraline sync`, or set [sync] on_stale_index = \"sync\".",
+            style().mark(Mark::Warning),
+            staleness::describe(&status)
+        ),
+        Ok(Freshness::Synced(result)) => info!(
+            files_added = result.files_added,
+            files_modified = result.files_modified,
+            files_removed = result.files_removed,
+            "synced stale index before query"
+        ),
+        Ok(Freshness::Refused(status)) => fail(
+            ErrorClass::Failure,
+            format!(
+                "{} Run `coraline sync` first.",
+                staleness::describe(&status)
+            ),
+        ),
+        Err(err) => debug!(error = %err, "index freshness check failed"),
+    }
+}
+
 fn resolve_project_root(path: Option<PathBuf>) -> PathBuf {
     path.unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
 }
 
+/// A project is initialized once its database exists; `.coraline/` alone is
+/// not enough, since logging creates `.coraline/logs/` for any command.
 fn is_initialized(project_root: &Path) -> bool {
-    let dir = project_root.join(".coraline");
-    dir.is_dir()
+    db::database_path(project_root).is_file()
 }
 
 fn create_coraline_dir(project_root: &Path) -> std::io::Result<()> {
@@ -1848,12 +5322,22 @@ fn create_coraline_dir(project_root: &Path) -> std::io::Result<()> {
     std::fs::create_dir_all(&dir)?;
     let gitignore_path = dir.join(".gitignore");
     if !gitignore_path.exists() {
-        let content = "# Coraline data files\n# These are local to each machine and should not be committed\n\n# Database\n*.db\n*.db-wal\n*.db-shm\n\n# Cache\ncache/\n\n# Logs\n*.log\n";
+        let content = "# Coraline data files\n# These are local to each machine and should not be committed\n\n# Database\n*.db\n*.db-wal\n*.db-shm\n\n# Cache\ncache/\n\n# Logs\n*.log\n\n# Daemon socket\ndaemon.sock\n";
         std::fs::write(gitignore_path, content)?;
     }
     Ok(())
 }
 
+/// The languages `--lang` names: a language id (`type_script`), or a
+/// common name such as `typescript` or `ts`, which also covers `tsx`.
+fn parse_languages(value: &str) -> Option<Vec<Language>> {
+    if let Ok(language) = serde_json::from_value(serde_json::Value::String(value.to_string())) {
+        return Some(vec![language]);
+    }
+    let hinted = coraline::utils::language_hint(value);
+    (!hinted.is_empty()).then(|| hinted.to_vec())
+}
+
 fn parse_node_kind(value: &str) -> Option<NodeKind> {
     match value.to_ascii_lowercase().as_str() {
         "file" => Some(NodeKind::File),
@@ -1878,6 +5362,8 @@ fn parse_node_kind(value: &str) -> Option<NodeKind> {
         "export" => Some(NodeKind::Export),
         "route" => Some(NodeKind::Route),
         "component" => Some(NodeKind::Component),
+        "model" => Some(NodeKind::Model),
+        "todo" => Some(NodeKind::Todo),
         _ => None,
     }
 }
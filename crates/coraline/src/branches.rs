@@ -0,0 +1,178 @@
+#![forbid(unsafe_code)]
+
+//! Per-branch graphs.
+//!
+//! With `[indexing] branch_graphs` on, the database records which git
+//! branch its graph was built for. When `index` or `sync` finds a different
+//! branch checked out, the live graph is saved to
+//! `.coraline/branches/<branch>.db` and the new branch's saved graph, if
+//! there is one, is restored in its place. The sync that follows only
+//! re-parses files that differ from that graph, and the shared parse cache
+//! is turned on so a file already parsed on another branch is stored
+//! without running tree-sitter again.
+//!
+//! A detached HEAD has no namespace: the graph stays filed under the last
+//! branch and is synced in place.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tracing::info;
+
+use crate::config;
+use crate::db;
+use crate::utils;
+
+const BRANCHES_DIR: &str = "branches";
+
+/// A change of the branch the live graph belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BranchSwitch {
+    /// The branch the graph was built for, saved before the switch.
+    pub from: String,
+    pub to: String,
+    /// Whether a saved graph of `to` replaced the live one.
+    pub restored: bool,
+}
+
+/// A branch graph saved under `.coraline/branches/`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SavedGraph {
+    pub branch: String,
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Whether `[indexing] branch_graphs` is on for the project.
+pub fn enabled(project_root: &Path) -> bool {
+    config::load_toml_config(project_root).is_ok_and(|cfg| cfg.indexing.branch_graphs)
+}
+
+/// The checked-out branch, or `None` outside git or on a detached HEAD.
+pub fn current_branch(project_root: &Path) -> Option<String> {
+    let name = utils::git(project_root, &["symbolic-ref", "--short", "-q", "HEAD"]).ok()?;
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Where the saved graph of `branch` lives. Characters outside
+/// `[A-Za-z0-9._-]` are percent-encoded, so `feat/x` and `feat%2Fx` never
+/// share a file.
+pub fn graph_path(project_root: &Path, branch: &str) -> PathBuf {
+    let mut file = String::with_capacity(branch.len() + 3);
+    for byte in branch.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_' | b'-') {
+            file.push(char::from(byte));
+        } else {
+            let _ = write!(file, "%{byte:02X}");
+        }
+    }
+    file.push_str(".db");
+    branches_dir(project_root).join(file)
+}
+
+fn branches_dir(project_root: &Path) -> PathBuf {
+    project_root.join(".coraline").join(BRANCHES_DIR)
+}
+
+fn branch_from_file(file: &str) -> Option<String> {
+    let stem = file.strip_suffix(".db")?;
+    let mut bytes = Vec::with_capacity(stem.len());
+    let mut rest = stem.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let (hex, after) = tail.split_at_checked(2)?;
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            rest = after;
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Move the live graph to the checked-out branch.
+///
+/// Save it under the branch it was built for and restore the current
+/// branch's saved graph. A database without a recorded branch is adopted
+/// by the current one. Returns `None` when nothing changed, the option is
+/// off, or HEAD is detached.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the graph cannot be saved or restored.
+pub fn switch(project_root: &Path, conn: &mut Connection) -> std::io::Result<Option<BranchSwitch>> {
+    if !enabled(project_root) {
+        return Ok(None);
+    }
+    let Some(branch) = current_branch(project_root) else {
+        return Ok(None);
+    };
+    let Some(previous) = db::get_graph_branch(conn)? else {
+        db::set_graph_branch(conn, &branch)?;
+        return Ok(None);
+    };
+    if previous == branch {
+        return Ok(None);
+    }
+
+    db::backup_database(conn, &graph_path(project_root, &previous))?;
+    let saved = graph_path(project_root, &branch);
+    let restored = saved.is_file();
+    if restored {
+        db::restore_database(conn, &saved)?;
+        // The live database is now the only copy worth keeping.
+        let _ = fs::remove_file(&saved);
+    }
+    db::set_graph_branch(conn, &branch)?;
+    info!(from = %previous, to = %branch, restored, "switched branch graph");
+    Ok(Some(BranchSwitch {
+        from: previous,
+        to: branch,
+        restored,
+    }))
+}
+
+/// Saved branch graphs, sorted by branch name.
+pub fn list(project_root: &Path) -> Vec<SavedGraph> {
+    let mut graphs: Vec<SavedGraph> = fs::read_dir(branches_dir(project_root))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let branch = branch_from_file(entry.file_name().to_str()?)?;
+            Some(SavedGraph {
+                branch,
+                path: entry.path(),
+                bytes: entry.metadata().ok()?.len(),
+            })
+        })
+        .collect();
+    graphs.sort_by(|a, b| a.branch.cmp(&b.branch));
+    graphs
+}
+
+/// Delete saved graphs of branches that no longer exist locally. Returns
+/// the branches removed.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the local branches cannot be listed.
+pub fn prune(project_root: &Path) -> std::io::Result<Vec<String>> {
+    let heads = utils::git(
+        project_root,
+        &["for-each-ref", "--format=%(refname:short)", "refs/heads"],
+    )?;
+    let heads: Vec<&str> = heads.lines().map(str::trim).collect();
+    let mut removed = Vec::new();
+    for graph in list(project_root) {
+        if !heads.contains(&graph.branch.as_str()) && fs::remove_file(&graph.path).is_ok() {
+            removed.push(graph.branch);
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,153 @@
+#![forbid(unsafe_code)]
+
+//! [`CodeGraph`], the entry point for using Coraline as a library.
+//!
+//! It holds one project, its configuration, and an open database, with
+//! the operations the CLI offers as methods. The modules underneath (`db`,
+//! `extraction`, `graph`, `context`) stay public for anything the facade
+//! does not cover; [`CodeGraph::connection`] hands them the database.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+
+use crate::config::{self, SearchConfig};
+use crate::context;
+use crate::db::{self, DbStats};
+use crate::error::{CoralineError, Result};
+use crate::extraction::{self, IndexResult, SyncResult};
+use crate::graph;
+use crate::types::{
+    BuildContextOptions, CodeGraphConfig, Node, SearchOptions, SearchResult, Subgraph,
+    TraversalOptions,
+};
+
+/// An indexed project.
+///
+/// ```no_run
+/// use coraline::CodeGraph;
+/// use coraline::types::{SearchOptions, TraversalOptions};
+///
+/// let mut graph = CodeGraph::open("/my/project")?;
+/// graph.sync()?;
+/// let hits = graph.search("parse_config", &SearchOptions::default())?;
+/// if let Some(hit) = hits.first() {
+///     let neighbourhood = graph.subgraph(&[hit.node.id.clone()], &TraversalOptions::default())?;
+///     println!("{} nodes around {}", neighbourhood.nodes.len(), hit.node.name);
+/// }
+/// # Ok::<(), coraline::CoralineError>(())
+/// ```
+#[derive(Debug)]
+pub struct CodeGraph {
+    root: PathBuf,
+    config: CodeGraphConfig,
+    search: SearchConfig,
+    conn: Connection,
+}
+
+impl CodeGraph {
+    /// Open the index of the project at `root`, with its configuration
+    /// (`config.json` overlaid with `config.toml`, as the CLI reads it).
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        if !db::database_path(&root).is_file() {
+            return Err(CoralineError::NotInitialized(root));
+        }
+        let config =
+            config::load_config(&root).map_err(|e| CoralineError::Config(e.to_string()))?;
+        let search = config::load_toml_config(&root)
+            .map_or_else(|_| SearchConfig::default(), |toml| toml.search);
+        let conn = db::open_database(&root)?;
+        Ok(Self {
+            root,
+            config,
+            search,
+            conn,
+        })
+    }
+
+    /// Create the configuration and an empty database for the project at
+    /// `root`, then open it; call [`index`](Self::index) next. Unlike
+    /// `coraline init`, no git hooks are installed.
+    pub fn init(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        let config = config::create_default_config(root);
+        config::save_config(root, &config).map_err(|e| CoralineError::Config(e.to_string()))?;
+        db::initialize_database(root)?;
+        Self::open(root)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub const fn config(&self) -> &CodeGraphConfig {
+        &self.config
+    }
+
+    /// The open database, for the lower-level `db` and `graph` functions.
+    pub const fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Index every file, skipping those whose content is unchanged since
+    /// the last run.
+    pub fn index(&mut self) -> Result<IndexResult> {
+        self.index_with(false)
+    }
+
+    /// Clear the graph and index every file from scratch.
+    pub fn reindex(&mut self) -> Result<IndexResult> {
+        self.index_with(true)
+    }
+
+    fn index_with(&mut self, force: bool) -> Result<IndexResult> {
+        let result = extraction::index_all(&self.root, &self.config, force, None)?;
+        self.reopen()?;
+        Ok(result)
+    }
+
+    /// Re-index the files added, modified, or removed since the last index.
+    pub fn sync(&mut self) -> Result<SyncResult> {
+        let result = extraction::sync(&self.root, &self.config, None)?;
+        self.reopen()?;
+        Ok(result)
+    }
+
+    /// Indexing may swap the database file (per-branch graphs), so the
+    /// connection is reopened after it.
+    fn reopen(&mut self) -> Result<()> {
+        self.conn = db::open_database(&self.root)?;
+        Ok(())
+    }
+
+    /// Ranked full-text search, scored with the project's `[search]`
+    /// settings.
+    pub fn search(&self, query: &str, options: &SearchOptions) -> Result<Vec<SearchResult>> {
+        db::search_nodes_with_options(&self.conn, query, options, &self.search)
+    }
+
+    pub fn node(&self, id: &str) -> Result<Option<Node>> {
+        db::get_node_by_id(&self.conn, id)
+    }
+
+    /// The nodes and edges reachable from `roots` within `options`.
+    pub fn subgraph(&self, roots: &[String], options: &TraversalOptions) -> Result<Subgraph> {
+        for id in roots {
+            if db::get_node_by_id(&self.conn, id)?.is_none() {
+                return Err(CoralineError::NotFound(format!("node '{id}'")));
+            }
+        }
+        graph::build_subgraph(&self.conn, roots, options)
+    }
+
+    /// The context document for `task`, as `coraline context` builds it.
+    pub fn context(&self, task: &str, options: &BuildContextOptions) -> Result<String> {
+        context::build_context(&self.root, task, options)
+    }
+
+    /// File, node, edge, and unresolved reference counts.
+    pub fn stats(&self) -> Result<DbStats> {
+        db::get_db_stats(&self.conn)
+    }
+}
@@ -1,11 +1,15 @@
 #![forbid(unsafe_code)]
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::types::{CodeGraphConfig, FrameworkHint, Language, NodeKind};
+use crate::memory::Namespace;
+use crate::types::{CodeGraphConfig, FrameworkHint, Language, NodeKind, PathCaseSensitivity};
 
 pub const CONFIG_FILENAME: &str = "config.json";
 
@@ -32,22 +36,99 @@ pub fn create_default_config(project_root: &Path) -> CodeGraphConfig {
         track_call_sites: true,
         enable_embeddings: true,
         custom_patterns: None,
+        path_case: PathCaseSensitivity::default(),
     }
 }
 
+/// Environment variables that override the project configuration, so CI
+/// runs can adjust indexing without editing checked-in files. They win over
+/// both `config.json` and `config.toml`.
+///
+/// - `CORALINE_MAX_FILE_SIZE`: the maximum file size in bytes.
+/// - `CORALINE_INCLUDE`, `CORALINE_EXCLUDE`: comma-separated glob patterns
+///   added to the include and exclude patterns.
+pub const ENV_OVERRIDES: [&str; 3] = [
+    "CORALINE_MAX_FILE_SIZE",
+    "CORALINE_INCLUDE",
+    "CORALINE_EXCLUDE",
+];
+
+/// Load `.coraline/config.json`, or the defaults without one, with
+/// `.coraline/config.toml` and the [`ENV_OVERRIDES`] applied.
+///
+/// The TOML is merged by [`apply_toml_to_code_graph`] and left out when it
+/// cannot be parsed; the overrides come last, so they win over both files.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if `config.json` cannot be read or parsed, or an
+/// override has an invalid value.
 pub fn load_config(project_root: &Path) -> std::io::Result<CodeGraphConfig> {
-    let path = config_path(project_root);
-    if !path.exists() {
-        return Ok(create_default_config(project_root));
+    let mut config = read_config_file(project_root)?;
+    if let Ok(toml_cfg) = load_toml_config(project_root) {
+        apply_toml_to_code_graph(&mut config, &toml_cfg);
     }
+    apply_env_overrides(&mut config)?;
+    Ok(config)
+}
 
-    let raw = fs::read_to_string(&path)?;
-    let mut config: CodeGraphConfig = serde_json::from_str(&raw)
-        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+/// `.coraline/config.json` as stored, without the environment overrides.
+fn read_config_file(project_root: &Path) -> std::io::Result<CodeGraphConfig> {
+    let path = config_path(project_root);
+    let mut config = if path.exists() {
+        let raw = fs::read_to_string(&path)?;
+        serde_json::from_str(&raw)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?
+    } else {
+        create_default_config(project_root)
+    };
     config.root_dir = project_root.to_string_lossy().to_string();
     Ok(config)
 }
 
+/// Apply the [`ENV_OVERRIDES`] set in the process environment.
+///
+/// # Errors
+///
+/// Returns an `io::Error` naming the variable when a value is invalid.
+pub fn apply_env_overrides(config: &mut CodeGraphConfig) -> std::io::Result<()> {
+    apply_overrides(config, |name| std::env::var(name).ok())
+}
+
+/// Apply the [`ENV_OVERRIDES`] that `var` returns a value for.
+///
+/// # Errors
+///
+/// Returns an `io::Error` naming the variable when a value is invalid.
+pub fn apply_overrides(
+    config: &mut CodeGraphConfig,
+    var: impl Fn(&str) -> Option<String>,
+) -> std::io::Result<()> {
+    let [max_file_size, include, exclude] = ENV_OVERRIDES;
+    if let Some(raw) = var(max_file_size) {
+        config.max_file_size = raw.trim().parse().map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{max_file_size}={raw:?} is not a size in bytes: {err}"),
+            )
+        })?;
+    }
+    let patterns = |raw: String| -> Vec<String> {
+        raw.split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    if let Some(raw) = var(include) {
+        add_include_patterns(config, &patterns(raw));
+    }
+    if let Some(raw) = var(exclude) {
+        add_exclude_patterns(config, &patterns(raw));
+    }
+    Ok(())
+}
+
 pub fn save_config(project_root: &Path, config: &CodeGraphConfig) -> std::io::Result<()> {
     let path = config_path(project_root);
     if let Some(parent) = path.parent() {
@@ -117,6 +198,7 @@ pub fn default_include_patterns() -> Vec<String> {
         "**/*.rb",
         "**/*.liquid",
         "**/*.razor",
+        "**/*.prisma",
     ]
     .into_iter()
     .map(std::string::ToString::to_string)
@@ -246,6 +328,7 @@ pub const fn is_language_supported(language: &Language) -> bool {
             | Language::Swift
             | Language::Kotlin
             | Language::Liquid
+            | Language::Prisma
             | Language::Markdown
             | Language::Blazor
             | Language::Unknown
@@ -273,6 +356,14 @@ pub struct ContextConfig {
     pub max_code_block_size: usize,
     /// Graph traversal depth from entry nodes.
     pub traversal_depth: usize,
+    /// Append each built context (query, selected nodes, token counts,
+    /// truncations) to `.coraline/contexts/log.jsonl`.
+    pub log_contexts: bool,
+    /// Entry points scoring below this relevance (0.0–1.0) are left out.
+    pub min_score: f32,
+    /// Share of relevance taken from vector similarity when the index has
+    /// embeddings (0.0–1.0); the rest comes from the full-text score.
+    pub semantic_weight: f32,
 }
 
 impl Default for ContextConfig {
@@ -282,6 +373,9 @@ impl Default for ContextConfig {
             max_code_blocks: 5,
             max_code_block_size: 1500,
             traversal_depth: 1,
+            log_contexts: false,
+            min_score: 0.0,
+            semantic_weight: 0.5,
         }
     }
 }
@@ -292,13 +386,39 @@ impl Default for ContextConfig {
 pub struct SyncConfig {
     /// Whether to install / honour git post-commit hooks.
     pub git_hooks_enabled: bool,
-    /// Enable watch mode (re-index on file changes) — not yet implemented.
+    /// Have the MCP server sync on filesystem events, like `coraline
+    /// watch`, instead of polling every `auto_sync_interval_secs`.
     pub watch_mode: bool,
-    /// Debounce delay in milliseconds for watch mode.
+    /// Quiet period in milliseconds after the last file change before a
+    /// watch sync.
     pub debounce_ms: u64,
     /// Interval in seconds for the MCP background auto-sync check.
     /// Set to 0 to disable. Default: 120 (2 minutes).
     pub auto_sync_interval_secs: u64,
+    /// What queries and context builds do when the graph is behind the
+    /// working tree. Default: `warn`.
+    pub on_stale_index: StaleIndexPolicy,
+    /// Shell command run after every sync that changed the graph. The sync
+    /// result is passed as JSON on stdin and in `CORALINE_SYNC_RESULT`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_sync_command: Option<String>,
+    /// URL that receives the sync result as a JSON `POST` after every sync
+    /// that changed the graph.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_sync_webhook: Option<String>,
+    /// Upper bound in seconds for each post-sync hook. Default: 10.
+    pub post_sync_timeout_secs: u64,
+    /// Worker threads for `index` and `sync` (parsing and embedding).
+    /// 0 = one per CPU core. Default: 0.
+    pub max_threads: usize,
+    /// Worker threads for `sync --throttle` (used by the git hook). Default: 1.
+    pub throttle_threads: usize,
+    /// Nice increment applied by `sync --throttle` on Unix; 0 keeps the
+    /// current priority. Default: 10.
+    pub throttle_nice: i32,
+    /// Move `sync --throttle` to the idle IO class (Linux `ionice`).
+    /// Default: true.
+    pub throttle_idle_io: bool,
 }
 
 impl Default for SyncConfig {
@@ -308,20 +428,67 @@ impl Default for SyncConfig {
             watch_mode: false,
             debounce_ms: 500,
             auto_sync_interval_secs: 120,
+            on_stale_index: StaleIndexPolicy::default(),
+            post_sync_command: None,
+            post_sync_webhook: None,
+            post_sync_timeout_secs: 10,
+            max_threads: 0,
+            throttle_threads: 1,
+            throttle_nice: 10,
+            throttle_idle_io: true,
         }
     }
 }
 
+/// Handling of a graph that is behind the working tree when it is queried.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StaleIndexPolicy {
+    /// Query without checking.
+    Off,
+    /// Query, and say how far behind the graph is.
+    #[default]
+    Warn,
+    /// Sync first, then query.
+    Sync,
+    /// Refuse to query until the project is synced.
+    Fail,
+}
+
+/// Backend that turns text into embedding vectors.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProvider {
+    /// Local ONNX model under `model_dir`.
+    #[default]
+    Onnx,
+    /// OpenAI-compatible `POST /embeddings` endpoint.
+    OpenAi,
+    /// Ollama `POST /api/embed` endpoint.
+    Ollama,
+}
+
 /// Vector-embedding settings.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct VectorsConfig {
     /// Enable vector embeddings (requires ONNX model).
     pub enabled: bool,
-    /// Model identifier.
+    /// Embedding backend.
+    pub provider: EmbeddingProvider,
+    /// Model identifier. For HTTP providers this is the model name sent to
+    /// the endpoint.
     pub model: String,
     /// Embedding dimension (must match the model).
     pub dimension: usize,
+    /// Base URL for HTTP providers. Defaults to `https://api.openai.com/v1`
+    /// for `openai` and `http://localhost:11434` for `ollama`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_base: Option<String>,
+    /// Environment variable holding the API key for `openai`
+    /// (default `OPENAI_API_KEY`). The key itself is never stored in config.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
     /// Batch size for embedding generation.
     pub batch_size: usize,
     /// Path to the model directory (containing an ONNX file + tokenizer.json).
@@ -340,8 +507,11 @@ impl Default for VectorsConfig {
     fn default() -> Self {
         Self {
             enabled: false,
+            provider: EmbeddingProvider::Onnx,
             model: "nomic-embed-text-v1.5".to_string(),
             dimension: 768,
+            api_base: None,
+            api_key_env: None,
             batch_size: 32,
             model_dir: None,
             model_file: None,
@@ -440,6 +610,27 @@ pub struct IndexingConfig {
     pub include_patterns: Vec<String>,
     /// Glob patterns to exclude.
     pub exclude_patterns: Vec<String>,
+    /// Path comparison policy for change detection: `auto`, `sensitive` or
+    /// `insensitive`.
+    pub path_case_sensitivity: PathCaseSensitivity,
+    /// Share extraction results between the git worktrees of one repository
+    /// through a cache in the git common directory. Only used when the
+    /// repository has linked worktrees.
+    pub shared_parse_cache: bool,
+    /// Index the files of checked-out git submodules, tagged
+    /// `project:<name>`. Off skips every submodule directory.
+    pub index_submodules: bool,
+    /// Keep a graph per git branch under `.coraline/branches/`, swapped in
+    /// when the checked-out branch changes, instead of rewriting one graph
+    /// on every switch.
+    pub branch_graphs: bool,
+    /// Skip files that likely hold secrets (`.env`, private keys, credential
+    /// stores) when indexing, and keep them out of code blocks and exports.
+    pub exclude_sensitive_files: bool,
+    /// Extra glob patterns to treat as sensitive, on top of the built-in set.
+    pub sensitive_patterns: Vec<String>,
+    /// Glob patterns of files to index even though they look sensitive.
+    pub allow_sensitive_files: Vec<String>,
 }
 
 impl Default for IndexingConfig {
@@ -449,6 +640,235 @@ impl Default for IndexingConfig {
             batch_size: 100,
             include_patterns: default_include_patterns(),
             exclude_patterns: default_exclude_patterns(),
+            path_case_sensitivity: PathCaseSensitivity::default(),
+            shared_parse_cache: true,
+            index_submodules: true,
+            branch_graphs: false,
+            exclude_sensitive_files: true,
+            sensitive_patterns: Vec::new(),
+            allow_sensitive_files: Vec::new(),
+        }
+    }
+}
+
+/// Search ranking settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Identifier tokens too common to discriminate between symbols. Results
+    /// that match a query only through these words are down-weighted.
+    pub noise_words: Vec<String>,
+    /// Score multiplier for results matching only noise words (0.0–1.0).
+    pub noise_weight: f32,
+    /// Score multiplier for results whose name equals the whole query,
+    /// ignoring case and `_` / `-` / whitespace separators.
+    pub exact_match_boost: f32,
+    /// Score multiplier for results in a language the query names
+    /// ("the React component", "the Rust service"). `1.0` disables.
+    pub language_boost: f32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            noise_words: default_noise_words(),
+            noise_weight: 0.25,
+            exact_match_boost: 2.0,
+            language_boost: 1.5,
+        }
+    }
+}
+
+/// Ubiquitous identifier tokens across the supported languages.
+fn default_noise_words() -> Vec<String> {
+    [
+        "get", "set", "new", "init", "__init__", "index", "main", "run", "call", "handle",
+        "handler", "process", "create", "update", "delete", "default", "value", "data", "item",
+        "items", "result", "test", "setup", "self", "this", "from", "into", "to", "is", "has",
+        "len", "size", "build", "execute", "apply", "string", "util", "utils", "helper",
+    ]
+    .iter()
+    .map(ToString::to_string)
+    .collect()
+}
+
+/// `SQLite` `synchronous` level.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SynchronousMode {
+    Off,
+    Normal,
+    Full,
+}
+
+impl SynchronousMode {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "OFF",
+            Self::Normal => "NORMAL",
+            Self::Full => "FULL",
+        }
+    }
+}
+
+/// Shell commands run at points of the indexing lifecycle (see
+/// [`crate::lifecycle`]). Each receives the event as JSON on stdin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifecycleConfig {
+    /// Before the project is scanned for files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_scan: Option<String>,
+    /// After each file's nodes and edges are stored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_file_extract: Option<String>,
+    /// After every changed file is stored, before references are resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_index: Option<String>,
+    /// After references are resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_resolve: Option<String>,
+    /// Upper bound in seconds for each command. Default: 10.
+    pub timeout_secs: u64,
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            pre_scan: None,
+            post_file_extract: None,
+            post_index: None,
+            post_resolve: None,
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// Issue-tracker linkage (`coraline link-issues`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IssuesConfig {
+    /// Regex matching issue ids in commit messages and comments. The first
+    /// capture group is the id when the pattern has one, else the whole
+    /// match. Default: `#\d+` (GitHub / GitLab style).
+    pub pattern: String,
+    /// Most recent commits to scan. Default: 1000.
+    pub max_commits: usize,
+}
+
+impl Default for IssuesConfig {
+    fn default() -> Self {
+        Self {
+            pattern: r"#\d+".to_string(),
+            max_commits: 1000,
+        }
+    }
+}
+
+/// Churn recording from git history (`coraline churn`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Most recent commits to scan. Default: 1000.
+    pub max_commits: usize,
+    /// Also record churn at the end of `coraline index`, and of every sync
+    /// that changed the graph. Default: false.
+    pub on_index: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_commits: 1000,
+            on_index: false,
+        }
+    }
+}
+
+/// Project memories (`.coraline/memories/`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryConfig {
+    /// Namespace memory tools use when a call names none: `shared`,
+    /// `local`, or `agents/<name>`. Default: `shared`.
+    pub default_namespace: Namespace,
+}
+
+/// What `coraline analyze dead-code` treats as used from outside the graph.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisConfig {
+    /// Glob patterns for symbol names (or qualified names) that are entry
+    /// points, e.g. `handle_*` for handlers wired up by a framework. `main`
+    /// and constructors are always entry points.
+    pub entry_points: Vec<String>,
+    /// Glob patterns for files whose exported symbols are public API used
+    /// by other projects, e.g. `src/lib.rs` or `src/index.ts`.
+    pub public_api: Vec<String>,
+}
+
+/// Layering rules checked by `coraline analyze arch`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchitectureConfig {
+    pub rules: Vec<ArchitectureRule>,
+}
+
+/// Which files the files matching `from` may depend on. A dependency is an
+/// import, call, reference, or type edge between two files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchitectureRule {
+    /// Shown with each violation; defaults to a description of the rule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Glob patterns for the files the rule constrains, e.g. `src/ui/**`.
+    pub from: Vec<String>,
+    /// Files they may not depend on.
+    pub forbid: Vec<String>,
+    /// When set, the only files outside `from` they may depend on.
+    pub allow: Vec<String>,
+}
+
+/// MCP tool registry overrides, applied by `tools::create_default_registry`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolsConfig {
+    /// Leave out every write-like tool (memory writes, edits, deletes, and
+    /// `coraline_update_config`). Default: false.
+    pub read_only: bool,
+    /// Tool names to leave out of the registry, e.g. `coraline_delete_memory`.
+    pub disabled: Vec<String>,
+    /// Replacement descriptions for `tools/list`, keyed by tool name.
+    pub descriptions: BTreeMap<String, String>,
+}
+
+/// `SQLite` tuning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    /// Size the page cache and memory map from the database size, and relax
+    /// `synchronous` for indexing. When false, a fixed 64 MB cache, 256 MB
+    /// mmap, and `NORMAL` are used. Default: true.
+    pub auto_tune: bool,
+    /// Page cache size in MB; overrides the tuned value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_size_mb: Option<u64>,
+    /// Memory-mapped I/O size in MB (0 disables mmap); overrides the tuned value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mmap_size_mb: Option<u64>,
+    /// `synchronous` level for every connection; overrides the tuned value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub synchronous: Option<SynchronousMode>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            auto_tune: true,
+            cache_size_mb: None,
+            mmap_size_mb: None,
+            synchronous: None,
         }
     }
 }
@@ -458,12 +878,22 @@ impl Default for IndexingConfig {
 /// Stored at `.coraline/config.toml`.  All sections are optional with
 /// sensible defaults so that an empty file is perfectly valid.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct CoralineConfig {
     pub indexing: IndexingConfig,
     pub context: ContextConfig,
     pub sync: SyncConfig,
     pub vectors: VectorsConfig,
+    pub search: SearchConfig,
     pub security: SecurityConfig,
+    pub database: DatabaseConfig,
+    pub lifecycle: LifecycleConfig,
+    pub issues: IssuesConfig,
+    pub history: HistoryConfig,
+    pub memory: MemoryConfig,
+    pub analysis: AnalysisConfig,
+    pub architecture: ArchitectureConfig,
+    pub tools: ToolsConfig,
 }
 
 impl CoralineConfig {
@@ -515,6 +945,312 @@ pub fn apply_toml_to_code_graph(code_cfg: &mut CodeGraphConfig, toml_cfg: &Coral
             .exclude
             .clone_from(&toml_cfg.indexing.exclude_patterns);
     }
+    if toml_cfg.indexing.path_case_sensitivity != def.path_case_sensitivity {
+        code_cfg.path_case = toml_cfg.indexing.path_case_sensitivity;
+    }
+}
+
+// ── Editing and validation ────────────────────────────────────────────────────
+
+/// `config.toml` keys holding glob patterns.
+const TOML_PATTERN_KEYS: [&str; 6] = [
+    "indexing.include_patterns",
+    "indexing.exclude_patterns",
+    "indexing.sensitive_patterns",
+    "indexing.allow_sensitive_files",
+    "analysis.entry_points",
+    "analysis.public_api",
+];
+
+/// `config.json` keys holding glob patterns.
+const JSON_PATTERN_KEYS: [&str; 2] = ["include", "exclude"];
+
+/// A problem [`validate`] found in the project configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigProblem {
+    /// `config.toml` or `config.json`.
+    pub file: &'static str,
+    /// The offending key, such as `indexing.exclude_patterns[2]`. Empty when
+    /// the file does not parse.
+    pub key: String,
+    pub message: String,
+}
+
+/// Read one configuration value.
+///
+/// Keys that start with a `config.toml` section (`indexing.max_file_size`,
+/// `tools.descriptions.coraline_context`) read `config.toml` with its
+/// defaults filled in; any other key reads a `config.json` field
+/// (`languages`, `include`).
+///
+/// # Errors
+///
+/// Returns an `InvalidInput` error for an unknown key or an option that is
+/// not set, and other errors if the file cannot be read.
+pub fn get_value(project_root: &Path, key: &str) -> std::io::Result<Value> {
+    let (_, values) = stored_values(project_root, key)?;
+    values
+        .pointer(&key_pointer(key))
+        .cloned()
+        .ok_or_else(|| unknown_key(key))
+}
+
+/// Set one configuration value and save the file it lives in.
+///
+/// Keys are resolved as in [`get_value`]. `raw` is parsed as JSON, so
+/// `50`, `true` and `["rust"]` keep their types, and anything else is a
+/// string. Returns the value as stored.
+///
+/// # Errors
+///
+/// Returns an `InvalidInput` error for an unknown key, a value of the wrong
+/// type, or an invalid glob pattern, and other errors if the file cannot be
+/// read or written.
+pub fn set_value(project_root: &Path, key: &str, raw: &str) -> std::io::Result<Value> {
+    let value: Value = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()));
+    let (in_toml, mut values) = stored_values(project_root, key)?;
+    let pointer = key_pointer(key);
+    let (parent, field) = pointer.rsplit_once('/').unwrap_or(("", key));
+    let Some(Value::Object(object)) = values.pointer_mut(parent) else {
+        return Err(unknown_key(key));
+    };
+    object.insert(field.to_string(), value.clone());
+
+    let (file, pattern_keys) = if in_toml {
+        (TOML_CONFIG_FILENAME, TOML_PATTERN_KEYS.as_slice())
+    } else {
+        (CONFIG_FILENAME, JSON_PATTERN_KEYS.as_slice())
+    };
+    let stored = if in_toml {
+        let cfg: CoralineConfig = parse_value(key, values)?;
+        let stored = serde_json::to_value(&cfg).map_err(std::io::Error::other)?;
+        check_set(file, key, &value, &stored, pattern_keys)?;
+        save_toml_config(project_root, &cfg)?;
+        stored
+    } else {
+        let cfg: CodeGraphConfig = parse_value(key, values)?;
+        let stored = serde_json::to_value(&cfg).map_err(std::io::Error::other)?;
+        check_set(file, key, &value, &stored, pattern_keys)?;
+        save_config(project_root, &cfg)?;
+        stored
+    };
+    Ok(stored.pointer(&pointer).cloned().unwrap_or(Value::Null))
+}
+
+/// Check `config.toml` and `config.json` without changing them.
+///
+/// Reports files that do not parse, keys the loader would silently ignore,
+/// glob patterns that do not compile, and language names Coraline does not
+/// know. Missing files use the defaults and are not a problem.
+pub fn validate(project_root: &Path) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    if let Ok(raw) = fs::read_to_string(toml_config_path(project_root)) {
+        match toml::from_str::<Value>(&raw) {
+            Ok(values) => {
+                check_file::<CoralineConfig>(
+                    TOML_CONFIG_FILENAME,
+                    &values,
+                    &TOML_PATTERN_KEYS,
+                    &mut problems,
+                );
+                let rule_keys = architecture_pattern_keys(&values);
+                let rule_keys: Vec<&str> = rule_keys.iter().map(String::as_str).collect();
+                check_patterns(TOML_CONFIG_FILENAME, &values, &rule_keys, &mut problems);
+            }
+            Err(err) => problems.push(ConfigProblem {
+                file: TOML_CONFIG_FILENAME,
+                key: String::new(),
+                message: err.to_string().trim_end().to_string(),
+            }),
+        }
+    }
+    if let Ok(raw) = fs::read_to_string(config_path(project_root)) {
+        match serde_json::from_str::<Value>(&raw) {
+            Ok(values) => {
+                let before = problems.len();
+                check_languages(&values, &mut problems);
+                // A bad language name already fails the whole file.
+                if problems.len() == before {
+                    check_file::<CodeGraphConfig>(
+                        CONFIG_FILENAME,
+                        &values,
+                        &JSON_PATTERN_KEYS,
+                        &mut problems,
+                    );
+                } else {
+                    check_patterns(CONFIG_FILENAME, &values, &JSON_PATTERN_KEYS, &mut problems);
+                }
+            }
+            Err(err) => problems.push(ConfigProblem {
+                file: CONFIG_FILENAME,
+                key: String::new(),
+                message: err.to_string(),
+            }),
+        }
+    }
+    problems
+}
+
+/// Whether `key` addresses `config.toml`, and that file's values.
+fn stored_values(project_root: &Path, key: &str) -> std::io::Result<(bool, Value)> {
+    let section = key.split('.').next().unwrap_or_default();
+    let toml =
+        serde_json::to_value(load_toml_config(project_root)?).map_err(std::io::Error::other)?;
+    if toml.get(section).is_some() {
+        return Ok((true, toml));
+    }
+    let json =
+        serde_json::to_value(read_config_file(project_root)?).map_err(std::io::Error::other)?;
+    // `root_dir` is always the project root, not a setting.
+    if section == "root_dir" || json.get(section).is_none() {
+        return Err(unknown_key(key));
+    }
+    Ok((false, json))
+}
+
+fn key_pointer(key: &str) -> String {
+    format!("/{}", key.replace('.', "/"))
+}
+
+fn unknown_key(key: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Unknown config key `{key}`, or an option that is not set"),
+    )
+}
+
+fn parse_value<T: DeserializeOwned>(key: &str, values: Value) -> std::io::Result<T> {
+    serde_json::from_value(values).map_err(|err| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid value for {key}: {err}"),
+        )
+    })
+}
+
+/// Reject a set that the loader would ignore or that stores a bad pattern.
+fn check_set(
+    file: &'static str,
+    key: &str,
+    value: &Value,
+    stored: &Value,
+    pattern_keys: &[&str],
+) -> std::io::Result<()> {
+    // Clearing an option leaves no key behind.
+    if !value.is_null() && stored.pointer(&key_pointer(key)).is_none() {
+        return Err(unknown_key(key));
+    }
+    let mut problems = Vec::new();
+    check_patterns(file, stored, pattern_keys, &mut problems);
+    match problems.into_iter().find(|p| p.key.starts_with(key)) {
+        Some(problem) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{}: {}", problem.key, problem.message),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn check_file<T: Serialize + DeserializeOwned>(
+    file: &'static str,
+    values: &Value,
+    pattern_keys: &[&str],
+    problems: &mut Vec<ConfigProblem>,
+) {
+    let parsed = match serde_json::from_value::<T>(values.clone()) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            problems.push(ConfigProblem {
+                file,
+                key: String::new(),
+                message: err.to_string(),
+            });
+            return;
+        }
+    };
+    // Keys that do not survive a round trip were not read.
+    if let Ok(stored) = serde_json::to_value(&parsed) {
+        let mut ignored = Vec::new();
+        ignored_keys(values, &stored, "", &mut ignored);
+        problems.extend(ignored.into_iter().map(|key| ConfigProblem {
+            file,
+            key,
+            message: "unknown key, ignored".to_string(),
+        }));
+    }
+    check_patterns(file, values, pattern_keys, problems);
+}
+
+fn ignored_keys(raw: &Value, stored: &Value, prefix: &str, ignored: &mut Vec<String>) {
+    let Value::Object(raw) = raw else {
+        return;
+    };
+    for (name, value) in raw {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{prefix}.{name}")
+        };
+        match stored.get(name) {
+            Some(stored) => ignored_keys(value, stored, &key, ignored),
+            None if !value.is_null() => ignored.push(key),
+            None => {}
+        }
+    }
+}
+
+fn check_patterns(
+    file: &'static str,
+    values: &Value,
+    pattern_keys: &[&str],
+    problems: &mut Vec<ConfigProblem>,
+) {
+    for key in pattern_keys {
+        let Some(Value::Array(patterns)) = values.pointer(&key_pointer(key)) else {
+            continue;
+        };
+        for (i, pattern) in patterns.iter().enumerate() {
+            let message = match pattern.as_str().map(globset::Glob::new) {
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => format!("invalid glob pattern: {}", err.kind()),
+                None => format!("expected a glob pattern string, found {pattern}"),
+            };
+            problems.push(ConfigProblem {
+                file,
+                key: format!("{key}[{i}]"),
+                message,
+            });
+        }
+    }
+}
+
+/// The pattern keys of each `[[architecture.rules]]` entry, such as
+/// `architecture.rules.0.from`.
+fn architecture_pattern_keys(values: &Value) -> Vec<String> {
+    let rules = values
+        .pointer("/architecture/rules")
+        .and_then(Value::as_array)
+        .map_or(0, Vec::len);
+    (0..rules)
+        .flat_map(|i| {
+            ["from", "forbid", "allow"].map(|field| format!("architecture.rules.{i}.{field}"))
+        })
+        .collect()
+}
+
+fn check_languages(values: &Value, problems: &mut Vec<ConfigProblem>) {
+    let Some(Value::Array(languages)) = values.get("languages") else {
+        return;
+    };
+    for (i, language) in languages.iter().enumerate() {
+        if let Err(err) = serde_json::from_value::<Language>(language.clone()) {
+            problems.push(ConfigProblem {
+                file: CONFIG_FILENAME,
+                key: format!("languages[{i}]"),
+                message: err.to_string(),
+            });
+        }
+    }
 }
 
 /// Write a well-commented default `config.toml` template.
@@ -549,18 +1285,47 @@ exclude_patterns = [
   "**/.venv/**", "**/venv/**", "**/*_venv/**", "**/*-venv/**",
   "**/env/**", "**/.env/**", "**/__pycache__/**",
 ]
+# How file paths are compared when detecting renames: "auto" treats paths
+# case-insensitively on macOS/Windows, or force "sensitive" / "insensitive".
+path_case_sensitivity = "auto"
+# With linked git worktrees, share parse results between them through
+# <git-common-dir>/coraline/parse-cache/ so each worktree skips re-parsing.
+shared_parse_cache = true
+# Index checked-out git submodules, tagging their files project:<name>.
+index_submodules = true
+# Keep one graph per git branch in .coraline/branches/ so switching back to a
+# branch restores its graph instead of re-indexing the difference.
+branch_graphs = false
+# Never index files that look like secrets (.env, *.pem, id_rsa,
+# credentials.json, ...). Add patterns, or let specific files through.
+exclude_sensitive_files = true
+sensitive_patterns      = []
+allow_sensitive_files   = []
 
 [context]
 max_nodes          = 20
 max_code_blocks    = 5
 max_code_block_size = 1500
 traversal_depth    = 1
+log_contexts       = false   # append each built context to .coraline/contexts/log.jsonl
+min_score          = 0.0     # drop entry points below this relevance (0-1)
+semantic_weight    = 0.5     # share of relevance from vector similarity, with embeddings
 
 [sync]
 git_hooks_enabled        = true
-watch_mode               = false
-debounce_ms              = 500
+watch_mode               = false  # MCP server syncs on file changes instead of polling
+debounce_ms              = 500    # quiet period before a watch sync
 auto_sync_interval_secs  = 120
+on_stale_index           = "warn" # off | warn | sync | fail, before queries
+post_sync_timeout_secs   = 10
+max_threads              = 0      # 0 = one per CPU core
+# `coraline sync --throttle` (run by the git hook) stays out of the way:
+throttle_threads         = 1
+throttle_nice            = 10     # Unix only; 0 keeps the current priority
+throttle_idle_io         = true   # Linux only (ionice idle class)
+# Run after every sync that changed the graph; receives the result as JSON.
+# post_sync_command = "./scripts/invalidate-cache.sh"
+# post_sync_webhook = "https://ci.example.com/hooks/coraline"
 
 [vectors]
 # Full vector search requires an ONNX model.
@@ -574,14 +1339,28 @@ auto_sync_interval_secs  = 120
 #   model_fp16.onnx      274 MB  — fp16
 #   model.onnx           547 MB  — full f32
 # Coraline auto-selects the best available file; set model_file to override.
+# Alternatively embed through an HTTP endpoint, e.g.
+#   provider = "openai", model = "text-embedding-3-small", dimension = 1536
+#   provider = "ollama", model = "nomic-embed-text",       dimension = 768
 # Then run: coraline embed
 enabled    = false
+provider   = "onnx"   # onnx | openai | ollama
 model      = "nomic-embed-text-v1.5"
 dimension  = 768
 batch_size = 32
 max_seq_len = 512
 # model_dir  = ".coraline/models/nomic-embed-text-v1.5"  # override default path
 # model_file = "model_int8.onnx"                          # pin a specific variant
+# api_base    = "http://localhost:11434"                  # HTTP provider endpoint
+# api_key_env = "OPENAI_API_KEY"                          # env var with the API key
+
+[search]
+# Results that match a query only through noise words are down-weighted;
+# results whose name equals the whole query are boosted.
+# noise_words     = ["get", "set", "new", "init", "index", ...]  # built-in list by default
+noise_weight      = 0.25
+exact_match_boost = 2.0
+language_boost    = 1.5   # for results in a language the query names
 
 [security]
 # MCP guardrails are opt-in by default to preserve current behavior.
@@ -609,4 +1388,61 @@ max_blocked_calls_per_session = 25
 enforce_flow_policy = false
 max_read_then_write_events_per_session = 10
 max_output_chars = 50000
+
+[database]
+# Size the SQLite page cache and memory map from the database size, and use
+# synchronous = OFF while indexing. Set to false for fixed defaults
+# (64 MB cache, 256 MB mmap, synchronous = NORMAL).
+auto_tune = true
+# cache_size_mb = 128
+# mmap_size_mb  = 512                # 0 disables memory-mapped I/O
+# synchronous   = "normal"           # off | normal | full
+
+[lifecycle]
+# Commands run during `index` and `sync`; each gets the event as JSON on stdin
+# and its name in CORALINE_HOOK_EVENT. Failures are logged, never fatal.
+timeout_secs = 10
+# pre_scan          = "./scripts/fetch-coverage.sh"
+# post_file_extract = "./scripts/lint-file.sh"    # once per stored file
+# post_index        = "./scripts/attach-coverage.sh"
+# post_resolve      = "./scripts/notify.sh"
+
+[issues]
+# Issue ids linked to nodes by `coraline link-issues`, from the messages of
+# the commits that last touched them and from their comments.
+pattern = '#\d+'                    # e.g. '[A-Z][A-Z0-9]+-\d+' for Jira keys
+max_commits = 1000
+
+[history]
+# Per-file and per-symbol change counts and last-modified times recorded by
+# `coraline churn` from the latest commits; `coraline analyze hotspots`
+# ranks symbols by churn and fan-in.
+max_commits = 1000
+on_index = false                    # true also records churn after index and sync
+
+[memory]
+# Namespace memory tools use when a call names none: "shared" (committed),
+# "local" (gitignored scratch notes), or "agents/<name>".
+default_namespace = "shared"
+
+[analysis]
+# Symbols `coraline analyze dead-code` never reports. `main` and
+# constructors are always entry points, and so is test code.
+entry_points = []                   # Name globs, e.g. ["handle_*", "*Controller"]
+public_api = []                     # Files whose exports are used elsewhere, e.g. ["src/lib.rs"]
+
+# Layering rules `coraline analyze arch` checks imports, calls, and
+# references against; it exits non-zero on a violation.
+# [[architecture.rules]]
+# name = "ui-not-db"
+# from = ["src/ui/**"]
+# forbid = ["src/db/**"]            # or allow = [...]: the only files they may use
+
+[tools]
+# MCP tools left out of `tools/list` and refused by `tools/call`.
+read_only = false                   # true drops memory writes/edits/deletes and config updates
+# disabled = ["coraline_delete_memory"]
+
+# [tools.descriptions]
+# coraline_context = "Build focused code context for our monorepo"
 "#;
@@ -0,0 +1,298 @@
+#![forbid(unsafe_code)]
+
+//! Reads of environment variables and configuration keys.
+//!
+//! Each variable or key a file reads becomes a `constant` node named after
+//! it, with the signature `env API_KEY` or `config database.url`, and each
+//! read a `references` edge to it from the enclosing function (or the file,
+//! at the top level). `coraline query --kind constant API_KEY` then lists
+//! every file reading `API_KEY`, and `coraline_find_references` on one of
+//! the nodes the code reading it.
+//!
+//! | Language | Environment | Configuration |
+//! |---|---|---|
+//! | Rust | `env::var("X")`, `env::var_os`, `env!`, `option_env!` | `config.get_string("k")`, `settings.get::<T>("k")` |
+//! | JavaScript, TypeScript | `process.env.X`, `process.env["X"]`, `import.meta.env.X`, `Deno.env.get("X")`, `Bun.env.X` | `config.get("k")`, `config.has("k")` |
+//! | Python | `os.environ["X"]`, `os.environ.get("X")`, `os.getenv("X")` | `settings.X` (Django), `app.config["X"]` (Flask) |
+//! | Go | `os.Getenv("X")`, `os.LookupEnv("X")` | `viper.GetString("k")`, `viper.IsSet("k")` |
+//! | Ruby | `ENV["X"]`, `ENV.fetch("X")` | |
+//! | Java, Kotlin | `System.getenv("X")` | `@Value("${k}")` (Spring) |
+//! | C# | `Environment.GetEnvironmentVariable("X")` | `Configuration["Section:Key"]` |
+//! | PHP | `getenv("X")`, `$_ENV["X"]` | |
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::types::{Edge, EdgeKind, Language, Node, NodeKind};
+use crate::utils::node_id_for_symbol;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Source {
+    Env,
+    Config,
+}
+
+impl Source {
+    const fn prefix(self) -> &'static str {
+        match self {
+            Self::Env => "env",
+            Self::Config => "config",
+        }
+    }
+
+    const fn description(self) -> &'static str {
+        match self {
+            Self::Env => "Environment variable",
+            Self::Config => "Configuration key",
+        }
+    }
+}
+
+const RUST: &[Language] = &[Language::Rust];
+const SCRIPTS: &[Language] = &[
+    Language::JavaScript,
+    Language::Jsx,
+    Language::TypeScript,
+    Language::Tsx,
+];
+const PYTHON: &[Language] = &[Language::Python];
+const GO: &[Language] = &[Language::Go];
+const RUBY: &[Language] = &[Language::Ruby];
+const JVM: &[Language] = &[Language::Java, Language::Kotlin];
+const CSHARP: &[Language] = &[Language::CSharp];
+const PHP: &[Language] = &[Language::Php];
+
+/// The languages, pattern, and source of each kind of read; the first
+/// group of a pattern captures the name.
+const READS: &[(&[Language], &str, Source)] = &[
+    (RUST, r#"\benv::var(?:_os)?\(\s*"(\w+)""#, Source::Env),
+    (RUST, r#"\b(?:option_)?env!\(\s*"(\w+)""#, Source::Env),
+    (
+        RUST,
+        r#"\b(?:config|settings|cfg)\.get(?:_string|_int|_float|_bool|_table|_array)?(?:::<[^>]*>)?\(\s*"([\w.\-]+)""#,
+        Source::Config,
+    ),
+    (SCRIPTS, r"\bprocess\.env\.(\w+)", Source::Env),
+    (
+        SCRIPTS,
+        r#"\bprocess\.env\[\s*['"`](\w+)['"`]\s*\]"#,
+        Source::Env,
+    ),
+    (SCRIPTS, r"\bimport\.meta\.env\.(\w+)", Source::Env),
+    (SCRIPTS, r"\bBun\.env\.(\w+)", Source::Env),
+    (
+        SCRIPTS,
+        r#"\bDeno\.env\.get\(\s*['"](\w+)['"]"#,
+        Source::Env,
+    ),
+    (
+        SCRIPTS,
+        r#"\bconfig\.(?:get|has)\(\s*['"]([\w.\-]+)['"]"#,
+        Source::Config,
+    ),
+    (
+        PYTHON,
+        r#"\bos\.environ\[\s*['"](\w+)['"]\s*\]"#,
+        Source::Env,
+    ),
+    (
+        PYTHON,
+        r#"\bos\.(?:environ\.get|getenv)\(\s*['"](\w+)['"]"#,
+        Source::Env,
+    ),
+    (PYTHON, r"\bsettings\.([A-Z][A-Z0-9_]*)\b", Source::Config),
+    (
+        PYTHON,
+        r#"\bapp\.config\[\s*['"]([\w.\-]+)['"]\s*\]"#,
+        Source::Config,
+    ),
+    (GO, r#"\bos\.(?:Getenv|LookupEnv)\(\s*"(\w+)""#, Source::Env),
+    (
+        GO,
+        r#"\bviper\.(?:Get\w*|IsSet)\(\s*"([\w.\-]+)""#,
+        Source::Config,
+    ),
+    (RUBY, r#"\bENV\[\s*['"](\w+)['"]\s*\]"#, Source::Env),
+    (RUBY, r#"\bENV\.fetch\(\s*['"](\w+)['"]"#, Source::Env),
+    (JVM, r#"\bSystem\.getenv\(\s*"(\w+)""#, Source::Env),
+    (
+        JVM,
+        r#"@Value\(\s*"\$\{([\w.\-]+)(?::[^}]*)?\}""#,
+        Source::Config,
+    ),
+    (
+        CSHARP,
+        r#"\bEnvironment\.GetEnvironmentVariable\(\s*"(\w+)""#,
+        Source::Env,
+    ),
+    (
+        CSHARP,
+        r#"\bConfiguration\[\s*"([\w:.\-]+)"\s*\]"#,
+        Source::Config,
+    ),
+    (PHP, r#"\bgetenv\(\s*['"](\w+)['"]"#, Source::Env),
+    (PHP, r#"\$_ENV\[\s*['"](\w+)['"]\s*\]"#, Source::Env),
+];
+
+static PATTERNS: LazyLock<Vec<(&'static [Language], Regex, Source)>> = LazyLock::new(|| {
+    READS
+        .iter()
+        .filter_map(|(languages, pattern, source)| {
+            Regex::new(pattern)
+                .ok()
+                .map(|regex| (*languages, regex, *source))
+        })
+        .collect()
+});
+
+/// The variable and key nodes of a file, with an edge for each read from
+/// the innermost function or method among `nodes` holding it.
+pub(crate) fn extract_config_keys(
+    file_path: &str,
+    source: &str,
+    language: Language,
+    nodes: &[Node],
+    root_id: &str,
+    now_ms: i64,
+) -> (Vec<Node>, Vec<Edge>) {
+    let patterns: Vec<&(&[Language], Regex, Source)> = PATTERNS
+        .iter()
+        .filter(|(languages, _, _)| languages.contains(&language))
+        .collect();
+    if patterns.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+
+    // (source, name) -> the reads of it, in order.
+    let mut reads: BTreeMap<(Source, String), Vec<(i64, i64)>> = BTreeMap::new();
+    for (index, line) in source.lines().enumerate() {
+        let code = line.trim_start();
+        if ["//", "#", "/*", "*"]
+            .iter()
+            .any(|marker| code.starts_with(marker))
+        {
+            continue;
+        }
+        for (_, regex, kind) in &patterns {
+            for caps in regex.captures_iter(line) {
+                let Some(name) = caps.get(1) else { continue };
+                reads
+                    .entry((*kind, name.as_str().to_string()))
+                    .or_default()
+                    .push((
+                        i64::try_from(index + 1).unwrap_or(i64::MAX),
+                        i64::try_from(name.start()).unwrap_or(i64::MAX),
+                    ));
+            }
+        }
+    }
+
+    let mut keys = Vec::new();
+    let mut edges = Vec::new();
+    for ((kind, name), uses) in reads {
+        let Some(&(line, column)) = uses.first() else {
+            continue;
+        };
+        let qualified_name = format!("{file_path}::{}::{name}", kind.prefix());
+        let id = node_id_for_symbol(file_path, "constant", &qualified_name, line, column);
+        edges.push(Edge {
+            source: root_id.to_string(),
+            target: id.clone(),
+            kind: EdgeKind::Contains,
+            metadata: None,
+            line: Some(line),
+            column: Some(column),
+        });
+        let mut seen = HashSet::new();
+        for (line, column) in uses {
+            let reader = nodes
+                .iter()
+                .filter(|n| matches!(n.kind, NodeKind::Function | NodeKind::Method))
+                .filter(|n| n.start_line <= line && line <= n.end_line)
+                .min_by_key(|n| n.end_line - n.start_line)
+                .map_or(root_id, |n| n.id.as_str());
+            if seen.insert((reader, line)) {
+                edges.push(Edge {
+                    source: reader.to_string(),
+                    target: id.clone(),
+                    kind: EdgeKind::References,
+                    metadata: None,
+                    line: Some(line),
+                    column: Some(column),
+                });
+            }
+        }
+        keys.push(Node {
+            id,
+            kind: NodeKind::Constant,
+            name: name.clone(),
+            qualified_name,
+            file_path: file_path.to_string(),
+            language,
+            start_line: line,
+            end_line: line,
+            start_column: column,
+            end_column: column.saturating_add(i64::try_from(name.len()).unwrap_or(i64::MAX)),
+            docstring: Some(format!("{} {name}", kind.description())),
+            signature: Some(format!("{} {name}", kind.prefix())),
+            visibility: None,
+            is_exported: false,
+            is_async: false,
+            is_static: false,
+            is_abstract: false,
+            decorators: None,
+            type_parameters: None,
+            updated_at: now_ms,
+        });
+    }
+    (keys, edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(source: &str, language: Language) -> Vec<String> {
+        extract_config_keys("f", source, language, &[], "root", 0)
+            .0
+            .into_iter()
+            .filter_map(|node| node.signature)
+            .collect()
+    }
+
+    #[test]
+    fn test_reads_by_language() {
+        assert_eq!(
+            names(
+                "let url = std::env::var(\"DATABASE_URL\")?;\nlet v = env!(\"CARGO_PKG_VERSION\");\nlet p = settings.get::<u16>(\"server.port\")?;",
+                Language::Rust
+            ),
+            [
+                "env CARGO_PKG_VERSION",
+                "env DATABASE_URL",
+                "config server.port"
+            ]
+        );
+        assert_eq!(
+            names(
+                "const key = process.env.API_KEY ?? process.env['FALLBACK_KEY'];\n// process.env.IGNORED",
+                Language::TypeScript
+            ),
+            ["env API_KEY", "env FALLBACK_KEY"]
+        );
+        assert_eq!(
+            names(
+                "token = os.environ[\"TOKEN\"]\nhost = os.getenv('HOST', 'localhost')\ndebug = settings.DEBUG",
+                Language::Python
+            ),
+            ["env HOST", "env TOKEN", "config DEBUG"]
+        );
+        assert_eq!(
+            names("addr := os.Getenv(\"ADDR\")", Language::Go),
+            ["env ADDR"]
+        );
+        assert!(names("process.env.API_KEY", Language::Python).is_empty());
+    }
+}
@@ -1,27 +1,98 @@
 #![forbid(unsafe_code)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
 use std::fs;
-use std::path::Path;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::annotations::Annotations;
 use crate::config;
 use crate::db;
+use crate::error::Result;
+use crate::extraction;
 use crate::graph;
+use crate::sensitive::SensitiveFiles;
+use crate::staleness::StalenessCheck;
 use crate::types::{
-    BuildContextOptions, CodeBlock, ContextFormat, ContextStats, EdgeKind, SearchResult, Subgraph,
-    TaskContext, TraversalDirection, TraversalOptions,
+    BuildContextOptions, CodeBlock, ContextFormat, ContextStats, Dependencies, EdgeKind, Node,
+    NodeCoverage, NodeKind, SearchResult, Subgraph, TaskContext, TaskInput, TraversalDirection,
+    TraversalOptions,
 };
+use crate::utils::hash_sha256;
 
 #[derive(Debug, Default)]
 pub struct ContextBuilder;
 
+/// Marker appended to a code block cut at `max_code_block_size`.
+const TRUNCATION_MARKER: &str = "\n// ... truncated ...";
+
 pub fn build_context(
     project_root: &Path,
     task: &str,
     options: &BuildContextOptions,
-) -> std::io::Result<String> {
-    let conn = db::open_database(project_root)?;
+) -> Result<String> {
+    build_task_context(project_root, &TaskInput::Text(task.to_string()), options)
+}
+
+/// [`build_context`] for a [`TaskInput`].
+///
+/// The sub-queries of a detailed task are each searched and traversed on
+/// their own; their entry points are taken in turn up to `max_nodes`,
+/// their subgraphs merged with every node attributed to the sub-queries
+/// that reached it, and their code blocks merged where they overlap.
+pub fn build_task_context(
+    project_root: &Path,
+    task: &TaskInput,
+    options: &BuildContextOptions,
+) -> Result<String> {
     let toml_cfg = config::load_toml_config(project_root).unwrap_or_default();
+    let built = assemble_context(project_root, task, options, &toml_cfg)?;
+    let output = render_context(project_root, &built.context, options);
+
+    if toml_cfg.context.log_contexts {
+        let entry = ContextLogEntry::new(options, &built, &output);
+        if let Err(err) = append_context_log(project_root, &entry) {
+            warn!("Failed to log context: {err}");
+        }
+    }
+
+    Ok(output)
+}
+
+/// A built context plus what was left out of it.
+struct BuiltContext {
+    context: TaskContext,
+    results: Vec<SearchResult>,
+    /// Search hits no code block shows.
+    omitted_blocks: usize,
+}
+
+fn render_context(
+    project_root: &Path,
+    context: &TaskContext,
+    options: &BuildContextOptions,
+) -> String {
+    match options.format.unwrap_or(ContextFormat::Markdown) {
+        ContextFormat::Markdown => format_context_markdown(context),
+        ContextFormat::Json => serde_json::to_string_pretty(context).unwrap_or_default(),
+        ContextFormat::Xml => format_context_xml(context),
+        ContextFormat::RepoMap => format_repo_map(project_root, context),
+    }
+}
+
+fn assemble_context(
+    project_root: &Path,
+    task: &TaskInput,
+    options: &BuildContextOptions,
+    toml_cfg: &config::CoralineConfig,
+) -> Result<BuiltContext> {
+    let conn = db::open_database(project_root)?;
     let ctx_cfg = &toml_cfg.context;
 
     let max_nodes = options.max_nodes.unwrap_or(ctx_cfg.max_nodes);
@@ -30,9 +101,14 @@ pub fn build_context(
         .max_code_block_size
         .unwrap_or(ctx_cfg.max_code_block_size);
     let include_code = options.include_code.unwrap_or(true);
-    let format = options.format.unwrap_or(ContextFormat::Markdown);
 
-    let results = db::search_nodes(&conn, task, None, max_nodes)?;
+    let query = task.query();
+    let queries = match task.sub_queries() {
+        [] => vec![query.clone()],
+        sub_queries => sub_queries.to_vec(),
+    };
+    let ranked = rank_entry_points(project_root, &conn, &queries, options, toml_cfg)?;
+    let results = interleave(&ranked, max_nodes);
     let entry_points: Vec<_> = results.iter().map(|r| r.node.clone()).collect();
     let traversal = TraversalOptions {
         max_depth: options.traversal_depth.or(Some(ctx_cfg.traversal_depth)),
@@ -41,39 +117,54 @@ pub fn build_context(
         direction: Some(TraversalDirection::Both),
         limit: Some(max_nodes.saturating_mul(4)),
         include_start: Some(true),
+        edge_costs: None,
+        incoming_cost_factor: None,
+        min_confidence: None,
     };
 
-    let subgraph = graph::build_subgraph(
-        &conn,
-        &entry_points
-            .iter()
-            .map(|n| n.id.clone())
-            .collect::<Vec<_>>(),
-        &traversal,
-    )
-    .unwrap_or_else(|_| Subgraph {
-        nodes: entry_points
-            .iter()
-            .map(|node| (node.id.clone(), node.clone()))
-            .collect::<HashMap<_, _>>(),
-        edges: Vec::new(),
-        roots: entry_points.iter().map(|n| n.id.clone()).collect(),
-    });
+    let (subgraph, attribution) = if task.sub_queries().is_empty() {
+        (
+            subgraph_of(&conn, &entry_points, &traversal),
+            BTreeMap::new(),
+        )
+    } else {
+        let parts = queries.iter().zip(&ranked).map(|(query, ranked)| {
+            let roots: Vec<Node> = entry_points
+                .iter()
+                .filter(|n| ranked.iter().any(|r| r.node.id == n.id))
+                .cloned()
+                .collect();
+            (query.clone(), subgraph_of(&conn, &roots, &traversal))
+        });
+        union_subgraphs(parts.collect())
+    };
 
-    let code_blocks = if include_code {
-        extract_code_blocks(project_root, &results, max_code_blocks, max_code_block_size)
+    let mut staleness = StalenessCheck::new(project_root, &conn);
+    let (code_blocks, omitted_blocks) = if include_code {
+        let code_blocks = extract_code_blocks(
+            project_root,
+            &results,
+            max_code_blocks,
+            max_code_block_size,
+            &SensitiveFiles::from_config(&toml_cfg.indexing),
+            &mut staleness,
+        );
+        let omitted = omitted_blocks(&results, &code_blocks);
+        (code_blocks, omitted)
     } else {
-        Vec::new()
+        (Vec::new(), 0)
     };
 
     let related_files = subgraph
         .nodes
         .values()
         .map(|node| node.file_path.clone())
-        .collect::<std::collections::HashSet<_>>()
+        .collect::<BTreeSet<_>>()
         .into_iter()
         .collect::<Vec<_>>();
 
+    let notes = EntryPointNotes::collect(&conn, &entry_points, &mut staleness)?;
+
     let summary = format!(
         "Found {} relevant symbols across {} files.",
         entry_points.len(),
@@ -89,62 +180,610 @@ pub fn build_context(
     };
 
     let context = TaskContext {
-        query: task.to_string(),
+        query,
         subgraph,
         entry_points,
         code_blocks,
         related_files,
         summary,
         stats,
+        untested: notes.untested,
+        stale: notes.stale,
+        tags: notes.tags,
+        dependencies: notes.dependencies,
+        imports: notes.imports,
+        todos: notes.todos,
+        sub_queries: task.sub_queries().to_vec(),
+        attribution,
     };
 
-    Ok(match format {
-        ContextFormat::Markdown => format_context_markdown(&context),
-        ContextFormat::Json => serde_json::to_string_pretty(&context).unwrap_or_default(),
+    Ok(BuiltContext {
+        context,
+        results,
+        omitted_blocks,
+    })
+}
+
+/// The ranked entry points of each of `queries`. Files edited since the last
+/// index have spans that no longer line up with their content, so those
+/// among the hits are re-synced and the queries searched again.
+fn rank_entry_points(
+    project_root: &Path,
+    conn: &Connection,
+    queries: &[String],
+    options: &BuildContextOptions,
+    toml_cfg: &config::CoralineConfig,
+) -> Result<Vec<Vec<SearchResult>>> {
+    let ctx_cfg = &toml_cfg.context;
+    let max_nodes = options.max_nodes.unwrap_or(ctx_cfg.max_nodes);
+    let min_score = options.min_score.unwrap_or(ctx_cfg.min_score);
+    let candidates = options
+        .search_limit
+        .unwrap_or_else(|| max_nodes.saturating_mul(CANDIDATE_FACTOR))
+        .max(max_nodes);
+    let find_entry_points = || -> Result<Vec<Vec<SearchResult>>> {
+        queries
+            .iter()
+            .map(|query| {
+                let lexical =
+                    db::search_nodes_with_config(conn, query, None, candidates, &toml_cfg.search)?;
+                let semantic = semantic_matches(project_root, conn, query, candidates);
+                Ok(rank_by_relevance(
+                    lexical,
+                    semantic,
+                    ctx_cfg.semantic_weight,
+                    min_score,
+                    max_nodes,
+                ))
+            })
+            .collect()
+    };
+    let ranked = find_entry_points()?;
+    let stale = stale_files(project_root, conn, &interleave(&ranked, max_nodes));
+    if stale.is_empty() {
+        return Ok(ranked);
+    }
+    debug!(
+        files = stale.len(),
+        "re-syncing stale files before building context"
+    );
+    match resync(project_root, &stale) {
+        Ok(()) => find_entry_points(),
+        Err(err) => {
+            warn!("Re-sync of stale files failed: {err}");
+            Ok(ranked)
+        }
+    }
+}
+
+/// Search hits whose node no code block shows.
+fn omitted_blocks(results: &[SearchResult], code_blocks: &[CodeBlock]) -> usize {
+    results
+        .iter()
+        .filter(|r| {
+            !code_blocks.iter().any(|b| {
+                b.file_path == r.node.file_path
+                    && b.start_line <= r.node.start_line.max(1)
+                    && r.node.end_line <= b.end_line
+            })
+        })
+        .count()
+}
+
+/// What a [`TaskContext`] notes about its entry points, each keyed by id.
+struct EntryPointNotes {
+    untested: Vec<String>,
+    stale: Vec<String>,
+    tags: BTreeMap<String, Vec<String>>,
+    dependencies: BTreeMap<String, Dependencies>,
+    imports: BTreeMap<String, Vec<Node>>,
+    todos: BTreeMap<String, Vec<Node>>,
+}
+
+impl EntryPointNotes {
+    fn collect(
+        conn: &Connection,
+        entry_points: &[Node],
+        staleness: &mut StalenessCheck,
+    ) -> Result<Self> {
+        let entry_ids: Vec<&str> = entry_points.iter().map(|n| n.id.as_str()).collect();
+        let coverage = db::get_coverage(conn, &entry_ids)?;
+        let annotations = Annotations::load(conn)?;
+        let mut notes = Self {
+            untested: Vec::new(),
+            stale: Vec::new(),
+            tags: BTreeMap::new(),
+            dependencies: BTreeMap::new(),
+            imports: BTreeMap::new(),
+            todos: BTreeMap::new(),
+        };
+        for node in entry_points {
+            if coverage
+                .get(&node.id)
+                .is_some_and(NodeCoverage::is_untested)
+            {
+                notes.untested.push(node.id.clone());
+            }
+            if staleness.is_stale(node) {
+                notes.stale.push(node.id.clone());
+            }
+            let tags = annotations.tags_for(node);
+            if !tags.is_empty() {
+                notes.tags.insert(node.id.clone(), tags);
+            }
+            if !notes.imports.contains_key(&node.file_path) {
+                let imports = db::get_nodes_by_file(conn, &node.file_path, Some(NodeKind::Import))?;
+                if !imports.is_empty() {
+                    notes.imports.insert(node.file_path.clone(), imports);
+                }
+            }
+            let file_imports = notes
+                .imports
+                .get(&node.file_path)
+                .map_or(&[][..], Vec::as_slice);
+            let deps = dependencies_of(conn, node, file_imports)?;
+            if !deps.imports.is_empty() || !deps.types.is_empty() {
+                notes.dependencies.insert(node.id.clone(), deps);
+            }
+            let comments = todos_of(conn, node)?;
+            if !comments.is_empty() {
+                notes.todos.insert(node.id.clone(), comments);
+            }
+        }
+        Ok(notes)
+    }
+}
+
+/// The entry points of several ranked lists: the best of each in turn, then
+/// the second best, and so on, each node taken once, up to `limit`.
+fn interleave(ranked: &[Vec<SearchResult>], limit: usize) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = Vec::new();
+    let longest = ranked.iter().map(Vec::len).max().unwrap_or(0);
+    for rank in 0..longest {
+        for result in ranked.iter().filter_map(|list| list.get(rank)) {
+            if results.len() >= limit {
+                return results;
+            }
+            if !results.iter().any(|r| r.node.id == result.node.id) {
+                results.push(result.clone());
+            }
+        }
+    }
+    results
+}
+
+/// The subgraph around `entry_points`, or the entry points alone when the
+/// traversal fails.
+fn subgraph_of(conn: &Connection, entry_points: &[Node], traversal: &TraversalOptions) -> Subgraph {
+    graph::build_subgraph(
+        conn,
+        &entry_points
+            .iter()
+            .map(|n| n.id.clone())
+            .collect::<Vec<_>>(),
+        traversal,
+    )
+    .unwrap_or_else(|_| Subgraph {
+        nodes: entry_points
+            .iter()
+            .map(|node| (node.id.clone(), node.clone()))
+            .collect::<BTreeMap<_, _>>(),
+        edges: Vec::new(),
+        roots: entry_points.iter().map(|n| n.id.clone()).collect(),
     })
 }
 
-fn extract_code_blocks(
+/// The union of the subgraphs of several queries, with the queries whose
+/// subgraph holds each node.
+fn union_subgraphs(parts: Vec<(String, Subgraph)>) -> (Subgraph, BTreeMap<String, Vec<String>>) {
+    let mut union = Subgraph {
+        nodes: BTreeMap::new(),
+        edges: Vec::new(),
+        roots: Vec::new(),
+    };
+    let mut attribution: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (query, subgraph) in parts {
+        for (id, node) in subgraph.nodes {
+            attribution
+                .entry(id.clone())
+                .or_default()
+                .push(query.clone());
+            union.nodes.entry(id).or_insert(node);
+        }
+        union.edges.extend(subgraph.edges);
+        for root in subgraph.roots {
+            if !union.roots.contains(&root) {
+                union.roots.push(root);
+            }
+        }
+    }
+    union
+        .edges
+        .sort_by(|a, b| graph::edge_key(a).cmp(&graph::edge_key(b)));
+    union
+        .edges
+        .dedup_by(|a, b| graph::edge_key(a) == graph::edge_key(b));
+    (union, attribution)
+}
+
+/// Referenced symbols looked at, and types listed, per entry point.
+const MAX_DEPENDENCY_TYPES: usize = 20;
+
+const fn is_type_kind(kind: NodeKind) -> bool {
+    matches!(
+        kind,
+        NodeKind::Class
+            | NodeKind::Struct
+            | NodeKind::Interface
+            | NodeKind::Trait
+            | NodeKind::Protocol
+            | NodeKind::Enum
+            | NodeKind::TypeAlias
+    )
+}
+
+/// The symbols `node` and the symbols it directly contains refer to by
+/// `references` edges, leaving out `node` itself: the types among them, and
+/// the `file_imports` naming any of them.
+fn dependencies_of(conn: &Connection, node: &Node, file_imports: &[Node]) -> Result<Dependencies> {
+    let mut sources = vec![node.id.clone()];
+    for edge in db::get_edges_by_source(conn, &node.id, Some(EdgeKind::Contains), 1000)? {
+        sources.push(edge.target);
+    }
+    let mut referenced_ids: Vec<String> = Vec::new();
+    for source in &sources {
+        for edge in db::get_edges_by_source(
+            conn,
+            source,
+            Some(EdgeKind::References),
+            MAX_DEPENDENCY_TYPES,
+        )? {
+            if edge.target != node.id && !referenced_ids.contains(&edge.target) {
+                referenced_ids.push(edge.target);
+            }
+        }
+    }
+    let referenced = db::get_nodes_by_ids(conn, &referenced_ids)?;
+    let imports = file_imports
+        .iter()
+        .filter(|import| referenced.iter().any(|n| imports_name(import, &n.name)))
+        .cloned()
+        .collect();
+    let mut types: Vec<Node> = referenced
+        .into_iter()
+        .filter(|n| is_type_kind(n.kind))
+        .collect();
+    types.sort_by(|a, b| (&a.name, &a.file_path).cmp(&(&b.name, &b.file_path)));
+    types.truncate(MAX_DEPENDENCY_TYPES);
+
+    Ok(Dependencies { imports, types })
+}
+
+/// Whether `import` brings in `name`: the last segment of its path is
+/// `name`.
+fn imports_name(import: &Node, name: &str) -> bool {
+    import
+        .name
+        .rsplit([':', '.', '/', '\\'])
+        .next()
+        .is_some_and(|last| last == name)
+}
+
+/// The TODO, FIXME, and HACK comments attached to `node`, in line order.
+fn todos_of(conn: &Connection, node: &Node) -> Result<Vec<Node>> {
+    let ids: Vec<String> = db::get_edges_by_source(conn, &node.id, Some(EdgeKind::Contains), 1000)?
+        .into_iter()
+        .map(|edge| edge.target)
+        .collect();
+    let mut todos: Vec<Node> = db::get_nodes_by_ids(conn, &ids)?
+        .into_iter()
+        .filter(|n| n.kind == NodeKind::Todo)
+        .collect();
+    todos.sort_by_key(|n| n.start_line);
+    Ok(todos)
+}
+
+/// `imports` grouped by the module they come from, in first-seen order, as
+/// `a`, `b` from `module`. An import whose signature names no module is
+/// listed by itself.
+fn import_groups(imports: &[Node]) -> Vec<(String, Vec<&str>)> {
+    let mut groups: Vec<(String, Vec<&str>)> = Vec::new();
+    for import in imports {
+        let module = import
+            .signature
+            .as_deref()
+            .map(|s| s.split_once("|export=").map_or(s, |(module, _)| module))
+            .unwrap_or_default()
+            .to_string();
+        match groups.iter_mut().find(|(m, _)| *m == module) {
+            Some((_, names)) => names.push(&import.name),
+            None => groups.push((module, vec![&import.name])),
+        }
+    }
+    groups
+}
+
+/// Search candidates fetched per entry point kept, when `search_limit` is
+/// unset, so ranking has more than the top full-text hits to choose from.
+const CANDIDATE_FACTOR: usize = 3;
+
+/// How much a match of `kind` counts towards relevance. Imports, exports,
+/// and parameters repeat a name defined elsewhere, so a task rarely wants
+/// them as entry points. TODO comments never are one: they are shown with
+/// their symbol instead.
+const fn kind_weight(kind: NodeKind) -> f32 {
+    match kind {
+        NodeKind::Function
+        | NodeKind::Method
+        | NodeKind::Class
+        | NodeKind::Struct
+        | NodeKind::Interface
+        | NodeKind::Trait
+        | NodeKind::Protocol
+        | NodeKind::Enum
+        | NodeKind::TypeAlias
+        | NodeKind::Route
+        | NodeKind::Component
+        | NodeKind::Model => 1.0,
+        NodeKind::File | NodeKind::Module | NodeKind::Namespace => 0.7,
+        NodeKind::Property
+        | NodeKind::Field
+        | NodeKind::Variable
+        | NodeKind::Constant
+        | NodeKind::EnumMember => 0.6,
+        NodeKind::Import | NodeKind::Export | NodeKind::Parameter => 0.3,
+        NodeKind::Todo => 0.0,
+    }
+}
+
+/// Rank full-text and vector matches into the `limit` most relevant entry
+/// points. Full-text scores are scaled so the best is 1; a node's relevance
+/// blends that with its cosine similarity by `semantic_weight` (when there
+/// are vector matches at all) and is multiplied by [`kind_weight`]. Kinds
+/// weighing nothing, and results scoring below `min_score`, are dropped;
+/// `score` holds the relevance.
+fn rank_by_relevance(
+    lexical: Vec<SearchResult>,
+    semantic: Vec<SearchResult>,
+    semantic_weight: f32,
+    min_score: f32,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let is_candidate = |result: &SearchResult| kind_weight(result.node.kind) > 0.0;
+    let lexical: Vec<SearchResult> = lexical.into_iter().filter(is_candidate).collect();
+    let semantic: Vec<SearchResult> = semantic.into_iter().filter(is_candidate).collect();
+    let best = lexical.iter().map(|r| r.score).fold(0.0_f32, f32::max);
+    let semantic_weight = if semantic.is_empty() {
+        0.0
+    } else {
+        semantic_weight.clamp(0.0, 1.0)
+    };
+
+    // Node id → (result, scaled full-text score, similarity), in first-seen
+    // order so equal scores keep the search order.
+    let mut order: Vec<String> = Vec::new();
+    let mut scored: BTreeMap<String, (SearchResult, f32, f32)> = BTreeMap::new();
+    for result in lexical {
+        let text = if best > 0.0 { result.score / best } else { 0.0 };
+        order.push(result.node.id.clone());
+        scored.insert(result.node.id.clone(), (result, text, 0.0));
+    }
+    for result in semantic {
+        let similarity = result.score.max(0.0);
+        if let Some(entry) = scored.get_mut(&result.node.id) {
+            entry.2 = similarity;
+        } else {
+            order.push(result.node.id.clone());
+            scored.insert(result.node.id.clone(), (result, 0.0, similarity));
+        }
+    }
+
+    let mut ranked: Vec<SearchResult> = order
+        .iter()
+        .filter_map(|id| scored.remove(id))
+        .map(|(mut result, text, similarity)| {
+            let blended = text.mul_add(1.0 - semantic_weight, similarity * semantic_weight);
+            result.score = blended * kind_weight(result.node.kind);
+            result
+        })
+        .filter(|result| result.score >= min_score)
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked.truncate(limit);
+    ranked
+}
+
+/// Nodes whose vectors are closest to `task`, when the index has vectors
+/// and an embedding model is available. Best-effort: anything missing
+/// yields no matches, leaving ranking to full-text scores.
+fn semantic_matches(
+    project_root: &Path,
+    conn: &Connection,
+    task: &str,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let has_vectors = conn
+        .query_row("SELECT EXISTS(SELECT 1 FROM vectors)", [], |row| {
+            row.get::<_, bool>(0)
+        })
+        .unwrap_or(false);
+    if !has_vectors {
+        return Vec::new();
+    }
+    let mut vm = match crate::vectors::VectorManager::from_project(project_root) {
+        Ok(vm) => vm,
+        Err(err) => {
+            debug!("no embedding model available; ranking context by full-text score: {err}");
+            return Vec::new();
+        }
+    };
+    match vm
+        .embed_query(task)
+        .and_then(|query| crate::vectors::search_similar(conn, &query, limit, 0.0, None))
+    {
+        Ok(matches) => matches,
+        Err(err) => {
+            warn!("Semantic ranking of context failed: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// Distinct files among `results` whose content no longer matches the
+/// indexed hash.
+fn stale_files(project_root: &Path, conn: &Connection, results: &[SearchResult]) -> Vec<String> {
+    let paths: BTreeSet<&str> = results.iter().map(|r| r.node.file_path.as_str()).collect();
+    paths
+        .into_iter()
+        .filter(|path| !is_fresh(project_root, conn, path))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether `relative_path` still hashes to its indexed content. Unreadable
+/// or untracked files count as fresh: there is nothing to re-sync.
+fn is_fresh(project_root: &Path, conn: &Connection, relative_path: &str) -> bool {
+    let Ok(Some(record)) = db::get_file_record(conn, relative_path) else {
+        return true;
+    };
+    fs::read_to_string(project_root.join(relative_path))
+        .map_or(true, |content| hash_sha256(&content) == record.content_hash)
+}
+
+fn resync(project_root: &Path, paths: &[String]) -> Result<()> {
+    let cfg = config::load_config(project_root)?;
+    extraction::reindex_files(project_root, &cfg, paths).map(|_| ())
+}
+
+/// Code blocks for the `max_blocks` best of `results`. Matches whose
+/// spans nest in or overlap each other share one block.
+pub(crate) fn extract_code_blocks(
     project_root: &Path,
     results: &[SearchResult],
     max_blocks: usize,
     max_block_size: usize,
+    sensitive: &SensitiveFiles,
+    staleness: &mut StalenessCheck<'_>,
 ) -> Vec<CodeBlock> {
-    let mut blocks = Vec::new();
+    let mut files: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut spans: Vec<Span> = Vec::new();
 
-    for result in results.iter().take(max_blocks) {
+    for result in results {
+        if spans.len() >= max_blocks {
+            break;
+        }
         let node = &result.node;
-        let file_path = project_root.join(&node.file_path);
-        let Ok(content) = fs::read_to_string(&file_path) else {
+        // Symbols indexed before a file was recognised as sensitive stay
+        // searchable, but their source is never copied into a context.
+        if sensitive.is_sensitive(&node.file_path) {
             continue;
-        };
+        }
+        if !files.contains_key(&node.file_path) {
+            let Ok(content) = fs::read_to_string(project_root.join(&node.file_path)) else {
+                continue;
+            };
+            files.insert(
+                node.file_path.clone(),
+                content.lines().map(str::to_string).collect(),
+            );
+        }
+        let line_count = files
+            .get(&node.file_path)
+            .map_or(0, |lines| i64::try_from(lines.len()).unwrap_or(i64::MAX));
 
-        let lines: Vec<&str> = content.lines().collect();
-        let start_idx = usize::try_from(node.start_line.saturating_sub(1)).unwrap_or(0);
-        let max_end = i64::try_from(lines.len()).unwrap_or(i64::MAX);
-        let end_idx = usize::try_from(node.end_line.min(max_end)).unwrap_or(lines.len());
-        let slice = lines
-            .get(start_idx..end_idx)
-            .map_or_else(String::new, |slice| slice.join("\n"));
-
-        let truncated = if slice.len() > max_block_size {
-            let prefix = slice.get(..max_block_size).unwrap_or(&slice);
-            format!("{prefix}\n// ... truncated ...")
-        } else {
-            slice
+        // Clamp the span to the file as it is now; a span that starts past
+        // the end has nothing left to show.
+        let start_line = node.start_line.max(1);
+        if start_line > line_count {
+            continue;
+        }
+        let span = Span {
+            node: node.clone(),
+            start_line,
+            end_line: node.end_line.clamp(start_line, line_count),
+            stale: staleness.is_stale(node),
         };
+        merge_span(&mut spans, span);
+    }
 
-        blocks.push(CodeBlock {
-            content: truncated,
-            file_path: node.file_path.clone(),
-            start_line: node.start_line,
-            end_line: node.end_line,
-            language: node.language,
-            node: Some(node.clone()),
-        });
+    spans
+        .into_iter()
+        .map(|span| {
+            let slice = files
+                .get(&span.node.file_path)
+                .and_then(|lines| {
+                    let start_idx = usize::try_from(span.start_line - 1).ok()?;
+                    let end_idx = usize::try_from(span.end_line).ok()?;
+                    lines.get(start_idx..end_idx).map(|slice| slice.join("\n"))
+                })
+                .unwrap_or_default();
+            let truncated = slice.len() > max_block_size;
+            let content = if truncated {
+                let prefix = slice.get(..max_block_size).unwrap_or(&slice);
+                format!("{prefix}{TRUNCATION_MARKER}")
+            } else {
+                slice
+            };
+            CodeBlock {
+                content,
+                file_path: span.node.file_path.clone(),
+                start_line: span.start_line,
+                end_line: span.end_line,
+                language: span.node.language,
+                node: Some(span.node),
+                stale: span.stale,
+                truncated,
+            }
+        })
+        .collect()
+}
+
+/// The lines of a code block before they are read: the span of `node`,
+/// widened to cover the matches merged into it.
+struct Span {
+    node: Node,
+    start_line: i64,
+    end_line: i64,
+    stale: bool,
+}
+
+/// Add `span` to `spans`, merging it with every span of the same file it
+/// nests in or overlaps, so no line is shown twice. The merged block takes
+/// the place of the best-ranked of them.
+fn merge_span(spans: &mut Vec<Span>, mut span: Span) {
+    let mut position = spans.len();
+    while let Some(index) = spans.iter().position(|other| {
+        other.node.file_path == span.node.file_path
+            && other.start_line.max(span.start_line) <= other.end_line.min(span.end_line)
+    }) {
+        let other = spans.remove(index);
+        position = position.min(index);
+        span = merged(other, span);
     }
+    spans.insert(position, span);
+}
 
-    blocks
+/// The union of `kept` and `added`, labelled with the tightest of their
+/// symbols whose lines enclose all of it, or with `kept`'s when neither
+/// does.
+fn merged(kept: Span, added: Span) -> Span {
+    let start_line = kept.start_line.min(added.start_line);
+    let end_line = kept.end_line.max(added.end_line);
+    let encloses = |node: &Node| node.start_line <= start_line && end_line <= node.end_line;
+    let size = |node: &Node| node.end_line - node.start_line;
+    let node = if encloses(&added.node)
+        && (!encloses(&kept.node) || size(&added.node) < size(&kept.node))
+    {
+        added.node
+    } else {
+        kept.node
+    };
+    Span {
+        node,
+        start_line,
+        end_line,
+        stale: kept.stale || added.stale,
+    }
 }
 
 fn format_context_markdown(context: &TaskContext) -> String {
@@ -153,19 +792,55 @@ fn format_context_markdown(context: &TaskContext) -> String {
     lines.push(String::new());
     lines.push(format!("**Query:** {}", context.query));
     lines.push(String::new());
+    if !context.sub_queries.is_empty() {
+        lines.push(format!(
+            "**Sub-queries:** {}",
+            context.sub_queries.join("; ")
+        ));
+        lines.push(String::new());
+    }
 
     if !context.entry_points.is_empty() {
         lines.push("### Entry Points".to_string());
         lines.push(String::new());
         for node in &context.entry_points {
+            let untested = if context.untested.contains(&node.id) {
+                " (untested)"
+            } else {
+                ""
+            };
+            let stale = if context.stale.contains(&node.id) {
+                " (stale)"
+            } else {
+                ""
+            };
+            let tags = context
+                .tags
+                .get(&node.id)
+                .map(|tags| format!(" [{}]", tags.join(", ")))
+                .unwrap_or_default();
+            let queries = context
+                .attribution
+                .get(&node.id)
+                .map(|queries| format!(" (for: {})", queries.join("; ")))
+                .unwrap_or_default();
             lines.push(format!(
-                "- **{}** ({:?}) - {}:{}",
+                "- **{}** ({:?}) - {}:{}{untested}{stale}{tags}{queries}",
                 node.name, node.kind, node.file_path, node.start_line
             ));
+            for todo in context.todos.get(&node.id).into_iter().flatten() {
+                lines.push(format!(
+                    "  - {} (line {})",
+                    todo.signature.as_deref().unwrap_or(&todo.name),
+                    todo.start_line
+                ));
+            }
         }
         lines.push(String::new());
     }
 
+    push_markdown_dependencies(context, &mut lines);
+
     if !context.code_blocks.is_empty() {
         lines.push("### Code".to_string());
         lines.push(String::new());
@@ -174,7 +849,22 @@ fn format_context_markdown(context: &TaskContext) -> String {
                 || block.file_path.clone(),
                 |n| format!("{} ({})", n.name, block.file_path),
             );
-            lines.push(format!("#### {header}"));
+            let untested = block
+                .node
+                .as_ref()
+                .is_some_and(|n| context.untested.contains(&n.id));
+            let header = if untested {
+                format!("{header} (untested)")
+            } else {
+                header
+            };
+            if block.stale {
+                lines.push(format!(
+                    "#### {header} (stale: file changed since indexing)"
+                ));
+            } else {
+                lines.push(format!("#### {header}"));
+            }
             lines.push(String::new());
             lines.push(format!("```{:?}", block.language));
             lines.push(block.content.clone());
@@ -185,3 +875,491 @@ fn format_context_markdown(context: &TaskContext) -> String {
 
     lines.join("\n")
 }
+
+fn format_context_xml(context: &TaskContext) -> String {
+    let mut lines = vec![
+        "<context>".to_string(),
+        format!("<query>{}</query>", xml_escape(&context.query)),
+        format!("<summary>{}</summary>", xml_escape(&context.summary)),
+    ];
+    if !context.sub_queries.is_empty() {
+        lines.push("<sub_queries>".to_string());
+        for query in &context.sub_queries {
+            lines.push(format!("<query>{}</query>", xml_escape(query)));
+        }
+        lines.push("</sub_queries>".to_string());
+    }
+
+    if !context.entry_points.is_empty() {
+        lines.push("<entry_points>".to_string());
+        for node in &context.entry_points {
+            let mut attributes = format!(
+                "kind=\"{}\" file=\"{}\" line=\"{}\"",
+                db::kind_to_string(node.kind),
+                xml_escape(&node.file_path),
+                node.start_line
+            );
+            if context.untested.contains(&node.id) {
+                attributes.push_str(" untested=\"true\"");
+            }
+            if context.stale.contains(&node.id) {
+                attributes.push_str(" stale=\"true\"");
+            }
+            if let Some(tags) = context.tags.get(&node.id) {
+                let _ = write!(attributes, " tags=\"{}\"", xml_escape(&tags.join(", ")));
+            }
+            if let Some(queries) = context.attribution.get(&node.id) {
+                let _ = write!(
+                    attributes,
+                    " queries=\"{}\"",
+                    xml_escape(&queries.join("; "))
+                );
+            }
+            lines.push(format!(
+                "<symbol {attributes}>{}</symbol>",
+                xml_escape(&node.name)
+            ));
+        }
+        lines.push("</entry_points>".to_string());
+    }
+
+    push_xml_dependencies(context, &mut lines);
+
+    if !context.todos.is_empty() {
+        lines.push("<todos>".to_string());
+        for node in &context.entry_points {
+            let Some(todos) = context.todos.get(&node.id) else {
+                continue;
+            };
+            lines.push(format!(
+                "<symbol name=\"{}\" file=\"{}\">",
+                xml_escape(&node.name),
+                xml_escape(&node.file_path)
+            ));
+            for todo in todos {
+                lines.push(format!(
+                    "<todo line=\"{}\">{}</todo>",
+                    todo.start_line,
+                    xml_escape(todo.signature.as_deref().unwrap_or(&todo.name))
+                ));
+            }
+            lines.push("</symbol>".to_string());
+        }
+        lines.push("</todos>".to_string());
+    }
+
+    if !context.code_blocks.is_empty() {
+        lines.push("<documents>".to_string());
+        for (index, block) in context.code_blocks.iter().enumerate() {
+            let stale = if block.stale { " stale=\"true\"" } else { "" };
+            lines.push(format!("<document index=\"{}\"{stale}>", index + 1));
+            lines.push(format!(
+                "<source>{}:{}-{}</source>",
+                xml_escape(&block.file_path),
+                block.start_line,
+                block.end_line
+            ));
+            if let Some(node) = &block.node {
+                lines.push(format!("<symbol>{}</symbol>", xml_escape(&node.name)));
+            }
+            // Code is left unescaped, as prompts quote it; only a literal
+            // closing tag is broken up.
+            lines.push("<document_content>".to_string());
+            lines.push(
+                block
+                    .content
+                    .replace("</document_content>", "<\\/document_content>"),
+            );
+            lines.push("</document_content>".to_string());
+            lines.push("</document>".to_string());
+        }
+        lines.push("</documents>".to_string());
+    }
+
+    lines.push("</context>".to_string());
+    lines.join("\n")
+}
+
+/// The Dependencies section of the Markdown context: the imports of each
+/// entry point's file once, then what each entry point refers to.
+fn push_markdown_dependencies(context: &TaskContext, lines: &mut Vec<String>) {
+    if context.dependencies.is_empty() && context.imports.is_empty() {
+        return;
+    }
+    lines.push("### Dependencies".to_string());
+    lines.push(String::new());
+    let mut files = BTreeSet::new();
+    for node in &context.entry_points {
+        if files.insert(node.file_path.as_str())
+            && let Some(imports) = context.imports.get(&node.file_path)
+        {
+            lines.push(format!(
+                "- Imports of {}: {}",
+                node.file_path,
+                markdown_imports(imports)
+            ));
+        }
+        let Some(deps) = context.dependencies.get(&node.id) else {
+            continue;
+        };
+        lines.push(format!("- **{}** ({})", node.name, node.file_path));
+        if !deps.imports.is_empty() {
+            lines.push(format!("  - Imports: {}", markdown_imports(&deps.imports)));
+        }
+        if !deps.types.is_empty() {
+            let types: Vec<String> = deps
+                .types
+                .iter()
+                .map(|t| format!("`{}` ({}:{})", t.name, t.file_path, t.start_line))
+                .collect();
+            lines.push(format!("  - Types: {}", types.join(", ")));
+        }
+    }
+    lines.push(String::new());
+}
+
+/// `imports` as `` `a`, `b` from `module` `` groups, separated by `; `.
+fn markdown_imports(imports: &[Node]) -> String {
+    import_groups(imports)
+        .into_iter()
+        .map(|(module, names)| {
+            let names = names
+                .iter()
+                .map(|name| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if module.is_empty() {
+                names
+            } else {
+                format!("{names} from `{module}`")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// The `<dependencies>` element of the XML context: a `<file>` with the
+/// imports of each entry point's file, once, and a `<symbol>` with what each
+/// entry point refers to.
+fn push_xml_dependencies(context: &TaskContext, lines: &mut Vec<String>) {
+    if context.dependencies.is_empty() && context.imports.is_empty() {
+        return;
+    }
+    lines.push("<dependencies>".to_string());
+    let mut files = BTreeSet::new();
+    for node in &context.entry_points {
+        if files.insert(node.file_path.as_str())
+            && let Some(imports) = context.imports.get(&node.file_path)
+        {
+            lines.push(format!("<file path=\"{}\">", xml_escape(&node.file_path)));
+            push_xml_imports(imports, lines);
+            lines.push("</file>".to_string());
+        }
+        let Some(deps) = context.dependencies.get(&node.id) else {
+            continue;
+        };
+        lines.push(format!(
+            "<symbol name=\"{}\" file=\"{}\">",
+            xml_escape(&node.name),
+            xml_escape(&node.file_path)
+        ));
+        push_xml_imports(&deps.imports, lines);
+        for t in &deps.types {
+            lines.push(format!(
+                "<type kind=\"{}\" file=\"{}\" line=\"{}\">{}</type>",
+                db::kind_to_string(t.kind),
+                xml_escape(&t.file_path),
+                t.start_line,
+                xml_escape(&t.name)
+            ));
+        }
+        lines.push("</symbol>".to_string());
+    }
+    lines.push("</dependencies>".to_string());
+}
+
+/// An `<import>` element per module of `imports`.
+fn push_xml_imports(imports: &[Node], lines: &mut Vec<String>) {
+    for (module, names) in import_groups(imports) {
+        lines.push(format!(
+            "<import module=\"{}\">{}</import>",
+            xml_escape(&module),
+            xml_escape(&names.join(", "))
+        ));
+    }
+}
+
+fn xml_escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Kinds left out of a repo map: they repeat names defined elsewhere or
+/// stand for the file itself.
+const REPO_MAP_SKIPPED: [NodeKind; 4] = [
+    NodeKind::File,
+    NodeKind::Import,
+    NodeKind::Export,
+    NodeKind::Parameter,
+];
+
+/// Every file of the context's subgraph with its symbols in line order,
+/// nested under the symbols whose span contains them (leaving out their
+/// local variables). Each is shown by its
+/// signature, else its first source line (never for sensitive files), else
+/// its kind and name. Entry points are marked `*`.
+fn format_repo_map(project_root: &Path, context: &TaskContext) -> String {
+    let mut by_file: BTreeMap<&str, Vec<&crate::types::Node>> = BTreeMap::new();
+    for node in context.subgraph.nodes.values() {
+        if !REPO_MAP_SKIPPED.contains(&node.kind) {
+            by_file.entry(&node.file_path).or_default().push(node);
+        }
+    }
+    let entry_ids: BTreeSet<&str> = context.entry_points.iter().map(|n| n.id.as_str()).collect();
+    let sensitive = SensitiveFiles::for_project(project_root);
+
+    let mut lines = Vec::new();
+    for (file, mut nodes) in by_file {
+        nodes.sort_by_key(|n| (n.start_line, std::cmp::Reverse(n.end_line)));
+        let source = if sensitive.is_sensitive(file) {
+            String::new()
+        } else {
+            fs::read_to_string(project_root.join(file)).unwrap_or_default()
+        };
+        let source_lines: Vec<&str> = source.lines().collect();
+        lines.push(format!("{file}:"));
+        // End lines of the enclosing symbols of the current node.
+        let mut open: Vec<i64> = Vec::new();
+        for node in nodes {
+            while open.last().is_some_and(|&end| end < node.start_line) {
+                open.pop();
+            }
+            // Locals of a function body are not part of its outline.
+            if node.kind == NodeKind::Variable && !open.is_empty() {
+                continue;
+            }
+            let marker = if entry_ids.contains(node.id.as_str()) {
+                '*'
+            } else {
+                ' '
+            };
+            let first_line = usize::try_from(node.start_line - 1)
+                .ok()
+                .and_then(|i| source_lines.get(i));
+            let label = node
+                .signature
+                .as_deref()
+                .or_else(|| first_line.copied())
+                .map(|s| {
+                    s.lines()
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .trim_end_matches('{')
+                        .trim_end()
+                })
+                .filter(|s| !s.is_empty())
+                .map_or_else(
+                    || format!("{} {}", db::kind_to_string(node.kind), node.name),
+                    str::to_string,
+                );
+            lines.push(format!("{marker}│{}{label}", "  ".repeat(open.len() + 1)));
+            open.push(node.end_line);
+        }
+    }
+    lines.join("\n")
+}
+
+// ── Context log ───────────────────────────────────────────────────────────────
+
+/// Rough token count for `text` (about four characters per token).
+pub const fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// `.coraline/contexts/log.jsonl`, appended to when `[context] log_contexts`
+/// is enabled.
+pub fn context_log_path(project_root: &Path) -> PathBuf {
+    project_root
+        .join(".coraline")
+        .join("contexts")
+        .join("log.jsonl")
+}
+
+/// One line of the context log: what a built context contained.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextLogEntry {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: i64,
+    pub query: String,
+    /// Sub-queries searched in place of `query`, when the task had any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sub_queries: Vec<String>,
+    /// Options the context was built with, so it can be replayed.
+    pub options: BuildContextOptions,
+    pub entry_points: Vec<LoggedNode>,
+    pub code_blocks: Vec<LoggedCodeBlock>,
+    pub stats: ContextStats,
+    /// Estimated tokens in the rendered context.
+    pub tokens: usize,
+    /// Estimated tokens spent on code blocks.
+    pub code_tokens: usize,
+    /// Code blocks cut at `max_code_block_size`.
+    pub truncated_blocks: usize,
+    /// Entry points no code block shows.
+    pub omitted_blocks: usize,
+}
+
+/// A selected symbol, as recorded in the context log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggedNode {
+    pub id: String,
+    pub qualified_name: String,
+    pub kind: NodeKind,
+    pub file_path: String,
+    pub score: f32,
+}
+
+/// A code block, as recorded in the context log (without its content).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedCodeBlock {
+    pub file_path: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub node_id: Option<String>,
+    pub chars: usize,
+    pub truncated: bool,
+    pub stale: bool,
+}
+
+impl ContextLogEntry {
+    fn new(options: &BuildContextOptions, built: &BuiltContext, output: &str) -> Self {
+        let context = &built.context;
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX)),
+            query: context.query.clone(),
+            sub_queries: context.sub_queries.clone(),
+            options: options.clone(),
+            entry_points: built
+                .results
+                .iter()
+                .map(|r| LoggedNode {
+                    id: r.node.id.clone(),
+                    qualified_name: r.node.qualified_name.clone(),
+                    kind: r.node.kind,
+                    file_path: r.node.file_path.clone(),
+                    score: r.score,
+                })
+                .collect(),
+            code_blocks: context
+                .code_blocks
+                .iter()
+                .map(|b| LoggedCodeBlock {
+                    file_path: b.file_path.clone(),
+                    start_line: b.start_line,
+                    end_line: b.end_line,
+                    node_id: b.node.as_ref().map(|n| n.id.clone()),
+                    chars: b.content.len(),
+                    truncated: b.truncated,
+                    stale: b.stale,
+                })
+                .collect(),
+            stats: context.stats.clone(),
+            tokens: estimate_tokens(output),
+            code_tokens: context
+                .code_blocks
+                .iter()
+                .map(|b| estimate_tokens(&b.content))
+                .sum(),
+            truncated_blocks: context.code_blocks.iter().filter(|b| b.truncated).count(),
+            omitted_blocks: built.omitted_blocks,
+        }
+    }
+}
+
+fn append_context_log(project_root: &Path, entry: &ContextLogEntry) -> Result<()> {
+    let path = context_log_path(project_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// All entries of the context log, oldest first. A missing log is empty;
+/// lines that do not parse are skipped.
+pub fn read_context_log(project_root: &Path) -> Result<Vec<ContextLogEntry>> {
+    let file = match fs::File::open(context_log_path(project_root)) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut entries = Vec::new();
+    for line in io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => warn!("Skipping unreadable context log line: {err}"),
+        }
+    }
+    Ok(entries)
+}
+
+/// How a logged context compares with the same query built against the
+/// current index.
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextReplay {
+    /// The context as built now (not appended to the log).
+    pub current: ContextLogEntry,
+    /// Entry points selected now but not in the logged context.
+    pub added: Vec<LoggedNode>,
+    /// Entry points in the logged context that are no longer selected.
+    pub removed: Vec<LoggedNode>,
+    /// Entry points selected both times.
+    pub kept: usize,
+}
+
+/// Rebuild `entry`'s query with its logged options and compare the
+/// selected entry points.
+pub fn replay_context(project_root: &Path, entry: &ContextLogEntry) -> Result<ContextReplay> {
+    let toml_cfg = config::load_toml_config(project_root).unwrap_or_default();
+    let task = TaskInput::with_queries(entry.query.clone(), entry.sub_queries.clone());
+    let built = assemble_context(project_root, &task, &entry.options, &toml_cfg)?;
+    let output = render_context(project_root, &built.context, &entry.options);
+    let current = ContextLogEntry::new(&entry.options, &built, &output);
+
+    let before: BTreeSet<&str> = entry.entry_points.iter().map(|n| n.id.as_str()).collect();
+    let after: BTreeSet<&str> = current.entry_points.iter().map(|n| n.id.as_str()).collect();
+    let added = current
+        .entry_points
+        .iter()
+        .filter(|n| !before.contains(n.id.as_str()))
+        .cloned()
+        .collect();
+    let removed = entry
+        .entry_points
+        .iter()
+        .filter(|n| !after.contains(n.id.as_str()))
+        .cloned()
+        .collect();
+
+    Ok(ContextReplay {
+        kept: before.intersection(&after).count(),
+        current,
+        added,
+        removed,
+    })
+}
@@ -0,0 +1,369 @@
+#![forbid(unsafe_code)]
+
+//! Coverage ingestion.
+//!
+//! Maps an LCOV (`lcov.info`) or Cobertura (`coverage.xml`) report onto the
+//! indexed graph: every function and method gets the number of its
+//! instrumented lines that ran, stored in the `node_coverage` table. Impact
+//! analysis and built contexts read it back to flag symbols no test exercises.
+//!
+//! Each ingest replaces the previous one, so re-run it after the tests when
+//! the coverage changes. Coverage is tied to line numbers, so sync the index
+//! before ingesting a report taken from newer sources.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::db;
+use crate::types::{NodeCoverage, NodeKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageFormat {
+    Lcov,
+    Cobertura,
+}
+
+impl CoverageFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "lcov" => Some(Self::Lcov),
+            "cobertura" | "xml" => Some(Self::Cobertura),
+            _ => None,
+        }
+    }
+
+    /// Cobertura reports are XML; anything else is read as LCOV.
+    pub fn detect(report: &str) -> Self {
+        if report.trim_start().starts_with('<') {
+            Self::Cobertura
+        } else {
+            Self::Lcov
+        }
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lcov => "lcov",
+            Self::Cobertura => "cobertura",
+        }
+    }
+}
+
+/// Line hits for one source file of a report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileCoverage {
+    /// The path as written in the report.
+    pub path: String,
+    /// Hit count per instrumented line.
+    pub lines: BTreeMap<i64, u64>,
+}
+
+/// A parsed report: per-file line hits, plus the Cobertura `<source>` roots
+/// its file names are relative to.
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    pub files: Vec<FileCoverage>,
+    pub sources: Vec<String>,
+}
+
+/// Outcome of [`ingest_coverage`].
+#[derive(Debug, Clone, Serialize)]
+pub struct IngestSummary {
+    pub format: CoverageFormat,
+    pub files_in_report: usize,
+    pub files_matched: usize,
+    /// Report paths that match no indexed file.
+    pub unmatched_files: Vec<String>,
+    /// Functions and methods with at least one instrumented line.
+    pub nodes_measured: usize,
+    /// Measured nodes none of whose lines ran.
+    pub nodes_untested: usize,
+    pub lines_covered: usize,
+    pub lines_total: usize,
+}
+
+/// Parse an LCOV tracefile (`SF:`, `DA:<line>,<hits>`, `end_of_record`).
+pub fn parse_lcov(report: &str) -> CoverageReport {
+    let mut files: Vec<FileCoverage> = Vec::new();
+    let mut current: Option<FileCoverage> = None;
+    for line in report.lines().map(str::trim) {
+        if let Some(path) = line.strip_prefix("SF:") {
+            if let Some(done) = current.take() {
+                files.push(done);
+            }
+            current = Some(FileCoverage {
+                path: path.to_string(),
+                lines: BTreeMap::new(),
+            });
+        } else if let Some(data) = line.strip_prefix("DA:") {
+            let Some(file) = current.as_mut() else {
+                continue;
+            };
+            let mut fields = data.split(',');
+            let (Some(Ok(number)), Some(Ok(hits))) = (
+                fields.next().map(|f| f.trim().parse::<i64>()),
+                fields.next().map(|f| f.trim().parse::<u64>()),
+            ) else {
+                continue;
+            };
+            record_hits(&mut file.lines, number, hits);
+        } else if line == "end_of_record"
+            && let Some(done) = current.take()
+        {
+            files.push(done);
+        }
+    }
+    if let Some(done) = current {
+        files.push(done);
+    }
+    CoverageReport {
+        files: merge_files(files),
+        sources: Vec::new(),
+    }
+}
+
+/// Parse a Cobertura XML report (`<source>`, `<class filename>`, `<line>`).
+pub fn parse_cobertura(report: &str) -> CoverageReport {
+    let (Ok(source_re), Ok(tag_re), Ok(attr_re)) = (
+        Regex::new(r"<source>\s*([^<]*?)\s*</source>"),
+        Regex::new(r"<(/?)(class|line)\b([^>]*)>"),
+        Regex::new(r#"([\w-]+)\s*=\s*"([^"]*)""#),
+    ) else {
+        return CoverageReport::default();
+    };
+
+    let sources = source_re
+        .captures_iter(report)
+        .map(|c| decode_entities(&c[1]))
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut files: Vec<FileCoverage> = Vec::new();
+    let mut current: Option<FileCoverage> = None;
+    for tag in tag_re.captures_iter(report) {
+        let closing = &tag[1] == "/";
+        match (&tag[2], closing) {
+            ("class", true) => {
+                if let Some(done) = current.take() {
+                    files.push(done);
+                }
+            }
+            ("class", false) => {
+                if let Some(done) = current.take() {
+                    files.push(done);
+                }
+                let filename = attr_re
+                    .captures_iter(&tag[3])
+                    .find(|a| &a[1] == "filename")
+                    .map(|a| decode_entities(&a[2]));
+                current = filename.map(|path| FileCoverage {
+                    path,
+                    lines: BTreeMap::new(),
+                });
+            }
+            ("line", false) => {
+                let Some(file) = current.as_mut() else {
+                    continue;
+                };
+                let mut number = None;
+                let mut hits = None;
+                for attr in attr_re.captures_iter(&tag[3]) {
+                    match &attr[1] {
+                        "number" => number = attr[2].parse::<i64>().ok(),
+                        "hits" => hits = attr[2].parse::<u64>().ok(),
+                        _ => {}
+                    }
+                }
+                if let (Some(number), Some(hits)) = (number, hits) {
+                    record_hits(&mut file.lines, number, hits);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(done) = current {
+        files.push(done);
+    }
+    CoverageReport {
+        files: merge_files(files),
+        sources,
+    }
+}
+
+/// Parse `report_path` and store per-node coverage for the project, replacing
+/// any earlier report. `format` is detected from the contents when `None`.
+pub fn ingest_coverage(
+    project_root: &Path,
+    report_path: &Path,
+    format: Option<CoverageFormat>,
+) -> std::io::Result<IngestSummary> {
+    let contents = fs::read_to_string(report_path)?;
+    let format = format.unwrap_or_else(|| CoverageFormat::detect(&contents));
+    let report = match format {
+        CoverageFormat::Lcov => parse_lcov(&contents),
+        CoverageFormat::Cobertura => parse_cobertura(&contents),
+    };
+
+    let mut conn = db::open_database(project_root)?;
+    let indexed: HashSet<String> = db::list_files(&conn)?.into_iter().map(|f| f.path).collect();
+
+    let mut coverage: Vec<(String, NodeCoverage)> = Vec::new();
+    let mut unmatched_files = Vec::new();
+    let mut files_matched = 0;
+    let mut nodes_untested = 0;
+    let mut lines_covered = 0;
+    let mut lines_total = 0;
+    for file in &report.files {
+        let Some(path) = match_indexed_path(project_root, &file.path, &report.sources, &indexed)
+        else {
+            unmatched_files.push(file.path.clone());
+            continue;
+        };
+        files_matched += 1;
+        lines_total += file.lines.len();
+        lines_covered += file.lines.values().filter(|&&hits| hits > 0).count();
+
+        for node in db::get_nodes_by_file(&conn, &path, None)? {
+            if !matches!(node.kind, NodeKind::Function | NodeKind::Method) {
+                continue;
+            }
+            let in_range = file.lines.range(node.start_line..=node.end_line);
+            let (covered, total) = in_range.fold((0_i64, 0_i64), |(covered, total), (_, &hits)| {
+                (covered + i64::from(hits > 0), total + 1)
+            });
+            if total == 0 {
+                continue;
+            }
+            let cov = NodeCoverage {
+                covered_lines: covered,
+                total_lines: total,
+            };
+            if cov.is_untested() {
+                nodes_untested += 1;
+            }
+            coverage.push((node.id, cov));
+        }
+    }
+
+    db::replace_coverage(&mut conn, &coverage, now_millis())?;
+
+    Ok(IngestSummary {
+        format,
+        files_in_report: report.files.len(),
+        files_matched,
+        unmatched_files,
+        nodes_measured: coverage.len(),
+        nodes_untested,
+        lines_covered,
+        lines_total,
+    })
+}
+
+fn record_hits(lines: &mut BTreeMap<i64, u64>, number: i64, hits: u64) {
+    let entry = lines.entry(number).or_insert(0);
+    *entry = (*entry).max(hits);
+}
+
+/// Merge entries for the same path (Cobertura lists a class per type, LCOV
+/// may repeat a file across test runs).
+fn merge_files(files: Vec<FileCoverage>) -> Vec<FileCoverage> {
+    let mut merged: Vec<FileCoverage> = Vec::new();
+    for file in files {
+        if let Some(existing) = merged.iter_mut().find(|f| f.path == file.path) {
+            for (number, hits) in file.lines {
+                record_hits(&mut existing.lines, number, hits);
+            }
+        } else {
+            merged.push(file);
+        }
+    }
+    merged
+}
+
+/// Resolve a report path to an indexed file path: exactly (after making it
+/// project-relative), under one of the report's source roots, or as the only
+/// indexed file it is a suffix of.
+fn match_indexed_path(
+    project_root: &Path,
+    report_path: &str,
+    sources: &[String],
+    indexed: &HashSet<String>,
+) -> Option<String> {
+    let mut candidates = vec![relative_to_root(project_root, report_path)];
+    for source in sources {
+        let source = source.replace('\\', "/");
+        let joined = format!("{}/{}", source.trim_end_matches('/'), report_path);
+        candidates.push(relative_to_root(project_root, &joined));
+    }
+
+    if let Some(exact) = candidates.iter().find(|c| indexed.contains(c.as_str())) {
+        return Some(exact.clone());
+    }
+
+    let relative = candidates.first()?;
+    let suffix = format!("/{relative}");
+    let mut matches = indexed
+        .iter()
+        .filter(|p| p.ends_with(&suffix) || relative.ends_with(&format!("/{p}")));
+    let first = matches.next()?;
+    matches.next().is_none().then(|| first.clone())
+}
+
+fn relative_to_root(project_root: &Path, path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let root = project_root.to_string_lossy().replace('\\', "/");
+    let canonical = project_root
+        .canonicalize()
+        .map_or_else(|_| root.clone(), |p| p.to_string_lossy().replace('\\', "/"));
+    let stripped = [root.as_str(), canonical.as_str()]
+        .into_iter()
+        .find_map(|r| path.strip_prefix(r.trim_end_matches('/')))
+        .and_then(|rest| rest.strip_prefix('/'))
+        .unwrap_or(&path);
+    stripped.trim_start_matches("./").to_string()
+}
+
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lcov_merges_repeated_files_by_max_hits() {
+        let report = parse_lcov(
+            "TN:\nSF:src/a.ts\nDA:1,0\nDA:2,3\nend_of_record\nSF:src/a.ts\nDA:1,2\nend_of_record\n",
+        );
+        assert_eq!(report.files.len(), 1);
+        let lines = report.files.first().map(|file| &file.lines);
+        assert_eq!(lines.and_then(|lines| lines.get(&1)), Some(&2));
+        assert_eq!(lines.and_then(|lines| lines.get(&2)), Some(&3));
+    }
+
+    #[test]
+    fn relative_paths_strip_root_and_dot_prefix() {
+        let root = Path::new("/work/project");
+        assert_eq!(relative_to_root(root, "/work/project/src/a.ts"), "src/a.ts");
+        assert_eq!(relative_to_root(root, "./src/a.ts"), "src/a.ts");
+        assert_eq!(relative_to_root(root, "src\\a.ts"), "src/a.ts");
+    }
+}
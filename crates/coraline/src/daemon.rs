@@ -0,0 +1,412 @@
+#![forbid(unsafe_code)]
+
+//! `coraline daemon`: one long-lived process per project.
+//!
+//! It serves queries, syncs, context builds, and MCP tool calls over a
+//! local socket, so each CLI command or MCP call skips process startup,
+//! config loading, and grammar setup.
+//!
+//! The socket is `.coraline/daemon.sock` (Unix only; elsewhere everything
+//! runs in-process). Each line a client writes is one JSON [`Request`];
+//! each line the daemon answers is the JSON of a `Result<Value, ToolError>`
+//! (`{"Ok": …}` or `{"Err": {"code", "message"}}`). [`connect`] checks the
+//! daemon's version first, so clients only delegate to a daemon built from
+//! the same release, and `CORALINE_NO_DAEMON` turns delegation off.
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use crate::annotations::Annotations;
+use crate::error::CoralineError;
+use crate::tools::{ToolError, ToolRegistry, ToolResult, create_default_registry};
+use crate::types::{BuildContextOptions, SearchOptions, SearchResult, TaskInput};
+use crate::{config, context, db, extraction, watch};
+
+/// Set to any value to keep the CLI and MCP server from delegating.
+pub const NO_DAEMON_ENV: &str = "CORALINE_NO_DAEMON";
+
+/// The daemon's socket inside `.coraline/`.
+pub fn socket_path(project_root: &Path) -> PathBuf {
+    project_root.join(".coraline").join("daemon.sock")
+}
+
+/// One request to the daemon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Request {
+    /// Answered with [`DaemonInfo`].
+    Ping,
+    /// An MCP tool call, answered with the tool's result.
+    Tool {
+        name: String,
+        #[serde(default)]
+        arguments: Value,
+    },
+    /// `coraline query`, answered with [`QueryHit`]s.
+    Query {
+        search: String,
+        options: SearchOptions,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    /// `coraline context`, answered with the rendered context.
+    Context {
+        task: String,
+        /// Sub-queries searched in place of `task` (`coraline context -q`).
+        #[serde(default)]
+        queries: Vec<String>,
+        options: BuildContextOptions,
+    },
+    /// An incremental sync, answered with its `SyncResult`.
+    Sync,
+    /// Stop the daemon once this request is answered.
+    Shutdown,
+}
+
+/// What a daemon reports about itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonInfo {
+    pub version: String,
+    pub pid: u32,
+    pub project_root: PathBuf,
+    /// Unix seconds.
+    pub started_at: i64,
+}
+
+/// A search hit with its node's annotation tags, as `coraline query`
+/// prints it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryHit {
+    pub result: SearchResult,
+    pub tags: Vec<String>,
+}
+
+/// Search the graph scoped by `options` and keep the hits carrying every
+/// tag in `tags`.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be read.
+pub fn query(
+    project_root: &Path,
+    search: &str,
+    options: &SearchOptions,
+    tags: &[String],
+) -> std::io::Result<Vec<QueryHit>> {
+    let conn = db::open_database(project_root)?;
+    let search_cfg = config::load_toml_config(project_root)
+        .unwrap_or_default()
+        .search;
+    let annotations = Annotations::load(&conn)?;
+    let results = if tags.is_empty() {
+        db::search_nodes_with_options(&conn, search, options, &search_cfg)?
+    } else {
+        // Fetch extra results when tag-filtering so we still hit the limit,
+        // and page after filtering.
+        let limit = options.limit.unwrap_or(10);
+        let offset = options.offset.unwrap_or(0);
+        let fetch = SearchOptions {
+            limit: Some(limit.saturating_add(offset).saturating_mul(5)),
+            offset: None,
+            ..options.clone()
+        };
+        let mut results = db::search_nodes_with_options(&conn, search, &fetch, &search_cfg)?;
+        results.retain(|r| annotations.has_tags(&r.node, tags));
+        results.into_iter().skip(offset).take(limit).collect()
+    };
+    Ok(results
+        .into_iter()
+        .map(|result| QueryHit {
+            tags: annotations.tags_for(&result.node),
+            result,
+        })
+        .collect())
+}
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+/// A connection to a running daemon.
+pub struct Client {
+    #[cfg(unix)]
+    reader: BufReader<UnixStream>,
+    #[cfg(not(unix))]
+    never: std::convert::Infallible,
+}
+
+impl Client {
+    /// Send `request` and wait for its answer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the daemon's error, or an `internal_error` if the connection
+    /// fails or the answer does not parse.
+    pub fn call(&mut self, request: &Request) -> ToolResult {
+        #[cfg(unix)]
+        {
+            let transport = |err: std::io::Error| {
+                ToolError::internal_error(format!("daemon connection failed: {err}"))
+            };
+            let mut line = serde_json::to_string(request)
+                .map_err(|e| ToolError::internal_error(e.to_string()))?;
+            line.push('\n');
+            let stream = self.reader.get_mut();
+            stream.write_all(line.as_bytes()).map_err(transport)?;
+            stream.flush().map_err(transport)?;
+
+            let mut answer = String::new();
+            if self.reader.read_line(&mut answer).map_err(transport)? == 0 {
+                return Err(ToolError::internal_error(
+                    "daemon closed the connection without answering",
+                ));
+            }
+            serde_json::from_str::<ToolResult>(&answer)
+                .map_err(|e| ToolError::internal_error(format!("bad daemon answer: {e}")))?
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = request;
+            match self.never {}
+        }
+    }
+
+    /// [`Client::call`], deserializing the answer as `T`.
+    ///
+    /// # Errors
+    ///
+    /// As [`Client::call`], plus an `internal_error` if the answer is not a
+    /// `T`.
+    pub fn call_as<T: DeserializeOwned>(&mut self, request: &Request) -> Result<T, ToolError> {
+        let value = self.call(request)?;
+        serde_json::from_value(value)
+            .map_err(|e| ToolError::internal_error(format!("bad daemon answer: {e}")))
+    }
+}
+
+/// The daemon serving `project_root`, whatever its version.
+fn connect_any(project_root: &Path) -> Option<(Client, DaemonInfo)> {
+    #[cfg(unix)]
+    {
+        let stream = UnixStream::connect(socket_path(project_root)).ok()?;
+        let mut client = Client {
+            reader: BufReader::new(stream),
+        };
+        let info = client.call_as(&Request::Ping).ok()?;
+        Some((client, info))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = project_root;
+        None
+    }
+}
+
+/// A connection to the daemon serving `project_root`, if one is running,
+/// is the same version as this build, and `CORALINE_NO_DAEMON` is unset.
+pub fn connect(project_root: &Path) -> Option<Client> {
+    if std::env::var_os(NO_DAEMON_ENV).is_some() {
+        return None;
+    }
+    let (client, info) = connect_any(project_root)?;
+    if info.version != env!("CARGO_PKG_VERSION") {
+        debug!(daemon = %info.version, "daemon version differs; running in-process");
+        return None;
+    }
+    Some(client)
+}
+
+/// Run `request` on the project's daemon; `None` when none is serving.
+pub fn delegate<T: DeserializeOwned>(
+    project_root: &Path,
+    request: &Request,
+) -> Option<Result<T, ToolError>> {
+    connect(project_root).map(|mut client| client.call_as(request))
+}
+
+/// What the daemon serving `project_root` reports, if one is running.
+pub fn status(project_root: &Path) -> Option<DaemonInfo> {
+    connect_any(project_root).map(|(_, info)| info)
+}
+
+/// Ask the daemon serving `project_root` to stop. Returns false when none
+/// is running.
+pub fn stop(project_root: &Path) -> bool {
+    connect_any(project_root).is_some_and(|(mut client, _)| client.call(&Request::Shutdown).is_ok())
+}
+
+struct Daemon {
+    project_root: PathBuf,
+    info: DaemonInfo,
+    registry: ToolRegistry,
+    /// Syncs write the whole graph; run one at a time.
+    sync_lock: Mutex<()>,
+    shutdown: AtomicBool,
+}
+
+impl Daemon {
+    fn handle(&self, request: Request) -> ToolResult {
+        fn internal(err: impl Into<CoralineError>) -> ToolError {
+            err.into().into()
+        }
+        match request {
+            Request::Ping => to_value(&self.info),
+            Request::Tool { name, arguments } => self.registry.execute(&name, arguments),
+            Request::Query {
+                search,
+                options,
+                tags,
+            } => to_value(&query(&self.project_root, &search, &options, &tags).map_err(internal)?),
+            Request::Context {
+                task,
+                queries,
+                options,
+            } => {
+                let task = TaskInput::with_queries(task, queries);
+                context::build_task_context(&self.project_root, &task, &options)
+                    .map(Value::String)
+                    .map_err(internal)
+            }
+            Request::Sync => {
+                let _guard = self
+                    .sync_lock
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner);
+                let cfg = watch::sync_config(&self.project_root).map_err(internal)?;
+                to_value(&extraction::sync(&self.project_root, &cfg, None).map_err(internal)?)
+            }
+            Request::Shutdown => {
+                self.shutdown.store(true, Ordering::Relaxed);
+                Ok(Value::Null)
+            }
+        }
+    }
+
+    /// Answer requests from one client until it disconnects.
+    #[cfg(unix)]
+    fn serve_connection(&self, stream: UnixStream) -> std::io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let answer = match serde_json::from_str::<Request>(&line) {
+                Ok(request) => {
+                    debug!(?request, "daemon request");
+                    self.handle(request)
+                }
+                Err(err) => Err(ToolError::invalid_params(format!("Invalid request: {err}"))),
+            };
+            let mut reply = serde_json::to_string(&answer).unwrap_or_else(|e| {
+                format!(r#"{{"Err":{{"code":"internal_error","message":"{e}"}}}}"#)
+            });
+            reply.push('\n');
+            writer.write_all(reply.as_bytes())?;
+            writer.flush()?;
+            if self.shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> ToolResult {
+    serde_json::to_value(value).map_err(|e| ToolError::internal_error(e.to_string()))
+}
+
+/// Serve `project_root` on its socket until a client sends
+/// [`Request::Shutdown`].
+///
+/// The socket is readable only by the current user. A socket left behind
+/// by a daemon that died is replaced.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if another daemon is already serving the
+/// project, the socket cannot be bound, or the platform has no Unix
+/// sockets.
+pub fn serve(project_root: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = socket_path(project_root);
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AddrInUse,
+                    format!("a daemon is already serving {}", project_root.display()),
+                ));
+            }
+            std::fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+        let daemon = Arc::new(Daemon {
+            project_root: project_root.to_path_buf(),
+            info: DaemonInfo {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                pid: std::process::id(),
+                project_root: project_root.to_path_buf(),
+                started_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+                    .unwrap_or_default(),
+            },
+            registry: create_default_registry(project_root),
+            sync_lock: Mutex::new(()),
+            shutdown: AtomicBool::new(false),
+        });
+        info!(socket = %path.display(), "daemon listening");
+
+        for stream in listener.incoming() {
+            if daemon.shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    warn!(error = %err, "daemon accept failed");
+                    continue;
+                }
+            };
+            let worker = Arc::clone(&daemon);
+            let spawned = std::thread::Builder::new()
+                .name("coraline-daemon-client".into())
+                .spawn(move || {
+                    if let Err(err) = worker.serve_connection(stream) {
+                        debug!(error = %err, "daemon client disconnected");
+                    }
+                    if worker.shutdown.load(Ordering::Relaxed) {
+                        // Wake the accept loop so it sees the flag.
+                        let _ = UnixStream::connect(socket_path(&worker.project_root));
+                    }
+                });
+            if let Err(err) = spawned {
+                warn!(error = %err, "failed to spawn daemon client thread");
+            }
+        }
+        let _ = std::fs::remove_file(&path);
+        info!("daemon stopped");
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = project_root;
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "the daemon needs Unix domain sockets, which this platform lacks",
+        ))
+    }
+}
@@ -1,35 +1,225 @@
 #![forbid(unsafe_code)]
 
+use std::collections::HashSet;
+use std::fmt::Write as _;
 use std::path::{Path, PathBuf};
 
 use rusqlite::{Connection, OptionalExtension, params};
 use tracing::{debug, warn};
 
+use crate::config::{self, DatabaseConfig, SearchConfig, SynchronousMode};
+use crate::error::{CoralineError, Result};
 use crate::types::{
-    Edge, EdgeKind, FileRecord, Language, Node, NodeKind, SearchResult, UnresolvedReference,
+    Annotation, AnnotationSource, Edge, EdgeKind, FileRecord, IssueLink, IssueSource, Language,
+    Node, NodeChurn, NodeCoverage, NodeKind, SearchOptions, SearchResult, UnresolvedReference,
     Visibility,
 };
+use crate::utils::language_hint;
 
 pub const DATABASE_FILENAME: &str = "coraline.db";
 pub const SCHEMA_SQL: &str = include_str!("db/schema.sql");
 
+/// Definitions of the analytical views created by [`create_analytical_views`].
+pub const VIEWS_SQL: &str = include_str!("db/views.sql");
+
+/// Names of the views in [`VIEWS_SQL`].
+pub const ANALYTICAL_VIEWS: [&str; 3] = ["call_pairs", "file_dependencies", "symbol_metrics"];
+
 /// PRAGMAs applied on every connection open.
 ///
 /// - `foreign_keys = ON`   — enforce referential integrity
 /// - `journal_mode = WAL`  — concurrent readers, faster writes
-/// - `synchronous = NORMAL`— durable on OS crash, faster than FULL
-/// - `cache_size = -65536` — 64 MB page cache (negative = KiB)
 /// - `temp_store = MEMORY` — temp tables in RAM
-/// - `mmap_size = 268435456` — 256 MB memory-mapped I/O
+///
+/// `cache_size`, `mmap_size`, and `synchronous` follow, from [`tune_pragmas`].
 const PERF_PRAGMAS: &str = "
     PRAGMA foreign_keys  = ON;
     PRAGMA journal_mode  = WAL;
-    PRAGMA synchronous   = NORMAL;
-    PRAGMA cache_size    = -65536;
     PRAGMA temp_store    = MEMORY;
-    PRAGMA mmap_size     = 268435456;
 ";
 
+const MIB: u64 = 1024 * 1024;
+
+/// What a connection is opened for; selects the pragma profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Workload {
+    /// Queries and small writes (CLI queries, MCP tools).
+    Interactive,
+    /// Indexing, syncing, and embedding: large write transactions.
+    Bulk,
+}
+
+/// Size- and workload-dependent PRAGMA values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PragmaSettings {
+    pub cache_size_bytes: u64,
+    pub mmap_size_bytes: u64,
+    pub synchronous: SynchronousMode,
+}
+
+impl PragmaSettings {
+    fn to_sql(self) -> String {
+        format!(
+            "PRAGMA cache_size = -{}; PRAGMA mmap_size = {}; PRAGMA synchronous = {};",
+            self.cache_size_bytes / 1024,
+            self.mmap_size_bytes,
+            self.synchronous.as_str()
+        )
+    }
+}
+
+/// Pick `cache_size`, `mmap_size`, and `synchronous` for a database of
+/// `db_size` bytes (main file plus WAL).
+///
+/// With `auto_tune` off the fixed defaults apply: 64 MB cache, 256 MB mmap,
+/// `NORMAL`. Otherwise:
+///
+/// - cache: a quarter of the database for interactive use (16–256 MB), half
+///   for bulk work (64–512 MB)
+/// - mmap: the database plus half again for growth (64 MB–2 GB)
+/// - synchronous: `NORMAL` for interactive use, `OFF` for bulk work — the
+///   graph can always be rebuilt from source, and files left half-written
+///   by a crash are re-indexed on the next run
+///
+/// Values set explicitly in `[database]` always win.
+pub fn tune_pragmas(db_size: u64, workload: Workload, cfg: &DatabaseConfig) -> PragmaSettings {
+    let auto = if cfg.auto_tune {
+        let (cache_min, cache_max, cache_divisor, synchronous) = match workload {
+            Workload::Interactive => (16 * MIB, 256 * MIB, 4, SynchronousMode::Normal),
+            Workload::Bulk => (64 * MIB, 512 * MIB, 2, SynchronousMode::Off),
+        };
+        PragmaSettings {
+            cache_size_bytes: (db_size / cache_divisor).clamp(cache_min, cache_max),
+            mmap_size_bytes: db_size
+                .saturating_add(db_size / 2)
+                .clamp(64 * MIB, 2048 * MIB),
+            synchronous,
+        }
+    } else {
+        PragmaSettings {
+            cache_size_bytes: 64 * MIB,
+            mmap_size_bytes: 256 * MIB,
+            synchronous: SynchronousMode::Normal,
+        }
+    };
+
+    PragmaSettings {
+        cache_size_bytes: cfg
+            .cache_size_mb
+            .map_or(auto.cache_size_bytes, |mb| mb.saturating_mul(MIB)),
+        mmap_size_bytes: cfg
+            .mmap_size_mb
+            .map_or(auto.mmap_size_bytes, |mb| mb.saturating_mul(MIB)),
+        synchronous: cfg.synchronous.unwrap_or(auto.synchronous),
+    }
+}
+
+/// Bytes used by the database at `db_path`, including its WAL file.
+fn database_size(db_path: &Path) -> u64 {
+    let wal = db_path.with_extension("db-wal");
+    [db_path, wal.as_path()]
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+fn apply_pragmas(
+    conn: &Connection,
+    project_root: &Path,
+    db_path: &Path,
+    workload: Workload,
+) -> Result<()> {
+    conn.execute_batch(PERF_PRAGMAS)?;
+    let cfg = config::load_toml_config(project_root)
+        .map(|c| c.database)
+        .unwrap_or_default();
+    let settings = tune_pragmas(database_size(db_path), workload, &cfg);
+    debug!(?workload, ?settings, "applying database pragmas");
+    conn.execute_batch(&settings.to_sql())
+        .map_err(CoralineError::from)
+}
+
+/// Tables added after the original schema. Created on every open so
+/// databases initialised by older versions pick them up.
+const ADDED_TABLES_SQL: &str = "
+    CREATE TABLE IF NOT EXISTS vector_chunks (
+        node_id TEXT NOT NULL,
+        chunk_index INTEGER NOT NULL,
+        embedding BLOB NOT NULL,
+        model TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (node_id, chunk_index)
+    );
+    CREATE TABLE IF NOT EXISTS node_coverage (
+        node_id TEXT PRIMARY KEY,
+        covered_lines INTEGER NOT NULL,
+        total_lines INTEGER NOT NULL,
+        ingested_at INTEGER NOT NULL,
+        FOREIGN KEY (node_id) REFERENCES nodes(id) ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS node_issues (
+        node_id TEXT NOT NULL,
+        issue_id TEXT NOT NULL,
+        source TEXT NOT NULL,
+        reference TEXT NOT NULL,
+        summary TEXT NOT NULL,
+        PRIMARY KEY (node_id, issue_id, reference),
+        FOREIGN KEY (node_id) REFERENCES nodes(id) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_node_issues_issue ON node_issues(issue_id);
+    CREATE TABLE IF NOT EXISTS annotations (
+        target TEXT NOT NULL,
+        symbol INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        source TEXT NOT NULL,
+        position INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (target, symbol, tag)
+    );
+    CREATE TABLE IF NOT EXISTS node_churn (
+        node_id TEXT PRIMARY KEY,
+        commits INTEGER NOT NULL,
+        authors INTEGER NOT NULL,
+        last_modified INTEGER NOT NULL,
+        recorded_at INTEGER NOT NULL,
+        FOREIGN KEY (node_id) REFERENCES nodes(id) ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS node_hashes (
+        node_id TEXT PRIMARY KEY,
+        source_hash TEXT NOT NULL,
+        FOREIGN KEY (node_id) REFERENCES nodes(id) ON DELETE CASCADE
+    );
+    CREATE TABLE IF NOT EXISTS graph_branch (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        name TEXT NOT NULL
+    );
+";
+
+/// Columns added to tables of the original schema, as (table, column,
+/// declaration). Added on open when missing, like [`ADDED_TABLES_SQL`].
+const ADDED_COLUMNS: [(&str, &str, &str); 2] = [
+    ("unresolved_refs", "arity", "INTEGER"),
+    ("unresolved_refs", "receiver", "TEXT"),
+];
+
+/// Bring a database created by an older version up to the current schema.
+fn upgrade_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(ADDED_TABLES_SQL)?;
+    for (table, column, declaration) in ADDED_COLUMNS {
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            conn.execute_batch(&format!(
+                "ALTER TABLE {table} ADD COLUMN {column} {declaration}"
+            ))?;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, Default)]
 pub struct Database;
 
@@ -39,15 +229,19 @@ pub struct UnresolvedRefRow {
     pub reference: UnresolvedReference,
 }
 
-fn io_other(err: impl std::error::Error + Send + Sync + 'static) -> std::io::Error {
-    std::io::Error::other(err)
+/// The newest node and unresolved reference rows, taken before a sync so
+/// the rows it adds can be told apart (see [`row_marks`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RowMarks {
+    pub node_rowid: i64,
+    pub ref_id: i64,
 }
 
 pub fn database_path(project_root: &Path) -> PathBuf {
     project_root.join(".coraline").join(DATABASE_FILENAME)
 }
 
-pub fn initialize_database(project_root: &Path) -> std::io::Result<PathBuf> {
+pub fn initialize_database(project_root: &Path) -> Result<PathBuf> {
     let db_path = database_path(project_root);
     debug!(path = %db_path.display(), "initializing database");
 
@@ -55,88 +249,130 @@ pub fn initialize_database(project_root: &Path) -> std::io::Result<PathBuf> {
         std::fs::create_dir_all(parent)?;
     }
 
-    let conn = rusqlite::Connection::open(&db_path).map_err(io_other)?;
-    conn.execute_batch(PERF_PRAGMAS).map_err(io_other)?;
-    conn.execute_batch(SCHEMA_SQL).map_err(io_other)?;
+    let conn = rusqlite::Connection::open(&db_path)?;
+    apply_pragmas(&conn, project_root, &db_path, Workload::Interactive)?;
+    conn.execute_batch(SCHEMA_SQL)?;
+    upgrade_schema(&conn)?;
     Ok(db_path)
 }
 
-pub fn open_database(project_root: &Path) -> std::io::Result<Connection> {
+pub fn open_database(project_root: &Path) -> Result<Connection> {
+    open_database_for(project_root, Workload::Interactive)
+}
+
+/// Open the project database with pragmas tuned for `workload`.
+pub fn open_database_for(project_root: &Path, workload: Workload) -> Result<Connection> {
     let db_path = database_path(project_root);
-    let conn = Connection::open(&db_path).map_err(io_other)?;
-    conn.execute_batch(PERF_PRAGMAS).map_err(io_other)?;
+    let conn = Connection::open(&db_path)?;
+    apply_pragmas(&conn, project_root, &db_path, workload)?;
+    upgrade_schema(&conn)?;
     Ok(conn)
 }
 
-pub fn clear_database(conn: &Connection) -> std::io::Result<()> {
+/// Copy the whole database to `path`, replacing any file there.
+pub fn backup_database(conn: &Connection, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    let copied = conn
+        .backup(rusqlite::MAIN_DB, &tmp, None)
+        .map_err(CoralineError::from)
+        .and_then(|()| Ok(std::fs::rename(&tmp, path)?));
+    if copied.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    copied
+}
+
+/// Replace the contents of the database with the copy at `path`. Other
+/// connections see the restored graph from their next read.
+pub fn restore_database(conn: &mut Connection, path: &Path) -> Result<()> {
+    conn.restore(
+        rusqlite::MAIN_DB,
+        path,
+        None::<fn(rusqlite::backup::Progress)>,
+    )?;
+    upgrade_schema(conn)
+}
+
+/// The git branch whose graph the database holds, if recorded.
+pub fn get_graph_branch(conn: &Connection) -> Result<Option<String>> {
+    conn.query_row("SELECT name FROM graph_branch WHERE id = 0", [], |row| {
+        row.get(0)
+    })
+    .optional()
+    .map_err(CoralineError::from)
+}
+
+pub fn set_graph_branch(conn: &Connection, branch: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO graph_branch (id, name) VALUES (0, ?1)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name",
+        params![branch],
+    )
+    .map(|_| ())
+    .map_err(CoralineError::from)
+}
+
+pub fn clear_database(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         "DELETE FROM unresolved_refs;
+         DELETE FROM node_coverage;
+         DELETE FROM node_issues;
+         DELETE FROM node_churn;
+         DELETE FROM node_hashes;
          DELETE FROM vectors;
+         DELETE FROM vector_chunks;
          DELETE FROM edges;
          DELETE FROM nodes;
          DELETE FROM files;",
     )
-    .map_err(io_other)
+    .map_err(CoralineError::from)
 }
 
-pub fn get_file_record(conn: &Connection, path: &str) -> std::io::Result<Option<FileRecord>> {
-    let row = conn
-        .query_row(
-            "SELECT path, content_hash, language, size, modified_at, indexed_at, node_count, errors FROM files WHERE path = ?",
-            params![path],
-            |row| {
-                let errors: Option<String> = row.get(7)?;
-                let language_raw: String = row.get(2)?;
-                Ok(FileRecord {
-                    path: row.get(0)?,
-                    content_hash: row.get(1)?,
-                    language: parse_language(&language_raw),
-                    size: u64::try_from(row.get::<_, i64>(3)?).unwrap_or(0),
-                    modified_at: row.get(4)?,
-                    indexed_at: row.get(5)?,
-                    node_count: row.get(6)?,
-                    errors: errors
-                        .and_then(|raw| serde_json::from_str(&raw).ok()),
-                })
-            },
-        )
-        .optional()
-        .map_err(io_other)?;
+const FILE_RECORD_COLUMNS: &str =
+    "path, content_hash, language, size, modified_at, indexed_at, node_count, errors";
 
-    Ok(row)
+pub fn get_file_record(conn: &Connection, path: &str) -> Result<Option<FileRecord>> {
+    conn.query_row(
+        &format!("SELECT {FILE_RECORD_COLUMNS} FROM files WHERE path = ?"),
+        params![path],
+        row_to_file_record,
+    )
+    .optional()
+    .map_err(CoralineError::from)
 }
 
-pub fn list_files(conn: &Connection) -> std::io::Result<Vec<FileRecord>> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT path, content_hash, language, size, modified_at, indexed_at, node_count, errors FROM files",
-        )
-        .map_err(io_other)?;
-    let rows = stmt
-        .query_map([], |row| {
-            let errors: Option<String> = row.get(7)?;
-            let language_raw: String = row.get(2)?;
-            Ok(FileRecord {
-                path: row.get(0)?,
-                content_hash: row.get(1)?,
-                language: parse_language(&language_raw),
-                size: u64::try_from(row.get::<_, i64>(3)?).unwrap_or(0),
-                modified_at: row.get(4)?,
-                indexed_at: row.get(5)?,
-                node_count: row.get(6)?,
-                errors: errors.and_then(|raw| serde_json::from_str(&raw).ok()),
-            })
-        })
-        .map_err(io_other)?;
+/// Look up a file record ignoring ASCII case, preferring an exact match.
+///
+/// Used on case-insensitive filesystems, where a case-only rename leaves the
+/// old record under a path that no longer matches byte-for-byte.
+pub fn get_file_record_ignore_case(conn: &Connection, path: &str) -> Result<Option<FileRecord>> {
+    conn.query_row(
+        &format!(
+            "SELECT {FILE_RECORD_COLUMNS} FROM files WHERE path = ? COLLATE NOCASE
+             ORDER BY path = ? DESC LIMIT 1"
+        ),
+        params![path, path],
+        row_to_file_record,
+    )
+    .optional()
+    .map_err(CoralineError::from)
+}
+
+pub fn list_files(conn: &Connection) -> Result<Vec<FileRecord>> {
+    let mut stmt = conn.prepare(&format!("SELECT {FILE_RECORD_COLUMNS} FROM files"))?;
+    let rows = stmt.query_map([], row_to_file_record)?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
 
-pub fn upsert_file(conn: &Connection, file: &FileRecord) -> std::io::Result<()> {
+pub fn upsert_file(conn: &Connection, file: &FileRecord) -> Result<()> {
     let errors = file
         .errors
         .as_ref()
@@ -162,25 +398,22 @@ pub fn upsert_file(conn: &Connection, file: &FileRecord) -> std::io::Result<()>
             file.node_count,
             errors,
         ],
-    )
-    .map_err(io_other)?;
+    )?;
     Ok(())
 }
 
-pub fn insert_nodes(conn: &mut Connection, nodes: &[Node]) -> std::io::Result<()> {
-    let tx = conn.transaction().map_err(io_other)?;
+pub fn insert_nodes(conn: &mut Connection, nodes: &[Node]) -> Result<()> {
+    let tx = conn.transaction()?;
     {
-        let mut stmt = tx
-            .prepare(
-                "INSERT INTO nodes (
+        let mut stmt = tx.prepare(
+            "INSERT INTO nodes (
                     id, kind, name, qualified_name, file_path, language,
                     start_line, end_line, start_column, end_column,
                     docstring, signature, visibility,
                     is_exported, is_async, is_static, is_abstract,
                     decorators, type_parameters, updated_at
                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            )
-            .map_err(io_other)?;
+        )?;
 
         for node in nodes {
             let decorators = node
@@ -213,22 +446,19 @@ pub fn insert_nodes(conn: &mut Connection, nodes: &[Node]) -> std::io::Result<()
                 decorators,
                 type_parameters,
                 node.updated_at,
-            ])
-            .map_err(io_other)?;
+            ])?;
         }
     }
-    tx.commit().map_err(io_other)
+    tx.commit().map_err(CoralineError::from)
 }
 
-pub fn insert_edges(conn: &mut Connection, edges: &[Edge]) -> std::io::Result<()> {
-    let tx = conn.transaction().map_err(io_other)?;
+pub fn insert_edges(conn: &mut Connection, edges: &[Edge]) -> Result<()> {
+    let tx = conn.transaction()?;
     {
-        let mut stmt = tx
-            .prepare(
-                "INSERT INTO edges (source, target, kind, metadata, line, col)
+        let mut stmt = tx.prepare(
+            "INSERT INTO edges (source, target, kind, metadata, line, col)
                  VALUES (?, ?, ?, ?, ?, ?)",
-            )
-            .map_err(io_other)?;
+        )?;
 
         for edge in edges {
             let metadata = edge
@@ -242,26 +472,21 @@ pub fn insert_edges(conn: &mut Connection, edges: &[Edge]) -> std::io::Result<()
                 metadata,
                 edge.line,
                 edge.column,
-            ])
-            .map_err(io_other)?;
+            ])?;
         }
     }
-    tx.commit().map_err(io_other)
+    tx.commit().map_err(CoralineError::from)
 }
 
-pub fn insert_unresolved_refs(
-    conn: &mut Connection,
-    refs: &[UnresolvedReference],
-) -> std::io::Result<()> {
-    let tx = conn.transaction().map_err(io_other)?;
+pub fn insert_unresolved_refs(conn: &mut Connection, refs: &[UnresolvedReference]) -> Result<()> {
+    let tx = conn.transaction()?;
     {
-        let mut stmt = tx
-            .prepare(
-                "INSERT INTO unresolved_refs (
-                    from_node_id, reference_name, reference_kind, line, col, candidates
-                 ) VALUES (?, ?, ?, ?, ?, ?)",
-            )
-            .map_err(io_other)?;
+        let mut stmt = tx.prepare(
+            "INSERT INTO unresolved_refs (
+                    from_node_id, reference_name, reference_kind, line, col, candidates,
+                    arity, receiver
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )?;
 
         for unresolved in refs {
             let candidates = unresolved
@@ -275,11 +500,12 @@ pub fn insert_unresolved_refs(
                 unresolved.line,
                 unresolved.column,
                 candidates,
-            ])
-            .map_err(io_other)?;
+                unresolved.arity,
+                unresolved.receiver,
+            ])?;
         }
     }
-    tx.commit().map_err(io_other)
+    tx.commit().map_err(CoralineError::from)
 }
 
 /// Store a fully-parsed file's results in a single `SQLite` transaction:
@@ -288,29 +514,90 @@ pub fn insert_unresolved_refs(
 /// This is more efficient than the three separate `insert_nodes` /
 /// `insert_edges` / `insert_unresolved_refs` calls because it incurs only
 /// one transaction commit instead of three.
-#[allow(clippy::too_many_lines)]
 pub fn store_file_batch(
     conn: &mut Connection,
     file_record: &FileRecord,
     nodes: &[Node],
     edges: &[Edge],
     unresolved_refs: &[UnresolvedReference],
-) -> std::io::Result<()> {
-    let tx = conn.transaction().map_err(io_other)?;
+    span_hashes: &[(String, String)],
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    insert_file_rows(&tx, file_record, nodes, edges, unresolved_refs, span_hashes)?;
+    tx.commit().map_err(|err| {
+        warn!(file = %file_record.path, error = %err, "store_file_batch commit failed");
+        CoralineError::from(err)
+    })
+}
 
+/// Replace everything stored for a file in a single `SQLite` transaction.
+///
+/// The rows of `file_record.path` and of every path in `replaced_paths`
+/// (e.g. a case-only rename) are deleted, then the new nodes, edges,
+/// unresolved refs, span hashes, and file record are written. A crash
+/// part-way through leaves the previous state intact.
+///
+/// `span_hashes` pairs node ids with [`crate::utils::span_hash`] of their
+/// source at indexing time.
+pub fn replace_file(
+    conn: &mut Connection,
+    replaced_paths: &[&str],
+    file_record: &FileRecord,
+    nodes: &[Node],
+    edges: &[Edge],
+    unresolved_refs: &[UnresolvedReference],
+    span_hashes: &[(String, String)],
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    requeue_incoming_refs(&tx, &file_record.path)?;
+    delete_file_rows(&tx, &file_record.path)?;
+    for path in replaced_paths.iter().filter(|p| **p != file_record.path) {
+        requeue_incoming_refs(&tx, path)?;
+        delete_file_rows(&tx, path)?;
+    }
+    insert_file_rows(&tx, file_record, nodes, edges, unresolved_refs, span_hashes)?;
+    tx.commit().map_err(|err| {
+        warn!(file = %file_record.path, error = %err, "replace_file commit failed");
+        CoralineError::from(err)
+    })
+}
+
+/// Turn the edges other files have into `path`'s nodes back into
+/// unresolved references, so the next resolve links them to the nodes
+/// that replace these. The edges themselves go with the nodes.
+fn requeue_incoming_refs(tx: &Connection, path: &str) -> Result<()> {
+    tx.execute(
+        "INSERT INTO unresolved_refs (from_node_id, reference_name, reference_kind, line, col)
+         SELECT e.source, t.name, e.kind, COALESCE(e.line, 0), COALESCE(e.col, 0)
+         FROM edges e
+         JOIN nodes t ON t.id = e.target
+         JOIN nodes s ON s.id = e.source
+         WHERE t.file_path = ?1 AND s.file_path <> ?1",
+        params![path],
+    )?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_lines)]
+fn insert_file_rows(
+    tx: &Connection,
+    file_record: &FileRecord,
+    nodes: &[Node],
+    edges: &[Edge],
+    unresolved_refs: &[UnresolvedReference],
+    span_hashes: &[(String, String)],
+) -> Result<()> {
     // Nodes
     if !nodes.is_empty() {
-        let mut stmt = tx
-            .prepare(
-                "INSERT INTO nodes (
+        let mut stmt = tx.prepare(
+            "INSERT INTO nodes (
                     id, kind, name, qualified_name, file_path, language,
                     start_line, end_line, start_column, end_column,
                     docstring, signature, visibility,
                     is_exported, is_async, is_static, is_abstract,
                     decorators, type_parameters, updated_at
                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            )
-            .map_err(io_other)?;
+        )?;
         for node in nodes {
             let decorators = node
                 .decorators
@@ -342,19 +629,25 @@ pub fn store_file_batch(
                 decorators,
                 type_parameters,
                 node.updated_at,
-            ])
-            .map_err(io_other)?;
+            ])?;
+        }
+    }
+
+    // Span hashes
+    if !span_hashes.is_empty() {
+        let mut stmt =
+            tx.prepare("INSERT OR REPLACE INTO node_hashes (node_id, source_hash) VALUES (?, ?)")?;
+        for (node_id, hash) in span_hashes {
+            stmt.execute(params![node_id, hash])?;
         }
     }
 
     // Edges
     if !edges.is_empty() {
-        let mut stmt = tx
-            .prepare(
-                "INSERT INTO edges (source, target, kind, metadata, line, col)
+        let mut stmt = tx.prepare(
+            "INSERT INTO edges (source, target, kind, metadata, line, col)
                  VALUES (?, ?, ?, ?, ?, ?)",
-            )
-            .map_err(io_other)?;
+        )?;
         for edge in edges {
             let metadata = edge
                 .metadata
@@ -367,20 +660,18 @@ pub fn store_file_batch(
                 metadata,
                 edge.line,
                 edge.column,
-            ])
-            .map_err(io_other)?;
+            ])?;
         }
     }
 
     // Unresolved references
     if !unresolved_refs.is_empty() {
-        let mut stmt = tx
-            .prepare(
-                "INSERT INTO unresolved_refs (
-                    from_node_id, reference_name, reference_kind, line, col, candidates
-                 ) VALUES (?, ?, ?, ?, ?, ?)",
-            )
-            .map_err(io_other)?;
+        let mut stmt = tx.prepare(
+            "INSERT INTO unresolved_refs (
+                    from_node_id, reference_name, reference_kind, line, col, candidates,
+                    arity, receiver
+                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )?;
         for r in unresolved_refs {
             let candidates = r
                 .candidates
@@ -393,8 +684,9 @@ pub fn store_file_batch(
                 r.line,
                 r.column,
                 candidates,
-            ])
-            .map_err(io_other)?;
+                r.arity,
+                r.receiver,
+            ])?;
         }
     }
 
@@ -424,21 +716,59 @@ pub fn store_file_batch(
             file_record.node_count,
             errors,
         ],
-    )
-    .map_err(io_other)?;
-
-    tx.commit().map_err(|err| {
-        warn!(file = %file_record.path, error = %err, "store_file_batch commit failed");
-        io_other(err)
-    })
+    )?;
+    Ok(())
 }
 
+/// Over-fetch factor for FTS candidates, so re-ranking can promote results
+/// that bm25 alone would have cut off.
+const RERANK_FETCH_FACTOR: usize = 4;
+
+/// Full-text search using the default `[search]` ranking settings.
 pub fn search_nodes(
     conn: &Connection,
     query: &str,
     kind: Option<NodeKind>,
     limit: usize,
-) -> std::io::Result<Vec<SearchResult>> {
+) -> Result<Vec<SearchResult>> {
+    search_nodes_with_config(conn, query, kind, limit, &SearchConfig::default())
+}
+
+/// Full-text search, re-ranked by `search`.
+///
+/// Results that match the query only through noise words are down-weighted,
+/// exact name matches are boosted, and so are results in a language the query
+/// names ("the Rust service").
+pub fn search_nodes_with_config(
+    conn: &Connection,
+    query: &str,
+    kind: Option<NodeKind>,
+    limit: usize,
+    search: &SearchConfig,
+) -> Result<Vec<SearchResult>> {
+    let options = SearchOptions {
+        kinds: kind.map(|kind| vec![kind]),
+        limit: Some(limit),
+        ..SearchOptions::default()
+    };
+    search_nodes_with_options(conn, query, &options, search)
+}
+
+/// Default number of results when `SearchOptions::limit` is unset.
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// Full-text search scoped by `options`, ranked as in
+/// [`search_nodes_with_config`].
+///
+/// Kind, language and export filters run in SQL; path globs and
+/// case-sensitive matching are applied to the candidates in rank order.
+/// `offset` skips results after re-ranking, so pages line up.
+pub fn search_nodes_with_options(
+    conn: &Connection,
+    query: &str,
+    options: &SearchOptions,
+    search: &SearchConfig,
+) -> Result<Vec<SearchResult>> {
     let Some(fts_query) = build_fts_query(query) else {
         return Ok(Vec::new());
     };
@@ -458,35 +788,170 @@ pub fn search_nodes(
 
     let mut params_vec: Vec<String> = vec![fts_query];
 
-    if let Some(kind) = kind {
-        sql.push_str(" AND n.kind = ?");
-        params_vec.push(kind_to_string(kind));
+    let mut push_any = |column: &str, values: Vec<String>| {
+        let _ = write!(
+            sql,
+            " AND {column} IN ({})",
+            vec!["?"; values.len()].join(", ")
+        );
+        params_vec.extend(values);
+    };
+    if let Some(kinds) = options.kinds.as_ref().filter(|k| !k.is_empty()) {
+        push_any("n.kind", kinds.iter().map(|k| kind_to_string(*k)).collect());
     }
-
-    sql.push_str(" ORDER BY score ASC, length(n.name) ASC LIMIT ?");
-    params_vec.push(limit.to_string());
-
-    let mut stmt = conn.prepare(&sql).map_err(io_other)?;
-    let rows = stmt
-        .query_map(rusqlite::params_from_iter(params_vec), |row| {
-            // FTS rank is negative, convert to positive score (higher = better)
-            let rank: f64 = row.get(20)?;
-            #[allow(clippy::cast_possible_truncation)]
-            let score = (-rank) as f32;
-            Ok(SearchResult {
-                node: row_to_node(row)?,
-                score,
-                highlights: None,
+    if let Some(languages) = options.languages.as_ref().filter(|l| !l.is_empty()) {
+        push_any(
+            "n.language",
+            languages.iter().map(|l| language_to_string(*l)).collect(),
+        );
+    }
+    if options.exported_only == Some(true) {
+        // Exported as `dead_code` sees it: flagged, public, or named by an
+        // export statement in its file.
+        sql.push_str(
+            " AND (n.is_exported = 1 OR n.visibility = 'public'
+                   OR EXISTS (SELECT 1 FROM nodes e WHERE e.kind = 'export'
+                              AND e.file_path = n.file_path AND e.name = n.name))",
+        );
+    }
+    sql.push_str(" ORDER BY score ASC, length(n.name) ASC");
+
+    let include = path_globs(options.include_patterns.as_deref());
+    let exclude = path_globs(options.exclude_patterns.as_deref());
+    let case_terms: Option<Vec<&str>> = (options.case_sensitive == Some(true)).then(|| {
+        query
+            .split_whitespace()
+            .map(|t| t.trim_matches('"'))
+            .collect()
+    });
+    let keep = |node: &Node| {
+        include
+            .as_ref()
+            .is_none_or(|set| set.is_match(&node.file_path))
+            && exclude
+                .as_ref()
+                .is_none_or(|set| !set.is_match(&node.file_path))
+            && case_terms.as_ref().is_none_or(|terms| {
+                terms
+                    .iter()
+                    .any(|t| node.name.contains(t) || node.qualified_name.contains(t))
             })
+    };
+
+    let limit = options.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let offset = options.offset.unwrap_or(0);
+    let fetch = limit
+        .saturating_add(offset)
+        .saturating_mul(RERANK_FETCH_FACTOR);
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params_vec), |row| {
+        // FTS rank is negative, convert to positive score (higher = better)
+        let rank: f64 = row.get(20)?;
+        #[allow(clippy::cast_possible_truncation)]
+        let score = (-rank) as f32;
+        Ok(SearchResult {
+            node: row_to_node(row)?,
+            score,
+            highlights: None,
         })
-        .map_err(io_other)?;
+    })?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        let result = row?;
+        if keep(&result.node) {
+            results.push(result);
+            if results.len() >= fetch {
+                break;
+            }
+        }
     }
 
-    Ok(results)
+    rerank_results(query, &mut results, search);
+    Ok(results.into_iter().skip(offset).take(limit).collect())
+}
+
+/// A `GlobSet` of `patterns`, or `None` when there are none. Invalid
+/// patterns never match.
+fn path_globs(patterns: Option<&[String]>) -> Option<globset::GlobSet> {
+    let patterns = patterns.filter(|p| !p.is_empty())?;
+    let mut builder = globset::GlobSetBuilder::new();
+    for pattern in patterns {
+        if let Ok(glob) = globset::Glob::new(pattern) {
+            builder.add(glob);
+        }
+    }
+    builder.build().ok()
+}
+
+/// Adjust bm25 scores for identifier noise and exact name matches, then
+/// re-sort (higher score first, shorter names breaking ties).
+fn rerank_results(query: &str, results: &mut [SearchResult], search: &SearchConfig) {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| term.trim_matches('"').to_lowercase())
+        .filter(|term| !term.is_empty())
+        .collect();
+    // Words naming a language steer ranking rather than match symbols.
+    let mut languages: Vec<Language> = Vec::new();
+    let mut language_words: HashSet<String> = HashSet::new();
+    for word in query.split_whitespace() {
+        let hinted = language_hint(word);
+        if !hinted.is_empty() {
+            languages.extend_from_slice(hinted);
+            language_words.insert(word.trim_matches('"').to_lowercase());
+        }
+    }
+    let informative: Vec<&str> = terms
+        .iter()
+        .filter(|term| {
+            !language_words.contains(*term)
+                && !search
+                    .noise_words
+                    .iter()
+                    .any(|noise| noise.eq_ignore_ascii_case(term))
+        })
+        .map(String::as_str)
+        .collect();
+    let query_key = identifier_key(query);
+
+    for result in results.iter_mut() {
+        let node = &result.node;
+        if !informative.is_empty() {
+            let haystack = format!(
+                "{} {} {}",
+                node.name,
+                node.qualified_name,
+                node.docstring.as_deref().unwrap_or_default()
+            )
+            .to_lowercase();
+            if !informative.iter().any(|term| haystack.contains(term)) {
+                result.score *= search.noise_weight;
+            }
+        }
+        if !query_key.is_empty() && identifier_key(&node.name) == query_key {
+            result.score *= search.exact_match_boost;
+        }
+        if languages.contains(&node.language) {
+            result.score *= search.language_boost;
+        }
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.node.name.len().cmp(&b.node.name.len()))
+    });
+}
+
+/// Lowercased alphanumerics only, so `get user`, `getUser` and `get_user`
+/// compare equal.
+fn identifier_key(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
 }
 
 fn build_fts_query(query: &str) -> Option<String> {
@@ -504,54 +969,46 @@ fn build_fts_query(query: &str) -> Option<String> {
     Some(fts_query)
 }
 
-pub fn find_nodes_by_name(conn: &Connection, name: &str) -> std::io::Result<Vec<Node>> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, kind, name, qualified_name, file_path, language,
+pub fn find_nodes_by_name(conn: &Connection, name: &str) -> Result<Vec<Node>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, name, qualified_name, file_path, language,
                     start_line, end_line, start_column, end_column,
                     docstring, signature, visibility,
                     is_exported, is_async, is_static, is_abstract,
                     decorators, type_parameters, updated_at
              FROM nodes WHERE name = ?",
-        )
-        .map_err(io_other)?;
-    let rows = stmt
-        .query_map(params![name], row_to_node)
-        .map_err(io_other)?;
+    )?;
+    let rows = stmt.query_map(params![name], row_to_node)?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
 
-pub fn find_exports_by_module(conn: &Connection, module_path: &str) -> std::io::Result<Vec<Node>> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, kind, name, qualified_name, file_path, language,
+pub fn find_exports_by_module(conn: &Connection, module_path: &str) -> Result<Vec<Node>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, name, qualified_name, file_path, language,
                     start_line, end_line, start_column, end_column,
                     docstring, signature, visibility,
                     is_exported, is_async, is_static, is_abstract,
                     decorators, type_parameters, updated_at
              FROM nodes WHERE kind = ? AND signature = ?",
-        )
-        .map_err(io_other)?;
-    let rows = stmt
-        .query_map(
-            params![kind_to_string(NodeKind::Export), module_path],
-            row_to_node,
-        )
-        .map_err(io_other)?;
+    )?;
+    let rows = stmt.query_map(
+        params![kind_to_string(NodeKind::Export), module_path],
+        row_to_node,
+    )?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
 
-pub fn get_node_by_id(conn: &Connection, node_id: &str) -> std::io::Result<Option<Node>> {
+pub fn get_node_by_id(conn: &Connection, node_id: &str) -> Result<Option<Node>> {
     let row = conn
         .query_row(
             "SELECT id, kind, name, qualified_name, file_path, language,
@@ -563,8 +1020,7 @@ pub fn get_node_by_id(conn: &Connection, node_id: &str) -> std::io::Result<Optio
             params![node_id],
             row_to_node,
         )
-        .optional()
-        .map_err(io_other)?;
+        .optional()?;
 
     Ok(row)
 }
@@ -574,7 +1030,7 @@ pub fn get_edges_by_source(
     source_id: &str,
     kind: Option<EdgeKind>,
     limit: usize,
-) -> std::io::Result<Vec<Edge>> {
+) -> Result<Vec<Edge>> {
     let mut sql = String::from(
         "SELECT source, target, kind, metadata, line, col FROM edges WHERE source = ?",
     );
@@ -588,14 +1044,12 @@ pub fn get_edges_by_source(
     sql.push_str(" ORDER BY COALESCE(line, 0) ASC, COALESCE(col, 0) ASC, target ASC LIMIT ?");
     params_vec.push(limit.to_string());
 
-    let mut stmt = conn.prepare(&sql).map_err(io_other)?;
-    let rows = stmt
-        .query_map(rusqlite::params_from_iter(params_vec), row_to_edge)
-        .map_err(io_other)?;
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params_vec), row_to_edge)?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
@@ -605,7 +1059,7 @@ pub fn get_edges_by_target(
     target_id: &str,
     kind: Option<EdgeKind>,
     limit: usize,
-) -> std::io::Result<Vec<Edge>> {
+) -> Result<Vec<Edge>> {
     let mut sql = String::from(
         "SELECT source, target, kind, metadata, line, col FROM edges WHERE target = ?",
     );
@@ -619,78 +1073,271 @@ pub fn get_edges_by_target(
     sql.push_str(" ORDER BY COALESCE(line, 0) ASC, COALESCE(col, 0) ASC, source ASC LIMIT ?");
     params_vec.push(limit.to_string());
 
-    let mut stmt = conn.prepare(&sql).map_err(io_other)?;
-    let rows = stmt
-        .query_map(rusqlite::params_from_iter(params_vec), row_to_edge)
-        .map_err(io_other)?;
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params_vec), row_to_edge)?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+    Ok(results)
+}
+
+/// Edges whose ends lie in different files, with the file of each end,
+/// leaving out structural `contains` and `exports` edges and references
+/// from Markdown.
+pub fn list_cross_file_edges(conn: &Connection) -> Result<Vec<(Edge, String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT e.source, e.target, e.kind, e.metadata, e.line, e.col, s.file_path, t.file_path
+         FROM edges e
+         JOIN nodes s ON s.id = e.source
+         JOIN nodes t ON t.id = e.target
+         WHERE s.file_path <> t.file_path
+           AND e.kind NOT IN ('contains', 'exports')
+           AND s.language <> 'markdown'
+         ORDER BY s.file_path, COALESCE(e.line, 0), t.file_path",
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row_to_edge(row)?, row.get(6)?, row.get(7)?)))?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+    Ok(results)
+}
+
+/// Fetch nodes for a batch of ids in one query. Missing ids are skipped and
+/// the result order is unspecified.
+pub fn get_nodes_by_ids(conn: &Connection, node_ids: &[String]) -> Result<Vec<Node>> {
+    if node_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!(
+        "SELECT id, kind, name, qualified_name, file_path, language,
+                start_line, end_line, start_column, end_column,
+                docstring, signature, visibility,
+                is_exported, is_async, is_static, is_abstract,
+                decorators, type_parameters, updated_at
+         FROM nodes WHERE id IN ({})",
+        sql_placeholders(node_ids.len())
+    );
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(node_ids), row_to_node)?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+    Ok(results)
+}
+
+/// Fetch every edge leaving (`outgoing = true`) or entering any of
+/// `node_ids` in one query, optionally restricted to `kinds`.
+///
+/// With `per_node_limit`, only the first that many edges of each
+/// node are returned.
+///
+/// Rows are ordered the same way as [`get_edges_by_source`] /
+/// [`get_edges_by_target`], so grouping by endpoint preserves per-node order.
+pub fn get_edges_for_nodes(
+    conn: &Connection,
+    node_ids: &[String],
+    outgoing: bool,
+    kinds: Option<&[EdgeKind]>,
+    per_node_limit: Option<usize>,
+) -> Result<Vec<Edge>> {
+    if node_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (column, other) = if outgoing {
+        ("source", "target")
+    } else {
+        ("target", "source")
+    };
+    let order = format!("COALESCE(line, 0) ASC, COALESCE(col, 0) ASC, {other} ASC");
+    let mut filter = format!("{column} IN ({})", sql_placeholders(node_ids.len()));
+    let mut params_vec: Vec<String> = node_ids.to_vec();
+
+    if let Some(kinds) = kinds {
+        if kinds.is_empty() {
+            return Ok(Vec::new());
+        }
+        let _ = write!(filter, " AND kind IN ({})", sql_placeholders(kinds.len()));
+        params_vec.extend(kinds.iter().map(|k| edge_kind_to_string(*k)));
+    }
+
+    let sql = per_node_limit.map_or_else(
+        || {
+            format!(
+                "SELECT source, target, kind, metadata, line, col FROM edges WHERE {filter} ORDER BY {order}"
+            )
+        },
+        |limit| {
+            params_vec.push(limit.to_string());
+            format!(
+                "SELECT source, target, kind, metadata, line, col FROM (
+                     SELECT source, target, kind, metadata, line, col,
+                            ROW_NUMBER() OVER (PARTITION BY {column} ORDER BY {order}) AS rank
+                     FROM edges WHERE {filter}
+                 ) WHERE rank <= CAST(? AS INTEGER) ORDER BY {order}"
+            )
+        },
+    );
+
+    let mut stmt = conn.prepare_cached(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params_vec), row_to_edge)?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
 
+fn sql_placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+/// Up to `limit` unresolved references with an id above `after_id`, in id
+/// order. With `since`, only references added after those marks, and
+/// older ones naming a node added after them.
 pub fn list_unresolved_refs(
     conn: &Connection,
+    after_id: i64,
+    since: Option<RowMarks>,
     limit: usize,
-) -> std::io::Result<Vec<UnresolvedRefRow>> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, from_node_id, reference_name, reference_kind, line, col, candidates
-             FROM unresolved_refs LIMIT ?",
-        )
-        .map_err(io_other)?;
-    let limit_i64 = i64::try_from(limit).unwrap_or(i64::MAX);
-    let rows = stmt
-        .query_map(params![limit_i64], |row| {
-            let id: i64 = row.get(0)?;
-            let reference_kind_raw: String = row.get(3)?;
-            let candidates_raw: Option<String> = row.get(6)?;
-            Ok(UnresolvedRefRow {
-                id,
-                reference: UnresolvedReference {
-                    from_node_id: row.get(1)?,
-                    reference_name: row.get(2)?,
-                    reference_kind: parse_edge_kind(&reference_kind_raw),
-                    line: row.get(4)?,
-                    column: row.get(5)?,
-                    candidates: candidates_raw.and_then(|raw| serde_json::from_str(&raw).ok()),
-                },
-            })
+) -> Result<Vec<UnresolvedRefRow>> {
+    let mut sql = String::from(
+        "SELECT id, from_node_id, reference_name, reference_kind, line, col, candidates,
+                arity, receiver
+         FROM unresolved_refs WHERE id > ?",
+    );
+    let mut values = vec![after_id];
+    if let Some(marks) = since {
+        sql.push_str(" AND (id > ? OR reference_name IN (SELECT name FROM nodes WHERE rowid > ?))");
+        values.extend([marks.ref_id, marks.node_rowid]);
+    }
+    sql.push_str(" ORDER BY id LIMIT ?");
+    values.push(i64::try_from(limit).unwrap_or(i64::MAX));
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+        let id: i64 = row.get(0)?;
+        let reference_kind_raw: String = row.get(3)?;
+        let candidates_raw: Option<String> = row.get(6)?;
+        Ok(UnresolvedRefRow {
+            id,
+            reference: UnresolvedReference {
+                from_node_id: row.get(1)?,
+                reference_name: row.get(2)?,
+                reference_kind: parse_edge_kind(&reference_kind_raw),
+                line: row.get(4)?,
+                column: row.get(5)?,
+                candidates: candidates_raw.and_then(|raw| serde_json::from_str(&raw).ok()),
+                arity: row.get(7)?,
+                receiver: row.get(8)?,
+            },
         })
-        .map_err(io_other)?;
+    })?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
 
-pub fn delete_unresolved_refs(conn: &mut Connection, ids: &[i64]) -> std::io::Result<()> {
+/// Marks of the newest node and unresolved reference rows. `SQLite` gives
+/// new rows higher rowids than every existing one, so rows above the
+/// marks were written after they were taken.
+pub fn row_marks(conn: &Connection) -> Result<RowMarks> {
+    conn.query_row(
+        "SELECT (SELECT COALESCE(MAX(rowid), 0) FROM nodes),
+                (SELECT COALESCE(MAX(id), 0) FROM unresolved_refs)",
+        [],
+        |row| {
+            Ok(RowMarks {
+                node_rowid: row.get(0)?,
+                ref_id: row.get(1)?,
+            })
+        },
+    )
+    .map_err(CoralineError::from)
+}
+
+pub fn count_unresolved_refs(conn: &Connection) -> Result<usize> {
+    let count: i64 =
+        conn.query_row("SELECT COUNT(*) FROM unresolved_refs", [], |row| row.get(0))?;
+    Ok(usize::try_from(count).unwrap_or(0))
+}
+
+pub fn delete_unresolved_refs(conn: &mut Connection, ids: &[i64]) -> Result<()> {
     if ids.is_empty() {
         return Ok(());
     }
-    let tx = conn.transaction().map_err(io_other)?;
+    let tx = conn.transaction()?;
     {
-        let mut stmt = tx
-            .prepare("DELETE FROM unresolved_refs WHERE id = ?")
-            .map_err(io_other)?;
+        let mut stmt = tx.prepare("DELETE FROM unresolved_refs WHERE id = ?")?;
         for id in ids {
-            stmt.execute(params![id]).map_err(io_other)?;
+            stmt.execute(params![id])?;
         }
     }
-    tx.commit().map_err(io_other)
+    tx.commit().map_err(CoralineError::from)
+}
+
+/// Delete the `kind` edges into nodes of `target_kind`, such as the
+/// derived links recomputed after each index. Returns the number deleted.
+pub fn delete_edges_to_kind(
+    conn: &Connection,
+    kind: EdgeKind,
+    target_kind: NodeKind,
+) -> Result<usize> {
+    Ok(conn.execute(
+        "DELETE FROM edges WHERE kind = ?
+             AND target IN (SELECT id FROM nodes WHERE kind = ?)",
+        params![edge_kind_to_string(kind), kind_to_string(target_kind)],
+    )?)
+}
+
+pub fn delete_file(conn: &mut Connection, path: &str) -> Result<()> {
+    let tx = conn.transaction()?;
+    delete_file_rows(&tx, path)?;
+    tx.commit().map_err(CoralineError::from)
+}
+
+fn delete_file_rows(tx: &Connection, path: &str) -> Result<()> {
+    // `vectors` and `vector_chunks` have no foreign key to `nodes`; drop
+    // embeddings explicitly.
+    for table in ["vectors", "vector_chunks"] {
+        tx.execute(
+            &format!(
+                "DELETE FROM {table} WHERE node_id IN (SELECT id FROM nodes WHERE file_path = ?)"
+            ),
+            params![path],
+        )?;
+    }
+    tx.execute("DELETE FROM nodes WHERE file_path = ?", params![path])?;
+    tx.execute("DELETE FROM files WHERE path = ?", params![path])?;
+    Ok(())
 }
 
-pub fn delete_file(conn: &mut Connection, path: &str) -> std::io::Result<()> {
-    let tx = conn.transaction().map_err(io_other)?;
-    tx.execute("DELETE FROM nodes WHERE file_path = ?", params![path])
-        .map_err(io_other)?;
-    tx.execute("DELETE FROM files WHERE path = ?", params![path])
-        .map_err(io_other)?;
-    tx.commit().map_err(io_other)
+/// Paths whose stored rows disagree with each other.
+///
+/// Either a file record's `node_count` differs from the nodes actually
+/// stored for it, or nodes have no file record at all. Both indicate an
+/// interrupted write.
+pub fn find_inconsistent_files(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT f.path FROM files f
+             WHERE f.node_count != (SELECT COUNT(*) FROM nodes n WHERE n.file_path = f.path)
+             UNION
+             SELECT DISTINCT n.file_path FROM nodes n
+             WHERE NOT EXISTS (SELECT 1 FROM files f WHERE f.path = n.file_path)
+             ORDER BY 1",
+    )?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect::<Result<_, _>>().map_err(CoralineError::from)
 }
 
 /// Get all nodes belonging to a specific file, optionally filtered by kind.
@@ -698,7 +1345,7 @@ pub fn get_nodes_by_file(
     conn: &Connection,
     file_path: &str,
     kind: Option<NodeKind>,
-) -> std::io::Result<Vec<Node>> {
+) -> Result<Vec<Node>> {
     let mut sql = String::from(
         "SELECT id, kind, name, qualified_name, file_path, language,
                 start_line, end_line, start_column, end_column,
@@ -716,45 +1363,113 @@ pub fn get_nodes_by_file(
 
     sql.push_str(" ORDER BY start_line ASC");
 
-    let mut stmt = conn.prepare(&sql).map_err(io_other)?;
-    let rows = stmt
-        .query_map(rusqlite::params_from_iter(params_vec), row_to_node)
-        .map_err(io_other)?;
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(params_vec), row_to_node)?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+    Ok(results)
+}
+
+/// Files indexed strictly after `since` (milliseconds since the Unix epoch).
+pub fn list_files_indexed_since(conn: &Connection, since: i64) -> Result<Vec<FileRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {FILE_RECORD_COLUMNS} FROM files WHERE indexed_at > ? ORDER BY path ASC"
+    ))?;
+    let rows = stmt.query_map(params![since], row_to_file_record)?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+    Ok(results)
+}
+
+/// Nodes written strictly after `since` (milliseconds since the Unix epoch).
+pub fn get_nodes_updated_since(conn: &Connection, since: i64) -> Result<Vec<Node>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, name, qualified_name, file_path, language,
+                    start_line, end_line, start_column, end_column,
+                    docstring, signature, visibility,
+                    is_exported, is_async, is_static, is_abstract,
+                    decorators, type_parameters, updated_at
+             FROM nodes
+             WHERE updated_at > ?
+             ORDER BY file_path ASC, start_line ASC",
+    )?;
+    let rows = stmt.query_map(params![since], row_to_node)?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+    Ok(results)
+}
+
+/// Edges whose source or target node was written strictly after `since`.
+pub fn get_edges_touching_nodes_updated_since(conn: &Connection, since: i64) -> Result<Vec<Edge>> {
+    let mut stmt = conn.prepare(
+        "SELECT e.source, e.target, e.kind, e.metadata, e.line, e.col
+             FROM edges e
+             WHERE e.source IN (SELECT id FROM nodes WHERE updated_at > ?1)
+                OR e.target IN (SELECT id FROM nodes WHERE updated_at > ?1)
+             ORDER BY e.source ASC, COALESCE(e.line, 0) ASC, COALESCE(e.col, 0) ASC, e.target ASC",
+    )?;
+    let rows = stmt.query_map(params![since], row_to_edge)?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
 
 /// Return every node in the database ordered by file path then start line.
-pub fn get_all_nodes(conn: &Connection) -> std::io::Result<Vec<Node>> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, kind, name, qualified_name, file_path, language,
+pub fn get_all_nodes(conn: &Connection) -> Result<Vec<Node>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, name, qualified_name, file_path, language,
                     start_line, end_line, start_column, end_column,
                     docstring, signature, visibility,
                     is_exported, is_async, is_static, is_abstract,
                     decorators, type_parameters, updated_at
              FROM nodes
              ORDER BY file_path ASC, start_line ASC",
-        )
-        .map_err(io_other)?;
+    )?;
 
-    let rows = stmt.query_map([], row_to_node).map_err(io_other)?;
+    let rows = stmt.query_map([], row_to_node)?;
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
+    }
+    Ok(results)
+}
+
+pub fn get_nodes_by_kind(conn: &Connection, kind: NodeKind) -> Result<Vec<Node>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, name, qualified_name, file_path, language,
+                    start_line, end_line, start_column, end_column,
+                    docstring, signature, visibility,
+                    is_exported, is_async, is_static, is_abstract,
+                    decorators, type_parameters, updated_at
+             FROM nodes
+             WHERE kind = ?
+             ORDER BY file_path ASC, start_line ASC",
+    )?;
+
+    let rows = stmt.query_map(params![kind_to_string(kind)], row_to_node)?;
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
     }
     Ok(results)
 }
 
 /// Return nodes that have no corresponding row in the `vectors` table.
-pub fn get_unembedded_nodes(conn: &Connection) -> std::io::Result<Vec<Node>> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT n.id, n.kind, n.name, n.qualified_name, n.file_path, n.language,
+pub fn get_unembedded_nodes(conn: &Connection) -> Result<Vec<Node>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, n.kind, n.name, n.qualified_name, n.file_path, n.language,
                     n.start_line, n.end_line, n.start_column, n.end_column,
                     n.docstring, n.signature, n.visibility,
                     n.is_exported, n.is_async, n.is_static, n.is_abstract,
@@ -763,17 +1478,343 @@ pub fn get_unembedded_nodes(conn: &Connection) -> std::io::Result<Vec<Node>> {
              LEFT JOIN vectors v ON n.id = v.node_id
              WHERE v.node_id IS NULL
              ORDER BY n.file_path ASC, n.start_line ASC",
-        )
-        .map_err(io_other)?;
+    )?;
 
-    let rows = stmt.query_map([], row_to_node).map_err(io_other)?;
+    let rows = stmt.query_map([], row_to_node)?;
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
 
+/// Delete embeddings (node vectors and body chunks) whose node no longer
+/// exists. Returns the number of node vectors removed.
+pub fn delete_orphaned_vectors(conn: &Connection) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM vector_chunks WHERE node_id NOT IN (SELECT id FROM nodes)",
+        [],
+    )?;
+    conn.execute(
+        "DELETE FROM vectors WHERE node_id NOT IN (SELECT id FROM nodes)",
+        [],
+    )
+    .map_err(CoralineError::from)
+}
+
+/// Replace all stored coverage with `coverage` (node id, coverage) in one
+/// transaction; a new report supersedes the previous one.
+pub fn replace_coverage(
+    conn: &mut Connection,
+    coverage: &[(String, NodeCoverage)],
+    ingested_at: i64,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM node_coverage", [])?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO node_coverage (node_id, covered_lines, total_lines, ingested_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+        for (node_id, cov) in coverage {
+            stmt.execute(params![
+                node_id,
+                cov.covered_lines,
+                cov.total_lines,
+                ingested_at
+            ])?;
+        }
+    }
+    tx.commit().map_err(CoralineError::from)
+}
+
+/// Replace all stored churn with `churn` (node id, churn) in one
+/// transaction.
+pub fn replace_churn(
+    conn: &mut Connection,
+    churn: &[(String, NodeChurn)],
+    recorded_at: i64,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM node_churn", [])?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO node_churn (node_id, commits, authors, last_modified, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+        for (node_id, c) in churn {
+            stmt.execute(params![
+                node_id,
+                c.commits,
+                c.authors,
+                c.last_modified,
+                recorded_at
+            ])?;
+        }
+    }
+    tx.commit().map_err(CoralineError::from)
+}
+
+/// Stored churn of `node_id`, if any.
+pub fn get_churn(conn: &Connection, node_id: &str) -> Result<Option<NodeChurn>> {
+    conn.prepare_cached(
+        "SELECT commits, authors, last_modified FROM node_churn WHERE node_id = ?1",
+    )?
+    .query_row(params![node_id], |row| {
+        Ok(NodeChurn {
+            commits: row.get(0)?,
+            authors: row.get(1)?,
+            last_modified: row.get(2)?,
+        })
+    })
+    .optional()
+    .map_err(CoralineError::from)
+}
+
+/// Symbols with recorded churn (file nodes excluded), with their number of
+/// incoming `calls` and `references` edges from other nodes.
+pub fn list_churned_nodes(conn: &Connection) -> Result<Vec<(Node, NodeChurn, i64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, n.kind, n.name, n.qualified_name, n.file_path, n.language,
+                    n.start_line, n.end_line, n.start_column, n.end_column,
+                    n.docstring, n.signature, n.visibility,
+                    n.is_exported, n.is_async, n.is_static, n.is_abstract,
+                    n.decorators, n.type_parameters, n.updated_at,
+                    c.commits, c.authors, c.last_modified,
+                    (SELECT COUNT(*) FROM edges e
+                     WHERE e.target = n.id AND e.source != n.id
+                       AND e.kind IN ('calls','references'))
+             FROM node_churn c JOIN nodes n ON n.id = c.node_id
+             WHERE n.kind != 'file'
+             ORDER BY n.file_path, n.start_line",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row_to_node(row)?,
+            NodeChurn {
+                commits: row.get(20)?,
+                authors: row.get(21)?,
+                last_modified: row.get(22)?,
+            },
+            row.get(23)?,
+        ))
+    })?;
+    rows.collect::<Result<_, _>>().map_err(CoralineError::from)
+}
+
+/// The span hash stored for `node_id` when it was indexed.
+pub fn get_node_hash(conn: &Connection, node_id: &str) -> Result<Option<String>> {
+    conn.prepare_cached("SELECT source_hash FROM node_hashes WHERE node_id = ?1")?
+        .query_row(params![node_id], |row| row.get(0))
+        .optional()
+        .map_err(CoralineError::from)
+}
+
+/// Stored coverage of the given nodes; nodes without coverage are absent.
+pub fn get_coverage(
+    conn: &Connection,
+    node_ids: &[&str],
+) -> Result<std::collections::HashMap<String, NodeCoverage>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT covered_lines, total_lines FROM node_coverage WHERE node_id = ?1",
+    )?;
+    let mut coverage = std::collections::HashMap::new();
+    for &node_id in node_ids {
+        let row = stmt
+            .query_row(params![node_id], |row| {
+                Ok(NodeCoverage {
+                    covered_lines: row.get(0)?,
+                    total_lines: row.get(1)?,
+                })
+            })
+            .optional()?;
+        if let Some(cov) = row {
+            coverage.insert(node_id.to_string(), cov);
+        }
+    }
+    Ok(coverage)
+}
+
+/// Replace all stored issue links with `links` in one transaction.
+pub fn replace_issue_links(conn: &mut Connection, links: &[IssueLink]) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM node_issues", [])?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR IGNORE INTO node_issues (node_id, issue_id, source, reference, summary)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for link in links {
+            stmt.execute(params![
+                link.node_id,
+                link.issue_id,
+                issue_source_to_string(link.source),
+                link.reference,
+                link.summary
+            ])?;
+        }
+    }
+    tx.commit().map_err(CoralineError::from)
+}
+
+/// Issue links of `node` and of the nodes nested in it (same file, within
+/// its line range), ordered by issue id.
+pub fn get_issue_links_for_node(conn: &Connection, node: &Node) -> Result<Vec<IssueLink>> {
+    let mut stmt = conn.prepare(
+        "SELECT i.node_id, i.issue_id, i.source, i.reference, i.summary
+             FROM node_issues i JOIN nodes n ON n.id = i.node_id
+             WHERE n.id = ?1
+                OR (n.file_path = ?2 AND n.start_line >= ?3 AND n.end_line <= ?4)
+             ORDER BY i.issue_id, i.source, i.reference",
+    )?;
+    let rows = stmt.query_map(
+        params![node.id, node.file_path, node.start_line, node.end_line],
+        |row| {
+            let source: String = row.get(2)?;
+            Ok(IssueLink {
+                node_id: row.get(0)?,
+                issue_id: row.get(1)?,
+                source: if source == "comment" {
+                    IssueSource::Comment
+                } else {
+                    IssueSource::Commit
+                },
+                reference: row.get(3)?,
+                summary: row.get(4)?,
+            })
+        },
+    )?;
+    rows.collect::<Result<_, _>>().map_err(CoralineError::from)
+}
+
+/// Number of stored issue links.
+pub fn count_issue_links(conn: &Connection) -> Result<i64> {
+    conn.query_row("SELECT COUNT(*) FROM node_issues", [], |row| row.get(0))
+        .map_err(CoralineError::from)
+}
+
+const fn issue_source_to_string(source: IssueSource) -> &'static str {
+    match source {
+        IssueSource::Commit => "commit",
+        IssueSource::Comment => "comment",
+    }
+}
+
+/// Store `annotations`, replacing any with the same target and tag.
+pub fn add_annotations(conn: &mut Connection, annotations: &[Annotation]) -> Result<()> {
+    let tx = conn.transaction()?;
+    insert_annotations(&tx, annotations)?;
+    tx.commit().map_err(CoralineError::from)
+}
+
+/// Replace every annotation from `source` with `annotations`.
+pub fn replace_annotations(
+    conn: &mut Connection,
+    source: AnnotationSource,
+    annotations: &[Annotation],
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM annotations WHERE source = ?1",
+        params![annotation_source_to_string(source)],
+    )?;
+    insert_annotations(&tx, annotations)?;
+    tx.commit().map_err(CoralineError::from)
+}
+
+fn insert_annotations(tx: &Connection, annotations: &[Annotation]) -> Result<()> {
+    let mut stmt = tx.prepare(
+        "INSERT OR REPLACE INTO annotations (target, symbol, tag, source, position)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for annotation in annotations {
+        stmt.execute(params![
+            annotation.target,
+            i32::from(annotation.symbol),
+            annotation.tag,
+            annotation_source_to_string(annotation.source),
+            annotation.position,
+        ])?;
+    }
+    Ok(())
+}
+
+/// Remove the given `tags` of `target`, or all of its tags when `tags` is
+/// empty. Returns the number removed.
+pub fn remove_annotations(
+    conn: &Connection,
+    target: &str,
+    symbol: bool,
+    tags: &[String],
+) -> Result<usize> {
+    if tags.is_empty() {
+        return conn
+            .execute(
+                "DELETE FROM annotations WHERE target = ?1 AND symbol = ?2",
+                params![target, i32::from(symbol)],
+            )
+            .map_err(CoralineError::from);
+    }
+    let mut removed = 0;
+    for tag in tags {
+        removed += conn.execute(
+            "DELETE FROM annotations WHERE target = ?1 AND symbol = ?2 AND tag = ?3",
+            params![target, i32::from(symbol), tag],
+        )?;
+    }
+    Ok(removed)
+}
+
+/// Every stored annotation, ordered by source, position, target, and tag.
+pub fn list_annotations(conn: &Connection) -> Result<Vec<Annotation>> {
+    let mut stmt = conn.prepare(
+        "SELECT target, symbol, tag, source, position FROM annotations
+             ORDER BY source DESC, position, target, tag",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let symbol: i32 = row.get(1)?;
+        let source: String = row.get(3)?;
+        Ok(Annotation {
+            target: row.get(0)?,
+            symbol: symbol != 0,
+            tag: row.get(2)?,
+            source: match source.as_str() {
+                "codeowners" => AnnotationSource::Codeowners,
+                "submodule" => AnnotationSource::Submodule,
+                "package" => AnnotationSource::Package,
+                _ => AnnotationSource::User,
+            },
+            position: row.get(4)?,
+        })
+    })?;
+    rows.collect::<Result<_, _>>().map_err(CoralineError::from)
+}
+
+const fn annotation_source_to_string(source: AnnotationSource) -> &'static str {
+    match source {
+        AnnotationSource::User => "user",
+        AnnotationSource::Codeowners => "codeowners",
+        AnnotationSource::Submodule => "submodule",
+        AnnotationSource::Package => "package",
+    }
+}
+
+/// (Re)create the analytical views (`call_pairs`, `file_dependencies`,
+/// `symbol_metrics`) for external BI tools and `DuckDB`.
+pub fn create_analytical_views(conn: &Connection) -> Result<()> {
+    conn.execute_batch(VIEWS_SQL).map_err(CoralineError::from)
+}
+
+/// Drop the analytical views if present.
+pub fn drop_analytical_views(conn: &Connection) -> Result<()> {
+    for view in ANALYTICAL_VIEWS {
+        conn.execute_batch(&format!("DROP VIEW IF EXISTS {view};"))?;
+    }
+    Ok(())
+}
+
 /// Database statistics returned by `get_db_stats`.
 #[derive(Debug, serde::Serialize)]
 pub struct DbStats {
@@ -784,19 +1825,12 @@ pub struct DbStats {
 }
 
 /// Return summary statistics for the indexed codebase.
-pub fn get_db_stats(conn: &Connection) -> std::io::Result<DbStats> {
-    let node_count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM nodes", [], |r| r.get(0))
-        .map_err(io_other)?;
-    let edge_count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM edges", [], |r| r.get(0))
-        .map_err(io_other)?;
-    let file_count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM files", [], |r| r.get(0))
-        .map_err(io_other)?;
-    let unresolved_count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM unresolved_refs", [], |r| r.get(0))
-        .map_err(io_other)?;
+pub fn get_db_stats(conn: &Connection) -> Result<DbStats> {
+    let node_count: i64 = conn.query_row("SELECT COUNT(*) FROM nodes", [], |r| r.get(0))?;
+    let edge_count: i64 = conn.query_row("SELECT COUNT(*) FROM edges", [], |r| r.get(0))?;
+    let file_count: i64 = conn.query_row("SELECT COUNT(*) FROM files", [], |r| r.get(0))?;
+    let unresolved_count: i64 =
+        conn.query_row("SELECT COUNT(*) FROM unresolved_refs", [], |r| r.get(0))?;
 
     Ok(DbStats {
         node_count,
@@ -813,7 +1847,7 @@ fn language_to_string(language: Language) -> String {
         .unwrap_or_else(|| "unknown".to_string())
 }
 
-fn kind_to_string(kind: NodeKind) -> String {
+pub(crate) fn kind_to_string(kind: NodeKind) -> String {
     serde_json::to_value(kind)
         .ok()
         .and_then(|v| v.as_str().map(std::string::ToString::to_string))
@@ -846,7 +1880,7 @@ fn parse_visibility(raw: &str) -> Visibility {
     serde_json::from_str::<Visibility>(&format!("\"{raw}\"")).unwrap_or(Visibility::Public)
 }
 
-fn parse_edge_kind(raw: &str) -> EdgeKind {
+pub(crate) fn parse_edge_kind(raw: &str) -> EdgeKind {
     serde_json::from_str::<EdgeKind>(&format!("\"{raw}\"")).unwrap_or(EdgeKind::Contains)
 }
 
@@ -881,6 +1915,21 @@ fn row_to_node(row: &rusqlite::Row<'_>) -> rusqlite::Result<Node> {
     })
 }
 
+fn row_to_file_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<FileRecord> {
+    let errors: Option<String> = row.get(7)?;
+    let language_raw: String = row.get(2)?;
+    Ok(FileRecord {
+        path: row.get(0)?,
+        content_hash: row.get(1)?,
+        language: parse_language(&language_raw),
+        size: u64::try_from(row.get::<_, i64>(3)?).unwrap_or(0),
+        modified_at: row.get(4)?,
+        indexed_at: row.get(5)?,
+        node_count: row.get(6)?,
+        errors: errors.and_then(|raw| serde_json::from_str(&raw).ok()),
+    })
+}
+
 fn row_to_edge(row: &rusqlite::Row<'_>) -> rusqlite::Result<Edge> {
     let kind_raw: String = row.get(2)?;
     let metadata: Option<String> = row.get(3)?;
@@ -902,11 +1951,7 @@ fn row_to_edge(row: &rusqlite::Row<'_>) -> rusqlite::Result<Edge> {
 /// 3. The caller has an import statement for the callee's module
 ///
 /// Returns false for cross-crate calls without proper imports.
-pub fn is_valid_call_edge(
-    conn: &Connection,
-    from_node: &Node,
-    to_node: &Node,
-) -> std::io::Result<bool> {
+pub fn is_valid_call_edge(conn: &Connection, from_node: &Node, to_node: &Node) -> Result<bool> {
     // Same file always valid
     if from_node.file_path == to_node.file_path {
         return Ok(true);
@@ -922,20 +1967,17 @@ pub fn is_valid_call_edge(
     // Check if caller imports the callee's module
     // Look for import nodes in the same file as the caller
     let mut stmt = conn
-        .prepare("SELECT id, name, signature FROM nodes WHERE file_path = ? AND kind = 'import'")
-        .map_err(io_other)?;
-    let imports = stmt
-        .query_map(params![&from_node.file_path], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, String>(1)?,
-                row.get::<_, Option<String>>(2)?,
-            ))
-        })
-        .map_err(io_other)?;
+        .prepare("SELECT id, name, signature FROM nodes WHERE file_path = ? AND kind = 'import'")?;
+    let imports = stmt.query_map(params![&from_node.file_path], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+        ))
+    })?;
 
     for import_result in imports {
-        let (_, import_name, import_sig) = import_result.map_err(io_other)?;
+        let (_, import_name, import_sig) = import_result?;
         // Check if import references the callee's file or module
         if to_node.file_path.contains(&import_name)
             || to_node
@@ -983,32 +2025,28 @@ pub struct DocUnresolvedRef {
 ///
 /// These represent inline `` `code_span` `` references that were not matched
 /// to any code symbol during the resolution pass — stale documentation.
-pub fn list_doc_unresolved_refs(conn: &Connection) -> std::io::Result<Vec<DocUnresolvedRef>> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT ur.reference_name, n.file_path, n.name, ur.line, ur.col
+pub fn list_doc_unresolved_refs(conn: &Connection) -> Result<Vec<DocUnresolvedRef>> {
+    let mut stmt = conn.prepare(
+        "SELECT ur.reference_name, n.file_path, n.name, ur.line, ur.col
              FROM unresolved_refs ur
              JOIN nodes n ON ur.from_node_id = n.id
              WHERE n.language = 'markdown'
              ORDER BY n.file_path, ur.line",
-        )
-        .map_err(io_other)?;
-
-    let rows = stmt
-        .query_map([], |row| {
-            Ok(DocUnresolvedRef {
-                reference_name: row.get(0)?,
-                doc_file_path: row.get(1)?,
-                doc_section_name: row.get(2)?,
-                line: row.get(3)?,
-                column: row.get(4)?,
-            })
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok(DocUnresolvedRef {
+            reference_name: row.get(0)?,
+            doc_file_path: row.get(1)?,
+            doc_section_name: row.get(2)?,
+            line: row.get(3)?,
+            column: row.get(4)?,
         })
-        .map_err(io_other)?;
+    })?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
@@ -1018,10 +2056,9 @@ pub fn list_doc_unresolved_refs(conn: &Connection) -> std::io::Result<Vec<DocUnr
 ///
 /// These are public API items that are not mentioned anywhere in the
 /// documentation.
-pub fn list_undocumented_exports(conn: &Connection) -> std::io::Result<Vec<Node>> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT n.id, n.kind, n.name, n.qualified_name, n.file_path, n.language,
+pub fn list_undocumented_exports(conn: &Connection) -> Result<Vec<Node>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, n.kind, n.name, n.qualified_name, n.file_path, n.language,
                     n.start_line, n.end_line, n.start_column, n.end_column,
                     n.docstring, n.signature, n.visibility,
                     n.is_exported, n.is_async, n.is_static, n.is_abstract,
@@ -1042,32 +2079,76 @@ pub fn list_undocumented_exports(conn: &Connection) -> std::io::Result<Vec<Node>
                        AND src.language = 'markdown'
                    )
              ORDER BY n.file_path, n.start_line",
-        )
-        .map_err(io_other)?;
+    )?;
+
+    let rows = stmt.query_map([], row_to_node)?;
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row?);
+    }
+    Ok(results)
+}
+
+/// Functions, methods, types, and constants that no other code node uses.
+///
+/// Such a node has no incoming `calls`, `references`, `imports`, type,
+/// inheritance, or `instantiates` edge from anything but the node itself or
+/// a Markdown file.
+pub fn list_unreferenced_nodes(conn: &Connection) -> Result<Vec<Node>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, n.kind, n.name, n.qualified_name, n.file_path, n.language,
+                    n.start_line, n.end_line, n.start_column, n.end_column,
+                    n.docstring, n.signature, n.visibility,
+                    n.is_exported, n.is_async, n.is_static, n.is_abstract,
+                    n.decorators, n.type_parameters, n.updated_at
+             FROM nodes n
+             WHERE n.language != 'markdown'
+               AND n.kind IN (
+                     'function','method','struct','class','interface',
+                     'trait','enum','type_alias','constant','component'
+                   )
+               AND NOT EXISTS (
+                     SELECT 1
+                     FROM edges e
+                     JOIN nodes src ON e.source = src.id
+                     WHERE e.target = n.id
+                       AND e.source != n.id
+                       AND e.kind NOT IN ('contains','exports')
+                       AND src.language != 'markdown'
+                   )
+             ORDER BY n.file_path, n.start_line",
+    )?;
 
-    let rows = stmt.query_map([], row_to_node).map_err(io_other)?;
+    let rows = stmt.query_map([], row_to_node)?;
     let mut results = Vec::new();
     for row in rows {
-        results.push(row.map_err(io_other)?);
+        results.push(row?);
     }
     Ok(results)
 }
 
+/// Distinct names of references the resolver left unresolved.
+pub fn list_unresolved_reference_names(conn: &Connection) -> Result<HashSet<String>> {
+    let mut stmt = conn.prepare("SELECT DISTINCT reference_name FROM unresolved_refs")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let mut names = HashSet::new();
+    for row in rows {
+        names.insert(row?);
+    }
+    Ok(names)
+}
+
 /// Return `(doc_files_count, doc_sections_count)` — the number of distinct
 /// Markdown files that have been indexed with heading nodes, and the total
 /// number of heading sections across all of them.
-pub fn get_doc_coverage_stats(conn: &Connection) -> std::io::Result<(usize, usize)> {
-    let mut stmt = conn
-        .prepare(
-            "SELECT COUNT(DISTINCT file_path), COUNT(id)
+pub fn get_doc_coverage_stats(conn: &Connection) -> Result<(usize, usize)> {
+    let mut stmt = conn.prepare(
+        "SELECT COUNT(DISTINCT file_path), COUNT(id)
              FROM nodes
              WHERE language = 'markdown' AND kind = 'module'",
-        )
-        .map_err(io_other)?;
+    )?;
 
-    let (files, sections): (i64, i64) = stmt
-        .query_row([], |row| Ok((row.get(0)?, row.get(1)?)))
-        .map_err(io_other)?;
+    let (files, sections): (i64, i64) = stmt.query_row([], |row| Ok((row.get(0)?, row.get(1)?)))?;
 
     Ok((
         usize::try_from(files).unwrap_or(0),
@@ -1077,7 +2158,8 @@ pub fn get_doc_coverage_stats(conn: &Connection) -> std::io::Result<(usize, usiz
 
 #[cfg(test)]
 mod tests {
-    use super::build_fts_query;
+    use super::{MIB, PragmaSettings, Workload, build_fts_query, tune_pragmas};
+    use crate::config::{DatabaseConfig, SynchronousMode};
     use rusqlite::Connection;
 
     #[test]
@@ -1140,4 +2222,53 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn tune_pragmas_scales_with_size_and_workload() {
+        let cfg = DatabaseConfig::default();
+
+        let small = tune_pragmas(MIB, Workload::Interactive, &cfg);
+        assert_eq!(small.cache_size_bytes, 16 * MIB);
+        assert_eq!(small.mmap_size_bytes, 64 * MIB);
+        assert_eq!(small.synchronous, SynchronousMode::Normal);
+
+        let large = tune_pragmas(400 * MIB, Workload::Interactive, &cfg);
+        assert_eq!(large.cache_size_bytes, 100 * MIB);
+        assert_eq!(large.mmap_size_bytes, 600 * MIB);
+
+        let bulk = tune_pragmas(400 * MIB, Workload::Bulk, &cfg);
+        assert_eq!(bulk.cache_size_bytes, 200 * MIB);
+        assert_eq!(bulk.synchronous, SynchronousMode::Off);
+
+        let huge = tune_pragmas(10 * 1024 * MIB, Workload::Bulk, &cfg);
+        assert_eq!(huge.cache_size_bytes, 512 * MIB);
+        assert_eq!(huge.mmap_size_bytes, 2048 * MIB);
+    }
+
+    #[test]
+    fn tune_pragmas_honours_config_overrides() {
+        let fixed = DatabaseConfig {
+            auto_tune: false,
+            ..DatabaseConfig::default()
+        };
+        assert_eq!(
+            tune_pragmas(10 * 1024 * MIB, Workload::Bulk, &fixed),
+            PragmaSettings {
+                cache_size_bytes: 64 * MIB,
+                mmap_size_bytes: 256 * MIB,
+                synchronous: SynchronousMode::Normal,
+            }
+        );
+
+        let pinned = DatabaseConfig {
+            cache_size_mb: Some(32),
+            mmap_size_mb: Some(0),
+            synchronous: Some(SynchronousMode::Full),
+            ..DatabaseConfig::default()
+        };
+        let settings = tune_pragmas(400 * MIB, Workload::Bulk, &pinned);
+        assert_eq!(settings.cache_size_bytes, 32 * MIB);
+        assert_eq!(settings.mmap_size_bytes, 0);
+        assert_eq!(settings.synchronous, SynchronousMode::Full);
+    }
 }
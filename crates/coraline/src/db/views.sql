@@ -0,0 +1,70 @@
+-- Analytical views for external consumers (BI tools, DuckDB, notebooks).
+-- Created by `coraline db views`; dropped and recreated on every run so the
+-- definitions track the installed version.
+
+DROP VIEW IF EXISTS call_pairs;
+CREATE VIEW call_pairs AS
+SELECT
+    e.source          AS caller_id,
+    s.name            AS caller_name,
+    s.qualified_name  AS caller_qualified_name,
+    s.kind            AS caller_kind,
+    s.file_path       AS caller_file,
+    e.target          AS callee_id,
+    t.name            AS callee_name,
+    t.qualified_name  AS callee_qualified_name,
+    t.kind            AS callee_kind,
+    t.file_path       AS callee_file,
+    e.line            AS line
+FROM edges e
+JOIN nodes s ON s.id = e.source
+JOIN nodes t ON t.id = e.target
+WHERE e.kind = 'calls';
+
+-- One row per ordered pair of distinct files linked by any non-containment edge.
+DROP VIEW IF EXISTS file_dependencies;
+CREATE VIEW file_dependencies AS
+SELECT
+    s.file_path                                  AS source_file,
+    t.file_path                                  AS target_file,
+    COUNT(*)                                     AS edge_count,
+    SUM(CASE WHEN e.kind = 'imports' THEN 1 ELSE 0 END) AS import_count,
+    SUM(CASE WHEN e.kind = 'calls' THEN 1 ELSE 0 END)   AS call_count,
+    SUM(CASE WHEN e.kind NOT IN ('imports', 'calls') THEN 1 ELSE 0 END) AS other_count
+FROM edges e
+JOIN nodes s ON s.id = e.source
+JOIN nodes t ON t.id = e.target
+WHERE e.kind <> 'contains'
+  AND s.file_path <> t.file_path
+GROUP BY s.file_path, t.file_path;
+
+-- One row per node: size, call/reference fan-in and fan-out, and the churn
+-- recorded by `coraline churn` (0 and NULL when none was).
+DROP VIEW IF EXISTS symbol_metrics;
+CREATE VIEW symbol_metrics AS
+SELECT
+    n.id,
+    n.name,
+    n.qualified_name,
+    n.kind,
+    n.file_path,
+    n.language,
+    n.end_line - n.start_line + 1    AS line_count,
+    COALESCE(cin.n, 0)               AS fan_in,
+    COALESCE(cout.n, 0)              AS fan_out,
+    COALESCE(rin.n, 0)               AS incoming_edges,
+    COALESCE(kids.n, 0)              AS child_count,
+    COALESCE(ch.commits, 0)          AS commits,
+    COALESCE(ch.authors, 0)          AS authors,
+    ch.last_modified                 AS last_modified
+FROM nodes n
+LEFT JOIN (SELECT target AS id, COUNT(*) AS n FROM edges WHERE kind = 'calls' GROUP BY target) cin
+       ON cin.id = n.id
+LEFT JOIN (SELECT source AS id, COUNT(*) AS n FROM edges WHERE kind = 'calls' GROUP BY source) cout
+       ON cout.id = n.id
+LEFT JOIN (SELECT target AS id, COUNT(*) AS n FROM edges WHERE kind <> 'contains' GROUP BY target) rin
+       ON rin.id = n.id
+LEFT JOIN (SELECT source AS id, COUNT(*) AS n FROM edges WHERE kind = 'contains' GROUP BY source) kids
+       ON kids.id = n.id
+LEFT JOIN node_churn ch
+       ON ch.node_id = n.id;
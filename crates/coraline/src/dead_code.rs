@@ -0,0 +1,178 @@
+#![forbid(unsafe_code)]
+
+//! Dead code candidates for `coraline analyze dead-code`.
+//!
+//! A candidate is a function, method, type, or constant that no other code
+//! node uses: it has no incoming `calls`, `references`, `imports`,
+//! `instantiates`, type, or inheritance edge, and no other file imports its
+//! name. The graph cannot see dynamic dispatch, reflection, or callers
+//! outside the project, so each candidate carries a [`Confidence`]:
+//!
+//! - **high** — not exported, and no unresolved reference shares its name.
+//! - **medium** — exported or public, so another project may use it, or a
+//!   method, which may be called through an interface or trait.
+//! - **low** — some reference the resolver could not pin down carries its
+//!   name, so it may well be called.
+//!
+//! Entry points are never reported: `main`, constructors and other
+//! protocol methods (`__init__`, `__str__`), test code, symbols matching
+//! `[analysis] entry_points`, and exported symbols of `[analysis]
+//! public_api` files.
+
+use std::collections::{HashMap, HashSet};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::AnalysisConfig;
+use crate::db;
+use crate::types::{Node, NodeKind, Visibility};
+use crate::utils::is_test_node;
+
+/// Names called by the runtime or language rather than by project code.
+const BUILTIN_ENTRY_POINTS: &[&str] = &["main", "constructor", "init", "__main__"];
+
+/// How sure the analysis is that a candidate is unused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    /// Parse `low`, `medium`, or `high`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+
+/// A symbol nothing in the project appears to use.
+#[derive(Debug, Clone)]
+pub struct DeadCodeCandidate {
+    pub node: Node,
+    pub confidence: Confidence,
+    /// Why the confidence is not higher, or that nothing refers to it.
+    pub reason: &'static str,
+}
+
+/// The output of [`find_dead_code`].
+#[derive(Debug, Default)]
+pub struct DeadCodeReport {
+    /// Most confident first, then by file and line.
+    pub candidates: Vec<DeadCodeCandidate>,
+    /// Unreferenced symbols left out as entry points.
+    pub entry_points_skipped: usize,
+}
+
+/// List unused symbols at `min_confidence` or above.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be queried.
+pub fn find_dead_code(
+    conn: &Connection,
+    cfg: &AnalysisConfig,
+    min_confidence: Confidence,
+) -> std::io::Result<DeadCodeReport> {
+    let unreferenced = db::list_unreferenced_nodes(conn)?;
+    // `this.repo.save` and `Store::save` both name `save`.
+    let unresolved: HashSet<String> = db::list_unresolved_reference_names(conn)?
+        .iter()
+        .filter_map(|name| name.rsplit(['.', ':']).next())
+        .map(str::to_string)
+        .collect();
+
+    let mut importers: HashMap<String, HashSet<String>> = HashMap::new();
+    for import in db::get_nodes_by_kind(conn, NodeKind::Import)? {
+        importers
+            .entry(import.name)
+            .or_default()
+            .insert(import.file_path);
+    }
+    let exports: HashSet<(String, String)> = db::get_nodes_by_kind(conn, NodeKind::Export)?
+        .into_iter()
+        .map(|n| (n.file_path, n.name))
+        .collect();
+
+    let entry_points = build_set(&cfg.entry_points);
+    let public_api = build_set(&cfg.public_api);
+
+    let mut report = DeadCodeReport::default();
+    for node in unreferenced {
+        let imported_elsewhere = importers
+            .get(&node.name)
+            .is_some_and(|files| files.iter().any(|f| *f != node.file_path));
+        if imported_elsewhere {
+            continue;
+        }
+
+        let exported = node.is_exported
+            || node.visibility == Some(Visibility::Public)
+            || exports.contains(&(node.file_path.clone(), node.name.clone()));
+        let is_entry_point = BUILTIN_ENTRY_POINTS.contains(&node.name.as_str())
+            || (node.name.starts_with("__") && node.name.ends_with("__"))
+            || is_test_node(&node)
+            || entry_points.is_match(&node.name)
+            || entry_points.is_match(&node.qualified_name)
+            || (exported && public_api.is_match(&node.file_path));
+        if is_entry_point {
+            report.entry_points_skipped += 1;
+            continue;
+        }
+
+        let (confidence, reason) = if unresolved.contains(&node.name) {
+            (
+                Confidence::Low,
+                "an unresolved reference in the project has this name",
+            )
+        } else if exported {
+            (
+                Confidence::Medium,
+                "exported, so code outside the project may use it",
+            )
+        } else if node.kind == NodeKind::Method {
+            (
+                Confidence::Medium,
+                "a method, which may be called through an interface or trait",
+            )
+        } else {
+            (Confidence::High, "nothing in the project refers to it")
+        };
+        if confidence >= min_confidence {
+            report.candidates.push(DeadCodeCandidate {
+                node,
+                confidence,
+                reason,
+            });
+        }
+    }
+
+    report.candidates.sort_by(|a, b| {
+        b.confidence.cmp(&a.confidence).then_with(|| {
+            (&a.node.file_path, a.node.start_line).cmp(&(&b.node.file_path, b.node.start_line))
+        })
+    });
+    Ok(report)
+}
+
+fn build_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(err) => warn!(pattern, error = %err, "ignoring invalid [analysis] pattern"),
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
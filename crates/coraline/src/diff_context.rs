@@ -0,0 +1,314 @@
+#![forbid(unsafe_code)]
+
+//! Review context for a git diff (`coraline context --diff`).
+//!
+//! The working tree is compared with a revision (`HEAD` by default), each
+//! changed hunk is mapped onto the indexed symbols whose lines it touches,
+//! and every changed symbol is listed with its callers, its callees, and
+//! its code. Line ranges are those of the index, so the index should be in
+//! step with the working tree; untracked files count as changed throughout.
+//! Symbols a change deleted are no longer indexed and cannot be shown.
+
+use std::collections::BTreeSet;
+use std::io;
+use std::path::Path;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::context::extract_code_blocks;
+use crate::db;
+use crate::history::{Hunk, parse_diff};
+use crate::issues::is_linkable;
+use crate::sensitive::SensitiveFiles;
+use crate::staleness::StalenessCheck;
+use crate::types::{CodeBlock, ContextFormat, EdgeKind, Node, NodeKind, SearchResult};
+use crate::utils::git;
+
+/// Callers and callees listed per changed symbol.
+const MAX_NEIGHBOURS: usize = 20;
+
+/// Options for [`build_diff_context`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffContextOptions {
+    /// Revision the working tree is compared with. Default: `HEAD`.
+    pub base: Option<String>,
+    /// Code blocks to attach, one per changed symbol in order. Default:
+    /// `[context] max_code_blocks`.
+    pub max_code_blocks: Option<usize>,
+    /// Characters per code block. Default: `[context] max_code_block_size`.
+    pub max_code_block_size: Option<usize>,
+    pub include_code: Option<bool>,
+}
+
+/// A symbol a diff touches, with the symbols around it in the call graph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedSymbol {
+    pub node: Node,
+    pub callers: Vec<Node>,
+    pub callees: Vec<Node>,
+}
+
+/// The review context of a diff.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffContext {
+    pub base: String,
+    /// Indexed files the diff changes, sorted.
+    pub files: Vec<String>,
+    /// Changed symbols by file and line.
+    pub changed: Vec<ChangedSymbol>,
+    pub code_blocks: Vec<CodeBlock>,
+}
+
+/// Map the diff of the working tree against `options.base` onto the index.
+///
+/// # Errors
+///
+/// Returns an `io::Error` when the project is not in a git work tree, the
+/// revision does not exist, or the database cannot be read.
+pub fn build_diff_context(
+    project_root: &Path,
+    options: &DiffContextOptions,
+) -> io::Result<DiffContext> {
+    let base = options.base.clone().unwrap_or_else(|| "HEAD".to_string());
+    let diff = git(
+        project_root,
+        &[
+            "diff",
+            "-U0",
+            "--relative",
+            "--no-renames",
+            "--no-color",
+            &base,
+            "--",
+        ],
+    )?;
+    let untracked = git(
+        project_root,
+        &["ls-files", "--others", "--exclude-standard"],
+    )?;
+
+    let conn = db::open_database(project_root)?;
+    let mut files = BTreeSet::new();
+    let mut changed: Vec<Node> = Vec::new();
+    let mut add_changed = |path: &str, hunks: Option<&[Hunk]>| -> io::Result<()> {
+        let nodes = outline_nodes(db::get_nodes_by_file(&conn, path, None)?);
+        if nodes.is_empty() && db::get_file_record(&conn, path)?.is_none() {
+            return Ok(());
+        }
+        files.insert(path.to_string());
+        for node in touched_nodes(&nodes, hunks) {
+            if !changed.iter().any(|n| n.id == node.id) {
+                changed.push(node.clone());
+            }
+        }
+        Ok(())
+    };
+    for (path, hunks) in parse_diff(&diff) {
+        add_changed(&path, Some(&hunks))?;
+    }
+    for path in untracked.lines().filter(|line| !line.is_empty()) {
+        add_changed(path, None)?;
+    }
+    changed.sort_by(|a, b| {
+        (&a.file_path, a.start_line, a.end_line).cmp(&(&b.file_path, b.start_line, b.end_line))
+    });
+
+    let mut symbols = Vec::with_capacity(changed.len());
+    for node in changed {
+        symbols.push(ChangedSymbol {
+            callers: calls_of(&conn, &node, true)?,
+            callees: calls_of(&conn, &node, false)?,
+            node,
+        });
+    }
+
+    let toml_cfg = config::load_toml_config(project_root).unwrap_or_default();
+    let code_blocks = if options.include_code.unwrap_or(true) {
+        let results: Vec<SearchResult> = symbols
+            .iter()
+            .map(|symbol| SearchResult {
+                node: symbol.node.clone(),
+                score: 1.0,
+                highlights: None,
+            })
+            .collect();
+        extract_code_blocks(
+            project_root,
+            &results,
+            options
+                .max_code_blocks
+                .unwrap_or(toml_cfg.context.max_code_blocks),
+            options
+                .max_code_block_size
+                .unwrap_or(toml_cfg.context.max_code_block_size),
+            &SensitiveFiles::from_config(&toml_cfg.indexing),
+            &mut StalenessCheck::new(project_root, &conn),
+        )
+    } else {
+        Vec::new()
+    };
+
+    Ok(DiffContext {
+        base,
+        files: files.into_iter().collect(),
+        changed: symbols,
+        code_blocks,
+    })
+}
+
+/// Render `context` as Markdown, or as JSON for [`ContextFormat::Json`].
+/// Other formats render as Markdown.
+pub fn render_diff_context(context: &DiffContext, format: ContextFormat) -> String {
+    match format {
+        ContextFormat::Json => serde_json::to_string_pretty(context).unwrap_or_default(),
+        _ => format_markdown(context),
+    }
+}
+
+/// The symbols of a file a change can be attributed to: no file, import,
+/// export, or parameter nodes, and no variables local to another symbol.
+fn outline_nodes(nodes: Vec<Node>) -> Vec<Node> {
+    let spans: Vec<(i64, i64, String)> = nodes
+        .iter()
+        .filter(|n| is_linkable(n.kind) && n.kind != NodeKind::Variable)
+        .map(|n| (n.start_line, n.end_line, n.id.clone()))
+        .collect();
+    nodes
+        .into_iter()
+        .filter(|n| is_linkable(n.kind))
+        .filter(|n| {
+            n.kind != NodeKind::Variable
+                || !spans.iter().any(|(start, end, id)| {
+                    *id != n.id && *start <= n.start_line && n.end_line <= *end
+                })
+        })
+        .collect()
+}
+
+/// The innermost of `nodes` each hunk touches; every node's innermost when
+/// `hunks` is `None` (a new file).
+fn touched_nodes<'a>(nodes: &'a [Node], hunks: Option<&[Hunk]>) -> Vec<&'a Node> {
+    let innermost = |touched: Vec<&'a Node>| -> Vec<&'a Node> {
+        touched
+            .iter()
+            .copied()
+            .filter(|outer| {
+                !touched.iter().any(|inner| {
+                    inner.id != outer.id
+                        && outer.start_line <= inner.start_line
+                        && inner.end_line <= outer.end_line
+                        && outer.end_line - outer.start_line > inner.end_line - inner.start_line
+                })
+            })
+            .collect()
+    };
+    let Some(hunks) = hunks else {
+        return innermost(nodes.iter().collect());
+    };
+    let mut touched: Vec<&Node> = Vec::new();
+    for hunk in hunks {
+        let hit = nodes
+            .iter()
+            .filter(|n| hunk.touches(n.start_line, n.end_line))
+            .collect();
+        for node in innermost(hit) {
+            if !touched.iter().any(|n| n.id == node.id) {
+                touched.push(node);
+            }
+        }
+    }
+    touched
+}
+
+/// Callers of `node` (`incoming`) or its callees, by valid `calls` edges.
+fn calls_of(conn: &Connection, node: &Node, incoming: bool) -> io::Result<Vec<Node>> {
+    let edges = if incoming {
+        db::get_edges_by_target(conn, &node.id, Some(EdgeKind::Calls), MAX_NEIGHBOURS)?
+    } else {
+        db::get_edges_by_source(conn, &node.id, Some(EdgeKind::Calls), MAX_NEIGHBOURS)?
+    };
+    let mut nodes: Vec<Node> = Vec::new();
+    for edge in edges {
+        let id = if incoming { edge.source } else { edge.target };
+        if nodes.iter().any(|n| n.id == id) {
+            continue;
+        }
+        let Some(other) = db::get_node_by_id(conn, &id)? else {
+            continue;
+        };
+        let (from, to) = if incoming {
+            (&other, node)
+        } else {
+            (node, &other)
+        };
+        if db::is_valid_call_edge(conn, from, to)? {
+            nodes.push(other);
+        }
+    }
+    Ok(nodes)
+}
+
+fn format_markdown(context: &DiffContext) -> String {
+    let mut lines = vec![
+        "## Diff Context".to_string(),
+        String::new(),
+        format!(
+            "**Changes:** working tree against `{}` — {} files, {} symbols",
+            context.base,
+            context.files.len(),
+            context.changed.len()
+        ),
+        String::new(),
+    ];
+
+    if !context.files.is_empty() {
+        lines.push("### Changed Files".to_string());
+        lines.push(String::new());
+        lines.extend(context.files.iter().map(|file| format!("- {file}")));
+        lines.push(String::new());
+    }
+
+    if !context.changed.is_empty() {
+        lines.push("### Changed Symbols".to_string());
+        lines.push(String::new());
+        let list = |nodes: &[Node]| {
+            if nodes.is_empty() {
+                "none".to_string()
+            } else {
+                nodes
+                    .iter()
+                    .map(|n| format!("{} ({}:{})", n.name, n.file_path, n.start_line))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        };
+        for symbol in &context.changed {
+            let node = &symbol.node;
+            lines.push(format!(
+                "- **{}** ({:?}) - {}:{}-{}",
+                node.name, node.kind, node.file_path, node.start_line, node.end_line
+            ));
+            lines.push(format!("  - Called by: {}", list(&symbol.callers)));
+            lines.push(format!("  - Calls: {}", list(&symbol.callees)));
+        }
+        lines.push(String::new());
+    }
+
+    if !context.code_blocks.is_empty() {
+        lines.push("### Code".to_string());
+        lines.push(String::new());
+        for block in &context.code_blocks {
+            let name = block.node.as_ref().map_or("", |n| n.name.as_str());
+            lines.push(format!("#### {name} ({})", block.file_path));
+            lines.push(String::new());
+            lines.push(format!("```{:?}", block.language));
+            lines.push(block.content.clone());
+            lines.push("```".to_string());
+            lines.push(String::new());
+        }
+    }
+
+    lines.join("\n")
+}
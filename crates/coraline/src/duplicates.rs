@@ -0,0 +1,396 @@
+#![forbid(unsafe_code)]
+
+//! Copy-pasted code for `coraline analyze duplicates`.
+//!
+//! Each function and method body is re-parsed and reduced to its
+//! tree-sitter tokens, with identifiers, strings, and numbers replaced by
+//! placeholders and comments dropped, so a copy with renamed variables or
+//! changed literals still matches. The tokens are cut into overlapping
+//! shingles of [`SHINGLE`] tokens, and two bodies are duplicates when the
+//! Jaccard similarity of their shingle sets reaches the threshold.
+//! Candidate pairs come from min-hash signatures banded for locality-sensitive
+//! hashing, so the bodies are never compared all against all. Duplicates
+//! are grouped into clusters, across files and languages alike.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use rusqlite::Connection;
+use tree_sitter::{Node as TsNode, Point};
+
+use crate::db;
+use crate::extraction::parse_source;
+use crate::types::{Node, NodeKind};
+
+/// Tokens per shingle.
+const SHINGLE: usize = 5;
+/// Min-hash bands, and rows per band; 8 × 4 finds a pair at similarity 0.85
+/// more than 99% of the time.
+const BANDS: usize = 8;
+const ROWS: usize = 4;
+
+/// What [`find_duplicates`] reports.
+#[derive(Debug, Clone, Copy)]
+pub struct DuplicateOptions {
+    /// Bodies with fewer normalized tokens are skipped.
+    pub min_tokens: usize,
+    /// Lowest Jaccard similarity of two bodies' shingles, from 0 to 1.
+    pub threshold: f64,
+}
+
+impl Default for DuplicateOptions {
+    fn default() -> Self {
+        Self {
+            min_tokens: 50,
+            threshold: 0.9,
+        }
+    }
+}
+
+/// Functions and methods whose bodies are copies of one another.
+#[derive(Debug, Clone)]
+pub struct DuplicateCluster {
+    /// By file and line.
+    pub members: Vec<Node>,
+    /// Normalized tokens of the largest member.
+    pub tokens: usize,
+    /// Lowest similarity among the pairs that formed the cluster.
+    pub similarity: f64,
+}
+
+/// The output of [`find_duplicates`].
+#[derive(Debug, Clone, Default)]
+pub struct DuplicatesReport {
+    /// Largest first: members times tokens.
+    pub clusters: Vec<DuplicateCluster>,
+    /// Bodies long enough to be compared.
+    pub functions: usize,
+}
+
+/// A body reduced for comparison.
+struct Fingerprint {
+    node: Node,
+    tokens: usize,
+    /// Sorted, without repeats.
+    shingles: Vec<u64>,
+    signature: [u64; BANDS * ROWS],
+}
+
+/// Find clusters of duplicated function and method bodies.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be queried. Files that
+/// cannot be read or parsed are skipped.
+pub fn find_duplicates(
+    project_root: &Path,
+    conn: &Connection,
+    options: DuplicateOptions,
+) -> std::io::Result<DuplicatesReport> {
+    let fingerprints = fingerprint_bodies(project_root, conn, options.min_tokens)?;
+
+    let mut parent: Vec<usize> = (0..fingerprints.len()).collect();
+    let mut weakest: HashMap<usize, f64> = HashMap::new();
+    for (a, b) in candidate_pairs(&fingerprints) {
+        let (Some(x), Some(y)) = (fingerprints.get(a), fingerprints.get(b)) else {
+            continue;
+        };
+        if nested(&x.node, &y.node) {
+            continue;
+        }
+        let similarity = jaccard(&x.shingles, &y.shingles);
+        if similarity < options.threshold {
+            continue;
+        }
+        let (root_a, root_b) = (find(&mut parent, a), find(&mut parent, b));
+        let lowest = [weakest.get(&root_a), weakest.get(&root_b)]
+            .into_iter()
+            .flatten()
+            .fold(similarity, |low, s| low.min(*s));
+        if let Some(slot) = parent.get_mut(root_b) {
+            *slot = root_a;
+        }
+        weakest.remove(&root_b);
+        weakest.insert(root_a, lowest);
+    }
+
+    let mut groups: HashMap<usize, Vec<&Fingerprint>> = HashMap::new();
+    for (index, fingerprint) in fingerprints.iter().enumerate() {
+        let root = find(&mut parent, index);
+        groups.entry(root).or_default().push(fingerprint);
+    }
+    let mut clusters: Vec<DuplicateCluster> = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| {
+            let mut nodes: Vec<Node> = members.iter().map(|f| f.node.clone()).collect();
+            nodes.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+            DuplicateCluster {
+                members: nodes,
+                tokens: members.iter().map(|f| f.tokens).max().unwrap_or_default(),
+                similarity: weakest.get(&root).copied().unwrap_or(1.0),
+            }
+        })
+        .collect();
+
+    // Closures inside duplicated functions are duplicated too; the
+    // enclosing copies say it already.
+    let reported: Vec<Node> = clusters
+        .iter()
+        .flat_map(|cluster| cluster.members.clone())
+        .collect();
+    clusters.retain(|cluster| {
+        !cluster.members.iter().all(|member| {
+            reported
+                .iter()
+                .any(|outer| outer.id != member.id && nested(outer, member))
+        })
+    });
+    let first = |cluster: &DuplicateCluster| {
+        cluster
+            .members
+            .first()
+            .map(|node| (node.file_path.clone(), node.start_line))
+    };
+    clusters.sort_by(|a, b| {
+        (b.members.len() * b.tokens)
+            .cmp(&(a.members.len() * a.tokens))
+            .then_with(|| first(a).cmp(&first(b)))
+    });
+
+    Ok(DuplicatesReport {
+        clusters,
+        functions: fingerprints.len(),
+    })
+}
+
+/// Fingerprints of every function and method body with at least
+/// `min_tokens` normalized tokens, by file.
+fn fingerprint_bodies(
+    project_root: &Path,
+    conn: &Connection,
+    min_tokens: usize,
+) -> std::io::Result<Vec<Fingerprint>> {
+    let mut by_file: HashMap<String, Vec<Node>> = HashMap::new();
+    for kind in [NodeKind::Function, NodeKind::Method] {
+        for node in db::get_nodes_by_kind(conn, kind)? {
+            by_file
+                .entry(node.file_path.clone())
+                .or_default()
+                .push(node);
+        }
+    }
+    let mut files: Vec<(String, Vec<Node>)> = by_file.into_iter().collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut fingerprints = Vec::new();
+    for (file, nodes) in files {
+        let Ok(source) = std::fs::read_to_string(project_root.join(&file)) else {
+            continue;
+        };
+        let Some(tree) = nodes
+            .first()
+            .and_then(|node| parse_source(&source, node.language))
+        else {
+            continue;
+        };
+        for node in nodes {
+            let Some(body) = syntax_of(tree.root_node(), &node) else {
+                continue;
+            };
+            let tokens = normalized_tokens(body, &source);
+            if tokens.len() < min_tokens.max(SHINGLE) {
+                continue;
+            }
+            let mut shingles: Vec<u64> = tokens.windows(SHINGLE).map(hash).collect();
+            shingles.sort_unstable();
+            shingles.dedup();
+            let signature = minhash(&shingles);
+            fingerprints.push(Fingerprint {
+                node,
+                tokens: tokens.len(),
+                shingles,
+                signature,
+            });
+        }
+    }
+
+    Ok(fingerprints)
+}
+
+/// Index pairs, smaller first and in order, of bodies sharing a whole band
+/// of their signature.
+fn candidate_pairs(fingerprints: &[Fingerprint]) -> Vec<(usize, usize)> {
+    let mut buckets: HashMap<(usize, &[u64]), Vec<usize>> = HashMap::new();
+    for (index, fingerprint) in fingerprints.iter().enumerate() {
+        for (band, rows) in fingerprint.signature.chunks(ROWS).enumerate() {
+            buckets.entry((band, rows)).or_default().push(index);
+        }
+    }
+    let mut candidates = HashSet::new();
+    for members in buckets.values() {
+        let mut rest = members.as_slice();
+        while let Some((&a, tail)) = rest.split_first() {
+            for &b in tail {
+                candidates.insert((a.min(b), a.max(b)));
+            }
+            rest = tail;
+        }
+    }
+    let mut pairs: Vec<(usize, usize)> = candidates.into_iter().collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// The syntax node `node` was extracted from.
+fn syntax_of<'t>(root: TsNode<'t>, node: &Node) -> Option<TsNode<'t>> {
+    let start = Point::new(
+        usize::try_from(node.start_line - 1).ok()?,
+        usize::try_from(node.start_column).ok()?,
+    );
+    let end = Point::new(
+        usize::try_from(node.end_line - 1).ok()?,
+        usize::try_from(node.end_column).ok()?,
+    );
+    root.named_descendant_for_point_range(start, end)
+        .filter(|syntax| syntax.start_position() == start)
+}
+
+/// The leaves of `node` with identifiers, strings, and numbers replaced by
+/// placeholders, and without comments.
+fn normalized_tokens<'s>(node: TsNode, source: &'s str) -> Vec<&'s str> {
+    let mut tokens = Vec::new();
+    let mut cursor = node.walk();
+    let mut stack = vec![node];
+    while let Some(current) = stack.pop() {
+        let kind = current.kind();
+        if kind.contains("comment") {
+            continue;
+        }
+        if current.child_count() == 0 {
+            tokens.push(normalize(current, kind, source));
+            continue;
+        }
+        let pushed = stack.len();
+        stack.extend(current.children(&mut cursor));
+        if let Some(children) = stack.get_mut(pushed..) {
+            children.reverse();
+        }
+    }
+    tokens
+}
+
+fn normalize<'s>(leaf: TsNode, kind: &'static str, source: &'s str) -> &'s str {
+    if !leaf.is_named() {
+        // Keywords and punctuation.
+        return leaf.utf8_text(source.as_bytes()).unwrap_or_default();
+    }
+    if kind.contains("identifier") || kind == "name" || kind == "variable" {
+        "$id"
+    } else if kind.contains("string") || kind.contains("char") || kind.contains("template") {
+        "$str"
+    } else if kind.contains("number") || kind.contains("integer") || kind.contains("float") {
+        "$num"
+    } else {
+        kind
+    }
+}
+
+fn hash(shingle: &[&str]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    shingle.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The minimum of each of [`BANDS`] × [`ROWS`] hash permutations over the
+/// shingles.
+fn minhash(shingles: &[u64]) -> [u64; BANDS * ROWS] {
+    let mut signature = [u64::MAX; BANDS * ROWS];
+    for &shingle in shingles {
+        for (seed, slot) in signature.iter_mut().enumerate() {
+            *slot = (*slot).min(mix(
+                shingle ^ (seed as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ));
+        }
+    }
+    signature
+}
+
+/// The `splitmix64` finalizer.
+const fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Jaccard similarity of two sorted sets.
+#[allow(clippy::cast_precision_loss)]
+fn jaccard(a: &[u64], b: &[u64]) -> f64 {
+    let (mut left, mut right) = (a.iter().peekable(), b.iter().peekable());
+    let mut shared = 0usize;
+    while let (Some(x), Some(y)) = (left.peek(), right.peek()) {
+        match x.cmp(y) {
+            std::cmp::Ordering::Less => {
+                left.next();
+            }
+            std::cmp::Ordering::Greater => {
+                right.next();
+            }
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                left.next();
+                right.next();
+            }
+        }
+    }
+    let union = a.len() + b.len() - shared;
+    if union == 0 {
+        0.0
+    } else {
+        shared as f64 / union as f64
+    }
+}
+
+/// Whether one of the two lies within the other.
+fn nested(a: &Node, b: &Node) -> bool {
+    let within = |inner: &Node, outer: &Node| {
+        outer.start_line <= inner.start_line && inner.end_line <= outer.end_line
+    };
+    a.file_path == b.file_path && (within(a, b) || within(b, a))
+}
+
+/// The root of `index` in the union-find forest `parent`, halving the path
+/// on the way.
+fn find(parent: &mut [usize], mut index: usize) -> usize {
+    while let Some(&next) = parent.get(index)
+        && next != index
+    {
+        let grandparent = parent.get(next).copied().unwrap_or(next);
+        if let Some(slot) = parent.get_mut(index) {
+            *slot = grandparent;
+        }
+        index = grandparent;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jaccard() {
+        assert!((jaccard(&[1, 2, 3, 4], &[2, 3, 4, 5]) - 0.6).abs() < f64::EPSILON);
+        assert!((jaccard(&[1, 2], &[1, 2]) - 1.0).abs() < f64::EPSILON);
+        assert!(jaccard(&[], &[]).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_minhash_of_equal_sets_is_equal() {
+        let shingles = [3, 17, 99, 1024];
+        assert_eq!(minhash(&shingles), minhash(&shingles));
+        assert_ne!(minhash(&shingles), minhash(&[4, 18, 100]));
+    }
+}
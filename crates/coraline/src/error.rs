@@ -0,0 +1,202 @@
+#![forbid(unsafe_code)]
+
+//! [`CoralineError`], the error of the `db`, `extraction`, `graph`, and
+//! `context` functions, so a caller can tell a locked database from a
+//! missing file from bad input.
+//!
+//! Modules that still return `std::io::Result` take these errors through
+//! `?`: the conversion keeps the `CoralineError` inside the `io::Error`,
+//! and converting back recovers it, so the variant survives the trip.
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::tools::ToolError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CoralineError {
+    /// Another connection holds the database lock (`SQLITE_BUSY` or
+    /// `SQLITE_LOCKED`) past the busy timeout.
+    #[error("database is locked: {0}")]
+    DatabaseLocked(#[source] rusqlite::Error),
+    /// Any other `SQLite` failure.
+    #[error("database error: {0}")]
+    Database(#[source] rusqlite::Error),
+    /// The project has no `.coraline/` index.
+    #[error("Coraline not initialized in {}", .0.display())]
+    NotInitialized(PathBuf),
+    /// The project configuration could not be read or written.
+    #[error("config error: {0}")]
+    Config(String),
+    /// A file, node, or checkpoint does not exist.
+    #[error("not found: {0}")]
+    NotFound(String),
+    /// Stored or supplied data could not be parsed.
+    #[error("failed to parse {what}: {message}")]
+    Parse { what: String, message: String },
+    /// An argument was malformed, such as a git revision that does not
+    /// resolve.
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+    /// Any other filesystem or process failure.
+    #[error(transparent)]
+    Io(io::Error),
+}
+
+pub type Result<T, E = CoralineError> = std::result::Result<T, E>;
+
+impl CoralineError {
+    /// A stable name for the variant. An [`Io`](Self::Io) error for a
+    /// missing file is `not_found`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotInitialized(_) => "not_initialized",
+            Self::Config(_) => "config_error",
+            Self::DatabaseLocked(_) => "database_locked",
+            Self::Database(_) => "database_error",
+            Self::NotFound(_) => "not_found",
+            Self::Io(err) if err.kind() == io::ErrorKind::NotFound => "not_found",
+            Self::Parse { .. } => "parse_error",
+            Self::InvalidInput(_) => "invalid_params",
+            Self::Io(_) => "io_error",
+        }
+    }
+
+    /// Whether retrying after the other writer finishes may succeed.
+    pub const fn is_locked(&self) -> bool {
+        matches!(self, Self::DatabaseLocked(_))
+    }
+
+    pub fn parse(what: impl Into<String>, message: &impl ToString) -> Self {
+        Self::Parse {
+            what: what.into(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for CoralineError {
+    fn from(err: rusqlite::Error) -> Self {
+        match err.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) => {
+                Self::DatabaseLocked(err)
+            }
+            _ => Self::Database(err),
+        }
+    }
+}
+
+impl From<serde_json::Error> for CoralineError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::parse("JSON", &err)
+    }
+}
+
+impl From<io::Error> for CoralineError {
+    fn from(err: io::Error) -> Self {
+        err.downcast::<Self>().unwrap_or_else(Self::Io)
+    }
+}
+
+impl From<CoralineError> for io::Error {
+    fn from(err: CoralineError) -> Self {
+        let kind = match &err {
+            CoralineError::Io(_) => return unwrap_io(err),
+            CoralineError::DatabaseLocked(_) => io::ErrorKind::ResourceBusy,
+            CoralineError::NotInitialized(_) | CoralineError::NotFound(_) => {
+                io::ErrorKind::NotFound
+            }
+            CoralineError::Config(_) | CoralineError::Database(_) => io::ErrorKind::Other,
+            CoralineError::Parse { .. } => io::ErrorKind::InvalidData,
+            CoralineError::InvalidInput(_) => io::ErrorKind::InvalidInput,
+        };
+        Self::new(kind, err)
+    }
+}
+
+fn unwrap_io(err: CoralineError) -> io::Error {
+    match err {
+        CoralineError::Io(err) => err,
+        other => io::Error::other(other),
+    }
+}
+
+/// The code is the variant's [`code`](CoralineError::code), except that
+/// plain I/O and `SQLite` failures are `internal_error` and a missing file or
+/// node is `resource_not_found`: the MCP server reads `not_found` as an
+/// unknown tool.
+impl From<CoralineError> for ToolError {
+    fn from(err: CoralineError) -> Self {
+        let code = match err.code() {
+            "database_error" | "io_error" => "internal_error",
+            "not_found" => "resource_not_found",
+            code => code,
+        };
+        Self::new(code, err.to_string())
+    }
+}
+
+impl ToolError {
+    /// `err` as a tool error whose message starts with `context`, coded by
+    /// its [`CoralineError`] variant.
+    pub fn failed(context: &str, err: impl Into<CoralineError>) -> Self {
+        let Self { code, message } = err.into().into();
+        Self::new(code, format!("{context}: {message}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sqlite_error(code: std::ffi::c_int) -> rusqlite::Error {
+        rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(code), None)
+    }
+
+    #[test]
+    fn test_busy_and_locked_sqlite_errors_are_database_locked() {
+        let busy = CoralineError::from(sqlite_error(rusqlite::ffi::SQLITE_BUSY));
+        assert!(busy.is_locked());
+        assert_eq!(busy.code(), "database_locked");
+        let locked = CoralineError::from(sqlite_error(rusqlite::ffi::SQLITE_LOCKED));
+        assert!(locked.is_locked());
+        let other = CoralineError::from(sqlite_error(rusqlite::ffi::SQLITE_CORRUPT));
+        assert_eq!(other.code(), "database_error");
+    }
+
+    #[test]
+    fn test_variant_survives_io_error_round_trip() {
+        let locked = CoralineError::from(sqlite_error(rusqlite::ffi::SQLITE_BUSY));
+        let io_err = io::Error::from(locked);
+        assert_eq!(io_err.kind(), io::ErrorKind::ResourceBusy);
+        assert!(CoralineError::from(io_err).is_locked());
+
+        let missing = io::Error::new(io::ErrorKind::NotFound, "gone");
+        let err = CoralineError::from(missing);
+        assert_eq!(err.code(), "not_found");
+        assert_eq!(io::Error::from(err).kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_tool_error_codes() {
+        let code = |err: CoralineError| ToolError::from(err).code;
+        assert_eq!(
+            code(CoralineError::InvalidInput("x".into())),
+            "invalid_params"
+        );
+        assert_eq!(
+            code(CoralineError::NotFound("x".into())),
+            "resource_not_found"
+        );
+        assert_eq!(code(CoralineError::parse("JSON", &"eof")), "parse_error");
+        assert_eq!(code(io::Error::other("disk").into()), "internal_error");
+
+        let failed = ToolError::failed("Search failed", sqlite_error(rusqlite::ffi::SQLITE_BUSY));
+        assert_eq!(failed.code, "database_locked");
+        assert!(
+            failed
+                .message
+                .starts_with("Search failed: database is locked")
+        );
+    }
+}
@@ -0,0 +1,548 @@
+#![forbid(unsafe_code)]
+
+//! Incremental export of graph changes.
+//!
+//! A *checkpoint* records a point in time, together with the set of tracked
+//! files at that moment, under a name in `.coraline/checkpoints.json`.
+//! Exporting since a checkpoint emits only the files, nodes, and edges written
+//! after it, plus the files that have disappeared, so downstream consumers
+//! (search services, dashboards) can apply increments instead of re-ingesting
+//! full dumps.
+//!
+//! Deltas serialise as JSON, via [`to_cypher`] as Cypher statements for
+//! loading into Neo4j or Memgraph, or (with the `parquet` feature) via
+//! [`write_parquet`] as Parquet tables for pandas, Polars, or `DuckDB`.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::db;
+use crate::sensitive::SensitiveFiles;
+use crate::types::{Edge, EdgeKind, FileRecord, Node, NodeKind};
+
+pub const CHECKPOINTS_FILENAME: &str = "checkpoints.json";
+
+/// Label applied to every exported node, alongside its kind label.
+pub const CYPHER_NODE_LABEL: &str = "CodeNode";
+
+/// A named point in time that later exports can be taken relative to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub name: String,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: i64,
+    /// Tracked file paths at checkpoint time, used to report removals.
+    pub files: Vec<String>,
+}
+
+/// Everything that changed in the graph within `(since, until]`.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphDelta {
+    pub since: i64,
+    pub until: i64,
+    /// Files (re-)indexed after `since`.
+    pub files: Vec<FileRecord>,
+    /// Files tracked at the checkpoint that are no longer tracked. Always
+    /// empty when exporting from a raw timestamp.
+    pub removed_files: Vec<String>,
+    /// Nodes written after `since`; replaces all nodes of the listed `files`.
+    pub nodes: Vec<Node>,
+    /// Edges from or to the exported nodes. Deleting the nodes of `files`
+    /// drops the edges into them from unchanged files too, so those are
+    /// re-created along with the rest.
+    pub edges: Vec<Edge>,
+}
+
+pub fn checkpoints_path(project_root: &Path) -> PathBuf {
+    project_root.join(".coraline").join(CHECKPOINTS_FILENAME)
+}
+
+/// Load all recorded checkpoints, keyed by name. Returns an empty map when
+/// none have been recorded yet.
+pub fn load_checkpoints(project_root: &Path) -> std::io::Result<BTreeMap<String, Checkpoint>> {
+    let path = checkpoints_path(project_root);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let raw = fs::read_to_string(&path)?;
+    serde_json::from_str(&raw)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+}
+
+/// Look up a checkpoint by name.
+pub fn get_checkpoint(project_root: &Path, name: &str) -> std::io::Result<Option<Checkpoint>> {
+    Ok(load_checkpoints(project_root)?.remove(name))
+}
+
+/// Record (or overwrite) checkpoint `name` at time `at`, snapshotting the
+/// currently tracked file list.
+pub fn record_checkpoint(
+    project_root: &Path,
+    conn: &Connection,
+    name: &str,
+    at: i64,
+) -> std::io::Result<Checkpoint> {
+    let mut files: Vec<String> = db::list_files(conn)?.into_iter().map(|f| f.path).collect();
+    files.sort();
+
+    let checkpoint = Checkpoint {
+        name: name.to_string(),
+        created_at: at,
+        files,
+    };
+
+    let mut all = load_checkpoints(project_root)?;
+    all.insert(name.to_string(), checkpoint.clone());
+    let raw = serde_json::to_string_pretty(&all)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    fs::write(checkpoints_path(project_root), raw)?;
+
+    Ok(checkpoint)
+}
+
+/// Collect graph changes written after `since`.
+///
+/// When `baseline_files` (the file list of a checkpoint) is given, tracked
+/// files missing from the current index are reported in `removed_files`.
+/// Files matched by `sensitive`, with their nodes and the edges touching
+/// them, are left out.
+pub fn export_delta(
+    conn: &Connection,
+    since: i64,
+    baseline_files: Option<&[String]>,
+    sensitive: &SensitiveFiles,
+) -> std::io::Result<GraphDelta> {
+    let until = now_millis();
+
+    let removed_files = match baseline_files {
+        Some(baseline) => {
+            let current: HashSet<String> =
+                db::list_files(conn)?.into_iter().map(|f| f.path).collect();
+            baseline
+                .iter()
+                .filter(|path| !current.contains(*path))
+                .cloned()
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    let mut files = db::list_files_indexed_since(conn, since)?;
+    files.retain(|file| !sensitive.is_sensitive(&file.path));
+    let (nodes, hidden): (Vec<Node>, Vec<Node>) = db::get_nodes_updated_since(conn, since)?
+        .into_iter()
+        .partition(|node| !sensitive.is_sensitive(&node.file_path));
+    let hidden: HashSet<String> = hidden.into_iter().map(|node| node.id).collect();
+    let mut edges = db::get_edges_touching_nodes_updated_since(conn, since)?;
+    edges.retain(|edge| !hidden.contains(&edge.source) && !hidden.contains(&edge.target));
+
+    Ok(GraphDelta {
+        since,
+        until,
+        files,
+        removed_files,
+        nodes,
+        edges,
+    })
+}
+
+/// Render `delta` as Cypher statements, one per line.
+///
+/// Nodes of re-indexed and removed files are detached and deleted first, so
+/// applying successive deltas in order keeps the target database in sync.
+/// Nodes are labelled `CodeNode` plus their kind (`Function`, `TypeAlias`,
+/// ...); relationships are named after the edge kind (`CALLS`, `TYPE_OF`, ...).
+pub fn to_cypher(delta: &GraphDelta) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "// Coraline graph export ({} → {}). Index CodeNode.id first for large graphs:",
+        delta.since, delta.until
+    );
+    let _ = writeln!(
+        out,
+        "//   Neo4j:    CREATE INDEX code_node_id IF NOT EXISTS FOR (n:{CYPHER_NODE_LABEL}) ON (n.id);"
+    );
+    let _ = writeln!(
+        out,
+        "//   Memgraph: CREATE INDEX ON :{CYPHER_NODE_LABEL}(id);"
+    );
+
+    let replaced: BTreeSet<&str> = delta
+        .files
+        .iter()
+        .map(|f| f.path.as_str())
+        .chain(delta.removed_files.iter().map(String::as_str))
+        .collect();
+    if !replaced.is_empty() {
+        let paths: Vec<String> = replaced.iter().map(|p| cypher_string(p)).collect();
+        let _ = writeln!(
+            out,
+            "MATCH (n:{CYPHER_NODE_LABEL}) WHERE n.file_path IN [{}] DETACH DELETE n;",
+            paths.join(", ")
+        );
+    }
+
+    for node in &delta.nodes {
+        let _ = writeln!(
+            out,
+            "CREATE (:{CYPHER_NODE_LABEL}:{} {});",
+            node_label(node.kind),
+            node_properties(node)
+        );
+    }
+
+    for edge in &delta.edges {
+        let line = edge
+            .line
+            .map_or_else(String::new, |line| format!(" {{line: {line}}}"));
+        let _ = writeln!(
+            out,
+            "MATCH (a:{CYPHER_NODE_LABEL} {{id: {}}}), (b:{CYPHER_NODE_LABEL} {{id: {}}}) CREATE (a)-[:{}{line}]->(b);",
+            cypher_string(&edge.source),
+            cypher_string(&edge.target),
+            relationship_type(edge.kind)
+        );
+    }
+
+    out
+}
+
+fn node_properties(node: &Node) -> String {
+    let mut props = vec![
+        format!("id: {}", cypher_string(&node.id)),
+        format!("name: {}", cypher_string(&node.name)),
+        format!("qualified_name: {}", cypher_string(&node.qualified_name)),
+        format!("kind: {}", cypher_string(&snake_case_name(&node.kind))),
+        format!("file_path: {}", cypher_string(&node.file_path)),
+        format!(
+            "language: {}",
+            cypher_string(&snake_case_name(&node.language))
+        ),
+        format!("start_line: {}", node.start_line),
+        format!("end_line: {}", node.end_line),
+        format!("is_exported: {}", node.is_exported),
+        format!("is_async: {}", node.is_async),
+        format!("is_static: {}", node.is_static),
+    ];
+    if let Some(signature) = &node.signature {
+        props.push(format!("signature: {}", cypher_string(signature)));
+    }
+    if let Some(docstring) = &node.docstring {
+        props.push(format!("docstring: {}", cypher_string(docstring)));
+    }
+    if let Some(visibility) = &node.visibility {
+        props.push(format!(
+            "visibility: {}",
+            cypher_string(&snake_case_name(visibility))
+        ));
+    }
+    format!("{{{}}}", props.join(", "))
+}
+
+/// `type_alias` → `TypeAlias`.
+fn node_label(kind: NodeKind) -> String {
+    snake_case_name(&kind)
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect()
+}
+
+/// `type_of` → `TYPE_OF`.
+fn relationship_type(kind: EdgeKind) -> String {
+    snake_case_name(&kind).to_ascii_uppercase()
+}
+
+/// The serde (`snake_case`) name of a unit enum variant.
+fn snake_case_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Single-quoted Cypher string literal.
+fn cypher_string(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len() + 2);
+    out.push('\'');
+    for c in raw.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// File names written by [`write_parquet`], one table each.
+pub const PARQUET_TABLES: [&str; 4] = [
+    "nodes.parquet",
+    "edges.parquet",
+    "files.parquet",
+    "removed_files.parquet",
+];
+
+/// Write `delta` as Snappy-compressed Parquet tables into `dir` (created if
+/// missing): `nodes`, `edges`, `files`, and `removed_files`.
+///
+/// Enum columns hold their `snake_case` names; nested values (decorators, type
+/// parameters, edge metadata, extraction errors) are JSON strings. Returns the
+/// paths written, in [`PARQUET_TABLES`] order.
+#[cfg(feature = "parquet")]
+pub fn write_parquet(delta: &GraphDelta, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let tables = [
+        columns::nodes(&delta.nodes),
+        columns::edges(&delta.edges),
+        columns::files(&delta.files),
+        vec![("path", columns::text(delta.removed_files.iter()), false)],
+    ];
+
+    let mut written = Vec::with_capacity(PARQUET_TABLES.len());
+    for (name, columns) in PARQUET_TABLES.iter().zip(tables) {
+        let path = dir.join(name);
+        write_parquet_table(&path, columns)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// The Arrow columns of the Parquet tables, each a name, its values, and
+/// whether they may be null.
+#[cfg(feature = "parquet")]
+mod columns {
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, BooleanArray, Int64Array, StringArray, UInt64Array};
+    use serde::Serialize;
+
+    use super::snake_case_name;
+    use crate::types::{Edge, FileRecord, Node};
+
+    pub type Column = (&'static str, ArrayRef, bool);
+
+    pub fn nodes(nodes: &[Node]) -> Vec<Column> {
+        vec![
+            ("id", text(nodes.iter().map(|n| n.id.as_str())), false),
+            (
+                "kind",
+                text(nodes.iter().map(|n| snake_case_name(&n.kind))),
+                false,
+            ),
+            ("name", text(nodes.iter().map(|n| n.name.as_str())), false),
+            (
+                "qualified_name",
+                text(nodes.iter().map(|n| n.qualified_name.as_str())),
+                false,
+            ),
+            (
+                "file_path",
+                text(nodes.iter().map(|n| n.file_path.as_str())),
+                false,
+            ),
+            (
+                "language",
+                text(nodes.iter().map(|n| snake_case_name(&n.language))),
+                false,
+            ),
+            (
+                "start_line",
+                ints(nodes.iter().map(|n| n.start_line)),
+                false,
+            ),
+            ("end_line", ints(nodes.iter().map(|n| n.end_line)), false),
+            (
+                "start_column",
+                ints(nodes.iter().map(|n| n.start_column)),
+                false,
+            ),
+            (
+                "end_column",
+                ints(nodes.iter().map(|n| n.end_column)),
+                false,
+            ),
+            (
+                "docstring",
+                opt_text(nodes.iter().map(|n| n.docstring.clone())),
+                true,
+            ),
+            (
+                "signature",
+                opt_text(nodes.iter().map(|n| n.signature.clone())),
+                true,
+            ),
+            (
+                "visibility",
+                opt_text(
+                    nodes
+                        .iter()
+                        .map(|n| n.visibility.as_ref().map(snake_case_name)),
+                ),
+                true,
+            ),
+            (
+                "is_exported",
+                bools(nodes.iter().map(|n| n.is_exported)),
+                false,
+            ),
+            ("is_async", bools(nodes.iter().map(|n| n.is_async)), false),
+            ("is_static", bools(nodes.iter().map(|n| n.is_static)), false),
+            (
+                "is_abstract",
+                bools(nodes.iter().map(|n| n.is_abstract)),
+                false,
+            ),
+            (
+                "decorators",
+                opt_text(nodes.iter().map(|n| json(n.decorators.as_ref()))),
+                true,
+            ),
+            (
+                "type_parameters",
+                opt_text(nodes.iter().map(|n| json(n.type_parameters.as_ref()))),
+                true,
+            ),
+            (
+                "updated_at",
+                ints(nodes.iter().map(|n| n.updated_at)),
+                false,
+            ),
+        ]
+    }
+
+    pub fn edges(edges: &[Edge]) -> Vec<Column> {
+        vec![
+            (
+                "source",
+                text(edges.iter().map(|e| e.source.as_str())),
+                false,
+            ),
+            (
+                "target",
+                text(edges.iter().map(|e| e.target.as_str())),
+                false,
+            ),
+            (
+                "kind",
+                text(edges.iter().map(|e| snake_case_name(&e.kind))),
+                false,
+            ),
+            ("line", opt_ints(edges.iter().map(|e| e.line)), true),
+            ("column", opt_ints(edges.iter().map(|e| e.column)), true),
+            (
+                "metadata",
+                opt_text(edges.iter().map(|e| json(e.metadata.as_ref()))),
+                true,
+            ),
+        ]
+    }
+
+    pub fn files(files: &[FileRecord]) -> Vec<Column> {
+        vec![
+            ("path", text(files.iter().map(|f| f.path.as_str())), false),
+            (
+                "content_hash",
+                text(files.iter().map(|f| f.content_hash.as_str())),
+                false,
+            ),
+            (
+                "language",
+                text(files.iter().map(|f| snake_case_name(&f.language))),
+                false,
+            ),
+            (
+                "size",
+                Arc::new(files.iter().map(|f| f.size).collect::<UInt64Array>()),
+                false,
+            ),
+            (
+                "modified_at",
+                ints(files.iter().map(|f| f.modified_at)),
+                false,
+            ),
+            (
+                "indexed_at",
+                ints(files.iter().map(|f| f.indexed_at)),
+                false,
+            ),
+            (
+                "node_count",
+                ints(files.iter().map(|f| f.node_count)),
+                false,
+            ),
+            (
+                "errors",
+                opt_text(files.iter().map(|f| json(f.errors.as_ref()))),
+                true,
+            ),
+        ]
+    }
+
+    pub fn text<S: AsRef<str>>(values: impl Iterator<Item = S>) -> ArrayRef {
+        Arc::new(values.map(Some).collect::<StringArray>())
+    }
+
+    fn opt_text(values: impl Iterator<Item = Option<String>>) -> ArrayRef {
+        Arc::new(values.collect::<StringArray>())
+    }
+
+    fn ints(values: impl Iterator<Item = i64>) -> ArrayRef {
+        Arc::new(values.collect::<Int64Array>())
+    }
+
+    fn opt_ints(values: impl Iterator<Item = Option<i64>>) -> ArrayRef {
+        Arc::new(values.collect::<Int64Array>())
+    }
+
+    fn bools(values: impl Iterator<Item = bool>) -> ArrayRef {
+        Arc::new(values.map(Some).collect::<BooleanArray>())
+    }
+
+    fn json<T: Serialize>(value: Option<&T>) -> Option<String> {
+        value.and_then(|v| serde_json::to_string(v).ok())
+    }
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet_table(
+    path: &Path,
+    columns: Vec<(&str, arrow_array::ArrayRef, bool)>,
+) -> std::io::Result<()> {
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+
+    let batch = arrow_array::RecordBatch::try_from_iter_with_nullable(columns)
+        .map_err(std::io::Error::other)?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(fs::File::create(path)?, batch.schema(), Some(props))
+        .map_err(std::io::Error::other)?;
+    writer.write(&batch).map_err(std::io::Error::other)?;
+    writer.close().map_err(std::io::Error::other)?;
+    Ok(())
+}
+
+/// Milliseconds since the Unix epoch, the unit of checkpoint times.
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+}
@@ -18,25 +18,40 @@
     clippy::used_underscore_binding
 )]
 
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use tree_sitter::{Node as TsNode, Parser};
 
+use crate::annotations;
+use crate::branches;
 use crate::config::is_language_supported;
+use crate::config_keys;
 use crate::db;
+use crate::error::{CoralineError, Result};
+use crate::history;
+use crate::implementations;
+use crate::lifecycle::{HookEvent, IndexMode, LifecycleHooks};
+use crate::models;
 use crate::resolution::ReferenceResolver;
+use crate::sensitive::SensitiveFiles;
+use crate::todos;
 use crate::types::{
     CodeGraphConfig, Edge, EdgeKind, ExtractionError, ExtractionErrorSeverity, FileRecord,
     Language, Node, NodeKind, UnresolvedReference,
 };
-use crate::utils::{hash_sha256, node_id_for_symbol};
+use crate::utils::{self, hash_sha256, node_id_for_symbol, path_key, span_hash};
+use crate::worktree::{self, CachedParse, ParseCache};
 use tracing::{debug, info, warn};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IndexPhase {
     Scanning,
     Parsing,
@@ -52,7 +67,7 @@ pub struct IndexProgress {
     pub current_file: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct IndexResult {
     pub success: bool,
     pub files_indexed: usize,
@@ -63,17 +78,25 @@ pub struct IndexResult {
     pub duration_ms: u128,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncResult {
     pub files_checked: usize,
     pub files_added: usize,
     pub files_modified: usize,
     pub files_removed: usize,
     pub nodes_updated: usize,
+    /// Nodes given a fresh embedding after the sync.
+    pub nodes_embedded: usize,
+    /// Tracked files read and hashed because their size or modification
+    /// time changed; the rest are skipped on metadata alone.
+    pub files_hashed: usize,
+    /// Changed files that could not be re-indexed; they keep their previous
+    /// records and are retried on the next sync.
+    pub files_failed: usize,
     pub duration_ms: u128,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SyncStatus {
     pub files_checked: usize,
     pub files_added: usize,
@@ -92,6 +115,7 @@ struct ParsedFile {
     nodes: Vec<Node>,
     edges: Vec<Edge>,
     unresolved_refs: Vec<UnresolvedReference>,
+    span_hashes: Vec<(String, String)>,
     node_count: usize,
     edge_count: usize,
 }
@@ -101,6 +125,7 @@ fn parse_file_only(
     config: &CodeGraphConfig,
     existing_hashes: &std::collections::HashMap<String, String>,
     relative_path: &str,
+    cache: CacheUse<'_>,
 ) -> Option<ParsedFile> {
     let full_path = project_root.join(relative_path);
     let content = fs::read_to_string(&full_path).ok()?;
@@ -122,49 +147,16 @@ fn parse_file_only(
         return None; // unchanged
     }
 
-    let file_name = Path::new(relative_path)
-        .file_name()
-        .and_then(|v| v.to_str())
-        .unwrap_or(relative_path);
-    let qualified_name = relative_path.to_string();
-    let node_id = node_id_for_symbol(relative_path, "file", &qualified_name, 1, 0);
-    let file_node_id = node_id.clone();
-
     let now_ms = now_millis();
-    let mut nodes = Vec::new();
-    let file_node = Node {
-        id: node_id,
-        kind: NodeKind::File,
-        name: file_name.to_string(),
-        qualified_name,
-        file_path: relative_path.to_string(),
-        language,
-        start_line: 1,
-        end_line: 1,
-        start_column: 0,
-        end_column: 0,
-        docstring: None,
-        signature: None,
-        visibility: None,
-        is_exported: false,
-        is_async: false,
-        is_static: false,
-        is_abstract: false,
-        decorators: None,
-        type_parameters: None,
-        updated_at: now_ms,
-    };
-    nodes.push(file_node);
-
-    let (mut extracted_nodes, edges, unresolved_refs) = extract_nodes(
+    let (nodes, edges, unresolved_refs) = extract_file(
         project_root,
         relative_path,
         &content,
+        &content_hash,
         language,
         now_ms,
-        &file_node_id,
+        cache,
     );
-    nodes.append(&mut extracted_nodes);
 
     let metadata = fs::metadata(&full_path).ok()?;
     let file_record = FileRecord {
@@ -172,16 +164,13 @@ fn parse_file_only(
         content_hash,
         language,
         size: metadata.len(),
-        modified_at: metadata
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-            .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX)),
+        modified_at: file_mtime_millis(&metadata),
         indexed_at: now_ms,
         node_count: nodes.len() as i64,
         errors: None,
     };
 
+    let span_hashes = span_hashes(&nodes, &content);
     let node_count = nodes.len();
     let edge_count = edges.len();
     Some(ParsedFile {
@@ -189,6 +178,7 @@ fn parse_file_only(
         nodes,
         edges,
         unresolved_refs,
+        span_hashes,
         node_count,
         edge_count,
     })
@@ -199,7 +189,19 @@ pub fn index_all(
     config: &CodeGraphConfig,
     force: bool,
     on_progress: Option<&dyn Fn(IndexProgress)>,
-) -> std::io::Result<IndexResult> {
+) -> Result<IndexResult> {
+    let hooks = LifecycleHooks::from_config(project_root);
+    index_all_with_hooks(project_root, config, force, on_progress, &hooks)
+}
+
+/// [`index_all`] firing `hooks` instead of only the configured commands.
+pub fn index_all_with_hooks(
+    project_root: &Path,
+    config: &CodeGraphConfig,
+    force: bool,
+    on_progress: Option<&dyn Fn(IndexProgress)>,
+    hooks: &LifecycleHooks,
+) -> Result<IndexResult> {
     let span = tracing::info_span!("index_all", ?force, root = %project_root.display());
     let _enter = span.enter();
     let start = Instant::now();
@@ -208,6 +210,23 @@ pub fn index_all(
     let mut nodes_created = 0;
     let mut edges_created = 0;
 
+    let mut conn = db::open_database_for(project_root, db::Workload::Bulk)?;
+    if let Err(err) = branches::switch(project_root, &mut conn) {
+        warn!(error = %err, "failed to switch branch graph");
+    }
+    let parse_cache = ParseCache::for_project(project_root);
+    let cache_use = match &parse_cache {
+        Some(cache) if force => CacheUse::Refresh(cache),
+        cache => CacheUse::from(cache.as_ref()),
+    };
+    hooks.fire(
+        project_root,
+        &conn,
+        &HookEvent::PreScan {
+            mode: IndexMode::Full,
+        },
+    );
+
     let files = scan_directory(project_root, config, |current, file| {
         if let Some(cb) = on_progress {
             cb(IndexProgress {
@@ -219,9 +238,19 @@ pub fn index_all(
         }
     });
 
-    let mut conn = db::open_database(project_root)?;
     if force {
         db::clear_database(&conn)?;
+    } else {
+        discard_inconsistent_files(&mut conn)?;
+    }
+    if let Err(err) = annotations::import_codeowners(project_root, &mut conn) {
+        warn!(error = %err, "failed to import CODEOWNERS");
+    }
+    if let Err(err) = annotations::import_submodules(project_root, &mut conn) {
+        warn!(error = %err, "failed to tag submodules");
+    }
+    if let Err(err) = annotations::import_packages(project_root, &mut conn) {
+        warn!(error = %err, "failed to tag workspace packages");
     }
 
     // Pre-fetch existing file hashes to avoid DB access in the parallel parse phase.
@@ -246,10 +275,12 @@ pub fn index_all(
     info!(total_files = files.len(), "starting parallel parse phase");
 
     // Phase 1: Parse all files in parallel (CPU-bound, no DB access).
-    let parsed: Vec<ParsedFile> = files
-        .par_iter()
-        .filter_map(|file| parse_file_only(project_root, config, &existing_hashes, file))
-        .collect();
+    let parse =
+        |file: &String| parse_file_only(project_root, config, &existing_hashes, file, cache_use);
+    let parsed: Vec<ParsedFile> = match on_progress {
+        Some(cb) => parse_with_progress(&files, parse, cb),
+        None => files.par_iter().filter_map(parse).collect(),
+    };
 
     let parsed_total = parsed.len();
     let files_skipped = files.len().saturating_sub(parsed_total);
@@ -270,8 +301,16 @@ pub fn index_all(
 
     // Phase 2: Store results sequentially (SQLite does not support concurrent writes).
     for (idx, parsed_file) in parsed.into_iter().enumerate() {
-        // Delete the old record before inserting the new batch so foreign keys are clean.
-        let _ = db::delete_file(&mut conn, &parsed_file.file_record.path);
+        // A case-only rename leaves the old record under its previous spelling.
+        let stale_path = if config.path_case.is_case_insensitive() {
+            db::get_file_record_ignore_case(&conn, &parsed_file.file_record.path)
+                .ok()
+                .flatten()
+                .map(|stale| stale.path)
+                .filter(|stale| *stale != parsed_file.file_record.path)
+        } else {
+            None
+        };
 
         if let Some(cb) = on_progress {
             cb(IndexProgress {
@@ -284,17 +323,31 @@ pub fn index_all(
 
         let path = parsed_file.file_record.path.clone();
         debug!(file = %path, nodes = parsed_file.node_count, edges = parsed_file.edge_count, "storing file");
-        match db::store_file_batch(
+        // Old rows are replaced in the same transaction as the new ones.
+        let replaced: Vec<&str> = stale_path.iter().map(String::as_str).collect();
+        match db::replace_file(
             &mut conn,
+            &replaced,
             &parsed_file.file_record,
             &parsed_file.nodes,
             &parsed_file.edges,
             &parsed_file.unresolved_refs,
+            &parsed_file.span_hashes,
         ) {
             Ok(()) => {
                 files_indexed += 1;
                 nodes_created += parsed_file.node_count;
                 edges_created += parsed_file.edge_count;
+                hooks.fire(
+                    project_root,
+                    &conn,
+                    &HookEvent::PostFileExtract {
+                        mode: IndexMode::Full,
+                        file: &parsed_file.file_record,
+                        nodes: &parsed_file.nodes,
+                        edges: &parsed_file.edges,
+                    },
+                );
             }
             Err(err) => {
                 warn!(file = %path, error = %err, "failed to store file");
@@ -309,16 +362,53 @@ pub fn index_all(
         }
     }
 
-    if let Err(err) = ReferenceResolver::resolve_unresolved(&mut conn, project_root, 10_000) {
-        warn!(error = %err, "reference resolver failed");
-        errors.push(ExtractionError {
-            message: format!("Resolver failed: {err}"),
-            line: None,
-            column: None,
-            severity: ExtractionErrorSeverity::Warning,
-            code: Some("resolver_failed".to_string()),
+    hooks.fire(
+        project_root,
+        &conn,
+        &HookEvent::PostIndex {
+            mode: IndexMode::Full,
+            files: files_indexed,
+            files_removed: 0,
+            nodes: nodes_created,
+            edges: edges_created,
+        },
+    );
+
+    if let Some(cb) = on_progress {
+        cb(IndexProgress {
+            phase: IndexPhase::Resolving,
+            current: 0,
+            total: 0,
+            current_file: None,
         });
     }
+    match ReferenceResolver::resolve_unresolved(&mut conn, project_root, 10_000) {
+        Ok(resolved) => hooks.fire(
+            project_root,
+            &conn,
+            &HookEvent::PostResolve {
+                mode: IndexMode::Full,
+                resolved: resolved.resolved,
+                remaining: resolved.remaining,
+            },
+        ),
+        Err(err) => {
+            warn!(error = %err, "reference resolver failed");
+            errors.push(ExtractionError {
+                message: format!("Resolver failed: {err}"),
+                line: None,
+                column: None,
+                severity: ExtractionErrorSeverity::Warning,
+                code: Some("resolver_failed".to_string()),
+            });
+        }
+    }
+    if let Err(err) = models::link_queries(project_root, &mut conn) {
+        warn!(error = %err, "failed to link ORM models to their queries");
+    }
+
+    record_churn_if_enabled(project_root);
+    crate::staleness::note_index_changed(project_root);
 
     info!(
         files_indexed,
@@ -342,25 +432,59 @@ pub fn index_all(
     })
 }
 
+/// Run `parse` over `files` in parallel, reporting each finished file to
+/// `on_progress` from the calling thread (the callback need not be `Sync`).
+fn parse_with_progress(
+    files: &[String],
+    parse: impl Fn(&String) -> Option<ParsedFile> + Sync,
+    on_progress: &dyn Fn(IndexProgress),
+) -> Vec<ParsedFile> {
+    let (done_tx, done_rx) = std::sync::mpsc::channel::<&str>();
+    let parse = &parse;
+    std::thread::scope(|scope| {
+        let workers = scope.spawn(move || {
+            files
+                .par_iter()
+                .filter_map(|file| {
+                    let parsed = parse(file);
+                    let _ = done_tx.send(file);
+                    parsed
+                })
+                .collect()
+        });
+        // The channel closes once every worker is done with `done_tx`.
+        for (idx, file) in done_rx.iter().enumerate() {
+            on_progress(IndexProgress {
+                phase: IndexPhase::Parsing,
+                current: idx + 1,
+                total: files.len(),
+                current_file: Some(file.to_string()),
+            });
+        }
+        workers
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
 /// Lightweight check for whether the index is out of date.
 ///
 /// Scans the project directory and compares the current file set and tracked
 /// filesystem metadata (modification time and size) against indexed state.
 /// If metadata differs, it verifies content hash before marking a file as
 /// modified. Returns detailed status of added, modified, and removed files.
-pub fn needs_sync(project_root: &Path, config: &CodeGraphConfig) -> std::io::Result<SyncStatus> {
+pub fn needs_sync(project_root: &Path, config: &CodeGraphConfig) -> Result<SyncStatus> {
     let conn = db::open_database(project_root)?;
 
     let current_files: HashSet<String> = scan_directory(project_root, config, |_count, _file| {})
         .into_iter()
         .collect();
     let tracked_files = db::list_files(&conn)?;
-
-    let tracked_paths: HashSet<&str> = tracked_files.iter().map(|f| f.path.as_str()).collect();
+    let paths = PathMatcher::new(&current_files, &tracked_files, config);
 
     let mut files_added = 0usize;
     for file in &current_files {
-        if !tracked_paths.contains(file.as_str()) {
+        if !paths.is_tracked(file) {
             files_added += 1;
         }
     }
@@ -368,9 +492,16 @@ pub fn needs_sync(project_root: &Path, config: &CodeGraphConfig) -> std::io::Res
     let mut files_removed = 0usize;
     let mut files_modified = 0usize;
     for tracked in &tracked_files {
-        if !current_files.contains(&tracked.path) {
-            files_removed += 1;
-            continue;
+        match paths.current_path_for(&tracked.path) {
+            TrackedPath::Removed | TrackedPath::Duplicate => {
+                files_removed += 1;
+                continue;
+            }
+            TrackedPath::Renamed => {
+                files_modified += 1;
+                continue;
+            }
+            TrackedPath::Unchanged => {}
         }
 
         let full_path = project_root.join(&tracked.path);
@@ -382,13 +513,7 @@ pub fn needs_sync(project_root: &Path, config: &CodeGraphConfig) -> std::io::Res
                 files_modified += 1;
             }
             Ok(meta) => {
-                let mtime = meta
-                    .modified()
-                    .ok()
-                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                    .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX));
-                let size = meta.len();
-                if mtime != tracked.modified_at || size != tracked.size {
+                if !metadata_matches(tracked, &meta) {
                     let content = if let Ok(content) = fs::read_to_string(&full_path) {
                         content
                     } else {
@@ -416,29 +541,204 @@ pub fn sync(
     project_root: &Path,
     config: &CodeGraphConfig,
     on_progress: Option<&dyn Fn(IndexProgress)>,
-) -> std::io::Result<SyncResult> {
-    let span = tracing::info_span!("sync", root = %project_root.display());
+) -> Result<SyncResult> {
+    let hooks = LifecycleHooks::from_config(project_root);
+    sync_with_hooks(project_root, config, on_progress, &hooks)
+}
+
+/// [`sync`] limited to the files git reports changed since `since` (see
+/// [`changed_since`]). Nothing else is scanned, read, or hashed, so edits
+/// git does not report wait for the next full sync.
+pub fn sync_since(
+    project_root: &Path,
+    config: &CodeGraphConfig,
+    since: &str,
+    on_progress: Option<&dyn Fn(IndexProgress)>,
+) -> Result<SyncResult> {
+    let hooks = LifecycleHooks::from_config(project_root);
+    sync_scoped(project_root, config, on_progress, &hooks, Some(since))
+}
+
+/// [`sync`] firing `hooks` instead of only the configured commands.
+pub fn sync_with_hooks(
+    project_root: &Path,
+    config: &CodeGraphConfig,
+    on_progress: Option<&dyn Fn(IndexProgress)>,
+    hooks: &LifecycleHooks,
+) -> Result<SyncResult> {
+    sync_scoped(project_root, config, on_progress, hooks, None)
+}
+
+/// Re-index just `paths` (relative to `project_root`) and resolve the
+/// references they add, returning how many files changed.
+///
+/// Unlike [`sync`] nothing is scanned, and no hooks, embedding, or churn
+/// recording run.
+///
+/// # Errors
+///
+/// Returns an error if the database cannot be opened or a file cannot be
+/// re-indexed.
+pub fn reindex_files(
+    project_root: &Path,
+    config: &CodeGraphConfig,
+    paths: &[String],
+) -> Result<usize> {
+    let mut conn = db::open_database_for(project_root, db::Workload::Interactive)?;
+    let marks = db::row_marks(&conn)?;
+    let hooks = LifecycleHooks::new();
+    let mut reindexed = 0;
+    for path in paths {
+        if index_file(project_root, config, &mut conn, path, &hooks, None)?.is_some() {
+            reindexed += 1;
+        }
+    }
+    if reindexed > 0 {
+        ReferenceResolver::resolve_since(&mut conn, project_root, marks, 10_000)?;
+        crate::staleness::note_index_changed(project_root);
+    }
+    Ok(reindexed)
+}
+
+/// Paths, relative to `project_root`, that git reports changed since
+/// `since`.
+///
+/// A single revision is compared with the working tree, and untracked
+/// files are added; a range (`a..b`, `a...b`) lists only the files it
+/// changed. Renames count as a removal and an addition.
+///
+/// # Errors
+///
+/// Returns [`CoralineError::InvalidInput`] if `since` looks like an option,
+/// and an I/O error if git cannot resolve it.
+pub fn changed_since(project_root: &Path, since: &str) -> Result<HashSet<String>> {
+    if since.is_empty() || since.starts_with('-') {
+        return Err(CoralineError::InvalidInput(format!(
+            "not a revision: {since:?}"
+        )));
+    }
+    let paths = |out: String| -> Vec<String> {
+        out.split('\0')
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let mut changed: HashSet<String> = paths(utils::git(
+        project_root,
+        &[
+            "diff",
+            "--name-only",
+            "--no-renames",
+            "--relative",
+            "-z",
+            since,
+            "--",
+        ],
+    )?)
+    .into_iter()
+    .collect();
+    if !since.contains("..") {
+        changed.extend(paths(utils::git(
+            project_root,
+            &["ls-files", "--others", "--exclude-standard", "-z"],
+        )?));
+    }
+    Ok(changed)
+}
+
+fn sync_scoped(
+    project_root: &Path,
+    config: &CodeGraphConfig,
+    on_progress: Option<&dyn Fn(IndexProgress)>,
+    hooks: &LifecycleHooks,
+    since: Option<&str>,
+) -> Result<SyncResult> {
+    let span = tracing::info_span!("sync", ?since, root = %project_root.display());
     let _enter = span.enter();
     let start = Instant::now();
-    let mut conn = db::open_database(project_root)?;
-
-    let current_files: HashSet<String> = scan_directory(project_root, config, |_current, _file| {})
-        .into_iter()
-        .collect();
-    let tracked_files = db::list_files(&conn)?;
+    let mut conn = db::open_database_for(project_root, db::Workload::Bulk)?;
+    if let Err(err) = branches::switch(project_root, &mut conn) {
+        warn!(error = %err, "failed to switch branch graph");
+    }
+    let parse_cache = ParseCache::for_project(project_root);
+    discard_inconsistent_files(&mut conn)?;
+    if let Err(err) = annotations::import_codeowners(project_root, &mut conn) {
+        warn!(error = %err, "failed to import CODEOWNERS");
+    }
+    if let Err(err) = annotations::import_submodules(project_root, &mut conn) {
+        warn!(error = %err, "failed to tag submodules");
+    }
+    if let Err(err) = annotations::import_packages(project_root, &mut conn) {
+        warn!(error = %err, "failed to tag workspace packages");
+    }
+    hooks.fire(
+        project_root,
+        &conn,
+        &HookEvent::PreScan {
+            mode: IndexMode::Sync,
+        },
+    );
+    // Only references the sync adds, and older ones naming a node it adds,
+    // are re-attempted; a name left unique by a removal waits for the next
+    // full index.
+    let marks = db::row_marks(&conn)?;
+
+    let (current_files, tracked_files): (HashSet<String>, Vec<FileRecord>) = match since {
+        None => (
+            scan_directory(project_root, config, |_current, _file| {})
+                .into_iter()
+                .collect(),
+            db::list_files(&conn)?,
+        ),
+        Some(since) => {
+            // Only the changed paths are candidates; every other record is
+            // assumed current.
+            let changed = changed_since(project_root, since)?;
+            let sensitive = SensitiveFiles::for_project(project_root);
+            let submodules = skipped_submodules(project_root);
+            let filter = PathFilter::for_config(config);
+            let current = changed
+                .iter()
+                .filter(|path| {
+                    project_root.join(path.as_str()).is_file()
+                        && !sensitive.is_sensitive(path)
+                        && !submodules
+                            .iter()
+                            .any(|s| path.starts_with(&format!("{s}/")))
+                        && filter.includes(path)
+                })
+                .cloned()
+                .collect();
+            let tracked = db::list_files(&conn)?
+                .into_iter()
+                .filter(|f| changed.contains(&f.path))
+                .collect();
+            (current, tracked)
+        }
+    };
 
     let mut files_added = 0;
     let mut files_modified = 0;
     let mut files_removed = 0;
     let mut nodes_updated = 0;
+    let mut edges_updated = 0;
 
+    let paths = PathMatcher::new(&current_files, &tracked_files, config);
     for tracked in &tracked_files {
-        if !current_files.contains(&tracked.path) {
+        if matches!(
+            paths.current_path_for(&tracked.path),
+            TrackedPath::Removed | TrackedPath::Duplicate
+        ) {
             db::delete_file(&mut conn, &tracked.path)?;
             files_removed += 1;
         }
     }
 
+    let tracked_by_path: HashMap<&str, &FileRecord> =
+        tracked_files.iter().map(|f| (f.path.as_str(), f)).collect();
+    let mut files_hashed = 0usize;
+    let mut files_failed = 0usize;
+
     for (idx, file) in current_files.iter().enumerate() {
         if let Some(cb) = on_progress {
             cb(IndexProgress {
@@ -450,56 +750,311 @@ pub fn sync(
         }
 
         let full_path = project_root.join(file);
-        let content = fs::read_to_string(&full_path)?;
-        let content_hash = hash_sha256(&content);
-        let tracked = tracked_files.iter().find(|f| f.path == *file);
-
-        if let Some(tracked) = tracked {
-            if tracked.content_hash != content_hash {
-                match index_file(project_root, config, &mut conn, file) {
-                    Ok(Some((node_count, _))) => {
-                        files_modified += 1;
-                        nodes_updated += node_count;
-                    }
-                    Ok(None) => {}
-                    Err(err) => {
-                        warn!(file = %file, error = %err, "failed to sync file");
-                    }
+
+        if paths.is_case_rename(file) {
+            // `index_file` drops the record stored under the old spelling.
+            match index_file(
+                project_root,
+                config,
+                &mut conn,
+                file,
+                hooks,
+                parse_cache.as_ref(),
+            ) {
+                Ok(Some((node_count, edge_count))) => {
+                    files_modified += 1;
+                    nodes_updated += node_count;
+                    edges_updated += edge_count;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    warn!(file = %file, error = %err, "failed to sync file");
+                    files_failed += 1;
+                }
+            }
+        } else if let Some(tracked) = tracked_by_path.get(file.as_str()) {
+            // Fast path: unchanged mtime and size means the file is untouched,
+            // so skip reading and hashing it entirely.
+            let meta = fs::metadata(&full_path)?;
+            if metadata_matches(tracked, &meta) {
+                continue;
+            }
+
+            let content = fs::read_to_string(&full_path)?;
+            files_hashed += 1;
+            if tracked.content_hash == hash_sha256(&content) {
+                // Touched but not edited (checkout, formatter no-op, ...).
+                // Refresh the stored metadata so the next sync hits the fast path.
+                let refreshed = FileRecord {
+                    size: meta.len(),
+                    modified_at: file_mtime_millis(&meta),
+                    ..(*tracked).clone()
+                };
+                db::upsert_file(&conn, &refreshed)?;
+                continue;
+            }
+
+            match index_file(
+                project_root,
+                config,
+                &mut conn,
+                file,
+                hooks,
+                parse_cache.as_ref(),
+            ) {
+                Ok(Some((node_count, edge_count))) => {
+                    files_modified += 1;
+                    nodes_updated += node_count;
+                    edges_updated += edge_count;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    warn!(file = %file, error = %err, "failed to sync file");
+                    files_failed += 1;
                 }
             }
         } else {
-            match index_file(project_root, config, &mut conn, file) {
-                Ok(Some((node_count, _))) => {
+            match index_file(
+                project_root,
+                config,
+                &mut conn,
+                file,
+                hooks,
+                parse_cache.as_ref(),
+            ) {
+                Ok(Some((node_count, edge_count))) => {
                     files_added += 1;
                     nodes_updated += node_count;
+                    edges_updated += edge_count;
                 }
                 Ok(None) => {}
                 Err(err) => {
                     warn!(file = %file, error = %err, "failed to sync file");
+                    files_failed += 1;
                 }
             }
         }
     }
 
-    let _ = ReferenceResolver::resolve_unresolved(&mut conn, project_root, 10_000);
+    hooks.fire(
+        project_root,
+        &conn,
+        &HookEvent::PostIndex {
+            mode: IndexMode::Sync,
+            files: files_added + files_modified,
+            files_removed,
+            nodes: nodes_updated,
+            edges: edges_updated,
+        },
+    );
+
+    if let Ok(resolved) = ReferenceResolver::resolve_since(&mut conn, project_root, marks, 10_000) {
+        hooks.fire(
+            project_root,
+            &conn,
+            &HookEvent::PostResolve {
+                mode: IndexMode::Sync,
+                resolved: resolved.resolved,
+                remaining: resolved.remaining,
+            },
+        );
+    }
+
+    if files_added + files_modified + files_removed > 0
+        && let Err(err) = models::link_queries(project_root, &mut conn)
+    {
+        warn!(error = %err, "failed to link ORM models to their queries");
+    }
+
+    // Keep the vector index in step with the graph: re-indexed files get new
+    // node IDs, so drop vectors for vanished nodes and embed the new ones.
+    let mut nodes_embedded = 0;
+    if files_added + files_modified + files_removed > 0 {
+        let orphaned = db::delete_orphaned_vectors(&conn)?;
+        if orphaned > 0 {
+            debug!(orphaned, "removed stale embeddings");
+        }
+        if crate::config::load_toml_config(project_root).is_ok_and(|cfg| cfg.vectors.enabled) {
+            nodes_embedded = embed_new_nodes(project_root, &conn);
+        }
+        // Re-indexed nodes lost their churn rows along with their old IDs.
+        record_churn_if_enabled(project_root);
+    }
 
     info!(
         files_added,
         files_modified,
         files_removed,
         nodes_updated,
+        nodes_embedded,
+        files_hashed,
+        files_failed,
         duration_ms = start.elapsed().as_millis(),
         "sync complete"
     );
 
-    Ok(SyncResult {
+    let result = SyncResult {
         files_checked: current_files.len(),
         files_added,
         files_modified,
         files_removed,
         nodes_updated,
+        nodes_embedded,
+        files_hashed,
+        files_failed,
         duration_ms: start.elapsed().as_millis(),
-    })
+    };
+    crate::staleness::note_index_changed(project_root);
+
+    match crate::config::load_toml_config(project_root) {
+        Ok(toml_cfg) => crate::sync::run_post_sync_hooks(project_root, &toml_cfg.sync, &result),
+        Err(err) => warn!("Skipping post-sync hooks, could not load config: {err}"),
+    }
+
+    Ok(result)
+}
+
+/// Record churn from git history when `[history] on_index` is set.
+/// Best-effort: failures, such as a project outside git, are only logged.
+fn record_churn_if_enabled(project_root: &Path) {
+    let cfg = match crate::config::load_toml_config(project_root) {
+        Ok(cfg) => cfg.history,
+        Err(err) => {
+            warn!("Skipping churn, could not load config: {err}");
+            return;
+        }
+    };
+    if !cfg.on_index {
+        return;
+    }
+    match history::record_churn(project_root, &cfg) {
+        Ok(summary) => debug!(
+            commits = summary.commits_scanned,
+            nodes = summary.nodes,
+            "recorded churn"
+        ),
+        Err(err) => warn!(error = %err, "failed to record churn"),
+    }
+}
+
+/// Recovery check run before every index and sync: drop the rows of files
+/// whose records are inconsistent (see [`db::find_inconsistent_files`]),
+/// so the pass that follows re-indexes them from disk like new files.
+fn discard_inconsistent_files(conn: &mut rusqlite::Connection) -> Result<usize> {
+    let paths = db::find_inconsistent_files(conn)?;
+    for path in &paths {
+        warn!(file = %path, "re-indexing file with inconsistent records");
+        db::delete_file(conn, path)?;
+    }
+    Ok(paths.len())
+}
+
+/// Embed nodes that lack a vector, for projects with `[vectors] enabled`.
+/// Best-effort: a missing model or runtime yields 0 rather than an error.
+fn embed_new_nodes(project_root: &Path, conn: &rusqlite::Connection) -> usize {
+    let mut vm = match crate::vectors::VectorManager::from_project(project_root) {
+        Ok(vm) => vm,
+        Err(err) => {
+            debug!("no embedding model available; skipping embedding refresh: {err}");
+            return 0;
+        }
+    };
+    match crate::vectors::embed_missing_nodes(project_root, conn, &mut vm) {
+        Ok(count) => count,
+        Err(err) => {
+            warn!("Embedding refresh after sync failed: {err}");
+            0
+        }
+    }
+}
+
+/// Where a tracked file record stands relative to the files found on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrackedPath {
+    /// Still present under the same path.
+    Unchanged,
+    /// No longer present.
+    Removed,
+    /// Present on disk under a different case (case-insensitive policy only).
+    Renamed,
+    /// A stale case variant of a path that is also tracked under its
+    /// current spelling.
+    Duplicate,
+}
+
+/// Matches scanned paths against tracked file records, honouring the
+/// configured [`PathCaseSensitivity`](crate::types::PathCaseSensitivity).
+struct PathMatcher<'a> {
+    case_insensitive: bool,
+    current_files: &'a HashSet<String>,
+    tracked_paths: HashSet<&'a str>,
+    current_by_key: HashMap<String, &'a str>,
+    tracked_keys: HashSet<String>,
+}
+
+impl<'a> PathMatcher<'a> {
+    fn new(
+        current_files: &'a HashSet<String>,
+        tracked_files: &'a [FileRecord],
+        config: &CodeGraphConfig,
+    ) -> Self {
+        let case_insensitive = config.path_case.is_case_insensitive();
+        let current_by_key = current_files
+            .iter()
+            .map(|f| (path_key(f, case_insensitive), f.as_str()))
+            .collect();
+        let tracked_keys = tracked_files
+            .iter()
+            .map(|f| path_key(&f.path, case_insensitive))
+            .collect();
+        Self {
+            case_insensitive,
+            current_files,
+            tracked_paths: tracked_files.iter().map(|f| f.path.as_str()).collect(),
+            current_by_key,
+            tracked_keys,
+        }
+    }
+
+    fn current_path_for(&self, tracked_path: &str) -> TrackedPath {
+        if self.current_files.contains(tracked_path) {
+            return TrackedPath::Unchanged;
+        }
+        if !self.case_insensitive {
+            return TrackedPath::Removed;
+        }
+        match self.current_by_key.get(&path_key(tracked_path, true)) {
+            None => TrackedPath::Removed,
+            Some(current) if self.tracked_paths.contains(current) => TrackedPath::Duplicate,
+            Some(_) => TrackedPath::Renamed,
+        }
+    }
+
+    /// Whether `file` is tracked, either exactly or as a case-only rename.
+    fn is_tracked(&self, file: &str) -> bool {
+        self.tracked_paths.contains(file)
+            || (self.case_insensitive && self.tracked_keys.contains(&path_key(file, true)))
+    }
+
+    /// Whether `file` is only tracked under a different case spelling.
+    fn is_case_rename(&self, file: &str) -> bool {
+        !self.tracked_paths.contains(file) && self.is_tracked(file)
+    }
+}
+
+/// Modification time of a file in milliseconds since the Unix epoch, or `0`
+/// when the platform does not report one.
+fn file_mtime_millis(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+}
+
+/// Whether the on-disk mtime and size still match what was recorded at index time.
+fn metadata_matches(tracked: &FileRecord, metadata: &fs::Metadata) -> bool {
+    file_mtime_millis(metadata) == tracked.modified_at && metadata.len() == tracked.size
 }
 
 fn index_file(
@@ -507,7 +1062,9 @@ fn index_file(
     config: &CodeGraphConfig,
     conn: &mut rusqlite::Connection,
     relative_path: &str,
-) -> std::io::Result<Option<(usize, usize)>> {
+    hooks: &LifecycleHooks,
+    cache: Option<&ParseCache>,
+) -> Result<Option<(usize, usize)>> {
     let full_path = project_root.join(relative_path);
     let content = fs::read_to_string(&full_path)?;
 
@@ -521,11 +1078,112 @@ fn index_file(
     }
 
     let content_hash = hash_sha256(&content);
-    if let Some(existing) = db::get_file_record(conn, relative_path)? {
-        if existing.content_hash == content_hash {
+    let existing = if config.path_case.is_case_insensitive() {
+        db::get_file_record_ignore_case(conn, relative_path)?
+    } else {
+        db::get_file_record(conn, relative_path)?
+    };
+    let mut replaced_paths = Vec::new();
+    if let Some(existing) = existing {
+        if existing.path == relative_path && existing.content_hash == content_hash {
             return Ok(None);
         }
-        db::delete_file(conn, relative_path)?;
+        replaced_paths.push(existing.path);
+    }
+
+    let now_ms = now_millis();
+    let (nodes, extracted_edges, unresolved_refs) = extract_file(
+        project_root,
+        relative_path,
+        &content,
+        &content_hash,
+        language,
+        now_ms,
+        CacheUse::from(cache),
+    );
+
+    let metadata = fs::metadata(&full_path)?;
+    let file_record = FileRecord {
+        path: relative_path.to_string(),
+        content_hash,
+        language,
+        size: metadata.len(),
+        modified_at: file_mtime_millis(&metadata),
+        indexed_at: now_ms,
+        node_count: nodes.len() as i64,
+        errors: None,
+    };
+    let replaced: Vec<&str> = replaced_paths.iter().map(String::as_str).collect();
+    db::replace_file(
+        conn,
+        &replaced,
+        &file_record,
+        &nodes,
+        &extracted_edges,
+        &unresolved_refs,
+        &span_hashes(&nodes, &content),
+    )?;
+    hooks.fire(
+        project_root,
+        conn,
+        &HookEvent::PostFileExtract {
+            mode: IndexMode::Sync,
+            file: &file_record,
+            nodes: &nodes,
+            edges: &extracted_edges,
+        },
+    );
+
+    Ok(Some((nodes.len(), extracted_edges.len())))
+}
+
+/// How [`extract_file`] uses the shared parse cache.
+#[derive(Clone, Copy)]
+enum CacheUse<'a> {
+    Off,
+    ReadWrite(&'a ParseCache),
+    /// Re-parse and overwrite the entry (`index --force`).
+    Refresh(&'a ParseCache),
+}
+
+impl<'a> From<Option<&'a ParseCache>> for CacheUse<'a> {
+    fn from(cache: Option<&'a ParseCache>) -> Self {
+        cache.map_or(Self::Off, Self::ReadWrite)
+    }
+}
+
+/// The file node plus everything extracted from `content`, taken from the
+/// shared parse cache when another worktree already parsed this content.
+/// Each node's id with the hash of its source span in `content`.
+fn span_hashes(nodes: &[Node], content: &str) -> Vec<(String, String)> {
+    nodes
+        .iter()
+        .map(|node| {
+            (
+                node.id.clone(),
+                span_hash(content, node.start_line, node.end_line),
+            )
+        })
+        .collect()
+}
+
+fn extract_file(
+    project_root: &Path,
+    relative_path: &str,
+    content: &str,
+    content_hash: &str,
+    language: Language,
+    now_ms: i64,
+    cache: CacheUse<'_>,
+) -> (Vec<Node>, Vec<Edge>, Vec<UnresolvedReference>) {
+    if let CacheUse::ReadWrite(cache) = cache
+        && let Some(mut cached) = cache.get(relative_path, content_hash)
+    {
+        debug!(file = %relative_path, "parse cache hit");
+        for node in &mut cached.nodes {
+            node.updated_at = now_ms;
+        }
+        return (cached.nodes, cached.edges, cached.unresolved_refs);
     }
 
     let file_name = Path::new(relative_path)
@@ -536,7 +1194,6 @@ fn index_file(
     let node_id = node_id_for_symbol(relative_path, "file", &qualified_name, 1, 0);
     let file_node_id = node_id.clone();
 
-    let now_ms = now_millis();
     let mut nodes = Vec::new();
     let file_node = Node {
         id: node_id,
@@ -562,44 +1219,26 @@ fn index_file(
     };
     nodes.push(file_node);
 
-    let (mut extracted_nodes, extracted_edges, unresolved_refs) = extract_nodes(
+    let (mut extracted_nodes, edges, unresolved_refs) = extract_nodes(
         project_root,
         relative_path,
-        &content,
+        content,
         language,
         now_ms,
         &file_node_id,
     );
     nodes.append(&mut extracted_nodes);
 
-    if !nodes.is_empty() {
-        db::insert_nodes(conn, &nodes)?;
-    }
-    if !extracted_edges.is_empty() {
-        db::insert_edges(conn, &extracted_edges)?;
-    }
-    if !unresolved_refs.is_empty() {
-        db::insert_unresolved_refs(conn, &unresolved_refs)?;
+    if let CacheUse::ReadWrite(cache) | CacheUse::Refresh(cache) = cache {
+        let parsed = CachedParse {
+            nodes,
+            edges,
+            unresolved_refs,
+        };
+        cache.put(relative_path, content_hash, &parsed);
+        return (parsed.nodes, parsed.edges, parsed.unresolved_refs);
     }
-
-    let metadata = fs::metadata(&full_path)?;
-    let file_record = FileRecord {
-        path: relative_path.to_string(),
-        content_hash,
-        language,
-        size: metadata.len(),
-        modified_at: metadata
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-            .map_or(0, |d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX)),
-        indexed_at: now_ms,
-        node_count: nodes.len() as i64,
-        errors: None,
-    };
-    db::upsert_file(conn, &file_record)?;
-
-    Ok(Some((nodes.len(), extracted_edges.len())))
+    (nodes, edges, unresolved_refs)
 }
 
 fn extract_nodes(
@@ -610,17 +1249,14 @@ fn extract_nodes(
     now_ms: i64,
     root_id: &str,
 ) -> (Vec<Node>, Vec<Edge>, Vec<UnresolvedReference>) {
-    let mut parser = Parser::new();
-    let ts_lang = match language_to_parser(language) {
-        Some(ts_lang) => ts_lang,
-        None => return (Vec::new(), Vec::new(), Vec::new()),
-    };
-
-    if parser.set_language(&ts_lang).is_err() {
-        return (Vec::new(), Vec::new(), Vec::new());
+    // Prisma schemas have no grammar; their models are all they declare.
+    if language == Language::Prisma {
+        let (models, edges) =
+            models::extract_models(file_path, source, language, &[], root_id, now_ms);
+        return (models, edges, Vec::new());
     }
 
-    let tree = match parser.parse(source, None) {
+    let tree = match parse_source(source, language) {
         Some(tree) => tree,
         None => return (Vec::new(), Vec::new(), Vec::new()),
     };
@@ -659,9 +1295,65 @@ fn extract_nodes(
         &mut unresolved_refs,
         &mut Vec::new(),
     );
+    let (todos, todo_edges) = todos::extract_todos(
+        tree.root_node(),
+        file_path,
+        source,
+        language,
+        &nodes,
+        root_id,
+        now_ms,
+    );
+    nodes.extend(todos);
+    edges.extend(todo_edges);
+    let (routes, route_edges, route_refs) = crate::routes::extract_routes(
+        tree.root_node(),
+        file_path,
+        source,
+        language,
+        &nodes,
+        root_id,
+        now_ms,
+    );
+    nodes.extend(routes);
+    edges.extend(route_edges);
+    unresolved_refs.extend(route_refs);
+    let (models, model_edges) =
+        models::extract_models(file_path, source, language, &nodes, root_id, now_ms);
+    nodes.extend(models);
+    edges.extend(model_edges);
+    let (keys, key_edges) =
+        config_keys::extract_config_keys(file_path, source, language, &nodes, root_id, now_ms);
+    nodes.extend(keys);
+    edges.extend(key_edges);
+    let (heritage_edges, heritage_refs) =
+        implementations::extract_heritage(tree.root_node(), source, language, &nodes);
+    edges.extend(heritage_edges);
+    unresolved_refs.extend(heritage_refs);
     (nodes, edges, unresolved_refs)
 }
 
+thread_local! {
+    /// Parsers by language, kept per thread so a long-lived process (the
+    /// daemon, the MCP server) sets each grammar up once per worker.
+    static PARSERS: RefCell<HashMap<Language, Parser>> = RefCell::new(HashMap::new());
+}
+
+pub(crate) fn parse_source(source: &str, language: Language) -> Option<tree_sitter::Tree> {
+    PARSERS.with(|parsers| {
+        let mut parsers = parsers.borrow_mut();
+        let parser = match parsers.entry(language) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let mut parser = Parser::new();
+                parser.set_language(&language_to_parser(language)?).ok()?;
+                entry.insert(parser)
+            }
+        };
+        parser.parse(source, None)
+    })
+}
+
 fn language_to_parser(language: Language) -> Option<tree_sitter::Language> {
     match language {
         Language::Rust => Some(tree_sitter::Language::new(tree_sitter_rust::LANGUAGE)),
@@ -710,7 +1402,7 @@ fn language_to_parser(language: Language) -> Option<tree_sitter::Language> {
         Language::Markdown => Some(tree_sitter_markdown_fork::language()),
         Language::Toml => Some(tree_sitter::Language::new(tree_sitter_toml_ng::LANGUAGE)),
         // Unsupported languages
-        Language::Liquid | Language::Unknown => None,
+        Language::Liquid | Language::Prisma | Language::Unknown => None,
     }
 }
 
@@ -923,6 +1615,8 @@ fn walk_tree_calls(
         if let Some(source_id) = scope_stack.last() {
             if let Some(callee_name) = call_name(&node, source, language) {
                 let start = node.start_position();
+                let arity = call_arity(&node);
+                let receiver = call_receiver(&node, source, language);
                 match symbol_index.by_name.get(&callee_name) {
                     Some(targets) if targets.len() == 1 => {
                         edges.push(Edge {
@@ -942,6 +1636,8 @@ fn walk_tree_calls(
                             line: start.row as i64 + 1,
                             column: start.column as i64,
                             candidates: Some(targets.clone()),
+                            arity,
+                            receiver,
                         });
                     }
                     None => {
@@ -952,6 +1648,8 @@ fn walk_tree_calls(
                             line: start.row as i64 + 1,
                             column: start.column as i64,
                             candidates: None,
+                            arity,
+                            receiver,
                         });
                     }
                 }
@@ -1123,31 +1821,8 @@ fn import_symbols(node: &TsNode, source: &str, language: Language) -> Vec<Import
             }]
         }
 
-        // === Python: from X import Y, Z ===
-        Language::Python => {
-            let mut imports = Vec::new();
-            let import_name = node
-                .child_by_field_name("name")
-                .or_else(|| node.child_by_field_name("alias"))
-                .and_then(|n| n.utf8_text(source.as_bytes()).ok())
-                .map(|s| s.to_string());
-
-            if let Some(name) = import_name {
-                imports.push(ImportSymbol {
-                    local_name: name.clone(),
-                    module_path,
-                    export_name: Some(name),
-                });
-            } else {
-                // Fallback for plain imports
-                imports.push(ImportSymbol {
-                    local_name: module_path.clone(),
-                    module_path,
-                    export_name: None,
-                });
-            }
-            imports
-        }
+        // === Python: import X.Y as Z / from X import Y as Z, W ===
+        Language::Python => python_import_symbols(node, source),
 
         // === Go ===
         Language::Go => {
@@ -1450,6 +2125,7 @@ fn import_symbols(node: &TsNode, source: &str, language: Language) -> Vec<Import
         | Language::Toml
         | Language::Yaml
         | Language::Liquid
+        | Language::Prisma
         | Language::Unknown => Vec::new(),
     }
 }
@@ -1487,6 +2163,12 @@ fn import_module_path(node: &TsNode, source: &str, language: Language) -> Option
         _ => "source",
     };
 
+    // `import a.b as c` has no `module_name`; its first name is the module.
+    if language == Language::Python && node.child_by_field_name(field).is_none() {
+        let name = node.child_by_field_name("name")?;
+        return python_dotted_name(name.child_by_field_name("name").unwrap_or(name), source);
+    }
+
     let child = node.child_by_field_name(field).or_else(|| {
         // Fallback: get first string-like child
         node.children(&mut node.walk())
@@ -1506,6 +2188,53 @@ fn import_module_path(node: &TsNode, source: &str, language: Language) -> Option
     }
 }
 
+/// One symbol per name a Python import binds. `import a.b` binds the
+/// dotted path, the way calls through it spell it; `from a import b` binds
+/// `b` with `a` (kept relative, as `..a`) as its module and `b` as the
+/// name it exports. `from a import *` binds nothing it can name.
+fn python_import_symbols(node: &TsNode, source: &str) -> Vec<ImportSymbol> {
+    let from = node
+        .child_by_field_name("module_name")
+        .and_then(|n| python_dotted_name(n, source));
+    let mut cursor = node.walk();
+    node.children_by_field_name("name", &mut cursor)
+        .filter_map(|name| {
+            let (path, alias) = if name.kind() == "aliased_import" {
+                (
+                    python_dotted_name(name.child_by_field_name("name")?, source)?,
+                    name.child_by_field_name("alias")
+                        .and_then(|n| python_dotted_name(n, source)),
+                )
+            } else {
+                (python_dotted_name(name, source)?, None)
+            };
+            Some(match &from {
+                Some(module) => ImportSymbol {
+                    local_name: alias.unwrap_or_else(|| path.clone()),
+                    module_path: module.clone(),
+                    export_name: Some(path),
+                },
+                None => ImportSymbol {
+                    local_name: alias.unwrap_or_else(|| path.clone()),
+                    module_path: path,
+                    export_name: None,
+                },
+            })
+        })
+        .collect()
+}
+
+/// The text of a Python `dotted_name` or `relative_import`, without the
+/// whitespace the grammar allows around its dots.
+fn python_dotted_name(node: TsNode, source: &str) -> Option<String> {
+    let text: String = node
+        .utf8_text(source.as_bytes())
+        .ok()?
+        .split_whitespace()
+        .collect();
+    if text.is_empty() { None } else { Some(text) }
+}
+
 fn collect_import_symbols(
     node: TsNode,
     source: &str,
@@ -1656,10 +2385,17 @@ fn module_name(node: &TsNode, source: &str, language: Language) -> Option<String
     }
 }
 
+/// The file `mod name;` in `file_path` loads: beside a crate root or
+/// `mod.rs`, and under a directory named after any other file (`a.rs`
+/// loads `a/name.rs`).
 fn rust_module_target(project_root: &Path, file_path: &str, name: &str) -> Option<String> {
-    let base_dir = Path::new(file_path)
-        .parent()
-        .unwrap_or_else(|| Path::new(""));
+    let path = Path::new(file_path);
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let base_dir = if crate::resolution::rust_modules::owns_directory(file_path) {
+        parent.to_path_buf()
+    } else {
+        parent.join(path.file_stem()?)
+    };
     let candidate_file = base_dir.join(format!("{name}.rs"));
     let candidate_mod = base_dir.join(name).join("mod.rs");
 
@@ -1953,6 +2689,7 @@ fn export_symbols(node: &TsNode, source: &str, language: Language) -> Vec<Export
         | Language::Toml
         | Language::Yaml
         | Language::Liquid
+        | Language::Prisma
         | Language::Unknown => Vec::new(),
     }
 }
@@ -2001,6 +2738,7 @@ fn collect_export_names(node: TsNode, source: &str, names: &mut Vec<String>) {
         node.kind(),
         "function_declaration"
             | "class_declaration"
+            | "abstract_class_declaration"
             | "interface_declaration"
             | "type_alias_declaration"
             | "enum_declaration"
@@ -2083,14 +2821,113 @@ fn is_call_expression(kind: &str, language: Language) -> bool {
         // Zig
         Language::Zig => matches!(kind, "call_expression"),
         // Markup/config files don't have calls
-        Language::Markdown | Language::Toml | Language::Yaml => false,
+        Language::Markdown | Language::Toml | Language::Yaml | Language::Prisma => false,
         // Unsupported
         Language::Liquid | Language::Blazor | Language::Unknown => false,
     }
 }
 
 fn call_name(node: &TsNode, source: &str, language: Language) -> Option<String> {
-    let callee = match language {
+    let callee = callee_node(node, language)?;
+    let raw = callee.utf8_text(source.as_bytes()).ok()?.to_string();
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let name = trimmed
+        .rsplit("::")
+        .next()
+        .unwrap_or(trimmed)
+        .rsplit('.')
+        .next()
+        .unwrap_or(trimmed)
+        .rsplit("->")
+        .next()
+        .unwrap_or(trimmed)
+        .to_string();
+
+    if name.is_empty() { None } else { Some(name) }
+}
+
+/// The arguments of a call expression, or `None` when the grammar has no
+/// `arguments` field or a spread argument makes the count unknown.
+fn call_arity(node: &TsNode) -> Option<i64> {
+    let arguments = node.child_by_field_name("arguments")?;
+    let mut count = 0;
+    for argument in arguments.named_children(&mut arguments.walk()) {
+        let kind = argument.kind();
+        if kind.contains("spread") || kind.contains("splat") {
+            return None;
+        }
+        if !kind.contains("comment") {
+            count += 1;
+        }
+    }
+    Some(count)
+}
+
+/// Last segment of the expression a call is made on, taken from the
+/// grammar's receiver field or from the callee text before its final `.`,
+/// `::`, or `->`. Arguments and indexing are dropped, so `a.items().push`
+/// gives `items`. A Rust path is kept whole without turbofish, so
+/// `crate::db::open()` gives `crate::db`, and so is a Python dotted name,
+/// so `app.util.clean()` gives `app.util`.
+fn call_receiver(node: &TsNode, source: &str, language: Language) -> Option<String> {
+    let text = if let Some(receiver) = node
+        .child_by_field_name("object")
+        .or_else(|| node.child_by_field_name("receiver"))
+    {
+        receiver.utf8_text(source.as_bytes()).ok()?.trim()
+    } else {
+        let callee = callee_node(node, language)?
+            .utf8_text(source.as_bytes())
+            .ok()?
+            .trim();
+        let cut = ["::", ".", "->"]
+            .iter()
+            .filter_map(|separator| callee.rfind(separator))
+            .max()?;
+        callee.get(..cut)?.trim()
+    };
+    if language == Language::Rust && text.contains("::") {
+        let path: Vec<&str> = text
+            .split("::")
+            .map(str::trim)
+            .filter(|segment| !segment.starts_with('<'))
+            .collect();
+        if path
+            .iter()
+            .all(|s| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_'))
+        {
+            return Some(path.join("::"));
+        }
+    }
+    if language == Language::Python
+        && text
+            .split('.')
+            .all(|s| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_'))
+    {
+        return Some(text.to_string());
+    }
+    let last = text
+        .rsplit(['.', ':', '>'])
+        .find(|segment| !segment.is_empty())?;
+    let last = last
+        .split(['(', '['])
+        .next()
+        .unwrap_or(last)
+        .trim_start_matches(['&', '*', '$', '@'])
+        .trim();
+    if last.is_empty() {
+        None
+    } else {
+        Some(last.to_string())
+    }
+}
+
+fn callee_node<'tree>(node: &TsNode<'tree>, language: Language) -> Option<TsNode<'tree>> {
+    match language {
         Language::Rust => node.child_by_field_name("function"),
         Language::JavaScript | Language::Jsx | Language::TypeScript | Language::Tsx => node
             .child_by_field_name("function")
@@ -2121,27 +2958,7 @@ fn call_name(node: &TsNode, source: &str, language: Language) -> Option<String>
         Language::Perl => node.child_by_field_name("method"),
         Language::Powershell => node.child_by_field_name("name"),
         _ => None,
-    }?;
-
-    let raw = callee.utf8_text(source.as_bytes()).ok()?.to_string();
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return None;
     }
-
-    let name = trimmed
-        .rsplit("::")
-        .next()
-        .unwrap_or(trimmed)
-        .rsplit('.')
-        .next()
-        .unwrap_or(trimmed)
-        .rsplit("->")
-        .next()
-        .unwrap_or(trimmed)
-        .to_string();
-
-    if name.is_empty() { None } else { Some(name) }
 }
 
 fn map_node_kind(kind: &str, language: Language) -> (Option<NodeKind>, bool) {
@@ -2161,7 +2978,7 @@ fn map_node_kind(kind: &str, language: Language) -> (Option<NodeKind>, bool) {
         // === JavaScript/TypeScript family ===
         Language::JavaScript | Language::Jsx | Language::TypeScript | Language::Tsx => match kind {
             "function_declaration" | "arrow_function" => (Some(NodeKind::Function), false),
-            "class_declaration" => (Some(NodeKind::Class), true),
+            "class_declaration" | "abstract_class_declaration" => (Some(NodeKind::Class), true),
             "method_definition" => (Some(NodeKind::Method), false),
             "interface_declaration" => (Some(NodeKind::Interface), true),
             "type_alias_declaration" => (Some(NodeKind::TypeAlias), false),
@@ -2485,10 +3302,25 @@ fn map_node_kind(kind: &str, language: Language) -> (Option<NodeKind>, bool) {
         | Language::Toml
         | Language::Yaml
         | Language::Liquid
+        | Language::Prisma
         | Language::Unknown => (None, false),
     }
 }
 
+/// Submodule paths to leave out of the scan when `[indexing]
+/// index_submodules` is off.
+fn skipped_submodules(project_root: &Path) -> HashSet<String> {
+    if crate::config::load_toml_config(project_root)
+        .map_or(true, |cfg| cfg.indexing.index_submodules)
+    {
+        return HashSet::new();
+    }
+    worktree::submodules(project_root)
+        .into_iter()
+        .map(|submodule| submodule.path)
+        .collect()
+}
+
 fn scan_directory(
     root_dir: &Path,
     config: &CodeGraphConfig,
@@ -2496,6 +3328,9 @@ fn scan_directory(
 ) -> Vec<String> {
     let mut files = Vec::new();
     let mut count = 0;
+    let sensitive = SensitiveFiles::for_project(root_dir);
+    let skipped_submodules = skipped_submodules(root_dir);
+    let filter = PathFilter::for_config(config);
 
     let mut stack = vec![root_dir.to_path_buf()];
     while let Some(dir) = stack.pop() {
@@ -2510,11 +3345,17 @@ fn scan_directory(
                 Ok(rel) => rel,
                 Err(_) => continue,
             };
-            let rel_str = rel_path.to_string_lossy().to_string();
+            // Always store forward-slash paths so records match across platforms.
+            let rel_str = rel_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
 
             if entry.file_type().is_ok_and(|t| t.is_dir()) {
-                let dir_pattern = format!("{}/", rel_str);
-                if config.exclude.iter().any(|p| matches_glob(&dir_pattern, p)) {
+                if filter.exclude.is_match(format!("{rel_str}/"))
+                    || skipped_submodules.contains(&rel_str)
+                {
                     continue;
                 }
                 // Skip Python virtual environments by their canonical marker file
@@ -2524,7 +3365,11 @@ fn scan_directory(
                 }
                 stack.push(path);
             } else if entry.file_type().is_ok_and(|t| t.is_file()) {
-                if should_include_file(&rel_str, config) {
+                if sensitive.is_sensitive(&rel_str) {
+                    debug!(file = %rel_str, "skipping sensitive file");
+                    continue;
+                }
+                if filter.includes(&rel_str) {
                     files.push(rel_str.clone());
                     count += 1;
                     on_progress(count, &rel_str);
@@ -2536,24 +3381,61 @@ fn scan_directory(
     files
 }
 
-fn should_include_file(file_path: &str, config: &CodeGraphConfig) -> bool {
-    for pattern in &config.exclude {
-        if matches_glob(file_path, pattern) {
-            return false;
-        }
-    }
+/// Whether an `exclude` pattern matches the file or directory at
+/// `file_path`.
+pub(crate) fn is_excluded(file_path: &str, config: &CodeGraphConfig) -> bool {
+    let filter = PathFilter::for_config(config);
+    filter.exclude.is_match(file_path) || filter.exclude.is_match(format!("{file_path}/"))
+}
 
-    for pattern in &config.include {
-        if matches_glob(file_path, pattern) {
-            return true;
-        }
+pub(crate) fn should_include_file(file_path: &str, config: &CodeGraphConfig) -> bool {
+    PathFilter::for_config(config).includes(file_path)
+}
+
+/// A config's include and exclude patterns, each compiled into one
+/// `GlobSet`. Invalid patterns never match.
+struct PathFilter {
+    include_patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl PathFilter {
+    /// The filter for `config`. The last one built is kept, so repeated
+    /// syncs with the same patterns (watch mode, the daemon) and per-path
+    /// checks compile them once.
+    fn for_config(config: &CodeGraphConfig) -> Arc<Self> {
+        static LAST: Mutex<Option<Arc<PathFilter>>> = Mutex::new(None);
+        let mut last = LAST.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(filter) = last.as_ref().filter(|f| {
+            f.include_patterns == config.include && f.exclude_patterns == config.exclude
+        }) {
+            return Arc::clone(filter);
+        }
+        let filter = Arc::new(Self {
+            include_patterns: config.include.clone(),
+            exclude_patterns: config.exclude.clone(),
+            include: glob_set(&config.include),
+            exclude: glob_set(&config.exclude),
+        });
+        *last = Some(Arc::clone(&filter));
+        filter
     }
 
-    false
+    fn includes(&self, file_path: &str) -> bool {
+        !self.exclude.is_match(file_path) && self.include.is_match(file_path)
+    }
 }
 
-fn matches_glob(file_path: &str, pattern: &str) -> bool {
-    globset::Glob::new(pattern).is_ok_and(|glob| glob.compile_matcher().is_match(file_path))
+fn glob_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        if let Ok(glob) = Glob::new(pattern) {
+            builder.add(glob);
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
 }
 
 fn detect_language(path: &str) -> Language {
@@ -2581,6 +3463,7 @@ fn detect_language(path: &str) -> Language {
         "swift" => Language::Swift,
         "kt" => Language::Kotlin,
         "liquid" => Language::Liquid,
+        "prisma" => Language::Prisma,
         "razor" | "cshtml" => Language::Blazor,
         // New languages
         "sh" | "bash" => Language::Bash,
@@ -2735,6 +3618,8 @@ fn walk_markdown_node(
                         line: start.row as i64 + 1,
                         column: start.column as i64,
                         candidates: None,
+                        arity: None,
+                        receiver: None,
                     });
                 }
             }
@@ -1,78 +1,243 @@
 #![forbid(unsafe_code)]
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::path::Path;
+
+use rusqlite::Connection;
 
 use crate::db;
-use crate::types::{Edge, EdgeKind, Subgraph, TraversalDirection, TraversalOptions};
+use crate::error::Result;
+use crate::types::{
+    Edge, EdgeKind, Node, NodeKind, Subgraph, TraversalDirection, TraversalOptions,
+};
 
 #[derive(Debug, Default)]
 pub struct Graph;
 
+/// Default traversal cost per edge kind. Call edges carry the most useful
+/// context per node, containment the least.
+pub const fn default_edge_cost(kind: EdgeKind) -> f64 {
+    match kind {
+        EdgeKind::Calls => 1.0,
+        EdgeKind::References | EdgeKind::Instantiates | EdgeKind::Overrides => 1.5,
+        EdgeKind::Extends | EdgeKind::Implements | EdgeKind::TypeOf | EdgeKind::Returns => 2.0,
+        EdgeKind::Imports | EdgeKind::Exports | EdgeKind::Decorates => 2.5,
+        EdgeKind::Contains => 3.0,
+    }
+}
+
+/// Minimum number of edges considered per node and direction during expansion.
+const MIN_NEIGHBOURHOOD_FETCH: usize = 256;
+
+/// Maximum number of frontier nodes loaded per batched query.
+const FRONTIER_BATCH_SIZE: usize = 128;
+
+/// A pending node in the best-first frontier, ordered so that the
+/// lowest accumulated cost pops first (ties broken by insertion order).
+struct Frontier {
+    cost: f64,
+    seq: usize,
+    node_id: String,
+    depth: usize,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// The symbols named `name`, sorted by file and line, for commands and
+/// tools that take a symbol by name.
+///
+/// `file` keeps those in one file: an absolute path inside the project, a
+/// project-relative path, or a trailing part of one (`math.ts` for
+/// `src/math.ts`). Imports and exports of the name are dropped when the
+/// definition itself is indexed.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be read.
+pub fn find_symbol(
+    conn: &Connection,
+    project_root: &Path,
+    name: &str,
+    file: Option<&str>,
+) -> Result<Vec<Node>> {
+    let mut candidates = db::find_nodes_by_name(conn, name)?;
+    if let Some(file) = file {
+        candidates.retain(|n| matches_file_hint(project_root, &n.file_path, file));
+    }
+    let is_import_or_export = |n: &Node| matches!(n.kind, NodeKind::Import | NodeKind::Export);
+    if candidates.iter().any(|n| !is_import_or_export(n)) {
+        candidates.retain(|n| !is_import_or_export(n));
+    }
+    candidates.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+    Ok(candidates)
+}
+
+/// Whether a node stored under `file_path` (project-relative) is in the file
+/// a caller named.
+fn matches_file_hint(project_root: &Path, file_path: &str, hint: &str) -> bool {
+    let hint = Path::new(hint).strip_prefix(project_root).map_or_else(
+        |_| hint.to_string(),
+        |rel| rel.to_string_lossy().replace('\\', "/"),
+    );
+    let hint = hint.trim_start_matches("./");
+    file_path == hint
+        || file_path
+            .strip_suffix(hint)
+            .is_some_and(|prefix| prefix.ends_with('/'))
+}
+
+/// Expand `roots` into a subgraph using a best-first traversal.
+///
+/// Nodes are visited in order of accumulated edge cost (see
+/// [`TraversalOptions::edge_costs`]), so when the `limit` edge budget runs out
+/// the cheapest relationships have already been collected. `max_depth` still
+/// bounds the number of hops from any root.
+///
+/// Neighbours go onto a cost-ordered heap and a node joins the subgraph only
+/// when it is popped, so a node reached cheaply through a later edge still
+/// wins over an earlier, costlier path.
+///
+/// The heap is drained in waves: every entry cheaper than the cheapest one
+/// plus the smallest step cost is popped together, since no path found
+/// while expanding the wave can undercut it. Each wave (up to
+/// `FRONTIER_BATCH_SIZE` nodes) loads its nodes and edges with one
+/// `IN (...)` query per table and direction.
 pub fn build_subgraph(
     conn: &rusqlite::Connection,
     roots: &[String],
     options: &TraversalOptions,
-) -> std::io::Result<Subgraph> {
-    let mut nodes = HashMap::new();
-    let mut edges = Vec::new();
+) -> Result<Subgraph> {
+    let mut nodes = BTreeMap::new();
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut seen_edges = HashSet::new();
     let mut visited = HashSet::new();
 
     let max_depth = options.max_depth.unwrap_or(1);
     let include_start = options.include_start.unwrap_or(true);
     let limit = options.limit.unwrap_or(200);
     let direction = options.direction.unwrap_or(TraversalDirection::Both);
-    let edge_kinds = options.edge_kinds.as_ref();
+    let edge_kinds = options.edge_kinds.as_deref();
     let node_kinds = options.node_kinds.as_ref();
+    let min_step = min_step_cost(options, direction);
 
-    let mut queue = VecDeque::new();
+    let mut seq = 0usize;
+    let mut queue = BinaryHeap::new();
     for root in roots {
-        queue.push_back((root.clone(), 0));
+        queue.push(Frontier {
+            cost: 0.0,
+            seq,
+            node_id: root.clone(),
+            depth: 0,
+        });
+        seq += 1;
     }
 
-    while let Some((node_id, depth)) = queue.pop_front() {
-        if depth > max_depth {
-            continue;
-        }
+    let fetch_limit = limit.max(MIN_NEIGHBOURHOOD_FETCH);
 
-        if !visited.insert(node_id.clone()) {
-            continue;
-        }
-
-        if (include_start || depth > 0)
-            && let Some(node) = db::get_node_by_id(conn, &node_id)?
-            && node_kinds.is_none_or(|kinds| kinds.contains(&node.kind))
-        {
-            nodes.insert(node_id.clone(), node);
-        }
-
-        if edges.len() >= limit {
+    'expand: loop {
+        let wave = pop_wave(&mut queue, &mut visited, max_depth, min_step);
+        if wave.is_empty() {
             break;
         }
 
-        let mut next_edges = Vec::new();
-        if direction != TraversalDirection::Incoming {
-            next_edges.extend(fetch_edges(conn, &node_id, true, edge_kinds, limit)?);
-        }
-        if direction != TraversalDirection::Outgoing {
-            next_edges.extend(fetch_edges(conn, &node_id, false, edge_kinds, limit)?);
-        }
+        let ids: Vec<String> = wave.iter().map(|e| e.node_id.clone()).collect();
+        let wanted: Vec<String> = wave
+            .iter()
+            .filter(|e| include_start || e.depth > 0)
+            .map(|e| e.node_id.clone())
+            .collect();
+        let mut found: HashMap<String, Node> = db::get_nodes_by_ids(conn, &wanted)?
+            .into_iter()
+            .map(|node| (node.id.clone(), node))
+            .collect();
+        // Take a full neighbourhood even under a tight budget so the cost
+        // ordering below, not the database row order, decides what is kept.
+        let mut outgoing = if direction == TraversalDirection::Incoming {
+            HashMap::new()
+        } else {
+            wave_edges(conn, &ids, true, edge_kinds, fetch_limit)?
+        };
+        let mut incoming = if direction == TraversalDirection::Outgoing {
+            HashMap::new()
+        } else {
+            wave_edges(conn, &ids, false, edge_kinds, fetch_limit)?
+        };
+
+        for Frontier {
+            cost,
+            node_id,
+            depth,
+            ..
+        } in wave
+        {
+            if let Some(node) = found.remove(&node_id)
+                && node_kinds.is_none_or(|kinds| kinds.contains(&node.kind))
+            {
+                nodes.insert(node_id.clone(), node);
+            }
 
-        for edge in next_edges {
             if edges.len() >= limit {
-                break;
+                break 'expand;
             }
-            let (next_id, next_depth) = if edge.source == node_id {
-                (edge.target.clone(), depth + 1)
-            } else {
-                (edge.source.clone(), depth + 1)
-            };
-            edges.push(edge);
-            if next_depth <= max_depth {
-                queue.push_back((next_id, next_depth));
+
+            let next_edges = costed_edges(
+                outgoing.remove(&node_id).unwrap_or_default(),
+                incoming.remove(&node_id).unwrap_or_default(),
+                options,
+            );
+            for (step_cost, edge) in next_edges {
+                if edges.len() >= limit {
+                    break;
+                }
+                let next_id = if edge.source == node_id {
+                    edge.target.clone()
+                } else {
+                    edge.source.clone()
+                };
+                let next_depth = depth + 1;
+                // An edge is discovered from both ends when traversing in
+                // both directions; keep the first sighting only.
+                let (source, target, kind, line, column) = edge_key(&edge);
+                if seen_edges.insert((source.to_owned(), target.to_owned(), kind, line, column)) {
+                    edges.push(edge);
+                }
+                if next_depth <= max_depth && !visited.contains(&next_id) {
+                    queue.push(Frontier {
+                        cost: cost + step_cost,
+                        seq,
+                        node_id: next_id,
+                        depth: next_depth,
+                    });
+                    seq += 1;
+                }
             }
         }
     }
 
+    edges.sort_by(|a, b| edge_key(a).cmp(&edge_key(b)));
+
     Ok(Subgraph {
         nodes,
         edges,
@@ -80,28 +245,122 @@ pub fn build_subgraph(
     })
 }
 
-fn fetch_edges(
+/// Pop the next wave of nodes to expand off `queue`: up to
+/// `FRONTIER_BATCH_SIZE` entries no path found while expanding the wave can
+/// undercut. A node is admitted when it is popped, at its cheapest cost;
+/// later entries for it are stale.
+fn pop_wave(
+    queue: &mut BinaryHeap<Frontier>,
+    visited: &mut HashSet<String>,
+    max_depth: usize,
+    min_step: f64,
+) -> Vec<Frontier> {
+    let mut wave: Vec<Frontier> = Vec::new();
+    while wave.len() < FRONTIER_BATCH_SIZE
+        && let Some(next) = queue.peek()
+    {
+        if let Some(first) = wave.first()
+            && !(next.cost < first.cost + min_step || (min_step >= 0.0 && next.cost <= first.cost))
+        {
+            break;
+        }
+        let Some(entry) = queue.pop() else { break };
+        if entry.depth <= max_depth && visited.insert(entry.node_id.clone()) {
+            wave.push(entry);
+        }
+    }
+    wave
+}
+
+/// The edges of a node with the cost of following each, cheapest first,
+/// dropping those below `options.min_confidence`.
+fn costed_edges(
+    outgoing: Vec<Edge>,
+    incoming: Vec<Edge>,
+    options: &TraversalOptions,
+) -> Vec<(f64, Edge)> {
+    let incoming_factor = options.incoming_cost_factor.unwrap_or(1.0);
+    let edge_cost = |edge: &Edge| {
+        options
+            .edge_costs
+            .as_ref()
+            .and_then(|costs| costs.get(&edge.kind).copied())
+            .unwrap_or_else(|| default_edge_cost(edge.kind))
+    };
+    let mut costed: Vec<(f64, Edge)> = outgoing
+        .into_iter()
+        .map(|edge| (edge_cost(&edge), edge))
+        .chain(
+            incoming
+                .into_iter()
+                .map(|edge| (edge_cost(&edge) * incoming_factor, edge)),
+        )
+        .collect();
+    if let Some(min) = options.min_confidence {
+        costed.retain(|(_, edge)| edge_confidence(edge) >= min);
+    }
+    // Stable sort keeps the database ordering among equal-cost edges.
+    costed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    costed
+}
+
+/// The cheapest cost a single traversal step can add, given the configured
+/// edge costs and, when incoming edges are followed, the incoming factor.
+fn min_step_cost(options: &TraversalOptions, direction: TraversalDirection) -> f64 {
+    let cheapest_default = default_edge_cost(EdgeKind::Calls);
+    let cheapest = options
+        .edge_costs
+        .iter()
+        .flat_map(|costs| costs.values().copied())
+        .fold(cheapest_default, f64::min);
+    let incoming_factor = options.incoming_cost_factor.unwrap_or(1.0);
+    if direction == TraversalDirection::Outgoing || incoming_factor >= 1.0 {
+        cheapest
+    } else {
+        cheapest.min(cheapest * incoming_factor)
+    }
+}
+
+/// The outgoing or incoming edges of each of `ids`, keeping the database
+/// order within each node's edges.
+fn wave_edges(
     conn: &rusqlite::Connection,
-    node_id: &str,
+    ids: &[String],
     outgoing: bool,
-    edge_kinds: Option<&Vec<EdgeKind>>,
+    edge_kinds: Option<&[EdgeKind]>,
     limit: usize,
-) -> std::io::Result<Vec<Edge>> {
-    let mut results = Vec::new();
-    if let Some(kinds) = edge_kinds {
-        for kind in kinds {
-            let edges = if outgoing {
-                db::get_edges_by_source(conn, node_id, Some(*kind), limit)?
-            } else {
-                db::get_edges_by_target(conn, node_id, Some(*kind), limit)?
-            };
-            results.extend(edges);
-        }
-    } else if outgoing {
-        results = db::get_edges_by_source(conn, node_id, None, limit)?;
-    } else {
-        results = db::get_edges_by_target(conn, node_id, None, limit)?;
+) -> Result<HashMap<String, Vec<Edge>>> {
+    let batch = db::get_edges_for_nodes(conn, ids, outgoing, edge_kinds, Some(limit))?;
+    let mut grouped: HashMap<String, Vec<Edge>> = HashMap::new();
+    for edge in batch {
+        let key = if outgoing {
+            edge.source.clone()
+        } else {
+            edge.target.clone()
+        };
+        grouped.entry(key).or_default().push(edge);
     }
+    Ok(grouped)
+}
+
+/// Confidence of an edge's target: `metadata.resolution.confidence` for an
+/// edge the resolver chose, 1.0 for one the extractor observed.
+pub fn edge_confidence(edge: &Edge) -> f64 {
+    edge.metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("resolution"))
+        .and_then(|resolution| resolution.get("confidence"))
+        .and_then(serde_json::Value::as_f64)
+        .unwrap_or(1.0)
+}
 
-    Ok(results)
+/// Identity of an edge for deduplication and canonical ordering.
+pub(crate) const fn edge_key(edge: &Edge) -> (&str, &str, EdgeKind, Option<i64>, Option<i64>) {
+    (
+        edge.source.as_str(),
+        edge.target.as_str(),
+        edge.kind,
+        edge.line,
+        edge.column,
+    )
 }
@@ -0,0 +1,608 @@
+#![forbid(unsafe_code)]
+
+//! GraphQL endpoint for `coraline serve --graphql`, so a frontend can fetch
+//! just the slice of the graph it draws:
+//!
+//! - `POST /graphql` with `{"query": "...", "variables": {...}, "operationName": "..."}`
+//! - `GET /graphql?query=<document>[&variables=<json>][&operationName=<name>]`
+//! - `GET /graphql/schema`: the schema in SDL
+//!
+//! Parsing, validation, and execution are [`juniper`]'s; the resolvers
+//! below answer from the same queries as the REST API (`db` lookups and
+//! search, `graph::build_subgraph`, `rest::call_neighbours`). Queries and
+//! introspection are supported; there are no mutations or subscriptions.
+//! Like the REST API it is read-only, unauthenticated, and serves one
+//! request at a time.
+
+use std::path::Path;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+use juniper::http::GraphQLRequest;
+use juniper::{
+    EmptyMutation, EmptySubscription, FieldError, FieldResult, ID, InputValue, RootNode,
+    graphql_object,
+};
+use rusqlite::Connection;
+use serde_json::{Value, json};
+use tracing::info;
+
+use crate::config::{self, SearchConfig};
+use crate::db::{self, DbStats};
+use crate::graph;
+use crate::rest::call_neighbours;
+use crate::types::{
+    Edge, EdgeKind, Node, NodeKind, SearchResult, Subgraph, TraversalDirection, TraversalOptions,
+};
+use crate::viz::{VizResponse, query_param, serve_http};
+
+pub const DEFAULT_PORT: u16 = 7881;
+
+const MAX_LIMIT: i32 = 200;
+const MAX_SUBGRAPH_LIMIT: i32 = 1000;
+const MAX_TRAVERSAL_DEPTH: i32 = 5;
+/// Most node-to-node hops (`callers`, `callees`, `edges`, `sourceNode`,
+/// `targetNode`) one query may chain, so `callers { callers { ... } }`
+/// cannot fan out without bound.
+const MAX_NODE_NESTING: usize = 6;
+
+/// The executable schema.
+pub type Schema = RootNode<Query, EmptyMutation<Context>, EmptySubscription<Context>>;
+
+static SCHEMA: LazyLock<Schema> = LazyLock::new(|| {
+    Schema::new(
+        Query,
+        EmptyMutation::<Context>::new(),
+        EmptySubscription::<Context>::new(),
+    )
+});
+
+/// What the resolvers read from: the project database and its search
+/// settings.
+pub struct Context {
+    conn: Mutex<Connection>,
+    search: SearchConfig,
+}
+
+impl juniper::Context for Context {}
+
+impl Context {
+    pub const fn new(conn: Connection, search: SearchConfig) -> Self {
+        Self {
+            conn: Mutex::new(conn),
+            search,
+        }
+    }
+
+    /// The context of the project at `project_root`.
+    pub fn open(project_root: &Path) -> crate::error::Result<Self> {
+        let conn = db::open_database(project_root)?;
+        let search = config::load_toml_config(project_root)
+            .unwrap_or_default()
+            .search;
+        Ok(Self::new(conn, search))
+    }
+
+    fn conn(&self) -> FieldResult<MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| FieldError::from("Database connection is unavailable"))
+    }
+}
+
+/// The schema in SDL, as served at `/graphql/schema`.
+pub fn schema_sdl() -> String {
+    SCHEMA.as_sdl()
+}
+
+/// Serve the endpoint on `addr` (e.g. `127.0.0.1:7881`) until the process
+/// is interrupted.
+pub fn serve(project_root: &Path, addr: &str) -> std::io::Result<()> {
+    let context = Context::open(project_root)?;
+    info!(addr, "GraphQL endpoint listening");
+    serve_http(addr, |method, url, body| route(&context, method, url, body))
+}
+
+/// Dispatch a `method` request for `url` (path plus optional query string).
+pub fn route(context: &Context, method: &str, url: &str, body: &str) -> VizResponse {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let request = match (method, path) {
+        ("GET", "/graphql/schema") => {
+            return VizResponse {
+                status: 200,
+                content_type: "text/plain; charset=utf-8",
+                body: schema_sdl(),
+            };
+        }
+        ("GET", "/graphql") => {
+            let Some(document) = query_param(query, "query") else {
+                return request_error("Missing query parameter 'query'");
+            };
+            let variables = match query_param(query, "variables") {
+                Some(raw) => match serde_json::from_str(&raw) {
+                    Ok(variables) => Some(variables),
+                    Err(err) => return request_error(format!("Invalid variables: {err}")),
+                },
+                None => None,
+            };
+            GraphQLRequest::new(document, query_param(query, "operationName"), variables)
+        }
+        ("POST", "/graphql") => match serde_json::from_str::<GraphQLRequest>(body) {
+            Ok(request) => request,
+            Err(err) => return request_error(format!("Invalid request body: {err}")),
+        },
+        (_, "/graphql" | "/graphql/schema") => {
+            return VizResponse::error(405, format!("{method} is not supported"));
+        }
+        _ => return VizResponse::error(404, "Not found"),
+    };
+
+    let response = run(&request, context);
+    let status = if response.get("data").is_some() {
+        200
+    } else {
+        400
+    };
+    VizResponse::json(status, &response)
+}
+
+fn request_error(message: impl Into<String>) -> VizResponse {
+    VizResponse::json(400, &json!({ "errors": [{ "message": message.into() }] }))
+}
+
+/// Run the operation `operation_name` (or the only one) of `document`,
+/// giving the GraphQL response: `data` unless the request itself is
+/// invalid, and `errors` if anything failed.
+pub fn execute(
+    context: &Context,
+    document: &str,
+    variables: Option<&Value>,
+    operation_name: Option<&str>,
+) -> Value {
+    let variables = match variables.cloned().map(serde_json::from_value::<InputValue>) {
+        Some(Ok(variables)) => Some(variables),
+        Some(Err(err)) => {
+            return json!({ "errors": [{ "message": format!("Invalid variables: {err}") }] });
+        }
+        None => None,
+    };
+    let request = GraphQLRequest::new(
+        document.to_string(),
+        operation_name.map(str::to_string),
+        variables,
+    );
+    run(&request, context)
+}
+
+fn run(request: &GraphQLRequest, context: &Context) -> Value {
+    // juniper panics on a mutation or subscription against a schema
+    // without one, so those are turned away first.
+    if let Some(keyword) = definition_keywords(&request.query)
+        .into_iter()
+        .find(|keyword| matches!(*keyword, "mutation" | "subscription"))
+    {
+        return json!({ "errors": [{ "message": format!("{keyword} operations are not supported") }] });
+    }
+    serde_json::to_value(request.execute_sync(&SCHEMA, context))
+        .unwrap_or_else(|err| json!({ "errors": [{ "message": err.to_string() }] }))
+}
+
+/// The keyword opening each top-level definition of `document` (`query`,
+/// `mutation`, `subscription`, `fragment`), skipping comments and strings.
+/// A shorthand `{ ... }` query has none.
+fn definition_keywords(document: &str) -> Vec<&str> {
+    let mut keywords = Vec::new();
+    let mut depth = 0usize;
+    let mut expect_keyword = true;
+    let mut chars = document.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '#' => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+            '"' => {
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            '{' | '(' | '[' => {
+                depth += 1;
+                expect_keyword = false;
+            }
+            '}' | ')' | ']' => {
+                depth = depth.saturating_sub(1);
+                expect_keyword = depth == 0 && c == '}';
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((index, c)) =
+                    chars.next_if(|&(_, c)| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    end = index + c.len_utf8();
+                }
+                if depth == 0 && expect_keyword {
+                    keywords.extend(document.get(start..end));
+                    expect_keyword = false;
+                }
+            }
+            _ => {}
+        }
+    }
+    keywords
+}
+
+/// The query root.
+pub struct Query;
+
+// Juniper hands resolvers their arguments by value.
+#[allow(clippy::needless_pass_by_value)]
+#[graphql_object(context = Context)]
+impl Query {
+    /// A node by ID.
+    fn node(context: &Context, id: ID) -> FieldResult<Option<NodeObject>> {
+        let node = db::get_node_by_id(&*context.conn()?, &id)?;
+        Ok(node.map(|node| NodeObject::new(node, 0)))
+    }
+
+    /// Nodes by ID, in order; null for unknown IDs.
+    fn nodes(context: &Context, ids: Vec<ID>) -> FieldResult<Vec<Option<NodeObject>>> {
+        let conn = context.conn()?;
+        let mut nodes = Vec::new();
+        for id in ids
+            .iter()
+            .take(usize::try_from(MAX_SUBGRAPH_LIMIT).unwrap_or_default())
+        {
+            let node = db::get_node_by_id(&conn, id)?;
+            nodes.push(node.map(|node| NodeObject::new(node, 0)));
+        }
+        drop(conn);
+        Ok(nodes)
+    }
+
+    /// Ranked symbol search.
+    fn search(
+        context: &Context,
+        query: String,
+        kind: Option<NodeKind>,
+        #[graphql(default = 20)] limit: i32,
+    ) -> FieldResult<Vec<SearchResultObject>> {
+        let results = db::search_nodes_with_config(
+            &*context.conn()?,
+            &query,
+            kind,
+            clamp(limit, 1, MAX_LIMIT),
+            &context.search,
+        )?;
+        Ok(results.into_iter().map(SearchResultObject).collect())
+    }
+
+    /// The nodes and edges within `depth` hops of `roots`.
+    fn subgraph(
+        context: &Context,
+        roots: Vec<ID>,
+        #[graphql(default = 1)] depth: i32,
+        #[graphql(default = TraversalDirection::Both)] direction: TraversalDirection,
+        edge_kinds: Option<Vec<EdgeKind>>,
+        node_kinds: Option<Vec<NodeKind>>,
+        #[graphql(default = 200)] limit: i32,
+    ) -> FieldResult<SubgraphObject> {
+        let options = TraversalOptions {
+            max_depth: Some(clamp(depth, 1, MAX_TRAVERSAL_DEPTH)),
+            edge_kinds,
+            node_kinds,
+            direction: Some(direction),
+            limit: Some(clamp(limit, 1, MAX_SUBGRAPH_LIMIT)),
+            include_start: Some(true),
+            ..TraversalOptions::default()
+        };
+        let roots: Vec<String> = roots.iter().map(ToString::to_string).collect();
+        let subgraph = graph::build_subgraph(&*context.conn()?, &roots, &options)?;
+        Ok(SubgraphObject(subgraph))
+    }
+
+    fn stats(context: &Context) -> FieldResult<StatsObject> {
+        Ok(StatsObject(db::get_db_stats(&*context.conn()?)?))
+    }
+}
+
+/// A node, and how many node hops the query took to reach it.
+pub struct NodeObject {
+    node: Node,
+    nesting: usize,
+}
+
+impl NodeObject {
+    const fn new(node: Node, nesting: usize) -> Self {
+        Self { node, nesting }
+    }
+
+    /// The nesting of the nodes this one leads to, or an error once the
+    /// query chains more than `MAX_NODE_NESTING` hops.
+    fn next_nesting(&self) -> FieldResult<usize> {
+        if self.nesting >= MAX_NODE_NESTING {
+            return Err(FieldError::from(format!(
+                "Query chains more than {MAX_NODE_NESTING} node lookups"
+            )));
+        }
+        Ok(self.nesting + 1)
+    }
+}
+
+#[graphql_object(name = "Node", context = Context)]
+impl NodeObject {
+    fn id(&self) -> ID {
+        ID::new(&self.node.id)
+    }
+
+    const fn kind(&self) -> NodeKind {
+        self.node.kind
+    }
+
+    fn name(&self) -> &str {
+        &self.node.name
+    }
+
+    fn qualified_name(&self) -> &str {
+        &self.node.qualified_name
+    }
+
+    fn file_path(&self) -> &str {
+        &self.node.file_path
+    }
+
+    fn language(&self) -> String {
+        snake_case(&self.node.language).unwrap_or_default()
+    }
+
+    fn start_line(&self) -> i32 {
+        int(self.node.start_line)
+    }
+
+    fn end_line(&self) -> i32 {
+        int(self.node.end_line)
+    }
+
+    fn start_column(&self) -> i32 {
+        int(self.node.start_column)
+    }
+
+    fn end_column(&self) -> i32 {
+        int(self.node.end_column)
+    }
+
+    fn signature(&self) -> Option<&str> {
+        self.node.signature.as_deref()
+    }
+
+    fn docstring(&self) -> Option<&str> {
+        self.node.docstring.as_deref()
+    }
+
+    fn visibility(&self) -> Option<String> {
+        self.node.visibility.as_ref().and_then(snake_case)
+    }
+
+    const fn is_exported(&self) -> bool {
+        self.node.is_exported
+    }
+
+    const fn is_async(&self) -> bool {
+        self.node.is_async
+    }
+
+    const fn is_static(&self) -> bool {
+        self.node.is_static
+    }
+
+    const fn is_abstract(&self) -> bool {
+        self.node.is_abstract
+    }
+
+    fn decorators(&self) -> Option<&[String]> {
+        self.node.decorators.as_deref()
+    }
+
+    fn type_parameters(&self) -> Option<&[String]> {
+        self.node.type_parameters.as_deref()
+    }
+
+    /// Edges from (OUTGOING), to (INCOMING), or either side of this node.
+    fn edges(
+        &self,
+        context: &Context,
+        #[graphql(default = TraversalDirection::Outgoing)] direction: TraversalDirection,
+        kinds: Option<Vec<EdgeKind>>,
+        #[graphql(default = 100)] limit: i32,
+    ) -> FieldResult<Vec<EdgeObject>> {
+        let nesting = self.next_nesting()?;
+        let limit = clamp(limit, 1, MAX_LIMIT);
+        let kinds: Vec<Option<EdgeKind>> =
+            kinds.map_or_else(|| vec![None], |kinds| kinds.into_iter().map(Some).collect());
+        let conn = context.conn()?;
+        let mut edges = Vec::new();
+        for kind in kinds {
+            if direction != TraversalDirection::Incoming {
+                edges.extend(db::get_edges_by_source(&conn, &self.node.id, kind, limit)?);
+            }
+            if direction != TraversalDirection::Outgoing {
+                edges.extend(db::get_edges_by_target(&conn, &self.node.id, kind, limit)?);
+            }
+        }
+        drop(conn);
+        edges.truncate(limit);
+        Ok(edges
+            .into_iter()
+            .map(|edge| EdgeObject { edge, nesting })
+            .collect())
+    }
+
+    /// Functions and methods that call this node.
+    fn callers(
+        &self,
+        context: &Context,
+        #[graphql(default = 20)] limit: i32,
+    ) -> FieldResult<Vec<Self>> {
+        self.call_neighbours(context, true, limit)
+    }
+
+    /// Functions and methods this node calls.
+    fn callees(
+        &self,
+        context: &Context,
+        #[graphql(default = 20)] limit: i32,
+    ) -> FieldResult<Vec<Self>> {
+        self.call_neighbours(context, false, limit)
+    }
+}
+
+impl NodeObject {
+    fn call_neighbours(
+        &self,
+        context: &Context,
+        incoming: bool,
+        limit: i32,
+    ) -> FieldResult<Vec<Self>> {
+        let nesting = self.next_nesting()?;
+        let neighbours = call_neighbours(
+            &*context.conn()?,
+            &self.node,
+            incoming,
+            clamp(limit, 1, MAX_LIMIT),
+        )?;
+        Ok(neighbours
+            .into_iter()
+            .map(|(node, _)| Self::new(node, nesting))
+            .collect())
+    }
+}
+
+/// An edge, with the nesting of the node it was reached from.
+pub struct EdgeObject {
+    edge: Edge,
+    nesting: usize,
+}
+
+#[graphql_object(name = "Edge", context = Context)]
+impl EdgeObject {
+    fn source(&self) -> ID {
+        ID::new(&self.edge.source)
+    }
+
+    fn target(&self) -> ID {
+        ID::new(&self.edge.target)
+    }
+
+    const fn kind(&self) -> EdgeKind {
+        self.edge.kind
+    }
+
+    fn line(&self) -> Option<i32> {
+        self.edge.line.map(int)
+    }
+
+    fn column(&self) -> Option<i32> {
+        self.edge.column.map(int)
+    }
+
+    /// 1.0 for an edge the extractor saw; the resolver's confidence otherwise.
+    fn confidence(&self) -> f64 {
+        graph::edge_confidence(&self.edge)
+    }
+
+    fn source_node(&self, context: &Context) -> FieldResult<Option<NodeObject>> {
+        self.endpoint(context, &self.edge.source)
+    }
+
+    fn target_node(&self, context: &Context) -> FieldResult<Option<NodeObject>> {
+        self.endpoint(context, &self.edge.target)
+    }
+}
+
+impl EdgeObject {
+    fn endpoint(&self, context: &Context, id: &str) -> FieldResult<Option<NodeObject>> {
+        let node = db::get_node_by_id(&*context.conn()?, id)?;
+        Ok(node.map(|node| NodeObject::new(node, self.nesting)))
+    }
+}
+
+pub struct SearchResultObject(SearchResult);
+
+#[graphql_object(name = "SearchResult", context = Context)]
+impl SearchResultObject {
+    fn score(&self) -> f64 {
+        f64::from(self.0.score)
+    }
+
+    fn node(&self) -> NodeObject {
+        NodeObject::new(self.0.node.clone(), 0)
+    }
+}
+
+pub struct SubgraphObject(Subgraph);
+
+#[graphql_object(name = "Subgraph", context = Context)]
+impl SubgraphObject {
+    fn roots(&self) -> Vec<ID> {
+        self.0.roots.iter().map(ID::new).collect()
+    }
+
+    fn nodes(&self) -> Vec<NodeObject> {
+        self.0
+            .nodes
+            .values()
+            .map(|node| NodeObject::new(node.clone(), 0))
+            .collect()
+    }
+
+    fn edges(&self) -> Vec<EdgeObject> {
+        self.0
+            .edges
+            .iter()
+            .map(|edge| EdgeObject {
+                edge: edge.clone(),
+                nesting: 0,
+            })
+            .collect()
+    }
+}
+
+pub struct StatsObject(DbStats);
+
+#[graphql_object(name = "Stats", context = Context)]
+impl StatsObject {
+    fn file_count(&self) -> i32 {
+        int(self.0.file_count)
+    }
+
+    fn node_count(&self) -> i32 {
+        int(self.0.node_count)
+    }
+
+    fn edge_count(&self) -> i32 {
+        int(self.0.edge_count)
+    }
+
+    fn unresolved_count(&self) -> i32 {
+        int(self.0.unresolved_count)
+    }
+}
+
+/// A GraphQL `Int` for a database integer, saturating at `i32::MAX`.
+fn int(value: i64) -> i32 {
+    i32::try_from(value).unwrap_or(if value < 0 { i32::MIN } else { i32::MAX })
+}
+
+/// A `limit`-style argument clamped to `min..=max`.
+fn clamp(value: i32, min: i32, max: i32) -> usize {
+    usize::try_from(value.clamp(min, max)).unwrap_or_default()
+}
+
+/// A `snake_case` enum the way it is serialised.
+fn snake_case(value: &impl serde::Serialize) -> Option<String> {
+    match serde_json::to_value(value) {
+        Ok(Value::String(name)) => Some(name),
+        _ => None,
+    }
+}
@@ -0,0 +1,374 @@
+#![forbid(unsafe_code)]
+
+//! Churn from git history.
+//!
+//! `coraline churn` (and, with `[history] on_index`, every index and sync
+//! that changed the graph) scans the latest `[history] max_commits` commits
+//! and stores in the `node_churn` table how many of them changed each file
+//! and each symbol, by how many authors, and when the newest did.
+//!
+//! A symbol's current line range is carried back through the history: a
+//! commit changed the symbol when one of its hunks overlaps the range as it
+//! was in that commit, and the range is then mapped onto the parent
+//! version. Uncommitted edits are mapped first without counting, and the
+//! walk stops where the symbol's lines were added. Renames are not
+//! followed, and on merged branches the mapping follows the log order, so
+//! the counts of long-lived branches are approximate.
+//!
+//! `coraline analyze hotspots` combines the counts with fan-in: code that
+//! changes often and that much of the project depends on is where a change
+//! is most likely to break something.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tracing::debug;
+
+use crate::config::HistoryConfig;
+use crate::db;
+use crate::issues::is_linkable;
+use crate::types::{Node, NodeChurn, NodeKind};
+use crate::utils::git;
+
+/// Outcome of [`record_churn`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChurnSummary {
+    pub commits_scanned: usize,
+    /// Indexed files changed by at least one scanned commit.
+    pub files: usize,
+    /// Symbols changed by at least one scanned commit.
+    pub nodes: usize,
+}
+
+/// A symbol ranked by [`hotspots`].
+#[derive(Debug, Clone)]
+pub struct Hotspot {
+    pub node: Node,
+    pub churn: NodeChurn,
+    /// Incoming `calls` and `references` edges from other nodes.
+    pub fan_in: i64,
+    /// `commits × (fan_in + 1)`.
+    pub score: i64,
+}
+
+/// One hunk of a zero-context diff. Each start is the first line of its
+/// range; an empty range starts at the line after the change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Hunk {
+    old_start: i64,
+    old_len: i64,
+    new_start: i64,
+    new_len: i64,
+}
+
+impl Hunk {
+    /// Parse `@@ -a,b +c,d @@`; a missing length is 1.
+    fn parse(header: &str) -> Option<Self> {
+        let mut fields = header.strip_prefix("@@ ")?.split(' ');
+        let (old_start, old_len) = range(fields.next()?.strip_prefix('-')?)?;
+        let (new_start, new_len) = range(fields.next()?.strip_prefix('+')?)?;
+        Some(Self {
+            old_start: if old_len == 0 {
+                old_start + 1
+            } else {
+                old_start
+            },
+            old_len,
+            new_start: if new_len == 0 {
+                new_start + 1
+            } else {
+                new_start
+            },
+            new_len,
+        })
+    }
+
+    /// Whether the hunk changes lines `start..=end` of the new version.
+    pub(crate) const fn touches(&self, start: i64, end: i64) -> bool {
+        if self.new_len == 0 {
+            // A deletion between two lines of the range.
+            start < self.new_start && self.new_start <= end
+        } else {
+            self.new_start <= end && start < self.new_start + self.new_len
+        }
+    }
+}
+
+fn range(spec: &str) -> Option<(i64, i64)> {
+    match spec.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((spec.parse().ok()?, 1)),
+    }
+}
+
+/// Map a line range of the new version of a file onto the old one through
+/// its (sorted) hunks; `None` when the whole range was added.
+fn map_range(hunks: &[Hunk], start: i64, end: i64) -> Option<(i64, i64)> {
+    let map = |line: i64, is_start: bool| {
+        let mut delta = 0;
+        for hunk in hunks {
+            if line < hunk.new_start {
+                break;
+            }
+            if line < hunk.new_start + hunk.new_len {
+                return if is_start {
+                    hunk.old_start
+                } else {
+                    hunk.old_start + hunk.old_len - 1
+                };
+            }
+            delta = (hunk.old_start + hunk.old_len) - (hunk.new_start + hunk.new_len);
+        }
+        line + delta
+    };
+    let (start, end) = (map(start, true), map(end, false));
+    (start <= end).then_some((start, end))
+}
+
+/// The hunks per file of a `git diff -U0` or `git log -p -U0` chunk. Files
+/// deleted by the change are left out.
+pub(crate) fn parse_diff(diff: &str) -> Vec<(String, Vec<Hunk>)> {
+    let mut files: Vec<(String, Vec<Hunk>)> = Vec::new();
+    let mut current: Option<usize> = None;
+    // Content lines still to skip in the current hunk; they may look like
+    // headers.
+    let mut pending = 0;
+    for line in diff.lines() {
+        if pending > 0 {
+            if !line.starts_with('\\') {
+                pending -= 1;
+            }
+            continue;
+        }
+        if line.starts_with("diff --git ") {
+            current = None;
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            current = path.trim_matches('"').strip_prefix("b/").map(|path| {
+                files.push((path.to_string(), Vec::new()));
+                files.len() - 1
+            });
+        } else if line.starts_with("@@ ") {
+            let Some(hunk) = Hunk::parse(line) else {
+                continue;
+            };
+            pending = hunk.old_len + hunk.new_len;
+            if let Some((_, hunks)) = current.and_then(|index| files.get_mut(index)) {
+                hunks.push(hunk);
+            }
+        }
+    }
+    for (_, hunks) in &mut files {
+        hunks.sort_by_key(|h| h.new_start);
+    }
+    files
+}
+
+/// Symbols of one file being carried back through the history.
+struct FileHistory {
+    file_node: Option<String>,
+    /// Node ids with their range in the version being visited; `None` once
+    /// the walk passed the commit that added them.
+    spans: Vec<(String, Option<(i64, i64)>)>,
+}
+
+impl FileHistory {
+    fn new(nodes: &[Node]) -> Self {
+        Self {
+            file_node: nodes
+                .iter()
+                .find(|n| n.kind == NodeKind::File)
+                .map(|n| n.id.clone()),
+            spans: nodes
+                .iter()
+                .filter(|n| is_linkable(n.kind))
+                .map(|n| (n.id.clone(), Some((n.start_line, n.end_line))))
+                .collect(),
+        }
+    }
+
+    /// Apply a change to the file: return the nodes it touched, then map
+    /// every range onto the parent version.
+    fn step(&mut self, hunks: &[Hunk]) -> Vec<&str> {
+        let mut touched = Vec::new();
+        for (id, span) in &mut self.spans {
+            let Some((start, end)) = *span else {
+                continue;
+            };
+            if hunks.iter().any(|h| h.touches(start, end)) {
+                touched.push(id.as_str());
+            }
+            *span = map_range(hunks, start, end);
+        }
+        touched.extend(self.file_node.as_deref());
+        touched
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    commits: i64,
+    authors: HashSet<String>,
+    last_modified: i64,
+}
+
+/// Scan the latest commits and store the churn of every indexed file and
+/// symbol, replacing the previous counts.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the project is not in a git work tree with at
+/// least one commit, or the database cannot be read or updated.
+pub fn record_churn(project_root: &Path, cfg: &HistoryConfig) -> std::io::Result<ChurnSummary> {
+    let mut conn = db::open_database(project_root)?;
+    let mut histories: HashMap<String, FileHistory> = HashMap::new();
+    for file in db::list_files(&conn)? {
+        let nodes = db::get_nodes_by_file(&conn, &file.path, None)?;
+        histories.insert(file.path, FileHistory::new(&nodes));
+    }
+
+    // Line ranges are those of the indexed working tree; carry them back
+    // to HEAD first.
+    let uncommitted = git(
+        project_root,
+        &[
+            "diff",
+            "-U0",
+            "--relative",
+            "--no-renames",
+            "--no-color",
+            "HEAD",
+        ],
+    )?;
+    for (path, hunks) in parse_diff(&uncommitted) {
+        if let Some(history) = histories.get_mut(&path) {
+            history.step(&hunks);
+        }
+    }
+
+    let log = git(
+        project_root,
+        &[
+            "log",
+            &format!("--max-count={}", cfg.max_commits),
+            "--format=%x1e%H%x1f%at%x1f%ae",
+            "-p",
+            "-U0",
+            "--relative",
+            "--no-renames",
+            "--no-color",
+            "--no-ext-diff",
+        ],
+    )?;
+
+    let mut summary = ChurnSummary::default();
+    let mut tallies: HashMap<String, Tally> = HashMap::new();
+    for record in log.split('\x1e').filter(|r| !r.trim().is_empty()) {
+        summary.commits_scanned += 1;
+        let (header, diff) = record.split_once('\n').unwrap_or((record, ""));
+        let mut fields = header.split('\x1f');
+        let (Some(hash), Some(time), Some(author)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let time: i64 = time.trim().parse().unwrap_or_default();
+        for (path, hunks) in parse_diff(diff) {
+            let Some(history) = histories.get_mut(&path) else {
+                continue;
+            };
+            for id in history.step(&hunks) {
+                let tally = tallies.entry(id.to_string()).or_default();
+                tally.commits += 1;
+                tally.authors.insert(author.trim().to_string());
+                tally.last_modified = tally.last_modified.max(time);
+            }
+        }
+        debug!(commit = hash, "scanned commit for churn");
+    }
+
+    let file_nodes: HashSet<&str> = histories
+        .values()
+        .filter_map(|h| h.file_node.as_deref())
+        .collect();
+    summary.files = tallies
+        .keys()
+        .filter(|id| file_nodes.contains(id.as_str()))
+        .count();
+    summary.nodes = tallies.len() - summary.files;
+
+    let churn: Vec<(String, NodeChurn)> = tallies
+        .into_iter()
+        .map(|(id, t)| {
+            let churn = NodeChurn {
+                commits: t.commits,
+                authors: i64::try_from(t.authors.len()).unwrap_or(i64::MAX),
+                last_modified: t.last_modified,
+            };
+            (id, churn)
+        })
+        .collect();
+    let recorded_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+        .unwrap_or_default();
+    db::replace_churn(&mut conn, &churn, recorded_at)?;
+    Ok(summary)
+}
+
+/// Symbols with recorded churn, highest score first.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be queried.
+pub fn hotspots(conn: &rusqlite::Connection) -> std::io::Result<Vec<Hotspot>> {
+    let mut hotspots: Vec<Hotspot> = db::list_churned_nodes(conn)?
+        .into_iter()
+        .map(|(node, churn, fan_in)| Hotspot {
+            score: churn.commits.saturating_mul(fan_in + 1),
+            node,
+            churn,
+            fan_in,
+        })
+        .collect();
+    hotspots.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| b.churn.commits.cmp(&a.churn.commits))
+            .then_with(|| {
+                (&a.node.file_path, a.node.start_line).cmp(&(&b.node.file_path, b.node.start_line))
+            })
+    });
+    Ok(hotspots)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+
+    use super::*;
+
+    fn hunk(header: &str) -> Hunk {
+        Hunk::parse(header).expect("the header should parse")
+    }
+
+    #[test]
+    fn maps_ranges_across_insertions_and_deletions() {
+        // Two lines inserted after line 2, then line 10 deleted.
+        let hunks = [hunk("@@ -2,0 +3,2 @@"), hunk("@@ -10 +11,0 @@")];
+        assert_eq!(map_range(&hunks, 5, 8), Some((3, 6)));
+        assert!(!hunks[0].touches(5, 8));
+        // A range wholly made of added lines did not exist before.
+        assert_eq!(map_range(&hunks, 3, 4), None);
+        // Deleting line 10 of the old version touches the range around it.
+        assert!(hunks[1].touches(9, 12));
+        assert_eq!(map_range(&hunks, 9, 12), Some((7, 11)));
+    }
+
+    #[test]
+    fn parse_diff_skips_hunk_content_that_looks_like_headers() {
+        let diff = "diff --git a/a.ts b/a.ts\n--- a/a.ts\n+++ b/a.ts\n@@ -1 +1,2 @@\n-x\n+++ b/fake.ts\n+y\ndiff --git a/gone.ts b/gone.ts\n--- a/gone.ts\n+++ /dev/null\n@@ -1 +0,0 @@\n-z\n";
+        let files = parse_diff(diff);
+        assert_eq!(files, [("a.ts".to_string(), vec![hunk("@@ -1 +1,2 @@")])]);
+    }
+}
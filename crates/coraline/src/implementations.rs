@@ -0,0 +1,614 @@
+#![forbid(unsafe_code)]
+
+//! Inheritance edges and the conformance report of
+//! `coraline analyze implementations`.
+//!
+//! At extraction, each declared supertype becomes an edge from the type
+//! declaring it: `extends` for a superclass or supertrait, `implements` for
+//! an interface or a Rust trait impl. A supertype defined in the same file
+//! is linked directly; any other is left to the resolver by name.
+//!
+//! | Language | `extends` | `implements` |
+//! |---|---|---|
+//! | Rust | `trait B: A` | `impl A for Type` |
+//! | JavaScript, TypeScript | `class B extends A`, `interface B extends A` | `class B implements A` |
+//! | Java | `class B extends A`, `interface B extends A` | `class B implements A` |
+//! | Python | `class B(A)` | |
+//! | C# | `class B : A` | `class B : IA`, `struct B : IA` |
+//!
+//! The report lists the types implementing a trait or interface, directly
+//! or through a subclass or subinterface, with the required methods each
+//! leaves undefined. Required methods are read from the source: trait
+//! method signatures without a default body, interface methods without a
+//! body or `?`, `abstract` methods, Python `@abstractmethod`s, and every
+//! method of a `Protocol`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+use rusqlite::Connection;
+use tree_sitter::{Node as TsNode, Tree};
+
+use crate::db;
+use crate::extraction::parse_source;
+use crate::types::{Edge, EdgeKind, Language, Node, NodeKind, UnresolvedReference};
+
+/// Implementing edges read per type.
+const MAX_IMPLEMENTERS: usize = 1000;
+
+/// Kinds a supertype or implementer may be.
+pub(crate) const fn is_type_kind(kind: NodeKind) -> bool {
+    matches!(
+        kind,
+        NodeKind::Class
+            | NodeKind::Struct
+            | NodeKind::Interface
+            | NodeKind::Trait
+            | NodeKind::Protocol
+            | NodeKind::Enum
+    )
+}
+
+const fn is_abstract_kind(kind: NodeKind) -> bool {
+    matches!(
+        kind,
+        NodeKind::Interface | NodeKind::Trait | NodeKind::Protocol
+    )
+}
+
+/// The `extends` and `implements` edges of a file's type declarations, and
+/// a reference for each supertype not defined in it.
+pub(crate) fn extract_heritage(
+    root: TsNode,
+    source: &str,
+    language: Language,
+    nodes: &[Node],
+) -> (Vec<Edge>, Vec<UnresolvedReference>) {
+    let mut edges = Vec::new();
+    let mut unresolved = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        stack.extend(node.named_children(&mut node.walk()));
+        let Some((owner, supertypes)) = heritage(node, source, language) else {
+            continue;
+        };
+        let row = i64::try_from(node.start_position().row + 1).unwrap_or(i64::MAX);
+        let Some(owner) = nodes.iter().find(|n| {
+            n.name == owner
+                && if node.kind() == "impl_item" {
+                    matches!(n.kind, NodeKind::Struct | NodeKind::Enum)
+                } else {
+                    is_type_kind(n.kind) && n.start_line == row
+                }
+        }) else {
+            continue;
+        };
+        for (kind, type_node) in supertypes {
+            let Some(name) = type_name(text(type_node, source)) else {
+                continue;
+            };
+            if language == Language::Python && name == "object" {
+                continue;
+            }
+            let start = type_node.start_position();
+            let (line, column) = (
+                i64::try_from(start.row + 1).unwrap_or(i64::MAX),
+                i64::try_from(start.column).unwrap_or(i64::MAX),
+            );
+            match nodes
+                .iter()
+                .find(|n| n.id != owner.id && is_type_kind(n.kind) && n.name == name)
+            {
+                Some(target) => edges.push(Edge {
+                    source: owner.id.clone(),
+                    target: target.id.clone(),
+                    kind,
+                    metadata: None,
+                    line: Some(line),
+                    column: Some(column),
+                }),
+                None => unresolved.push(UnresolvedReference {
+                    from_node_id: owner.id.clone(),
+                    reference_name: name,
+                    reference_kind: kind,
+                    line,
+                    column,
+                    candidates: None,
+                    arity: None,
+                    receiver: None,
+                }),
+            }
+        }
+    }
+    (edges, unresolved)
+}
+
+/// The name of the type `node` declares and the syntax of its supertypes,
+/// or `None` when it declares none.
+fn heritage<'t>(
+    node: TsNode<'t>,
+    source: &str,
+    language: Language,
+) -> Option<(String, Vec<(EdgeKind, TsNode<'t>)>)> {
+    let mut supertypes = Vec::new();
+    match (language, node.kind()) {
+        (Language::Rust, "impl_item") => {
+            let owner = type_name(text(node.child_by_field_name("type")?, source))?;
+            supertypes.push((EdgeKind::Implements, node.child_by_field_name("trait")?));
+            return Some((owner, supertypes));
+        }
+        (Language::Rust, "trait_item") => {
+            if let Some(bounds) = node.child_by_field_name("bounds") {
+                supertypes.extend(
+                    bounds
+                        .named_children(&mut bounds.walk())
+                        .filter(|bound| bound.kind() != "lifetime")
+                        .map(|bound| (EdgeKind::Extends, bound)),
+                );
+            }
+        }
+        (
+            Language::JavaScript | Language::Jsx | Language::TypeScript | Language::Tsx,
+            "class_declaration" | "abstract_class_declaration" | "interface_declaration",
+        ) => {
+            js_supertypes(node, &mut supertypes);
+        }
+        (Language::Java, "class_declaration" | "interface_declaration" | "enum_declaration") => {
+            for child in node.named_children(&mut node.walk()) {
+                let kind = match child.kind() {
+                    "superclass" => {
+                        supertypes.extend(
+                            child
+                                .named_children(&mut child.walk())
+                                .map(|class| (EdgeKind::Extends, class)),
+                        );
+                        continue;
+                    }
+                    "super_interfaces" => EdgeKind::Implements,
+                    "extends_interfaces" => EdgeKind::Extends,
+                    _ => continue,
+                };
+                for list in child.named_children(&mut child.walk()) {
+                    supertypes.extend(
+                        list.named_children(&mut list.walk())
+                            .map(|interface| (kind, interface)),
+                    );
+                }
+            }
+        }
+        (Language::Python, "class_definition") => {
+            if let Some(bases) = node.child_by_field_name("superclasses") {
+                supertypes.extend(
+                    bases
+                        .named_children(&mut bases.walk())
+                        .filter(|base| {
+                            matches!(base.kind(), "identifier" | "attribute" | "subscript")
+                        })
+                        .map(|base| (EdgeKind::Extends, base)),
+                );
+            }
+        }
+        (
+            Language::CSharp,
+            "class_declaration"
+            | "struct_declaration"
+            | "interface_declaration"
+            | "record_declaration",
+        ) => {
+            csharp_supertypes(node, source, &mut supertypes);
+        }
+        _ => return None,
+    }
+    if supertypes.is_empty() {
+        return None;
+    }
+    let owner = text(node.child_by_field_name("name")?, source).to_string();
+    Some((owner, supertypes))
+}
+
+/// The supertypes of a JavaScript or TypeScript class or interface.
+fn js_supertypes<'t>(node: TsNode<'t>, supertypes: &mut Vec<(EdgeKind, TsNode<'t>)>) {
+    for child in node.named_children(&mut node.walk()) {
+        match child.kind() {
+            "class_heritage" => {
+                for clause in child.named_children(&mut child.walk()) {
+                    match clause.kind() {
+                        "extends_clause" => supertypes.extend(
+                            clause
+                                .children_by_field_name("value", &mut clause.walk())
+                                .map(|value| (EdgeKind::Extends, value)),
+                        ),
+                        "implements_clause" => supertypes.extend(
+                            clause
+                                .named_children(&mut clause.walk())
+                                .map(|interface| (EdgeKind::Implements, interface)),
+                        ),
+                        // JavaScript puts the superclass right under the heritage.
+                        _ => supertypes.push((EdgeKind::Extends, clause)),
+                    }
+                }
+            }
+            "extends_type_clause" => supertypes.extend(
+                child
+                    .children_by_field_name("type", &mut child.walk())
+                    .map(|interface| (EdgeKind::Extends, interface)),
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// The supertypes of a C# class, struct, interface, or record.
+fn csharp_supertypes<'t>(
+    node: TsNode<'t>,
+    source: &str,
+    supertypes: &mut Vec<(EdgeKind, TsNode<'t>)>,
+) {
+    for child in node.named_children(&mut node.walk()) {
+        if child.kind() != "base_list" {
+            continue;
+        }
+        for base in child.named_children(&mut child.walk()) {
+            if !matches!(
+                base.kind(),
+                "identifier" | "generic_name" | "qualified_name"
+            ) {
+                continue;
+            }
+            // C# lists the base class and interfaces alike; interfaces
+            // are told apart by the `I` prefix convention.
+            let kind = match node.kind() {
+                "interface_declaration" => EdgeKind::Extends,
+                "struct_declaration" => EdgeKind::Implements,
+                _ if type_name(text(base, source)).is_some_and(|n| is_interface_name(&n)) => {
+                    EdgeKind::Implements
+                }
+                _ => EdgeKind::Extends,
+            };
+            supertypes.push((kind, base));
+        }
+    }
+}
+
+fn text<'s>(node: TsNode, source: &'s str) -> &'s str {
+    node.utf8_text(source.as_bytes()).unwrap_or_default()
+}
+
+/// The bare name of a type expression: `Display` for `std::fmt::Display`,
+/// `Repository` for `Repository<User>`, `Base` for `models.Base`. `None`
+/// for references, trait objects, and other non-path types.
+fn type_name(text: &str) -> Option<String> {
+    let end = text.find(['<', '[', '(']).unwrap_or(text.len());
+    let name = text[..end].trim().rsplit(['.', ':']).next()?;
+    let mut chars = name.chars();
+    let first = chars.next()?;
+    ((first.is_alphabetic() || first == '_') && chars.all(|c| c.is_alphanumeric() || c == '_'))
+        .then(|| name.to_string())
+}
+
+fn is_interface_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next() == Some('I') && chars.next().is_some_and(char::is_uppercase)
+}
+
+/// A type implementing the report's target.
+#[derive(Debug, Clone)]
+pub struct Implementation {
+    pub node: Node,
+    /// The edge linking it to the target, or to the type in `via`.
+    pub kind: EdgeKind,
+    /// The subclass or subinterface it implements the target through;
+    /// `None` when it names the target itself.
+    pub via: Option<String>,
+    /// Required methods of the target neither it nor a type between it and
+    /// the target defines. Always empty for traits and interfaces.
+    pub missing: Vec<String>,
+}
+
+/// The output of [`find_implementations`].
+#[derive(Debug, Clone)]
+pub struct ImplementationsReport {
+    pub target: Node,
+    /// Methods of the target without a default body.
+    pub required: Vec<String>,
+    /// Direct implementers first, then by file and line.
+    pub implementations: Vec<Implementation>,
+}
+
+/// The traits, interfaces, and classes named `name`, in `file` when given.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be queried.
+pub fn find_targets(
+    conn: &Connection,
+    name: &str,
+    file: Option<&str>,
+) -> std::io::Result<Vec<Node>> {
+    Ok(db::find_nodes_by_name(conn, name)?
+        .into_iter()
+        .filter(|node| {
+            matches!(
+                node.kind,
+                NodeKind::Trait | NodeKind::Interface | NodeKind::Protocol | NodeKind::Class
+            )
+        })
+        .filter(|node| file.is_none_or(|file| node.file_path == file))
+        .collect())
+}
+
+/// List the types implementing `target` and the required methods each
+/// leaves undefined.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be queried.
+pub fn find_implementations(
+    project_root: &Path,
+    conn: &Connection,
+    target: &Node,
+) -> std::io::Result<ImplementationsReport> {
+    let mut sources = Sources::new(project_root);
+    let own = sources.members(target, None);
+    let required = own.required;
+
+    let mut implementations = Vec::new();
+    let mut seen = HashSet::from([target.id.clone()]);
+    // (type, the name it is reached through, methods it inherits)
+    let mut queue = VecDeque::from([(target.id.clone(), None::<String>, own.provided)]);
+    while let Some((id, via, inherited)) = queue.pop_front() {
+        let mut edges =
+            db::get_edges_by_target(conn, &id, Some(EdgeKind::Implements), MAX_IMPLEMENTERS)?;
+        edges.extend(db::get_edges_by_target(
+            conn,
+            &id,
+            Some(EdgeKind::Extends),
+            MAX_IMPLEMENTERS,
+        )?);
+        for edge in edges {
+            if !seen.insert(edge.source.clone()) {
+                continue;
+            }
+            let Some(node) = db::get_node_by_id(conn, &edge.source)? else {
+                continue;
+            };
+            // A Rust type's methods live in the impl block the edge starts in.
+            let block = if node.language == Language::Rust {
+                edge.line
+            } else {
+                None
+            };
+            let mut provided = inherited.clone();
+            provided.extend(sources.members(&node, block).provided);
+            let missing = if is_abstract_kind(node.kind) {
+                Vec::new()
+            } else {
+                required
+                    .iter()
+                    .filter(|method| !provided.contains(*method))
+                    .cloned()
+                    .collect()
+            };
+            // Implementing a subtrait does not implement its supertraits in
+            // Rust; each takes its own impl, and so its own edge.
+            if node.language != Language::Rust {
+                queue.push_back((node.id.clone(), Some(node.name.clone()), provided));
+            }
+            implementations.push(Implementation {
+                node,
+                kind: edge.kind,
+                via: via.clone(),
+                missing,
+            });
+        }
+    }
+    implementations.sort_by(|a, b| {
+        (a.via.is_some(), &a.node.file_path, a.node.start_line).cmp(&(
+            b.via.is_some(),
+            &b.node.file_path,
+            b.node.start_line,
+        ))
+    });
+
+    Ok(ImplementationsReport {
+        target: target.clone(),
+        required,
+        implementations,
+    })
+}
+
+/// The methods a type declares.
+#[derive(Debug, Default)]
+struct Members {
+    /// Declared without a body.
+    required: Vec<String>,
+    /// Defined, including default bodies.
+    provided: HashSet<String>,
+}
+
+/// Parsed project files, read once per report.
+struct Sources<'a> {
+    project_root: &'a Path,
+    parsed: HashMap<String, Option<(String, Tree)>>,
+}
+
+impl<'a> Sources<'a> {
+    fn new(project_root: &'a Path) -> Self {
+        Self {
+            project_root,
+            parsed: HashMap::new(),
+        }
+    }
+
+    /// The members of `node`'s declaration, or of the Rust impl block
+    /// holding `block_line`.
+    fn members(&mut self, node: &Node, block_line: Option<i64>) -> Members {
+        let project_root = self.project_root;
+        let parsed = self
+            .parsed
+            .entry(node.file_path.clone())
+            .or_insert_with(|| {
+                let source = std::fs::read_to_string(project_root.join(&node.file_path)).ok()?;
+                let tree = parse_source(&source, node.language)?;
+                Some((source, tree))
+            });
+        let Some((source, tree)) = parsed else {
+            return Members::default();
+        };
+        let declaration = block_line.map_or_else(
+            || declaration_at(tree.root_node(), node, source),
+            |line| impl_at(tree.root_node(), line - 1),
+        );
+        declaration.map_or_else(Members::default, |declaration| {
+            members(declaration, source, node.language)
+        })
+    }
+}
+
+/// The innermost `impl` block spanning `row`.
+fn impl_at(root: TsNode, row: i64) -> Option<TsNode> {
+    let mut found = None;
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        let (start, end) = (
+            i64::try_from(node.start_position().row).unwrap_or(i64::MAX),
+            i64::try_from(node.end_position().row).unwrap_or(i64::MAX),
+        );
+        if row < start || end < row {
+            continue;
+        }
+        if node.kind() == "impl_item" {
+            found = Some(node);
+        }
+        stack.extend(node.named_children(&mut node.walk()));
+    }
+    found
+}
+
+/// The declaration with a body that `node` was extracted from.
+fn declaration_at<'t>(root: TsNode<'t>, node: &Node, source: &str) -> Option<TsNode<'t>> {
+    let row = node.start_line - 1;
+    let mut stack = vec![root];
+    while let Some(candidate) = stack.pop() {
+        let (start, end) = (
+            i64::try_from(candidate.start_position().row).unwrap_or(i64::MAX),
+            i64::try_from(candidate.end_position().row).unwrap_or(i64::MAX),
+        );
+        if row < start || end < row {
+            continue;
+        }
+        if start == row
+            && candidate.child_by_field_name("body").is_some()
+            && candidate
+                .child_by_field_name("name")
+                .is_some_and(|name| text(name, source) == node.name)
+        {
+            return Some(candidate);
+        }
+        stack.extend(candidate.named_children(&mut candidate.walk()));
+    }
+    None
+}
+
+fn members(declaration: TsNode, source: &str, language: Language) -> Members {
+    let mut members = Members::default();
+    let Some(body) = declaration.child_by_field_name("body") else {
+        return members;
+    };
+    let name = |member: TsNode| {
+        member
+            .child_by_field_name("name")
+            .map(|name| text(name, source).to_string())
+    };
+    // Words before a member's name: its modifiers and return type.
+    let prefix = |member: TsNode| {
+        member.child_by_field_name("name").map_or("", |name| {
+            source
+                .get(member.start_byte()..name.start_byte())
+                .unwrap_or_default()
+        })
+    };
+    let protocol = language == Language::Python
+        && declaration
+            .child_by_field_name("superclasses")
+            .is_some_and(|bases| text(bases, source).contains("Protocol"));
+
+    for member in body.named_children(&mut body.walk()) {
+        let (required, member) = match (language, member.kind()) {
+            (Language::Rust, "function_signature_item")
+            | (
+                Language::JavaScript | Language::Jsx | Language::TypeScript | Language::Tsx,
+                "abstract_method_signature",
+            ) => (true, member),
+            (Language::Rust, "function_item")
+            | (
+                Language::JavaScript | Language::Jsx | Language::TypeScript | Language::Tsx,
+                "method_definition",
+            ) => (false, member),
+            (
+                Language::JavaScript | Language::Jsx | Language::TypeScript | Language::Tsx,
+                "method_signature",
+            ) => {
+                if member
+                    .children(&mut member.walk())
+                    .any(|child| child.kind() == "?")
+                {
+                    continue;
+                }
+                (true, member)
+            }
+            (Language::Java | Language::CSharp, "method_declaration") => {
+                let bodyless = member.child_by_field_name("body").is_none()
+                    && !member
+                        .children(&mut member.walk())
+                        .any(|child| child.kind() == "arrow_expression_clause");
+                if bodyless
+                    && prefix(member)
+                        .split_whitespace()
+                        .any(|word| word == "static")
+                {
+                    continue;
+                }
+                (bodyless, member)
+            }
+            (Language::Python, "function_definition") => (protocol, member),
+            (Language::Python, "decorated_definition") => {
+                let Some(definition) = member.child_by_field_name("definition") else {
+                    continue;
+                };
+                let abstract_method = member.named_children(&mut member.walk()).any(|child| {
+                    child.kind() == "decorator" && text(child, source).ends_with("abstractmethod")
+                });
+                (abstract_method || protocol, definition)
+            }
+            _ => continue,
+        };
+        let Some(method) = name(member) else { continue };
+        if protocol && method.starts_with("__") {
+            continue;
+        }
+        if required {
+            members.required.push(method);
+        } else {
+            members.provided.insert(method);
+        }
+    }
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_name() {
+        assert_eq!(type_name("std::fmt::Display").as_deref(), Some("Display"));
+        assert_eq!(type_name("Repository<User>").as_deref(), Some("Repository"));
+        assert_eq!(type_name("models.Base").as_deref(), Some("Base"));
+        assert_eq!(type_name("Generic[T]").as_deref(), Some("Generic"));
+        assert_eq!(type_name("&T"), None);
+        assert_eq!(type_name("dyn Any"), None);
+        assert!(is_interface_name("IRepository"));
+        assert!(!is_interface_name("Item"));
+    }
+}
@@ -0,0 +1,387 @@
+#![forbid(unsafe_code)]
+
+//! Issue-tracker linkage.
+//!
+//! `coraline link-issues` finds issue ids (`[issues] pattern`) in two places
+//! and links them to nodes in the `node_issues` table:
+//!
+//! - commit messages: the recent commits whose message names an issue are
+//!   matched to nodes through `git blame`, so an issue is linked to the
+//!   symbols whose current lines those commits wrote;
+//! - comments and docstrings: an id in a comment is linked to the node the
+//!   comment documents (the one declared right below it) or else to the
+//!   innermost node containing it.
+//!
+//! Each scan replaces the previous links. `coraline issues-for` reads them
+//! back to explain why a piece of code exists.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+use regex::Regex;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::config;
+use crate::db;
+use crate::types::{IssueLink, IssueSource, Node, NodeKind};
+use crate::utils::git;
+
+/// Longest comment text stored as a link summary.
+const MAX_SUMMARY_CHARS: usize = 200;
+
+/// Outcome of [`link_issues`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkSummary {
+    /// False when the project is not in a git work tree; only comments were
+    /// scanned.
+    pub git: bool,
+    pub commits_scanned: usize,
+    /// Scanned commits whose message names at least one issue.
+    pub commits_with_issues: usize,
+    /// Comment and docstring occurrences of issue ids.
+    pub comment_mentions: usize,
+    /// Distinct issue ids linked to at least one node.
+    pub issues: usize,
+    pub nodes: usize,
+    pub links: usize,
+}
+
+/// Issue ids `pattern` finds in `text`, in order of first occurrence: the
+/// first capture group when the pattern has one, else the whole match.
+pub fn extract_issue_ids(pattern: &Regex, text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    pattern
+        .captures_iter(text)
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(0)))
+        .map(|m| m.as_str().to_string())
+        .filter(|id| !id.is_empty() && seen.insert(id.clone()))
+        .collect()
+}
+
+/// Scan commit messages and comments and store the issue links found,
+/// replacing earlier ones.
+pub fn link_issues(project_root: &Path) -> std::io::Result<LinkSummary> {
+    let cfg = config::load_toml_config(project_root)?.issues;
+    let pattern = Regex::new(&cfg.pattern).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid [issues] pattern: {e}"),
+        )
+    })?;
+
+    let mut conn = db::open_database(project_root)?;
+    let files: Vec<String> = db::list_files(&conn)?.into_iter().map(|f| f.path).collect();
+    let mut nodes_by_file: HashMap<String, Vec<Node>> = HashMap::new();
+    for path in &files {
+        nodes_by_file.insert(path.clone(), db::get_nodes_by_file(&conn, path, None)?);
+    }
+
+    let mut summary = LinkSummary::default();
+    let mut links = Vec::new();
+
+    match issue_commits(project_root, &pattern, cfg.max_commits) {
+        Ok((scanned, commits)) => {
+            summary.git = true;
+            summary.commits_scanned = scanned;
+            summary.commits_with_issues = commits.len();
+            link_commits(project_root, &commits, &nodes_by_file, &mut links);
+        }
+        Err(err) => debug!("Skipping commit messages: {err}"),
+    }
+
+    for path in &files {
+        let Some(nodes) = nodes_by_file.get(path) else {
+            continue;
+        };
+        summary.comment_mentions += link_comments(project_root, path, nodes, &pattern, &mut links);
+    }
+
+    let mut seen = HashSet::new();
+    links.retain(|l: &IssueLink| {
+        seen.insert((l.node_id.clone(), l.issue_id.clone(), l.reference.clone()))
+    });
+    summary.links = links.len();
+    summary.issues = links
+        .iter()
+        .map(|l| &l.issue_id)
+        .collect::<HashSet<_>>()
+        .len();
+    summary.nodes = links
+        .iter()
+        .map(|l| &l.node_id)
+        .collect::<HashSet<_>>()
+        .len();
+
+    db::replace_issue_links(&mut conn, &links)?;
+    Ok(summary)
+}
+
+/// A commit whose message names issues.
+struct IssueCommit {
+    subject: String,
+    issues: Vec<String>,
+    /// Touched files, relative to the project root.
+    files: Vec<String>,
+}
+
+/// The commits among the latest `max_commits` whose message names an issue,
+/// by hash, and the number of commits scanned.
+fn issue_commits(
+    project_root: &Path,
+    pattern: &Regex,
+    max_commits: usize,
+) -> std::io::Result<(usize, HashMap<String, IssueCommit>)> {
+    // Records start with \x1e; hash and message end with \x1f, then come
+    // the touched paths (relative to the project root), one per line.
+    let log = git(
+        project_root,
+        &[
+            "log",
+            &format!("--max-count={max_commits}"),
+            "--format=%x1e%H%x1f%B%x1f",
+            "--name-only",
+            "--relative",
+            "--no-renames",
+        ],
+    )?;
+
+    let mut scanned = 0;
+    let mut commits = HashMap::new();
+    for record in log.split('\x1e').filter(|r| !r.trim().is_empty()) {
+        scanned += 1;
+        let mut fields = record.splitn(3, '\x1f');
+        let (Some(hash), Some(message)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let issues = extract_issue_ids(pattern, message);
+        if issues.is_empty() {
+            continue;
+        }
+        let files = fields
+            .next()
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect();
+        commits.insert(
+            hash.trim().to_string(),
+            IssueCommit {
+                subject: message
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+                issues,
+                files,
+            },
+        );
+    }
+    Ok((scanned, commits))
+}
+
+/// Link each issue commit to the nodes containing the lines `git blame`
+/// attributes to it.
+fn link_commits(
+    project_root: &Path,
+    commits: &HashMap<String, IssueCommit>,
+    nodes_by_file: &HashMap<String, Vec<Node>>,
+    links: &mut Vec<IssueLink>,
+) {
+    let touched: BTreeSet<&str> = commits
+        .values()
+        .flat_map(|c| c.files.iter().map(String::as_str))
+        .filter(|f| nodes_by_file.contains_key(*f))
+        .collect();
+
+    for path in touched {
+        let blame = match git(project_root, &["blame", "--porcelain", "--", path]) {
+            Ok(blame) => blame,
+            Err(err) => {
+                warn!(path, "Skipping issue links from git blame: {err}");
+                continue;
+            }
+        };
+        let nodes = &nodes_by_file[path];
+        let mut linked = HashSet::new();
+        for (hash, line) in blamed_lines(&blame) {
+            let Some(commit) = commits.get(hash) else {
+                continue;
+            };
+            let Some(node) = innermost_node(nodes, line) else {
+                continue;
+            };
+            if !linked.insert((hash, node.id.as_str())) {
+                continue;
+            }
+            for issue in &commit.issues {
+                links.push(IssueLink {
+                    node_id: node.id.clone(),
+                    issue_id: issue.clone(),
+                    source: IssueSource::Commit,
+                    reference: hash.to_string(),
+                    summary: commit.subject.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// `(commit hash, final line)` of every line in `git blame --porcelain`
+/// output.
+fn blamed_lines(blame: &str) -> impl Iterator<Item = (&str, i64)> {
+    blame.lines().filter_map(|line| {
+        let mut fields = line.split(' ');
+        let hash = fields.next()?;
+        if hash.len() < 40 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let _original = fields.next()?;
+        let final_line = fields.next()?.parse().ok()?;
+        Some((hash, final_line))
+    })
+}
+
+/// Link issue ids in the comments and docstrings of `path`; returns the
+/// number of mentions found.
+fn link_comments(
+    project_root: &Path,
+    path: &str,
+    nodes: &[Node],
+    pattern: &Regex,
+    links: &mut Vec<IssueLink>,
+) -> usize {
+    let mut mentions = 0;
+
+    for node in nodes.iter().filter(|n| is_linkable(n.kind)) {
+        let Some(docstring) = node.docstring.as_deref() else {
+            continue;
+        };
+        for issue in extract_issue_ids(pattern, docstring) {
+            mentions += 1;
+            links.push(IssueLink {
+                node_id: node.id.clone(),
+                issue_id: issue,
+                source: IssueSource::Comment,
+                reference: format!("{path}:{}", node.start_line),
+                summary: truncate(docstring.lines().next().unwrap_or_default().trim()),
+            });
+        }
+    }
+
+    let Ok(source) = std::fs::read_to_string(project_root.join(path)) else {
+        return mentions;
+    };
+    let source_lines: Vec<&str> = source.lines().collect();
+    for (index, line) in source_lines.iter().enumerate() {
+        let Some(comment) = comment_text(line) else {
+            continue;
+        };
+        let issues = extract_issue_ids(pattern, comment);
+        if issues.is_empty() {
+            continue;
+        }
+        let line_number = i64::try_from(index + 1).unwrap_or(i64::MAX);
+        let Some(node) = documented_node(nodes, &source_lines, index)
+            .or_else(|| innermost_node(nodes, line_number))
+        else {
+            continue;
+        };
+        for issue in issues {
+            mentions += 1;
+            links.push(IssueLink {
+                node_id: node.id.clone(),
+                issue_id: issue,
+                source: IssueSource::Comment,
+                reference: format!("{path}:{line_number}"),
+                summary: truncate(comment.trim()),
+            });
+        }
+    }
+    mentions
+}
+
+/// The comment part of a source line: the whole line when it starts with a
+/// comment marker, else the text from a trailing ` //`, ` #`, or ` --`.
+fn comment_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    if ["//", "/*", "*", "#", "--", "<!--"]
+        .iter()
+        .any(|marker| trimmed.starts_with(marker))
+    {
+        return Some(trimmed);
+    }
+    [" //", " #", " --"]
+        .iter()
+        .filter_map(|marker| line.find(marker))
+        .min()
+        .map(|start| &line[start..])
+}
+
+/// The node declared right below the comment block containing line `index`
+/// (0-based), if any.
+fn documented_node<'a>(nodes: &'a [Node], lines: &[&str], index: usize) -> Option<&'a Node> {
+    let next = lines
+        .iter()
+        .enumerate()
+        .skip(index + 1)
+        .find(|(_, l)| comment_text(l).is_none_or(|c| c.len() != l.trim_start().len()))
+        .map(|(i, _)| i64::try_from(i + 1).unwrap_or(i64::MAX))?;
+    nodes
+        .iter()
+        .filter(|n| is_linkable(n.kind) && n.start_line == next)
+        .max_by_key(|n| n.end_line)
+}
+
+/// The smallest node whose range contains `line`; the file node when no
+/// other node does.
+fn innermost_node(nodes: &[Node], line: i64) -> Option<&Node> {
+    nodes
+        .iter()
+        .filter(|n| is_linkable(n.kind) && n.start_line <= line && line <= n.end_line)
+        .min_by_key(|n| n.end_line - n.start_line)
+        .or_else(|| nodes.iter().find(|n| n.kind == NodeKind::File))
+}
+
+/// Symbols issues are linked to. Import and export nodes only wrap the
+/// declarations they share a range with, and a `todo` is one comment line.
+pub const fn is_linkable(kind: NodeKind) -> bool {
+    !matches!(
+        kind,
+        NodeKind::File | NodeKind::Import | NodeKind::Export | NodeKind::Parameter | NodeKind::Todo
+    )
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_SUMMARY_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn extracts_capture_group_or_whole_match() {
+        let github = Regex::new(r"#\d+").expect("valid pattern");
+        assert_eq!(
+            extract_issue_ids(&github, "Fix #12 and #7, again #12"),
+            ["#12", "#7"]
+        );
+        let jira = Regex::new(r"\b([A-Z]+-\d+)\b").expect("valid pattern");
+        assert_eq!(extract_issue_ids(&jira, "CORE-9: retry"), ["CORE-9"]);
+    }
+
+    #[test]
+    fn comment_text_finds_leading_and_trailing_comments() {
+        assert_eq!(comment_text("    // see #4"), Some("// see #4"));
+        assert_eq!(comment_text("x = 1  # hack for #9"), Some(" # hack for #9"));
+        assert_eq!(comment_text("let color = \"#123\";"), None);
+    }
+}
@@ -2,30 +2,59 @@
 // Transitive dependency version conflicts we can't control (base64, getrandom, hashbrown).
 #![allow(clippy::multiple_crate_versions)]
 
+pub mod annotations;
+pub mod architecture;
 pub mod audit;
+pub mod branches;
+pub mod code_graph;
 pub mod config;
+pub mod config_keys;
 pub mod context;
+pub mod coverage;
+pub mod daemon;
 pub mod db;
+pub mod dead_code;
+pub mod diff_context;
+pub mod duplicates;
+pub mod error;
+pub mod export;
 pub mod extraction;
 pub mod graph;
+pub mod graphql;
+pub mod history;
+pub mod implementations;
+pub mod issues;
+pub mod lifecycle;
 pub mod logging;
+pub mod map;
 pub mod mcp;
+pub mod mcp_http;
 pub mod memory;
+pub mod models;
+pub mod output;
+pub mod packages;
+pub mod prompts;
+pub mod remotes;
 pub mod resolution;
+pub mod resources;
+pub mod rest;
+pub mod routes;
+pub mod scip;
 pub mod security;
+pub mod sensitive;
+pub mod staleness;
 pub mod sync;
+pub mod todos;
 pub mod tools;
+pub mod tour;
+pub mod tui;
 pub mod types;
 pub mod update;
 pub mod utils;
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 pub mod vectors;
+pub mod viz;
+pub mod watch;
+pub mod worktree;
 
-#[derive(Debug, Default)]
-pub struct CodeGraph;
-
-impl CodeGraph {
-    pub const fn new() -> Self {
-        Self
-    }
-}
+pub use code_graph::CodeGraph;
+pub use error::CoralineError;
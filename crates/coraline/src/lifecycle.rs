@@ -0,0 +1,198 @@
+#![forbid(unsafe_code)]
+
+//! Hook points in the indexing lifecycle, for enrichment such as attaching
+//! coverage data or lint results to nodes right after extraction.
+//!
+//! `index` and `sync` fire, in order:
+//!
+//! - `pre_scan` — before the project is scanned for files
+//! - `post_file_extract` — after each file's nodes and edges are stored
+//! - `post_index` — after every changed file is stored
+//! - `post_resolve` — after references are resolved; the graph is complete
+//!
+//! Each event runs the command configured under `[lifecycle]` in
+//! `config.toml` (the event as JSON on stdin, its name in
+//! `CORALINE_HOOK_EVENT`), then every Rust callback registered for it on
+//! [`LifecycleHooks`]. Callbacks get the indexing connection, so they can
+//! write their own tables. Hook failures are logged and never fail the
+//! index or sync.
+
+use std::path::Path;
+use std::time::Duration;
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::config::{self, LifecycleConfig};
+use crate::types::{Edge, FileRecord, Node};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleEvent {
+    PreScan,
+    PostFileExtract,
+    PostIndex,
+    PostResolve,
+}
+
+impl LifecycleEvent {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::PreScan => "pre_scan",
+            Self::PostFileExtract => "post_file_extract",
+            Self::PostIndex => "post_index",
+            Self::PostResolve => "post_resolve",
+        }
+    }
+}
+
+/// Which entry point fired the event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexMode {
+    /// `coraline index` / [`crate::extraction::index_all`].
+    Full,
+    /// `coraline sync` / [`crate::extraction::sync`].
+    Sync,
+}
+
+/// One lifecycle event, as passed to callbacks and (serialized) to commands.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HookEvent<'a> {
+    PreScan {
+        mode: IndexMode,
+    },
+    PostFileExtract {
+        mode: IndexMode,
+        file: &'a FileRecord,
+        nodes: &'a [Node],
+        edges: &'a [Edge],
+    },
+    PostIndex {
+        mode: IndexMode,
+        /// Files (re-)indexed.
+        files: usize,
+        /// Files dropped from the index (sync only).
+        files_removed: usize,
+        nodes: usize,
+        edges: usize,
+    },
+    PostResolve {
+        mode: IndexMode,
+        resolved: usize,
+        remaining: usize,
+    },
+}
+
+impl HookEvent<'_> {
+    pub const fn kind(&self) -> LifecycleEvent {
+        match self {
+            Self::PreScan { .. } => LifecycleEvent::PreScan,
+            Self::PostFileExtract { .. } => LifecycleEvent::PostFileExtract,
+            Self::PostIndex { .. } => LifecycleEvent::PostIndex,
+            Self::PostResolve { .. } => LifecycleEvent::PostResolve,
+        }
+    }
+}
+
+type Callback = Box<dyn Fn(&HookEvent<'_>, &Connection) -> std::io::Result<()> + Send + Sync>;
+
+/// The commands and callbacks run at each [`LifecycleEvent`].
+#[derive(Default)]
+pub struct LifecycleHooks {
+    commands: LifecycleConfig,
+    callbacks: Vec<(LifecycleEvent, Callback)>,
+}
+
+impl std::fmt::Debug for LifecycleHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LifecycleHooks")
+            .field("commands", &self.commands)
+            .field("callbacks", &self.callbacks.len())
+            .finish()
+    }
+}
+
+impl LifecycleHooks {
+    /// Hooks with no commands and no callbacks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The commands configured under `[lifecycle]` for `project_root`.
+    pub fn from_config(project_root: &Path) -> Self {
+        match config::load_toml_config(project_root) {
+            Ok(cfg) => Self::new().with_commands(cfg.lifecycle),
+            Err(err) => {
+                warn!("Skipping lifecycle hook commands, could not load config: {err}");
+                Self::new()
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn with_commands(mut self, commands: LifecycleConfig) -> Self {
+        self.commands = commands;
+        self
+    }
+
+    /// Run `callback` at every `event`, after its command.
+    #[must_use]
+    pub fn with_callback(
+        mut self,
+        event: LifecycleEvent,
+        callback: impl Fn(&HookEvent<'_>, &Connection) -> std::io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.callbacks.push((event, Box::new(callback)));
+        self
+    }
+
+    /// Fire `event`: its command, then its callbacks.
+    pub fn fire(&self, project_root: &Path, conn: &Connection, event: &HookEvent<'_>) {
+        let kind = event.kind();
+        if let Some(command) = self.command(kind) {
+            self.run_command(project_root, command, event);
+        }
+        for (_, callback) in self.callbacks.iter().filter(|(e, _)| *e == kind) {
+            if let Err(err) = callback(event, conn) {
+                warn!(
+                    event = kind.as_str(),
+                    "Lifecycle hook callback failed: {err}"
+                );
+            }
+        }
+    }
+
+    fn command(&self, event: LifecycleEvent) -> Option<&str> {
+        match event {
+            LifecycleEvent::PreScan => self.commands.pre_scan.as_deref(),
+            LifecycleEvent::PostFileExtract => self.commands.post_file_extract.as_deref(),
+            LifecycleEvent::PostIndex => self.commands.post_index.as_deref(),
+            LifecycleEvent::PostResolve => self.commands.post_resolve.as_deref(),
+        }
+    }
+
+    fn run_command(&self, project_root: &Path, command: &str, event: &HookEvent<'_>) {
+        let kind = event.kind().as_str();
+        let body = match serde_json::to_string(event) {
+            Ok(body) => body,
+            Err(err) => {
+                warn!(event = kind, "Failed to serialize lifecycle event: {err}");
+                return;
+            }
+        };
+        let timeout = Duration::from_secs(self.commands.timeout_secs.max(1));
+        debug!(event = kind, command, "running lifecycle hook command");
+        if let Err(err) = crate::sync::run_hook_command(
+            project_root,
+            command,
+            &[("CORALINE_HOOK_EVENT", kind)],
+            &body,
+            timeout,
+        ) {
+            warn!(event = kind, "Lifecycle hook command failed: {err}");
+        }
+    }
+}
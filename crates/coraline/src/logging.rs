@@ -4,18 +4,52 @@
 //!
 //! Initializes `tracing` with:
 //! - File output to `.coraline/logs/coraline.log` (daily rotation)
+//! - MCP request log in `.coraline/logs/mcp.log` (daily rotation, last 7 days)
 //! - Stderr fallback when no project root is available
 //! - Log level controlled by `CORALINE_LOG` env var (default: `coraline=info`)
+//!
+//! The MCP log receives only events with target [`MCP_TARGET`]. Its level
+//! starts at `info` and is changed at runtime by the MCP `logging/setLevel`
+//! request (see [`set_mcp_level`]).
 
 use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
 
+use tracing::subscriber::Interest;
+use tracing::{Level, Subscriber};
 use tracing_appender::non_blocking::WorkerGuard;
-use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{
+    EnvFilter, Layer, filter, fmt, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+/// Target of the events written to `mcp.log`: one per JSON-RPC request,
+/// tool execution, and error response.
+pub const MCP_TARGET: &str = "mcp";
+
+/// Levels accepted by the MCP `logging/setLevel` request.
+pub const MCP_LOG_LEVELS: &[&str] = &[
+    "debug",
+    "info",
+    "notice",
+    "warning",
+    "error",
+    "critical",
+    "alert",
+    "emergency",
+];
+
+/// Rotated `mcp.log` files kept.
+const MCP_LOG_FILES: usize = 7;
+
+static MCP_LEVEL: AtomicU8 = AtomicU8::new(level_index(Level::INFO));
 
 /// Opaque guard that must be kept alive for the duration of the program.
-/// When dropped, the file appender worker thread flushes and exits.
+/// When dropped, the file appender worker threads flush and exit.
 pub struct LogGuard {
-    _guard: Option<WorkerGuard>,
+    _guards: Vec<WorkerGuard>,
 }
 
 /// Initialize structured logging.
@@ -35,7 +69,9 @@ pub fn init(project_root: Option<&Path>) -> LogGuard {
     // When no project root is available (e.g. fresh `init` before `.coraline`
     // exists), avoid stderr logging so progress output remains stable.
     if project_root.is_none() {
-        return LogGuard { _guard: None };
+        return LogGuard {
+            _guards: Vec::new(),
+        };
     }
 
     // Attempt to set up file logging
@@ -44,16 +80,32 @@ pub fn init(project_root: Option<&Path>) -> LogGuard {
         if std::fs::create_dir_all(&log_dir).is_ok() {
             let file_appender = tracing_appender::rolling::daily(&log_dir, "coraline.log");
             let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+            let mut guards = vec![guard];
+
+            let mcp_layer = RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix("mcp.log")
+                .max_log_files(MCP_LOG_FILES)
+                .build(&log_dir)
+                .ok()
+                .map(|appender| {
+                    let (writer, guard) = tracing_appender::non_blocking(appender);
+                    guards.push(guard);
+                    mcp_layer(writer, &MCP_LEVEL)
+                });
 
             let result = tracing_subscriber::registry()
-                .with(env_filter.clone())
-                .with(fmt::layer().with_writer(non_blocking).with_ansi(false))
+                .with(
+                    fmt::layer()
+                        .with_writer(non_blocking)
+                        .with_ansi(false)
+                        .with_filter(env_filter.clone()),
+                )
+                .with(mcp_layer)
                 .try_init();
 
             if result.is_ok() {
-                return LogGuard {
-                    _guard: Some(guard),
-                };
+                return LogGuard { _guards: guards };
             }
         }
     }
@@ -64,5 +116,134 @@ pub fn init(project_root: Option<&Path>) -> LogGuard {
         .with(fmt::layer().with_writer(std::io::stderr))
         .try_init();
 
-    LogGuard { _guard: None }
+    LogGuard {
+        _guards: Vec::new(),
+    }
+}
+
+/// Set the most verbose level written to `mcp.log`.
+pub fn set_mcp_level(level: Level) {
+    MCP_LEVEL.store(level_index(level), Ordering::Relaxed);
+}
+
+/// The most verbose level written to `mcp.log`.
+pub fn mcp_level() -> Level {
+    level_from_index(MCP_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Map an MCP (RFC 5424) log level to a `tracing` level: `notice` logs as
+/// `info`, and `critical`, `alert`, and `emergency` as `error`.
+pub fn parse_mcp_level(value: &str) -> Option<Level> {
+    match value {
+        "debug" => Some(Level::DEBUG),
+        "info" | "notice" => Some(Level::INFO),
+        "warning" => Some(Level::WARN),
+        "error" | "critical" | "alert" | "emergency" => Some(Level::ERROR),
+        _ => None,
+    }
+}
+
+/// Plain-text layer for [`MCP_TARGET`] events at or above `level`.
+fn mcp_layer<S, W>(writer: W, level: &'static AtomicU8) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    fmt::layer()
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_target(false)
+        .with_filter(
+            // Re-checked on every event, so `logging/setLevel` applies to
+            // callsites already seen.
+            filter::dynamic_filter_fn(move |meta, _| {
+                meta.target() == MCP_TARGET
+                    && *meta.level() <= level_from_index(level.load(Ordering::Relaxed))
+            })
+            .with_callsite_filter(|meta| {
+                if meta.target() == MCP_TARGET {
+                    Interest::sometimes()
+                } else {
+                    Interest::never()
+                }
+            }),
+        )
+}
+
+const fn level_index(level: Level) -> u8 {
+    match level {
+        Level::ERROR => 0,
+        Level::WARN => 1,
+        Level::INFO => 2,
+        Level::DEBUG => 3,
+        Level::TRACE => 4,
+    }
+}
+
+const fn level_from_index(index: u8) -> Level {
+    match index {
+        0 => Level::ERROR,
+        1 => Level::WARN,
+        2 => Level::INFO,
+        3 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .lock()
+                .map_err(|_| std::io::Error::other("poisoned"))?
+                .extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn mcp_layer_keeps_mcp_events_at_the_current_level() {
+        static LEVEL: AtomicU8 = AtomicU8::new(level_index(Level::INFO));
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber =
+            tracing_subscriber::registry().with(mcp_layer(move || writer.clone(), &LEVEL));
+
+        let params = |n: u32| tracing::debug!(target: MCP_TARGET, n, "params");
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: MCP_TARGET, method = "ping", "request");
+            params(1);
+            tracing::info!("not an mcp event");
+            LEVEL.store(level_index(Level::DEBUG), Ordering::Relaxed);
+            params(2);
+        });
+
+        let log =
+            String::from_utf8(buffer.0.lock().expect("buffer lock").clone()).expect("log is utf-8");
+        assert!(log.contains("request method=\"ping\""), "{log}");
+        assert!(log.contains("params n=2"));
+        assert!(!log.contains("params n=1"));
+        assert!(!log.contains("not an mcp event"));
+    }
+
+    #[test]
+    fn mcp_levels_map_onto_tracing_levels() {
+        assert_eq!(parse_mcp_level("notice"), Some(Level::INFO));
+        assert_eq!(parse_mcp_level("critical"), Some(Level::ERROR));
+        assert_eq!(parse_mcp_level("verbose"), None);
+    }
 }
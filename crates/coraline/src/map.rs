@@ -0,0 +1,470 @@
+#![forbid(unsafe_code)]
+
+//! Per-directory code map for `coraline map`.
+//!
+//! Aggregates indexed files, symbols, bytes, and lines per directory into a
+//! tree, rendered as an indented text report with proportional bars, as a
+//! self-contained HTML treemap, or as JSON.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::output::OutputStyle;
+
+/// What box and bar sizes are proportional to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MapMetric {
+    #[default]
+    Nodes,
+    Bytes,
+    Lines,
+}
+
+impl MapMetric {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.to_ascii_lowercase().as_str() {
+            "nodes" | "symbols" => Some(Self::Nodes),
+            "bytes" | "size" => Some(Self::Bytes),
+            "lines" => Some(Self::Lines),
+            _ => None,
+        }
+    }
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Nodes => "symbols",
+            Self::Bytes => "bytes",
+            Self::Lines => "lines",
+        }
+    }
+}
+
+/// Totals for one directory, including everything beneath it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DirectoryStats {
+    /// Path relative to the project root; empty for the root.
+    pub path: String,
+    pub files: u64,
+    /// Symbols, excluding the per-file `file` nodes.
+    pub nodes: u64,
+    pub bytes: u64,
+    /// Lines covered by symbols (the furthest symbol end line per file).
+    pub lines: u64,
+    /// Symbol count per node kind.
+    pub kinds: BTreeMap<String, u64>,
+    /// True for the synthetic `(files)` entry standing for the files that sit
+    /// directly in the parent, next to its subdirectories.
+    pub loose_files: bool,
+    /// Subdirectories, largest first by the map metric.
+    pub children: Vec<Self>,
+}
+
+impl DirectoryStats {
+    pub const fn weight(&self, metric: MapMetric) -> u64 {
+        match metric {
+            MapMetric::Nodes => self.nodes,
+            MapMetric::Bytes => self.bytes,
+            MapMetric::Lines => self.lines,
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    fn child_mut(&mut self, path: String) -> Option<&mut Self> {
+        let index = self
+            .children
+            .iter()
+            .position(|c| c.path == path)
+            .unwrap_or_else(|| {
+                self.children.push(Self {
+                    path,
+                    ..Self::default()
+                });
+                self.children.len() - 1
+            });
+        self.children.get_mut(index)
+    }
+
+    /// Give every directory with subdirectories a `(files)` child for the
+    /// files directly inside it, so children always add up to the parent.
+    fn add_loose_files(&mut self) {
+        if self.children.is_empty() {
+            return;
+        }
+        for child in &mut self.children {
+            child.add_loose_files();
+        }
+
+        let mut loose = Self {
+            path: self.path.clone(),
+            files: self.files,
+            nodes: self.nodes,
+            bytes: self.bytes,
+            lines: self.lines,
+            kinds: self.kinds.clone(),
+            loose_files: true,
+            children: Vec::new(),
+        };
+        for child in &self.children {
+            loose.files -= child.files;
+            loose.nodes -= child.nodes;
+            loose.bytes -= child.bytes;
+            loose.lines -= child.lines;
+            for (kind, count) in &child.kinds {
+                if let Some(total) = loose.kinds.get_mut(kind) {
+                    *total -= count;
+                }
+            }
+        }
+        loose.kinds.retain(|_, count| *count > 0);
+        if loose.files > 0 {
+            self.children.push(loose);
+        }
+    }
+
+    fn sort(&mut self, metric: MapMetric) {
+        self.children.sort_by(|a, b| {
+            b.weight(metric)
+                .cmp(&a.weight(metric))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        for child in &mut self.children {
+            child.sort(metric);
+        }
+    }
+}
+
+/// Per-file figures before aggregation.
+#[derive(Default)]
+struct FileFigures {
+    bytes: u64,
+    lines: u64,
+    kinds: BTreeMap<String, u64>,
+}
+
+/// Build the directory tree from the index. Directories deeper than
+/// `max_depth` are folded into their ancestor at that depth.
+pub fn build_map(
+    conn: &Connection,
+    max_depth: usize,
+    metric: MapMetric,
+) -> std::io::Result<DirectoryStats> {
+    let mut files: BTreeMap<String, FileFigures> = BTreeMap::new();
+
+    let mut stmt = conn
+        .prepare("SELECT path, size FROM files")
+        .map_err(std::io::Error::other)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(std::io::Error::other)?;
+    for row in rows {
+        let (path, size) = row.map_err(std::io::Error::other)?;
+        files.entry(path).or_default().bytes = u64::try_from(size).unwrap_or(0);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT file_path, kind, COUNT(*), MAX(end_line) FROM nodes
+             GROUP BY file_path, kind",
+        )
+        .map_err(std::io::Error::other)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })
+        .map_err(std::io::Error::other)?;
+    for row in rows {
+        let (path, kind, count, end_line) = row.map_err(std::io::Error::other)?;
+        // Nodes of files dropped from the `files` table are stale; skip them.
+        let Some(figures) = files.get_mut(&path) else {
+            continue;
+        };
+        figures.lines = figures.lines.max(u64::try_from(end_line).unwrap_or(0));
+        if kind != "file" {
+            figures
+                .kinds
+                .insert(kind, u64::try_from(count).unwrap_or(0));
+        }
+    }
+
+    let mut root = DirectoryStats::default();
+    for (path, figures) in files {
+        let nodes: u64 = figures.kinds.values().sum();
+        let dirs: Vec<&str> = path
+            .rsplit_once('/')
+            .map(|(dirs, _)| dirs.split('/').collect())
+            .unwrap_or_default();
+
+        let mut current = &mut root;
+        add_file(current, nodes, &figures);
+        for depth in 1..=dirs.len().min(max_depth) {
+            let Some(child) = current.child_mut(dirs.get(..depth).unwrap_or_default().join("/"))
+            else {
+                break;
+            };
+            current = child;
+            add_file(current, nodes, &figures);
+        }
+    }
+    root.add_loose_files();
+    root.sort(metric);
+
+    Ok(root)
+}
+
+fn add_file(dir: &mut DirectoryStats, nodes: u64, figures: &FileFigures) {
+    dir.files += 1;
+    dir.nodes += nodes;
+    dir.bytes += figures.bytes;
+    dir.lines += figures.lines;
+    for (kind, count) in &figures.kinds {
+        *dir.kinds.entry(kind.clone()).or_default() += count;
+    }
+}
+
+/// Display name for a directory: its last path component, `.` for the root.
+fn display_name(dir: &DirectoryStats) -> String {
+    if dir.loose_files {
+        "(files)".to_string()
+    } else if dir.path.is_empty() {
+        ".".to_string()
+    } else {
+        format!("{}/", dir.name())
+    }
+}
+
+/// Human-readable size (`12.3 KB`).
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = "KB";
+    for larger in UNITS.iter().skip(1) {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = larger;
+    }
+    format!("{value:.1} {unit}")
+}
+
+/// The most common kinds, e.g. `function 10, method 8, class 1`.
+fn kind_summary(kinds: &BTreeMap<String, u64>, style: OutputStyle, top: usize) -> String {
+    let mut sorted: Vec<_> = kinds.iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    sorted
+        .into_iter()
+        .take(top)
+        .map(|(kind, count)| format!("{kind} {}", style.count(count)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn share(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+const BAR_WIDTH: usize = 20;
+
+/// Indented text report: share of the metric, a proportional bar, and the
+/// totals and top symbol kinds for each directory.
+pub fn render_text(root: &DirectoryStats, metric: MapMetric, style: &OutputStyle) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Code map: {} files, {} symbols, {}, {} lines (sized by {})",
+        style.count(root.files),
+        style.count(root.nodes),
+        format_size(root.bytes),
+        style.count(root.lines),
+        metric.label()
+    );
+    out.push('\n');
+
+    let total = root.weight(metric);
+    let name_width = widest_name(root, 0).max("directory".len());
+    let _ = writeln!(
+        out,
+        "{:>7}  {:<BAR_WIDTH$}  {:<name_width$}  {:>6}  {:>7}  {:>9}  top kinds",
+        "share", "", "directory", "files", "symbols", "size"
+    );
+    push_text_row(&mut out, root, 0, total, metric, *style, name_width);
+    out
+}
+
+fn widest_name(dir: &DirectoryStats, depth: usize) -> usize {
+    dir.children
+        .iter()
+        .map(|child| widest_name(child, depth + 1))
+        .fold(depth * 2 + display_name(dir).chars().count(), usize::max)
+}
+
+fn push_text_row(
+    out: &mut String,
+    dir: &DirectoryStats,
+    depth: usize,
+    total: u64,
+    metric: MapMetric,
+    style: OutputStyle,
+    name_width: usize,
+) {
+    let fraction = share(dir.weight(metric), total);
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    let filled = ((fraction * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+    let (full, empty) = if style.plain {
+        ('#', '.')
+    } else {
+        ('█', '░')
+    };
+    let bar: String = std::iter::repeat_n(full, filled)
+        .chain(std::iter::repeat_n(empty, BAR_WIDTH - filled))
+        .collect();
+    let name = format!("{}{}", "  ".repeat(depth), display_name(dir));
+
+    let _ = writeln!(
+        out,
+        "{:>6.1}%  {bar}  {name:<name_width$}  {:>6}  {:>7}  {:>9}  {}",
+        fraction * 100.0,
+        style.count(dir.files),
+        style.count(dir.nodes),
+        format_size(dir.bytes),
+        kind_summary(&dir.kinds, style, 3),
+    );
+
+    for child in &dir.children {
+        push_text_row(out, child, depth + 1, total, metric, style, name_width);
+    }
+}
+
+/// Self-contained HTML treemap. Boxes nest per directory, alternate between
+/// row and column layout per level, and are sized by `metric`.
+pub fn render_html(root: &DirectoryStats, metric: MapMetric) -> String {
+    let mut body = String::new();
+    push_html_box(&mut body, root, 0, metric);
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>Coraline code map</title>
+<style>
+body {{ margin: 0; font: 12px system-ui, sans-serif; background: #111; color: #eee; }}
+header {{ padding: 8px 12px; }}
+main {{ position: absolute; top: 36px; right: 8px; bottom: 8px; left: 8px; display: flex; }}
+.box {{ display: flex; flex-basis: 0; min-width: 0; min-height: 0; overflow: hidden;
+       border: 1px solid rgba(0, 0, 0, 0.5); box-sizing: border-box; }}
+.row {{ flex-direction: row; }}
+.col {{ flex-direction: column; }}
+.box > .label {{ flex: 0 0 auto; padding: 2px 4px; white-space: nowrap; overflow: hidden;
+                text-overflow: ellipsis; font-weight: 600; }}
+.box > .children {{ display: flex; flex: 1 1 0; min-width: 0; min-height: 0; }}
+</style>
+</head>
+<body>
+<header>Code map: {} files, {} symbols, {}, {} lines (sized by {})</header>
+<main>{body}</main>
+</body>
+</html>
+",
+        root.files,
+        root.nodes,
+        format_size(root.bytes),
+        root.lines,
+        metric.label(),
+    )
+}
+
+fn push_html_box(out: &mut String, dir: &DirectoryStats, depth: usize, metric: MapMetric) {
+    // At least 1 so empty directories stay visible as slivers.
+    let grow = dir.weight(metric).max(1);
+    let hue = (depth * 47 + 200) % 360;
+    let lightness = 22 + (depth * 6).min(30);
+    let title = format!(
+        "{}{}: {} files, {} symbols, {}, {} lines",
+        if dir.path.is_empty() { "." } else { &dir.path },
+        if dir.loose_files { " (files)" } else { "" },
+        dir.files,
+        dir.nodes,
+        format_size(dir.bytes),
+        dir.lines,
+    );
+    let layout = if depth.is_multiple_of(2) {
+        "col"
+    } else {
+        "row"
+    };
+    let children_layout = if depth.is_multiple_of(2) {
+        "row"
+    } else {
+        "col"
+    };
+
+    let _ = write!(
+        out,
+        "<div class=\"box {layout}\" style=\"flex-grow: {grow}; background: hsl({hue}, 35%, {lightness}%)\" title=\"{}\">\
+         <div class=\"label\">{}</div>",
+        html_escape(&title),
+        html_escape(&display_name(dir)),
+    );
+    if !dir.children.is_empty() {
+        let _ = write!(out, "<div class=\"children {children_layout}\">");
+        for child in &dir.children {
+            push_html_box(out, child, depth + 1, metric);
+        }
+        out.push_str("</div>");
+    }
+    out.push_str("</div>");
+}
+
+fn html_escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_size, html_escape};
+
+    #[test]
+    fn format_size_uses_binary_units() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+
+    #[test]
+    fn html_escape_escapes_markup() {
+        assert_eq!(
+            html_escape("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+}
@@ -21,9 +21,9 @@
 use std::collections::HashMap;
 use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::sync::{Arc, Mutex, OnceLock, mpsc};
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -31,26 +31,75 @@ use sha2::{Digest, Sha256};
 use tracing::{debug, info, warn};
 
 use crate::config::SecurityConfig;
+use crate::logging::{self, MCP_TARGET};
 use crate::security::{GuardrailDecision, apply_input_guardrails, apply_output_guardrails};
-use crate::tools::{ToolRegistry, ToolRisk, classify_tool_risk, create_default_registry};
+use crate::tools::{
+    ToolContext, ToolError, ToolRegistry, ToolRisk, classify_tool_risk, create_default_registry,
+    writes_project,
+};
 
 const LATEST_PROTOCOL_VERSION: &str = "2025-11-25";
-const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[LATEST_PROTOCOL_VERSION, "2024-11-05"];
+pub(crate) const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[LATEST_PROTOCOL_VERSION, "2024-11-05"];
+/// First protocol version with `structuredContent` in tool results.
+const STRUCTURED_CONTENT_SINCE: &str = "2025-06-18";
 const TOOLS_LIST_PAGE_SIZE: usize = 100;
 const SESSION_SECURITY_STATUS_TOOL_NAME: &str = "coraline_session_security_status";
+/// Threads running read-only tool calls, shared by every session.
+const TOOL_WORKERS: usize = 4;
 
 #[derive(Default)]
 pub struct McpServer {
     project_root: Option<PathBuf>,
     init_error: Option<String>,
-    tool_registry: Option<ToolRegistry>,
+    tool_registry: Option<Arc<ToolRegistry>>,
     initialize_completed: bool,
     client_initialized: bool,
     negotiated_protocol_version: String,
-    shutdown: Arc<AtomicBool>,
-    auto_sync_spawned: bool,
+    /// Set for HTTP sessions, whose server runs auto-sync for all of them.
+    external_auto_sync: bool,
+    /// Every workspace folder served, the primary project first. Tool calls
+    /// pick one with a `project` argument or an absolute path argument.
+    projects: Vec<WorkspaceProject>,
     security_config: SecurityConfig,
     session_security_state: SessionSecurityState,
+    /// Set by [`McpServer::start`]: tool calls then run on worker threads and
+    /// report back through this channel. Without it they run inline.
+    events: Option<mpsc::Sender<ServerEvent>>,
+    /// Contexts of tool calls still running, by JSON-RPC request id.
+    in_flight: HashMap<String, ToolContext>,
+    /// Where responses and notifications go; stdout when unset.
+    sink: Option<MessageSink>,
+    /// Set for HTTP sessions: the project root and tool registry belong to
+    /// the server, so `initialize` neither re-roots nor rebuilds them.
+    pinned_root: bool,
+}
+
+/// A workspace folder and the tool registry serving it.
+struct WorkspaceProject {
+    name: String,
+    root: PathBuf,
+    registry: Arc<ToolRegistry>,
+    /// Stops the folder's auto-sync thread once it leaves the workspace.
+    shutdown: Arc<AtomicBool>,
+}
+
+/// Receives every outgoing JSON-RPC message of a session.
+pub(crate) type MessageSink = Arc<dyn Fn(Value) -> io::Result<()> + Send + Sync>;
+
+/// Input to the server loop: client messages and finished tool calls.
+pub(crate) enum ServerEvent {
+    Line(String),
+    InputClosed,
+    InputFailed(io::Error),
+    ToolFinished(FinishedToolCall),
+}
+
+pub(crate) struct FinishedToolCall {
+    id: JsonRpcId,
+    parsed: ToolCallParams,
+    request_id: String,
+    arg_hash: String,
+    outcome: Result<Value, ToolError>,
 }
 
 #[derive(Default)]
@@ -81,6 +130,22 @@ struct InitializeParams {
 #[derive(Debug, Deserialize)]
 struct WorkspaceFolder {
     uri: String,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// `workspace/didChangeWorkspaceFolders` params.
+#[derive(Debug, Deserialize)]
+struct WorkspaceFoldersChange {
+    event: WorkspaceFoldersChangeEvent,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceFoldersChangeEvent {
+    #[serde(default)]
+    added: Vec<WorkspaceFolder>,
+    #[serde(default)]
+    removed: Vec<WorkspaceFolder>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -100,6 +165,10 @@ struct ToolsListParams {
 #[serde(rename_all = "camelCase")]
 struct ToolResult {
     content: Vec<ToolContent>,
+    /// The tool's JSON result as an object, for clients that negotiated
+    /// structured content; `content` carries the same JSON as text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    structured_content: Option<Value>,
     is_error: bool,
 }
 
@@ -114,7 +183,7 @@ enum ToolCallExecution {
     UnknownTool(String),
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 enum JsonRpcId {
     String(String),
@@ -146,10 +215,14 @@ impl McpServer {
             initialize_completed: false,
             client_initialized: false,
             negotiated_protocol_version: LATEST_PROTOCOL_VERSION.to_string(),
-            shutdown: Arc::new(AtomicBool::new(false)),
-            auto_sync_spawned: false,
+            external_auto_sync: false,
+            projects: Vec::new(),
             security_config,
             session_security_state: SessionSecurityState::default(),
+            events: None,
+            in_flight: HashMap::new(),
+            sink: None,
+            pinned_root: false,
         };
         if let Some(ref root) = server.project_root {
             server.initialize_tools(root.clone());
@@ -157,40 +230,115 @@ impl McpServer {
         server
     }
 
+    /// A session of the HTTP transport: serves `project_root` with the
+    /// server-wide `registry` and writes its messages to `sink`. Auto-sync is
+    /// left to the HTTP server, which runs one for all sessions.
+    pub(crate) fn for_session(
+        project_root: PathBuf,
+        registry: Arc<ToolRegistry>,
+        sink: MessageSink,
+    ) -> Self {
+        let mut server = Self::new(None);
+        server.reload_security_config(&project_root);
+        server.project_root = Some(project_root);
+        server.tool_registry = Some(registry);
+        server.sink = Some(sink);
+        server.pinned_root = true;
+        server.external_auto_sync = true;
+        server
+    }
+
     pub fn start(&mut self) -> io::Result<()> {
-        let stdin = io::stdin();
-        let mut handle = stdin.lock();
-        let mut line = String::new();
-
-        loop {
-            line.clear();
-            let bytes = handle.read_line(&mut line)?;
-            if bytes == 0 {
-                break;
-            }
+        let (sender, events) = mpsc::channel();
+        spawn_stdin_reader(sender.clone())?;
+        self.run(sender, &events)
+    }
 
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
+    /// Serve `events` until input closes and every running tool call has
+    /// been answered. `sender` feeds the same channel; worker threads use it
+    /// to report finished tool calls.
+    pub(crate) fn run(
+        &mut self,
+        sender: mpsc::Sender<ServerEvent>,
+        events: &mpsc::Receiver<ServerEvent>,
+    ) -> io::Result<()> {
+        self.events = Some(sender);
 
-            match serde_json::from_str::<Value>(trimmed) {
-                Ok(message) => {
-                    if let Err(err) = self.handle_message(message) {
-                        self.send_error(None, -32603, &format!("Internal error: {err}"), None)?;
+        // Once input closes, still answer the tool calls already running.
+        let mut input_open = true;
+        while input_open || !self.in_flight.is_empty() {
+            let Ok(event) = events.recv() else {
+                break;
+            };
+            match event {
+                ServerEvent::Line(line) => self.handle_line(&line)?,
+                ServerEvent::ToolFinished(finished) => self.finish_tool_call(finished)?,
+                ServerEvent::InputClosed => input_open = false,
+                ServerEvent::InputFailed(err) => {
+                    for ctx in self.in_flight.values() {
+                        ctx.cancel();
                     }
-                }
-                Err(_) => {
-                    self.send_error(None, -32700, "Parse error: invalid JSON", None)?;
+                    self.stop();
+                    return Err(err);
                 }
             }
         }
 
-        self.shutdown.store(true, Ordering::Relaxed);
+        self.stop();
+        Ok(())
+    }
+
+    fn handle_line(&mut self, line: &str) -> io::Result<()> {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return Ok(());
+        }
+
+        match serde_json::from_str::<Value>(trimmed) {
+            Ok(message) => {
+                if let Err(err) = self.handle_message(message) {
+                    self.send_error(None, -32603, &format!("Internal error: {err}"), None)?;
+                }
+            }
+            Err(_) => {
+                self.send_error(None, -32700, "Parse error: invalid JSON", None)?;
+            }
+        }
         Ok(())
     }
 
+    /// Handle one JSON-RPC message, logging it and its handling time to the
+    /// MCP request log.
     fn handle_message(&mut self, message: Value) -> io::Result<()> {
+        let started = Instant::now();
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+        let id = message.get("id").map(Value::to_string);
+        let params = message.get("params").unwrap_or(&Value::Null);
+        debug!(
+            target: MCP_TARGET,
+            method = %method,
+            id = id.as_deref(),
+            params = %params,
+            "request params"
+        );
+
+        let result = self.dispatch_message(message);
+        info!(
+            target: MCP_TARGET,
+            method = %method,
+            id = id.as_deref(),
+            elapsed_ms = elapsed_millis(started),
+            ok = result.is_ok(),
+            "request"
+        );
+        result
+    }
+
+    fn dispatch_message(&mut self, message: Value) -> io::Result<()> {
         let method = message.get("method").and_then(|m| m.as_str()).unwrap_or("");
         let id = message.get("id").and_then(json_rpc_id_from_value);
 
@@ -238,9 +386,40 @@ impl McpServer {
                     self.handle_tools_call(id, message.get("params"))?;
                 }
             }
+            "resources/list" => {
+                if let Some(id) = id {
+                    self.handle_resources_list(id)?;
+                }
+            }
+            "resources/read" => {
+                if let Some(id) = id {
+                    self.handle_resources_read(id, message.get("params"))?;
+                }
+            }
+            "prompts/list" => {
+                if let Some(id) = id {
+                    self.handle_prompts_list(id)?;
+                }
+            }
+            "prompts/get" => {
+                if let Some(id) = id {
+                    self.handle_prompts_get(id, message.get("params"))?;
+                }
+            }
             "notifications/initialized" => {
                 self.client_initialized = true;
             }
+            "notifications/cancelled" | "$/cancelRequest" => {
+                self.handle_cancel(message.get("params"));
+            }
+            "workspace/didChangeWorkspaceFolders" => {
+                self.handle_workspace_folders_changed(message.get("params"))?;
+            }
+            "logging/setLevel" => {
+                if let Some(id) = id {
+                    self.handle_set_level(id, message.get("params"))?;
+                }
+            }
             "ping" => {
                 if let Some(id) = id {
                     self.send_result(id, serde_json::json!({}))?;
@@ -264,6 +443,7 @@ impl McpServer {
     fn handle_initialize(&mut self, id: JsonRpcId, params: Option<&Value>) -> io::Result<()> {
         let negotiated_protocol_version;
         let mut project_root = self.project_root.clone();
+        let mut folders;
 
         if let Some(params) = params.cloned() {
             let Ok(parsed) = serde_json::from_value::<InitializeParams>(params) else {
@@ -285,13 +465,17 @@ impl McpServer {
                 if let Some(root_path) = parse_project_root(&root_uri) {
                     project_root = Some(root_path);
                 }
-            } else if let Some(folders) = parsed.workspace_folders {
-                if let Some(folder) = folders.first() {
-                    if let Some(root_path) = parse_project_root(&folder.uri) {
-                        project_root = Some(root_path);
-                    }
+            } else if let Some(folder) = parsed.workspace_folders.as_ref().and_then(|f| f.first()) {
+                if let Some(root_path) = parse_project_root(&folder.uri) {
+                    project_root = Some(root_path);
                 }
             }
+            folders = parsed.workspace_folders.unwrap_or_default();
+            // HTTP sessions serve the server's project, whatever the client asks for.
+            if self.pinned_root {
+                project_root = self.project_root.clone();
+                folders.clear();
+            }
 
             let _ = parsed.capabilities;
             let _ = parsed.client_info;
@@ -313,12 +497,11 @@ impl McpServer {
 
         if let Some(ref root) = project_root {
             self.reload_security_config(root);
-            self.initialize_tools(root.clone());
-            if self.init_error.is_none() && !self.auto_sync_spawned {
-                self.spawn_auto_sync(root.clone());
-                self.auto_sync_spawned = true;
+            if !self.pinned_root {
+                self.initialize_tools(root.clone());
             }
         }
+        self.set_workspace(&folders);
 
         self.initialize_completed = true;
         self.client_initialized = false;
@@ -327,7 +510,12 @@ impl McpServer {
 
         let response = serde_json::json!({
             "protocolVersion": self.negotiated_protocol_version,
-            "capabilities": { "tools": { "listChanged": false } },
+            "capabilities": {
+                "tools": { "listChanged": true },
+                "resources": { "subscribe": false, "listChanged": false },
+                "prompts": { "listChanged": false },
+                "logging": {},
+            },
             "serverInfo": ServerInfo {
                 name: "coraline",
                 version: env!("CARGO_PKG_VERSION"),
@@ -361,6 +549,11 @@ impl McpServer {
             Some(registry) => registry.get_tool_metadata(),
             None => Vec::new(),
         };
+        if self.projects.len() > 1 {
+            for tool in &mut tools {
+                self.add_project_argument(tool);
+            }
+        }
         tools.push(session_security_status_tool_metadata());
 
         tools.sort_by(|left, right| {
@@ -392,6 +585,114 @@ impl McpServer {
         self.send_result(id, result)
     }
 
+    fn handle_resources_list(&mut self, id: JsonRpcId) -> io::Result<()> {
+        let resources = self
+            .project_root
+            .as_deref()
+            .map(crate::resources::list_resources)
+            .unwrap_or_default();
+        self.send_result(id, serde_json::json!({ "resources": resources }))
+    }
+
+    fn handle_resources_read(&mut self, id: JsonRpcId, params: Option<&Value>) -> io::Result<()> {
+        let Some(uri) = params.and_then(|p| p.get("uri")).and_then(Value::as_str) else {
+            return self.send_error(Some(id), -32602, "Missing required param: uri", None);
+        };
+        let Some(project_root) = self.project_root.clone() else {
+            return self.send_error(Some(id), -32603, "No project root", None);
+        };
+
+        match crate::resources::read_resource(&project_root, uri) {
+            Ok(Some(mut contents)) => {
+                // Resources reach the client just like tool output.
+                let guardrail = apply_output_guardrails(&contents.text, &self.security_config);
+                self.session_security_state.guardrail_hits += guardrail.guardrail_hits;
+                info!(
+                    event = "mcp_resource_read",
+                    uri,
+                    decision = guardrail.decision.as_str(),
+                    guardrail_hits = guardrail.guardrail_hits,
+                    "resource read audit"
+                );
+                if guardrail.decision == GuardrailDecision::Deny {
+                    return self.send_error(Some(id), -32603, &guardrail.text, None);
+                }
+                contents.text = guardrail.text;
+                self.send_result(id, serde_json::json!({ "contents": [contents] }))
+            }
+            Ok(None) => self.send_error(
+                Some(id),
+                -32002,
+                "Resource not found",
+                Some(serde_json::json!({ "uri": uri })),
+            ),
+            Err(err) => self.send_error(
+                Some(id),
+                -32603,
+                &format!("Failed to read resource: {err}"),
+                None,
+            ),
+        }
+    }
+
+    fn handle_prompts_list(&mut self, id: JsonRpcId) -> io::Result<()> {
+        self.send_result(
+            id,
+            serde_json::json!({ "prompts": crate::prompts::list_prompts() }),
+        )
+    }
+
+    fn handle_prompts_get(&mut self, id: JsonRpcId, params: Option<&Value>) -> io::Result<()> {
+        let Some(name) = params.and_then(|p| p.get("name")).and_then(Value::as_str) else {
+            return self.send_error(Some(id), -32602, "Missing required param: name", None);
+        };
+        let arguments = params
+            .and_then(|p| p.get("arguments"))
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+
+        self.ensure_tools_initialized();
+        let (Some(project_root), Some(registry)) = (&self.project_root, &self.tool_registry) else {
+            return self.send_error(Some(id), -32603, "No project root", None);
+        };
+
+        match crate::prompts::get_prompt(project_root, registry, name, &arguments) {
+            Ok(Some(mut prompt)) => {
+                // Prompts embed graph data and source, so they pass the same
+                // guardrails as tool output.
+                let mut hits = 0;
+                for message in &mut prompt.messages {
+                    let guardrail =
+                        apply_output_guardrails(&message.content.text, &self.security_config);
+                    hits += guardrail.guardrail_hits;
+                    if guardrail.decision == GuardrailDecision::Deny {
+                        self.session_security_state.guardrail_hits += hits;
+                        return self.send_error(Some(id), -32603, &guardrail.text, None);
+                    }
+                    message.content.text = guardrail.text;
+                }
+                self.session_security_state.guardrail_hits += hits;
+                info!(
+                    event = "mcp_prompt_get",
+                    prompt = name,
+                    guardrail_hits = hits,
+                    "prompt get audit"
+                );
+                self.send_result(id, serde_json::to_value(prompt).unwrap_or_default())
+            }
+            Ok(None) => self.send_error(Some(id), -32602, &format!("Unknown prompt: {name}"), None),
+            Err(err) => {
+                let code = if err.code == "internal_error" {
+                    -32603
+                } else {
+                    -32602
+                };
+                self.send_error(Some(id), code, &err.message, None)
+            }
+        }
+    }
+
     fn handle_tools_call(&mut self, id: JsonRpcId, params: Option<&Value>) -> io::Result<()> {
         let request_id = json_rpc_id_to_string(&id);
 
@@ -399,38 +700,358 @@ impl McpServer {
             return self.send_error(Some(id), -32602, "Missing tool params", None);
         };
 
-        let Ok(parsed) = serde_json::from_value::<ToolCallParams>(params.clone()) else {
+        let Ok(mut parsed) = serde_json::from_value::<ToolCallParams>(params.clone()) else {
             return self.send_error(Some(id), -32602, "Invalid tool params", None);
         };
 
         if parsed.name == SESSION_SECURITY_STATUS_TOOL_NAME {
+            let payload = self.session_security_status_payload();
             let tool_result = ToolResult {
                 content: vec![ToolContent {
                     r#type: "text",
-                    text: self.session_security_status_payload().to_string(),
+                    text: payload.to_string(),
                 }],
+                structured_content: self.supports_structured_content().then_some(payload),
                 is_error: false,
             };
             return self.send_result(id, serde_json::to_value(tool_result).unwrap_or_default());
         }
 
-        if let Some(error) = &self.init_error {
-            return self.send_error(Some(id), -32603, error, None);
-        }
+        let routed = match self.route_tool_call(&mut parsed) {
+            Ok(routed) => routed,
+            Err(message) => {
+                let projects: Vec<&str> = self.projects.iter().map(|p| p.name.as_str()).collect();
+                return self.send_error(
+                    Some(id),
+                    -32602,
+                    &message,
+                    Some(serde_json::json!({ "projects": projects })),
+                );
+            }
+        };
 
-        if self.tool_registry.is_none() {
-            return self.send_error(Some(id), -32603, "Tool registry not initialized", None);
-        }
+        let registry = if let Some(registry) = routed {
+            registry
+        } else {
+            if let Some(error) = &self.init_error {
+                return self.send_error(Some(id), -32603, error, None);
+            }
+            let Some(registry) = self.tool_registry.clone() else {
+                return self.send_error(Some(id), -32603, "Tool registry not initialized", None);
+            };
+            registry
+        };
 
         let args_json = serde_json::to_value(&parsed.arguments)
             .unwrap_or(Value::Object(serde_json::Map::new()));
         let arg_hash = hash_json_value(&args_json);
 
-        let registry = self.tool_registry.take().unwrap_or_default();
-        let execution =
-            self.execute_tool_call(&parsed, &registry, &request_id, &args_json, &arg_hash);
-        self.tool_registry = Some(registry);
+        let Some(events) = self.events.clone() else {
+            let execution =
+                self.execute_tool_call(&parsed, &registry, &request_id, &args_json, &arg_hash);
+            return self.send_tool_execution(id, execution);
+        };
+
+        if let Some(blocked) = self.check_tool_call(&parsed, &request_id, &args_json, &arg_hash) {
+            return self.send_tool_execution(id, blocked);
+        }
+
+        // Run the tool off the message loop so pings, cancellations, and
+        // other requests are still served while it works.
+        let ctx = tool_context(params, self.sink.clone());
+        self.in_flight.insert(request_id.clone(), ctx.clone());
+        let name = parsed.name.clone();
+        let response_id = id.clone();
+        let worker_request_id = request_id.clone();
+        let job: Job = Box::new(move || {
+            debug!(tool = %parsed.name, "dispatching tool call");
+            let started = Instant::now();
+            let outcome = registry.execute_with_context(&parsed.name, args_json, &ctx);
+            log_tool_execution(&parsed.name, &worker_request_id, started, &outcome);
+            let _ = events.send(ServerEvent::ToolFinished(FinishedToolCall {
+                id,
+                parsed,
+                request_id: worker_request_id,
+                arg_hash,
+                outcome,
+            }));
+        });
+        let submitted =
+            ToolWorkers::shared().and_then(|workers| workers.submit(writes_project(&name), job));
+
+        if let Err(err) = submitted {
+            self.in_flight.remove(&request_id);
+            warn!(tool = %name, error = %err, "failed to queue tool call");
+            return self.send_error(
+                Some(response_id),
+                -32603,
+                &format!("Failed to run {name}: {err}"),
+                None,
+            );
+        }
+        Ok(())
+    }
 
+    /// Send the response for a tool call that ran on a worker thread, unless
+    /// the client cancelled it (cancelled requests get no response).
+    fn finish_tool_call(&mut self, finished: FinishedToolCall) -> io::Result<()> {
+        let cancelled = self
+            .in_flight
+            .remove(&finished.request_id)
+            .is_some_and(|ctx| ctx.is_cancelled());
+        if cancelled {
+            info!(
+                event = "mcp_tool_call",
+                request_id = %finished.request_id,
+                tool = %finished.parsed.name,
+                decision = "cancelled",
+                guardrail_hits = 0,
+                arg_hash = %finished.arg_hash,
+                result_size = 0,
+                "tool call audit"
+            );
+            return Ok(());
+        }
+
+        let execution = self.complete_tool_call(
+            &finished.parsed,
+            &finished.request_id,
+            &finished.arg_hash,
+            finished.outcome,
+        );
+        self.send_tool_execution(finished.id, execution)
+    }
+
+    /// The registry of the workspace folder a tool call targets: the one
+    /// named by its `project` argument (removed from the arguments), else
+    /// the one containing an absolute `path` / `file` / `file_path`
+    /// argument. `None` means the primary project.
+    fn route_tool_call(
+        &self,
+        parsed: &mut ToolCallParams,
+    ) -> Result<Option<Arc<ToolRegistry>>, String> {
+        if let Some(requested) = parsed.arguments.remove("project") {
+            let Some(requested) = requested.as_str() else {
+                return Err("Invalid project argument: expected a string".to_string());
+            };
+            let requested_root = parse_project_root(requested);
+            let index = self
+                .projects
+                .iter()
+                .position(|p| p.name == requested)
+                .or_else(|| {
+                    requested_root
+                        .as_ref()
+                        .and_then(|root| self.projects.iter().position(|p| &p.root == root))
+                })
+                .ok_or_else(|| format!("Unknown project: {requested}"))?;
+            return Ok(self
+                .projects
+                .get(index)
+                .filter(|_| index > 0)
+                .map(|project| Arc::clone(&project.registry)));
+        }
+
+        let routed = ["path", "file", "file_path"]
+            .iter()
+            .filter_map(|key| parsed.arguments.get(*key).and_then(Value::as_str))
+            .map(Path::new)
+            .filter(|path| path.is_absolute())
+            .find_map(|path| {
+                self.projects
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| path.starts_with(&p.root))
+                    .max_by_key(|(_, p)| p.root.components().count())
+                    .map(|(index, _)| index)
+            });
+        Ok(routed
+            .filter(|&index| index > 0)
+            .and_then(|index| self.projects.get(index))
+            .map(|project| Arc::clone(&project.registry)))
+    }
+
+    /// Apply a `workspace/didChangeWorkspaceFolders` notification. Removing
+    /// the primary project promotes the next folder.
+    fn handle_workspace_folders_changed(&mut self, params: Option<&Value>) -> io::Result<()> {
+        if self.pinned_root {
+            debug!("ignoring workspace folder change for a pinned project");
+            return Ok(());
+        }
+        let Some(change) =
+            params.and_then(|p| serde_json::from_value::<WorkspaceFoldersChange>(p.clone()).ok())
+        else {
+            warn!("invalid workspace/didChangeWorkspaceFolders params");
+            return Ok(());
+        };
+
+        let primary = self.projects.first().map(|p| p.root.clone());
+        let mut changed = false;
+        for folder in &change.event.removed {
+            let Some(root) = parse_project_root(&folder.uri) else {
+                continue;
+            };
+            if let Some(index) = self.projects.iter().position(|p| p.root == root) {
+                let project = self.projects.remove(index);
+                project.shutdown.store(true, Ordering::Relaxed);
+                info!(project = %project.name, "workspace folder removed");
+                changed = true;
+            }
+        }
+        for folder in &change.event.added {
+            changed |= self.add_workspace_folder(folder);
+        }
+
+        if self.projects.first().map(|p| p.root.clone()) != primary {
+            if let Some(project) = self.projects.first() {
+                let root = project.root.clone();
+                self.tool_registry = Some(Arc::clone(&project.registry));
+                self.reload_security_config(&root);
+                self.project_root = Some(root);
+            } else {
+                self.tool_registry = None;
+                self.project_root = None;
+            }
+            self.initialize_codegraph();
+        }
+
+        if changed {
+            self.send(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/tools/list_changed",
+            }))?;
+        }
+        Ok(())
+    }
+
+    /// Serve the primary project plus `folders`, replacing the previous
+    /// workspace.
+    fn set_workspace(&mut self, folders: &[WorkspaceFolder]) {
+        for project in self.projects.drain(..) {
+            project.shutdown.store(true, Ordering::Relaxed);
+        }
+        let (Some(root), Some(registry)) = (self.project_root.clone(), self.tool_registry.clone())
+        else {
+            return;
+        };
+        let sync = self.init_error.is_none();
+        let primary = self.open_project(root, None, registry, sync);
+        self.projects.push(primary);
+        for folder in folders {
+            self.add_workspace_folder(folder);
+        }
+    }
+
+    /// Start serving `folder`; `false` when it is already served, not a
+    /// file URI, or not initialized.
+    fn add_workspace_folder(&mut self, folder: &WorkspaceFolder) -> bool {
+        let Some(root) = parse_project_root(&folder.uri) else {
+            warn!(uri = %folder.uri, "skipping workspace folder with a non-file URI");
+            return false;
+        };
+        if self.projects.iter().any(|p| p.root == root) {
+            return false;
+        }
+        if !is_initialized(&root) {
+            warn!(
+                root = %root.display(),
+                "skipping workspace folder, Coraline not initialized (run 'coraline init')"
+            );
+            return false;
+        }
+        let registry = Arc::new(project_registry(&root));
+        let project = self.open_project(root, folder.name.clone(), registry, true);
+        info!(project = %project.name, "workspace folder added");
+        self.projects.push(project);
+        true
+    }
+
+    fn open_project(
+        &self,
+        root: PathBuf,
+        name: Option<String>,
+        registry: Arc<ToolRegistry>,
+        sync: bool,
+    ) -> WorkspaceProject {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        if sync && !self.external_auto_sync {
+            spawn_auto_sync(root.clone(), Arc::clone(&shutdown));
+        }
+        let name = name.filter(|n| !n.is_empty()).unwrap_or_else(|| {
+            root.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| root.display().to_string())
+        });
+        WorkspaceProject {
+            name,
+            root,
+            registry,
+            shutdown,
+        }
+    }
+
+    /// Advertise the `project` argument on a tool's input schema.
+    fn add_project_argument(&self, tool: &mut Value) {
+        let names: Vec<&str> = self.projects.iter().map(|p| p.name.as_str()).collect();
+        let Some(schema) = tool.get_mut("inputSchema").and_then(Value::as_object_mut) else {
+            return;
+        };
+        let properties = schema
+            .entry("properties")
+            .or_insert_with(|| serde_json::json!({}));
+        if let Some(properties) = properties.as_object_mut() {
+            properties.insert(
+                "project".to_string(),
+                serde_json::json!({
+                    "type": "string",
+                    "description": format!(
+                        "Workspace folder to run against, by name ({}) or root path. Defaults to {}.",
+                        names.join(", "),
+                        names.first().copied().unwrap_or_default()
+                    ),
+                }),
+            );
+        }
+    }
+
+    /// Stop auto-sync for every workspace folder.
+    fn stop(&self) {
+        for project in &self.projects {
+            project.shutdown.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn handle_cancel(&mut self, params: Option<&Value>) {
+        let Some(target) = params
+            .and_then(|p| p.get("requestId").or_else(|| p.get("id")))
+            .and_then(json_rpc_id_from_value)
+        else {
+            return;
+        };
+        let request_id = json_rpc_id_to_string(&target);
+        if let Some(ctx) = self.in_flight.get(&request_id) {
+            ctx.cancel();
+            info!(request_id = %request_id, "tool call cancellation requested");
+        }
+    }
+
+    /// `logging/setLevel`: the most verbose level written to `mcp.log`.
+    fn handle_set_level(&self, id: JsonRpcId, params: Option<&Value>) -> io::Result<()> {
+        let requested = params.and_then(|p| p.get("level")).and_then(Value::as_str);
+        let Some(level) = requested.and_then(logging::parse_mcp_level) else {
+            return self.send_error(
+                Some(id),
+                -32602,
+                "Invalid log level",
+                Some(serde_json::json!({ "levels": logging::MCP_LOG_LEVELS })),
+            );
+        };
+        logging::set_mcp_level(level);
+        info!(target: MCP_TARGET, level = requested, "log level set");
+        self.send_result(id, serde_json::json!({}))
+    }
+
+    fn send_tool_execution(&self, id: JsonRpcId, execution: ToolCallExecution) -> io::Result<()> {
         match execution {
             ToolCallExecution::ToolResult(value) => self.send_result(id, value),
             ToolCallExecution::UnknownTool(name) => {
@@ -447,16 +1068,36 @@ impl McpServer {
         args_json: &Value,
         arg_hash: &str,
     ) -> ToolCallExecution {
+        if let Some(blocked) = self.check_tool_call(parsed, request_id, args_json, arg_hash) {
+            return blocked;
+        }
+
+        debug!(tool = %parsed.name, "dispatching tool call");
+        let started = Instant::now();
+        let outcome = registry.execute(&parsed.name, args_json.clone());
+        log_tool_execution(&parsed.name, request_id, started, &outcome);
+        self.complete_tool_call(parsed, request_id, arg_hash, outcome)
+    }
+
+    /// Session limits, flow policy, and input guardrails; `Some` when the
+    /// call is blocked.
+    fn check_tool_call(
+        &mut self,
+        parsed: &ToolCallParams,
+        request_id: &str,
+        args_json: &Value,
+        arg_hash: &str,
+    ) -> Option<ToolCallExecution> {
         self.session_security_state.tool_calls += 1;
         if let Some(reason) = self.session_limit_violation_reason() {
-            return self.blocked_session_tool_result(parsed, request_id, arg_hash, reason);
+            return Some(self.blocked_session_tool_result(parsed, request_id, arg_hash, reason));
         }
 
         let tool_risk = classify_tool_risk(&parsed.name);
         if let Some(flow_block) =
             self.record_flow_transition_and_enforce(parsed, request_id, arg_hash, tool_risk)
         {
-            return flow_block;
+            return Some(flow_block);
         }
 
         let input_guardrail = apply_input_guardrails(args_json, &self.security_config);
@@ -464,16 +1105,16 @@ impl McpServer {
 
         if input_guardrail.decision == GuardrailDecision::Deny {
             self.session_security_state.blocked_calls += 1;
-            return self.blocked_input_tool_result(
+            return Some(self.blocked_input_tool_result(
                 parsed,
                 request_id,
                 arg_hash,
                 input_guardrail.guardrail_hits,
-            );
+            ));
         }
 
         if let Some(reason) = self.session_limit_violation_reason() {
-            return self.blocked_session_tool_result(parsed, request_id, arg_hash, reason);
+            return Some(self.blocked_session_tool_result(parsed, request_id, arg_hash, reason));
         }
 
         if input_guardrail.guardrail_hits > 0 {
@@ -489,8 +1130,18 @@ impl McpServer {
             );
         }
 
-        debug!(tool = %parsed.name, "dispatching tool call");
-        match registry.execute(&parsed.name, args_json.clone()) {
+        None
+    }
+
+    /// Output guardrails and audit logging for a tool's result.
+    fn complete_tool_call(
+        &mut self,
+        parsed: &ToolCallParams,
+        request_id: &str,
+        arg_hash: &str,
+        outcome: Result<Value, ToolError>,
+    ) -> ToolCallExecution {
+        match outcome {
             Ok(result) => self.handle_successful_tool_call(parsed, request_id, arg_hash, result),
             Err(err) => self.handle_tool_error(parsed, request_id, arg_hash, err),
         }
@@ -520,6 +1171,7 @@ impl McpServer {
                 r#type: "text",
                 text: "Error: Blocked by MCP input security policy.".to_string(),
             }],
+            structured_content: None,
             is_error: true,
         };
 
@@ -556,6 +1208,7 @@ impl McpServer {
                 r#type: "text",
                 text: format!("Error: Blocked by MCP session security policy ({reason})."),
             }],
+            structured_content: None,
             is_error: true,
         };
 
@@ -592,6 +1245,7 @@ impl McpServer {
                     r#type: "text",
                     text: format!("Error: {}", guardrail.text),
                 }],
+                structured_content: None,
                 is_error: true,
             };
             return ToolCallExecution::ToolResult(
@@ -609,11 +1263,22 @@ impl McpServer {
             info!(tool = %parsed.name, request_id = %request_id, "tool call ok");
         }
 
+        // Parse the guarded text rather than reuse `result`, so redactions
+        // apply to both; output cut at `max_output_chars` no longer parses
+        // and is sent as text only.
+        let structured_content = if self.supports_structured_content() {
+            serde_json::from_str(&guardrail.text)
+                .ok()
+                .filter(Value::is_object)
+        } else {
+            None
+        };
         let tool_result = ToolResult {
             content: vec![ToolContent {
                 r#type: "text",
                 text: guardrail.text,
             }],
+            structured_content,
             is_error: false,
         };
         ToolCallExecution::ToolResult(serde_json::to_value(tool_result).unwrap_or_default())
@@ -624,7 +1289,7 @@ impl McpServer {
         parsed: &ToolCallParams,
         request_id: &str,
         arg_hash: &str,
-        err: crate::tools::ToolError,
+        err: ToolError,
     ) -> ToolCallExecution {
         if err.code == "not_found" {
             return ToolCallExecution::UnknownTool(parsed.name.clone());
@@ -647,11 +1312,16 @@ impl McpServer {
                 r#type: "text",
                 text: format!("Error: {}", err.message),
             }],
+            structured_content: None,
             is_error: true,
         };
         ToolCallExecution::ToolResult(serde_json::to_value(tool_result).unwrap_or_default())
     }
 
+    fn supports_structured_content(&self) -> bool {
+        self.negotiated_protocol_version.as_str() >= STRUCTURED_CONTENT_SINCE
+    }
+
     fn session_limit_violation_reason(&self) -> Option<&'static str> {
         if !self.security_config.enabled || !self.security_config.enforce_session_limits {
             return None;
@@ -762,7 +1432,7 @@ impl McpServer {
     }
 
     fn initialize_tools(&mut self, project_root: PathBuf) {
-        self.tool_registry = Some(create_default_registry(&project_root));
+        self.tool_registry = Some(Arc::new(project_registry(&project_root)));
     }
 
     fn ensure_tools_initialized(&mut self) {
@@ -785,38 +1455,11 @@ impl McpServer {
         }
     }
 
-    /// Spawn a background thread that periodically checks whether the index
-    /// is stale and, if so, performs an incremental sync (and optionally
-    /// embeds any new nodes when the embeddings feature is compiled in and
-    /// the ONNX model is available on disk).
-    ///
-    /// Controlled by `[sync] auto_sync_interval_secs` in `config.toml`.
-    /// A value of `0` disables the background thread entirely.
-    fn spawn_auto_sync(&self, project_root: PathBuf) {
-        let interval_secs = crate::config::load_toml_config(&project_root)
-            .map(|c| c.sync.auto_sync_interval_secs)
-            .unwrap_or_else(|_| {
-                crate::config::CoralineConfig::default()
-                    .sync
-                    .auto_sync_interval_secs
-            });
-
-        if interval_secs == 0 {
-            info!("auto-sync disabled (auto_sync_interval_secs = 0)");
-            return;
+    fn send(&self, message: Value) -> io::Result<()> {
+        match &self.sink {
+            Some(sink) => sink(message),
+            None => send_response(message),
         }
-
-        let shutdown = Arc::clone(&self.shutdown);
-        let interval = Duration::from_secs(interval_secs);
-
-        std::thread::Builder::new()
-            .name("coraline-auto-sync".into())
-            .spawn(move || {
-                info!(interval_secs = interval_secs, "auto-sync thread started");
-                auto_sync_loop(&project_root, interval, &shutdown);
-                info!("auto-sync thread stopped");
-            })
-            .ok(); // If thread creation fails, degrade gracefully.
     }
 
     fn send_result(&self, id: JsonRpcId, result: Value) -> io::Result<()> {
@@ -825,7 +1468,7 @@ impl McpServer {
             "id": id,
             "result": result,
         });
-        send_response(response)
+        self.send(response)
     }
 
     fn send_error(
@@ -835,6 +1478,14 @@ impl McpServer {
         message: &str,
         data: Option<Value>,
     ) -> io::Result<()> {
+        let logged_id = id.as_ref().map(json_rpc_id_to_string);
+        warn!(
+            target: MCP_TARGET,
+            id = logged_id.as_deref(),
+            code,
+            error = message,
+            "error response"
+        );
         let error = match data {
             Some(data) => serde_json::json!({ "code": code, "message": message, "data": data }),
             None => serde_json::json!({ "code": code, "message": message }),
@@ -845,10 +1496,45 @@ impl McpServer {
             "id": id,
             "error": error,
         });
-        send_response(response)
+        self.send(response)
+    }
+}
+
+/// Log a finished tool execution to the MCP request log.
+fn log_tool_execution(
+    tool: &str,
+    request_id: &str,
+    started: Instant,
+    outcome: &Result<Value, ToolError>,
+) {
+    let elapsed_ms = elapsed_millis(started);
+    match outcome {
+        Ok(_) => info!(target: MCP_TARGET, tool, request_id, elapsed_ms, "tool call"),
+        Err(err) => warn!(
+            target: MCP_TARGET,
+            tool,
+            request_id,
+            elapsed_ms,
+            error = %err,
+            "tool call failed"
+        ),
     }
 }
 
+/// The tool registry for a project the server opens: calls go to the
+/// project's `coraline daemon` while one is running, and graph queries
+/// follow `[sync] on_stale_index`.
+pub(crate) fn project_registry(project_root: &Path) -> ToolRegistry {
+    let mut registry = create_default_registry(project_root);
+    registry.delegate_to_daemon(project_root);
+    registry.check_freshness(project_root);
+    registry
+}
+
+fn elapsed_millis(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
 fn parse_project_root(root: &str) -> Option<PathBuf> {
     if let Some(raw_path) = root.strip_prefix("file://") {
         #[cfg(windows)]
@@ -923,6 +1609,128 @@ fn hash_json_value(value: &Value) -> String {
     hex::encode(hasher.finalize())
 }
 
+/// A queued tool call.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The threads tool calls run on, off the sessions' message loops.
+///
+/// Read-only tools share a pool of [`TOOL_WORKERS`] threads; tools that
+/// [write to the project](writes_project) queue for a single writer thread,
+/// so a sync never overlaps another sync or a memory write.
+struct ToolWorkers {
+    reads: mpsc::Sender<Job>,
+    writes: mpsc::Sender<Job>,
+}
+
+impl ToolWorkers {
+    /// The process-wide workers, started on first use.
+    fn shared() -> io::Result<&'static Self> {
+        static WORKERS: OnceLock<ToolWorkers> = OnceLock::new();
+        if let Some(workers) = WORKERS.get() {
+            return Ok(workers);
+        }
+        let workers = Self::spawn(TOOL_WORKERS)?;
+        Ok(WORKERS.get_or_init(|| workers))
+    }
+
+    fn spawn(readers: usize) -> io::Result<Self> {
+        let (reads, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        for index in 0..readers {
+            let queue = Arc::clone(&queue);
+            std::thread::Builder::new()
+                .name(format!("coraline-tool-worker-{index}"))
+                .spawn(move || {
+                    // The lock is released before the job runs.
+                    while let Some(job) = queue.lock().ok().and_then(|queue| queue.recv().ok()) {
+                        run_job(job);
+                    }
+                })?;
+        }
+        let (writes, queue) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("coraline-tool-writer".into())
+            .spawn(move || queue.into_iter().for_each(run_job))?;
+        Ok(Self { reads, writes })
+    }
+
+    /// Queue `job` for the writer thread when `writes`, else for the pool.
+    fn submit(&self, writes: bool, job: Job) -> io::Result<()> {
+        let queue = if writes { &self.writes } else { &self.reads };
+        queue
+            .send(job)
+            .map_err(|_| io::Error::other("tool workers have stopped"))
+    }
+}
+
+/// Run a job, keeping its worker alive if the tool panics.
+fn run_job(job: Job) {
+    if std::panic::catch_unwind(std::panic::AssertUnwindSafe(job)).is_err() {
+        warn!("tool call panicked");
+    }
+}
+
+/// Forward stdin lines to the server loop until input closes.
+fn spawn_stdin_reader(events: mpsc::Sender<ServerEvent>) -> io::Result<()> {
+    std::thread::Builder::new()
+        .name("coraline-mcp-stdin".into())
+        .spawn(move || {
+            let stdin = io::stdin();
+            let mut handle = stdin.lock();
+            let mut line = String::new();
+            loop {
+                line.clear();
+                let event = match handle.read_line(&mut line) {
+                    Ok(0) => ServerEvent::InputClosed,
+                    Ok(_) => ServerEvent::Line(line.clone()),
+                    Err(err) => ServerEvent::InputFailed(err),
+                };
+                let last = !matches!(event, ServerEvent::Line(_));
+                if events.send(event).is_err() || last {
+                    break;
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// Context for a `tools/call`: reports `notifications/progress` when the
+/// client sent `_meta.progressToken`.
+fn tool_context(params: &Value, sink: Option<MessageSink>) -> ToolContext {
+    let token = params
+        .get("_meta")
+        .and_then(|meta| meta.get("progressToken"))
+        .filter(|token| token.is_string() || token.is_i64())
+        .cloned();
+    let Some(token) = token else {
+        return ToolContext::new();
+    };
+
+    ToolContext::new().with_progress(move |progress, total, message| {
+        let mut params = serde_json::Map::new();
+        params.insert("progressToken".to_string(), token.clone());
+        params.insert("progress".to_string(), serde_json::json!(progress));
+        if let Some(total) = total {
+            params.insert("total".to_string(), serde_json::json!(total));
+        }
+        if let Some(message) = message {
+            params.insert("message".to_string(), serde_json::json!(message));
+        }
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": params,
+        });
+        let sent = match &sink {
+            Some(sink) => sink(notification),
+            None => send_response(notification),
+        };
+        if let Err(err) = sent {
+            debug!(error = %err, "failed to send progress notification");
+        }
+    })
+}
+
 fn send_response(response: Value) -> io::Result<()> {
     let mut stdout = io::stdout();
     writeln!(stdout, "{}", response)?;
@@ -933,10 +1741,82 @@ fn send_response(response: Value) -> io::Result<()> {
 // Background auto-sync
 // ---------------------------------------------------------------------------
 
+/// Spawn a background thread that periodically checks whether the index
+/// is stale and, if so, performs an incremental sync (and optionally
+/// embeds any new nodes when the embeddings feature is compiled in and
+/// the ONNX model is available on disk).
+///
+/// Controlled by `[sync] auto_sync_interval_secs` in `config.toml`.
+/// A value of `0` disables the background thread entirely. With `[sync]
+/// watch_mode` the thread syncs on filesystem events instead, falling back
+/// to the interval when the project cannot be watched.
+pub(crate) fn spawn_auto_sync(project_root: PathBuf, shutdown: Arc<AtomicBool>) {
+    let sync_cfg = crate::config::load_toml_config(&project_root)
+        .map(|c| c.sync)
+        .unwrap_or_default();
+    let interval_secs = sync_cfg.auto_sync_interval_secs;
+
+    if sync_cfg.watch_mode {
+        let debounce = Duration::from_millis(sync_cfg.debounce_ms);
+        std::thread::Builder::new()
+            .name("coraline-watch".into())
+            .spawn(move || {
+                info!("watch thread started");
+                let watched =
+                    crate::watch::watch(
+                        &project_root,
+                        debounce,
+                        &shutdown,
+                        |result| match result {
+                            Ok(result) => info!(
+                                files_added = result.files_added,
+                                files_modified = result.files_modified,
+                                files_removed = result.files_removed,
+                                nodes_updated = result.nodes_updated,
+                                duration_ms = result.duration_ms,
+                                "watch: sync complete"
+                            ),
+                            Err(err) => warn!(error = %err, "watch: sync failed"),
+                        },
+                    );
+                match watched {
+                    Ok(()) => info!("watch thread stopped"),
+                    Err(err) if interval_secs > 0 => {
+                        warn!(error = %err, "cannot watch the project; polling instead");
+                        auto_sync_loop(
+                            &project_root,
+                            Duration::from_secs(interval_secs),
+                            &shutdown,
+                        );
+                    }
+                    Err(err) => warn!(error = %err, "cannot watch the project"),
+                }
+            })
+            .ok();
+        return;
+    }
+
+    if interval_secs == 0 {
+        info!("auto-sync disabled (auto_sync_interval_secs = 0)");
+        return;
+    }
+
+    let interval = Duration::from_secs(interval_secs);
+
+    std::thread::Builder::new()
+        .name("coraline-auto-sync".into())
+        .spawn(move || {
+            info!(interval_secs = interval_secs, "auto-sync thread started");
+            auto_sync_loop(&project_root, interval, &shutdown);
+            info!("auto-sync thread stopped");
+        })
+        .ok(); // If thread creation fails, degrade gracefully.
+}
+
 /// Core loop run on the background thread.  Checks `needs_sync` at each tick
 /// and performs an incremental sync when the index is stale.  When the
 /// embeddings feature is compiled in **and** ONNX model files are present,
-/// any newly-added nodes are embedded automatically after each sync.
+/// the sync itself embeds any new nodes.
 fn auto_sync_loop(project_root: &Path, interval: Duration, shutdown: &AtomicBool) {
     // Sleep a full interval before the first check so we don't race with
     // the initial indexing that may still be in progress.
@@ -955,12 +1835,9 @@ fn auto_sync_loop(project_root: &Path, interval: Duration, shutdown: &AtomicBool
     }
 }
 
-/// A single tick: load config → check staleness → sync → optionally embed.
+/// A single tick: load config → check staleness → sync.
 fn auto_sync_tick(project_root: &Path) -> io::Result<()> {
-    let mut cfg = crate::config::load_config(project_root)?;
-    if let Ok(toml_cfg) = crate::config::load_toml_config(project_root) {
-        crate::config::apply_toml_to_code_graph(&mut cfg, &toml_cfg);
-    }
+    let cfg = crate::config::load_config(project_root)?;
 
     let status = crate::extraction::needs_sync(project_root, &cfg)?;
     if !status.is_stale() {
@@ -981,71 +1858,14 @@ fn auto_sync_tick(project_root: &Path) -> io::Result<()> {
         files_modified = result.files_modified,
         files_removed = result.files_removed,
         nodes_updated = result.nodes_updated,
+        nodes_embedded = result.nodes_embedded,
         duration_ms = result.duration_ms,
         "auto-sync: sync complete"
     );
 
-    auto_embed_new_nodes(project_root);
-
     Ok(())
 }
 
-/// Best-effort embedding of any nodes that don't yet have a vector.
-/// Silently skipped when the embeddings feature is not compiled in or the
-/// model files are missing.
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
-fn auto_embed_new_nodes(project_root: &Path) {
-    let toml_cfg = crate::config::load_toml_config(project_root).unwrap_or_default();
-    if !toml_cfg.vectors.enabled {
-        return;
-    }
-
-    let Ok(mut vm) = crate::vectors::VectorManager::from_project(project_root) else {
-        return; // model files not present — silently skip
-    };
-
-    let Ok(conn) = crate::db::open_database(project_root) else {
-        return;
-    };
-
-    let Ok(nodes) = crate::db::get_unembedded_nodes(&conn) else {
-        return;
-    };
-
-    if nodes.is_empty() {
-        return;
-    }
-
-    info!(count = nodes.len(), "auto-sync: embedding new nodes");
-
-    let mut ok = 0usize;
-    for node in &nodes {
-        let text = crate::vectors::node_embed_text(
-            &node.name,
-            &node.qualified_name,
-            node.docstring.as_deref(),
-            node.signature.as_deref(),
-        );
-        if let Ok(embedding) = vm.embed(&text) {
-            if crate::vectors::store_embedding(&conn, &node.id, &embedding, vm.model_name()).is_ok()
-            {
-                ok += 1;
-            }
-        }
-    }
-
-    info!(
-        embedded = ok,
-        total = nodes.len(),
-        "auto-sync: embedding done"
-    );
-}
-
-#[cfg(not(any(feature = "embeddings", feature = "embeddings-dynamic")))]
-fn auto_embed_new_nodes(_project_root: &Path) {
-    // Embeddings feature not compiled in — nothing to do.
-}
-
 /// Sleep for `duration` but wake early if `shutdown` becomes true.
 /// Checks every 500 ms so the thread exits promptly on shutdown.
 fn interruptible_sleep(duration: Duration, shutdown: &AtomicBool) {
@@ -1064,14 +1884,16 @@ fn interruptible_sleep(duration: Duration, shutdown: &AtomicBool) {
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
 
     use serde_json::{Value, json};
 
     use super::{
-        McpServer, ToolCallExecution, ToolCallParams, ToolContent, ToolResult, parse_project_root,
+        McpServer, ToolCallExecution, ToolCallParams, ToolContent, ToolResult, ToolWorkers,
+        parse_project_root,
     };
     use crate::config::{GuardrailMode, SecurityConfig};
-    use crate::tools::{Tool, ToolError, ToolRegistry};
+    use crate::tools::{Tool, ToolContext, ToolError, ToolRegistry};
 
     struct StaticTool {
         tool_name: &'static str,
@@ -1091,11 +1913,31 @@ mod tests {
             json!({ "type": "object" })
         }
 
-        fn execute(&self, _params: Value) -> Result<Value, ToolError> {
+        fn execute(&self, _params: Value, _ctx: &ToolContext) -> Result<Value, ToolError> {
             Ok(self.output.clone())
         }
     }
 
+    #[test]
+    fn cancel_notifications_cancel_in_flight_tool_calls() {
+        let mut server = McpServer::new(None);
+        let numeric = ToolContext::new();
+        let named = ToolContext::new();
+        let untouched = ToolContext::new();
+        server.in_flight.insert("7".to_string(), numeric.clone());
+        server.in_flight.insert("req-a".to_string(), named.clone());
+        server.in_flight.insert("8".to_string(), untouched.clone());
+
+        server.handle_cancel(Some(&json!({ "requestId": 7, "reason": "user aborted" })));
+        server.handle_cancel(Some(&json!({ "id": "req-a" })));
+        server.handle_cancel(Some(&json!({ "requestId": 99 })));
+        server.handle_cancel(None);
+
+        assert!(numeric.is_cancelled());
+        assert!(named.is_cancelled());
+        assert!(!untouched.is_cancelled());
+    }
+
     #[test]
     fn tools_are_initialized_without_explicit_path() {
         let mut server = McpServer::new(None);
@@ -1157,6 +1999,7 @@ mod tests {
                 r#type: "text",
                 text: "ok".to_string(),
             }],
+            structured_content: None,
             is_error: true,
         };
 
@@ -1231,6 +2074,51 @@ mod tests {
         assert!(content_text.contains("[REDACTED_EMAIL]"));
     }
 
+    #[test]
+    fn tools_call_response_includes_redacted_structured_content() {
+        let mut server = McpServer::new(None);
+        server.security_config = SecurityConfig {
+            enabled: true,
+            output_guardrail_mode: GuardrailMode::Enforce,
+            ..SecurityConfig::default()
+        };
+
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(StaticTool {
+            tool_name: "test_structured",
+            output: json!({"contact": "nick@example.com", "count": 2}),
+        }));
+
+        let parsed = ToolCallParams {
+            name: "test_structured".to_string(),
+            arguments: HashMap::new(),
+        };
+        let args_json = json!({});
+        let arg_hash = super::hash_json_value(&args_json);
+
+        let call = |server: &mut McpServer| match server.execute_tool_call(
+            &parsed,
+            &registry,
+            "req-structured",
+            &args_json,
+            &arg_hash,
+        ) {
+            ToolCallExecution::ToolResult(value) => value,
+            ToolCallExecution::UnknownTool(_) => Value::Null,
+        };
+
+        let value = call(&mut server);
+        assert_eq!(
+            value.get("structuredContent"),
+            Some(&json!({"contact": "[REDACTED_EMAIL]", "count": 2}))
+        );
+
+        server.negotiated_protocol_version = "2024-11-05".to_string();
+        let value = call(&mut server);
+        assert!(value.get("structuredContent").is_none());
+        assert!(value.get("content").is_some());
+    }
+
     #[test]
     fn tools_call_response_json_denies_output_in_enforce_mode() {
         let mut server = McpServer::new(None);
@@ -1504,4 +2392,254 @@ mod tests {
             Some(super::SESSION_SECURITY_STATUS_TOOL_NAME)
         );
     }
+
+    /// An indexed project named `name` defining one function, `symbol`.
+    #[allow(clippy::expect_used)]
+    fn indexed_folder(parent: &std::path::Path, name: &str, symbol: &str) -> Value {
+        let root = parent.join(name);
+        std::fs::create_dir_all(&root).expect("Failed to create project");
+        std::fs::write(
+            root.join("lib.ts"),
+            format!("export function {symbol}(): number {{ return 1; }}\n"),
+        )
+        .expect("Failed to write source");
+        crate::db::initialize_database(&root).expect("Failed to initialize database");
+        let cfg = crate::config::create_default_config(&root);
+        crate::extraction::index_all(&root, &cfg, false, None).expect("Failed to index project");
+        json!({ "uri": format!("file://{}", root.display()), "name": name })
+    }
+
+    /// A server writing its messages to the returned log, initialized with
+    /// `folders`.
+    #[allow(clippy::expect_used)]
+    fn workspace_server(folders: &[Value]) -> (McpServer, Arc<Mutex<Vec<Value>>>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut server = McpServer::new(None);
+        let sink_log = Arc::clone(&log);
+        server.sink = Some(Arc::new(move |message| {
+            sink_log.lock().expect("log lock").push(message);
+            Ok(())
+        }));
+        server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": { "protocolVersion": "2025-11-25", "workspaceFolders": folders },
+            }))
+            .expect("initialize");
+        server
+            .handle_message(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .expect("initialized");
+        (server, log)
+    }
+
+    #[allow(clippy::expect_used)]
+    fn search_names(server: &mut McpServer, log: &Mutex<Vec<Value>>, arguments: Value) -> Value {
+        server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": { "name": "coraline_search", "arguments": arguments },
+            }))
+            .expect("tools/call");
+        let response = log.lock().expect("log lock").pop().expect("response");
+        if response.get("error").is_some() {
+            return response;
+        }
+        let results = response
+            .pointer("/result/structuredContent/results")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        let mut names: Vec<Value> = results
+            .iter()
+            .filter_map(|r| r.pointer("/node/name").cloned())
+            .collect();
+        names.dedup();
+        Value::Array(names)
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn tool_calls_route_to_workspace_folders() {
+        let temp = tempfile::TempDir::new().expect("Failed to create temp directory");
+        let alpha = indexed_folder(temp.path(), "alpha", "alphaOnly");
+        let beta = indexed_folder(temp.path(), "beta", "betaOnly");
+        let uninitialized = temp.path().join("gamma");
+        std::fs::create_dir_all(&uninitialized).expect("Failed to create folder");
+        let gamma = json!({ "uri": format!("file://{}", uninitialized.display()) });
+        let (mut server, log) = workspace_server(&[alpha, beta.clone(), gamma]);
+
+        let names: Vec<&str> = server.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["alpha", "beta"]);
+
+        server
+            .handle_message(json!({ "jsonrpc": "2.0", "id": 5, "method": "tools/list" }))
+            .expect("tools/list");
+        let list = log
+            .lock()
+            .expect("log lock")
+            .pop()
+            .expect("tools/list response");
+        let search = list
+            .pointer("/result/tools")
+            .and_then(Value::as_array)
+            .and_then(|tools| tools.iter().find(|t| t["name"] == "coraline_search"))
+            .expect("coraline_search should be listed");
+        assert!(search.pointer("/inputSchema/properties/project").is_some());
+
+        let default = search_names(&mut server, &log, json!({ "query": "alphaOnly" }));
+        assert_eq!(default, json!(["alphaOnly"]));
+        let by_name = search_names(
+            &mut server,
+            &log,
+            json!({ "query": "betaOnly", "project": "beta" }),
+        );
+        assert_eq!(by_name, json!(["betaOnly"]));
+        let elsewhere = search_names(
+            &mut server,
+            &log,
+            json!({ "query": "alphaOnly", "project": "beta" }),
+        );
+        assert_eq!(elsewhere, json!([]));
+        let by_root = search_names(
+            &mut server,
+            &log,
+            json!({ "query": "betaOnly", "project": beta.get("uri") }),
+        );
+        assert_eq!(by_root, json!(["betaOnly"]));
+
+        let unknown = search_names(
+            &mut server,
+            &log,
+            json!({ "query": "betaOnly", "project": "gamma" }),
+        );
+        assert_eq!(unknown.pointer("/error/code"), Some(&json!(-32602)));
+        assert_eq!(
+            unknown.pointer("/error/data/projects"),
+            Some(&json!(["alpha", "beta"]))
+        );
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn workspace_folder_changes_add_remove_and_promote() {
+        let temp = tempfile::TempDir::new().expect("Failed to create temp directory");
+        let alpha = indexed_folder(temp.path(), "alpha", "alphaOnly");
+        let beta = indexed_folder(temp.path(), "beta", "betaOnly");
+        let (mut server, log) = workspace_server(std::slice::from_ref(&alpha));
+        assert_eq!(server.projects.len(), 1);
+        log.lock().expect("log lock").clear();
+
+        server
+            .handle_message(json!({
+                "jsonrpc": "2.0",
+                "method": "workspace/didChangeWorkspaceFolders",
+                "params": { "event": { "added": [beta], "removed": [alpha] } },
+            }))
+            .expect("didChangeWorkspaceFolders");
+
+        let notifications = log.lock().expect("log lock").clone();
+        assert_eq!(
+            notifications,
+            [json!({ "jsonrpc": "2.0", "method": "notifications/tools/list_changed" })]
+        );
+        let names: Vec<&str> = server.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["beta"]);
+        assert_eq!(
+            server.project_root.as_deref(),
+            Some(temp.path().join("beta").as_path())
+        );
+        log.lock().expect("log lock").clear();
+        let found = search_names(&mut server, &log, json!({ "query": "betaOnly" }));
+        assert_eq!(found, json!(["betaOnly"]));
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn logging_set_level_changes_the_mcp_log_level() {
+        let (mut server, log) = workspace_server(&[]);
+        let initialize = log
+            .lock()
+            .expect("log lock")
+            .first()
+            .cloned()
+            .expect("initialize response");
+        assert_eq!(
+            initialize.pointer("/result/capabilities/logging"),
+            Some(&json!({}))
+        );
+
+        let mut set_level = |level: &str| {
+            server
+                .handle_message(json!({
+                    "jsonrpc": "2.0",
+                    "id": 3,
+                    "method": "logging/setLevel",
+                    "params": { "level": level },
+                }))
+                .expect("logging/setLevel");
+            log.lock().expect("log lock").pop().expect("response")
+        };
+
+        assert_eq!(set_level("debug").get("result"), Some(&json!({})));
+        assert_eq!(crate::logging::mcp_level(), tracing::Level::DEBUG);
+        let rejected = set_level("loud");
+        assert_eq!(rejected.pointer("/error/code"), Some(&json!(-32602)));
+        assert!(
+            rejected
+                .pointer("/error/data/levels")
+                .and_then(Value::as_array)
+                .is_some_and(|levels| levels.contains(&json!("warning")))
+        );
+        assert_eq!(crate::logging::mcp_level(), tracing::Level::DEBUG);
+        assert_eq!(set_level("info").get("result"), Some(&json!({})));
+        assert_eq!(crate::logging::mcp_level(), tracing::Level::INFO);
+    }
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn tool_workers_bound_reads_and_serialize_writes() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Calls running now, and the most that ever ran at once.
+        #[derive(Default)]
+        struct Concurrency {
+            running: AtomicUsize,
+            peak: AtomicUsize,
+        }
+
+        let workers = ToolWorkers::spawn(2).expect("spawn workers");
+        let reads = Arc::new(Concurrency::default());
+        let writes = Arc::new(Concurrency::default());
+        let (done, finished) = std::sync::mpsc::channel();
+        for call in 0..8 {
+            let write = call % 2 == 0;
+            let seen = Arc::clone(if write { &writes } else { &reads });
+            let done = done.clone();
+            workers
+                .submit(
+                    write,
+                    Box::new(move || {
+                        let now = seen.running.fetch_add(1, Ordering::SeqCst) + 1;
+                        seen.peak.fetch_max(now, Ordering::SeqCst);
+                        std::thread::sleep(std::time::Duration::from_millis(20));
+                        seen.running.fetch_sub(1, Ordering::SeqCst);
+                        let _ = done.send(());
+                    }),
+                )
+                .expect("submit job");
+        }
+        for _ in 0..8 {
+            finished.recv().expect("job finished");
+        }
+        assert!(reads.peak.load(Ordering::SeqCst) <= 2, "reads use the pool");
+        assert_eq!(
+            writes.peak.load(Ordering::SeqCst),
+            1,
+            "writes never overlap"
+        );
+    }
 }
@@ -0,0 +1,609 @@
+#![forbid(unsafe_code)]
+
+//! Streamable-HTTP transport for the MCP server (`coraline serve --http`).
+//!
+//! One endpoint, `/mcp`:
+//!
+//! - `POST` a JSON-RPC message. A request is answered with its JSON-RPC
+//!   response as `application/json`; a `tools/call` carrying a
+//!   `progressToken` from a client that accepts `text/event-stream` gets an
+//!   SSE stream of `notifications/progress` followed by the response.
+//!   Notifications and responses are acknowledged with `202 Accepted`.
+//! - `DELETE` ends the session.
+//!
+//! `initialize` opens a session and returns its id in the `Mcp-Session-Id`
+//! header; every later message must carry it. Each session runs its own
+//! [`McpServer`] loop (protocol state, security counters, in-flight calls),
+//! while all sessions share one tool registry and one auto-sync thread for
+//! the server's project. Sessions idle for longer than
+//! [`SESSION_IDLE_TIMEOUT`] are closed.
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, mpsc};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tiny_http::{Header, Method, Request, Response};
+use tracing::{debug, info, warn};
+
+use crate::mcp::{self, McpServer, MessageSink, ServerEvent};
+use crate::tools::ToolRegistry;
+
+pub const ENDPOINT_PATH: &str = "/mcp";
+pub const SESSION_HEADER: &str = "Mcp-Session-Id";
+const PROTOCOL_VERSION_HEADER: &str = "MCP-Protocol-Version";
+
+/// Sessions without a message for this long are closed.
+pub const SESSION_IDLE_TIMEOUT: Duration = Duration::from_mins(30);
+const MAX_BODY_BYTES: u64 = 8 * 1024 * 1024;
+
+/// A bound MCP HTTP server; [`HttpServer::run`] serves it.
+pub struct HttpServer {
+    server: tiny_http::Server,
+    state: Arc<HttpState>,
+}
+
+struct HttpState {
+    project_root: PathBuf,
+    registry: Arc<ToolRegistry>,
+    sessions: Mutex<HashMap<String, Session>>,
+    next_session: AtomicU64,
+}
+
+#[derive(Clone)]
+struct Session {
+    events: mpsc::Sender<ServerEvent>,
+    routes: Arc<Routes>,
+    last_seen: Instant,
+}
+
+/// Routes a session's outgoing messages to the HTTP requests waiting on
+/// them.
+#[derive(Default)]
+struct Routes {
+    table: Mutex<RouteTable>,
+}
+
+#[derive(Default)]
+struct RouteTable {
+    /// Open requests, by JSON-RPC id (as JSON text).
+    requests: HashMap<String, mpsc::Sender<Value>>,
+    /// `progressToken` (as JSON text) to the id of the request it reports on.
+    progress: HashMap<String, String>,
+    closed: bool,
+}
+
+/// How a POST is answered.
+enum Reply {
+    Empty(u16),
+    Json {
+        status: u16,
+        body: Value,
+        session_id: Option<String>,
+    },
+    Stream {
+        replies: mpsc::Receiver<Value>,
+        routes: Arc<Routes>,
+        id_key: String,
+    },
+}
+
+impl Reply {
+    fn error(status: u16, code: i64, message: impl Into<String>) -> Self {
+        Self::Json {
+            status,
+            body: json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": code, "message": message.into() },
+            }),
+            session_id: None,
+        }
+    }
+}
+
+/// Serve MCP over HTTP for `project_root` on `addr` (e.g. `127.0.0.1:7879`)
+/// until the process is interrupted.
+pub fn serve(project_root: &Path, addr: &str) -> io::Result<()> {
+    let server = HttpServer::bind(project_root, addr)?;
+    info!(addr, "MCP HTTP transport listening");
+    server.run();
+    Ok(())
+}
+
+impl HttpServer {
+    pub fn bind(project_root: &Path, addr: &str) -> io::Result<Self> {
+        let server = tiny_http::Server::http(addr).map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(Self {
+            server,
+            state: Arc::new(HttpState {
+                project_root: project_root.to_path_buf(),
+                registry: Arc::new(crate::mcp::project_registry(project_root)),
+                sessions: Mutex::new(HashMap::new()),
+                next_session: AtomicU64::new(0),
+            }),
+        })
+    }
+
+    /// The bound address (useful after binding port 0).
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Serve requests, each on its own thread, until the listener fails.
+    pub fn run(self) {
+        if self.state.project_root.join(".coraline").is_dir() {
+            mcp::spawn_auto_sync(
+                self.state.project_root.clone(),
+                Arc::new(AtomicBool::new(false)),
+            );
+        }
+
+        for request in self.server.incoming_requests() {
+            let state = Arc::clone(&self.state);
+            let spawned = std::thread::Builder::new()
+                .name("coraline-mcp-http".into())
+                .spawn(move || handle_request(&state, request));
+            if let Err(err) = spawned {
+                warn!(error = %err, "failed to spawn MCP HTTP request thread");
+            }
+        }
+    }
+}
+
+fn handle_request(state: &HttpState, mut request: Request) {
+    let method = request.method().clone();
+    let reply = if request.url().split('?').next() != Some(ENDPOINT_PATH) {
+        Reply::error(
+            404,
+            -32600,
+            format!("Not found; the MCP endpoint is {ENDPOINT_PATH}"),
+        )
+    } else if !origin_allowed(header(&request, "Origin").as_deref()) {
+        Reply::error(403, -32600, "Origin not allowed")
+    } else {
+        match method {
+            Method::Post => handle_post(state, &mut request),
+            Method::Delete => handle_delete(state, &request),
+            _ => Reply::error(405, -32600, "Only POST and DELETE are supported"),
+        }
+    };
+    debug!(method = %method, url = request.url(), "MCP HTTP request");
+
+    if let Err(err) = respond(request, reply) {
+        debug!(error = %err, "failed to write MCP HTTP response");
+    }
+}
+
+fn handle_post(state: &HttpState, request: &mut Request) -> Reply {
+    if let Some(version) = header(request, PROTOCOL_VERSION_HEADER)
+        && !mcp::SUPPORTED_PROTOCOL_VERSIONS.contains(&version.as_str())
+    {
+        return Reply::error(
+            400,
+            -32600,
+            format!("Unsupported {PROTOCOL_VERSION_HEADER}: {version}"),
+        );
+    }
+
+    let mut body = String::new();
+    if let Err(err) = request
+        .as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_string(&mut body)
+    {
+        return Reply::error(400, -32700, format!("Failed to read body: {err}"));
+    }
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Reply::error(413, -32600, "Request body too large");
+    }
+
+    let message = match serde_json::from_str::<Value>(&body) {
+        Ok(Value::Object(message)) => Value::Object(message),
+        Ok(Value::Array(_)) => {
+            return Reply::error(400, -32600, "Batch requests are not supported");
+        }
+        Ok(_) => return Reply::error(400, -32600, "Invalid request: expected an object"),
+        Err(_) => return Reply::error(400, -32700, "Parse error: invalid JSON"),
+    };
+    let method = message.get("method").and_then(Value::as_str);
+    let id = message.get("id").filter(|id| !id.is_null());
+
+    let (session_id, session, opened) = match header(request, SESSION_HEADER) {
+        Some(session_id) => match state.touch_session(&session_id) {
+            Some(session) => (session_id, session, false),
+            None => return Reply::error(404, -32001, "Session not found"),
+        },
+        None if method == Some("initialize") => match state.open_session() {
+            Ok((session_id, session)) => (session_id, session, true),
+            Err(err) => {
+                return Reply::error(500, -32603, format!("Failed to open session: {err}"));
+            }
+        },
+        None => return Reply::error(400, -32600, format!("Missing {SESSION_HEADER} header")),
+    };
+
+    // Notifications and responses are only acknowledged.
+    let (Some(_), Some(id)) = (method, id) else {
+        if matches!(method, Some("notifications/cancelled" | "$/cancelRequest"))
+            && let Some(target) = message
+                .get("params")
+                .and_then(|p| p.get("requestId").or_else(|| p.get("id")))
+        {
+            session.routes.abandon(&target.to_string());
+        }
+        if session.events.send(ServerEvent::Line(body)).is_err() {
+            state.close_session(&session_id);
+            return Reply::error(404, -32001, "Session not found");
+        }
+        return Reply::Empty(202);
+    };
+
+    let id_key = id.to_string();
+    let progress_key = message
+        .pointer("/params/_meta/progressToken")
+        .map(Value::to_string);
+    let stream = progress_key.is_some()
+        && header(request, "Accept").is_some_and(|accept| accept.contains("text/event-stream"));
+    let Some(replies) = session.routes.open(&id_key, progress_key) else {
+        return Reply::error(
+            409,
+            -32600,
+            format!("Request id {id_key} is already in use"),
+        );
+    };
+    if session.events.send(ServerEvent::Line(body)).is_err() {
+        state.close_session(&session_id);
+        return Reply::error(404, -32001, "Session not found");
+    }
+
+    if stream {
+        return Reply::Stream {
+            replies,
+            routes: session.routes,
+            id_key,
+        };
+    }
+
+    // Without a stream, progress notifications are dropped.
+    let Some(response) = replies.iter().find(is_response) else {
+        // Cancelled, or the session ended before answering.
+        return Reply::Empty(204);
+    };
+    let session_id = if opened {
+        if response.get("result").is_none() {
+            state.close_session(&session_id);
+            None
+        } else {
+            info!(session = %session_id, "MCP HTTP session opened");
+            Some(session_id)
+        }
+    } else {
+        None
+    };
+    Reply::Json {
+        status: 200,
+        body: response,
+        session_id,
+    }
+}
+
+fn handle_delete(state: &HttpState, request: &Request) -> Reply {
+    let Some(session_id) = header(request, SESSION_HEADER) else {
+        return Reply::error(400, -32600, format!("Missing {SESSION_HEADER} header"));
+    };
+    if state.close_session(&session_id) {
+        Reply::Empty(204)
+    } else {
+        Reply::error(404, -32001, "Session not found")
+    }
+}
+
+impl HttpState {
+    /// Start a session loop on its own thread and register it.
+    fn open_session(&self) -> io::Result<(String, Session)> {
+        self.close_idle_sessions();
+
+        let session_id = self.new_session_id();
+        let routes = Arc::new(Routes::default());
+        let (sender, events) = mpsc::channel();
+
+        let project_root = self.project_root.clone();
+        let registry = Arc::clone(&self.registry);
+        let loop_sender = sender.clone();
+        let loop_routes = Arc::clone(&routes);
+        let loop_session_id = session_id.clone();
+        std::thread::Builder::new()
+            .name("coraline-mcp-session".into())
+            .spawn(move || {
+                let sink_routes = Arc::clone(&loop_routes);
+                let sink: MessageSink = Arc::new(move |message| {
+                    sink_routes.deliver(message);
+                    Ok(())
+                });
+                let mut server = McpServer::for_session(project_root, registry, sink);
+                if let Err(err) = server.run(loop_sender, &events) {
+                    warn!(session = %loop_session_id, error = %err, "MCP HTTP session failed");
+                }
+                loop_routes.close();
+                debug!(session = %loop_session_id, "MCP HTTP session ended");
+            })?;
+
+        let session = Session {
+            events: sender,
+            routes,
+            last_seen: Instant::now(),
+        };
+        self.sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(session_id.clone(), session.clone());
+        Ok((session_id, session))
+    }
+
+    fn touch_session(&self, session_id: &str) -> Option<Session> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        let session = sessions.get_mut(session_id)?;
+        session.last_seen = Instant::now();
+        let session = session.clone();
+        drop(sessions);
+        Some(session)
+    }
+
+    /// End a session once its running tool calls are answered; `false` if
+    /// there is no such session.
+    fn close_session(&self, session_id: &str) -> bool {
+        let session = self
+            .sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(session_id);
+        let Some(session) = session else {
+            return false;
+        };
+        let _ = session.events.send(ServerEvent::InputClosed);
+        info!(session = %session_id, "MCP HTTP session closed");
+        true
+    }
+
+    fn close_idle_sessions(&self) {
+        let idle: Vec<String> = self
+            .sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|(_, session)| session.last_seen.elapsed() > SESSION_IDLE_TIMEOUT)
+            .map(|(session_id, _)| session_id.clone())
+            .collect();
+        for session_id in idle {
+            self.close_session(&session_id);
+        }
+    }
+
+    /// An unguessable id: a hash of the time, a counter, and a per-call
+    /// randomly keyed hash.
+    fn new_session_id(&self) -> String {
+        let counter = self.next_session.fetch_add(1, Ordering::Relaxed);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        let random = std::collections::hash_map::RandomState::new().hash_one(counter);
+
+        let mut hasher = Sha256::new();
+        hasher.update(nanos.to_le_bytes());
+        hasher.update(counter.to_le_bytes());
+        hasher.update(random.to_le_bytes());
+        hasher.update(std::process::id().to_le_bytes());
+        hasher
+            .finalize()
+            .get(..16)
+            .map(hex::encode)
+            .unwrap_or_default()
+    }
+}
+
+impl Routes {
+    /// Route the response to `id_key` (and progress for `progress_key`) to
+    /// the returned receiver; `None` if the id is already open or the
+    /// session has ended.
+    fn open(&self, id_key: &str, progress_key: Option<String>) -> Option<mpsc::Receiver<Value>> {
+        let mut table = self.table.lock().unwrap_or_else(PoisonError::into_inner);
+        if table.closed || table.requests.contains_key(id_key) {
+            return None;
+        }
+        let (sender, receiver) = mpsc::channel();
+        table.requests.insert(id_key.to_string(), sender);
+        if let Some(progress_key) = progress_key {
+            table.progress.insert(progress_key, id_key.to_string());
+        }
+        drop(table);
+        Some(receiver)
+    }
+
+    /// Forward one outgoing message to the request it belongs to.
+    fn deliver(&self, message: Value) {
+        let mut table = self.table.lock().unwrap_or_else(PoisonError::into_inner);
+        if message.get("method").and_then(Value::as_str) == Some("notifications/progress") {
+            let target = message
+                .pointer("/params/progressToken")
+                .and_then(|token| table.progress.get(&token.to_string()));
+            if let Some(sender) = target.and_then(|id_key| table.requests.get(id_key)) {
+                let _ = sender.send(message);
+            }
+            return;
+        }
+
+        let Some(id_key) = message.get("id").map(Value::to_string) else {
+            debug!("dropping MCP HTTP message without a request to answer");
+            return;
+        };
+        table.progress.retain(|_, target| *target != id_key);
+        if let Some(sender) = table.requests.remove(&id_key) {
+            let _ = sender.send(message);
+        } else {
+            debug!(id = %id_key, "dropping MCP HTTP response without a waiting request");
+        }
+    }
+
+    /// Stop waiting for `id_key`; its waiting request is released.
+    fn abandon(&self, id_key: &str) {
+        let mut table = self.table.lock().unwrap_or_else(PoisonError::into_inner);
+        table.requests.remove(id_key);
+        table.progress.retain(|_, target| target != id_key);
+    }
+
+    /// Release every waiting request; the session has ended.
+    fn close(&self) {
+        let mut table = self.table.lock().unwrap_or_else(PoisonError::into_inner);
+        table.closed = true;
+        table.requests.clear();
+        table.progress.clear();
+    }
+}
+
+fn respond(request: Request, reply: Reply) -> io::Result<()> {
+    match reply {
+        Reply::Empty(status) => request.respond(Response::empty(status)),
+        Reply::Json {
+            status,
+            body,
+            session_id,
+        } => {
+            let mut response = with_header(
+                Response::from_string(body.to_string()).with_status_code(status),
+                "Content-Type",
+                "application/json",
+            );
+            if let Some(session_id) = session_id {
+                response = with_header(response, SESSION_HEADER, &session_id);
+            }
+            request.respond(response)
+        }
+        Reply::Stream {
+            replies,
+            routes,
+            id_key,
+        } => {
+            let streamed = stream_events(request, &replies);
+            if streamed.is_err() {
+                routes.abandon(&id_key);
+            }
+            streamed
+        }
+    }
+}
+
+/// Write `replies` as a chunked `text/event-stream`, ending after the
+/// response. Written by hand because `tiny_http` buffers chunked bodies, which
+/// would hold back progress events.
+fn stream_events(request: Request, replies: &mpsc::Receiver<Value>) -> io::Result<()> {
+    let mut writer = request.into_writer();
+    writer.write_all(
+        b"HTTP/1.1 200 OK\r\n\
+          Content-Type: text/event-stream\r\n\
+          Cache-Control: no-cache\r\n\
+          Transfer-Encoding: chunked\r\n\r\n",
+    )?;
+    writer.flush()?;
+
+    for message in replies {
+        let event = format!("event: message\ndata: {message}\n\n");
+        write!(writer, "{:x}\r\n{event}\r\n", event.len())?;
+        writer.flush()?;
+        if is_response(&message) {
+            break;
+        }
+    }
+    writer.write_all(b"0\r\n\r\n")?;
+    writer.flush()
+}
+
+fn is_response(message: &Value) -> bool {
+    message.get("method").is_none()
+}
+
+/// Browsers may only reach the server from a local page; this blocks DNS
+/// rebinding. Clients that send no `Origin` (editors, CLIs) are allowed.
+pub(crate) fn origin_allowed(origin: Option<&str>) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    let host = origin
+        .split_once("://")
+        .map_or(origin, |(_, rest)| rest)
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    let host = host.strip_prefix('[').map_or_else(
+        || host.split(':').next().unwrap_or_default(),
+        |rest| rest.split(']').next().unwrap_or_default(),
+    );
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+fn header(request: &Request, name: &str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+fn with_header<R: Read>(response: Response<R>, name: &str, value: &str) -> Response<R> {
+    match Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+        Ok(header) => response.with_header(header),
+        Err(()) => response,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+
+    use serde_json::json;
+
+    use super::{Routes, origin_allowed};
+
+    #[test]
+    fn origin_allowed_only_for_local_pages() {
+        assert!(origin_allowed(None));
+        assert!(origin_allowed(Some("http://localhost:3000")));
+        assert!(origin_allowed(Some("http://127.0.0.1")));
+        assert!(origin_allowed(Some("http://[::1]:8080")));
+        assert!(!origin_allowed(Some("https://evil.example")));
+        assert!(!origin_allowed(Some("http://localhost.evil.example")));
+    }
+
+    #[test]
+    fn routes_deliver_progress_and_response_to_the_waiting_request() {
+        let routes = Routes::default();
+        let replies = routes
+            .open("7", Some("\"tok\"".to_string()))
+            .expect("id should be free");
+        assert!(routes.open("7", None).is_none(), "ids are single-use");
+
+        routes.deliver(json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": { "progressToken": "tok", "progress": 1 },
+        }));
+        routes.deliver(json!({ "jsonrpc": "2.0", "id": 7, "result": {} }));
+
+        let received: Vec<_> = replies.iter().collect();
+        assert_eq!(received.len(), 2);
+        assert_eq!(
+            received.last().and_then(|reply| reply.get("id")),
+            Some(&json!(7))
+        );
+        assert!(
+            routes.open("7", None).is_some(),
+            "answered ids are released"
+        );
+    }
+}
@@ -9,31 +9,161 @@
 //!
 //! Memories are stored as markdown files in `.coraline/memories/` and provide
 //! a way to persist project knowledge across sessions.
+//!
+//! Each memory belongs to a [`Namespace`], a subdirectory of the memories
+//! directory:
+//!
+//! - `shared/` — team knowledge, committed with the project
+//! - `local/` — personal scratch notes, ignored by git
+//! - `agents/<name>/` — notes kept by one agent
+//!
+//! Operations use the manager's namespace, which defaults to
+//! `[memory] default_namespace`. Memories written before namespaces existed
+//! sit directly in `memories/` and are moved into `shared/` on first use.
+//!
+//! Each file starts with a YAML front matter block holding its
+//! [`MemoryMeta`]:
+//!
+//! ```text
+//! ---
+//! tags: [architecture, storage]
+//! author: alice
+//! related_symbols: [MemoryManager, db::open_database]
+//! created_at: 1760400000
+//! updated_at: 1760486400
+//! ---
+//!
+//! # Storage notes
+//! ```
+//!
+//! Only these keys are read; others are dropped when the memory is next
+//! written. Files without front matter are still read, with their
+//! modification time standing in for both timestamps.
 
+use std::fmt::{self, Write as _};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 
-/// Memory metadata and content.
+use crate::config;
+
+/// A group of memories, stored in its own directory. Written as `shared`,
+/// `local`, or `agents/<name>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Namespace {
+    /// Committed team knowledge.
+    #[default]
+    Shared,
+    /// Personal notes, ignored by git.
+    Local,
+    /// Notes kept by the named agent.
+    Agent(String),
+}
+
+impl Namespace {
+    /// The directory, relative to the memories directory.
+    fn relative_dir(&self) -> PathBuf {
+        match self {
+            Self::Shared => PathBuf::from("shared"),
+            Self::Local => PathBuf::from("local"),
+            Self::Agent(name) => Path::new("agents").join(name),
+        }
+    }
+}
+
+impl fmt::Display for Namespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shared => f.write_str("shared"),
+            Self::Local => f.write_str("local"),
+            Self::Agent(name) => write!(f, "agents/{name}"),
+        }
+    }
+}
+
+impl FromStr for Namespace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shared" => Ok(Self::Shared),
+            "local" => Ok(Self::Local),
+            _ => match s.strip_prefix("agents/") {
+                Some(name)
+                    if !name.is_empty()
+                        && name
+                            .chars()
+                            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                        && !name.starts_with('.') =>
+                {
+                    Ok(Self::Agent(name.to_string()))
+                }
+                _ => Err(format!(
+                    "invalid memory namespace '{s}' (expected shared, local, or agents/<name>)"
+                )),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for Namespace {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Namespace> for String {
+    fn from(namespace: Namespace) -> Self {
+        namespace.to_string()
+    }
+}
+
+/// A memory: its body and the metadata from its front matter.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Memory {
     pub name: String,
+    pub namespace: Namespace,
+    /// The Markdown body, without front matter.
     pub content: String,
+    #[serde(flatten)]
+    pub meta: MemoryMeta,
+}
+
+/// Front matter of a memory file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryMeta {
     #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Symbols the memory is about, by name or qualified name.
+    #[serde(default)]
+    pub related_symbols: Vec<String>,
+    /// Unix seconds.
     pub created_at: i64,
+    /// Unix seconds.
     pub updated_at: i64,
 }
 
-/// Manager for project-specific memories.
+/// Manager for project-specific memories in one [`Namespace`].
+#[derive(Debug, Clone)]
 pub struct MemoryManager {
+    /// `.coraline/memories/`.
+    memories_root: PathBuf,
+    namespace: Namespace,
     memory_dir: PathBuf,
 }
 
 impl MemoryManager {
-    /// Create a new memory manager for the given project root.
+    /// Create a memory manager for the given project root, in the
+    /// configured default namespace.
     pub fn new(project_root: &Path) -> io::Result<Self> {
         let coraline_dir = project_root.join(".coraline");
         if !coraline_dir.is_dir() {
@@ -45,45 +175,174 @@ impl MemoryManager {
                 ),
             ));
         }
-        let memory_dir = coraline_dir.join("memories");
-        Ok(Self { memory_dir })
+        let memories_root = coraline_dir.join("memories");
+        migrate_unscoped_memories(&memories_root)?;
+        let namespace = config::load_toml_config(project_root)
+            .map(|cfg| cfg.memory.default_namespace)
+            .unwrap_or_default();
+        Ok(Self {
+            memory_dir: memories_root.join(namespace.relative_dir()),
+            memories_root,
+            namespace,
+        })
+    }
+
+    /// The same project's memories in `namespace`.
+    #[must_use]
+    pub fn with_namespace(&self, namespace: Namespace) -> Self {
+        Self {
+            memory_dir: self.memories_root.join(namespace.relative_dir()),
+            memories_root: self.memories_root.clone(),
+            namespace,
+        }
+    }
+
+    /// The namespace this manager reads and writes.
+    pub const fn namespace(&self) -> &Namespace {
+        &self.namespace
+    }
+
+    /// Namespaces that have a directory: `shared` and `local`, then every
+    /// agent in name order.
+    pub fn namespaces(&self) -> io::Result<Vec<Namespace>> {
+        let mut namespaces: Vec<Namespace> = [Namespace::Shared, Namespace::Local]
+            .into_iter()
+            .filter(|ns| self.memories_root.join(ns.relative_dir()).is_dir())
+            .collect();
+        let agents_dir = self.memories_root.join("agents");
+        if agents_dir.is_dir() {
+            let mut agents = Vec::new();
+            for entry in fs::read_dir(&agents_dir)? {
+                let entry = entry?;
+                if entry.path().is_dir()
+                    && let Some(name) = entry.file_name().to_str()
+                    && let Ok(namespace) = format!("agents/{name}").parse()
+                {
+                    agents.push(namespace);
+                }
+            }
+            agents.sort();
+            namespaces.extend(agents);
+        }
+        Ok(namespaces)
+    }
+
+    /// Memories of every namespace, grouped as [`Self::namespaces`] orders
+    /// them and sorted by name within each.
+    pub fn list_all_memories(&self) -> io::Result<Vec<Memory>> {
+        let mut memories = Vec::new();
+        for namespace in self.namespaces()? {
+            memories.extend(self.with_namespace(namespace).list_memories()?);
+        }
+        Ok(memories)
     }
 
     /// Get the file path for a memory by name (strips and adds .md extension).
-    fn get_memory_path(&self, name: &str) -> PathBuf {
-        let name = name.trim_end_matches(".md");
-        self.memory_dir.join(format!("{name}.md"))
+    /// Names failing [`validate_name`] are an `InvalidInput` error.
+    fn get_memory_path(&self, name: &str) -> io::Result<PathBuf> {
+        let name = validate_name(name)
+            .map_err(|message| io::Error::new(io::ErrorKind::InvalidInput, message))?;
+        Ok(self.memory_dir.join(format!("{name}.md")))
     }
 
-    /// Write or update a memory.
+    /// Write or update a memory, keeping the metadata of an existing one.
+    /// Front matter at the start of `content` replaces the stored fields it
+    /// sets.
     pub fn write_memory(&self, name: &str, content: &str) -> io::Result<String> {
+        self.write_memory_with(name, content, |_| {})
+    }
+
+    /// Write or update a memory, letting `edit` change its metadata first.
+    /// `created_at` is set on the first write and `updated_at` on every one.
+    pub fn write_memory_with(
+        &self,
+        name: &str,
+        content: &str,
+        edit: impl FnOnce(&mut MemoryMeta),
+    ) -> io::Result<String> {
+        let now = unix_now();
+        let mut meta = self.get_memory(name)?.map_or_else(
+            || MemoryMeta {
+                created_at: now,
+                ..MemoryMeta::default()
+            },
+            |memory| memory.meta,
+        );
+        let body = match split_front_matter(content) {
+            Some((front, body)) => {
+                let given = parse_front_matter(front);
+                meta.tags = given.tags;
+                meta.author = given.author.or(meta.author);
+                meta.related_symbols = given.related_symbols;
+                body
+            }
+            None => content,
+        };
+        edit(&mut meta);
+        meta.updated_at = now;
+
         fs::create_dir_all(&self.memory_dir)?;
-        let path = self.get_memory_path(name);
-        fs::write(&path, content)?;
+        if self.namespace == Namespace::Local {
+            let gitignore = self.memory_dir.join(".gitignore");
+            if !gitignore.exists() {
+                fs::write(gitignore, "# Personal memories are not committed\n*\n")?;
+            }
+        }
+        let path = self.get_memory_path(name)?;
+        fs::write(&path, format!("{}\n{body}", render_front_matter(&meta)))?;
         Ok(format!("Memory '{name}' written successfully"))
     }
 
-    /// Read a memory by name.
+    /// Read a memory's body by name.
     pub fn read_memory(&self, name: &str) -> io::Result<String> {
-        let path = self.get_memory_path(name);
-
-        if !path.exists() {
-            return Ok(format!(
+        match self.get_memory(name)? {
+            Some(memory) => Ok(memory.content),
+            None => Ok(format!(
                 "Memory '{name}' not found. Consider creating it with write_memory if needed."
-            ));
+            )),
         }
-
-        fs::read_to_string(&path)
     }
 
-    /// List all available memories.
-    pub fn list_memories(&self) -> io::Result<Vec<String>> {
-        let mut memories = Vec::new();
+    /// Read a memory with its metadata, or `None` if there is no such memory.
+    pub fn get_memory(&self, name: &str) -> io::Result<Option<Memory>> {
+        let path = self.get_memory_path(name)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&path)?;
+        let (mut meta, content) = match split_front_matter(&text) {
+            Some((front, body)) => (parse_front_matter(front), body),
+            None => (MemoryMeta::default(), text.as_str()),
+        };
+        if meta.created_at == 0 || meta.updated_at == 0 {
+            let modified = fs::metadata(&path)?
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX));
+            if meta.updated_at == 0 {
+                meta.updated_at = modified;
+            }
+            if meta.created_at == 0 {
+                meta.created_at = meta.updated_at;
+            }
+        }
+        Ok(Some(Memory {
+            name: name.trim_end_matches(".md").to_string(),
+            namespace: self.namespace.clone(),
+            content: content.to_string(),
+            meta,
+        }))
+    }
 
+    /// List the namespace's memories with their metadata, sorted by name.
+    pub fn list_memories(&self) -> io::Result<Vec<Memory>> {
         if !self.memory_dir.exists() {
-            return Ok(memories);
+            return Ok(Vec::new());
         }
 
+        let mut names = Vec::new();
+
         for entry in fs::read_dir(&self.memory_dir)? {
             let entry = entry?;
             let path = entry.path();
@@ -92,17 +351,21 @@ impl MemoryManager {
                 && path.extension().and_then(|s| s.to_str()) == Some("md")
                 && let Some(name) = path.file_stem().and_then(|s| s.to_str())
             {
-                memories.push(name.to_string());
+                names.push(name.to_string());
             }
         }
 
-        memories.sort();
+        names.sort();
+        let mut memories = Vec::with_capacity(names.len());
+        for name in names {
+            memories.extend(self.get_memory(&name)?);
+        }
         Ok(memories)
     }
 
     /// Delete a memory by name.
     pub fn delete_memory(&self, name: &str) -> io::Result<String> {
-        let path = self.get_memory_path(name);
+        let path = self.get_memory_path(name)?;
 
         if !path.exists() {
             return Err(io::Error::new(
@@ -117,18 +380,215 @@ impl MemoryManager {
 
     /// Check if a memory exists.
     pub fn memory_exists(&self, name: &str) -> bool {
-        self.get_memory_path(name).exists()
+        self.get_memory_path(name).is_ok_and(|path| path.exists())
     }
 
-    /// Get the full path to the memories directory.
+    /// Get the full path to the namespace's memories directory.
     pub fn memory_dir(&self) -> &Path {
         &self.memory_dir
     }
 }
 
+/// Move `*.md` files sitting directly in the memories directory, from before
+/// namespaces, into `shared/`. An existing shared memory of the same name
+/// is kept.
+fn migrate_unscoped_memories(memories_root: &Path) -> io::Result<()> {
+    if !memories_root.is_dir() {
+        return Ok(());
+    }
+    let shared = memories_root.join(Namespace::Shared.relative_dir());
+    for entry in fs::read_dir(memories_root)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("md") {
+            continue;
+        }
+        let Some(file_name) = path.file_name() else {
+            continue;
+        };
+        let target = shared.join(file_name);
+        if !target.exists() {
+            fs::create_dir_all(&shared)?;
+            fs::rename(&path, target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check a memory name, returning it without a trailing `.md`. A name must be
+/// a single file name: no path separators, no drive prefix, not only dots,
+/// and no control characters.
+pub fn validate_name(name: &str) -> Result<&str, String> {
+    let stem = name.strip_suffix(".md").unwrap_or(name);
+    if stem.trim().is_empty() {
+        return Err("Memory name must not be empty".to_string());
+    }
+    if stem.contains(['/', '\\', ':']) || stem.chars().any(char::is_control) {
+        return Err(format!(
+            "Invalid memory name '{name}': names may not contain path separators, ':', or control characters"
+        ));
+    }
+    if stem.chars().all(|c| c == '.') {
+        return Err(format!("Invalid memory name '{name}'"));
+    }
+    Ok(stem)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+}
+
+/// Split `text` into its front matter (between `---` fences on the first
+/// line and a later line) and the body after it.
+fn split_front_matter(text: &str) -> Option<(&str, &str)> {
+    let rest = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let body = &rest[offset + line.len()..];
+            // The blank line written after the fence is not part of the body.
+            let body = body
+                .strip_prefix("\r\n")
+                .or_else(|| body.strip_prefix('\n'))
+                .unwrap_or(body);
+            return Some((&rest[..offset], body));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Read the [`MemoryMeta`] keys from front matter. Lists may be written in
+/// flow (`[a, b]`) or block (`- a`) style; unknown keys are ignored.
+fn parse_front_matter(front: &str) -> MemoryMeta {
+    let mut meta = MemoryMeta::default();
+    let mut block_list: Option<&str> = None;
+    for line in front.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(item) = trimmed.strip_prefix('-') {
+            if let Some(list) = block_list.and_then(|key| meta_list(&mut meta, key)) {
+                list.push(unquote(item.trim()));
+            }
+            continue;
+        }
+        block_list = None;
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if let Some(list) = meta_list(&mut meta, key) {
+            match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                Some(items) => list.extend(
+                    split_flow_list(items)
+                        .iter()
+                        .map(|item| unquote(item))
+                        .filter(|item| !item.is_empty()),
+                ),
+                None if value.is_empty() => block_list = Some(key),
+                None => list.push(unquote(value)),
+            }
+            continue;
+        }
+        match key {
+            "author" => meta.author = Some(unquote(value)).filter(|a| !a.is_empty()),
+            "created_at" => meta.created_at = value.parse().unwrap_or(0),
+            "updated_at" => meta.updated_at = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    meta
+}
+
+fn meta_list<'a>(meta: &'a mut MemoryMeta, key: &str) -> Option<&'a mut Vec<String>> {
+    match key {
+        "tags" => Some(&mut meta.tags),
+        "related_symbols" => Some(&mut meta.related_symbols),
+        _ => None,
+    }
+}
+
+/// Split the inside of a flow list on commas outside quotes.
+fn split_flow_list(items: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    for c in items.chars() {
+        match quote {
+            None if c == ',' => out.push(std::mem::take(&mut current).trim().to_string()),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            Some(q) if c == q => {
+                quote = None;
+                current.push(c);
+            }
+            _ => current.push(c),
+        }
+    }
+    out.push(current.trim().to_string());
+    out
+}
+
+fn unquote(value: &str) -> String {
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        return inner.replace("\\\"", "\"").replace("\\\\", "\\");
+    }
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return inner.replace("''", "'");
+    }
+    value.to_string()
+}
+
+/// `value` as a YAML scalar, double-quoted when plain style would change
+/// its meaning.
+fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with(['-', '\'', '"', '&', '*', '!', '|', '>', '%', '@', '`', '?'])
+        && !value.contains([':', '#', ',', '[', ']', '{', '}', '\n']);
+    if plain {
+        value.to_string()
+    } else {
+        format!(
+            "\"{}\"",
+            value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', " ")
+        )
+    }
+}
+
+fn render_front_matter(meta: &MemoryMeta) -> String {
+    let list = |items: &[String]| {
+        let items: Vec<String> = items.iter().map(|item| quote(item)).collect();
+        format!("[{}]", items.join(", "))
+    };
+    let mut out = format!("---\ntags: {}\n", list(&meta.tags));
+    if let Some(author) = &meta.author {
+        let _ = writeln!(out, "author: {}", quote(author));
+    }
+    if !meta.related_symbols.is_empty() {
+        let _ = writeln!(out, "related_symbols: {}", list(&meta.related_symbols));
+    }
+    let _ = write!(
+        out,
+        "created_at: {}\nupdated_at: {}\n---\n",
+        meta.created_at, meta.updated_at
+    );
+    out
+}
+
 /// Create initial memory templates for a new project.
 pub fn create_initial_memories(project_root: &Path, project_name: &str) -> io::Result<()> {
-    let manager = MemoryManager::new(project_root)?;
+    let manager = MemoryManager::new(project_root)?.with_namespace(Namespace::Shared);
 
     // Project Overview
     let project_overview = format!(
@@ -309,11 +769,13 @@ mod tests {
             .write_memory("memory3", "content3")
             .expect("Failed to write memory3");
 
-        let memories = manager.list_memories().expect("Failed to list memories");
-        assert_eq!(memories.len(), 3);
-        assert!(memories.contains(&"memory1".to_string()));
-        assert!(memories.contains(&"memory2".to_string()));
-        assert!(memories.contains(&"memory3".to_string()));
+        let memories: Vec<String> = manager
+            .list_memories()
+            .expect("Failed to list memories")
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        assert_eq!(memories, vec!["memory1", "memory2", "memory3"]);
     }
 
     #[test]
@@ -353,7 +815,12 @@ mod tests {
             .expect("Failed to create initial memories");
 
         let manager = MemoryManager::new(temp_dir.path()).expect("Failed to create MemoryManager");
-        let memories = manager.list_memories().expect("Failed to list memories");
+        let memories: Vec<String> = manager
+            .list_memories()
+            .expect("Failed to list memories")
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
 
         assert_eq!(memories.len(), 4);
         assert!(memories.contains(&"project_overview".to_string()));
@@ -366,4 +833,147 @@ mod tests {
             .expect("Failed to read project_overview");
         assert!(overview.contains("test_project"));
     }
+
+    #[test]
+    fn test_memory_front_matter_round_trip() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        init_project_root(temp_dir.path());
+        let manager = MemoryManager::new(temp_dir.path()).expect("Failed to create MemoryManager");
+
+        manager
+            .write_memory(
+                "auth",
+                "---\ntags: [security, \"login flow\"]\nauthor: alice\nrelated_symbols:\n  - AuthService\n  - src/auth.ts::login\n---\nTokens expire after an hour.",
+            )
+            .expect("Failed to write memory");
+        let memory = manager
+            .get_memory("auth")
+            .expect("Failed to read memory")
+            .expect("memory should exist");
+        assert_eq!(memory.content, "Tokens expire after an hour.");
+        assert_eq!(memory.meta.tags, vec!["security", "login flow"]);
+        assert_eq!(memory.meta.author.as_deref(), Some("alice"));
+        assert_eq!(
+            memory.meta.related_symbols,
+            vec!["AuthService", "src/auth.ts::login"]
+        );
+        assert!(memory.meta.created_at > 0);
+        let created_at = memory.meta.created_at;
+
+        // Rewriting the body keeps the metadata and the creation time.
+        manager
+            .write_memory("auth", "Tokens expire after a day.")
+            .expect("Failed to rewrite memory");
+        let memory = manager
+            .get_memory("auth")
+            .expect("Failed to read memory")
+            .expect("memory should exist");
+        assert_eq!(memory.content, "Tokens expire after a day.");
+        assert_eq!(memory.meta.tags, vec!["security", "login flow"]);
+        assert_eq!(memory.meta.created_at, created_at);
+        assert!(memory.meta.updated_at >= created_at);
+        assert_eq!(
+            manager.read_memory("auth").expect("Failed to read memory"),
+            "Tokens expire after a day."
+        );
+
+        // Files written by hand without front matter still list.
+        fs::write(manager.memory_dir().join("plain.md"), "Just notes.")
+            .expect("Failed to write file");
+        let plain = manager
+            .get_memory("plain")
+            .expect("Failed to read memory")
+            .expect("memory should exist");
+        assert_eq!(plain.content, "Just notes.");
+        assert!(plain.meta.tags.is_empty());
+        assert!(plain.meta.updated_at > 0);
+    }
+
+    #[test]
+    fn test_namespaces_keep_memories_apart() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        init_project_root(temp_dir.path());
+        let memories = temp_dir.path().join(".coraline/memories");
+        fs::create_dir_all(&memories).expect("Failed to create memories directory");
+        fs::write(memories.join("legacy.md"), "From before namespaces.")
+            .expect("Failed to write legacy memory");
+
+        let shared = MemoryManager::new(temp_dir.path()).expect("Failed to create MemoryManager");
+        assert_eq!(shared.namespace(), &Namespace::Shared);
+        assert!(memories.join("shared/legacy.md").is_file());
+        assert!(!memories.join("legacy.md").exists());
+
+        let local = shared.with_namespace(Namespace::Local);
+        let agent = shared.with_namespace("agents/reviewer".parse().expect("valid namespace"));
+        local
+            .write_memory("scratch", "Personal notes.")
+            .expect("Failed to write local memory");
+        agent
+            .write_memory("scratch", "Agent notes.")
+            .expect("Failed to write agent memory");
+        assert!(!shared.memory_exists("scratch"));
+        assert_eq!(
+            local.read_memory("scratch").expect("Failed to read memory"),
+            "Personal notes."
+        );
+        assert!(memories.join("local/.gitignore").is_file());
+        assert!(memories.join("agents/reviewer/scratch.md").is_file());
+
+        let listed: Vec<String> = shared
+            .list_all_memories()
+            .expect("Failed to list memories")
+            .into_iter()
+            .map(|m| format!("{}/{}", m.namespace, m.name))
+            .collect();
+        assert_eq!(
+            listed,
+            vec!["shared/legacy", "local/scratch", "agents/reviewer/scratch"]
+        );
+
+        for invalid in ["", "team", "agents/", "agents/../x", "agents/a/b"] {
+            assert!(invalid.parse::<Namespace>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn test_default_namespace_from_config() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        init_project_root(temp_dir.path());
+        fs::write(
+            temp_dir.path().join(".coraline/config.toml"),
+            "[memory]\ndefault_namespace = \"agents/bot\"\n",
+        )
+        .expect("Failed to write config");
+        let manager = MemoryManager::new(temp_dir.path()).expect("Failed to create MemoryManager");
+        assert_eq!(manager.namespace(), &Namespace::Agent("bot".to_string()));
+        assert!(manager.memory_dir().ends_with("memories/agents/bot"));
+    }
+
+    #[test]
+    fn test_memory_names_cannot_escape_directory() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        init_project_root(temp_dir.path());
+        let manager = MemoryManager::new(temp_dir.path()).expect("Failed to create MemoryManager");
+
+        for name in [
+            "../../config",
+            "..",
+            "...",
+            "a/b",
+            "a\\b",
+            "C:config",
+            "",
+            "x\0y",
+        ] {
+            let err = manager
+                .write_memory(name, "content")
+                .expect_err("invalid names are rejected");
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{name:?}");
+            assert!(manager.get_memory(name).is_err(), "{name:?}");
+            assert!(manager.delete_memory(name).is_err(), "{name:?}");
+            assert!(!manager.memory_exists(name), "{name:?}");
+        }
+        assert!(!temp_dir.path().join("config.md").exists());
+        assert_eq!(validate_name("notes.v2.md"), Ok("notes.v2"));
+    }
 }
@@ -0,0 +1,547 @@
+#![forbid(unsafe_code)]
+
+//! ORM models and the code that queries them.
+//!
+//! Each model becomes a `model` node named after it, with the signature
+//! `table <name>`, contained in its file and linked to the struct or class
+//! defining it by a `references` edge:
+//!
+//! | ORM | Model |
+//! |---|---|
+//! | Diesel | a struct deriving `Queryable`, `Selectable`, `Insertable`, ...; `#[diesel(table_name = users)]` |
+//! | `SeaORM` | the `Model` struct deriving `DeriveEntityModel`, named after its module; `#[sea_orm(table_name = "users")]` |
+//! | `SQLAlchemy`, Flask-SQLAlchemy, Django, `SQLModel` | a class of `Base`, `db.Model`, `models.Model`, or `SQLModel, table=True`; `__tablename__`, `db_table` |
+//! | `ActiveRecord` | a class of `ApplicationRecord` or `ActiveRecord::Base`; `self.table_name` |
+//! | Prisma | a `model` block of a `.prisma` schema; `@@map("users")` |
+//!
+//! Without an explicit name, the table is named the way the ORM would: the
+//! `snake_case` model name, pluralized by Diesel and `ActiveRecord`.
+//!
+//! After each index and sync, [`link_queries`] adds a `type_of` edge from
+//! every function or method touching a model to the model, so the impact of
+//! a model is the data-layer code reading and writing its table. A function
+//! touches a model when one of its lines uses the ORM's query API with it
+//! (`users::table`, `session.query(User)`, `User.where(...)`,
+//! `prisma.user.findMany(...)`) or names its table in SQL (`FROM users`,
+//! `INSERT INTO users`, `UPDATE users`, `JOIN users`).
+
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use rusqlite::Connection;
+
+use crate::db;
+use crate::error::Result;
+use crate::types::{Edge, EdgeKind, Language, Node, NodeKind};
+use crate::utils::node_id_for_symbol;
+
+/// Diesel derives that make a struct a model of its table.
+const DIESEL_DERIVES: &[&str] = &[
+    "Queryable",
+    "QueryableByName",
+    "Selectable",
+    "Insertable",
+    "AsChangeset",
+    "Identifiable",
+];
+
+/// Base classes of Python models. `SQLAlchemy`'s declarative `Base` also
+/// parents abstract classes, so its models must name their table.
+const PYTHON_BASES: &[&str] = &[
+    "Base",
+    "DeclarativeBase",
+    "db.Model",
+    "models.Model",
+    "Model",
+];
+
+/// `table_name = users`, `table_name = "users"`, `table_name = crate::schema::users`.
+static TABLE_NAME: LazyLock<Option<Regex>> =
+    LazyLock::new(|| Regex::new(r#"table_name\s*=\s*"?((?:\w+::)*\w+)"?"#).ok());
+
+/// `__tablename__ = "users"`, `db_table = "users"`, `self.table_name = "users"`.
+static ASSIGNED_TABLE: LazyLock<Option<Regex>> = LazyLock::new(|| {
+    Regex::new(r#"(?:__tablename__|db_table|self\.table_name)\s*=\s*['"](\w+)['"]"#).ok()
+});
+
+/// `class User(Base):`; the bases are captured.
+static PYTHON_CLASS: LazyLock<Option<Regex>> =
+    LazyLock::new(|| Regex::new(r"^\s*class\s+\w+\s*\(([^)]*)\)\s*:").ok());
+
+/// `class User < ApplicationRecord`.
+static RUBY_MODEL: LazyLock<Option<Regex>> = LazyLock::new(|| {
+    Regex::new(r"^\s*class\s+[\w:]+\s*<\s*(?:ApplicationRecord|ActiveRecord::Base)\b").ok()
+});
+
+/// `model User {` of a Prisma schema.
+static PRISMA_MODEL: LazyLock<Option<Regex>> =
+    LazyLock::new(|| Regex::new(r"(?m)^model\s+(\w+)\s*\{").ok());
+
+/// `@@map("users")` inside a Prisma model.
+static PRISMA_MAP: LazyLock<Option<Regex>> =
+    LazyLock::new(|| Regex::new(r#"@@map\(\s*(?:name:\s*)?"(\w+)"\s*\)"#).ok());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framework {
+    Diesel,
+    SeaOrm,
+    Python,
+    ActiveRecord,
+    Prisma,
+}
+
+impl Framework {
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Diesel => "Diesel",
+            Self::SeaOrm => "SeaORM",
+            Self::Python => "Python ORM",
+            Self::ActiveRecord => "ActiveRecord",
+            Self::Prisma => "Prisma",
+        }
+    }
+}
+
+/// A model found in a file: its framework, name, table, line span, and
+/// the struct or class defining it.
+type Found<'a> = (Framework, String, String, i64, i64, Option<&'a Node>);
+
+/// The model nodes of a file, defined by its structs and classes among
+/// `nodes` or, in a Prisma schema, by its `model` blocks.
+pub(crate) fn extract_models(
+    file_path: &str,
+    source: &str,
+    language: Language,
+    nodes: &[Node],
+    root_id: &str,
+    now_ms: i64,
+) -> (Vec<Node>, Vec<Edge>) {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut found: Vec<Found<'_>> = Vec::new();
+    if language == Language::Prisma {
+        found.extend(
+            prisma_models(source)
+                .into_iter()
+                .map(|(name, table, start, end)| {
+                    (Framework::Prisma, name, table, start, end, None)
+                }),
+        );
+    }
+    for node in nodes {
+        let model = match (language, node.kind) {
+            (Language::Rust, NodeKind::Struct) => rust_model(file_path, &lines, node),
+            (Language::Python, NodeKind::Class) => python_model(&lines, node),
+            (Language::Ruby, NodeKind::Class) => ruby_model(&lines, node),
+            _ => None,
+        };
+        if let Some((framework, name, table)) = model {
+            found.push((
+                framework,
+                name,
+                table,
+                node.start_line,
+                node.end_line,
+                Some(node),
+            ));
+        }
+    }
+
+    let mut models = Vec::new();
+    let mut edges = Vec::new();
+    for (framework, name, table, start_line, end_line, definition) in found {
+        let qualified_name = format!("{file_path}::model::{name}");
+        let id = node_id_for_symbol(file_path, "model", &qualified_name, start_line, 0);
+        edges.push(Edge {
+            source: root_id.to_string(),
+            target: id.clone(),
+            kind: EdgeKind::Contains,
+            metadata: None,
+            line: Some(start_line),
+            column: Some(0),
+        });
+        if let Some(definition) = definition {
+            edges.push(Edge {
+                source: id.clone(),
+                target: definition.id.clone(),
+                kind: EdgeKind::References,
+                metadata: None,
+                line: Some(start_line),
+                column: Some(0),
+            });
+        }
+        models.push(Node {
+            id,
+            kind: NodeKind::Model,
+            name,
+            qualified_name,
+            file_path: file_path.to_string(),
+            language,
+            start_line,
+            end_line,
+            start_column: 0,
+            end_column: 0,
+            docstring: Some(format!("{} model of the {table} table", framework.label())),
+            signature: Some(format!("table {table}")),
+            visibility: None,
+            is_exported: definition.is_some_and(|d| d.is_exported),
+            is_async: false,
+            is_static: false,
+            is_abstract: false,
+            decorators: None,
+            type_parameters: None,
+            updated_at: now_ms,
+        });
+    }
+    (models, edges)
+}
+
+/// The attribute lines above a Rust item starting at `start_line`.
+fn attributes_above(lines: &[&str], start_line: i64) -> String {
+    let end = usize::try_from(start_line - 1)
+        .unwrap_or_default()
+        .min(lines.len());
+    let above = lines.get(..end).unwrap_or_default();
+    let start = above
+        .iter()
+        .rposition(|line| {
+            let line = line.trim();
+            line.is_empty() || line.ends_with(';') || line.ends_with('}')
+        })
+        .map_or(0, |index| index + 1);
+    above.get(start..).unwrap_or_default().join("\n")
+}
+
+fn rust_model(file_path: &str, lines: &[&str], node: &Node) -> Option<(Framework, String, String)> {
+    let attributes = attributes_above(lines, node.start_line);
+    let table = TABLE_NAME
+        .as_ref()
+        .and_then(|re| re.captures(&attributes))
+        .and_then(|caps| caps[1].rsplit("::").next().map(str::to_string));
+    if attributes.contains("DeriveEntityModel") {
+        let module = module_name(file_path);
+        let table = table.unwrap_or_else(|| module.clone());
+        return Some((Framework::SeaOrm, pascal_case(&module), table));
+    }
+    let diesel = attributes.contains("diesel(")
+        || DIESEL_DERIVES
+            .iter()
+            .any(|derive| contains_word(&attributes, derive));
+    diesel.then(|| {
+        let table = table.unwrap_or_else(|| format!("{}s", snake_case(&node.name)));
+        (Framework::Diesel, node.name.clone(), table)
+    })
+}
+
+fn python_model(lines: &[&str], node: &Node) -> Option<(Framework, String, String)> {
+    let body = span(lines, node.start_line, node.end_line);
+    let bases = PYTHON_CLASS
+        .as_ref()?
+        .captures(body.lines().find(|line| line.contains("class "))?)?[1]
+        .to_string();
+    let bases: Vec<&str> = bases.split(',').map(str::trim).collect();
+    let sqlmodel =
+        bases.contains(&"SQLModel") && bases.iter().any(|b| b.replace(' ', "") == "table=True");
+    if !sqlmodel && !bases.iter().any(|base| PYTHON_BASES.contains(base)) {
+        return None;
+    }
+    let assigned = ASSIGNED_TABLE
+        .as_ref()
+        .and_then(|re| re.captures(&body))
+        .map(|caps| caps[1].to_string());
+    // A declarative base class, or an abstract model, names no table.
+    let declarative = bases
+        .iter()
+        .any(|b| *b == "Base" || *b == "DeclarativeBase");
+    if (declarative && assigned.is_none()) || body.contains("abstract = True") {
+        return None;
+    }
+    let table = assigned.unwrap_or_else(|| snake_case(&node.name));
+    Some((Framework::Python, node.name.clone(), table))
+}
+
+fn ruby_model(lines: &[&str], node: &Node) -> Option<(Framework, String, String)> {
+    let body = span(lines, node.start_line, node.end_line);
+    RUBY_MODEL.as_ref()?.find(body.lines().next()?)?;
+    if body.contains("self.abstract_class = true") {
+        return None;
+    }
+    let table = ASSIGNED_TABLE
+        .as_ref()
+        .and_then(|re| re.captures(&body))
+        .map_or_else(
+            || pluralize(&snake_case(&node.name)),
+            |caps| caps[1].to_string(),
+        );
+    Some((Framework::ActiveRecord, node.name.clone(), table))
+}
+
+/// The models of a Prisma schema: name, table, and line span.
+fn prisma_models(source: &str) -> Vec<(String, String, i64, i64)> {
+    let (Some(model), Some(map)) = (PRISMA_MODEL.as_ref(), PRISMA_MAP.as_ref()) else {
+        return Vec::new();
+    };
+    model
+        .captures_iter(source)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            let body = &source[whole.end()..];
+            let body = &body[..body.find("\n}").map_or(body.len(), |end| end + 2)];
+            let start = source[..whole.start()].matches('\n').count() + 1;
+            let start = i64::try_from(start).unwrap_or(i64::MAX);
+            let end = start.saturating_add(i64::try_from(body.matches('\n').count()).ok()?);
+            let table = map
+                .captures(body)
+                .map_or_else(|| caps[1].to_string(), |m| m[1].to_string());
+            Some((caps[1].to_string(), table, start, end))
+        })
+        .collect()
+}
+
+/// Replace the `type_of` edges to models with one from each function or
+/// method touching a model. Returns the number of edges added.
+pub fn link_queries(project_root: &Path, conn: &mut Connection) -> Result<usize> {
+    db::delete_edges_to_kind(conn, EdgeKind::TypeOf, NodeKind::Model)?;
+    let models = db::get_nodes_by_kind(conn, NodeKind::Model)?;
+    if models.is_empty() {
+        return Ok(0);
+    }
+    let matchers: Vec<Matcher> = models.iter().filter_map(Matcher::new).collect();
+
+    let mut edges = Vec::new();
+    for file in db::list_files(conn)? {
+        let Ok(source) = std::fs::read_to_string(project_root.join(&file.path)) else {
+            continue;
+        };
+        let lines: Vec<&str> = source.lines().collect();
+        let functions = db::get_nodes_by_file(conn, &file.path, None)?
+            .into_iter()
+            .filter(|node| matches!(node.kind, NodeKind::Function | NodeKind::Method));
+        for function in functions {
+            let body = span(&lines, function.start_line, function.end_line);
+            for matcher in &matchers {
+                if let Some(offset) = matcher.first_use(function.language, &body) {
+                    edges.push(Edge {
+                        source: function.id.clone(),
+                        target: matcher.model_id.clone(),
+                        kind: EdgeKind::TypeOf,
+                        metadata: None,
+                        line: i64::try_from(offset)
+                            .ok()
+                            .map(|offset| function.start_line + offset),
+                        column: Some(0),
+                    });
+                }
+            }
+        }
+    }
+    db::insert_edges(conn, &edges)?;
+    Ok(edges.len())
+}
+
+/// The uses of one model to look for in function bodies.
+struct Matcher {
+    model_id: String,
+    /// Words one of which a body must contain to be scanned.
+    needles: Vec<String>,
+    /// The ORM query API, in the languages that use it.
+    api: Option<(Regex, &'static [Language])>,
+    /// The table named in SQL, in any language.
+    sql: Regex,
+}
+
+impl Matcher {
+    fn new(model: &Node) -> Option<Self> {
+        let table = model.signature.as_deref()?.strip_prefix("table ")?;
+        let name = regex::escape(&model.name);
+        let table_re = regex::escape(table);
+        let sql = Regex::new(&format!(
+            r#"\b(?:FROM|INTO|UPDATE|JOIN|TABLE|from|into|update|join)\s+["`\[]?{table_re}\b"#
+        ))
+        .ok()?;
+        let scripts: &'static [Language] = &[
+            Language::TypeScript,
+            Language::Tsx,
+            Language::JavaScript,
+            Language::Jsx,
+        ];
+        let (api, languages): (String, &'static [Language]) = match model.language {
+            // Diesel's table module and SeaORM's entity module.
+            Language::Rust => (
+                format!(
+                    r"\b{table_re}::(?:table|dsl)\b|\b{module}::(?:Entity|ActiveModel|Column)\b|\b{name}::as_select\(|::<{name}>",
+                    module = regex::escape(&module_name(&model.file_path)),
+                ),
+                &[Language::Rust],
+            ),
+            Language::Python => (
+                format!(
+                    r"\b(?:query|select|insert|update|delete)\(\s*{name}\b|\.get\(\s*{name}\s*,|\b{name}\.(?:query|objects)\b"
+                ),
+                &[Language::Python],
+            ),
+            Language::Ruby => (
+                format!(
+                    r"\b{name}\.(?:where|find|find_by|find_each|find_or_create_by|create|all|first|last|joins|includes|order|pluck|count|update_all|delete_all|destroy_all|new)\b"
+                ),
+                &[Language::Ruby],
+            ),
+            Language::Prisma => (
+                format!(
+                    r"\.{client}\.(?:find|create|update|delete|upsert|count|aggregate|groupBy)\w*\s*\(",
+                    client = regex::escape(&camel_case(&model.name)),
+                ),
+                scripts,
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            model_id: model.id.clone(),
+            needles: vec![
+                table.to_string(),
+                model.name.clone(),
+                camel_case(&model.name),
+                module_name(&model.file_path),
+            ],
+            api: Regex::new(&api).ok().map(|re| (re, languages)),
+            sql,
+        })
+    }
+
+    /// The line offset in `body` of its first use of the model.
+    fn first_use(&self, language: Language, body: &str) -> Option<usize> {
+        if !self
+            .needles
+            .iter()
+            .any(|needle| body.contains(needle.as_str()))
+        {
+            return None;
+        }
+        let api = self
+            .api
+            .as_ref()
+            .filter(|(_, languages)| languages.contains(&language))
+            .map(|(re, _)| re);
+        body.lines().position(|line| {
+            let code = line.trim_start();
+            let comment = ["//", "#", "/*", "*", "--"]
+                .iter()
+                .any(|marker| code.starts_with(marker));
+            // `from users import ...` is Python, not SQL.
+            let import = language == Language::Python && code.starts_with("from ");
+            !comment
+                && (api.is_some_and(|re| re.is_match(line)) || (!import && self.sql.is_match(line)))
+        })
+    }
+}
+
+/// Lines `start..=end` (1-based) of a file.
+fn span(lines: &[&str], start: i64, end: i64) -> String {
+    let start = usize::try_from(start - 1)
+        .unwrap_or_default()
+        .min(lines.len());
+    let end = usize::try_from(end)
+        .unwrap_or_default()
+        .clamp(start, lines.len());
+    lines.get(start..end).unwrap_or_default().join("\n")
+}
+
+/// The module of a Rust file: its stem, or its directory for `mod.rs`.
+fn module_name(file_path: &str) -> String {
+    let path = Path::new(file_path);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+    match stem.as_deref() {
+        Some("mod") => path
+            .parent()
+            .and_then(Path::file_name)
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        _ => stem.unwrap_or_default(),
+    }
+}
+
+fn contains_word(text: &str, word: &str) -> bool {
+    text.match_indices(word).any(|(index, _)| {
+        let before = text[..index].chars().next_back();
+        let after = text[index + word.len()..].chars().next();
+        !before.is_some_and(|c| c.is_alphanumeric() || c == '_')
+            && !after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_uppercase().chain(chars).collect()
+            })
+        })
+        .collect()
+}
+
+fn camel_case(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_lowercase().chain(chars).collect()
+    })
+}
+
+/// The English plural of a `snake_case` name, as `ActiveRecord` forms table
+/// names for the common cases.
+fn pluralize(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix('y')
+        && !stem.ends_with(['a', 'e', 'i', 'o', 'u'])
+    {
+        return format!("{stem}ies");
+    }
+    if name.ends_with(['s', 'x', 'z']) || name.ends_with("ch") || name.ends_with("sh") {
+        return format!("{name}es");
+    }
+    format!("{name}s")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_names() {
+        assert_eq!(snake_case("OrderItem"), "order_item");
+        assert_eq!(pluralize("order_item"), "order_items");
+        assert_eq!(pluralize("category"), "categories");
+        assert_eq!(pluralize("key"), "keys");
+        assert_eq!(pluralize("address"), "addresses");
+        assert_eq!(pascal_case("order_item"), "OrderItem");
+        assert_eq!(camel_case("OrderItem"), "orderItem");
+        assert_eq!(module_name("src/entity/user/mod.rs"), "user");
+    }
+
+    #[test]
+    fn test_prisma_models() {
+        let schema =
+            "model User {\n  id Int @id\n  @@map(\"users\")\n}\n\nmodel Post {\n  id Int @id\n}\n";
+        assert_eq!(
+            prisma_models(schema),
+            [
+                ("User".to_string(), "users".to_string(), 1, 4),
+                ("Post".to_string(), "Post".to_string(), 6, 8),
+            ]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+#![forbid(unsafe_code)]
+
+//! Terminal output conventions for the CLI.
+//!
+//! - `--plain`: no ANSI colour, no spinners, no emoji, ungrouped numbers —
+//!   stable output for screen readers and strict log parsers
+//! - `--no-emoji`: status marks spelled out as words
+//! - Counts grouped per the `LC_ALL` / `LC_NUMERIC` / `LANG` locale
+//! - [`exit`] codes shared by every command, and the [`ErrorEnvelope`]
+//!   printed for failures under `--json-errors`
+
+/// Process exit codes. Every command exits with one of these.
+pub mod exit {
+    /// The command completed.
+    pub const SUCCESS: i32 = 0;
+    /// The operation failed for a reason not covered below (I/O, indexing,
+    /// network, embedding).
+    pub const FAILURE: i32 = 1;
+    /// The project has no `.coraline/` directory; run `coraline init`.
+    pub const NOT_INITIALIZED: i32 = 2;
+    /// The configuration could not be read or written, or is one the command
+    /// refuses to run with.
+    pub const CONFIG: i32 = 3;
+    /// The database could not be opened, read, or written.
+    pub const DATABASE: i32 = 4;
+    /// The command finished, but some of its work failed (files that could
+    /// not be indexed, embeddings not stored).
+    pub const PARTIAL: i32 = 5;
+    /// Invalid arguments (`EX_USAGE` from `sysexits.h`).
+    pub const USAGE: i32 = 64;
+}
+
+/// What kind of failure ended a command; selects the exit code and the
+/// `class` of the `--json-errors` envelope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    Failure,
+    NotInitialized,
+    Config,
+    Database,
+    Partial,
+    Usage,
+}
+
+impl ErrorClass {
+    pub const fn exit_code(self) -> i32 {
+        match self {
+            Self::Failure => exit::FAILURE,
+            Self::NotInitialized => exit::NOT_INITIALIZED,
+            Self::Config => exit::CONFIG,
+            Self::Database => exit::DATABASE,
+            Self::Partial => exit::PARTIAL,
+            Self::Usage => exit::USAGE,
+        }
+    }
+}
+
+/// The object `--json-errors` prints to stderr, on one line, in place of the
+/// plain error message.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ErrorEnvelope {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ErrorBody {
+    pub class: ErrorClass,
+    pub exit_code: i32,
+    pub message: String,
+}
+
+impl ErrorEnvelope {
+    pub fn new(class: ErrorClass, message: impl Into<String>) -> Self {
+        Self {
+            error: ErrorBody {
+                class,
+                exit_code: class.exit_code(),
+                message: message.into(),
+            },
+        }
+    }
+}
+
+/// A status mark printed before a result line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    Ok,
+    Warning,
+}
+
+/// How the CLI renders its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputStyle {
+    pub plain: bool,
+    pub emoji: bool,
+    pub color: bool,
+    /// Thousands separator for counts; `None` leaves numbers ungrouped.
+    pub group_separator: Option<char>,
+}
+
+impl Default for OutputStyle {
+    fn default() -> Self {
+        Self {
+            plain: false,
+            emoji: true,
+            color: true,
+            group_separator: Some(','),
+        }
+    }
+}
+
+impl OutputStyle {
+    /// Build the style from the CLI flags and the environment (`NO_COLOR`,
+    /// `TERM=dumb`, and the locale variables).
+    pub fn from_env(plain: bool, no_emoji: bool) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+            || std::env::var("TERM").is_ok_and(|t| t == "dumb");
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .find_map(|key| std::env::var(key).ok().filter(|v| !v.is_empty()))
+            .unwrap_or_default();
+        Self {
+            plain,
+            emoji: !plain && !no_emoji,
+            color: !plain && !no_color,
+            group_separator: if plain {
+                None
+            } else {
+                grouping_separator(&locale)
+            },
+        }
+    }
+
+    /// Format a count with the locale's thousands separator.
+    pub fn count(&self, n: impl std::fmt::Display) -> String {
+        group_digits(&n.to_string(), self.group_separator)
+    }
+
+    pub const fn mark(&self, mark: Mark) -> &'static str {
+        match (mark, self.emoji) {
+            (Mark::Ok, true) => "✔ ",
+            (Mark::Warning, true) => "⚠ ",
+            (Mark::Ok, false) => "OK:",
+            (Mark::Warning, false) => "WARNING:",
+        }
+    }
+}
+
+/// Thousands separator for a POSIX locale name such as `de_DE.UTF-8`.
+///
+/// `C`, `POSIX`, and an unset locale leave numbers ungrouped.
+pub fn grouping_separator(locale: &str) -> Option<char> {
+    let language = locale
+        .split(['_', '.', '@', '-'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match language.as_str() {
+        "" | "c" | "posix" => None,
+        "de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+        | "sr" | "vi" => Some('.'),
+        "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "nn" | "no" | "fi" | "uk" | "hu"
+        | "bg" | "lt" | "lv" | "et" => Some('\u{a0}'),
+        _ => Some(','),
+    }
+}
+
+/// Insert `separator` between groups of three digits in a formatted
+/// integer (an optional leading `-` is kept in place).
+pub fn group_digits(number: &str, separator: Option<char>) -> String {
+    let (sign, digits) = number
+        .strip_prefix('-')
+        .map_or(("", number), |rest| ("-", rest));
+    let Some(separator) = separator.filter(|_| digits.bytes().all(|b| b.is_ascii_digit())) else {
+        return number.to_string();
+    };
+
+    let mut out = String::with_capacity(number.len() + digits.len() / 3);
+    out.push_str(sign);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(separator);
+        }
+        out.push(digit);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+
+    use super::{ErrorClass, ErrorEnvelope, Mark, OutputStyle, group_digits, grouping_separator};
+
+    #[test]
+    fn group_digits_inserts_separators() {
+        assert_eq!(group_digits("0", Some(',')), "0");
+        assert_eq!(group_digits("999", Some(',')), "999");
+        assert_eq!(group_digits("1000", Some(',')), "1,000");
+        assert_eq!(group_digits("1234567", Some('.')), "1.234.567");
+        assert_eq!(group_digits("-1234", Some(',')), "-1,234");
+        assert_eq!(group_digits("1234567", None), "1234567");
+        assert_eq!(group_digits("12.5", Some(',')), "12.5");
+    }
+
+    #[test]
+    fn grouping_separator_follows_locale_language() {
+        assert_eq!(grouping_separator("en_US.UTF-8"), Some(','));
+        assert_eq!(grouping_separator("de_DE.UTF-8"), Some('.'));
+        assert_eq!(grouping_separator("fr_FR"), Some('\u{a0}'));
+        assert_eq!(grouping_separator("C"), None);
+        assert_eq!(grouping_separator("POSIX"), None);
+        assert_eq!(grouping_separator(""), None);
+    }
+
+    #[test]
+    fn plain_style_is_ascii_and_ungrouped() {
+        let style = OutputStyle::from_env(true, false);
+        assert!(!style.color && !style.emoji);
+        assert_eq!(style.count(1_234_567), "1234567");
+        assert_eq!(style.mark(Mark::Ok), "OK:");
+    }
+
+    #[test]
+    fn error_envelope_carries_class_and_exit_code() {
+        let envelope = ErrorEnvelope::new(ErrorClass::Database, "Failed to open database");
+        let value = serde_json::to_value(&envelope).expect("serializable");
+        assert_eq!(
+            value.pointer("/error/class"),
+            Some(&serde_json::json!("database"))
+        );
+        assert_eq!(
+            value.pointer("/error/exit_code"),
+            Some(&serde_json::json!(4))
+        );
+        assert_eq!(
+            value.pointer("/error/message"),
+            Some(&serde_json::json!("Failed to open database"))
+        );
+        assert_eq!(ErrorClass::NotInitialized.exit_code(), 2);
+        assert_eq!(ErrorClass::Partial.exit_code(), 5);
+    }
+}
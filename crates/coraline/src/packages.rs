@@ -0,0 +1,441 @@
+#![forbid(unsafe_code)]
+
+//! Workspace and monorepo package detection.
+//!
+//! The manifests at the project root declare its packages: the `members`
+//! of a Cargo `[workspace]`, the `packages` of `pnpm-workspace.yaml`, the
+//! `workspaces` of `package.json` (npm and yarn), and the `use` directives
+//! of `go.work`. Each member is named by its own manifest (`[package]
+//! name`, `"name"`, or the `module` of `go.mod`), or by its directory when
+//! the manifest has none.
+//!
+//! Every index and sync tags the files of each package `package:<name>`;
+//! a file in nested packages belongs to the innermost one only. `coraline
+//! query --package` and the `package` parameter of the search and impact
+//! tools filter on these tags.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use globset::{Glob, GlobMatcher};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Manifest file names whose changes can change the packages.
+pub const MANIFEST_FILES: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pnpm-workspace.yaml",
+    "go.work",
+    "go.mod",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageKind {
+    Cargo,
+    Npm,
+    Go,
+}
+
+/// A member of a workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Package {
+    pub name: String,
+    /// Forward-slash directory relative to the project root; `.` for a
+    /// workspace root that is also a package.
+    pub root: String,
+    pub kind: PackageKind,
+}
+
+impl Package {
+    /// Whether the file at `path` (project-relative) is under this package.
+    pub fn contains(&self, path: &str) -> bool {
+        root_contains(&self.root, path)
+    }
+}
+
+/// Whether the package directory `root` contains the file at `path`.
+pub fn root_contains(root: &str, path: &str) -> bool {
+    root == "."
+        || path
+            .strip_prefix(root)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Orders package roots innermost first, the project root last.
+pub fn root_depth(root: &str) -> usize {
+    if root == "." { 0 } else { root.len() }
+}
+
+/// The packages of every workspace manifest at `project_root`, sorted by
+/// root. A directory claimed by several manifests keeps the first.
+pub fn detect(project_root: &Path) -> Vec<Package> {
+    let mut packages = Vec::new();
+    packages.extend(cargo_packages(project_root));
+    packages.extend(npm_packages(project_root));
+    packages.extend(go_packages(project_root));
+    let mut seen = BTreeSet::new();
+    packages.retain(|package| seen.insert(package.root.clone()));
+    packages.sort_by(|a, b| a.root.cmp(&b.root));
+    packages
+}
+
+/// [`detect`], plus the package of the root manifest when no workspace
+/// claims the root: the name a single-package repository is imported by.
+pub fn declared(project_root: &Path) -> Vec<Package> {
+    let mut packages = detect(project_root);
+    if packages.iter().any(|package| package.root == ".") {
+        return packages;
+    }
+    let cargo = read_toml(&project_root.join("Cargo.toml"))
+        .and_then(|manifest| cargo_package_name(&manifest))
+        .map(|name| (name, PackageKind::Cargo));
+    let npm = || {
+        read_json(&project_root.join("package.json"))?
+            .get("name")
+            .and_then(Value::as_str)
+            .map(|name| (name.to_string(), PackageKind::Npm))
+    };
+    let go = || {
+        fs::read_to_string(project_root.join("go.mod"))
+            .ok()?
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("module "))
+            .map(|module| (module.trim().trim_matches('"').to_string(), PackageKind::Go))
+    };
+    if let Some((name, kind)) = cargo.or_else(npm).or_else(go) {
+        packages.push(Package {
+            name,
+            root: ".".to_string(),
+            kind,
+        });
+    }
+    packages
+}
+
+/// The package of the file at `path`: the one with the longest root
+/// containing it.
+pub fn package_for<'a>(packages: &'a [Package], path: &str) -> Option<&'a Package> {
+    packages
+        .iter()
+        .filter(|package| package.contains(path))
+        .max_by_key(|package| root_depth(&package.root))
+}
+
+fn cargo_packages(project_root: &Path) -> Vec<Package> {
+    let Some(manifest) = read_toml(&project_root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Some(workspace) = manifest.get("workspace") else {
+        return Vec::new();
+    };
+    let patterns = toml_strings(workspace.get("members"));
+    let excluded = toml_strings(workspace.get("exclude"));
+    let mut packages: Vec<Package> = expand_members(project_root, &patterns, &excluded)
+        .into_iter()
+        .filter_map(|root| {
+            let manifest = read_toml(&project_root.join(&root).join("Cargo.toml"))?;
+            let name = cargo_package_name(&manifest).unwrap_or_else(|| dir_name(&root));
+            Some(Package {
+                name,
+                root,
+                kind: PackageKind::Cargo,
+            })
+        })
+        .collect();
+    if let Some(name) = cargo_package_name(&manifest) {
+        packages.push(Package {
+            name,
+            root: ".".to_string(),
+            kind: PackageKind::Cargo,
+        });
+    }
+    packages
+}
+
+fn cargo_package_name(manifest: &toml::Table) -> Option<String> {
+    manifest
+        .get("package")?
+        .get("name")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn npm_packages(project_root: &Path) -> Vec<Package> {
+    let root_manifest = read_json(&project_root.join("package.json"));
+    let mut patterns = pnpm_patterns(project_root);
+    if let Some(workspaces) = root_manifest.as_ref().and_then(|m| m.get("workspaces")) {
+        // An array, or yarn's `{ "packages": [...] }`.
+        let list = workspaces.get("packages").unwrap_or(workspaces);
+        patterns.extend(
+            list.as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string),
+        );
+    }
+    if patterns.is_empty() {
+        return Vec::new();
+    }
+    let (excluded, included): (Vec<String>, Vec<String>) =
+        patterns.into_iter().partition(|p| p.starts_with('!'));
+    let excluded: Vec<String> = excluded
+        .iter()
+        .map(|p| p.trim_start_matches('!').to_string())
+        .collect();
+    expand_members(project_root, &included, &excluded)
+        .into_iter()
+        .filter_map(|root| {
+            let manifest = read_json(&project_root.join(&root).join("package.json"))?;
+            let name = manifest
+                .get("name")
+                .and_then(Value::as_str)
+                .map_or_else(|| dir_name(&root), str::to_string);
+            Some(Package {
+                name,
+                root,
+                kind: PackageKind::Npm,
+            })
+        })
+        .collect()
+}
+
+/// The `packages:` list of `pnpm-workspace.yaml`, read line by line.
+fn pnpm_patterns(project_root: &Path) -> Vec<String> {
+    let Ok(text) = fs::read_to_string(project_root.join("pnpm-workspace.yaml")) else {
+        return Vec::new();
+    };
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = trimmed.trim_end_matches(':').trim() == "packages";
+            continue;
+        }
+        if in_packages && let Some(item) = trimmed.strip_prefix('-') {
+            let item = item.split(" #").next().unwrap_or_default().trim();
+            patterns.push(item.trim_matches(['"', '\'']).to_string());
+        }
+    }
+    patterns
+}
+
+fn go_packages(project_root: &Path) -> Vec<Package> {
+    let Ok(text) = fs::read_to_string(project_root.join("go.work")) else {
+        return Vec::new();
+    };
+    let mut dirs = Vec::new();
+    let mut in_block = false;
+    for line in text.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                dirs.push(line.to_string());
+            }
+        } else if let Some(rest) = line.strip_prefix("use") {
+            let rest = rest.trim();
+            if rest == "(" {
+                in_block = true;
+            } else if !rest.is_empty() {
+                dirs.push(rest.to_string());
+            }
+        }
+    }
+    dirs.into_iter()
+        .filter_map(|dir| {
+            let root = normalize(dir.trim_matches('"'));
+            let go_mod = fs::read_to_string(project_root.join(&root).join("go.mod")).ok()?;
+            let name = go_mod
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("module "))
+                .map_or_else(
+                    || dir_name(&root),
+                    |m| m.trim().trim_matches('"').to_string(),
+                );
+            Some(Package {
+                name,
+                root,
+                kind: PackageKind::Go,
+            })
+        })
+        .collect()
+}
+
+/// The directories matching the member `patterns` and none of `excluded`.
+/// A `*` or `?` matches within one path component; `**` is not expanded.
+fn expand_members(project_root: &Path, patterns: &[String], excluded: &[String]) -> Vec<String> {
+    let excluded: Vec<GlobMatcher> = excluded
+        .iter()
+        .filter_map(|p| Glob::new(&normalize(p)).ok())
+        .map(|g| g.compile_matcher())
+        .collect();
+    let mut roots = BTreeSet::new();
+    for pattern in patterns {
+        let pattern = normalize(pattern);
+        if pattern == "." {
+            continue;
+        }
+        let mut dirs = vec![String::new()];
+        for component in pattern.split('/') {
+            let matcher = Glob::new(component).ok().map(|g| g.compile_matcher());
+            let literal = !component.contains(['*', '?', '[']);
+            dirs = dirs
+                .into_iter()
+                .flat_map(|dir| {
+                    let join = |name: &str| {
+                        if dir.is_empty() {
+                            name.to_string()
+                        } else {
+                            format!("{dir}/{name}")
+                        }
+                    };
+                    if literal {
+                        return vec![join(component)];
+                    }
+                    let Ok(entries) = fs::read_dir(project_root.join(&dir)) else {
+                        return Vec::new();
+                    };
+                    entries
+                        .flatten()
+                        .filter(|entry| entry.path().is_dir())
+                        .filter_map(|entry| entry.file_name().into_string().ok())
+                        .filter(|name| !name.starts_with('.') && name != "node_modules")
+                        .filter(|name| matcher.as_ref().is_some_and(|m| m.is_match(name)))
+                        .map(|name| join(&name))
+                        .collect()
+                })
+                .collect();
+        }
+        roots.extend(
+            dirs.into_iter()
+                .filter(|dir| project_root.join(dir).is_dir())
+                .filter(|dir| !excluded.iter().any(|m| m.is_match(dir))),
+        );
+    }
+    roots.into_iter().collect()
+}
+
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/")
+        .trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+fn dir_name(root: &str) -> String {
+    root.rsplit('/').next().unwrap_or(root).to_string()
+}
+
+fn read_toml(path: &Path) -> Option<toml::Table> {
+    fs::read_to_string(path).ok()?.parse().ok()
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn toml_strings(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(toml::Value::as_str)
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+
+    use super::*;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().expect("path has a parent")).expect("create dir");
+        fs::write(path, content).expect("write file");
+    }
+
+    #[test]
+    fn test_detects_cargo_npm_and_go_members() {
+        let temp = tempfile::TempDir::new().expect("temp dir");
+        let root = temp.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/skip\"]\n",
+        );
+        write(
+            root,
+            "crates/api/Cargo.toml",
+            "[package]\nname = \"api-server\"\n",
+        );
+        write(
+            root,
+            "crates/skip/Cargo.toml",
+            "[package]\nname = \"skip\"\n",
+        );
+        write(
+            root,
+            "pnpm-workspace.yaml",
+            "packages:\n  - 'web/*'\n  - \"!web/legacy\"\ncatalog:\n  - nope\n",
+        );
+        write(root, "web/app/package.json", r#"{"name": "@acme/app"}"#);
+        write(root, "web/legacy/package.json", r#"{"name": "legacy"}"#);
+        write(root, "go.work", "go 1.22\n\nuse (\n\t./svc // service\n)\n");
+        write(root, "svc/go.mod", "module example.com/svc\n");
+
+        let packages = detect(root);
+        let names: Vec<(&str, &str)> = packages
+            .iter()
+            .map(|p| (p.root.as_str(), p.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("crates/api", "api-server"),
+                ("svc", "example.com/svc"),
+                ("web/app", "@acme/app"),
+            ]
+        );
+        assert_eq!(
+            package_for(&packages, "crates/api/src/lib.rs").map(|p| p.kind),
+            Some(PackageKind::Cargo)
+        );
+        assert!(package_for(&packages, "crates/apix/lib.rs").is_none());
+    }
+
+    #[test]
+    fn test_innermost_package_wins() {
+        let packages = [
+            Package {
+                name: "root".into(),
+                root: ".".into(),
+                kind: PackageKind::Npm,
+            },
+            Package {
+                name: "outer".into(),
+                root: "packages/outer".into(),
+                kind: PackageKind::Npm,
+            },
+            Package {
+                name: "inner".into(),
+                root: "packages/outer/inner".into(),
+                kind: PackageKind::Npm,
+            },
+        ];
+        let name = |path| package_for(&packages, path).map(|p| p.name.as_str());
+        assert_eq!(name("packages/outer/inner/index.ts"), Some("inner"));
+        assert_eq!(name("packages/outer/index.ts"), Some("outer"));
+        assert_eq!(name("scripts/build.ts"), Some("root"));
+    }
+}
@@ -0,0 +1,197 @@
+#![forbid(unsafe_code)]
+
+//! MCP prompts: parameterised one-click workflows, pre-filled with graph data.
+//!
+//! - `review_impact` — review the blast radius of changing a symbol
+//!   (pre-filled with `coraline_impact` output)
+//! - `plan_change` — plan an implementation task (pre-filled with
+//!   `coraline_context` output)
+//! - `onboarding` — orient a newcomer (pre-filled with the graph summary and
+//!   the project's memories)
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::memory::MemoryManager;
+use crate::resources;
+use crate::tools::{ToolError, ToolRegistry};
+
+/// An entry of `prompts/list`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Prompt {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub arguments: Vec<PromptArgument>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptArgument {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+}
+
+/// The result of `prompts/get`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptResult {
+    pub description: String,
+    pub messages: Vec<PromptMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptMessage {
+    pub role: &'static str,
+    pub content: PromptContent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptContent {
+    pub r#type: &'static str,
+    pub text: String,
+}
+
+/// All built-in prompts.
+pub fn list_prompts() -> Vec<Prompt> {
+    vec![
+        Prompt {
+            name: "review_impact",
+            description: "Review the impact of changing a symbol: callers, references, and what to re-test",
+            arguments: vec![
+                PromptArgument {
+                    name: "symbol",
+                    description: "Name of the symbol you plan to change",
+                    required: true,
+                },
+                PromptArgument {
+                    name: "file",
+                    description: "File path to disambiguate the symbol",
+                    required: false,
+                },
+            ],
+        },
+        Prompt {
+            name: "plan_change",
+            description: "Plan an implementation task using the most relevant code in the graph",
+            arguments: vec![PromptArgument {
+                name: "task",
+                description: "Task or issue description",
+                required: true,
+            }],
+        },
+        Prompt {
+            name: "onboarding",
+            description: "Get oriented in this codebase from the graph summary and project memories",
+            arguments: vec![PromptArgument {
+                name: "focus",
+                description: "Area of the codebase to focus on",
+                required: false,
+            }],
+        },
+    ]
+}
+
+/// Render prompt `name` with `arguments`; `Ok(None)` for an unknown prompt.
+///
+/// Graph data is gathered through `registry`, so a failing lookup (e.g. an
+/// unknown symbol) surfaces as that tool's error.
+pub fn get_prompt(
+    project_root: &Path,
+    registry: &ToolRegistry,
+    name: &str,
+    arguments: &Map<String, Value>,
+) -> Result<Option<PromptResult>, ToolError> {
+    let optional = |key: &str| {
+        arguments
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+    let required = |key: &str| {
+        optional(key).ok_or_else(|| ToolError::invalid_params(format!("Missing argument: {key}")))
+    };
+
+    let (description, text) = match name {
+        "review_impact" => {
+            let symbol = required("symbol")?;
+            let params = json!({ "name": symbol, "file": optional("file") });
+            let impact = registry.execute("coraline_impact", params)?;
+
+            let mut text = format!(
+                "I'm planning to change `{symbol}`. Review the impact of that change.\n\n\
+                 Using the impact analysis below (symbols that call or reference it, \
+                 transitively):\n\
+                 1. List the callers and references most likely to break, and why.\n\
+                 2. Point out public API or cross-module boundaries the change crosses.\n\
+                 3. Suggest which tests to run or add, and a safe order for the change.\n\n"
+            );
+            let impact = serde_json::to_string_pretty(&impact).unwrap_or_default();
+            let _ = write!(text, "## Impact analysis\n\n```json\n{impact}\n```\n");
+            (format!("Impact review for {symbol}"), text)
+        }
+        "plan_change" => {
+            let task = required("task")?;
+            let context = registry.execute("coraline_context", json!({ "task": task }))?;
+            let context = context
+                .get("context")
+                .and_then(Value::as_str)
+                .map_or_else(|| context.to_string(), str::to_string);
+
+            let text = format!(
+                "Plan how to implement this task:\n\n> {task}\n\n\
+                 Using the relevant code below, identify the files and symbols to change, \
+                 the order to change them in, and the risks to watch for. Ask before \
+                 guessing at anything the context does not cover.\n\n\
+                 ## Relevant code (from coraline_context)\n\n{context}\n"
+            );
+            (format!("Implementation plan for: {task}"), text)
+        }
+        "onboarding" => {
+            let mut text = String::from(
+                "Help me get oriented in this codebase. Explain its overall structure, \
+                 the main modules and how they relate, and where a newcomer should start \
+                 reading.",
+            );
+            if let Some(focus) = optional("focus") {
+                let _ = write!(text, " Focus on: {focus}.");
+            }
+            text.push_str("\n\n");
+
+            if let Some(summary) =
+                resources::read_resource(project_root, resources::GRAPH_SUMMARY_URI).map_err(
+                    |e| ToolError::internal_error(format!("Failed to summarise graph: {e}")),
+                )?
+            {
+                text.push_str(&summary.text);
+                text.push('\n');
+            }
+            if let Ok(manager) = MemoryManager::new(project_root) {
+                for memory in manager.list_all_memories().unwrap_or_default() {
+                    let _ = write!(
+                        text,
+                        "\n## Memory: {}/{}\n\n{}\n",
+                        memory.namespace,
+                        memory.name,
+                        memory.content.trim_end()
+                    );
+                }
+            }
+            ("Codebase onboarding".to_string(), text)
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(PromptResult {
+        description,
+        messages: vec![PromptMessage {
+            role: "user",
+            content: PromptContent {
+                r#type: "text",
+                text,
+            },
+        }],
+    }))
+}
@@ -0,0 +1,352 @@
+#![forbid(unsafe_code)]
+
+//! Federation with other indexed projects.
+//!
+//! `coraline remote add` registers another project, by the path of its
+//! root or the URL of its `coraline serve --rest` API, in
+//! `.coraline/remotes.json`. Nothing is copied: `coraline query` searches
+//! each remote's own graph at query time and merges the hits with the local
+//! ones by score.
+//!
+//! A path remote is read like a local project, with every search filter. A
+//! URL remote is asked through `GET /search`, which takes a kind and a
+//! limit; language and exported-only filters are applied to its hits
+//! afterwards, and tag filters skip it.
+//!
+//! [`resolve_imports`] links imports of the local project to the path
+//! remotes publishing the imported package (see [`crate::packages`]): an
+//! npm package name or subpath, a Rust crate path, or a Go module path.
+
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::daemon::{self, QueryHit};
+use crate::db;
+use crate::error::{CoralineError, Result};
+use crate::packages::{self, Package};
+use crate::types::{Language, Node, NodeKind, SearchOptions, SearchResult};
+
+const REMOTES_FILENAME: &str = "remotes.json";
+
+/// Budget of one request to a URL remote.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A registered external project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remote {
+    pub name: String,
+    /// Absolute path of the project root, or the base URL of its REST API.
+    pub location: String,
+}
+
+impl Remote {
+    pub fn is_url(&self) -> bool {
+        is_url(&self.location)
+    }
+}
+
+fn is_url(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+pub fn remotes_path(project_root: &Path) -> PathBuf {
+    project_root.join(".coraline").join(REMOTES_FILENAME)
+}
+
+/// The registered remotes, in the order they were added.
+pub fn list(project_root: &Path) -> Result<Vec<Remote>> {
+    match fs::read_to_string(remotes_path(project_root)) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|e| CoralineError::parse("remotes.json", &e))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save(project_root: &Path, remotes: &[Remote]) -> Result<()> {
+    fs::write(
+        remotes_path(project_root),
+        serde_json::to_string_pretty(remotes)? + "\n",
+    )?;
+    Ok(())
+}
+
+/// Register the project at `location`, an indexed project root or a REST
+/// API base URL, under `name` (by default its package name, or the
+/// directory or host name).
+pub fn add(project_root: &Path, location: &str, name: Option<&str>) -> Result<Remote> {
+    let (location, default_name) = if is_url(location) {
+        let url = location.trim_end_matches('/').to_string();
+        let host = url
+            .split_once("://")
+            .map_or(url.as_str(), |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        (url, host)
+    } else {
+        let root = fs::canonicalize(location)
+            .map_err(|_| CoralineError::NotFound(format!("project '{location}'")))?;
+        if !db::database_path(&root).is_file() {
+            return Err(CoralineError::NotInitialized(root));
+        }
+        if fs::canonicalize(project_root).is_ok_and(|own| own == root) {
+            return Err(CoralineError::InvalidInput(
+                "a project cannot be its own remote".to_string(),
+            ));
+        }
+        let name = packages::declared(&root)
+            .into_iter()
+            .find(|package| package.root == ".")
+            .map(|package| package.name)
+            .or_else(|| root.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_default();
+        (root.to_string_lossy().into_owned(), name)
+    };
+    let name = name.map_or(default_name, str::to_string);
+    if name.is_empty() || name.chars().any(char::is_whitespace) {
+        return Err(CoralineError::InvalidInput(format!(
+            "invalid remote name '{name}'"
+        )));
+    }
+    let mut remotes = list(project_root)?;
+    if let Some(existing) = remotes.iter().find(|r| r.name == name) {
+        return Err(CoralineError::InvalidInput(format!(
+            "remote '{name}' already exists ({})",
+            existing.location
+        )));
+    }
+    let remote = Remote { name, location };
+    remotes.push(remote.clone());
+    save(project_root, &remotes)?;
+    Ok(remote)
+}
+
+/// Unregister the remote called `name`. Returns whether it existed.
+pub fn remove(project_root: &Path, name: &str) -> Result<bool> {
+    let mut remotes = list(project_root)?;
+    let before = remotes.len();
+    remotes.retain(|remote| remote.name != name);
+    if remotes.len() == before {
+        return Ok(false);
+    }
+    save(project_root, &remotes)?;
+    Ok(true)
+}
+
+/// A search hit of the local project or of a remote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FederatedHit {
+    /// The remote the hit came from; `None` for the local project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    #[serde(flatten)]
+    pub result: SearchResult,
+    #[serde(skip)]
+    pub tags: Vec<String>,
+}
+
+/// Search `remote` like [`daemon::query`] searches the local project.
+pub fn query(
+    remote: &Remote,
+    search: &str,
+    options: &SearchOptions,
+    tags: &[String],
+) -> Result<Vec<QueryHit>> {
+    if !remote.is_url() {
+        return Ok(daemon::query(
+            Path::new(&remote.location),
+            search,
+            options,
+            tags,
+        )?);
+    }
+    if !tags.is_empty() {
+        return Err(CoralineError::InvalidInput(
+            "tag filters need a path remote".to_string(),
+        ));
+    }
+    let limit = options
+        .limit
+        .unwrap_or(10)
+        .saturating_add(options.offset.unwrap_or(0));
+    let agent = ureq::Agent::new_with_config(
+        ureq::config::Config::builder()
+            .timeout_global(Some(HTTP_TIMEOUT))
+            .user_agent("coraline-remote")
+            .build(),
+    );
+    let mut request = agent
+        .get(format!("{}/search", remote.location))
+        .query("q", search)
+        .query("limit", limit.to_string());
+    if let Some(kind) = options.kinds.as_ref().and_then(|kinds| kinds.first()) {
+        request = request.query("kind", kind_name(*kind));
+    }
+    let body: serde_json::Value = request
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(|err| std::io::Error::other(format!("{}: {err}", remote.location)))?;
+    let results: Vec<SearchResult> =
+        serde_json::from_value(body.get("results").cloned().unwrap_or_default())?;
+    Ok(results
+        .into_iter()
+        .filter(|r| {
+            options
+                .languages
+                .as_ref()
+                .is_none_or(|languages| languages.contains(&r.node.language))
+        })
+        .filter(|r| options.exported_only != Some(true) || r.node.is_exported)
+        .map(|result| QueryHit {
+            result,
+            tags: Vec::new(),
+        })
+        .collect())
+}
+
+fn kind_name(kind: NodeKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Merge the local hits with each remote's, best score first (the local
+/// hit first on a tie), then page with `offset` and `limit`.
+pub fn merge(
+    local: Vec<QueryHit>,
+    remote: Vec<(String, Vec<QueryHit>)>,
+    offset: usize,
+    limit: usize,
+) -> Vec<FederatedHit> {
+    let hit = |remote: Option<&String>, QueryHit { result, tags }: QueryHit| FederatedHit {
+        remote: remote.cloned(),
+        result,
+        tags,
+    };
+    let mut hits: Vec<FederatedHit> = local.into_iter().map(|h| hit(None, h)).collect();
+    for (name, results) in remote {
+        hits.extend(results.into_iter().map(|h| hit(Some(&name), h)));
+    }
+    hits.sort_by(|a, b| {
+        b.result
+            .score
+            .partial_cmp(&a.result.score)
+            .unwrap_or(Ordering::Equal)
+    });
+    hits.into_iter().skip(offset).take(limit).collect()
+}
+
+/// A local import of a package a remote publishes.
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossRepoImport {
+    pub file_path: String,
+    pub line: i64,
+    /// The imported module path, as written.
+    pub module: String,
+    /// The imported name, when the import names one.
+    pub symbol: Option<String>,
+    pub remote: String,
+    pub package: String,
+    /// The symbol in the remote's graph, when found.
+    pub target: Option<Node>,
+}
+
+/// Link the imports in `conn` (the local graph) to the path remotes whose
+/// packages they name. Packages of the local workspace take precedence,
+/// and URL remotes are skipped.
+pub fn resolve_imports(
+    project_root: &Path,
+    conn: &Connection,
+    remotes: &[Remote],
+) -> Result<Vec<CrossRepoImport>> {
+    let local: Vec<String> = packages::declared(project_root)
+        .into_iter()
+        .map(|package| package.name)
+        .collect();
+    let mut published: Vec<(&Remote, Package, Connection)> = Vec::new();
+    for remote in remotes.iter().filter(|remote| !remote.is_url()) {
+        let root = Path::new(&remote.location);
+        for package in packages::declared(root) {
+            if !local.contains(&package.name) {
+                published.push((remote, package, db::open_database(root)?));
+            }
+        }
+    }
+    // Longest name first, so `@acme/ui-kit` wins over `@acme/ui`.
+    published.sort_by_key(|(_, package, _)| std::cmp::Reverse(package.name.len()));
+    if published.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut links = Vec::new();
+    for import in db::get_nodes_by_kind(conn, NodeKind::Import)? {
+        let signature = import.signature.as_deref().unwrap_or_default();
+        let (module, export) = match signature.split_once("|export=") {
+            Some((module, export)) => (module, Some(export)),
+            None => (signature, None),
+        };
+        let Some((remote, package, remote_conn)) = published
+            .iter()
+            .find(|(_, package, _)| names_package(module, &package.name))
+        else {
+            continue;
+        };
+        // A Go import names a package, not a symbol in it.
+        let symbol = export
+            .map(str::to_string)
+            .or_else(|| (import.name != module).then(|| import.name.clone()))
+            .filter(|name| name != "default" && name != "*")
+            .filter(|_| import.language != Language::Go);
+        let target = match &symbol {
+            Some(name) => exported_symbol(remote_conn, package, name)?,
+            None => None,
+        };
+        links.push(CrossRepoImport {
+            file_path: import.file_path,
+            line: import.start_line,
+            module: module.to_string(),
+            symbol,
+            remote: remote.name.clone(),
+            package: package.name.clone(),
+            target,
+        });
+    }
+    links.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+    Ok(links)
+}
+
+/// Whether `module` is the package `name` or a path inside it. A Rust
+/// crate is named with `-` in its manifest and `_` in code.
+fn names_package(module: &str, name: &str) -> bool {
+    let crate_name = name.replace('-', "_");
+    [name, crate_name.as_str()].into_iter().any(|name| {
+        module
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/') || rest.starts_with("::"))
+    })
+}
+
+/// The definition of `name` in `package`, preferring an exported one.
+fn exported_symbol(conn: &Connection, package: &Package, name: &str) -> Result<Option<Node>> {
+    let mut candidates: Vec<Node> = db::find_nodes_by_name(conn, name)?
+        .into_iter()
+        .filter(|node| {
+            !matches!(
+                node.kind,
+                NodeKind::Import | NodeKind::Export | NodeKind::Todo
+            )
+        })
+        .filter(|node| package.contains(&node.file_path))
+        .collect();
+    candidates.sort_by_key(|node| !node.is_exported);
+    Ok(candidates.into_iter().next())
+}
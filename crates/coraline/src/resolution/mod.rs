@@ -1,117 +1,586 @@
 #![forbid(unsafe_code)]
 
 pub mod frameworks;
+pub mod python_modules;
+pub mod rust_modules;
+pub mod tsconfig;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+use serde::Serialize;
+
+use self::python_modules::PythonModules;
+use self::rust_modules::RustModules;
+use self::tsconfig::PathAliases;
 use crate::db;
-use crate::types::Node;
-use crate::types::{Edge, EdgeKind, NodeKind};
+use crate::types::{Edge, EdgeKind, Language, NodeKind};
+use crate::types::{Node, UnresolvedReference};
 
 #[derive(Debug, Default)]
 pub struct ReferenceResolver;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ResolveResult {
+    /// References examined in the first pass.
     pub scanned: usize,
     pub resolved: usize,
+    /// Unresolved references left in the database.
     pub remaining: usize,
+    /// Passes run; the last resolved nothing unless [`MAX_PASSES`] ran out.
+    pub passes: usize,
+}
+
+/// Passes over the unresolved references before resolution stops short of
+/// a fixed point.
+pub const MAX_PASSES: usize = 5;
+
+/// A name unresolved references share, for `coraline resolve --report`.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnresolvedName {
+    pub name: String,
+    pub kind: EdgeKind,
+    /// Unresolved references to the name.
+    pub references: usize,
+    /// Indexed symbols with the name, not counting imports and exports.
+    /// 0 points at code outside the index; more than one at an ambiguity
+    /// resolution could not break.
+    pub candidates: usize,
+}
+
+/// The `limit` names with the most unresolved references, most first.
+pub fn unresolved_names(
+    conn: &rusqlite::Connection,
+    limit: usize,
+) -> std::io::Result<Vec<UnresolvedName>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT u.reference_name, u.reference_kind, COUNT(*) AS refs,
+                    (SELECT COUNT(*) FROM nodes n
+                     WHERE n.name = u.reference_name AND n.kind NOT IN ('import', 'export'))
+             FROM unresolved_refs u
+             GROUP BY u.reference_name, u.reference_kind
+             ORDER BY refs DESC, u.reference_name, u.reference_kind
+             LIMIT ?1",
+        )
+        .map_err(std::io::Error::other)?;
+    let rows = stmt
+        .query_map([i64::try_from(limit).unwrap_or(i64::MAX)], |row| {
+            let kind: String = row.get(1)?;
+            let references: i64 = row.get(2)?;
+            let candidates: i64 = row.get(3)?;
+            Ok(UnresolvedName {
+                name: row.get(0)?,
+                kind: db::parse_edge_kind(&kind),
+                references: usize::try_from(references).unwrap_or(0),
+                candidates: usize::try_from(candidates).unwrap_or(0),
+            })
+        })
+        .map_err(std::io::Error::other)?;
+    rows.collect::<Result<_, _>>()
+        .map_err(std::io::Error::other)
+}
+
+/// How a resolved edge's target was chosen, stored on the edge as
+/// `metadata.resolution`. Edges the extractor observed directly have none.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resolution {
+    /// The rule that left a single candidate: `import_hint`, `module_path`,
+    /// `same_file`, `same_dir`, `name`, or `framework`, or a call-site tie-break
+    /// (`arity`, `self_receiver`, `receiver_type`, `call_form`).
+    pub method: &'static str,
+    /// Lowest confidence of the rules applied, from 0.0 to 1.0.
+    pub confidence: f64,
 }
 
+impl Resolution {
+    const fn new(method: &'static str, confidence: f64) -> Self {
+        Self { method, confidence }
+    }
+
+    /// `self` narrowed further by `rule`.
+    const fn then(self, rule: Self) -> Self {
+        Self {
+            method: rule.method,
+            confidence: self.confidence.min(rule.confidence),
+        }
+    }
+
+    fn to_metadata(self) -> HashMap<String, serde_json::Value> {
+        HashMap::from([(
+            "resolution".to_string(),
+            serde_json::json!({ "method": self.method, "confidence": self.confidence }),
+        )])
+    }
+}
+
+const IMPORT_HINT: Resolution = Resolution::new("import_hint", 0.95);
+/// Found in the Rust or Python module a path names.
+const MODULE_PATH: Resolution = Resolution::new("module_path", 0.95);
+const SAME_FILE: Resolution = Resolution::new("same_file", 0.9);
+const SAME_DIR: Resolution = Resolution::new("same_dir", 0.75);
+const FRAMEWORK: Resolution = Resolution::new("framework", 0.7);
+/// Matched by name alone, anywhere in the project.
+const NAME_ONLY: Resolution = Resolution::new("name", 0.5);
+const ARITY: Resolution = Resolution::new("arity", 0.9);
+const SELF_RECEIVER: Resolution = Resolution::new("self_receiver", 0.9);
+const RECEIVER_TYPE: Resolution = Resolution::new("receiver_type", 0.8);
+const CALL_FORM: Resolution = Resolution::new("call_form", 0.7);
+
 impl ReferenceResolver {
-    #[allow(clippy::option_if_let_else)]
+    /// Resolve every unresolved reference that now names exactly one node,
+    /// reading `batch` references at a time. Passes repeat until one
+    /// resolves nothing, at most [`MAX_PASSES`] times.
     pub fn resolve_unresolved(
+        conn: &mut rusqlite::Connection,
+        project_root: &Path,
+        batch: usize,
+    ) -> std::io::Result<ResolveResult> {
+        Self::resolve(conn, project_root, None, None, batch)
+    }
+
+    /// [`Self::resolve_unresolved`] for the `limit` oldest unresolved
+    /// references only; later passes retry those left of them.
+    pub fn resolve_first(
         conn: &mut rusqlite::Connection,
         project_root: &Path,
         limit: usize,
+        batch: usize,
     ) -> std::io::Result<ResolveResult> {
-        let unresolved = db::list_unresolved_refs(conn, limit)?;
-        if unresolved.is_empty() {
-            return Ok(ResolveResult {
-                scanned: 0,
-                resolved: 0,
-                remaining: 0,
-            });
-        }
+        Self::resolve(conn, project_root, None, Some(limit), batch)
+    }
 
-        let mut resolved_edges = Vec::new();
-        let mut resolved_ids = Vec::new();
-
-        for row in &unresolved {
-            let reference = &row.reference;
-            let from_node = db::get_node_by_id(conn, &reference.from_node_id)?;
-            let candidates = match reference.reference_kind {
-                EdgeKind::Calls => {
-                    // Prefer extractor-provided candidate IDs for better locality/precision.
-                    let from_ids = reference
-                        .candidates
-                        .as_ref()
-                        .map_or_else(Vec::new, |ids| nodes_from_ids(conn, ids));
-                    if from_ids.is_empty() {
-                        filter_by_call_kind(db::find_nodes_by_name(
-                            conn,
-                            &reference.reference_name,
-                        )?)
-                    } else {
-                        filter_by_call_kind(from_ids)
+    /// [`Self::resolve_unresolved`] for the references a sync added after
+    /// `marks` were taken, and the older ones naming a node it added.
+    pub fn resolve_since(
+        conn: &mut rusqlite::Connection,
+        project_root: &Path,
+        marks: db::RowMarks,
+        batch: usize,
+    ) -> std::io::Result<ResolveResult> {
+        Self::resolve(conn, project_root, Some(marks), None, batch)
+    }
+
+    fn resolve(
+        conn: &mut rusqlite::Connection,
+        project_root: &Path,
+        since: Option<db::RowMarks>,
+        limit: Option<usize>,
+        batch: usize,
+    ) -> std::io::Result<ResolveResult> {
+        let batch = batch.max(1);
+        let mut scanned = 0;
+        let mut resolved = 0;
+        let mut passes = 0;
+        // The last reference the first pass read, when `limit` is set.
+        let mut last_id = None;
+        let mut aliases = PathAliases::new(project_root);
+        let modules = RustModules::load(conn)?;
+        let packages = PythonModules::load(conn)?;
+        while passes < MAX_PASSES {
+            passes += 1;
+            let mut resolved_in_pass = 0;
+            let mut after_id = 0;
+            loop {
+                let wanted = match limit {
+                    Some(limit) if passes == 1 => batch.min(limit - scanned),
+                    _ => batch,
+                };
+                if wanted == 0 {
+                    break;
+                }
+                let mut rows = db::list_unresolved_refs(conn, after_id, since, wanted)?;
+                if passes > 1
+                    && let Some(last_id) = last_id
+                {
+                    rows.retain(|row| row.id <= last_id);
+                }
+                let Some(last) = rows.last() else {
+                    break;
+                };
+                after_id = last.id;
+                if passes == 1 {
+                    scanned += rows.len();
+                    if limit.is_some() {
+                        last_id = Some(after_id);
                     }
                 }
-                _ => db::find_nodes_by_name(conn, &reference.reference_name)?,
-            };
+                resolved_in_pass +=
+                    resolve_rows(conn, project_root, &mut aliases, &modules, &packages, &rows)?;
+            }
+            resolved += resolved_in_pass;
+            if resolved_in_pass == 0 {
+                break;
+            }
+        }
+
+        Ok(ResolveResult {
+            scanned,
+            resolved,
+            remaining: db::count_unresolved_refs(conn)?,
+            passes,
+        })
+    }
+}
 
-            let import_hint = from_node
+/// The nodes named as `reference` names its target, of the kinds such a
+/// target can be.
+fn name_candidates(
+    conn: &rusqlite::Connection,
+    reference: &UnresolvedReference,
+    from_node: Option<&Node>,
+) -> std::io::Result<Vec<Node>> {
+    Ok(match reference.reference_kind {
+        EdgeKind::Calls => {
+            // Prefer extractor-provided candidate IDs for better locality/precision.
+            let from_ids = reference
+                .candidates
                 .as_ref()
-                .and_then(|node| import_match_hint(conn, node, &reference.reference_name).ok())
-                .flatten();
-            let candidates = rank_candidates(
+                .map_or_else(Vec::new, |ids| nodes_from_ids(conn, ids));
+            if from_ids.is_empty() {
+                filter_by_call_kind(db::find_nodes_by_name(conn, &reference.reference_name)?)
+            } else {
+                filter_by_call_kind(from_ids)
+            }
+        }
+        // A route's handler is a function, not the import or export
+        // of one.
+        EdgeKind::References if from_node.is_some_and(|node| node.kind == NodeKind::Route) => {
+            filter_by_call_kind(db::find_nodes_by_name(conn, &reference.reference_name)?)
+        }
+        // A supertype is a type, not a constructor or import of the name.
+        EdgeKind::Extends | EdgeKind::Implements => {
+            db::find_nodes_by_name(conn, &reference.reference_name)?
+                .into_iter()
+                .filter(|node| crate::implementations::is_type_kind(node.kind))
+                .collect()
+        }
+        // A `TODO` comment is named after its marker, not declared.
+        _ => db::find_nodes_by_name(conn, &reference.reference_name)?
+            .into_iter()
+            .filter(|node| node.kind != NodeKind::Todo)
+            .collect(),
+    })
+}
+
+/// Turn each of `rows` that names exactly one node into an edge. Returns
+/// how many were resolved.
+#[allow(clippy::option_if_let_else)]
+fn resolve_rows(
+    conn: &mut rusqlite::Connection,
+    project_root: &Path,
+    aliases: &mut PathAliases,
+    modules: &RustModules,
+    packages: &PythonModules,
+    unresolved: &[db::UnresolvedRefRow],
+) -> std::io::Result<usize> {
+    let mut resolved_edges = Vec::new();
+    let mut resolved_ids = Vec::new();
+    let mut sources = HashMap::new();
+
+    for row in unresolved {
+        let reference = &row.reference;
+        let from_node = db::get_node_by_id(conn, &reference.from_node_id)?;
+        let candidates = name_candidates(conn, reference, from_node.as_ref())?;
+
+        let import_hint = from_node
+            .as_ref()
+            .and_then(|node| import_match_hint(conn, node, &reference.reference_name).ok())
+            .flatten();
+        let in_module = match from_node {
+            Some(ref from) if from.language == Language::Rust => {
+                rust_path_candidates(conn, modules, from, reference, import_hint.as_ref())?
+            }
+            Some(ref from) if from.language == Language::Python => {
+                python_path_candidates(conn, packages, from, reference, import_hint.as_ref())?
+            }
+            _ => Vec::new(),
+        };
+        let (candidates, resolution) = if in_module.is_empty() {
+            rank_candidates(
                 conn,
                 candidates,
                 from_node.as_ref(),
                 import_hint.as_ref(),
+                aliases,
                 &reference.reference_name,
                 reference.reference_kind,
-            )?;
-
-            // If generic resolution found nothing, try framework-specific hints.
-            let candidates = if candidates.is_empty() {
-                if let Some(ref from) = from_node {
-                    framework_fallback(conn, project_root, from, &reference.reference_name)
-                        .unwrap_or_default()
-                } else {
-                    candidates
-                }
+            )?
+        } else {
+            (in_module, MODULE_PATH)
+        };
+
+        // If generic resolution found nothing, try framework-specific hints.
+        let (candidates, resolution) = if candidates.is_empty() {
+            if let Some(ref from) = from_node {
+                let found = framework_fallback(conn, project_root, from, &reference.reference_name)
+                    .unwrap_or_default();
+                (found, FRAMEWORK)
             } else {
-                candidates
-            };
-
-            if let [target] = candidates.as_slice() {
-                resolved_edges.push(Edge {
-                    source: reference.from_node_id.clone(),
-                    target: target.id.clone(),
-                    kind: reference.reference_kind,
-                    metadata: None,
-                    line: Some(reference.line),
-                    column: Some(reference.column),
-                });
-                resolved_ids.push(row.id);
+                (candidates, resolution)
+            }
+        } else {
+            (candidates, resolution)
+        };
+
+        let (candidates, resolution) = match from_node {
+            Some(ref from)
+                if candidates.len() > 1 && reference.reference_kind == EdgeKind::Calls =>
+            {
+                disambiguate_call(
+                    project_root,
+                    from,
+                    reference,
+                    candidates,
+                    resolution,
+                    &mut sources,
+                )
             }
+            _ => (candidates, resolution),
+        };
+
+        if let [target] = candidates.as_slice() {
+            resolved_edges.push(Edge {
+                source: reference.from_node_id.clone(),
+                target: target.id.clone(),
+                kind: reference.reference_kind,
+                metadata: Some(resolution.to_metadata()),
+                line: Some(reference.line),
+                column: Some(reference.column),
+            });
+            resolved_ids.push(row.id);
         }
+    }
+
+    if !resolved_edges.is_empty() {
+        db::insert_edges(conn, &resolved_edges)?;
+    }
+    if !resolved_ids.is_empty() {
+        db::delete_unresolved_refs(conn, &resolved_ids)?;
+    }
+    Ok(resolved_ids.len())
+}
+
+/// Narrow the `candidates` of a call by the call site: its argument count
+/// against each candidate's parameters, then its receiver. A `self` or
+/// `this` receiver picks methods of the caller's class; another receiver
+/// picks methods of a class (or functions of a file) named like it, then
+/// methods over functions; a bare call picks functions, or in languages
+/// where a bare name can call a method, the caller's own class. A rule is
+/// skipped when no candidate or every candidate passes it. Returns the
+/// narrowed candidates and `resolution` refined by the rules applied.
+fn disambiguate_call(
+    project_root: &Path,
+    from: &Node,
+    reference: &UnresolvedReference,
+    mut candidates: Vec<Node>,
+    mut resolution: Resolution,
+    sources: &mut HashMap<String, Option<String>>,
+) -> (Vec<Node>, Resolution) {
+    let mut narrow =
+        |candidates: &mut Vec<Node>, rule: Resolution, keep: &dyn Fn(&Node) -> bool| {
+            let kept = candidates.iter().filter(|n| keep(n)).count();
+            if kept > 0 && kept < candidates.len() {
+                candidates.retain(|n| keep(n));
+                resolution = resolution.then(rule);
+            }
+        };
+
+    if let Some(arity) = reference.arity.and_then(|a| usize::try_from(a).ok()) {
+        let ranges: HashMap<String, Option<(usize, Option<usize>)>> = candidates
+            .iter()
+            .map(|n| (n.id.clone(), parameter_range(project_root, n, sources)))
+            .collect();
+        narrow(&mut candidates, ARITY, &|n| {
+            ranges
+                .get(&n.id)
+                .copied()
+                .flatten()
+                .is_none_or(|(min, max)| min <= arity && max.is_none_or(|max| arity <= max))
+        });
+    }
 
-        if !resolved_edges.is_empty() {
-            db::insert_edges(conn, &resolved_edges)?;
+    let caller_class = container(&from.qualified_name);
+    let receiver = reference
+        .receiver
+        .as_deref()
+        .map(|receiver| receiver.rsplit([':', '.']).next().unwrap_or(receiver));
+    match receiver {
+        Some("self" | "this" | "Self" | "cls") => {
+            narrow(&mut candidates, SELF_RECEIVER, &|n| {
+                container(&n.qualified_name) == caller_class
+            });
+            narrow(&mut candidates, SELF_RECEIVER, &|n| {
+                n.kind == NodeKind::Method
+            });
+        }
+        Some(receiver) => {
+            narrow(&mut candidates, RECEIVER_TYPE, &|n| {
+                let class = container(&n.qualified_name).rsplit("::").next();
+                let file = Path::new(&n.file_path).file_stem().and_then(|s| s.to_str());
+                class.is_some_and(|c| same_identifier(c, receiver))
+                    || file.is_some_and(|f| same_identifier(f, receiver))
+            });
+            narrow(&mut candidates, CALL_FORM, &|n| n.kind == NodeKind::Method);
+        }
+        None if implicit_receiver(from.language) => {
+            narrow(&mut candidates, CALL_FORM, &|n| {
+                container(&n.qualified_name) == caller_class
+            });
         }
-        if !resolved_ids.is_empty() {
-            db::delete_unresolved_refs(conn, &resolved_ids)?;
+        None => {
+            narrow(&mut candidates, CALL_FORM, &|n| {
+                n.kind == NodeKind::Function
+            });
         }
+    }
 
-        let remaining = unresolved.len().saturating_sub(resolved_ids.len());
-        Ok(ResolveResult {
-            scanned: unresolved.len(),
-            resolved: resolved_ids.len(),
-            remaining,
-        })
+    (candidates, resolution)
+}
+
+/// The qualified name of the class or module a symbol is declared in.
+fn container(qualified_name: &str) -> &str {
+    qualified_name
+        .rsplit_once("::")
+        .map_or("", |(container, _)| container)
+}
+
+/// Whether a receiver like `user_service` names `UserService`.
+fn same_identifier(name: &str, receiver: &str) -> bool {
+    let normalise = |s: &str| {
+        s.chars()
+            .filter(|c| *c != '_')
+            .map(|c| c.to_ascii_lowercase())
+            .collect::<String>()
+    };
+    normalise(name) == normalise(receiver)
+}
+
+/// Languages where a bare call inside a class can name one of its methods.
+const fn implicit_receiver(language: Language) -> bool {
+    matches!(
+        language,
+        Language::Java
+            | Language::CSharp
+            | Language::Cpp
+            | Language::Kotlin
+            | Language::Scala
+            | Language::Swift
+            | Language::Dart
+            | Language::Groovy
+            | Language::Ruby
+    )
+}
+
+/// The fewest and most arguments `node` accepts (`None` for no limit),
+/// read from the parameter list after its name in the source. `None` when
+/// the source cannot be read or has no parameter list.
+fn parameter_range(
+    project_root: &Path,
+    node: &Node,
+    sources: &mut HashMap<String, Option<String>>,
+) -> Option<(usize, Option<usize>)> {
+    let source = sources
+        .entry(node.file_path.clone())
+        .or_insert_with(|| std::fs::read_to_string(project_root.join(&node.file_path)).ok())
+        .as_deref()?;
+    let start = usize::try_from(node.start_line - 1).ok()?;
+    let count = usize::try_from(node.end_line - node.start_line + 1).ok()?;
+    let text = source
+        .lines()
+        .skip(start)
+        .take(count)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let after_name = &text[text.find(&node.name)? + node.name.len()..];
+    let list = parameter_list(&after_name[after_name.find('(')?..])?;
+
+    let mut min = 0;
+    let mut max = Some(0);
+    for (index, parameter) in split_parameters(list).into_iter().enumerate() {
+        let binding = parameter.split(':').next().unwrap_or(parameter).trim();
+        let bare = binding
+            .rsplit(' ')
+            .next()
+            .unwrap_or(binding)
+            .trim_start_matches('&');
+        if (index == 0 && matches!(bare, "self" | "this" | "cls")) || parameter == "void" {
+            continue;
+        }
+        if parameter.contains("...")
+            || parameter.starts_with('*')
+            || parameter.starts_with("params ")
+        {
+            max = None;
+        } else if has_default(parameter) || binding.ends_with('?') {
+            max = max.map(|m| m + 1);
+        } else {
+            min += 1;
+            max = max.map(|m| m + 1);
+        }
     }
+    Some((min, max))
+}
+
+/// The text inside the parenthesised list `text` starts with.
+fn parameter_list(text: &str) -> Option<&str> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[1..index]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `list` split at its top-level commas, each part trimmed, empty parts
+/// dropped.
+fn split_parameters(list: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0_i32;
+    let mut quote = None;
+    let mut start = 0;
+    let mut previous = ' ';
+    for (index, c) in list.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q && previous != '\\' => quote = None,
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '(' | '[' | '{' | '<') => depth += 1,
+            (None, '>') if matches!(previous, '=' | '-') => {}
+            (None, ')' | ']' | '}' | '>') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.extend(list.get(start..index).map(str::trim));
+                start = index + 1;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    parts.extend(list.get(start..).map(str::trim));
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Whether a parameter declares a default value (`b = 2`, not `f: () => T`).
+fn has_default(parameter: &str) -> bool {
+    let chars: Vec<char> = parameter.chars().collect();
+    chars.iter().enumerate().any(|(index, c)| {
+        *c == '='
+            && chars
+                .get(index + 1)
+                .is_none_or(|next| !matches!(next, '>' | '='))
+            && index
+                .checked_sub(1)
+                .and_then(|i| chars.get(i))
+                .is_none_or(|prev| !matches!(prev, '=' | '!' | '<' | '>'))
+    })
 }
 
 fn nodes_from_ids(conn: &rusqlite::Connection, ids: &[String]) -> Vec<Node> {
@@ -180,22 +649,25 @@ fn filter_by_call_kind(nodes: Vec<Node>) -> Vec<Node> {
     filtered
 }
 
+/// The best-placed of `nodes` for a reference from `from_node`, with the
+/// rule that placed them.
 fn rank_candidates(
     conn: &rusqlite::Connection,
     nodes: Vec<Node>,
     from_node: Option<&Node>,
     import_hint: Option<&ImportHint>,
+    aliases: &mut PathAliases,
     symbol_name: &str,
     reference_kind: EdgeKind,
-) -> std::io::Result<Vec<Node>> {
+) -> std::io::Result<(Vec<Node>, Resolution)> {
     let Some(from_node) = from_node else {
-        return Ok(nodes);
+        return Ok((nodes, NAME_ONLY));
     };
 
     if let Some(hint) = import_hint {
         let export_name = hint.export_name.as_deref().unwrap_or(symbol_name);
         if let Some(exports) = export_candidates(conn, &hint.module_path, export_name)? {
-            return Ok(exports);
+            return Ok((exports, IMPORT_HINT));
         }
     }
 
@@ -205,8 +677,13 @@ fn rank_candidates(
     let mut same_dir = Vec::new();
     let mut others = Vec::new();
 
+    let scripts = is_script(from_node.language);
     for node in nodes {
-        if import_hint.is_some_and(|hint| matches_import_hint(&node.file_path, &hint.module_path)) {
+        if import_hint.is_some_and(|hint| {
+            matches_import_hint(&node.file_path, &hint.module_path)
+                || (scripts
+                    && aliases.matches(&from_node.file_path, &hint.module_path, &node.file_path))
+        }) {
             import_matches.push(node);
             continue;
         }
@@ -220,18 +697,150 @@ fn rank_candidates(
     }
 
     if !import_matches.is_empty() {
-        Ok(import_matches)
+        Ok((import_matches, IMPORT_HINT))
     } else if !same_file.is_empty() {
-        Ok(same_file)
+        Ok((same_file, SAME_FILE))
     } else if !same_dir.is_empty() {
-        Ok(same_dir)
+        Ok((same_dir, SAME_DIR))
     } else if reference_kind == EdgeKind::Calls {
         // Avoid low-confidence global-name fallback for call edges because
         // it causes noisy cross-project links in mixed active/legacy workspaces.
-        Ok(Vec::new())
+        Ok((Vec::new(), NAME_ONLY))
+    } else {
+        Ok((others, NAME_ONLY))
+    }
+}
+
+/// The symbols a Rust reference may name inside the module its path
+/// names: the receiver path of a call (`crate::util::helper()`,
+/// `db::query::run()`), with a leading imported name expanded, or else the
+/// `use` path that imported the name. Empty without a path, or when
+/// nothing with the name lives in that module.
+fn rust_path_candidates(
+    conn: &rusqlite::Connection,
+    modules: &RustModules,
+    from: &Node,
+    reference: &UnresolvedReference,
+    import_hint: Option<&ImportHint>,
+) -> std::io::Result<Vec<Node>> {
+    let mut path: Vec<String> = match (reference.receiver.as_deref(), import_hint) {
+        // `self.method()` cannot be told from `self::function()`.
+        (Some("self"), _) | (None, None) => return Ok(Vec::new()),
+        (Some(receiver), _) => receiver.split("::").map(str::to_string).collect(),
+        (None, Some(hint)) => {
+            let mut path: Vec<String> = hint.module_path.split("::").map(str::to_string).collect();
+            if path.pop().as_deref() != Some(reference.reference_name.as_str()) {
+                return Ok(Vec::new());
+            }
+            path
+        }
+    };
+    if reference.receiver.is_some()
+        && let Some(first) = path.first()
+        && !matches!(first.as_str(), "crate" | "self" | "super")
+        && let Some(hint) = import_match_hint(conn, from, first)?
+    {
+        let mut expanded: Vec<String> = hint.module_path.split("::").map(str::to_string).collect();
+        expanded.extend(path.drain(1..));
+        path = expanded;
+    }
+
+    let segments: Vec<&str> = path.iter().map(String::as_str).collect();
+    let targets = modules.resolve(&from.file_path, &segments);
+    if targets.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(
+        named_targets(conn, &reference.reference_name, reference.reference_kind)?
+            .into_iter()
+            .filter(|node| targets.iter().any(|target| target.contains(node)))
+            .collect(),
+    )
+}
+
+/// The module-level symbols a Python reference may name in the module its
+/// path names: a call's receiver bound by an import (`u.clean()` after
+/// `import app.util as u`, `mailer.send()` after `from app import
+/// mailer`), or else the module the name was imported from, by the name
+/// it exports there (`tidy()` after `from ..util import clean as tidy`).
+/// Empty without an import, or when nothing with the name lives there.
+fn python_path_candidates(
+    conn: &rusqlite::Connection,
+    packages: &PythonModules,
+    from: &Node,
+    reference: &UnresolvedReference,
+    import_hint: Option<&ImportHint>,
+) -> std::io::Result<Vec<Node>> {
+    let (module, name) = if let Some(receiver) = reference.receiver.as_deref() {
+        // The longest leading part of the receiver an import binds.
+        let segments: Vec<&str> = receiver.split('.').collect();
+        let mut bound = None;
+        for len in (1..=segments.len()).rev() {
+            let prefix = segments.get(..len).unwrap_or_default().join(".");
+            if let Some(hint) = import_match_hint(conn, from, &prefix)? {
+                bound = Some((hint, len));
+                break;
+            }
+        }
+        let Some((hint, len)) = bound else {
+            return Ok(Vec::new());
+        };
+        let mut module = match &hint.export_name {
+            Some(export) => python_modules::submodule(&hint.module_path, export),
+            None => hint.module_path,
+        };
+        for segment in segments.get(len..).unwrap_or_default() {
+            module = python_modules::submodule(&module, segment);
+        }
+        (module, reference.reference_name.clone())
+    } else if let Some(hint) = import_hint {
+        let name = hint
+            .export_name
+            .clone()
+            .unwrap_or_else(|| reference.reference_name.clone());
+        (hint.module_path.clone(), name)
     } else {
-        Ok(others)
+        return Ok(Vec::new());
+    };
+
+    let files = packages.resolve(&from.file_path, &module);
+    if files.is_empty() {
+        return Ok(Vec::new());
     }
+    Ok(named_targets(conn, &name, reference.reference_kind)?
+        .into_iter()
+        .filter(|node| {
+            files.contains(&node.file_path)
+                && node.qualified_name == format!("{}::{}", node.file_path, node.name)
+        })
+        .collect())
+}
+
+/// The nodes named `name` a reference of `kind` may target: functions and
+/// methods for a call, anything but imports, exports, and TODO comments
+/// otherwise.
+fn named_targets(
+    conn: &rusqlite::Connection,
+    name: &str,
+    kind: EdgeKind,
+) -> std::io::Result<Vec<Node>> {
+    let named = db::find_nodes_by_name(conn, name)?;
+    Ok(if kind == EdgeKind::Calls {
+        filter_by_call_kind(named)
+    } else {
+        named
+            .into_iter()
+            .filter(|n| !matches!(n.kind, NodeKind::Import | NodeKind::Export | NodeKind::Todo))
+            .collect()
+    })
+}
+
+/// Languages whose import specifiers [`PathAliases`] understands.
+const fn is_script(language: Language) -> bool {
+    matches!(
+        language,
+        Language::TypeScript | Language::Tsx | Language::JavaScript | Language::Jsx
+    )
 }
 
 fn import_match_hint(
@@ -0,0 +1,99 @@
+#![forbid(unsafe_code)]
+
+//! Python module paths mapped onto files.
+//!
+//! `a.b` names the module `a/b.py` or the package `a/b/__init__.py`. The
+//! source roots a project puts on `sys.path` are not known, so an absolute
+//! path matches under any directory; a relative one (`.b`, `..a`) resolves
+//! against the package of the importing file.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+/// The indexed Python files.
+#[derive(Debug, Default)]
+pub struct PythonModules {
+    files: HashSet<String>,
+}
+
+impl PythonModules {
+    pub fn load(conn: &rusqlite::Connection) -> std::io::Result<Self> {
+        let mut stmt = conn
+            .prepare("SELECT DISTINCT file_path FROM nodes WHERE language = 'python'")
+            .map_err(std::io::Error::other)?;
+        let files = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(std::io::Error::other)?
+            .collect::<Result<_, _>>()
+            .map_err(std::io::Error::other)?;
+        Ok(Self { files })
+    }
+
+    /// The files `module` imported from `from_file` may name, sorted.
+    pub fn resolve(&self, from_file: &str, module: &str) -> Vec<String> {
+        let relative = module.chars().take_while(|c| *c == '.').count();
+        let segments: Vec<&str> = module[relative..]
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let stem = segments.join("/");
+
+        let mut found: Vec<String> = if relative == 0 {
+            if stem.is_empty() {
+                return Vec::new();
+            }
+            let module_file = format!("{stem}.py");
+            let package_file = format!("{stem}/__init__.py");
+            self.files
+                .iter()
+                .filter(|file| {
+                    [&module_file, &package_file].iter().any(|target| {
+                        file.strip_suffix(target.as_str())
+                            .is_some_and(|dir| dir.is_empty() || dir.ends_with('/'))
+                    })
+                })
+                .cloned()
+                .collect()
+        } else {
+            let mut package = Path::new(from_file).parent();
+            for _ in 1..relative {
+                package = package.and_then(Path::parent);
+            }
+            let Some(package) = package else {
+                return Vec::new();
+            };
+            let base = package.to_string_lossy().replace('\\', "/");
+            let join = |tail: &str| {
+                if base.is_empty() {
+                    tail.to_string()
+                } else {
+                    format!("{base}/{tail}")
+                }
+            };
+            let targets = if stem.is_empty() {
+                vec![join("__init__.py")]
+            } else {
+                vec![
+                    join(&format!("{stem}.py")),
+                    join(&format!("{stem}/__init__.py")),
+                ]
+            };
+            targets
+                .into_iter()
+                .filter(|target| self.files.contains(target))
+                .collect()
+        };
+        found.sort();
+        found
+    }
+}
+
+/// `module` followed by `name`, as `from module import name` spells the
+/// submodule: `a` and `b` give `a.b`, `..` and `b` give `..b`.
+pub fn submodule(module: &str, name: &str) -> String {
+    if module.ends_with('.') {
+        format!("{module}{name}")
+    } else {
+        format!("{module}.{name}")
+    }
+}
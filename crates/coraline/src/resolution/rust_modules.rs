@@ -0,0 +1,203 @@
+#![forbid(unsafe_code)]
+
+//! Rust module paths mapped onto files.
+//!
+//! The module tree is rebuilt from the `module` nodes `mod name;` leaves
+//! behind, whose signature is the file the declaration loads, starting at
+//! each crate root (`lib.rs`, `main.rs`, `build.rs`, and the files of
+//! `bin/`, `examples/`, `tests/`, and `benches/`). `crate::`, `self::`, and
+//! `super::` paths then resolve through it; a bare path is tried against
+//! the current module and the crate root. Inline `mod name { .. }` blocks
+//! are followed through the qualified names of the symbols inside them.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+
+use crate::types::Node;
+
+/// Directories whose files are each a crate root.
+const ROOT_DIRS: [&str; 4] = ["bin", "examples", "tests", "benches"];
+
+/// Files that are crate roots wherever they are.
+const ROOT_FILES: [&str; 3] = ["lib.rs", "main.rs", "build.rs"];
+
+/// Whether `mod name;` in `file_path` loads a file beside it, as in a
+/// crate root or a `mod.rs`, rather than one under a directory named
+/// after it.
+pub fn owns_directory(file_path: &str) -> bool {
+    let path = Path::new(file_path);
+    path.file_name().and_then(|n| n.to_str()) == Some("mod.rs") || is_crate_root(path)
+}
+
+fn is_crate_root(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let dir = path
+        .parent()
+        .and_then(Path::file_name)
+        .and_then(|n| n.to_str())
+        .unwrap_or("");
+    ROOT_FILES.contains(&name) || ROOT_DIRS.contains(&dir)
+}
+
+/// A module: a file, and the inline modules within it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleTarget {
+    pub file: String,
+    pub inline: Vec<String>,
+}
+
+impl ModuleTarget {
+    /// Whether `node` is declared directly in this module.
+    pub fn contains(&self, node: &Node) -> bool {
+        node.file_path == self.file && inline_path(node) == self.inline
+    }
+}
+
+/// The module tree of every crate in the index.
+#[derive(Debug, Default)]
+pub struct RustModules {
+    /// Crate root and module path of each file in the tree.
+    files: HashMap<String, (String, Vec<String>)>,
+    /// File of each module, by crate root and module path.
+    modules: HashMap<(String, Vec<String>), String>,
+}
+
+impl RustModules {
+    /// Build the tree from the indexed Rust modules.
+    pub fn load(conn: &rusqlite::Connection) -> std::io::Result<Self> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT file_path, name, qualified_name, signature FROM nodes
+                 WHERE language = 'rust' AND kind = 'module'",
+            )
+            .map_err(std::io::Error::other)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })
+            .map_err(std::io::Error::other)?;
+        let mut declared: HashMap<String, Vec<(Vec<String>, String)>> = HashMap::new();
+        for row in rows {
+            let (file, name, qualified_name, target) = row.map_err(std::io::Error::other)?;
+            let Some(target) = target else {
+                continue;
+            };
+            let mut path = inline_segments(&file, &qualified_name);
+            path.push(name);
+            declared.entry(file).or_default().push((path, target));
+        }
+
+        let mut stmt = conn
+            .prepare("SELECT DISTINCT file_path FROM nodes WHERE language = 'rust'")
+            .map_err(std::io::Error::other)?;
+        let files = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(std::io::Error::other)?;
+        let mut tree = Self::default();
+        let mut queue = VecDeque::new();
+        for file in files {
+            let file = file.map_err(std::io::Error::other)?;
+            if is_crate_root(Path::new(&file)) {
+                tree.insert(&file, &file, Vec::new());
+                queue.push_back(file);
+            }
+        }
+        while let Some(file) = queue.pop_front() {
+            let Some((root, path)) = tree.files.get(&file).cloned() else {
+                continue;
+            };
+            for (relative, target) in declared.get(&file).into_iter().flatten() {
+                if tree.files.contains_key(target) {
+                    continue;
+                }
+                let mut module = path.clone();
+                module.extend(relative.iter().cloned());
+                tree.insert(target, &root, module);
+                queue.push_back(target.clone());
+            }
+        }
+        Ok(tree)
+    }
+
+    fn insert(&mut self, file: &str, root: &str, path: Vec<String>) {
+        self.modules
+            .insert((root.to_string(), path.clone()), file.to_string());
+        self.files
+            .insert(file.to_string(), (root.to_string(), path));
+    }
+
+    /// The modules `path` (the segments before a symbol's name, such as
+    /// `["crate", "db"]`) may name from `from_file`.
+    pub fn resolve(&self, from_file: &str, path: &[&str]) -> Vec<ModuleTarget> {
+        let Some((root, here)) = self.files.get(from_file) else {
+            return Vec::new();
+        };
+        let owned = |segments: &[&str]| segments.iter().map(|s| (*s).to_string()).collect();
+        let mut modules: Vec<Vec<String>> = Vec::new();
+        let rest = path.get(1..).unwrap_or_default();
+        match path.first().copied() {
+            None => modules.push(here.clone()),
+            Some("crate") => modules.push(owned(rest)),
+            Some("self") => {
+                let mut module = here.clone();
+                module.extend(owned(rest));
+                modules.push(module);
+            }
+            Some("super") => {
+                let ups = path.iter().take_while(|s| **s == "super").count();
+                if let Some(parent) = here.len().checked_sub(ups).and_then(|len| here.get(..len)) {
+                    let mut module = parent.to_vec();
+                    module.extend(owned(path.get(ups..).unwrap_or_default()));
+                    modules.push(module);
+                }
+            }
+            Some(_) => {
+                let mut child = here.clone();
+                child.extend(owned(path));
+                modules.push(child);
+                if !here.is_empty() {
+                    modules.push(owned(path));
+                }
+            }
+        }
+        modules
+            .into_iter()
+            .filter_map(|module| self.target(root, &module))
+            .collect()
+    }
+
+    /// The file declaring `module` of the crate at `root`, and the inline
+    /// modules to follow within it.
+    fn target(&self, root: &str, module: &[String]) -> Option<ModuleTarget> {
+        (0..=module.len()).rev().find_map(|split| {
+            let (declared, inline) = module.split_at_checked(split)?;
+            let file = self.modules.get(&(root.to_string(), declared.to_vec()))?;
+            Some(ModuleTarget {
+                file: file.clone(),
+                inline: inline.to_vec(),
+            })
+        })
+    }
+}
+
+/// The inline modules (and other containers) `node` is declared in.
+fn inline_path(node: &Node) -> Vec<String> {
+    inline_segments(&node.file_path, &node.qualified_name)
+}
+
+fn inline_segments(file_path: &str, qualified_name: &str) -> Vec<String> {
+    let Some(rest) = qualified_name
+        .strip_prefix(file_path)
+        .and_then(|rest| rest.strip_prefix("::"))
+    else {
+        return Vec::new();
+    };
+    let mut segments: Vec<String> = rest.split("::").map(str::to_string).collect();
+    segments.pop();
+    segments
+}
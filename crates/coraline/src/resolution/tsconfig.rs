@@ -0,0 +1,298 @@
+#![forbid(unsafe_code)]
+
+//! TypeScript and JavaScript module specifiers mapped onto project files.
+//!
+//! Relative specifiers (`./math`, `../lib/util.js`) resolve against the
+//! importing file. Others go through the `compilerOptions.paths` and
+//! `baseUrl` of the nearest `tsconfig.json` or `jsconfig.json` above the
+//! importing file, following relative `extends` for options a config does
+//! not set itself. Configs are JSON with comments and trailing commas.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use serde_json::Value;
+
+/// Config files looked for in each directory, in order.
+const CONFIG_FILES: [&str; 2] = ["tsconfig.json", "jsconfig.json"];
+
+/// `extends` links followed before giving up on a cycle.
+const MAX_EXTENDS: usize = 8;
+
+/// Extensions a specifier may spell out (`./math.js` for `math.ts`).
+const SCRIPT_EXTENSIONS: [&str; 8] = ["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
+
+/// Path mappings of one config, with paths relative to the project root.
+#[derive(Debug, Clone, Default)]
+struct PathMappings {
+    /// Directory non-relative specifiers resolve against; `None` without
+    /// a `baseUrl`.
+    base_url: Option<PathBuf>,
+    /// Directory `paths` targets are relative to.
+    paths_base: PathBuf,
+    /// `paths` patterns and their targets, in file order.
+    paths: Vec<(String, Vec<String>)>,
+}
+
+/// Resolves module specifiers for the files of one project, reading each
+/// directory's config at most once.
+#[derive(Debug)]
+pub struct PathAliases {
+    project_root: PathBuf,
+    /// Mappings in effect for a directory, keyed by its project-relative
+    /// path; `None` where no config applies.
+    by_dir: HashMap<PathBuf, Option<PathMappings>>,
+}
+
+impl PathAliases {
+    pub fn new(project_root: &Path) -> Self {
+        Self {
+            project_root: project_root.to_path_buf(),
+            by_dir: HashMap::new(),
+        }
+    }
+
+    /// Project-relative paths, without extension, that `specifier`
+    /// imported from `from_file` may name. Empty for a package import or a
+    /// path outside the project.
+    pub fn resolve(&mut self, from_file: &str, specifier: &str) -> Vec<String> {
+        let from_dir = Path::new(from_file)
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .to_path_buf();
+        if specifier.starts_with("./") || specifier.starts_with("../") {
+            return normalise(&from_dir.join(specifier)).into_iter().collect();
+        }
+
+        let Some(mappings) = self.mappings_for(&from_dir) else {
+            return Vec::new();
+        };
+        let mut targets = Vec::new();
+        for (pattern, replacements) in best_patterns(&mappings.paths, specifier) {
+            let star = match pattern.split_once('*') {
+                Some((prefix, suffix)) => &specifier[prefix.len()..specifier.len() - suffix.len()],
+                None => "",
+            };
+            for replacement in replacements {
+                let target = replacement.replacen('*', star, 1);
+                targets.extend(normalise(&mappings.paths_base.join(target)));
+            }
+        }
+        if let Some(base_url) = &mappings.base_url {
+            targets.extend(normalise(&base_url.join(specifier)));
+        }
+        targets
+    }
+
+    /// Whether `file_path` is a file `specifier` imported from `from_file`
+    /// may name, as `math.ts` or `math/index.ts`.
+    pub fn matches(&mut self, from_file: &str, specifier: &str, file_path: &str) -> bool {
+        let file = strip_script_extension(file_path);
+        self.resolve(from_file, specifier).iter().any(|target| {
+            file == target
+                || file
+                    .strip_prefix(target.as_str())
+                    .is_some_and(|rest| rest == "/index")
+        })
+    }
+
+    fn mappings_for(&mut self, dir: &Path) -> Option<PathMappings> {
+        if let Some(found) = self.by_dir.get(dir) {
+            return found.clone();
+        }
+        let found = CONFIG_FILES
+            .iter()
+            .map(|name| self.project_root.join(dir).join(name))
+            .find(|path| path.is_file())
+            .and_then(|path| load(&self.project_root, &path, 0))
+            .or_else(|| dir.parent().and_then(|parent| self.mappings_for(parent)));
+        self.by_dir.insert(dir.to_path_buf(), found.clone());
+        found
+    }
+}
+
+/// The mappings of the config at `path`, with what it leaves unset taken
+/// from the config it `extends`.
+fn load(project_root: &Path, path: &Path, depth: usize) -> Option<PathMappings> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let config: Value = serde_json::from_str(&strip_jsonc(&text)).ok()?;
+    let dir = path
+        .parent()?
+        .strip_prefix(project_root)
+        .ok()?
+        .to_path_buf();
+    let options = config.get("compilerOptions");
+
+    let parent = config
+        .get("extends")
+        .and_then(Value::as_str)
+        .filter(|extends| extends.starts_with('.') && depth < MAX_EXTENDS)
+        .and_then(|extends| {
+            let mut parent = project_root.join(&dir).join(extends);
+            if parent.extension().is_none() {
+                parent.set_extension("json");
+            }
+            load(project_root, &parent, depth + 1)
+        });
+
+    let base_url = options
+        .and_then(|o| o.get("baseUrl"))
+        .and_then(Value::as_str)
+        .map(|base| dir.join(base));
+    let paths: Option<Vec<(String, Vec<String>)>> = options
+        .and_then(|o| o.get("paths"))
+        .and_then(Value::as_object)
+        .map(|paths| {
+            paths
+                .iter()
+                .map(|(pattern, targets)| {
+                    let targets = targets
+                        .as_array()
+                        .map(|targets| {
+                            targets
+                                .iter()
+                                .filter_map(Value::as_str)
+                                .map(str::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    (pattern.clone(), targets)
+                })
+                .collect()
+        });
+
+    if base_url.is_none() && paths.is_none() {
+        return parent;
+    }
+    let inherited = parent.unwrap_or_default();
+    let base_url = base_url.or(inherited.base_url);
+    Some(match paths {
+        Some(paths) => PathMappings {
+            paths_base: base_url.clone().unwrap_or(dir),
+            base_url,
+            paths,
+        },
+        None => PathMappings {
+            base_url,
+            ..inherited
+        },
+    })
+}
+
+/// The patterns of `paths` matching `specifier`: an exact pattern, or the
+/// `*` patterns with the longest prefix, as TypeScript picks them.
+fn best_patterns<'a>(
+    paths: &'a [(String, Vec<String>)],
+    specifier: &str,
+) -> Vec<(&'a str, &'a [String])> {
+    if let Some((pattern, targets)) = paths.iter().find(|(pattern, _)| pattern == specifier) {
+        return vec![(pattern.as_str(), targets.as_slice())];
+    }
+    let matching: Vec<(&str, &[String], usize)> = paths
+        .iter()
+        .filter_map(|(pattern, targets)| {
+            let (prefix, suffix) = pattern.split_once('*')?;
+            (specifier.len() >= prefix.len() + suffix.len()
+                && specifier.starts_with(prefix)
+                && specifier.ends_with(suffix))
+            .then_some((pattern.as_str(), targets.as_slice(), prefix.len()))
+        })
+        .collect();
+    let longest = matching.iter().map(|(_, _, len)| *len).max();
+    matching
+        .into_iter()
+        .filter(|(_, _, len)| Some(*len) == longest)
+        .map(|(pattern, targets, _)| (pattern, targets))
+        .collect()
+}
+
+/// `path` with `.` and `..` folded and any script extension dropped, using
+/// `/` separators. `None` when it leaves the project root.
+fn normalise(path: &Path) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    let joined = parts.join("/");
+    Some(strip_script_extension(&joined).to_string())
+}
+
+fn strip_script_extension(path: &str) -> &str {
+    match path.rsplit_once('.') {
+        Some((stem, extension))
+            if !extension.contains('/') && SCRIPT_EXTENSIONS.contains(&extension) =>
+        {
+            stem.strip_suffix(".d").unwrap_or(stem)
+        }
+        _ => path,
+    }
+}
+
+/// `text` with `//` and `/* */` comments and trailing commas removed, so
+/// a tsconfig parses as JSON.
+fn strip_jsonc(text: &str) -> String {
+    let mut code: Vec<char> = Vec::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                code.push(c);
+                while let Some(c) = chars.next() {
+                    code.push(c);
+                    match c {
+                        '\\' => code.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        code.push(c);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => code.push(c),
+        }
+    }
+
+    let mut out = String::with_capacity(code.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in code.iter().copied().enumerate() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ','
+            && matches!(
+                code.iter().skip(index + 1).find(|c| !c.is_whitespace()),
+                Some('}' | ']')
+            )
+        {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
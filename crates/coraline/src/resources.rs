@@ -0,0 +1,186 @@
+#![forbid(unsafe_code)]
+
+//! MCP resources: read-only context a client can attach without a tool call.
+//!
+//! - `coraline://memories/<namespace>/<name>` — each memory, such as
+//!   `coraline://memories/shared/project_overview`
+//! - `coraline://config` — the project's `.coraline/config.toml`
+//! - `coraline://graph/summary` — a Markdown overview generated from the index
+
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::config;
+use crate::db;
+use crate::memory::{Memory, MemoryManager};
+
+pub const MEMORY_URI_PREFIX: &str = "coraline://memories/";
+pub const CONFIG_URI: &str = "coraline://config";
+pub const GRAPH_SUMMARY_URI: &str = "coraline://graph/summary";
+
+/// Rows shown in each "top" table of the graph summary.
+const SUMMARY_TOP_N: usize = 10;
+
+/// An entry of `resources/list`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Resource {
+    pub uri: String,
+    pub name: String,
+    pub description: String,
+    pub mime_type: &'static str,
+}
+
+/// The text contents returned by `resources/read`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceContents {
+    pub uri: String,
+    pub mime_type: &'static str,
+    pub text: String,
+}
+
+/// All resources available for `project_root`. Missing pieces (no memories,
+/// no config file, no database) are simply left out.
+pub fn list_resources(project_root: &Path) -> Vec<Resource> {
+    let mut resources = Vec::new();
+
+    if db::database_path(project_root).is_file() {
+        resources.push(Resource {
+            uri: GRAPH_SUMMARY_URI.to_string(),
+            name: "Graph summary".to_string(),
+            description: "Files, languages, symbol kinds, largest files, and most-called symbols"
+                .to_string(),
+            mime_type: "text/markdown",
+        });
+    }
+
+    if config::toml_config_path(project_root).is_file() {
+        resources.push(Resource {
+            uri: CONFIG_URI.to_string(),
+            name: "Project config".to_string(),
+            description: "Coraline configuration (.coraline/config.toml)".to_string(),
+            mime_type: "application/toml",
+        });
+    }
+
+    for memory in memories(project_root) {
+        let path = format!("{}/{}", memory.namespace, memory.name);
+        resources.push(Resource {
+            uri: format!("{MEMORY_URI_PREFIX}{path}"),
+            description: format!("Project memory .coraline/memories/{path}.md"),
+            name: format!("Memory: {path}"),
+            mime_type: "text/markdown",
+        });
+    }
+
+    resources
+}
+
+/// Read the resource at `uri`; `Ok(None)` when no such resource exists.
+pub fn read_resource(project_root: &Path, uri: &str) -> io::Result<Option<ResourceContents>> {
+    let contents = |mime_type, text| {
+        Some(ResourceContents {
+            uri: uri.to_string(),
+            mime_type,
+            text,
+        })
+    };
+
+    if uri == GRAPH_SUMMARY_URI {
+        if !db::database_path(project_root).is_file() {
+            return Ok(None);
+        }
+        let conn = db::open_database(project_root)?;
+        return Ok(contents("text/markdown", graph_summary(&conn)?));
+    }
+
+    if uri == CONFIG_URI {
+        let path = config::toml_config_path(project_root);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        return Ok(contents("application/toml", std::fs::read_to_string(path)?));
+    }
+
+    if let Some(path) = uri.strip_prefix(MEMORY_URI_PREFIX) {
+        // Only listed memories are readable, so a URI cannot escape the
+        // memories directory.
+        let memory = memories(project_root)
+            .into_iter()
+            .find(|m| format!("{}/{}", m.namespace, m.name) == path);
+        return Ok(memory.and_then(|memory| contents("text/markdown", memory.content)));
+    }
+
+    Ok(None)
+}
+
+fn memories(project_root: &Path) -> Vec<Memory> {
+    MemoryManager::new(project_root)
+        .and_then(|manager| manager.list_all_memories())
+        .unwrap_or_default()
+}
+
+/// Markdown overview of the indexed graph.
+pub fn graph_summary(conn: &Connection) -> io::Result<String> {
+    let stats = db::get_db_stats(conn)?;
+    let mut out = String::from("# Code graph summary\n\n");
+    let _ = writeln!(out, "- Files: {}", stats.file_count);
+    let _ = writeln!(out, "- Nodes: {}", stats.node_count);
+    let _ = writeln!(out, "- Edges: {}", stats.edge_count);
+    let _ = writeln!(out, "- Unresolved references: {}", stats.unresolved_count);
+
+    let sections: [(&str, [&str; 2], String); 4] = [
+        (
+            "Languages",
+            ["Language", "Files"],
+            "SELECT language, COUNT(*) FROM files GROUP BY language ORDER BY 2 DESC, 1".to_string(),
+        ),
+        (
+            "Symbol kinds",
+            ["Kind", "Nodes"],
+            "SELECT kind, COUNT(*) FROM nodes GROUP BY kind ORDER BY 2 DESC, 1".to_string(),
+        ),
+        (
+            "Largest files",
+            ["File", "Symbols"],
+            format!(
+                "SELECT path, node_count FROM files ORDER BY node_count DESC, path LIMIT {SUMMARY_TOP_N}"
+            ),
+        ),
+        (
+            "Most-called symbols",
+            ["Symbol", "Call sites"],
+            format!(
+                "SELECT n.qualified_name, COUNT(*) FROM edges e
+                 JOIN nodes n ON n.id = e.target
+                 WHERE e.kind = 'calls'
+                 GROUP BY e.target ORDER BY 2 DESC, 1 LIMIT {SUMMARY_TOP_N}"
+            ),
+        ),
+    ];
+
+    for (title, [label, count], sql) in sections {
+        let mut stmt = conn.prepare(&sql).map_err(io::Error::other)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })
+            .map_err(io::Error::other)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io::Error::other)?;
+        if rows.is_empty() {
+            continue;
+        }
+        let _ = write!(out, "\n## {title}\n\n| {label} | {count} |\n|---|---|\n");
+        for (name, n) in rows {
+            let _ = writeln!(out, "| `{name}` | {n} |");
+        }
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,255 @@
+#![forbid(unsafe_code)]
+
+//! Read-only REST API for `coraline serve --rest`, for integrations that
+//! do not speak MCP (dashboards, bots, scripts):
+//!
+//! - `GET /search?q=<text>[&kind=<node kind>][&limit=<n>]`
+//! - `GET /nodes/<id>`
+//! - `GET /callers/<id>[?limit=<n>]`
+//! - `GET /context?task=<text>[&max_nodes=<n>][&format=json|markdown|xml|repo_map][&include_code=false]`
+//! - `GET /stats`
+//! - `GET /openapi.json`
+//!
+//! Responses are JSON (a Markdown, XML, or repo-map context is served as
+//! text), errors `{"error": "..."}`. Like `coraline viz`, requests are
+//! served one at a time over one use of the project database, with no
+//! authentication.
+
+use std::path::Path;
+
+use rusqlite::Connection;
+use serde_json::{Value, json};
+use tracing::info;
+
+use crate::config::{self, SearchConfig};
+use crate::context;
+use crate::db;
+use crate::types::{BuildContextOptions, ContextFormat, EdgeKind, Node};
+use crate::viz::{VizResponse, api_node, api_search, percent_decode, query_param, serve_get};
+
+pub const DEFAULT_PORT: u16 = 7880;
+
+const DEFAULT_CALLERS_LIMIT: usize = 20;
+const MAX_CALLERS_LIMIT: usize = 200;
+
+/// Serve the API on `addr` (e.g. `127.0.0.1:7880`) until the process is
+/// interrupted.
+pub fn serve(project_root: &Path, addr: &str) -> std::io::Result<()> {
+    let conn = db::open_database(project_root)?;
+    let search = config::load_toml_config(project_root)
+        .unwrap_or_default()
+        .search;
+    info!(addr, "REST API listening");
+    serve_get(addr, |url| route(project_root, &conn, &search, url))
+}
+
+/// Dispatch a request for `url` (path plus optional query string).
+pub fn route(
+    project_root: &Path,
+    conn: &Connection,
+    search: &SearchConfig,
+    url: &str,
+) -> VizResponse {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    match path {
+        "/search" => api_search(conn, search, query),
+        "/context" => api_context(project_root, query),
+        "/stats" => match db::get_db_stats(conn) {
+            Ok(stats) => VizResponse::json(200, &json!(stats)),
+            Err(err) => VizResponse::error(500, format!("Failed to get stats: {err}")),
+        },
+        "/openapi.json" => VizResponse::json(200, &openapi()),
+        _ => match (path.strip_prefix("/nodes/"), path.strip_prefix("/callers/")) {
+            (Some(id), _) => api_node(conn, &percent_decode(id)),
+            (_, Some(id)) => api_callers(conn, &percent_decode(id), query),
+            (None, None) => VizResponse::error(404, "Not found"),
+        },
+    }
+}
+
+fn api_callers(conn: &Connection, id: &str, query: &str) -> VizResponse {
+    let node = match db::get_node_by_id(conn, id) {
+        Ok(Some(node)) => node,
+        Ok(None) => return VizResponse::error(404, format!("Node '{id}' not found")),
+        Err(err) => return VizResponse::error(500, format!("Lookup failed: {err}")),
+    };
+    let limit = query_param(query, "limit")
+        .and_then(|raw| raw.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_CALLERS_LIMIT)
+        .clamp(1, MAX_CALLERS_LIMIT);
+
+    match call_neighbours(conn, &node, true, limit) {
+        Ok(callers) => {
+            let callers: Vec<Value> = callers
+                .into_iter()
+                .map(|(caller, line)| json!({ "node": caller, "line": line }))
+                .collect();
+            VizResponse::json(200, &json!({ "node": node, "callers": callers }))
+        }
+        Err(err) => VizResponse::error(500, format!("Failed to get callers: {err}")),
+    }
+}
+
+/// Up to `limit` functions calling `node` (`incoming`) or called by it,
+/// each with the line of the call, after the same crate-boundary check as
+/// `coraline callers` and `coraline callees`.
+pub(crate) fn call_neighbours(
+    conn: &Connection,
+    node: &Node,
+    incoming: bool,
+    limit: usize,
+) -> crate::error::Result<Vec<(Node, Option<i64>)>> {
+    let edges = if incoming {
+        db::get_edges_by_target(conn, &node.id, Some(EdgeKind::Calls), limit * 2)?
+    } else {
+        db::get_edges_by_source(conn, &node.id, Some(EdgeKind::Calls), limit * 2)?
+    };
+    let mut neighbours = Vec::new();
+    for edge in edges {
+        if neighbours.len() == limit {
+            break;
+        }
+        let other = if incoming { &edge.source } else { &edge.target };
+        let Some(other) = db::get_node_by_id(conn, other)? else {
+            continue;
+        };
+        let (from, to) = if incoming {
+            (&other, node)
+        } else {
+            (node, &other)
+        };
+        if db::is_valid_call_edge(conn, from, to)? {
+            neighbours.push((other, edge.line));
+        }
+    }
+    Ok(neighbours)
+}
+
+fn api_context(project_root: &Path, query: &str) -> VizResponse {
+    let Some(task) = query_param(query, "task").filter(|t| !t.trim().is_empty()) else {
+        return VizResponse::error(400, "Missing query parameter 'task'");
+    };
+    let format = match query_param(query, "format") {
+        Some(raw) => match ContextFormat::parse(&raw) {
+            Some(format) => format,
+            None => return VizResponse::error(400, format!("Unknown context format '{raw}'")),
+        },
+        None => ContextFormat::Json,
+    };
+    let options = BuildContextOptions {
+        max_nodes: query_param(query, "max_nodes").and_then(|raw| raw.parse().ok()),
+        include_code: query_param(query, "include_code").map(|raw| raw != "false"),
+        format: Some(format),
+        ..BuildContextOptions::default()
+    };
+
+    match context::build_context(project_root, &task, &options) {
+        Ok(body) if format == ContextFormat::Json => VizResponse {
+            status: 200,
+            content_type: "application/json",
+            body,
+        },
+        Ok(body) => VizResponse {
+            status: 200,
+            content_type: match format {
+                ContextFormat::Xml => "application/xml; charset=utf-8",
+                ContextFormat::Markdown => "text/markdown; charset=utf-8",
+                _ => "text/plain; charset=utf-8",
+            },
+            body,
+        },
+        Err(err) => VizResponse::error(500, format!("Context failed: {err}")),
+    }
+}
+
+/// The `OpenAPI` 3 description of the API.
+#[allow(clippy::literal_string_with_formatting_args)]
+pub fn openapi() -> Value {
+    let id = json!({
+        "name": "id", "in": "path", "required": true,
+        "description": "Node ID, as returned by /search",
+        "schema": { "type": "string" },
+    });
+    let query = |name: &str, kind: &str, description: &str| {
+        json!({
+            "name": name, "in": "query", "required": false,
+            "description": description, "schema": { "type": kind },
+        })
+    };
+    let ok = |description: &str| {
+        json!({
+            "200": {
+                "description": description,
+                "content": { "application/json": { "schema": { "type": "object" } } },
+            },
+            "default": { "$ref": "#/components/responses/Error" },
+        })
+    };
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Coraline REST API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Read-only queries over a Coraline code graph.",
+        },
+        "paths": {
+            "/search": { "get": {
+                "summary": "Ranked symbol search",
+                "parameters": [
+                    {
+                        "name": "q", "in": "query", "required": true,
+                        "description": "Search text", "schema": { "type": "string" },
+                    },
+                    query("kind", "string", "Only nodes of this kind (function, class, ...)"),
+                    query("limit", "integer", "Results to return (1-200, default 20)"),
+                ],
+                "responses": ok("The query and its results, best first"),
+            }},
+            "/nodes/{id}": { "get": {
+                "summary": "A single node",
+                "parameters": [id],
+                "responses": ok("The node"),
+            }},
+            "/callers/{id}": { "get": {
+                "summary": "Functions and methods that call a node",
+                "parameters": [
+                    id,
+                    query("limit", "integer", "Callers to return (1-200, default 20)"),
+                ],
+                "responses": ok("The node and its callers, each with the line of the call"),
+            }},
+            "/context": { "get": {
+                "summary": "Context for a task: entry points, related symbols, and code",
+                "parameters": [
+                    {
+                        "name": "task", "in": "query", "required": true,
+                        "description": "The task in plain words", "schema": { "type": "string" },
+                    },
+                    query("max_nodes", "integer", "Symbols to include"),
+                    query("format", "string", "json (default), markdown, xml, or repo_map"),
+                    query("include_code", "boolean", "Attach code blocks (default true)"),
+                ],
+                "responses": ok("The context; text for formats other than json"),
+            }},
+            "/stats": { "get": {
+                "summary": "File, node, edge, and unresolved reference counts",
+                "responses": ok("Index statistics"),
+            }},
+            "/openapi.json": { "get": {
+                "summary": "This document",
+                "responses": ok("The OpenAPI document"),
+            }},
+        },
+        "components": {
+            "responses": {
+                "Error": {
+                    "description": "A bad request (400), an unknown node or path (404), or a failure (500)",
+                    "content": { "application/json": { "schema": {
+                        "type": "object",
+                        "properties": { "error": { "type": "string" } },
+                    }}},
+                },
+            },
+        },
+    })
+}
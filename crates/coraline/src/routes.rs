@@ -0,0 +1,472 @@
+#![forbid(unsafe_code)]
+
+//! HTTP route extraction for backend frameworks.
+//!
+//! Each route registration becomes a `route` node named `<METHOD> <path>`
+//! (`GET /users/{id}`), contained in its file and linked to its handler by
+//! a `references` edge. A handler defined in the same file is linked
+//! directly; any other is left to the resolver by name.
+//!
+//! | Framework | Registration |
+//! |---|---|
+//! | `actix-web`, Rocket | `#[get("/users")]`, `#[route("/users", method = "GET")]` on the handler |
+//! | axum | `.route("/users", get(list).post(create))` |
+//! | `actix-web` | `.route("/users", web::get().to(list))` |
+//! | Express, Koa, Fastify, Hono | `app.get("/users", auth, list)`, `router.post(...)` |
+//! | `FastAPI`, Flask | `@app.get("/users")`, `@router.post(...)`, `@app.route("/users", methods=["GET"])` |
+//!
+//! `ANY` stands for a route serving every method (axum's `any`, Express's
+//! `all`, an attribute route without a method).
+//!
+//! Registrations starting inside a comment or a string literal of the
+//! file's syntax tree are skipped, so route examples in doc comments and
+//! docstrings are not taken for routes. A method registered twice at one
+//! place (`get(a).get(b)`) yields a single route.
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use tree_sitter::Node as TsNode;
+
+use crate::types::{Edge, EdgeKind, Language, Node, NodeKind, UnresolvedReference};
+use crate::utils::node_id_for_symbol;
+
+const METHODS: &str = "get|post|put|delete|patch|head|options";
+
+/// `#[get("/users")]`, `#[actix_web::post("/x")]`, `#[route("/x", method = "GET")]`.
+static RUST_ATTRIBUTE: LazyLock<Option<Regex>> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r#"#\[(?:[a-z_]+::)*({METHODS}|route)\(\s*"([^"]*)"([^\]]*)\]"#
+    ))
+    .ok()
+});
+
+/// `.route("/users", ...`; the method router follows up to the closing paren.
+static RUST_ROUTE_CALL: LazyLock<Option<Regex>> =
+    LazyLock::new(|| Regex::new(r#"\.route\(\s*"([^"]*)"\s*,"#).ok());
+
+/// `get(list_users)` of axum and `web::get().to(list_users)` of actix-web.
+static RUST_METHOD_HANDLER: LazyLock<Option<Regex>> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"\b({METHODS}|any)\s*\(\s*(?:\)\s*\.to\s*\(\s*)?([A-Za-z_][A-Za-z0-9_:]*)\s*\)"
+    ))
+    .ok()
+});
+
+/// `app.get("/users", ...`, with any receiver.
+static JS_ROUTE_CALL: LazyLock<Option<Regex>> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r#"\b[A-Za-z_$][\w$]*\.({METHODS}|all)\(\s*(['"`])(/[^'"`]*)['"`]\s*,"#
+    ))
+    .ok()
+});
+
+/// `@app.get("/users")`, `@router.api_route("/x", methods=[...])`, `@bp.route(...)`.
+static PYTHON_DECORATOR: LazyLock<Option<Regex>> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r#"(?m)^[ \t]*@[A-Za-z_][\w.]*\.({METHODS}|route|api_route|websocket)\(\s*[rf]?['"]([^'"]*)['"]([^\n]*)"#
+    ))
+    .ok()
+});
+
+/// Words whose presence marks a JavaScript file as a server, so HTTP
+/// clients (`axios.get("/users", ...)`) elsewhere are not taken for routes.
+const JS_SERVER_MARKERS: &[&str] = &["express", "Router(", "fastify", "koa", "hono"];
+
+/// One registration found in a file.
+struct Registration {
+    method: String,
+    path: String,
+    /// Byte offset of the registration.
+    offset: usize,
+    handler: Handler,
+}
+
+enum Handler {
+    /// The name of a function registered by reference.
+    Named(String),
+    /// The function the attribute or decorator is on: the first one
+    /// starting at or after the registration.
+    Decorated,
+    /// An inline closure; the route has no separate handler.
+    Inline,
+}
+
+/// The route nodes of a file, with the edges to their handlers among
+/// `nodes` (the file's symbols) and references to handlers defined
+/// elsewhere. `root` is the file's syntax tree.
+pub(crate) fn extract_routes(
+    root: TsNode,
+    file_path: &str,
+    source: &str,
+    language: Language,
+    nodes: &[Node],
+    root_id: &str,
+    now_ms: i64,
+) -> (Vec<Node>, Vec<Edge>, Vec<UnresolvedReference>) {
+    let registrations = match language {
+        Language::Rust => rust_registrations(source),
+        Language::JavaScript | Language::Jsx | Language::TypeScript | Language::Tsx
+            if JS_SERVER_MARKERS.iter().any(|m| source.contains(m)) =>
+        {
+            js_registrations(source)
+        }
+        Language::Python => python_registrations(source),
+        _ => Vec::new(),
+    };
+    let skipped = comment_and_string_ranges(root);
+    let mut seen = HashSet::new();
+
+    let mut routes = Vec::new();
+    let mut edges = Vec::new();
+    let mut unresolved = Vec::new();
+    for registration in registrations {
+        if skipped
+            .iter()
+            .any(|range| range.contains(&registration.offset))
+            || !seen.insert((
+                registration.method.clone(),
+                registration.path.clone(),
+                registration.offset,
+            ))
+        {
+            continue;
+        }
+        let (line, column) = position(source, registration.offset);
+        let name = format!("{} {}", registration.method, registration.path);
+        let qualified_name = format!("{file_path}::route::{name}");
+        let id = node_id_for_symbol(file_path, "route", &qualified_name, line, column);
+        let handler = handler_in_file(&registration.handler, nodes, line);
+        if let Some(handler) = handler {
+            edges.push(Edge {
+                source: id.clone(),
+                target: handler.id.clone(),
+                kind: EdgeKind::References,
+                metadata: None,
+                line: Some(line),
+                column: Some(column),
+            });
+        } else if let Handler::Named(handler) = &registration.handler {
+            unresolved.push(UnresolvedReference {
+                from_node_id: id.clone(),
+                reference_name: local_name(handler).to_string(),
+                reference_kind: EdgeKind::References,
+                line,
+                column,
+                candidates: None,
+                arity: None,
+                receiver: None,
+            });
+        }
+        edges.push(Edge {
+            source: root_id.to_string(),
+            target: id.clone(),
+            kind: EdgeKind::Contains,
+            metadata: None,
+            line: Some(line),
+            column: Some(column),
+        });
+        routes.push(Node {
+            id,
+            kind: NodeKind::Route,
+            name: name.clone(),
+            qualified_name,
+            file_path: file_path.to_string(),
+            language,
+            start_line: line,
+            end_line: line,
+            start_column: column,
+            end_column: column,
+            docstring: None,
+            signature: Some(name),
+            visibility: None,
+            is_exported: false,
+            is_async: false,
+            is_static: false,
+            is_abstract: false,
+            decorators: None,
+            type_parameters: None,
+            updated_at: now_ms,
+        });
+    }
+    (routes, edges, unresolved)
+}
+
+/// The 1-based line and 0-based column of byte `offset` in `source`.
+fn position(source: &str, offset: usize) -> (i64, i64) {
+    let before = source.get(..offset).unwrap_or(source);
+    let line = i64::try_from(before.matches('\n').count() + 1).unwrap_or(i64::MAX);
+    let column = before.rsplit('\n').next().map_or(0, str::len);
+    let column = i64::try_from(column).unwrap_or(i64::MAX);
+    (line, column)
+}
+
+/// The function or method among `nodes` that `handler`, registered on
+/// `line`, names or decorates.
+fn handler_in_file<'n>(handler: &Handler, nodes: &'n [Node], line: i64) -> Option<&'n Node> {
+    let mut callable = nodes
+        .iter()
+        .filter(|node| matches!(node.kind, NodeKind::Function | NodeKind::Method));
+    match handler {
+        Handler::Named(handler) => callable.find(|n| n.name == local_name(handler)),
+        Handler::Decorated => callable
+            .filter(|n| n.end_line >= line)
+            .min_by_key(|n| (n.start_line < line, n.start_line)),
+        Handler::Inline => None,
+    }
+}
+
+/// The last segment of a handler path (`users::list`, `handlers.list`).
+fn local_name(handler: &str) -> &str {
+    handler.rsplit([':', '.']).next().unwrap_or(handler)
+}
+
+/// Capture group `index` of `caps`, or `""` when it did not take part.
+fn group<'s>(caps: &Captures<'s>, index: usize) -> &'s str {
+    caps.get(index).map_or("", |m| m.as_str())
+}
+
+/// The byte ranges of the comments and string literals under `root`.
+fn comment_and_string_ranges(root: TsNode) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut stack = vec![root];
+    let mut cursor = root.walk();
+    while let Some(current) = stack.pop() {
+        let kind = current.kind();
+        if kind.contains("comment") || kind.contains("string") {
+            ranges.push(current.byte_range());
+        } else {
+            stack.extend(current.children(&mut cursor));
+        }
+    }
+    ranges
+}
+
+fn rust_registrations(source: &str) -> Vec<Registration> {
+    let (Some(attribute), Some(route_call), Some(method_handler)) = (
+        RUST_ATTRIBUTE.as_ref(),
+        RUST_ROUTE_CALL.as_ref(),
+        RUST_METHOD_HANDLER.as_ref(),
+    ) else {
+        return Vec::new();
+    };
+    let mut registrations = Vec::new();
+    for caps in attribute.captures_iter(source) {
+        let path = group(&caps, 2).to_string();
+        let offset = caps.get(0).map_or(0, |m| m.start());
+        let methods = if group(&caps, 1) == "route" {
+            // `method = "GET"`, possibly repeated.
+            let methods: Vec<String> = group(&caps, 3)
+                .split("method")
+                .skip(1)
+                .filter_map(|rest| rest.split('"').nth(1))
+                .map(str::to_uppercase)
+                .collect();
+            if methods.is_empty() {
+                vec!["ANY".to_string()]
+            } else {
+                methods
+            }
+        } else {
+            vec![group(&caps, 1).to_uppercase()]
+        };
+        for method in methods {
+            registrations.push(Registration {
+                method,
+                path: path.clone(),
+                offset,
+                handler: Handler::Decorated,
+            });
+        }
+    }
+    for caps in route_call.captures_iter(source) {
+        let Some(whole) = caps.get(0) else { continue };
+        let router = call_arguments(source.get(whole.end()..).unwrap_or_default());
+        for handler in method_handler.captures_iter(router) {
+            registrations.push(Registration {
+                method: group(&handler, 1).to_uppercase(),
+                path: group(&caps, 1).to_string(),
+                offset: whole.start() + 1,
+                handler: Handler::Named(group(&handler, 2).to_string()),
+            });
+        }
+    }
+    registrations
+}
+
+fn js_registrations(source: &str) -> Vec<Registration> {
+    let Some(route_call) = JS_ROUTE_CALL.as_ref() else {
+        return Vec::new();
+    };
+    route_call
+        .captures_iter(source)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            let arguments = call_arguments(source.get(whole.end()..).unwrap_or_default());
+            // The handler is the last argument; the ones before are middleware.
+            let last = split_top_level(arguments).pop().unwrap_or_default();
+            let handler = if is_js_reference(last) {
+                Handler::Named(last.to_string())
+            } else {
+                Handler::Inline
+            };
+            let method = match group(&caps, 1) {
+                "all" => "ANY".to_string(),
+                method => method.to_uppercase(),
+            };
+            Some(Registration {
+                method,
+                path: group(&caps, 3).to_string(),
+                offset: whole.start(),
+                handler,
+            })
+        })
+        .collect()
+}
+
+fn is_js_reference(argument: &str) -> bool {
+    !argument.is_empty()
+        && argument
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '.'))
+        && !argument.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn python_registrations(source: &str) -> Vec<Registration> {
+    let Some(decorator) = PYTHON_DECORATOR.as_ref() else {
+        return Vec::new();
+    };
+    let mut registrations = Vec::new();
+    for caps in decorator.captures_iter(source) {
+        let offset = caps.get(0).map_or(0, |m| {
+            m.start() + m.as_str().len() - m.as_str().trim_start().len()
+        });
+        let methods = match group(&caps, 1) {
+            "route" | "api_route" => {
+                // `methods=["GET", "POST"]`; Flask defaults to GET.
+                let methods: Vec<String> = group(&caps, 3)
+                    .split_once("methods")
+                    .map(|(_, rest)| {
+                        let list = rest.split(']').next().unwrap_or_default();
+                        list.split(['"', '\''])
+                            .skip(1)
+                            .step_by(2)
+                            .map(str::to_uppercase)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if methods.is_empty() {
+                    vec![
+                        if group(&caps, 1) == "route" {
+                            "GET"
+                        } else {
+                            "ANY"
+                        }
+                        .to_string(),
+                    ]
+                } else {
+                    methods
+                }
+            }
+            "websocket" => vec!["WEBSOCKET".to_string()],
+            method => vec![method.to_uppercase()],
+        };
+        for method in methods {
+            registrations.push(Registration {
+                method,
+                path: group(&caps, 2).to_string(),
+                offset,
+                handler: Handler::Decorated,
+            });
+        }
+    }
+    registrations
+}
+
+/// The text of call arguments starting at `rest`, up to the parenthesis
+/// closing the call.
+fn call_arguments(rest: &str) -> &str {
+    let mut depth = 0usize;
+    for (index, c) in rest.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' if depth == 0 => return rest.get(..index).unwrap_or(rest),
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    rest
+}
+
+/// `arguments` split on the commas outside brackets, trimmed.
+fn split_top_level(arguments: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (index, c) in arguments.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.extend(arguments.get(start..index).map(str::trim));
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.extend(arguments.get(start..).map(str::trim));
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Whether the concrete `path` is served by the route template `pattern`:
+/// segments are equal, or the template's is a parameter (`:id`, `{id}`,
+/// `<id>`, `<int:id>`) or a trailing `*`/`{*rest}` wildcard.
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_matches('/').split('/').collect();
+    let path: Vec<&str> = path.trim_matches('/').split('/').collect();
+    for (index, segment) in pattern.iter().enumerate() {
+        let wildcard = *segment == "*" || segment.starts_with("{*") || segment.starts_with('*');
+        if wildcard && index + 1 == pattern.len() {
+            return true;
+        }
+        let Some(actual) = path.get(index) else {
+            return false;
+        };
+        let parameter = segment.starts_with(':')
+            || (segment.starts_with('{') && segment.ends_with('}'))
+            || (segment.starts_with('<') && segment.ends_with('>'));
+        if !parameter && segment != actual {
+            return false;
+        }
+    }
+    pattern.len() == path.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_matches_templates() {
+        assert!(path_matches("/users/{id}", "/users/42"));
+        assert!(path_matches("/users/:id/posts", "/users/7/posts"));
+        assert!(path_matches("/files/<path:name>", "/files/a.txt"));
+        assert!(path_matches("/static/*", "/static/css/site.css"));
+        assert!(path_matches("/", "/"));
+        assert!(!path_matches("/users/{id}", "/users"));
+        assert!(!path_matches("/users", "/users/42"));
+        assert!(!path_matches("/users/me", "/users/42"));
+    }
+
+    #[test]
+    fn test_argument_helpers() {
+        assert_eq!(call_arguments("a, f(b, c)), rest"), "a, f(b, c)");
+        assert_eq!(
+            split_top_level(" auth , validate(schema, { strict: 1 }), handlers.list "),
+            ["auth", "validate(schema, { strict: 1 })", "handlers.list"]
+        );
+    }
+}
@@ -0,0 +1,649 @@
+#![forbid(unsafe_code)]
+
+//! Export of the index as a [SCIP](https://github.com/sourcegraph/scip)
+//! index (`coraline export --format scip`), for code-search tools and
+//! editors that read SCIP.
+//!
+//! Each indexed file becomes a document. Its symbols (everything but file,
+//! import, and export nodes) get a definition occurrence at their name and
+//! a `SymbolInformation` with their signature and docstring as
+//! documentation; each call, reference, type use, and the like becomes a
+//! reference occurrence at its line, and `extends`, `implements`, and
+//! `overrides` edges become implementation relationships. Symbols use the
+//! `coraline` scheme with a placeholder package and the file path as
+//! namespace descriptors, so they are unique within the project but do not
+//! link up with other indexers' symbols. The protobuf encoding is written
+//! by hand for the handful of messages needed.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use crate::export::export_delta;
+use crate::sensitive::SensitiveFiles;
+use crate::types::{Edge, EdgeKind, Language, Node, NodeKind};
+
+/// Scheme of every symbol, followed by the placeholder package `. . .`.
+pub const SYMBOL_SCHEME: &str = "coraline";
+
+/// `Occurrence.symbol_roles` bit for a definition.
+pub const ROLE_DEFINITION: i32 = 0x1;
+
+/// `Document.position_encoding` for byte offsets from the line start, as
+/// tree-sitter columns are.
+const UTF8_POSITIONS: i32 = 1;
+
+/// `Metadata.text_document_encoding` for UTF-8.
+const UTF8_TEXT: i32 = 1;
+
+/// A SCIP index of the project.
+#[derive(Debug, Clone, Default)]
+pub struct ScipIndex {
+    /// `file://` URI of the project root.
+    pub project_root: String,
+    /// Documents by path.
+    pub documents: Vec<ScipDocument>,
+}
+
+/// One indexed file.
+#[derive(Debug, Clone, Default)]
+pub struct ScipDocument {
+    pub relative_path: String,
+    /// SCIP language name (`Rust`, `TypeScriptReact`, ...).
+    pub language: String,
+    pub occurrences: Vec<ScipOccurrence>,
+    pub symbols: Vec<ScipSymbol>,
+}
+
+/// A definition of or a reference to a symbol.
+#[derive(Debug, Clone, Default)]
+pub struct ScipOccurrence {
+    /// Zero-based `[line, start, end]`, or `[start line, start, end line,
+    /// end]` when it spans lines.
+    pub range: Vec<i32>,
+    pub symbol: String,
+    /// [`ROLE_DEFINITION`] or 0 for a reference.
+    pub symbol_roles: i32,
+    /// For a definition, the range of the whole declaration.
+    pub enclosing_range: Vec<i32>,
+}
+
+/// What a document says about a symbol it defines.
+#[derive(Debug, Clone, Default)]
+pub struct ScipSymbol {
+    pub symbol: String,
+    /// Markdown: the signature as a code block, then the docstring.
+    pub documentation: Vec<String>,
+    pub relationships: Vec<ScipRelationship>,
+    /// `SymbolInformation.Kind` value.
+    pub kind: i32,
+    pub display_name: String,
+    /// The symbol of the class, module, or function it is declared in.
+    pub enclosing_symbol: String,
+}
+
+/// A symbol another one implements or has as its type.
+#[derive(Debug, Clone, Default)]
+pub struct ScipRelationship {
+    pub symbol: String,
+    pub is_implementation: bool,
+    pub is_type_definition: bool,
+}
+
+/// The signature, as a code block, and the docstring of `node`.
+fn documentation(node: &Node) -> Vec<String> {
+    let mut documentation = Vec::new();
+    if let Some(signature) = node.signature.as_deref().filter(|s| !s.is_empty()) {
+        let language = serde_json::to_value(node.language)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        documentation.push(format!("```{language}\n{signature}\n```"));
+    }
+    if let Some(docstring) = node.docstring.as_deref().filter(|s| !s.is_empty()) {
+        documentation.push(docstring.to_string());
+    }
+    documentation
+}
+
+/// Record an occurrence of its target's symbol for each edge whose target is
+/// named on the edge's line, and return the implementation and type
+/// relationships of each source symbol, by id.
+fn edge_occurrences<'n>(
+    project_root: &Path,
+    edges: &[Edge],
+    by_id: &HashMap<&str, &'n Node>,
+    symbols: &HashMap<&str, String>,
+    documents: &mut BTreeMap<String, ScipDocument>,
+    texts: &mut HashMap<String, Option<Vec<String>>>,
+) -> HashMap<&'n str, Vec<ScipRelationship>> {
+    let mut relationships: HashMap<&str, Vec<ScipRelationship>> = HashMap::new();
+    for edge in edges {
+        let (Some(source), Some(target)) = (
+            by_id.get(edge.source.as_str()),
+            by_id.get(edge.target.as_str()),
+        ) else {
+            continue;
+        };
+        let Some(symbol) = symbols.get(target.id.as_str()).cloned() else {
+            continue;
+        };
+        let (is_implementation, is_type_definition) = match edge.kind {
+            EdgeKind::Extends | EdgeKind::Implements | EdgeKind::Overrides => (true, false),
+            EdgeKind::TypeOf => (false, true),
+            _ => (false, false),
+        };
+        if is_implementation || is_type_definition {
+            relationships
+                .entry(&source.id)
+                .or_default()
+                .push(ScipRelationship {
+                    symbol: symbol.clone(),
+                    is_implementation,
+                    is_type_definition,
+                });
+        }
+        if matches!(
+            edge.kind,
+            EdgeKind::Contains | EdgeKind::Imports | EdgeKind::Exports
+        ) {
+            continue;
+        }
+        let (Some(line), Some(document)) = (edge.line, documents.get_mut(&source.file_path)) else {
+            continue;
+        };
+        let Some(lines) = file_lines(texts, project_root, &source.file_path) else {
+            continue;
+        };
+        let column = edge.column.unwrap_or(0);
+        if let Some(range) = find_name(lines, line - 1, column, line - 1, &target.name) {
+            document.occurrences.push(ScipOccurrence {
+                range,
+                symbol,
+                symbol_roles: 0,
+                enclosing_range: Vec::new(),
+            });
+        }
+    }
+    relationships
+}
+
+/// Build the SCIP index of everything indexed, leaving out the files
+/// `sensitive` matches.
+///
+/// File text is read from `project_root` to place occurrences on names; a
+/// file that cannot be read keeps its symbols at the start of their
+/// declarations and loses its references.
+///
+/// # Errors
+///
+/// Returns an `io::Error` when the database cannot be read.
+pub fn build_scip_index(
+    project_root: &Path,
+    conn: &Connection,
+    sensitive: &SensitiveFiles,
+) -> std::io::Result<ScipIndex> {
+    let graph = export_delta(conn, 0, None, sensitive)?;
+    let mut nodes: Vec<&Node> = graph.nodes.iter().filter(|n| is_symbol(n.kind)).collect();
+    nodes.sort_by(|a, b| {
+        (&a.file_path, a.start_line, a.start_column).cmp(&(
+            &b.file_path,
+            b.start_line,
+            b.start_column,
+        ))
+    });
+    let symbols = symbol_names(&nodes);
+    let by_id: HashMap<&str, &Node> = nodes.iter().map(|n| (n.id.as_str(), *n)).collect();
+    let by_qualified: HashMap<&str, &Node> = nodes
+        .iter()
+        .map(|n| (n.qualified_name.as_str(), *n))
+        .collect();
+
+    let mut documents: BTreeMap<String, ScipDocument> = graph
+        .files
+        .iter()
+        .map(|file| {
+            (
+                file.path.clone(),
+                ScipDocument {
+                    relative_path: file.path.clone(),
+                    language: scip_language(file.language),
+                    ..ScipDocument::default()
+                },
+            )
+        })
+        .collect();
+    let mut texts: HashMap<String, Option<Vec<String>>> = HashMap::new();
+
+    let mut relationships = edge_occurrences(
+        project_root,
+        &graph.edges,
+        &by_id,
+        &symbols,
+        &mut documents,
+        &mut texts,
+    );
+
+    for node in &nodes {
+        let (Some(document), Some(symbol)) = (
+            documents.get_mut(&node.file_path),
+            symbols.get(node.id.as_str()).cloned(),
+        ) else {
+            continue;
+        };
+        let start_line = node.start_line - 1;
+        let end_line = node.end_line - 1;
+        let enclosing_range = range(start_line, node.start_column, end_line, node.end_column);
+        let name_range = file_lines(&mut texts, project_root, &node.file_path)
+            .and_then(|lines| find_name(lines, start_line, node.start_column, end_line, &node.name))
+            .unwrap_or_else(|| {
+                range(
+                    start_line,
+                    node.start_column,
+                    start_line,
+                    node.start_column
+                        .saturating_add(i64::try_from(node.name.len()).unwrap_or(i64::MAX)),
+                )
+            });
+        document.occurrences.push(ScipOccurrence {
+            range: name_range,
+            symbol: symbol.clone(),
+            symbol_roles: ROLE_DEFINITION,
+            enclosing_range,
+        });
+
+        let enclosing_symbol = container(node)
+            .and_then(|parent| by_qualified.get(parent))
+            .and_then(|parent| symbols.get(parent.id.as_str()).cloned())
+            .unwrap_or_default();
+        document.symbols.push(ScipSymbol {
+            symbol,
+            documentation: documentation(node),
+            relationships: relationships.remove(node.id.as_str()).unwrap_or_default(),
+            kind: symbol_kind(node.kind),
+            display_name: node.name.clone(),
+            enclosing_symbol,
+        });
+    }
+
+    for document in documents.values_mut() {
+        document
+            .occurrences
+            .sort_by(|a, b| (&a.range, &a.symbol).cmp(&(&b.range, &b.symbol)));
+        document
+            .occurrences
+            .dedup_by(|a, b| a.range == b.range && a.symbol == b.symbol);
+    }
+
+    let root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    Ok(ScipIndex {
+        project_root: format!("file://{}", root.to_string_lossy().replace('\\', "/")),
+        documents: documents.into_values().collect(),
+    })
+}
+
+/// Build the SCIP index of the project and write it to `output`. Returns
+/// the index written.
+///
+/// # Errors
+///
+/// Returns an `io::Error` when the database cannot be read or `output`
+/// cannot be written.
+pub fn write_scip_index(
+    project_root: &Path,
+    conn: &Connection,
+    sensitive: &SensitiveFiles,
+    output: &Path,
+) -> std::io::Result<ScipIndex> {
+    let index = build_scip_index(project_root, conn, sensitive)?;
+    std::fs::write(output, index.encode())?;
+    Ok(index)
+}
+
+impl ScipIndex {
+    /// The `scip.Index` protobuf message.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut tool = Message::default();
+        tool.string(1, "coraline");
+        tool.string(2, env!("CARGO_PKG_VERSION"));
+        let mut metadata = Message::default();
+        metadata.message(2, &tool);
+        metadata.string(3, &self.project_root);
+        metadata.int(4, i64::from(UTF8_TEXT));
+
+        let mut index = Message::default();
+        index.message(1, &metadata);
+        for document in &self.documents {
+            index.message(2, &document.encode());
+        }
+        index.bytes
+    }
+}
+
+impl ScipDocument {
+    fn encode(&self) -> Message {
+        let mut message = Message::default();
+        message.string(1, &self.relative_path);
+        for occurrence in &self.occurrences {
+            let mut encoded = Message::default();
+            encoded.packed(1, &occurrence.range);
+            encoded.string(2, &occurrence.symbol);
+            encoded.int(3, i64::from(occurrence.symbol_roles));
+            encoded.packed(7, &occurrence.enclosing_range);
+            message.message(2, &encoded);
+        }
+        for symbol in &self.symbols {
+            let mut encoded = Message::default();
+            encoded.string(1, &symbol.symbol);
+            for documentation in &symbol.documentation {
+                encoded.string(3, documentation);
+            }
+            for relationship in &symbol.relationships {
+                let mut related = Message::default();
+                related.string(1, &relationship.symbol);
+                related.int(3, i64::from(relationship.is_implementation));
+                related.int(4, i64::from(relationship.is_type_definition));
+                encoded.message(4, &related);
+            }
+            encoded.int(5, i64::from(symbol.kind));
+            encoded.string(6, &symbol.display_name);
+            encoded.string(8, &symbol.enclosing_symbol);
+            message.message(3, &encoded);
+        }
+        message.string(4, &self.language);
+        message.int(6, i64::from(UTF8_POSITIONS));
+        message
+    }
+}
+
+/// A protobuf message being encoded. Fields holding their default value
+/// are left out, as proto3 does.
+#[derive(Debug, Default)]
+struct Message {
+    bytes: Vec<u8>,
+}
+
+impl Message {
+    fn varint(&mut self, mut value: u64) {
+        loop {
+            let low = u8::try_from(value & 0x7f).unwrap_or_default();
+            value >>= 7;
+            if value == 0 {
+                self.bytes.push(low);
+                return;
+            }
+            self.bytes.push(low | 0x80);
+        }
+    }
+
+    fn tag(&mut self, field: u32, wire_type: u8) {
+        self.varint(u64::from(field) << 3 | u64::from(wire_type));
+    }
+
+    /// An `int32`, enum, or bool field.
+    fn int(&mut self, field: u32, value: i64) {
+        if value != 0 {
+            self.tag(field, 0);
+            // Negative values are sign-extended to ten bytes.
+            self.varint(value.cast_unsigned());
+        }
+    }
+
+    fn length_delimited(&mut self, field: u32, bytes: &[u8]) {
+        self.tag(field, 2);
+        self.varint(bytes.len() as u64);
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn string(&mut self, field: u32, value: &str) {
+        if !value.is_empty() {
+            self.length_delimited(field, value.as_bytes());
+        }
+    }
+
+    /// An embedded message field, written even when empty.
+    fn message(&mut self, field: u32, message: &Self) {
+        self.length_delimited(field, &message.bytes);
+    }
+
+    /// A packed `repeated int32` field.
+    fn packed(&mut self, field: u32, values: &[i32]) {
+        if values.is_empty() {
+            return;
+        }
+        let mut packed = Self::default();
+        for value in values {
+            packed.varint(i64::from(*value).cast_unsigned());
+        }
+        self.length_delimited(field, &packed.bytes);
+    }
+}
+
+/// Node kinds exported as symbols.
+const fn is_symbol(kind: NodeKind) -> bool {
+    !matches!(
+        kind,
+        NodeKind::File | NodeKind::Import | NodeKind::Export | NodeKind::Todo
+    )
+}
+
+/// The symbol of each of `nodes` (sorted by position), by node ID.
+/// Functions and methods that would share a symbol, such as overloads, get
+/// `(+1)`, `(+2)`, ... disambiguators in order.
+fn symbol_names<'a>(nodes: &[&'a Node]) -> HashMap<&'a str, String> {
+    let kinds: HashMap<&str, NodeKind> = nodes
+        .iter()
+        .map(|n| (n.qualified_name.as_str(), n.kind))
+        .collect();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut symbols = HashMap::new();
+    for node in nodes {
+        let mut prefix = format!("{SYMBOL_SCHEME} . . . ");
+        for segment in node.file_path.split('/') {
+            prefix.push_str(&escape(segment));
+            prefix.push('/');
+        }
+        let inner = node
+            .qualified_name
+            .strip_prefix(node.file_path.as_str())
+            .and_then(|rest| rest.strip_prefix("::"))
+            .unwrap_or(&node.name);
+        let mut segments: Vec<&str> = inner.split("::").collect();
+        segments.pop();
+        let mut qualified = node.file_path.clone();
+        for segment in segments {
+            qualified.push_str("::");
+            qualified.push_str(segment);
+            let kind = kinds
+                .get(qualified.as_str())
+                .copied()
+                .unwrap_or(NodeKind::Namespace);
+            prefix.push_str(&descriptor(kind, segment, 0));
+        }
+
+        let mut disambiguator = 0;
+        let symbol = loop {
+            let symbol = format!(
+                "{prefix}{}",
+                descriptor(node.kind, &node.name, disambiguator)
+            );
+            if seen.insert(symbol.clone()) || !is_callable(node.kind) {
+                break symbol;
+            }
+            disambiguator += 1;
+        };
+        symbols.insert(node.id.as_str(), symbol);
+    }
+    symbols
+}
+
+const fn is_callable(kind: NodeKind) -> bool {
+    matches!(
+        kind,
+        NodeKind::Function | NodeKind::Method | NodeKind::Route
+    )
+}
+
+/// The SCIP descriptor of a `kind` symbol named `name`: `name/` for a
+/// namespace, `name#` for a type, `name().` for a callable, `(name)` for a
+/// parameter, and `name.` for a term.
+fn descriptor(kind: NodeKind, name: &str, disambiguator: usize) -> String {
+    let name = escape(name);
+    match kind {
+        NodeKind::Module | NodeKind::Namespace => format!("{name}/"),
+        NodeKind::Class
+        | NodeKind::Struct
+        | NodeKind::Interface
+        | NodeKind::Trait
+        | NodeKind::Protocol
+        | NodeKind::Enum
+        | NodeKind::TypeAlias
+        | NodeKind::Component
+        | NodeKind::Model => format!("{name}#"),
+        NodeKind::Function | NodeKind::Method | NodeKind::Route if disambiguator > 0 => {
+            format!("{name}(+{disambiguator}).")
+        }
+        NodeKind::Function | NodeKind::Method | NodeKind::Route => format!("{name}()."),
+        NodeKind::Parameter => format!("({name})"),
+        _ => format!("{name}."),
+    }
+}
+
+/// `name` as a descriptor name: as is when it is a simple identifier,
+/// otherwise in backticks with backticks doubled.
+fn escape(name: &str) -> String {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '+' | '-' | '$'))
+    {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "``"))
+    }
+}
+
+/// The qualified name of the node `node` is declared in, if not its file.
+fn container(node: &Node) -> Option<&str> {
+    let (parent, _) = node.qualified_name.rsplit_once("::")?;
+    (parent != node.file_path).then_some(parent)
+}
+
+/// SCIP's `SymbolInformation.Kind` for `kind`.
+const fn symbol_kind(kind: NodeKind) -> i32 {
+    match kind {
+        NodeKind::Class | NodeKind::Component => 7,
+        NodeKind::Constant => 8,
+        NodeKind::Enum => 11,
+        NodeKind::EnumMember => 12,
+        NodeKind::Field => 15,
+        NodeKind::File => 16,
+        NodeKind::Function | NodeKind::Route => 17,
+        NodeKind::Interface => 21,
+        NodeKind::Method => 26,
+        NodeKind::Module => 29,
+        NodeKind::Namespace => 30,
+        NodeKind::Parameter => 37,
+        NodeKind::Property => 41,
+        NodeKind::Protocol => 42,
+        NodeKind::Struct | NodeKind::Model => 49,
+        NodeKind::Trait => 53,
+        NodeKind::TypeAlias => 55,
+        NodeKind::Variable => 61,
+        NodeKind::Import | NodeKind::Export | NodeKind::Todo => 0,
+    }
+}
+
+/// SCIP's name for `language`, as in its `Language` enum.
+fn scip_language(language: Language) -> String {
+    match language {
+        Language::Tsx => "TypeScriptReact".to_string(),
+        Language::Jsx => "JavaScriptReact".to_string(),
+        Language::Cpp => "CPP".to_string(),
+        Language::Php => "PHP".to_string(),
+        Language::Bash => "ShellScript".to_string(),
+        Language::Powershell => "PowerShell".to_string(),
+        other => format!("{other:?}"),
+    }
+}
+
+/// A SCIP range from zero-based lines and byte columns.
+fn range(start_line: i64, start_column: i64, end_line: i64, end_column: i64) -> Vec<i32> {
+    let clamp = |value: i64| i32::try_from(value.max(0)).unwrap_or(i32::MAX);
+    if start_line == end_line {
+        vec![clamp(start_line), clamp(start_column), clamp(end_column)]
+    } else {
+        vec![
+            clamp(start_line),
+            clamp(start_column),
+            clamp(end_line),
+            clamp(end_column),
+        ]
+    }
+}
+
+/// The lines of `file`, read once; `None` when it cannot be read.
+fn file_lines<'a>(
+    cache: &'a mut HashMap<String, Option<Vec<String>>>,
+    project_root: &Path,
+    file: &str,
+) -> Option<&'a [String]> {
+    cache
+        .entry(file.to_string())
+        .or_insert_with(|| {
+            std::fs::read_to_string(project_root.join(file))
+                .ok()
+                .map(|text| text.lines().map(str::to_string).collect())
+        })
+        .as_deref()
+}
+
+/// The range of the first whole-word `name` in `lines` from `(line,
+/// column)` up to the end of `last_line`, or, failing that, anywhere on
+/// `line`.
+fn find_name(
+    lines: &[String],
+    line: i64,
+    column: i64,
+    last_line: i64,
+    name: &str,
+) -> Option<Vec<i32>> {
+    if name.is_empty() || line < 0 {
+        return None;
+    }
+    let first = usize::try_from(line).ok()?;
+    let last = usize::try_from(last_line).unwrap_or(first).max(first);
+    let column = usize::try_from(column).unwrap_or(0);
+    let word = |c: char| c.is_alphanumeric() || c == '_';
+    let find_in = |text: &str, from: usize| -> Option<usize> {
+        let mut start = from.min(text.len());
+        while let Some(found) = text.get(start..)?.find(name) {
+            let at = start + found;
+            let (head, tail) = text.split_at_checked(at)?;
+            let before = head.chars().next_back();
+            let after = tail.get(name.len()..).and_then(|rest| rest.chars().next());
+            if !before.is_some_and(word) && !after.is_some_and(word) {
+                return Some(at);
+            }
+            start = at + name.len();
+        }
+        None
+    };
+    let at = |number: usize, at: usize| {
+        let number = i64::try_from(number).unwrap_or(i64::MAX);
+        let at = i64::try_from(at).unwrap_or(i64::MAX);
+        let end = at.saturating_add(i64::try_from(name.len()).unwrap_or(i64::MAX));
+        range(number, at, number, end)
+    };
+    for (number, text) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let from = if number == first { column } else { 0 };
+        if let Some(found) = find_in(text, from) {
+            return Some(at(number, found));
+        }
+    }
+    let text = lines.get(first)?;
+    find_in(text, 0).map(|found| at(first, found))
+}
@@ -0,0 +1,195 @@
+#![forbid(unsafe_code)]
+
+//! Detection of files that are likely to hold secrets.
+//!
+//! Key material, `.env` files and credential stores should never reach the
+//! graph database, a context code block, or an export. Matching files are
+//! skipped while scanning the project, even when an include pattern would
+//! pick them up, and are filtered out of code blocks and exports so a
+//! database indexed before the check existed does not leak them either.
+//!
+//! The built-in patterns can be extended with `[indexing]
+//! sensitive_patterns`, individual files let through with
+//! `allow_sensitive_files`, and the whole check turned off with
+//! `exclude_sensitive_files = false`.
+
+use std::path::Path;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use tracing::warn;
+
+use crate::config::{self, IndexingConfig};
+
+/// File patterns treated as sensitive unless allowed explicitly.
+pub const BUILTIN_PATTERNS: &[&str] = &[
+    // Environment files, except the committed templates.
+    "**/.env",
+    "**/.env.*",
+    "**/*.env",
+    // Private keys and certificate stores.
+    "**/*.pem",
+    "**/*.key",
+    "**/*.p12",
+    "**/*.pfx",
+    "**/*.jks",
+    "**/*.keystore",
+    "**/id_rsa",
+    "**/id_rsa.*",
+    "**/id_dsa",
+    "**/id_ecdsa",
+    "**/id_ed25519",
+    "**/id_ed25519.*",
+    "**/.ssh/**",
+    // Credential and token stores.
+    "**/credentials",
+    "**/credentials.json",
+    "**/client_secret*.json",
+    "**/service-account*.json",
+    "**/secrets.json",
+    "**/secrets.yml",
+    "**/secrets.yaml",
+    "**/secrets.toml",
+    "**/.aws/credentials",
+    "**/.docker/config.json",
+    "**/.netrc",
+    "**/.npmrc",
+    "**/.pypirc",
+    "**/.htpasswd",
+    "**/*.kdbx",
+    "**/*.tfvars",
+    "**/*.tfstate",
+    "**/*.tfstate.backup",
+];
+
+/// Templates that match a built-in pattern but carry no secrets by
+/// convention.
+const BUILTIN_ALLOWED: &[&str] = &[
+    "**/.env.example",
+    "**/.env.sample",
+    "**/.env.template",
+    "**/.env.dist",
+    "**/*.pub",
+];
+
+/// Matcher deciding whether a project file is sensitive.
+#[derive(Debug, Clone)]
+pub struct SensitiveFiles {
+    enabled: bool,
+    patterns: GlobSet,
+    allowed: GlobSet,
+}
+
+impl Default for SensitiveFiles {
+    fn default() -> Self {
+        Self::from_config(&IndexingConfig::default())
+    }
+}
+
+impl SensitiveFiles {
+    /// Matcher for the `[indexing]` settings.
+    pub fn from_config(cfg: &IndexingConfig) -> Self {
+        let patterns = BUILTIN_PATTERNS
+            .iter()
+            .copied()
+            .chain(cfg.sensitive_patterns.iter().map(String::as_str));
+        let allowed = BUILTIN_ALLOWED
+            .iter()
+            .copied()
+            .chain(cfg.allow_sensitive_files.iter().map(String::as_str));
+        Self {
+            enabled: cfg.exclude_sensitive_files,
+            patterns: build_set(patterns),
+            allowed: build_set(allowed),
+        }
+    }
+
+    /// Matcher for the project's `config.toml`, or the defaults when it is
+    /// missing or unreadable.
+    pub fn for_project(project_root: &Path) -> Self {
+        config::load_toml_config(project_root)
+            .map_or_else(|_| Self::default(), |cfg| Self::from_config(&cfg.indexing))
+    }
+
+    /// Matcher that treats nothing as sensitive.
+    pub const fn disabled() -> Self {
+        Self {
+            enabled: false,
+            patterns: GlobSet::empty(),
+            allowed: GlobSet::empty(),
+        }
+    }
+
+    /// Whether `relative_path` (forward slashes, relative to the project
+    /// root) should be kept out of the index and agent output.
+    pub fn is_sensitive(&self, relative_path: &str) -> bool {
+        self.enabled
+            && self.patterns.is_match(relative_path)
+            && !self.allowed.is_match(relative_path)
+    }
+}
+
+fn build_set<'a>(patterns: impl Iterator<Item = &'a str>) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(err) => warn!(pattern, error = %err, "ignoring invalid sensitive file pattern"),
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_builtin_patterns() {
+        let sensitive = SensitiveFiles::default();
+        for path in [
+            ".env",
+            "services/api/.env.production",
+            "certs/server.pem",
+            "deploy/id_rsa",
+            "config/credentials.json",
+            "infra/prod.tfvars",
+            "home/.ssh/config",
+        ] {
+            assert!(sensitive.is_sensitive(path), "{path} should be sensitive");
+        }
+        for path in [
+            "src/env.rs",
+            ".env.example",
+            "deploy/id_rsa.pub",
+            "src/credentials.ts",
+            "src/keys.py",
+        ] {
+            assert!(
+                !sensitive.is_sensitive(path),
+                "{path} should not be sensitive"
+            );
+        }
+    }
+
+    #[test]
+    fn config_extends_allows_and_disables() {
+        let cfg = IndexingConfig {
+            sensitive_patterns: vec!["**/secret_*.py".to_string()],
+            allow_sensitive_files: vec!["fixtures/test.pem".to_string()],
+            ..IndexingConfig::default()
+        };
+        let sensitive = SensitiveFiles::from_config(&cfg);
+        assert!(sensitive.is_sensitive("app/secret_keys.py"));
+        assert!(!sensitive.is_sensitive("fixtures/test.pem"));
+        assert!(sensitive.is_sensitive("fixtures/other.pem"));
+
+        let off = SensitiveFiles::from_config(&IndexingConfig {
+            exclude_sensitive_files: false,
+            ..IndexingConfig::default()
+        });
+        assert!(!off.is_sensitive(".env"));
+        assert!(!SensitiveFiles::disabled().is_sensitive(".env"));
+    }
+}
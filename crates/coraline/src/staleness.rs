@@ -0,0 +1,204 @@
+#![forbid(unsafe_code)]
+
+//! Staleness of the index and of individual query results.
+//!
+//! Before a query, [`ensure_fresh`] compares the project with the index the
+//! way `sync` would (file set, then modification times and sizes, then
+//! content hashes) and applies `[sync] on_stale_index`: query anyway and
+//! warn, sync first, or refuse. A [`FreshnessCache`] reuses that answer for
+//! [`FRESHNESS_TTL`], or until this process next syncs, so a burst of tool
+//! calls walks the project once.
+//!
+//! Indexing stores a hash of each node's source span. A result is stale
+//! when its file changed after indexing and the lines at its recorded span
+//! no longer hash to the stored value, so its line numbers cannot be
+//! trusted until the next sync. Edits elsewhere in the file, such as below
+//! the node, leave it fresh.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use rusqlite::Connection;
+
+use crate::config::{self, StaleIndexPolicy};
+use crate::db;
+use crate::extraction::{self, SyncResult, SyncStatus};
+use crate::types::Node;
+use crate::utils::{hash_sha256, span_hash};
+
+/// The index compared with the working tree, after `[sync]
+/// on_stale_index` was applied.
+#[derive(Debug, Clone)]
+pub enum Freshness {
+    /// Up to date, or the policy is `off`.
+    Fresh,
+    /// Behind the working tree; the policy is `warn`.
+    Stale(SyncStatus),
+    /// Was behind, and has been synced; the policy is `sync`.
+    Synced(SyncResult),
+    /// Behind the working tree; the policy is `fail`.
+    Refused(SyncStatus),
+}
+
+/// Check the index of `project_root` against the working tree and apply
+/// `[sync] on_stale_index`.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the configuration or index cannot be read, or
+/// the sync the policy asks for fails.
+pub fn ensure_fresh(project_root: &Path) -> std::io::Result<Freshness> {
+    let toml_cfg = config::load_toml_config(project_root).unwrap_or_default();
+    let policy = toml_cfg.sync.on_stale_index;
+    if policy == StaleIndexPolicy::Off {
+        return Ok(Freshness::Fresh);
+    }
+    let cfg = config::load_config(project_root)?;
+    let status = extraction::needs_sync(project_root, &cfg)?;
+    if !status.is_stale() {
+        return Ok(Freshness::Fresh);
+    }
+    Ok(match policy {
+        StaleIndexPolicy::Off => Freshness::Fresh,
+        StaleIndexPolicy::Warn => Freshness::Stale(status),
+        StaleIndexPolicy::Sync => Freshness::Synced(extraction::sync(project_root, &cfg, None)?),
+        StaleIndexPolicy::Fail => Freshness::Refused(status),
+    })
+}
+
+/// How long a [`FreshnessCache`] reuses a check.
+pub const FRESHNESS_TTL: Duration = Duration::from_secs(2);
+
+/// Per project, bumped by every sync or full index in this process, so
+/// cached checks made before it are not reused.
+static INDEX_GENERATIONS: LazyLock<Mutex<HashMap<PathBuf, u64>>> = LazyLock::new(Mutex::default);
+
+/// Record that the index of `project_root` was just rebuilt or synced.
+pub(crate) fn note_index_changed(project_root: &Path) {
+    let mut generations = INDEX_GENERATIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    *generations.entry(project_root.to_path_buf()).or_default() += 1;
+}
+
+fn index_generation(project_root: &Path) -> u64 {
+    INDEX_GENERATIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(project_root)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// The last [`ensure_fresh`] answer, reused for [`FRESHNESS_TTL`] unless
+/// the index changed in the meantime.
+#[derive(Debug, Default)]
+pub struct FreshnessCache {
+    last: Mutex<Option<(Instant, u64, Freshness)>>,
+}
+
+impl FreshnessCache {
+    /// [`ensure_fresh`], or its answer from a check made less than
+    /// [`FRESHNESS_TTL`] ago. A sync is remembered as [`Freshness::Fresh`].
+    ///
+    /// # Errors
+    ///
+    /// As [`ensure_fresh`]; failures are not cached.
+    pub fn check(&self, project_root: &Path) -> std::io::Result<Freshness> {
+        let mut last = self.last.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some((at, generation, freshness)) = last.as_ref()
+            && at.elapsed() < FRESHNESS_TTL
+            && *generation == index_generation(project_root)
+        {
+            return Ok(freshness.clone());
+        }
+        let freshness = ensure_fresh(project_root)?;
+        let remembered = match &freshness {
+            Freshness::Synced(_) => Freshness::Fresh,
+            other => other.clone(),
+        };
+        *last = Some((Instant::now(), index_generation(project_root), remembered));
+        drop(last);
+        Ok(freshness)
+    }
+}
+
+/// One line saying how far the index is behind, e.g. "The index is behind
+/// the working tree: 2 files added, 1 modified."
+pub fn describe(status: &SyncStatus) -> String {
+    let parts: Vec<String> = [
+        (status.files_added, "added"),
+        (status.files_modified, "modified"),
+        (status.files_removed, "removed"),
+    ]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .enumerate()
+    .map(|(i, (count, what))| match (i, count) {
+        (0, 1) => format!("1 file {what}"),
+        (0, _) => format!("{count} files {what}"),
+        _ => format!("{count} {what}"),
+    })
+    .collect();
+    format!(
+        "The index is behind the working tree: {}.",
+        parts.join(", ")
+    )
+}
+
+/// What a file looks like now compared with its indexed content.
+enum FileState {
+    /// Not in the index, or hashing to the indexed content.
+    Fresh,
+    /// Edited since indexing; holds the current content, or `None` if the
+    /// file is gone.
+    Changed(Option<String>),
+}
+
+/// Checks results against the files on disk, reading each file once.
+pub struct StalenessCheck<'a> {
+    project_root: &'a Path,
+    conn: &'a Connection,
+    files: HashMap<String, FileState>,
+}
+
+impl<'a> StalenessCheck<'a> {
+    pub fn new(project_root: &'a Path, conn: &'a Connection) -> Self {
+        Self {
+            project_root,
+            conn,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Whether `node`'s file changed since indexing in a way that moved or
+    /// altered its span. Nodes of deleted files are always stale.
+    pub fn is_stale(&mut self, node: &Node) -> bool {
+        let state = self
+            .files
+            .entry(node.file_path.clone())
+            .or_insert_with(|| file_state(self.project_root, self.conn, &node.file_path));
+        match state {
+            FileState::Fresh => false,
+            FileState::Changed(None) => true,
+            FileState::Changed(Some(content)) => db::get_node_hash(self.conn, &node.id)
+                .ok()
+                .flatten()
+                .is_none_or(|stored| span_hash(content, node.start_line, node.end_line) != stored),
+        }
+    }
+}
+
+fn file_state(project_root: &Path, conn: &Connection, relative_path: &str) -> FileState {
+    let Ok(Some(record)) = db::get_file_record(conn, relative_path) else {
+        return FileState::Fresh;
+    };
+    match fs::read_to_string(project_root.join(relative_path)) {
+        Ok(content) if hash_sha256(&content) == record.content_hash => FileState::Fresh,
+        Ok(content) => FileState::Changed(Some(content)),
+        Err(_) => FileState::Changed(None),
+    }
+}
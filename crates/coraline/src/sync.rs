@@ -1,33 +1,191 @@
 #![forbid(unsafe_code)]
 
+use std::fmt::Write as _;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
-const POST_COMMIT_HOOK: &str = "post-commit";
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::config::SyncConfig;
+use crate::extraction::SyncResult;
+use crate::utils;
+use crate::worktree;
+
+/// Worker-thread cap set by [`limit_threads`]; 0 means no cap.
+static THREAD_LIMIT: AtomicUsize = AtomicUsize::new(0);
 const CODEGRAPH_MARKER: &str = "# Coraline auto-sync hook";
 
-fn post_commit_script() -> String {
-    let script = r#"#!/bin/sh
-# Coraline auto-sync hook
-# This hook keeps the graph in sync after each commit.
-# To remove: coraline hooks remove
+/// The git hooks Coraline can install, each running a background sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitHook {
+    /// After each commit.
+    PostCommit,
+    /// After `git merge` and `git pull`.
+    PostMerge,
+    /// After a branch switch or a checkout of files.
+    PostCheckout,
+    /// After `git commit --amend` and `git rebase`.
+    PostRewrite,
+}
+
+impl GitHook {
+    pub const ALL: [Self; 4] = [
+        Self::PostCommit,
+        Self::PostMerge,
+        Self::PostCheckout,
+        Self::PostRewrite,
+    ];
+
+    /// The hook's file name in `.git/hooks/`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::PostCommit => "post-commit",
+            Self::PostMerge => "post-merge",
+            Self::PostCheckout => "post-checkout",
+            Self::PostRewrite => "post-rewrite",
+        }
+    }
+
+    const fn title(self) -> &'static str {
+        match self {
+            Self::PostCommit => "Post-commit",
+            Self::PostMerge => "Post-merge",
+            Self::PostCheckout => "Post-checkout",
+            Self::PostRewrite => "Post-rewrite",
+        }
+    }
+
+    const fn trigger(self) -> &'static str {
+        match self {
+            Self::PostCommit => "each commit",
+            Self::PostMerge => "each merge or pull",
+            Self::PostCheckout => "each checkout",
+            Self::PostRewrite => "each amend or rebase",
+        }
+    }
+
+    /// The commit the working tree was at before the git operation, when
+    /// a diff against it covers every file the operation touched. A
+    /// checkout can restore files to their committed content, which no such
+    /// diff shows, and a rebase or amend has no single starting point, so
+    /// those hooks run a full sync.
+    const fn previous_rev(self) -> Option<&'static str> {
+        match self {
+            Self::PostCommit => Some("HEAD~1"),
+            Self::PostMerge => Some("ORIG_HEAD"),
+            Self::PostCheckout | Self::PostRewrite => None,
+        }
+    }
+}
+
+/// A tool that owns the repository's hooks and may overwrite scripts
+/// written around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookManager {
+    /// Hook scripts live in `.husky/`; `core.hooksPath` points at its
+    /// generated wrappers.
+    Husky,
+    /// Hooks are declared in `lefthook.yml`, and `lefthook install`
+    /// rewrites `.git/hooks`.
+    Lefthook,
+}
 
+impl HookManager {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Husky => "husky",
+            Self::Lefthook => "lefthook",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "husky" => Some(Self::Husky),
+            "lefthook" => Some(Self::Lefthook),
+            _ => None,
+        }
+    }
+}
+
+const LEFTHOOK_CONFIGS: &[&str] = &[
+    "lefthook.yml",
+    "lefthook.yaml",
+    ".lefthook.yml",
+    ".lefthook.yaml",
+];
+
+const SYNC_COMMAND: &str = "coraline sync --quiet --throttle";
+
+/// The sync `hook` runs: only the files the git operation changed, with a
+/// full sync as the fallback, as on the first commit, which has no parent.
+fn sync_command(hook: GitHook) -> String {
+    hook.previous_rev().map_or_else(
+        || SYNC_COMMAND.to_string(),
+        |rev| format!("{SYNC_COMMAND} --since {rev} || {SYNC_COMMAND}"),
+    )
+}
+
+/// Config for `manager` that runs the sync from each of `hooks`: a
+/// `lefthook.yml` fragment, or the `.husky/<hook>` files.
+pub fn hook_snippet(manager: HookManager, hooks: &[GitHook]) -> String {
+    let mut out = String::new();
+    for &hook in hooks {
+        let _ = match manager {
+            HookManager::Lefthook => writeln!(
+                out,
+                "{}:\n  commands:\n    coraline-sync:\n      run: (({}) >/dev/null 2>&1 &)",
+                hook.name(),
+                sync_command(hook)
+            ),
+            HookManager::Husky => writeln!(
+                out,
+                "# .husky/{}\n(({}) >/dev/null 2>&1 &)",
+                hook.name(),
+                sync_command(hook)
+            ),
+        };
+        out.push('\n');
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
+fn hook_script(hook: GitHook) -> String {
+    // `git checkout -b` and `git switch -c` move to a new branch without
+    // changing a file.
+    let guard = if hook == GitHook::PostCheckout {
+        "\nif [ \"$3\" = \"1\" ] && [ \"$1\" = \"$2\" ]; then\n  exit 0\nfi\n"
+    } else {
+        ""
+    };
+    format!(
+        r#"#!/bin/sh
+{CODEGRAPH_MARKER}
+# This hook keeps the graph in sync after {trigger}.
+# To remove: coraline hooks remove
+{guard}
 (
   if [ ! -d ".coraline" ]; then
 	exit 0
   fi
 
     if command -v coraline >/dev/null 2>&1; then
-	coraline sync --quiet 2>/dev/null &
+	({sync}) 2>/dev/null &
     elif command -v cargo >/dev/null 2>&1 && [ -f "Cargo.toml" ]; then
-	cargo run -q -p coraline --bin coraline -- sync --quiet 2>/dev/null &
+	cargo run -q -p coraline --bin coraline -- sync --quiet --throttle 2>/dev/null &
   fi
 ) &
 
 exit 0
-"#;
-
-    script.to_string()
+"#,
+        trigger = hook.trigger(),
+        sync = sync_command(hook)
+    )
 }
 
 #[derive(Debug)]
@@ -50,27 +208,100 @@ pub struct HookRemoveResult {
 pub struct GitHooksManager {
     git_dir: PathBuf,
     hooks_dir: PathBuf,
+    manager: Option<HookManager>,
 }
 
 impl GitHooksManager {
+    /// Hooks go where git runs them: `core.hooksPath` when set (relative
+    /// to `project_root`), else `.git/hooks`. Under husky they go in
+    /// `.husky/`, beside the project's own hook scripts, rather than among
+    /// the wrappers husky regenerates.
+    ///
+    /// In a linked worktree or a submodule, where `.git` is a `gitdir:`
+    /// file, hooks go in the git directory it points to (for a worktree,
+    /// the common one, since git runs the same hooks for every worktree).
     pub fn new(project_root: &Path) -> Self {
-        let git_dir = project_root.join(".git");
-        let hooks_dir = git_dir.join("hooks");
-        Self { git_dir, hooks_dir }
+        let git_dir = worktree::git_dir(project_root).unwrap_or_else(|| project_root.join(".git"));
+        let configured = utils::git(
+            project_root,
+            &["config", "--path", "--get", "core.hooksPath"],
+        )
+        .ok()
+        .map(|out| out.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .map(|path| project_root.join(path));
+        let (hooks_dir, manager) = match configured {
+            Some(dir)
+                if dir.file_name().is_some_and(|n| n == "_") && is_husky_dir(dir.parent()) =>
+            {
+                let husky = dir.parent().map_or_else(|| dir.clone(), Path::to_path_buf);
+                (husky, Some(HookManager::Husky))
+            }
+            Some(dir) if is_husky_dir(Some(&dir)) => (dir, Some(HookManager::Husky)),
+            Some(dir) => (dir, None),
+            None => (git_dir.join("hooks"), None),
+        };
+        let manager = manager.or_else(|| {
+            LEFTHOOK_CONFIGS
+                .iter()
+                .any(|name| project_root.join(name).is_file())
+                .then_some(HookManager::Lefthook)
+        });
+        Self {
+            git_dir,
+            hooks_dir,
+            manager,
+        }
     }
 
     pub fn is_git_repository(&self) -> bool {
         self.git_dir.is_dir()
     }
 
+    /// Where hooks are installed.
+    pub fn hooks_dir(&self) -> &Path {
+        &self.hooks_dir
+    }
+
+    /// Whether the hooks directory is `.git/hooks`.
+    pub fn is_default_hooks_dir(&self) -> bool {
+        self.hooks_dir == self.git_dir.join("hooks")
+    }
+
+    /// The hook manager the repository uses, if one is detected.
+    pub const fn manager(&self) -> Option<HookManager> {
+        self.manager
+    }
+
+    /// Whether the post-commit hook is Coraline's.
     pub fn is_hook_installed(&self) -> bool {
-        let hook_path = self.hooks_dir.join(POST_COMMIT_HOOK);
+        self.is_installed(GitHook::PostCommit)
+    }
+
+    /// Whether `hook` is Coraline's.
+    pub fn is_installed(&self, hook: GitHook) -> bool {
+        let hook_path = self.hooks_dir.join(hook.name());
         let content = fs::read_to_string(&hook_path).unwrap_or_default();
         content.contains(CODEGRAPH_MARKER)
     }
 
+    /// The hooks of [`GitHook::ALL`] that are Coraline's.
+    pub fn installed_hooks(&self) -> Vec<GitHook> {
+        GitHook::ALL
+            .into_iter()
+            .filter(|&hook| self.is_installed(hook))
+            .collect()
+    }
+
+    /// Install the post-commit hook.
     pub fn install_hook(&self) -> HookInstallResult {
-        let hook_path = self.hooks_dir.join(POST_COMMIT_HOOK);
+        self.install(GitHook::PostCommit)
+    }
+
+    /// Install `hook`, backing up a hook that is not Coraline's to
+    /// `<hook>.coraline-backup`.
+    pub fn install(&self, hook: GitHook) -> HookInstallResult {
+        let hook_path = self.hooks_dir.join(hook.name());
 
         if !self.is_git_repository() {
             return HookInstallResult {
@@ -113,7 +344,7 @@ impl GitHooksManager {
             }
         }
 
-        if let Err(err) = fs::write(&hook_path, post_commit_script()) {
+        if let Err(err) = fs::write(&hook_path, hook_script(hook)) {
             return HookInstallResult {
                 success: false,
                 hook_path,
@@ -136,20 +367,26 @@ impl GitHooksManager {
         HookInstallResult {
             success: true,
             hook_path,
-            message: "Post-commit hook installed.".to_string(),
+            message: format!("{} hook installed.", hook.title()),
             previous_hook_backed_up,
             backup_path,
         }
     }
 
+    /// Remove the post-commit hook.
     pub fn remove_hook(&self) -> HookRemoveResult {
-        let hook_path = self.hooks_dir.join(POST_COMMIT_HOOK);
+        self.remove(GitHook::PostCommit)
+    }
+
+    /// Remove `hook` if it is Coraline's, restoring the hook it replaced.
+    pub fn remove(&self, hook: GitHook) -> HookRemoveResult {
+        let hook_path = self.hooks_dir.join(hook.name());
         let backup_path = hook_path.with_extension("coraline-backup");
 
         if !hook_path.exists() {
             return HookRemoveResult {
                 success: true,
-                message: "No post-commit hook found.".to_string(),
+                message: format!("No {} hook found.", hook.name()),
                 restored_from_backup: false,
             };
         }
@@ -158,7 +395,7 @@ impl GitHooksManager {
         if !content.contains(CODEGRAPH_MARKER) {
             return HookRemoveResult {
                 success: false,
-                message: "Post-commit hook was not installed by Coraline.".to_string(),
+                message: format!("{} hook was not installed by Coraline.", hook.title()),
                 restored_from_backup: false,
             };
         }
@@ -175,25 +412,33 @@ impl GitHooksManager {
             if let Err(err) = fs::rename(&backup_path, &hook_path) {
                 return HookRemoveResult {
                     success: true,
-                    message: format!("Hook removed. Failed to restore backup: {err}"),
+                    message: format!(
+                        "{} hook removed. Failed to restore backup: {err}",
+                        hook.title()
+                    ),
                     restored_from_backup: false,
                 };
             }
             return HookRemoveResult {
                 success: true,
-                message: "Hook removed. Previous hook restored.".to_string(),
+                message: format!("{} hook removed. Previous hook restored.", hook.title()),
                 restored_from_backup: true,
             };
         }
 
         HookRemoveResult {
             success: true,
-            message: "Hook removed.".to_string(),
+            message: format!("{} hook removed.", hook.title()),
             restored_from_backup: false,
         }
     }
 }
 
+fn is_husky_dir(dir: Option<&Path>) -> bool {
+    dir.and_then(Path::file_name)
+        .is_some_and(|name| name == ".husky")
+}
+
 fn make_executable(path: &Path) -> std::io::Result<()> {
     #[cfg(unix)]
     {
@@ -209,3 +454,218 @@ fn make_executable(path: &Path) -> std::io::Result<()> {
         Ok(())
     }
 }
+
+/// Body delivered to post-sync hooks.
+#[derive(Debug, Serialize)]
+struct PostSyncPayload<'a> {
+    event: &'static str,
+    project_root: String,
+    result: &'a SyncResult,
+}
+
+/// Run the configured post-sync command and webhook for a completed sync.
+///
+/// Hooks only fire when the sync added, modified, or removed files. Hook
+/// failures are logged and never fail the sync itself.
+pub fn run_post_sync_hooks(project_root: &Path, config: &SyncConfig, result: &SyncResult) {
+    if config.post_sync_command.is_none() && config.post_sync_webhook.is_none() {
+        return;
+    }
+    if result.files_added + result.files_modified + result.files_removed == 0 {
+        return;
+    }
+
+    let payload = PostSyncPayload {
+        event: "sync_completed",
+        project_root: project_root.display().to_string(),
+        result,
+    };
+    let body = match serde_json::to_string(&payload) {
+        Ok(body) => body,
+        Err(err) => {
+            warn!("Failed to serialize post-sync payload: {err}");
+            return;
+        }
+    };
+    let timeout = Duration::from_secs(config.post_sync_timeout_secs.max(1));
+
+    if let Some(command) = &config.post_sync_command
+        && let Err(err) = run_post_sync_command(project_root, command, &body, timeout)
+    {
+        warn!("Post-sync command failed: {err}");
+    }
+    if let Some(url) = &config.post_sync_webhook
+        && let Err(err) = post_sync_webhook(url, &body, timeout)
+    {
+        warn!("Post-sync webhook failed: {err}");
+    }
+}
+
+fn run_post_sync_command(
+    project_root: &Path,
+    command: &str,
+    body: &str,
+    timeout: Duration,
+) -> std::io::Result<()> {
+    run_hook_command(
+        project_root,
+        command,
+        &[("CORALINE_SYNC_RESULT", body)],
+        body,
+        timeout,
+    )
+}
+
+/// Run `command` through the platform shell in `project_root`, with `body`
+/// on stdin and `env` (plus `CORALINE_PROJECT_ROOT`) set, killing it after
+/// `timeout`. Exiting non-zero is an error.
+pub(crate) fn run_hook_command(
+    project_root: &Path,
+    command: &str,
+    env: &[(&str, &str)],
+    body: &str,
+    timeout: Duration,
+) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+
+    let mut child = cmd
+        .current_dir(project_root)
+        .env("CORALINE_PROJECT_ROOT", project_root)
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // Written from its own thread, which closes stdin once done, so a
+    // command that never reads a body larger than the pipe buffer still
+    // times out. The thread is not joined: a process the command left
+    // behind may hold the pipe open. A command that ignores stdin may close
+    // it early; that is not an error.
+    if let Some(mut stdin) = child.stdin.take() {
+        let body = body.to_string();
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(body.as_bytes());
+        });
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return if status.success() {
+                debug!(command, "hook command finished");
+                Ok(())
+            } else {
+                Err(std::io::Error::other(format!(
+                    "command exited with {status}"
+                )))
+            };
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(std::io::Error::other(format!(
+                "command timed out after {}s",
+                timeout.as_secs()
+            )));
+        }
+        std::thread::sleep(Duration::from_millis(25));
+    }
+}
+
+/// Cap the worker threads used for parsing (the global rayon pool) and
+/// embedding inference. `0` leaves the defaults. Only the first call can
+/// size the rayon pool, so call this before any indexing work.
+pub fn limit_threads(threads: usize) {
+    if threads == 0 {
+        return;
+    }
+    THREAD_LIMIT.store(threads, Ordering::Relaxed);
+    if let Err(err) = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+    {
+        debug!("Thread pool already initialised: {err}");
+    }
+}
+
+/// The cap set by [`limit_threads`], if any.
+pub fn thread_limit() -> Option<usize> {
+    match THREAD_LIMIT.load(Ordering::Relaxed) {
+        0 => None,
+        n => Some(n),
+    }
+}
+
+/// Lower this process's CPU priority by `nice` and, when `idle_io` is set,
+/// move it to the idle IO class.
+///
+/// Best-effort: uses `renice` (Unix) and `ionice` (Linux) and does nothing
+/// where they are unavailable.
+pub fn lower_priority(nice: i32, idle_io: bool) {
+    let pid = std::process::id().to_string();
+    if cfg!(unix) && nice > 0 {
+        run_quietly("renice", &["-n", &nice.to_string(), "-p", &pid]);
+    }
+    if cfg!(target_os = "linux") && idle_io {
+        run_quietly("ionice", &["-c", "3", "-p", &pid]);
+    }
+}
+
+fn run_quietly(program: &str, args: &[&str]) {
+    let status = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => debug!(program, "lowered process priority"),
+        Ok(status) => debug!(program, %status, "could not lower process priority"),
+        Err(err) => debug!(program, error = %err, "could not lower process priority"),
+    }
+}
+
+fn post_sync_webhook(url: &str, body: &str, timeout: Duration) -> std::io::Result<()> {
+    let agent = ureq::Agent::new_with_config(
+        ureq::config::Config::builder()
+            .timeout_global(Some(timeout))
+            .user_agent("coraline-post-sync")
+            .build(),
+    );
+
+    agent
+        .post(url)
+        .header("Content-Type", "application/json")
+        .send(body)
+        .map_err(|err| std::io::Error::other(err.to_string()))?;
+    debug!("Post-sync webhook delivered to {url}");
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::expect_used)]
+    fn test_hook_command_times_out_without_reading_a_large_body() {
+        let temp = tempfile::TempDir::new().expect("Failed to create temp directory");
+        let body = "x".repeat(1 << 20);
+        let started = Instant::now();
+        let result = run_hook_command(temp.path(), "sleep 5", &[], &body, Duration::from_secs(1));
+        assert!(result.is_err_and(|err| err.to_string().contains("timed out")));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
@@ -0,0 +1,263 @@
+#![forbid(unsafe_code)]
+
+//! `TODO`, `FIXME`, and `HACK` comments.
+//!
+//! Each marker in a comment becomes a `todo` node named after its note, with
+//! the note and the comment lines continuing it as the docstring and the
+//! marker line itself, such as `FIXME(ana): retry on timeout`, as the
+//! signature; a bare marker names its node. The node is contained
+//! by its enclosing function, method, or type, or by the one the comment
+//! directly precedes, and otherwise by the file. `coraline query TODO --kind
+//! todo` lists them, and a context shows the ones of each entry point.
+//!
+//! A marker counts only when it starts a comment line, after the comment
+//! punctuation, so a comment merely mentioning "the TODO list" or code
+//! followed by "see TODO: below" is left alone. Markers are upper case.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use tree_sitter::Node as TsNode;
+
+use crate::types::{Edge, EdgeKind, Language, Node, NodeKind};
+use crate::utils::node_id_for_symbol;
+
+/// A marker leading a comment line.
+static MARKER: LazyLock<Option<Regex>> =
+    LazyLock::new(|| Regex::new(r"^[\s/*#!;%{<\-]*\b(TODO|FIXME|HACK)\b(\([^)]*\))?:?(.*)$").ok());
+
+/// Comment punctuation opening a line.
+const OPENERS: &str = "/*#!;%{<-";
+
+/// The `todo` nodes of the comments under `root`, each with a `contains`
+/// edge from its symbol among `nodes`, or from the file.
+pub(crate) fn extract_todos(
+    root: TsNode,
+    file_path: &str,
+    source: &str,
+    language: Language,
+    nodes: &[Node],
+    root_id: &str,
+    now_ms: i64,
+) -> (Vec<Node>, Vec<Edge>) {
+    let mut todos = Vec::new();
+    let mut edges = Vec::new();
+    let lines: Vec<&str> = source.lines().collect();
+    let mut stack = vec![root];
+    let mut cursor = root.walk();
+    while let Some(current) = stack.pop() {
+        if !current.kind().contains("comment") {
+            let pushed = stack.len();
+            stack.extend(current.children(&mut cursor));
+            if let Some(children) = stack.get_mut(pushed..) {
+                children.reverse();
+            }
+            continue;
+        }
+        let Ok(text) = current.utf8_text(source.as_bytes()) else {
+            continue;
+        };
+        let first_line = current.start_position().row;
+        let owner = owner_of(current, &lines, nodes);
+        let text_lines: Vec<&str> = text.lines().collect();
+        for (offset, line) in text_lines.iter().enumerate() {
+            let Some((marker, author, note, column)) = parse(line) else {
+                continue;
+            };
+            let row = first_line + offset;
+            let column = if offset == 0 {
+                current.start_position().column + column
+            } else {
+                column
+            };
+            let rest = text_lines.get(offset + 1..).unwrap_or_default();
+            let more = continuation(current, rest, row, source);
+            let line_number = i64::try_from(row + 1).unwrap_or(i64::MAX);
+            let end_line =
+                line_number.saturating_add(i64::try_from(more.len()).unwrap_or(i64::MAX));
+            let column = i64::try_from(column).unwrap_or(i64::MAX);
+            let qualified_name = format!(
+                "{}::{marker}",
+                owner.map_or(file_path, |owner| owner.qualified_name.as_str())
+            );
+            let id = node_id_for_symbol(file_path, "todo", &qualified_name, line_number, column);
+            edges.push(Edge {
+                source: owner.map_or(root_id, |owner| owner.id.as_str()).to_string(),
+                target: id.clone(),
+                kind: EdgeKind::Contains,
+                metadata: None,
+                line: Some(line_number),
+                column: Some(column),
+            });
+            let signature = if note.is_empty() {
+                format!("{marker}{author}")
+            } else {
+                format!("{marker}{author}: {note}")
+            };
+            let body = std::iter::once(note)
+                .chain(more)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            todos.push(Node {
+                id,
+                kind: NodeKind::Todo,
+                name: if note.is_empty() { marker } else { note }.to_string(),
+                qualified_name,
+                file_path: file_path.to_string(),
+                language,
+                start_line: line_number,
+                end_line,
+                start_column: column,
+                end_column: column.saturating_add(i64::try_from(marker.len()).unwrap_or(i64::MAX)),
+                docstring: (!body.is_empty()).then_some(body),
+                signature: Some(signature),
+                visibility: None,
+                is_exported: false,
+                is_async: false,
+                is_static: false,
+                is_abstract: false,
+                decorators: None,
+                type_parameters: None,
+                updated_at: now_ms,
+            });
+        }
+    }
+    (todos, edges)
+}
+
+/// The marker, `(author)` (or nothing), and note of a comment line, and the
+/// column of the marker.
+fn parse(line: &str) -> Option<(&str, &str, &str, usize)> {
+    let caps = MARKER.as_ref()?.captures(line)?;
+    let marker = caps.get(1)?;
+    let author = caps.get(2).map_or("", |author| author.as_str());
+    let note = caps.get(3).map_or("", |note| note.as_str());
+    let note = strip(note).trim_start_matches(':').trim_start();
+    Some((marker.as_str(), author, note, marker.start()))
+}
+
+/// The text of a comment line, without its comment punctuation.
+fn strip(line: &str) -> &str {
+    line.trim_end()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim_end_matches("%}")
+        .trim_start_matches(|c: char| c.is_whitespace() || OPENERS.contains(c))
+        .trim_end()
+}
+
+/// The lines continuing a note on `row`: those left in its comment, `rest`,
+/// then the comments lined up right below it, up to a blank line or the
+/// next marker.
+fn continuation<'s>(
+    comment: TsNode,
+    rest: &[&'s str],
+    row: usize,
+    source: &'s str,
+) -> Vec<&'s str> {
+    let continues =
+        |line: &'s str| Some(strip(line)).filter(|text| !text.is_empty() && parse(line).is_none());
+    let mut lines = Vec::new();
+    for line in rest {
+        let Some(text) = continues(line) else {
+            return lines;
+        };
+        lines.push(text);
+    }
+    let column = comment.start_position().column;
+    let mut next = comment.next_sibling();
+    while let Some(sibling) = next
+        && sibling.kind().contains("comment")
+        && sibling.start_position().row == row + lines.len() + 1
+        && sibling.start_position().column == column
+        && let Ok(text) = sibling.utf8_text(source.as_bytes())
+    {
+        for line in text.lines() {
+            let Some(text) = continues(line) else {
+                return lines;
+            };
+            lines.push(text);
+        }
+        next = sibling.next_sibling();
+    }
+    lines
+}
+
+/// Symbols a comment can belong to.
+const fn is_owner(kind: NodeKind) -> bool {
+    matches!(
+        kind,
+        NodeKind::Function
+            | NodeKind::Method
+            | NodeKind::Class
+            | NodeKind::Struct
+            | NodeKind::Interface
+            | NodeKind::Trait
+            | NodeKind::Protocol
+            | NodeKind::Enum
+            | NodeKind::TypeAlias
+            | NodeKind::Module
+            | NodeKind::Namespace
+            | NodeKind::Component
+    )
+}
+
+/// The symbol a comment on lines of its own precedes, past further
+/// comments, attributes, and decorators, else the innermost one holding it.
+fn owner_of<'n>(comment: TsNode, lines: &[&str], nodes: &'n [Node]) -> Option<&'n Node> {
+    let position = comment.start_position();
+    let start = i64::try_from(position.row + 1).unwrap_or(i64::MAX);
+    let end = i64::try_from(comment.end_position().row + 1).unwrap_or(i64::MAX);
+    let own_line = lines
+        .get(position.row)
+        .and_then(|line| line.get(..position.column))
+        .is_some_and(|before| before.trim().is_empty());
+    let mut next = if own_line { end + 1 } else { end };
+    while usize::try_from(next - 1)
+        .ok()
+        .and_then(|index| lines.get(index))
+        .is_some_and(|line| {
+            let line = line.trim_start();
+            ["//", "#", "@", "/*"]
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+        })
+    {
+        next += 1;
+    }
+    let owners = || nodes.iter().filter(|n| is_owner(n.kind));
+    owners()
+        .filter(|n| end < n.start_line && n.start_line <= next)
+        .min_by_key(|n| n.start_line)
+        .or_else(|| {
+            owners()
+                .filter(|n| n.start_line <= start && end <= n.end_line)
+                .min_by_key(|n| n.end_line - n.start_line)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("// TODO: handle retries"),
+            Some(("TODO", "", "handle retries", 3))
+        );
+        assert_eq!(
+            parse("    # FIXME(ana) drop this"),
+            Some(("FIXME", "(ana)", "drop this", 6))
+        );
+        assert_eq!(
+            parse("/* HACK - until v2 */"),
+            Some(("HACK", "", "until v2", 3))
+        );
+        assert_eq!(parse("// parse the header, TODO: trailers"), None);
+        assert_eq!(parse("<!-- TODO -->"), Some(("TODO", "", "", 5)));
+        assert_eq!(parse("// keeps the TODO list sorted"), None);
+        assert_eq!(parse("// TODOS and todo: are not markers"), None);
+    }
+}
@@ -1,14 +1,19 @@
 #![forbid(unsafe_code)]
 
-//! MCP tool for doc-accuracy auditing.
+//! MCP tools for auditing docs and code against the graph.
 
 use std::path::PathBuf;
 
 use serde_json::{Value, json};
 
-use crate::audit;
+use crate::dead_code::{self, Confidence};
+use crate::{audit, config, db};
 
-use super::{Tool, ToolError, ToolResult};
+use super::responses::{
+    DeadCodeEntry, DeadCodeResponse, DocAuditResponse, DocAuditSummary, NodeRef, StaleRef,
+    UndocumentedSymbol, to_result,
+};
+use super::{Tool, ToolContext, ToolError, ToolResult};
 
 /// MCP tool that audits documentation coverage and accuracy against the
 /// indexed code graph.
@@ -33,6 +38,10 @@ impl Tool for AuditDocsTool {
         "coraline_audit_docs"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Audit documentation accuracy and coverage against the indexed code graph. \
          Returns two lists: (1) stale_refs — inline `code_span` references in \
@@ -66,7 +75,7 @@ impl Tool for AuditDocsTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let show_undocumented = params
             .get("show_undocumented")
             .and_then(Value::as_bool)
@@ -88,14 +97,12 @@ impl Tool for AuditDocsTool {
                 .stale_refs
                 .iter()
                 .take(limit)
-                .map(|r| {
-                    json!({
-                        "reference": r.reference_name,
-                        "doc_file": r.doc_file,
-                        "section": r.doc_section,
-                        "line": r.line,
-                        "column": r.column
-                    })
+                .map(|r| StaleRef {
+                    reference: r.reference_name.clone(),
+                    doc_file: r.doc_file.clone(),
+                    section: r.doc_section.clone(),
+                    line: r.line,
+                    column: r.column,
                 })
                 .collect::<Vec<_>>()
         } else {
@@ -107,29 +114,113 @@ impl Tool for AuditDocsTool {
                 .undocumented_exports
                 .iter()
                 .take(limit)
-                .map(|u| {
-                    json!({
-                        "name": u.name,
-                        "qualified_name": u.qualified_name,
-                        "kind": u.kind,
-                        "file": u.file_path,
-                        "line": u.start_line
-                    })
+                .map(|u| UndocumentedSymbol {
+                    name: u.name.clone(),
+                    qualified_name: u.qualified_name.clone(),
+                    kind: u.kind.clone(),
+                    file: u.file_path.clone(),
+                    line: u.start_line,
                 })
                 .collect::<Vec<_>>()
         } else {
             Vec::new()
         };
 
-        Ok(json!({
-            "summary": {
-                "doc_files_indexed": report.doc_files_indexed,
-                "doc_sections_indexed": report.doc_sections_indexed,
-                "stale_refs_count": report.stale_refs.len(),
-                "undocumented_exports_count": report.undocumented_exports.len()
+        to_result(&DocAuditResponse {
+            summary: DocAuditSummary {
+                doc_files_indexed: report.doc_files_indexed,
+                doc_sections_indexed: report.doc_sections_indexed,
+                stale_refs_count: report.stale_refs.len(),
+                undocumented_exports_count: report.undocumented_exports.len(),
+            },
+            stale_refs: stale,
+            undocumented_exports: undoc,
+        })
+    }
+}
+
+/// MCP tool that lists functions, types, and constants nothing in the
+/// project uses, with a confidence level for each.
+pub struct DeadCodeTool {
+    project_root: PathBuf,
+}
+
+impl DeadCodeTool {
+    pub const fn new(project_root: PathBuf) -> Self {
+        Self { project_root }
+    }
+}
+
+impl Tool for DeadCodeTool {
+    fn name(&self) -> &'static str {
+        "coraline_dead_code"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Find symbols with no incoming calls, references, or imports: candidates for deletion. \
+         Each has a confidence (high, medium, low) and the reason it is not higher. Entry \
+         points (main, constructors, tests, [analysis] entry_points and public_api) are \
+         left out."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "min_confidence": {
+                    "type": "string",
+                    "description": "Lowest confidence to list (default: low, i.e. all)",
+                    "enum": ["low", "medium", "high"],
+                    "default": "low"
+                },
+                "limit": {
+                    "type": "number",
+                    "description": "Maximum candidates to return (default: 100).",
+                    "default": 100
+                }
             },
-            "stale_refs": stale,
-            "undocumented_exports": undoc
-        }))
+            "required": []
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let min_confidence = match params.get("min_confidence").and_then(Value::as_str) {
+            Some(value) => Confidence::parse(value).ok_or_else(|| {
+                ToolError::invalid_params(format!(
+                    "min_confidence must be low, medium, or high, not '{value}'"
+                ))
+            })?,
+            None => Confidence::Low,
+        };
+        let limit = params
+            .get("limit")
+            .and_then(Value::as_u64)
+            .map_or(100, |n| usize::try_from(n).unwrap_or(100));
+
+        let cfg = config::load_toml_config(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to load config", e))?;
+        let conn = db::open_database(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+        let report = dead_code::find_dead_code(&conn, &cfg.analysis, min_confidence)
+            .map_err(|e| ToolError::internal_error(e.to_string()))?;
+
+        to_result(&DeadCodeResponse {
+            total: report.candidates.len(),
+            entry_points_skipped: report.entry_points_skipped,
+            candidates: report
+                .candidates
+                .iter()
+                .take(limit)
+                .map(|c| DeadCodeEntry {
+                    node: NodeRef::from(&c.node),
+                    confidence: c.confidence,
+                    reason: c.reason.to_string(),
+                })
+                .collect(),
+        })
     }
 }
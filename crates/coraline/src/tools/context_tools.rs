@@ -7,9 +7,11 @@ use std::path::PathBuf;
 use serde_json::{Value, json};
 
 use crate::context;
-use crate::types::{BuildContextOptions, ContextFormat};
+use crate::diff_context::{self, DiffContextOptions};
+use crate::types::{BuildContextOptions, ContextFormat, TaskInput};
 
-use super::{Tool, ToolError, ToolResult};
+use super::responses::{MarkdownContextResponse, to_result};
+use super::{Tool, ToolContext, ToolError, ToolResult, string_list};
 
 /// Tool for building context for a task or query
 pub struct BuildContextTool {
@@ -27,6 +29,10 @@ impl Tool for BuildContextTool {
         "coraline_context"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Build relevant code context for a task or issue description. Returns structured context with relevant symbols, code blocks, and file references."
     }
@@ -39,6 +45,11 @@ impl Tool for BuildContextTool {
                     "type": "string",
                     "description": "Task or issue description to build context for"
                 },
+                "queries": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Aspects of the task to search separately (e.g. [\"parser\", \"error handling\"]); their contexts are merged, each node attributed to the queries that reached it"
+                },
                 "max_nodes": {
                     "type": "number",
                     "description": "Maximum number of relevant nodes to include",
@@ -64,10 +75,19 @@ impl Tool for BuildContextTool {
                     "description": "Depth for graph traversal from entry points",
                     "default": 1
                 },
+                "search_limit": {
+                    "type": "number",
+                    "description": "Search candidates ranked for entry points (default 3 × max_nodes)"
+                },
+                "min_score": {
+                    "type": "number",
+                    "description": "Minimum relevance (0–1) for an entry point",
+                    "default": 0.0
+                },
                 "format": {
                     "type": "string",
-                    "description": "Output format",
-                    "enum": ["markdown", "json"],
+                    "description": "Output format: markdown, structured json, xml tags, or repo_map (file paths and signatures only)",
+                    "enum": ["markdown", "json", "xml", "repo_map"],
                     "default": "markdown"
                 }
             },
@@ -76,11 +96,15 @@ impl Tool for BuildContextTool {
     }
 
     #[allow(clippy::cast_possible_truncation)]
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, ctx: &ToolContext) -> ToolResult {
         let task = params
             .get("task")
             .and_then(Value::as_str)
             .ok_or_else(|| ToolError::invalid_params("task must be a string"))?;
+        let task = TaskInput::with_queries(
+            task.to_string(),
+            string_list(&params, "queries")?.unwrap_or_default(),
+        );
 
         let max_nodes = params
             .get("max_nodes")
@@ -101,9 +125,12 @@ impl Tool for BuildContextTool {
             .map(|n| n as usize);
 
         let format = match params.get("format").and_then(Value::as_str) {
-            Some("json") => Some(ContextFormat::Json),
-            Some("markdown") | None => Some(ContextFormat::Markdown),
-            _ => None,
+            Some(name) => Some(ContextFormat::parse(name).ok_or_else(|| {
+                ToolError::invalid_params(format!(
+                    "Unknown format '{name}'; expected markdown, json, xml, or repo_map"
+                ))
+            })?),
+            None => Some(ContextFormat::Markdown),
         };
 
         let options = BuildContextOptions {
@@ -123,24 +150,117 @@ impl Tool for BuildContextTool {
                 .map(|f| f as f32),
         };
 
-        let context = context::build_context(&self.project_root, task, &options)
-            .map_err(|e| ToolError::internal_error(format!("Failed to build context: {e}")))?;
+        ctx.check_cancelled()?;
+        let context = context::build_task_context(&self.project_root, &task, &options)
+            .map_err(|e| ToolError::failed("Failed to build context", e))?;
 
         // If format is JSON, return structured data; otherwise return as text
         match format {
             Some(ContextFormat::Json) => {
                 // Parse the JSON string back to Value
-                serde_json::from_str(&context).map_err(|e| {
-                    ToolError::internal_error(format!("Failed to parse context JSON: {e}"))
-                })
+                serde_json::from_str(&context)
+                    .map_err(|e| ToolError::failed("Failed to parse context JSON", e))
             }
             _ => {
-                // Return markdown as text content
-                Ok(json!({
-                    "context": context,
-                    "format": "markdown"
-                }))
+                // Return the rendered text as content
+                to_result(&MarkdownContextResponse {
+                    context,
+                    format: format
+                        .unwrap_or(ContextFormat::Markdown)
+                        .as_str()
+                        .to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// Tool for building review context from the working tree's git diff
+pub struct DiffContextTool {
+    project_root: PathBuf,
+}
+
+impl DiffContextTool {
+    pub const fn new(project_root: PathBuf) -> Self {
+        Self { project_root }
+    }
+}
+
+impl Tool for DiffContextTool {
+    fn name(&self) -> &'static str {
+        "coraline_diff_context"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Build review context for the uncommitted changes, or the changes since a revision. Maps changed lines to symbols and lists each with its callers, callees, and code."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "rev": {
+                    "type": "string",
+                    "description": "Revision to compare the working tree with (branch, tag, or commit)",
+                    "default": "HEAD"
+                },
+                "max_code_blocks": {
+                    "type": "number",
+                    "description": "Maximum number of code blocks to include"
+                },
+                "include_code": {
+                    "type": "boolean",
+                    "description": "Whether to include the changed symbols' code",
+                    "default": true
+                },
+                "format": {
+                    "type": "string",
+                    "description": "Output format",
+                    "enum": ["markdown", "json"],
+                    "default": "markdown"
+                }
             }
+        })
+    }
+
+    fn execute(&self, params: Value, ctx: &ToolContext) -> ToolResult {
+        let json_format = match params.get("format").and_then(Value::as_str) {
+            None | Some("markdown") => false,
+            Some("json") => true,
+            Some(other) => {
+                return Err(ToolError::invalid_params(format!(
+                    "Unknown format '{other}'; expected markdown or json"
+                )));
+            }
+        };
+        let options = DiffContextOptions {
+            base: params
+                .get("rev")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            max_code_blocks: params
+                .get("max_code_blocks")
+                .and_then(Value::as_u64)
+                .and_then(|n| usize::try_from(n).ok()),
+            max_code_block_size: None,
+            include_code: params.get("include_code").and_then(Value::as_bool),
+        };
+
+        ctx.check_cancelled()?;
+        let context = diff_context::build_diff_context(&self.project_root, &options)
+            .map_err(|e| ToolError::failed("Failed to build diff context", e))?;
+
+        if json_format {
+            to_result(&context)
+        } else {
+            to_result(&MarkdownContextResponse {
+                context: diff_context::render_diff_context(&context, ContextFormat::Markdown),
+                format: "markdown".to_string(),
+            })
         }
     }
 }
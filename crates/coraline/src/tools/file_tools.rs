@@ -3,16 +3,19 @@
 //! File system tools for reading files and listing directory contents.
 
 use std::path::PathBuf;
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 use std::sync::Mutex;
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 use std::time::{Duration, Instant};
 
 use serde_json::{Value, json};
 
 use crate::db;
 
-use super::{Tool, ToolError, ToolResult};
+use super::responses::{
+    ConfigResponse, DirEntry, FileNode, FileNodesResponse, FindFileResponse, ListDirResponse,
+    ReadFileResponse, StatusResponse, StatusStats, SyncResponse, UpdateConfigResponse, to_result,
+};
+use super::responses::{Freshness, SearchHit, SemanticSearchResponse};
+use super::{Tool, ToolContext, ToolError, ToolResult};
 
 /// Tool for reading file contents with optional line range
 pub struct ReadFileTool {
@@ -55,7 +58,7 @@ impl Tool for ReadFileTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let raw_path = params
             .get("path")
             .and_then(Value::as_str)
@@ -91,14 +94,14 @@ impl Tool for ReadFileTool {
             .unwrap_or_default()
             .join("\n");
 
-        Ok(json!({
-            "path": path,
-            "content": content,
-            "start_line": start_line,
-            "end_line": start_idx + (end_idx - start_idx),
-            "total_lines": total_lines,
-            "truncated": end_idx < total_lines,
-        }))
+        to_result(&ReadFileResponse {
+            path,
+            content,
+            start_line,
+            end_line: end_idx,
+            total_lines,
+            truncated: end_idx < total_lines,
+        })
     }
 }
 
@@ -134,7 +137,7 @@ impl Tool for ListDirTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let raw_path = params.get("path").and_then(Value::as_str).unwrap_or(".");
 
         let dir = resolve_path(&self.project_root, raw_path);
@@ -156,26 +159,20 @@ impl Tool for ListDirTool {
             } else {
                 name.clone()
             };
-            items.push(json!({
-                "name": display,
-                "is_dir": is_dir,
-            }));
+            items.push(DirEntry {
+                name: display,
+                is_dir,
+            });
         }
 
-        items.sort_by(|a, b| {
-            let a_dir = a["is_dir"].as_bool().unwrap_or(false);
-            let b_dir = b["is_dir"].as_bool().unwrap_or(false);
-            // Directories first, then alphabetical
-            b_dir
-                .cmp(&a_dir)
-                .then_with(|| a["name"].as_str().cmp(&b["name"].as_str()))
-        });
-
-        Ok(json!({
-            "path": dir,
-            "entries": items,
-            "count": items.len(),
-        }))
+        // Directories first, then alphabetical
+        items.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+        to_result(&ListDirResponse {
+            path: dir,
+            count: items.len(),
+            entries: items,
+        })
     }
 }
 
@@ -195,6 +192,10 @@ impl Tool for GetFileNodesTool {
         "coraline_get_file_nodes"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Get all indexed code symbols (nodes) in a specific file, ordered by line number."
     }
@@ -217,7 +218,7 @@ impl Tool for GetFileNodesTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let raw_path = params
             .get("file_path")
             .and_then(Value::as_str)
@@ -233,41 +234,38 @@ impl Tool for GetFileNodesTool {
             .to_string();
 
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
 
         // Try absolute path first, fall back to raw_path (in case stored relative)
         let nodes = {
             let mut n = db::get_nodes_by_file(&conn, &abs_path, kind)
-                .map_err(|e| ToolError::internal_error(format!("Failed to query nodes: {e}")))?;
+                .map_err(|e| ToolError::failed("Failed to query nodes", e))?;
             if n.is_empty() {
-                n = db::get_nodes_by_file(&conn, raw_path, kind).map_err(|e| {
-                    ToolError::internal_error(format!("Failed to query nodes: {e}"))
-                })?;
+                n = db::get_nodes_by_file(&conn, raw_path, kind)
+                    .map_err(|e| ToolError::failed("Failed to query nodes", e))?;
             }
             n
         };
 
-        let symbols: Vec<Value> = nodes
-            .iter()
-            .map(|n| {
-                json!({
-                    "id": n.id,
-                    "kind": n.kind,
-                    "name": n.name,
-                    "qualified_name": n.qualified_name,
-                    "start_line": n.start_line,
-                    "end_line": n.end_line,
-                    "signature": n.signature,
-                    "is_exported": n.is_exported,
-                })
+        let symbols: Vec<FileNode> = nodes
+            .into_iter()
+            .map(|n| FileNode {
+                id: n.id,
+                kind: n.kind,
+                name: n.name,
+                qualified_name: n.qualified_name,
+                start_line: n.start_line,
+                end_line: n.end_line,
+                signature: n.signature,
+                is_exported: n.is_exported,
             })
             .collect();
 
-        Ok(json!({
-            "file_path": abs_path,
-            "nodes": symbols,
-            "count": symbols.len(),
-        }))
+        to_result(&FileNodesResponse {
+            file_path: abs_path,
+            count: symbols.len(),
+            nodes: symbols,
+        })
     }
 }
 
@@ -287,6 +285,10 @@ impl Tool for FindFileTool {
         "coraline_find_file"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Search for files by name substring or glob pattern across the project. \
          Returns matching file paths relative to the project root."
@@ -310,7 +312,7 @@ impl Tool for FindFileTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let pattern = params
             .get("pattern")
             .and_then(Value::as_str)
@@ -334,12 +336,12 @@ impl Tool for FindFileTool {
             &mut matches,
         );
 
-        Ok(json!({
-            "pattern": pattern,
-            "matches": matches,
-            "count": matches.len(),
-            "truncated": matches.len() >= limit,
-        }))
+        to_result(&FindFileResponse {
+            pattern: pattern.to_string(),
+            count: matches.len(),
+            truncated: matches.len() >= limit,
+            matches,
+        })
     }
 }
 
@@ -479,27 +481,27 @@ impl Tool for StatusTool {
         })
     }
 
-    fn execute(&self, _params: Value) -> ToolResult {
+    fn execute(&self, _params: Value, _ctx: &ToolContext) -> ToolResult {
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
 
-        let stats = db::get_db_stats(&conn)
-            .map_err(|e| ToolError::internal_error(format!("Failed to get stats: {e}")))?;
+        let stats =
+            db::get_db_stats(&conn).map_err(|e| ToolError::failed("Failed to get stats", e))?;
 
         let db_path = db::database_path(&self.project_root);
         let db_size = std::fs::metadata(&db_path).map_or(0, |m| m.len());
 
-        Ok(json!({
-            "project_root": self.project_root,
-            "database": db_path,
-            "database_size_bytes": db_size,
-            "stats": {
-                "nodes": stats.node_count,
-                "edges": stats.edge_count,
-                "files": stats.file_count,
-                "unresolved_references": stats.unresolved_count,
-            }
-        }))
+        to_result(&StatusResponse {
+            project_root: self.project_root.clone(),
+            database: db_path,
+            database_size_bytes: db_size,
+            stats: StatusStats {
+                nodes: stats.node_count,
+                edges: stats.edge_count,
+                files: stats.file_count,
+                unresolved_references: stats.unresolved_count,
+            },
+        })
     }
 }
 
@@ -554,19 +556,19 @@ impl Tool for GetConfigTool {
             "properties": {
                 "section": {
                     "type": "string",
-                    "description": "Optional: return only this section (indexing, context, sync, vectors)",
-                    "enum": ["indexing", "context", "sync", "vectors"]
+                    "description": "Optional: return only this section (indexing, context, sync, vectors, search, database, lifecycle, issues, history, analysis, tools)",
+                    "enum": ["indexing", "context", "sync", "vectors", "search", "database", "lifecycle", "issues", "history", "analysis", "tools"]
                 }
             }
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let cfg = crate::config::load_toml_config(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to load config: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to load config", e))?;
 
-        let full = serde_json::to_value(&cfg)
-            .map_err(|e| ToolError::internal_error(format!("Serialization failed: {e}")))?;
+        let full =
+            serde_json::to_value(&cfg).map_err(|e| ToolError::failed("Serialization failed", e))?;
 
         let result = if let Some(section) = params.get("section").and_then(Value::as_str) {
             full.get(section).cloned().unwrap_or(Value::Null)
@@ -575,11 +577,11 @@ impl Tool for GetConfigTool {
         };
 
         let config_path = crate::config::toml_config_path(&self.project_root);
-        Ok(json!({
-            "config_path": config_path,
-            "config_exists": config_path.exists(),
-            "config": result,
-        }))
+        to_result(&ConfigResponse {
+            config_exists: config_path.exists(),
+            config_path,
+            config: result,
+        })
     }
 }
 
@@ -610,7 +612,7 @@ impl Tool for UpdateConfigTool {
                 "section": {
                     "type": "string",
                     "description": "Config section to update",
-                    "enum": ["indexing", "context", "sync", "vectors"]
+                    "enum": ["indexing", "context", "sync", "vectors", "search", "database", "issues", "history", "analysis"]
                 },
                 "key": {
                     "type": "string",
@@ -624,7 +626,7 @@ impl Tool for UpdateConfigTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let section = params
             .get("section")
             .and_then(Value::as_str)
@@ -642,10 +644,10 @@ impl Tool for UpdateConfigTool {
 
         // Load current config, mutate it as JSON, write back
         let cfg = crate::config::load_toml_config(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to load config: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to load config", e))?;
 
-        let mut cfg_json = serde_json::to_value(&cfg)
-            .map_err(|e| ToolError::internal_error(format!("Serialization failed: {e}")))?;
+        let mut cfg_json =
+            serde_json::to_value(&cfg).map_err(|e| ToolError::failed("Serialization failed", e))?;
 
         let section_obj = cfg_json
             .get_mut(section)
@@ -669,14 +671,14 @@ impl Tool for UpdateConfigTool {
             })?;
 
         crate::config::save_toml_config(&self.project_root, &updated)
-            .map_err(|e| ToolError::internal_error(format!("Failed to save config: {e}")))?;
-
-        Ok(json!({
-            "updated": true,
-            "section": section,
-            "key": key,
-            "new_value": new_value,
-        }))
+            .map_err(|e| ToolError::failed("Failed to save config", e))?;
+
+        to_result(&UpdateConfigResponse {
+            updated: true,
+            section: section.to_string(),
+            key: key.to_string(),
+            new_value,
+        })
     }
 }
 
@@ -710,35 +712,43 @@ impl Tool for SyncTool {
         })
     }
 
-    fn execute(&self, _params: Value) -> ToolResult {
-        let mut cfg = crate::config::load_config(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to load config: {e}")))?;
-        if let Ok(toml_cfg) = crate::config::load_toml_config(&self.project_root) {
-            crate::config::apply_toml_to_code_graph(&mut cfg, &toml_cfg);
-        }
-
-        let result = crate::extraction::sync(&self.project_root, &cfg, None)
-            .map_err(|e| ToolError::internal_error(format!("Sync failed: {e}")))?;
-
-        Ok(json!({
-            "files_checked":  result.files_checked,
-            "files_added":    result.files_added,
-            "files_modified": result.files_modified,
-            "files_removed":  result.files_removed,
-            "nodes_updated":  result.nodes_updated,
-            "duration_ms":    result.duration_ms,
-        }))
+    fn execute(&self, _params: Value, ctx: &ToolContext) -> ToolResult {
+        let cfg = crate::config::load_config(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to load config", e))?;
+
+        ctx.check_cancelled()?;
+        // A started sync runs to completion: every file is committed in its
+        // own transaction, so there is nothing half-done to roll back.
+        #[allow(clippy::cast_precision_loss)]
+        let on_progress = |progress: crate::extraction::IndexProgress| {
+            ctx.report_progress(
+                progress.current as f64,
+                Some(progress.total as f64),
+                progress.current_file.as_deref(),
+            );
+        };
+        let result = crate::extraction::sync(&self.project_root, &cfg, Some(&on_progress))
+            .map_err(|e| ToolError::failed("Sync failed", e))?;
+
+        to_result(&SyncResponse {
+            files_checked: result.files_checked,
+            files_added: result.files_added,
+            files_modified: result.files_modified,
+            files_removed: result.files_removed,
+            nodes_updated: result.nodes_updated,
+            nodes_embedded: result.nodes_embedded,
+            files_failed: result.files_failed,
+            duration_ms: result.duration_ms,
+        })
     }
 }
 
 /// Tool for semantic (vector) search over indexed nodes.
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 pub struct SemanticSearchTool {
     project_root: PathBuf,
     freshness_state: Mutex<SemanticFreshnessState>,
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 impl SemanticSearchTool {
     pub fn new(project_root: PathBuf) -> Self {
         Self {
@@ -750,7 +760,7 @@ impl SemanticSearchTool {
     fn maybe_refresh_index_and_embeddings(
         &self,
         vm: Option<&mut crate::vectors::VectorManager>,
-    ) -> Result<FreshnessUpdate, ToolError> {
+    ) -> Result<Freshness, ToolError> {
         let now = Instant::now();
         let should_check = {
             let state = self
@@ -762,22 +772,23 @@ impl SemanticSearchTool {
         };
 
         if !should_check {
-            return Ok(FreshnessUpdate::default());
+            return Ok(Freshness {
+                check_interval_seconds: FRESHNESS_CHECK_INTERVAL_SECS,
+                ..Freshness::default()
+            });
         }
 
-        let mut update = FreshnessUpdate {
+        let mut update = Freshness {
             checked: true,
-            ..FreshnessUpdate::default()
+            check_interval_seconds: FRESHNESS_CHECK_INTERVAL_SECS,
+            ..Freshness::default()
         };
 
-        let mut cfg = crate::config::load_config(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to load config: {e}")))?;
-        if let Ok(toml_cfg) = crate::config::load_toml_config(&self.project_root) {
-            crate::config::apply_toml_to_code_graph(&mut cfg, &toml_cfg);
-        }
+        let cfg = crate::config::load_config(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to load config", e))?;
 
         let sync_status = crate::extraction::needs_sync(&self.project_root, &cfg)
-            .map_err(|e| ToolError::internal_error(format!("Sync-state check failed: {e}")))?;
+            .map_err(|e| ToolError::failed("Sync-state check failed", e))?;
 
         update.stale_files_added = sync_status.files_added;
         update.stale_files_modified = sync_status.files_modified;
@@ -785,33 +796,31 @@ impl SemanticSearchTool {
 
         if sync_status.is_stale() {
             let result = crate::extraction::sync(&self.project_root, &cfg, None)
-                .map_err(|e| ToolError::internal_error(format!("Auto-sync failed: {e}")))?;
+                .map_err(|e| ToolError::failed("Auto-sync failed", e))?;
             update.synced = true;
             update.files_added = result.files_added;
             update.files_modified = result.files_modified;
             update.files_removed = result.files_removed;
         }
 
-        let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("DB error: {e}")))?;
+        let conn =
+            db::open_database(&self.project_root).map_err(|e| ToolError::failed("DB error", e))?;
 
         let stale_count = stale_embedding_count(&conn)
-            .map_err(|e| ToolError::internal_error(format!("Embedding-state check failed: {e}")))?;
+            .map_err(|e| ToolError::failed("Embedding-state check failed", e))?;
 
         if stale_count > 0 {
             let refreshed = if let Some(vm) = vm {
-                refresh_stale_embeddings(&conn, vm).map_err(|e| {
-                    ToolError::internal_error(format!("Embedding refresh failed: {e}"))
-                })?
+                refresh_stale_embeddings(&self.project_root, &conn, vm)
+                    .map_err(|e| ToolError::failed("Embedding refresh failed", e))?
             } else {
                 let mut vm = crate::vectors::VectorManager::from_project(&self.project_root).map_err(|e| {
                     ToolError::internal_error(format!(
                         "Could not load embedding model: {e}. Download the model and run 'coraline embed' first."
                     ))
                 })?;
-                refresh_stale_embeddings(&conn, &mut vm).map_err(|e| {
-                    ToolError::internal_error(format!("Embedding refresh failed: {e}"))
-                })?
+                refresh_stale_embeddings(&self.project_root, &conn, &mut vm)
+                    .map_err(|e| ToolError::failed("Embedding refresh failed", e))?
             };
 
             update.embeddings_refreshed = true;
@@ -830,31 +839,13 @@ impl SemanticSearchTool {
     }
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 const FRESHNESS_CHECK_INTERVAL_SECS: u64 = 30;
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 #[derive(Default)]
 struct SemanticFreshnessState {
     last_checked_at: Option<Instant>,
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
-#[derive(Default)]
-struct FreshnessUpdate {
-    checked: bool,
-    stale_files_added: usize,
-    stale_files_modified: usize,
-    stale_files_removed: usize,
-    synced: bool,
-    files_added: usize,
-    files_modified: usize,
-    files_removed: usize,
-    embeddings_refreshed: bool,
-    embeddings_refreshed_count: usize,
-}
-
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 fn stale_embedding_count(conn: &rusqlite::Connection) -> std::io::Result<usize> {
     let count = conn
         .query_row(
@@ -870,11 +861,10 @@ fn stale_embedding_count(conn: &rusqlite::Connection) -> std::io::Result<usize>
     usize::try_from(count).map_err(std::io::Error::other)
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 type StaleNodeRow = (String, String, String, Option<String>, Option<String>);
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 fn refresh_stale_embeddings(
+    project_root: &std::path::Path,
     conn: &rusqlite::Connection,
     vm: &mut crate::vectors::VectorManager,
 ) -> std::io::Result<usize> {
@@ -909,29 +899,37 @@ fn refresh_stale_embeddings(
         .map_err(std::io::Error::other)?;
 
     let mut refreshed = 0usize;
+    let mut refreshed_ids = Vec::with_capacity(stale_nodes.len());
     for (id, name, qualified_name, docstring, signature) in stale_nodes {
-        let text = crate::vectors::node_embed_text(
+        let text = vm.document_text(&crate::vectors::node_embed_text(
             &name,
             &qualified_name,
             docstring.as_deref(),
             signature.as_deref(),
-        );
+        ));
 
         let embedding = vm.embed(&text)?;
         crate::vectors::store_embedding(&tx, &id, &embedding, vm.model_name())?;
         refreshed += 1;
+        refreshed_ids.push(id);
     }
 
+    let nodes = db::get_nodes_by_ids(&tx, &refreshed_ids)?;
+    crate::vectors::embed_body_chunks(project_root, &tx, vm, &nodes)?;
+
     tx.commit().map_err(std::io::Error::other)?;
     Ok(refreshed)
 }
 
-#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 impl Tool for SemanticSearchTool {
     fn name(&self) -> &'static str {
         "coraline_semantic_search"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Search indexed code nodes using natural-language vector similarity. \
          Requires embeddings to have been generated with `coraline embed`."
@@ -952,13 +950,18 @@ impl Tool for SemanticSearchTool {
                 "min_similarity": {
                     "type": "number",
                     "description": "Minimum cosine similarity threshold 0–1 (default 0.3)"
+                },
+                "kind": {
+                    "type": "string",
+                    "description": "Node kind filter (function, class, method, etc.)",
+                    "enum": ["function", "method", "class", "struct", "interface", "trait", "module"]
                 }
             },
             "required": ["query"]
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, ctx: &ToolContext) -> ToolResult {
         let query = params
             .get("query")
             .and_then(Value::as_str)
@@ -974,7 +977,9 @@ impl Tool for SemanticSearchTool {
             .get("min_similarity")
             .and_then(Value::as_f64)
             .unwrap_or(0.3) as f32;
+        let kind = super::graph_tools::search_kind_param(&params);
 
+        ctx.report_progress(0.0, Some(3.0), Some("Loading embedding model"));
         let mut vm =
             crate::vectors::VectorManager::from_project(&self.project_root).map_err(|e| {
                 ToolError::internal_error(format!(
@@ -983,51 +988,40 @@ impl Tool for SemanticSearchTool {
                 ))
             })?;
 
+        // Refuse before refreshing so stale nodes are not re-embedded into a
+        // mixed-model index.
+        let conn =
+            db::open_database(&self.project_root).map_err(|e| ToolError::failed("DB error", e))?;
+        crate::vectors::check_vector_models(&conn, vm.model_name(), None)
+            .map_err(|e| ToolError::internal_error(e.to_string()))?;
+
+        ctx.check_cancelled()?;
+        ctx.report_progress(1.0, Some(3.0), Some("Refreshing stale embeddings"));
         let freshness = self.maybe_refresh_index_and_embeddings(Some(&mut vm))?;
 
+        ctx.check_cancelled()?;
+        ctx.report_progress(2.0, Some(3.0), Some("Searching"));
         let embedding = vm
-            .embed(query)
-            .map_err(|e| ToolError::internal_error(format!("Embedding failed: {e}")))?;
-
-        let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("DB error: {e}")))?;
+            .embed_query(query)
+            .map_err(|e| ToolError::failed("Embedding failed", e))?;
+        crate::vectors::check_vector_models(&conn, vm.model_name(), Some(embedding.len()))
+            .map_err(|e| ToolError::internal_error(e.to_string()))?;
 
-        let results = crate::vectors::search_similar(&conn, &embedding, limit, min_similarity)
-            .map_err(|e| ToolError::internal_error(format!("Search failed: {e}")))?;
+        let results =
+            crate::vectors::search_similar(&conn, &embedding, limit, min_similarity, kind)
+                .map_err(|e| ToolError::failed("Search failed", e))?;
 
-        let items: Vec<Value> = results
-            .into_iter()
-            .map(|r| {
-                json!({
-                    "id":           r.node.id,
-                    "name":         r.node.name,
-                    "qualified_name": r.node.qualified_name,
-                    "kind":         r.node.kind,
-                    "file_path":    r.node.file_path,
-                    "start_line":   r.node.start_line,
-                    "docstring":    r.node.docstring,
-                    "signature":    r.node.signature,
-                    "score":        r.score,
-                })
-            })
+        let mut staleness = crate::staleness::StalenessCheck::new(&self.project_root, &conn);
+        let items: Vec<SearchHit> = results
+            .iter()
+            .map(|r| SearchHit::from(r).with_stale(staleness.is_stale(&r.node)))
             .collect();
 
-        Ok(json!({
-            "query": query,
-            "freshness": {
-                "checked": freshness.checked,
-                "stale_files_added": freshness.stale_files_added,
-                "stale_files_modified": freshness.stale_files_modified,
-                "stale_files_removed": freshness.stale_files_removed,
-                "synced": freshness.synced,
-                "files_added": freshness.files_added,
-                "files_modified": freshness.files_modified,
-                "files_removed": freshness.files_removed,
-                "embeddings_refreshed": freshness.embeddings_refreshed,
-                "embeddings_refreshed_count": freshness.embeddings_refreshed_count,
-                "check_interval_seconds": FRESHNESS_CHECK_INTERVAL_SECS,
-            },
-            "results": items
-        }))
+        to_result(&SemanticSearchResponse {
+            query: query.to_string(),
+            freshness,
+            count: items.len(),
+            results: items,
+        })
     }
 }
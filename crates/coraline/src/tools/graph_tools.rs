@@ -2,15 +2,29 @@
 
 //! Graph query tools for exploring the code graph
 
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use serde_json::{Value, json};
 
+use crate::annotations::Annotations;
 use crate::db;
 use crate::graph;
-use crate::types::{EdgeKind, NodeKind, TraversalDirection, TraversalOptions};
-
-use super::{Tool, ToolError, ToolResult};
+use crate::staleness::StalenessCheck;
+use crate::types::{
+    Edge, EdgeKind, Node, NodeCoverage, NodeKind, TraversalDirection, TraversalOptions,
+};
+
+use super::responses::{
+    CallSite, CalleesResponse, CallersResponse, CommitSummary, DependenciesResponse,
+    DependentsResponse, EdgeRef, ExplainResponse, FileImport, FileLink, FileOverviewResponse,
+    FindSymbolResponse, ImpactResponse, ImpactStats, ImplementationEntry, ImplementationsResponse,
+    MemoryMention, NodeDetails, NodeRef, OutlineNode, OverviewEntry, OverviewSymbol,
+    OwnersResponse, PathResponse, Reference, ReferencesResponse, RenameFile, RenameImpactResponse,
+    RenameUsage, RouteEntry, RoutesResponse, SearchHit, SearchResponse, SourceLocation,
+    StatsResponse, StatsTotals, SymbolMatch, SymbolsOverviewResponse, to_result,
+};
+use super::{Tool, ToolContext, ToolError, ToolResult};
 
 /// Tool for searching nodes by name or pattern
 pub struct SearchTool {
@@ -28,6 +42,10 @@ impl Tool for SearchTool {
         "coraline_search"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Search for code symbols by name or pattern across the indexed codebase"
     }
@@ -49,6 +67,14 @@ impl Tool for SearchTool {
                     "type": "string",
                     "description": "Restrict results to symbols in this file path"
                 },
+                "tag": {
+                    "type": "string",
+                    "description": "Only symbols with this annotation tag, e.g. 'payments' or 'owner:@team'"
+                },
+                "package": {
+                    "type": "string",
+                    "description": "Only symbols in this workspace package (Cargo, pnpm/yarn/npm, or go.work member)"
+                },
                 "limit": {
                     "type": "number",
                     "description": "Maximum number of results to return",
@@ -59,25 +85,13 @@ impl Tool for SearchTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let query = params
             .get("query")
             .and_then(Value::as_str)
             .ok_or_else(|| ToolError::invalid_params("query must be a string"))?;
 
-        let kind = params
-            .get("kind")
-            .and_then(Value::as_str)
-            .and_then(|s| match s {
-                "function" => Some(NodeKind::Function),
-                "method" => Some(NodeKind::Method),
-                "class" => Some(NodeKind::Class),
-                "struct" => Some(NodeKind::Struct),
-                "interface" => Some(NodeKind::Interface),
-                "trait" => Some(NodeKind::Trait),
-                "module" => Some(NodeKind::Module),
-                _ => None,
-            });
+        let kind = search_kind_param(&params);
 
         let limit = params
             .get("limit")
@@ -86,18 +100,35 @@ impl Tool for SearchTool {
             .unwrap_or(10);
 
         let file_filter = params.get("file").and_then(Value::as_str);
+        let tags: Vec<String> = params
+            .get("tag")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .into_iter()
+            .chain(
+                params
+                    .get("package")
+                    .and_then(Value::as_str)
+                    .map(|name| format!("package:{name}")),
+            )
+            .collect();
 
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+        let annotations = Annotations::load(&conn)
+            .map_err(|e| ToolError::failed("Failed to load annotations", e))?;
 
-        // Fetch extra results when file-filtering so we still hit the requested limit.
-        let fetch_limit = if file_filter.is_some() {
+        // Fetch extra results when filtering so we still hit the requested limit.
+        let fetch_limit = if file_filter.is_some() || !tags.is_empty() {
             limit * 5
         } else {
             limit
         };
-        let results = db::search_nodes(&conn, query, kind, fetch_limit)
-            .map_err(|e| ToolError::internal_error(format!("Search failed: {e}")))?;
+        let search_cfg = crate::config::load_toml_config(&self.project_root)
+            .unwrap_or_default()
+            .search;
+        let results = db::search_nodes_with_config(&conn, query, kind, fetch_limit, &search_cfg)
+            .map_err(|e| ToolError::failed("Search failed", e))?;
 
         let abs_file = file_filter.map(|f| {
             if std::path::Path::new(f).is_absolute() {
@@ -107,39 +138,47 @@ impl Tool for SearchTool {
             }
         });
 
-        let results_json: Vec<Value> = results
-            .into_iter()
+        let mut staleness = StalenessCheck::new(&self.project_root, &conn);
+        let results: Vec<SearchHit> = results
+            .iter()
             .filter(|r| {
                 abs_file.as_ref().is_none_or(|af| {
                     r.node.file_path == *af || file_filter.is_some_and(|f| r.node.file_path == f)
                 })
             })
+            .filter(|r| annotations.has_tags(&r.node, &tags))
             .take(limit)
             .map(|r| {
-                json!({
-                    "node": {
-                        "id": r.node.id,
-                        "kind": r.node.kind,
-                        "name": r.node.name,
-                        "qualified_name": r.node.qualified_name,
-                        "file_path": r.node.file_path,
-                        "start_line": r.node.start_line,
-                        "end_line": r.node.end_line,
-                        "language": r.node.language,
-                        "signature": r.node.signature,
-                    },
-                    "score": r.score,
-                })
+                let mut hit = SearchHit::from(r).with_stale(staleness.is_stale(&r.node));
+                hit.node.tags = annotations.tags_for(&r.node);
+                hit
             })
             .collect();
 
-        Ok(json!({
-            "results": results_json,
-            "count": results_json.len(),
-        }))
+        to_result(&SearchResponse {
+            count: results.len(),
+            results,
+        })
     }
 }
 
+/// The optional `kind` filter of the search tools.
+pub(crate) fn search_kind_param(params: &Value) -> Option<NodeKind> {
+    params
+        .get("kind")
+        .and_then(Value::as_str)
+        .and_then(|s| match s {
+            "function" => Some(NodeKind::Function),
+            "method" => Some(NodeKind::Method),
+            "class" => Some(NodeKind::Class),
+            "struct" => Some(NodeKind::Struct),
+            "interface" => Some(NodeKind::Interface),
+            "trait" => Some(NodeKind::Trait),
+            "module" => Some(NodeKind::Module),
+            _ => None,
+        })
+}
+
 /// Tool for finding callers of a function/method
 pub struct CallersTool {
     project_root: PathBuf,
@@ -156,6 +195,10 @@ impl Tool for CallersTool {
         "coraline_callers"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Find all functions/methods that call a given symbol"
     }
@@ -170,77 +213,70 @@ impl Tool for CallersTool {
                 },
                 "name": {
                     "type": "string",
-                    "description": "Symbol name (alternative to node_id). If ambiguous, add 'file'."
+                    "description": "Symbol name (alternative to node_id). If several symbols share it, the candidates are returned instead; add 'file_path' or pick one's id."
                 },
-                "file": {
+                "file_path": {
                     "type": "string",
                     "description": "File path to disambiguate when using 'name'"
                 },
+                "file": {
+                    "type": "string",
+                    "description": "Alias of 'file_path'"
+                },
                 "limit": {
                     "type": "number",
-                    "description": "Maximum number of callers to return",
+                    "description": "Maximum number of callers to return per symbol",
                     "default": 20
+                },
+                "depth": {
+                    "type": "number",
+                    "description": "Levels of the call graph to walk; above 1, each entry lists its own callers under 'children'",
+                    "default": 1
+                },
+                "max_nodes": {
+                    "type": "number",
+                    "description": "Maximum symbols in the whole tree when depth > 1",
+                    "default": 50
                 }
             }
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
-
-        let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
-
-        let limit = params
-            .get("limit")
-            .and_then(Value::as_u64)
-            .and_then(|n| usize::try_from(n).ok())
-            .unwrap_or(20);
-
-        // Get the target node for crate boundary validation
-        let to_node = db::get_node_by_id(&conn, &node_id)
-            .map_err(|e| ToolError::internal_error(format!("Failed to get target node: {e}")))?;
-
-        // Get edges where this node is the target and edge kind is "calls"
-        let edges = db::get_edges_by_target(&conn, &node_id, Some(EdgeKind::Calls), limit * 2)
-            .map_err(|e| ToolError::internal_error(format!("Failed to get edges: {e}")))?;
-
-        let mut callers = Vec::new();
-        for edge in edges {
-            if let Some(caller) = db::get_node_by_id(&conn, &edge.source)
-                .map_err(|e| ToolError::internal_error(format!("Failed to get node: {e}")))?
-            {
-                // Validate that the call edge has proper crate/import boundaries
-                let is_valid = if let Some(ref target) = to_node {
-                    db::is_valid_call_edge(&conn, &caller, target).map_err(|e| {
-                        ToolError::internal_error(format!("Failed to validate edge: {e}"))
-                    })?
-                } else {
-                    true // If we can't find the target node, allow the edge (shouldn't happen)
-                };
-
-                if is_valid {
-                    callers.push(json!({
-                        "id": caller.id,
-                        "kind": caller.kind,
-                        "name": caller.name,
-                        "qualified_name": caller.qualified_name,
-                        "file_path": caller.file_path,
-                        "start_line": caller.start_line,
-                        "line": edge.line,
-                    }));
-
-                    if callers.len() >= limit {
-                        break;
-                    }
-                }
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+
+        let node_id = match resolve_symbol(&conn, &self.project_root, &params, "node_id")? {
+            Resolved::Node(id) => id,
+            Resolved::Ambiguous(candidates) => {
+                return to_result(&CallersResponse {
+                    callers: Vec::new(),
+                    count: 0,
+                    total: 0,
+                    truncated: false,
+                    candidates: candidates.iter().map(NodeRef::from).collect(),
+                });
             }
-        }
+        };
 
-        Ok(json!({
-            "callers": callers,
-            "count": callers.len(),
-        }))
+        let (limit, depth, max_nodes) = call_tree_params(&params);
+        let tree = call_tree(
+            &self.project_root,
+            &conn,
+            &node_id,
+            Direction::Callers,
+            depth,
+            limit,
+            max_nodes,
+        )?;
+
+        to_result(&CallersResponse {
+            count: tree.sites.len(),
+            callers: tree.sites,
+            total: tree.total,
+            truncated: tree.truncated,
+            candidates: Vec::new(),
+        })
     }
 }
 
@@ -260,6 +296,10 @@ impl Tool for CalleesTool {
         "coraline_callees"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Find all functions/methods that a given symbol calls"
     }
@@ -274,77 +314,70 @@ impl Tool for CalleesTool {
                 },
                 "name": {
                     "type": "string",
-                    "description": "Symbol name (alternative to node_id). If ambiguous, add 'file'."
+                    "description": "Symbol name (alternative to node_id). If several symbols share it, the candidates are returned instead; add 'file_path' or pick one's id."
                 },
-                "file": {
+                "file_path": {
                     "type": "string",
                     "description": "File path to disambiguate when using 'name'"
                 },
+                "file": {
+                    "type": "string",
+                    "description": "Alias of 'file_path'"
+                },
                 "limit": {
                     "type": "number",
-                    "description": "Maximum number of callees to return",
+                    "description": "Maximum number of callees to return per symbol",
                     "default": 20
+                },
+                "depth": {
+                    "type": "number",
+                    "description": "Levels of the call graph to walk; above 1, each entry lists its own callees under 'children'",
+                    "default": 1
+                },
+                "max_nodes": {
+                    "type": "number",
+                    "description": "Maximum symbols in the whole tree when depth > 1",
+                    "default": 50
                 }
             }
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
-
-        let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
-
-        let limit = params
-            .get("limit")
-            .and_then(Value::as_u64)
-            .and_then(|n| usize::try_from(n).ok())
-            .unwrap_or(20);
-
-        // Get the calling node for crate boundary validation
-        let from_node = db::get_node_by_id(&conn, &node_id)
-            .map_err(|e| ToolError::internal_error(format!("Failed to get source node: {e}")))?;
-
-        // Get edges where this node is the source and edge kind is "calls"
-        let edges = db::get_edges_by_source(&conn, &node_id, Some(EdgeKind::Calls), limit * 2)
-            .map_err(|e| ToolError::internal_error(format!("Failed to get edges: {e}")))?;
-
-        let mut callees = Vec::new();
-        for edge in edges {
-            if let Some(callee) = db::get_node_by_id(&conn, &edge.target)
-                .map_err(|e| ToolError::internal_error(format!("Failed to get node: {e}")))?
-            {
-                // Validate that the call edge has proper crate/import boundaries
-                let is_valid = if let Some(ref from) = from_node {
-                    db::is_valid_call_edge(&conn, from, &callee).map_err(|e| {
-                        ToolError::internal_error(format!("Failed to validate edge: {e}"))
-                    })?
-                } else {
-                    true // If we can't find the source node, allow the edge (shouldn't happen)
-                };
-
-                if is_valid {
-                    callees.push(json!({
-                        "id": callee.id,
-                        "kind": callee.kind,
-                        "name": callee.name,
-                        "qualified_name": callee.qualified_name,
-                        "file_path": callee.file_path,
-                        "start_line": callee.start_line,
-                        "line": edge.line,
-                    }));
-
-                    if callees.len() >= limit {
-                        break;
-                    }
-                }
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+
+        let node_id = match resolve_symbol(&conn, &self.project_root, &params, "node_id")? {
+            Resolved::Node(id) => id,
+            Resolved::Ambiguous(candidates) => {
+                return to_result(&CalleesResponse {
+                    callees: Vec::new(),
+                    count: 0,
+                    total: 0,
+                    truncated: false,
+                    candidates: candidates.iter().map(NodeRef::from).collect(),
+                });
             }
-        }
+        };
 
-        Ok(json!({
-            "callees": callees,
-            "count": callees.len(),
-        }))
+        let (limit, depth, max_nodes) = call_tree_params(&params);
+        let tree = call_tree(
+            &self.project_root,
+            &conn,
+            &node_id,
+            Direction::Callees,
+            depth,
+            limit,
+            max_nodes,
+        )?;
+
+        to_result(&CalleesResponse {
+            count: tree.sites.len(),
+            callees: tree.sites,
+            total: tree.total,
+            truncated: tree.truncated,
+            candidates: Vec::new(),
+        })
     }
 }
 
@@ -364,6 +397,10 @@ impl Tool for ImpactTool {
         "coraline_impact"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Analyze the impact radius of changing a symbol - what might be affected"
     }
@@ -393,16 +430,29 @@ impl Tool for ImpactTool {
                     "type": "number",
                     "description": "Maximum nodes to include in result",
                     "default": 50
+                },
+                "min_confidence": {
+                    "type": "number",
+                    "description": "Skip resolved edges whose resolution confidence (0.0-1.0) is lower; directly observed edges always count"
+                },
+                "package": {
+                    "type": "string",
+                    "description": "Only report affected symbols in this workspace package"
                 }
             }
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, ctx: &ToolContext) -> ToolResult {
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
 
         let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
+        let package = params
+            .get("package")
+            .and_then(Value::as_str)
+            .map(|name| vec![format!("package:{name}")]);
+        let min_confidence = params.get("min_confidence").and_then(Value::as_f64);
 
         let max_depth = params
             .get("max_depth")
@@ -415,228 +465,654 @@ impl Tool for ImpactTool {
 
         let traversal_options = TraversalOptions {
             max_depth,
-            edge_kinds: Some(vec![EdgeKind::Calls, EdgeKind::References]),
+            // `type_of` brings in the queries of an ORM model.
+            edge_kinds: Some(vec![
+                EdgeKind::Calls,
+                EdgeKind::References,
+                EdgeKind::TypeOf,
+            ]),
             node_kinds: None,
             direction: Some(TraversalDirection::Incoming), // Find what depends on this
             limit: max_nodes,
             include_start: Some(true),
+            edge_costs: None,
+            incoming_cost_factor: None,
+            min_confidence,
         };
 
-        let subgraph = graph::build_subgraph(&conn, &[node_id], &traversal_options)
-            .map_err(|e| ToolError::internal_error(format!("Failed to build subgraph: {e}")))?;
+        ctx.check_cancelled()?;
+        let mut subgraph =
+            graph::build_subgraph(&conn, std::slice::from_ref(&node_id), &traversal_options)
+                .map_err(|e| ToolError::failed("Failed to build subgraph", e))?;
+        ctx.check_cancelled()?;
+
+        let annotations = Annotations::load(&conn)
+            .map_err(|e| ToolError::failed("Failed to load annotations", e))?;
+        if let Some(package) = &package {
+            // Keep the target and the affected symbols inside the package.
+            subgraph
+                .nodes
+                .retain(|id, node| *id == node_id || annotations.has_tags(node, package));
+            let kept = &subgraph.nodes;
+            subgraph
+                .edges
+                .retain(|e| kept.contains_key(&e.source) && kept.contains_key(&e.target));
+        }
+
+        let nodes: Vec<NodeRef> = subgraph.nodes.values().map(NodeRef::from).collect();
+        let edges: Vec<EdgeRef> = subgraph.edges.iter().map(EdgeRef::from).collect();
+
+        let files: std::collections::HashSet<_> =
+            subgraph.nodes.values().map(|n| &n.file_path).collect();
 
-        let nodes: Vec<Value> = subgraph
+        let ids: Vec<&str> = subgraph.nodes.keys().map(String::as_str).collect();
+        let coverage = db::get_coverage(&conn, &ids)
+            .map_err(|e| ToolError::failed("Failed to read coverage", e))?;
+        let untested: Vec<NodeRef> = subgraph
             .nodes
             .values()
-            .map(|node| {
-                json!({
-                    "id": node.id,
-                    "kind": node.kind,
-                    "name": node.name,
-                    "qualified_name": node.qualified_name,
-                    "file_path": node.file_path,
-                    "start_line": node.start_line,
-                })
-            })
-            .collect();
-
-        let edges: Vec<Value> = subgraph
-            .edges
-            .iter()
-            .map(|edge| {
-                json!({
-                    "source": edge.source,
-                    "target": edge.target,
-                    "kind": edge.kind,
-                    "line": edge.line,
-                })
-            })
+            .filter(|n| coverage.get(&n.id).is_some_and(NodeCoverage::is_untested))
+            .map(NodeRef::from)
             .collect();
 
-        let files: std::collections::HashSet<_> =
-            subgraph.nodes.values().map(|n| &n.file_path).collect();
+        let owners = annotations.group_by_owner(subgraph.nodes.values());
+        let packages = annotations.group_by_package(subgraph.nodes.values());
 
-        Ok(json!({
-            "nodes": nodes,
-            "edges": edges,
-            "stats": {
-                "node_count": nodes.len(),
-                "edge_count": edges.len(),
-                "file_count": files.len(),
-                "max_depth": max_depth.unwrap_or(2),
-            }
-        }))
+        to_result(&ImpactResponse {
+            stats: ImpactStats {
+                node_count: nodes.len(),
+                edge_count: edges.len(),
+                file_count: files.len(),
+                max_depth: max_depth.unwrap_or(2),
+            },
+            nodes,
+            edges,
+            untested,
+            owners,
+            packages,
+        })
     }
 }
 
-/// Tool for finding a symbol by name pattern (richer than search — returns hierarchy/depth info)
-pub struct FindSymbolTool {
+/// Tool for the owners of a file or symbol and of the code depending on it
+pub struct OwnersTool {
     project_root: PathBuf,
 }
 
-impl FindSymbolTool {
+impl OwnersTool {
     pub const fn new(project_root: PathBuf) -> Self {
         Self { project_root }
     }
 }
 
-impl Tool for FindSymbolTool {
+impl Tool for OwnersTool {
     fn name(&self) -> &'static str {
-        "coraline_find_symbol"
+        "coraline_owners"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
     }
 
     fn description(&self) -> &'static str {
-        "Find symbols by exact name or substring pattern. Returns node metadata and optionally the source code body."
+        "Who owns a file or symbol (CODEOWNERS and owner: annotations), and whose code \
+         a change to it touches: the owners of everything that calls or references it, \
+         directly or transitively."
     }
 
     fn input_schema(&self) -> Value {
         json!({
             "type": "object",
             "properties": {
-                "name_pattern": {
+                "path": {
                     "type": "string",
-                    "description": "Symbol name or substring to search for"
+                    "description": "Project-relative file path; the whole file is the target"
                 },
-                "kind": {
+                "node_id": {
                     "type": "string",
-                    "description": "Optional node kind filter",
-                    "enum": ["function", "method", "class", "struct", "interface", "trait", "module"]
+                    "description": "ID of a symbol (alternative to path)"
                 },
-                "file": {
+                "name": {
                     "type": "string",
-                    "description": "Restrict results to symbols in this file path"
+                    "description": "Symbol name (alternative to node_id). If ambiguous, add 'file_path'."
                 },
-                "include_body": {
+                "file_path": {
+                    "type": "string",
+                    "description": "File path to disambiguate when using 'name' (alias: 'file')"
+                },
+                "impact": {
                     "type": "boolean",
-                    "description": "Whether to include the source code body of the symbol",
-                    "default": false
+                    "description": "Also group the dependent code by owner",
+                    "default": true
                 },
-                "limit": {
+                "max_depth": {
                     "type": "number",
-                    "description": "Maximum results to return",
-                    "default": 10
+                    "description": "Levels of callers and referrers to follow",
+                    "default": 2
+                },
+                "max_nodes": {
+                    "type": "number",
+                    "description": "Maximum dependent symbols to visit",
+                    "default": 200
                 }
-            },
-            "required": ["name_pattern"]
+            }
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
-        let pattern = params
-            .get("name_pattern")
-            .and_then(Value::as_str)
-            .ok_or_else(|| ToolError::invalid_params("name_pattern must be a string"))?;
-
-        let kind = params
-            .get("kind")
-            .and_then(Value::as_str)
-            .and_then(str_to_node_kind);
-
-        let include_body = params
-            .get("include_body")
+    fn execute(&self, params: Value, ctx: &ToolContext) -> ToolResult {
+        let conn = db::open_database(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+        let annotations = Annotations::load(&conn)
+            .map_err(|e| ToolError::failed("Failed to load annotations", e))?;
+
+        let (target, tags, start_ids) =
+            if let Some(path) = params.get("path").and_then(Value::as_str) {
+                let path = path.trim_start_matches("./");
+                let nodes = db::get_nodes_by_file(&conn, path, None)
+                    .map_err(|e| ToolError::failed("Failed to get nodes", e))?;
+                if nodes.is_empty() {
+                    return Err(ToolError::not_found(format!("File not indexed: {path}")));
+                }
+                let ids: Vec<String> = nodes.into_iter().map(|n| n.id).collect();
+                (path.to_string(), annotations.tags_for_path(path), ids)
+            } else {
+                let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
+                let node = db::get_node_by_id(&conn, &node_id)
+                    .map_err(|e| ToolError::failed("Failed to get node", e))?
+                    .ok_or_else(|| ToolError::not_found(format!("Node not found: {node_id}")))?;
+                let tags = annotations.tags_for(&node);
+                (node.qualified_name, tags, vec![node_id])
+            };
+        let owners = crate::annotations::owners(&tags);
+
+        let impact = params
+            .get("impact")
             .and_then(Value::as_bool)
-            .unwrap_or(false);
+            .unwrap_or(true);
+        if !impact {
+            return to_result(&OwnersResponse {
+                target,
+                owners,
+                tags,
+                impacted: None,
+                unowned_files: Vec::new(),
+                truncated: false,
+            });
+        }
 
-        let limit = params
-            .get("limit")
+        let max_depth = params
+            .get("max_depth")
             .and_then(Value::as_u64)
             .and_then(|n| usize::try_from(n).ok())
-            .unwrap_or(10);
-
-        let file_filter = params.get("file").and_then(Value::as_str);
-
-        let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
-
-        // Fetch extra results when file-filtering so we still hit the requested limit.
-        let fetch_limit = if file_filter.is_some() {
-            limit * 5
-        } else {
-            limit
+            .unwrap_or(2);
+        let max_nodes = params
+            .get("max_nodes")
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(200);
+        let traversal = TraversalOptions {
+            max_depth: Some(max_depth),
+            edge_kinds: Some(vec![
+                EdgeKind::Calls,
+                EdgeKind::References,
+                EdgeKind::TypeOf,
+            ]),
+            node_kinds: None,
+            direction: Some(TraversalDirection::Incoming),
+            limit: Some(max_nodes.saturating_add(start_ids.len())),
+            include_start: Some(false),
+            edge_costs: None,
+            incoming_cost_factor: None,
+            min_confidence: None,
         };
-        let results = db::search_nodes(&conn, pattern, kind, fetch_limit)
-            .map_err(|e| ToolError::internal_error(format!("Search failed: {e}")))?;
-
-        let abs_file = file_filter.map(|f| {
-            if std::path::Path::new(f).is_absolute() {
-                f.to_string()
-            } else {
-                self.project_root.join(f).to_string_lossy().to_string()
-            }
-        });
+        ctx.check_cancelled()?;
+        let subgraph = graph::build_subgraph(&conn, &start_ids, &traversal)
+            .map_err(|e| ToolError::failed("Failed to build subgraph", e))?;
+        let start: HashSet<&str> = start_ids.iter().map(String::as_str).collect();
+        let dependents: Vec<&Node> = subgraph
+            .nodes
+            .values()
+            .filter(|n| !start.contains(n.id.as_str()))
+            .collect();
 
-        let symbols: Vec<Value> = results
+        let unowned_files: Vec<String> = dependents
+            .iter()
+            .filter(|n| annotations.owners_for(n).is_empty())
+            .map(|n| n.file_path.clone())
+            .collect::<BTreeSet<_>>()
             .into_iter()
-            .filter(|r| {
-                abs_file.as_ref().is_none_or(|af| {
-                    r.node.file_path == *af || file_filter.is_some_and(|f| r.node.file_path == f)
-                })
-            })
-            .take(limit)
-            .map(|r| {
-                let body = if include_body {
-                    read_node_source(&self.project_root, &r.node)
-                } else {
-                    None
-                };
-                json!({
-                    "id": r.node.id,
-                    "kind": r.node.kind,
-                    "name": r.node.name,
-                    "qualified_name": r.node.qualified_name,
-                    "file_path": r.node.file_path,
-                    "language": r.node.language,
-                    "start_line": r.node.start_line,
-                    "end_line": r.node.end_line,
-                    "signature": r.node.signature,
-                    "docstring": r.node.docstring,
-                    "is_exported": r.node.is_exported,
-                    "is_async": r.node.is_async,
-                    "is_static": r.node.is_static,
-                    "score": r.score,
-                    "body": body,
-                })
-            })
             .collect();
 
-        Ok(json!({ "symbols": symbols, "count": symbols.len() }))
+        to_result(&OwnersResponse {
+            target,
+            owners,
+            tags,
+            impacted: Some(annotations.group_by_owner(dependents.iter().copied())),
+            unowned_files,
+            truncated: dependents.len() >= max_nodes,
+        })
     }
 }
 
-/// Tool for getting a symbol overview for a file
-pub struct GetSymbolsOverviewTool {
+/// Tool for planning a rename: every place naming a symbol, and the tests that reach it
+pub struct RenameImpactTool {
     project_root: PathBuf,
 }
 
-impl GetSymbolsOverviewTool {
+/// The usages of `node_id`, every incoming edge but the parent's
+/// `contains`, in file order: at most `limit`, and whether there were more.
+fn rename_usages(
+    conn: &rusqlite::Connection,
+    node_id: &str,
+    limit: usize,
+) -> Result<(Vec<RenameUsage>, bool), ToolError> {
+    let edges = db::get_edges_by_target(conn, node_id, None, limit + 1)
+        .map_err(|e| ToolError::failed("Failed to get edges", e))?;
+    let edges: Vec<&Edge> = edges
+        .iter()
+        .filter(|e| e.kind != EdgeKind::Contains)
+        .collect();
+    let truncated = edges.len() > limit;
+    let source_ids: Vec<String> = edges
+        .iter()
+        .take(limit)
+        .map(|e| e.source.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let sources: HashMap<String, Node> = db::get_nodes_by_ids(conn, &source_ids)
+        .map_err(|e| ToolError::failed("Failed to get nodes", e))?
+        .into_iter()
+        .map(|n| (n.id.clone(), n))
+        .collect();
+    let mut usages: Vec<RenameUsage> = edges
+        .iter()
+        .take(limit)
+        .filter_map(|e| {
+            let source = sources.get(&e.source)?;
+            Some(RenameUsage {
+                location: SourceLocation {
+                    file_path: source.file_path.clone(),
+                    line: e.line.unwrap_or(source.start_line),
+                    column: e.column,
+                },
+                edge_kind: e.kind,
+                used_by: NodeRef::from(source),
+            })
+        })
+        .collect();
+    usages.sort_by(|a, b| {
+        (&a.location.file_path, a.location.line, a.location.column).cmp(&(
+            &b.location.file_path,
+            b.location.line,
+            b.location.column,
+        ))
+    });
+    Ok((usages, truncated))
+}
+
+/// The files a rename edits, each with the lines it touches.
+fn rename_files(
+    definition: &SourceLocation,
+    usages: &[RenameUsage],
+    imports: &[SourceLocation],
+    exports: &[NodeRef],
+) -> Vec<RenameFile> {
+    let mut lines: BTreeMap<&str, BTreeSet<i64>> = BTreeMap::new();
+    for location in std::iter::once(definition)
+        .chain(usages.iter().map(|u| &u.location))
+        .chain(imports.iter())
+    {
+        lines
+            .entry(location.file_path.as_str())
+            .or_default()
+            .insert(location.line);
+    }
+    for export in exports {
+        lines
+            .entry(export.file_path.as_str())
+            .or_default()
+            .insert(export.start_line);
+    }
+    lines
+        .into_iter()
+        .map(|(file, lines)| RenameFile {
+            file_path: file.to_string(),
+            lines: lines.into_iter().collect(),
+        })
+        .collect()
+}
+
+impl RenameImpactTool {
     pub const fn new(project_root: PathBuf) -> Self {
         Self { project_root }
     }
 }
 
-impl Tool for GetSymbolsOverviewTool {
+impl Tool for RenameImpactTool {
     fn name(&self) -> &'static str {
-        "coraline_get_symbols_overview"
+        "coraline_rename_impact"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
     }
 
     fn description(&self) -> &'static str {
-        "Get an overview of all symbols in a file, grouped by kind and ordered by line number."
+        "Plan a rename or signature change: the definition and every usage of a symbol \
+         (file, line, column), the imports and exports naming it, the tests that call it \
+         directly or transitively, and the files to edit."
     }
 
     fn input_schema(&self) -> Value {
         json!({
             "type": "object",
             "properties": {
+                "node_id": {
+                    "type": "string",
+                    "description": "ID of the symbol to rename"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Symbol name (alternative to node_id). If ambiguous, add 'file_path'."
+                },
                 "file_path": {
                     "type": "string",
-                    "description": "Path to the file (relative to project root or absolute)"
-                }
-            },
-            "required": ["file_path"]
-        })
-    }
+                    "description": "File path to disambiguate when using 'name' (alias: 'file')"
+                },
+                "max_depth": {
+                    "type": "number",
+                    "description": "How many levels of callers to search for tests",
+                    "default": 3
+                },
+                "limit": {
+                    "type": "number",
+                    "description": "Maximum usages to list",
+                    "default": 200
+                }
+            }
+        })
+    }
+
+    fn execute(&self, params: Value, ctx: &ToolContext) -> ToolResult {
+        let conn = db::open_database(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+
+        let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
+        let node = db::get_node_by_id(&conn, &node_id)
+            .map_err(|e| ToolError::failed("Failed to get node", e))?
+            .ok_or_else(|| ToolError::not_found(format!("Node not found: {node_id}")))?;
+
+        let max_depth = params
+            .get("max_depth")
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(3);
+        let limit = params
+            .get("limit")
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(200);
+
+        let (usages, truncated) = rename_usages(&conn, &node_id, limit)?;
+        ctx.check_cancelled()?;
+
+        // Imports and exports are matched by name: an import binding the
+        // name from a module that looks like the definition's file, and
+        // export statements of the name there or in an importing file.
+        let same_name = db::find_nodes_by_name(&conn, &node.name)
+            .map_err(|e| ToolError::failed("Name lookup failed", e))?;
+        let imports: Vec<&Node> = same_name
+            .iter()
+            .filter(|n| n.kind == NodeKind::Import && imports_from(n, &node.file_path))
+            .collect();
+        let export_files: HashSet<&str> = std::iter::once(node.file_path.as_str())
+            .chain(imports.iter().map(|n| n.file_path.as_str()))
+            .collect();
+        let mut exports: Vec<NodeRef> = same_name
+            .iter()
+            .filter(|n| n.kind == NodeKind::Export && export_files.contains(n.file_path.as_str()))
+            .map(NodeRef::from)
+            .collect();
+        if node.is_exported && !exports.iter().any(|e| e.file_path == node.file_path) {
+            exports.push(NodeRef::from(&node));
+        }
+        exports.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+        let mut imports: Vec<SourceLocation> = imports
+            .into_iter()
+            .map(|n| SourceLocation {
+                file_path: n.file_path.clone(),
+                line: n.start_line,
+                column: Some(n.start_column),
+            })
+            .collect();
+        imports.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+
+        let traversal_options = TraversalOptions {
+            max_depth: Some(max_depth),
+            edge_kinds: Some(vec![EdgeKind::Calls, EdgeKind::References]),
+            node_kinds: None,
+            direction: Some(TraversalDirection::Incoming),
+            limit: None,
+            include_start: Some(false),
+            edge_costs: None,
+            incoming_cost_factor: None,
+            min_confidence: None,
+        };
+        let subgraph =
+            graph::build_subgraph(&conn, std::slice::from_ref(&node_id), &traversal_options)
+                .map_err(|e| ToolError::failed("Failed to build subgraph", e))?;
+        ctx.check_cancelled()?;
+        let mut tests: Vec<NodeRef> = subgraph
+            .nodes
+            .values()
+            .filter(|n| n.id != node_id && crate::utils::is_test_node(n))
+            .map(NodeRef::from)
+            .collect();
+        tests.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+
+        let definition = SourceLocation {
+            file_path: node.file_path.clone(),
+            line: node.start_line,
+            column: Some(node.start_column),
+        };
+        let files = rename_files(&definition, &usages, &imports, &exports);
+
+        to_result(&RenameImpactResponse {
+            symbol: NodeRef::from(&node),
+            definition,
+            usages,
+            imports,
+            exports,
+            tests,
+            files,
+            truncated,
+        })
+    }
+}
+
+/// Tool for finding a symbol by name pattern (richer than search — returns hierarchy/depth info)
+pub struct FindSymbolTool {
+    project_root: PathBuf,
+}
+
+impl FindSymbolTool {
+    pub const fn new(project_root: PathBuf) -> Self {
+        Self { project_root }
+    }
+}
 
-    fn execute(&self, params: Value) -> ToolResult {
+impl Tool for FindSymbolTool {
+    fn name(&self) -> &'static str {
+        "coraline_find_symbol"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Find symbols by exact name or substring pattern. Returns node metadata and optionally the source code body."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name_pattern": {
+                    "type": "string",
+                    "description": "Symbol name or substring to search for"
+                },
+                "kind": {
+                    "type": "string",
+                    "description": "Optional node kind filter",
+                    "enum": ["function", "method", "class", "struct", "interface", "trait", "module"]
+                },
+                "file": {
+                    "type": "string",
+                    "description": "Restrict results to symbols in this file path"
+                },
+                "include_body": {
+                    "type": "boolean",
+                    "description": "Whether to include the source code body of the symbol",
+                    "default": false
+                },
+                "limit": {
+                    "type": "number",
+                    "description": "Maximum results to return",
+                    "default": 10
+                }
+            },
+            "required": ["name_pattern"]
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let pattern = params
+            .get("name_pattern")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolError::invalid_params("name_pattern must be a string"))?;
+
+        let kind = params
+            .get("kind")
+            .and_then(Value::as_str)
+            .and_then(str_to_node_kind);
+
+        let include_body = params
+            .get("include_body")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let limit = params
+            .get("limit")
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(10);
+
+        let file_filter = params.get("file").and_then(Value::as_str);
+
+        let conn = db::open_database(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+
+        // Fetch extra results when file-filtering so we still hit the requested limit.
+        let fetch_limit = if file_filter.is_some() {
+            limit * 5
+        } else {
+            limit
+        };
+        let search_cfg = crate::config::load_toml_config(&self.project_root)
+            .unwrap_or_default()
+            .search;
+        let results = db::search_nodes_with_config(&conn, pattern, kind, fetch_limit, &search_cfg)
+            .map_err(|e| ToolError::failed("Search failed", e))?;
+
+        let abs_file = file_filter.map(|f| {
+            if std::path::Path::new(f).is_absolute() {
+                f.to_string()
+            } else {
+                self.project_root.join(f).to_string_lossy().to_string()
+            }
+        });
+
+        let symbols: Vec<SymbolMatch> = results
+            .into_iter()
+            .filter(|r| {
+                abs_file.as_ref().is_none_or(|af| {
+                    r.node.file_path == *af || file_filter.is_some_and(|f| r.node.file_path == f)
+                })
+            })
+            .take(limit)
+            .map(|r| {
+                let body = if include_body {
+                    read_node_source(&self.project_root, &r.node)
+                } else {
+                    None
+                };
+                SymbolMatch {
+                    id: r.node.id,
+                    kind: r.node.kind,
+                    name: r.node.name,
+                    qualified_name: r.node.qualified_name,
+                    file_path: r.node.file_path,
+                    language: r.node.language,
+                    start_line: r.node.start_line,
+                    end_line: r.node.end_line,
+                    signature: r.node.signature,
+                    docstring: r.node.docstring,
+                    is_exported: r.node.is_exported,
+                    is_async: r.node.is_async,
+                    is_static: r.node.is_static,
+                    score: r.score,
+                    body,
+                }
+            })
+            .collect();
+
+        to_result(&FindSymbolResponse {
+            count: symbols.len(),
+            symbols,
+        })
+    }
+}
+
+/// Tool for getting a symbol overview for a file
+pub struct GetSymbolsOverviewTool {
+    project_root: PathBuf,
+}
+
+impl GetSymbolsOverviewTool {
+    pub const fn new(project_root: PathBuf) -> Self {
+        Self { project_root }
+    }
+}
+
+impl Tool for GetSymbolsOverviewTool {
+    fn name(&self) -> &'static str {
+        "coraline_get_symbols_overview"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Get an overview of all symbols in a file, grouped by kind and ordered by line number."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "Path to the file (relative to project root or absolute)"
+                }
+            },
+            "required": ["file_path"]
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let file_path = params
             .get("file_path")
             .and_then(Value::as_str)
@@ -653,15 +1129,15 @@ impl Tool for GetSymbolsOverviewTool {
         };
 
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
 
         let nodes = db::get_nodes_by_file(&conn, &abs_path, None)
-            .map_err(|e| ToolError::internal_error(format!("Failed to get nodes: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to get nodes", e))?;
 
         if nodes.is_empty() {
             // Try with the path as-is (might be stored relative)
             let nodes_fallback = db::get_nodes_by_file(&conn, file_path, None)
-                .map_err(|e| ToolError::internal_error(format!("Failed to get nodes: {e}")))?;
+                .map_err(|e| ToolError::failed("Failed to get nodes", e))?;
 
             return build_overview_response(&nodes_fallback, file_path);
         }
@@ -670,168 +1146,692 @@ impl Tool for GetSymbolsOverviewTool {
     }
 }
 
-#[allow(clippy::unnecessary_wraps)]
 fn build_overview_response(nodes: &[crate::types::Node], file_path: &str) -> ToolResult {
-    use std::collections::HashMap;
+    let mut by_kind: std::collections::BTreeMap<String, Vec<OverviewEntry>> =
+        std::collections::BTreeMap::new();
+
+    for node in nodes {
+        let kind_str = format!("{:?}", node.kind).to_lowercase();
+        by_kind.entry(kind_str).or_default().push(OverviewEntry {
+            id: node.id.clone(),
+            name: node.name.clone(),
+            qualified_name: node.qualified_name.clone(),
+            start_line: node.start_line,
+            end_line: node.end_line,
+            signature: node.signature.clone(),
+            is_exported: node.is_exported,
+        });
+    }
+
+    let symbols = nodes
+        .iter()
+        .map(|n| OverviewSymbol {
+            id: n.id.clone(),
+            kind: n.kind,
+            name: n.name.clone(),
+            start_line: n.start_line,
+            end_line: n.end_line,
+            signature: n.signature.clone(),
+        })
+        .collect();
+
+    to_result(&SymbolsOverviewResponse {
+        file_path: file_path.to_string(),
+        symbol_count: nodes.len(),
+        by_kind,
+        symbols,
+    })
+}
+
+/// An import node as the file overview lists it: the module its signature
+/// names and, when renamed, the name the module exports it under.
+fn file_import(node: &Node) -> FileImport {
+    let signature = node.signature.clone().unwrap_or_default();
+    let (module, export_name) = match signature.split_once("|export=") {
+        Some((module, export)) => (module.to_string(), Some(export.to_string())),
+        None => (signature, None),
+    };
+    FileImport {
+        name: node.name.clone(),
+        module,
+        export_name: export_name.filter(|e| *e != node.name),
+        line: node.start_line,
+    }
+}
+
+/// Tool for a structured outline of one file: nested symbols, imports,
+/// exports, and the files on either side of its dependencies
+pub struct FileOverviewTool {
+    project_root: PathBuf,
+}
+
+impl FileOverviewTool {
+    pub const fn new(project_root: PathBuf) -> Self {
+        Self { project_root }
+    }
+}
+
+impl Tool for FileOverviewTool {
+    fn name(&self) -> &'static str {
+        "coraline_file_overview"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Explain a file in one call: its nested symbol outline (classes with their methods, \
+         modules with their items), imports, exports, the files it depends on, and the files \
+         that depend on it."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "file_path": {
+                    "type": "string",
+                    "description": "Path to the file (relative to project root or absolute)"
+                },
+                "limit": {
+                    "type": "number",
+                    "description": "Maximum dependent and dependency files to list (default: 50)",
+                    "default": 50
+                }
+            },
+            "required": ["file_path"]
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let file_path = params
+            .get("file_path")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolError::invalid_params("file_path must be a string"))?;
+        let limit = params
+            .get("limit")
+            .and_then(Value::as_u64)
+            .and_then(|v| usize::try_from(v).ok())
+            .unwrap_or(50);
+
+        let path = std::path::Path::new(file_path);
+        let relative = path
+            .strip_prefix(&self.project_root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        let conn = db::open_database(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+        let nodes = db::get_nodes_by_file(&conn, &relative, None)
+            .map_err(|e| ToolError::failed("Failed to get nodes", e))?;
+        let Some(file_node) = nodes.iter().find(|n| n.kind == NodeKind::File) else {
+            return Err(ToolError::not_found(format!(
+                "File not indexed: {file_path}"
+            )));
+        };
+
+        let ids: Vec<String> = nodes.iter().map(|n| n.id.clone()).collect();
+        let outgoing = db::get_edges_for_nodes(&conn, &ids, true, None, None)
+            .map_err(|e| ToolError::failed("Failed to get edges", e))?;
+        let incoming = db::get_edges_for_nodes(&conn, &ids, false, None, None)
+            .map_err(|e| ToolError::failed("Failed to get edges", e))?;
+
+        let local: HashMap<&str, &Node> = nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+        let outline = build_outline(&nodes, &outgoing, &file_node.id);
+
+        let imports = nodes
+            .iter()
+            .filter(|n| n.kind == NodeKind::Import)
+            .map(file_import)
+            .collect();
+        let exports = nodes
+            .iter()
+            .filter(|n| n.kind == NodeKind::Export || (n.is_exported && n.kind != NodeKind::File))
+            .map(NodeRef::from)
+            .collect();
+
+        // Remote endpoints of cross-file edges, fetched in one query.
+        let remote_ids: Vec<String> = outgoing
+            .iter()
+            .map(|e| &e.target)
+            .chain(incoming.iter().map(|e| &e.source))
+            .filter(|id| !local.contains_key(id.as_str()))
+            .cloned()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let remote: HashMap<String, Node> = db::get_nodes_by_ids(&conn, &remote_ids)
+            .map_err(|e| ToolError::failed("Failed to get nodes", e))?
+            .into_iter()
+            .map(|n| (n.id.clone(), n))
+            .collect();
+
+        let mut depends_on = link_files(
+            outgoing
+                .iter()
+                .filter(|e| e.kind != EdgeKind::Contains)
+                .filter_map(|e| {
+                    let target = remote.get(&e.target)?;
+                    Some((target.file_path.as_str(), e.kind, target.name.as_str()))
+                }),
+        );
+        let mut dependents = link_files(
+            incoming
+                .iter()
+                .filter(|e| e.kind != EdgeKind::Contains)
+                .filter_map(|e| {
+                    let source = remote.get(&e.source)?;
+                    let used = local.get(e.target.as_str())?;
+                    Some((source.file_path.as_str(), e.kind, used.name.as_str()))
+                }),
+        );
+        let dependent_count = dependents.len();
+        depends_on.truncate(limit);
+        dependents.truncate(limit);
+
+        to_result(&FileOverviewResponse {
+            file_path: relative,
+            language: file_node.language,
+            docstring: file_node.docstring.clone(),
+            symbol_count: nodes.len() - 1,
+            outline,
+            imports,
+            exports,
+            depends_on,
+            dependents,
+            dependent_count,
+        })
+    }
+}
+
+/// Nest a file's symbols along its `contains` edges. Symbols without a
+/// parent in the file hang off the top level; imports and exports are
+/// listed separately and left out.
+fn build_outline(nodes: &[Node], edges: &[Edge], file_id: &str) -> Vec<OutlineNode> {
+    let mut children: HashMap<&str, Vec<&Node>> = HashMap::new();
+    let mut has_parent: HashSet<&str> = HashSet::new();
+    let by_id: HashMap<&str, &Node> = nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+    for edge in edges.iter().filter(|e| e.kind == EdgeKind::Contains) {
+        if let Some(child) = by_id.get(edge.target.as_str())
+            && by_id.contains_key(edge.source.as_str())
+            && edge.source != file_id
+            && has_parent.insert(child.id.as_str())
+        {
+            children
+                .entry(edge.source.as_str())
+                .or_default()
+                .push(child);
+        }
+    }
+
+    for kids in children.values_mut() {
+        kids.sort_by_key(|n| (n.start_line, n.start_column));
+    }
+    nodes
+        .iter()
+        .filter(|n| {
+            !matches!(n.kind, NodeKind::File | NodeKind::Import | NodeKind::Export)
+                && !has_parent.contains(n.id.as_str())
+        })
+        .map(|n| outline_node(n, &children, 0))
+        .collect()
+}
+
+/// `node` with its nested children, cut off below a depth no real file reaches.
+fn outline_node(node: &Node, children: &HashMap<&str, Vec<&Node>>, depth: usize) -> OutlineNode {
+    let nested = if depth < 32 {
+        children
+            .get(node.id.as_str())
+            .map_or_else(Vec::new, |kids| {
+                kids.iter()
+                    .map(|k| outline_node(k, children, depth + 1))
+                    .collect()
+            })
+    } else {
+        Vec::new()
+    };
+    OutlineNode {
+        id: node.id.clone(),
+        kind: node.kind,
+        name: node.name.clone(),
+        start_line: node.start_line,
+        end_line: node.end_line,
+        signature: node.signature.clone(),
+        is_exported: node.is_exported,
+        children: nested,
+    }
+}
+
+/// Group `(other file, edge kind, symbol)` triples by file, most edges first.
+fn link_files<'a>(links: impl Iterator<Item = (&'a str, EdgeKind, &'a str)>) -> Vec<FileLink> {
+    let mut by_file: HashMap<&str, (usize, BTreeSet<EdgeKind>, BTreeSet<&str>)> = HashMap::new();
+    for (file, kind, symbol) in links {
+        let entry = by_file.entry(file).or_default();
+        entry.0 += 1;
+        entry.1.insert(kind);
+        entry.2.insert(symbol);
+    }
+    let mut files: Vec<FileLink> = by_file
+        .into_iter()
+        .map(|(file, (edge_count, kinds, symbols))| FileLink {
+            file_path: file.to_string(),
+            edge_count,
+            edge_kinds: kinds.into_iter().collect(),
+            symbols: symbols.into_iter().map(str::to_string).collect(),
+        })
+        .collect();
+    files.sort_by(|a, b| {
+        b.edge_count
+            .cmp(&a.edge_count)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    files
+}
+
+/// Tool for finding all references to a node
+pub struct FindReferencesTool {
+    project_root: PathBuf,
+}
+
+impl FindReferencesTool {
+    pub const fn new(project_root: PathBuf) -> Self {
+        Self { project_root }
+    }
+}
+
+impl Tool for FindReferencesTool {
+    fn name(&self) -> &'static str {
+        "coraline_find_references"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Find all nodes that reference (call, import, extend, implement, etc.) a given symbol."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "node_id": {
+                    "type": "string",
+                    "description": "ID of the node to find references to"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Symbol name (alternative to node_id). If ambiguous, add 'file'."
+                },
+                "file": {
+                    "type": "string",
+                    "description": "File path to disambiguate when using 'name'"
+                },
+                "edge_kind": {
+                    "type": "string",
+                    "description": "Filter by edge kind (calls, imports, extends, implements, references)",
+                    "enum": ["calls", "imports", "extends", "implements", "references"]
+                },
+                "limit": {
+                    "type": "number",
+                    "description": "Maximum number of references to return",
+                    "default": 50
+                }
+            }
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let conn = db::open_database(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+
+        let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
+
+        let edge_kind = params
+            .get("edge_kind")
+            .and_then(Value::as_str)
+            .and_then(|s| match s {
+                "calls" => Some(EdgeKind::Calls),
+                "imports" => Some(EdgeKind::Imports),
+                "extends" => Some(EdgeKind::Extends),
+                "implements" => Some(EdgeKind::Implements),
+                "references" => Some(EdgeKind::References),
+                _ => None,
+            });
+
+        let limit = params
+            .get("limit")
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(50);
+
+        let edges = db::get_edges_by_target(&conn, &node_id, edge_kind, limit)
+            .map_err(|e| ToolError::failed("Failed to get edges", e))?;
+
+        let mut references = Vec::new();
+        for edge in &edges {
+            if let Some(node) = db::get_node_by_id(&conn, &edge.source)
+                .map_err(|e| ToolError::failed("Failed to get node", e))?
+            {
+                references.push(Reference {
+                    node: NodeRef::from(&node),
+                    edge_kind: edge.kind,
+                    edge_line: edge.line,
+                });
+            }
+        }
+
+        to_result(&ReferencesResponse {
+            node_id,
+            count: references.len(),
+            references,
+        })
+    }
+}
+
+/// Tool for getting full node details including source code
+pub struct GetNodeTool {
+    project_root: PathBuf,
+}
+
+impl GetNodeTool {
+    pub const fn new(project_root: PathBuf) -> Self {
+        Self { project_root }
+    }
+}
+
+impl Tool for GetNodeTool {
+    fn name(&self) -> &'static str {
+        "coraline_node"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "Get complete details for a specific node by ID, including its source code body."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "node_id": {
+                    "type": "string",
+                    "description": "The node ID to retrieve"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Symbol name (alternative to node_id). If ambiguous, add 'file'."
+                },
+                "file": {
+                    "type": "string",
+                    "description": "File path to disambiguate when using 'name'"
+                },
+                "include_edges": {
+                    "type": "boolean",
+                    "description": "Whether to include outgoing and incoming edge counts",
+                    "default": false
+                }
+            }
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let conn = db::open_database(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+
+        let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
+
+        let include_edges = params
+            .get("include_edges")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let node = db::get_node_by_id(&conn, &node_id)
+            .map_err(|e| ToolError::failed("Failed to get node", e))?
+            .ok_or_else(|| ToolError::not_found(format!("Node not found: {node_id}")))?;
+
+        let body = read_node_source(&self.project_root, &node);
+
+        let mut details = NodeDetails::new(&node, body);
+
+        if include_edges {
+            let out_edges = db::get_edges_by_source(&conn, &node_id, None, 200)
+                .map_err(|e| ToolError::failed("Failed to get edges", e))?;
+            let in_edges = db::get_edges_by_target(&conn, &node_id, None, 200)
+                .map_err(|e| ToolError::failed("Failed to get edges", e))?;
+            details.outgoing_edge_count = Some(out_edges.len());
+            details.incoming_edge_count = Some(in_edges.len());
+        }
+
+        to_result(&details)
+    }
+}
+
+/// Tool for listing the HTTP routes the project serves, with their handlers
+pub struct RoutesTool {
+    project_root: PathBuf,
+}
+
+impl RoutesTool {
+    pub const fn new(project_root: PathBuf) -> Self {
+        Self { project_root }
+    }
+}
+
+impl Tool for RoutesTool {
+    fn name(&self) -> &'static str {
+        "coraline_routes"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
+    fn description(&self) -> &'static str {
+        "List HTTP routes registered with axum, actix-web, Rocket, Express, Koa, Fastify, FastAPI, or Flask, with the handler serving each. Answers 'what code serves GET /users/42'."
+    }
+
+    fn input_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "method": {
+                    "type": "string",
+                    "description": "Only routes serving this HTTP method (GET, POST, ...); routes registered for any method always match"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Only routes serving this concrete path, e.g. '/users/42' matches '/users/{id}' and '/users/:id'"
+                },
+                "limit": {
+                    "type": "number",
+                    "description": "Maximum number of routes to return",
+                    "default": 50
+                }
+            }
+        })
+    }
+
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let method = params
+            .get("method")
+            .and_then(Value::as_str)
+            .map(str::to_uppercase);
+        let path = params.get("path").and_then(Value::as_str);
+        let limit = params
+            .get("limit")
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(50);
 
-    let mut by_kind: HashMap<String, Vec<Value>> = HashMap::new();
+        let conn = db::open_database(&self.project_root)
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+        let nodes = db::get_nodes_by_kind(&conn, NodeKind::Route)
+            .map_err(|e| ToolError::failed("Failed to get routes", e))?;
 
-    for node in nodes {
-        let kind_str = format!("{:?}", node.kind).to_lowercase();
-        by_kind.entry(kind_str).or_default().push(json!({
-            "id": node.id,
-            "name": node.name,
-            "qualified_name": node.qualified_name,
-            "start_line": node.start_line,
-            "end_line": node.end_line,
-            "signature": node.signature,
-            "is_exported": node.is_exported,
-        }));
-    }
-
-    let symbols: Vec<Value> = nodes
-        .iter()
-        .map(|n| {
-            json!({
-                "id": n.id,
-                "kind": n.kind,
-                "name": n.name,
-                "start_line": n.start_line,
-                "end_line": n.end_line,
-                "signature": n.signature,
-            })
-        })
-        .collect();
+        let mut routes = Vec::new();
+        for node in nodes {
+            let Some((route_method, route_path)) = node.name.split_once(' ') else {
+                continue;
+            };
+            if method
+                .as_deref()
+                .is_some_and(|m| route_method != m && route_method != "ANY")
+            {
+                continue;
+            }
+            if path.is_some_and(|p| !crate::routes::path_matches(route_path, p)) {
+                continue;
+            }
+            let handler = db::get_edges_by_source(&conn, &node.id, Some(EdgeKind::References), 1)
+                .map_err(|e| ToolError::failed("Failed to get edges", e))?
+                .first()
+                .map(|edge| db::get_node_by_id(&conn, &edge.target))
+                .transpose()
+                .map_err(|e| ToolError::failed("Failed to get node", e))?
+                .flatten();
+            routes.push(RouteEntry {
+                method: route_method.to_string(),
+                path: route_path.to_string(),
+                file_path: node.file_path.clone(),
+                line: node.start_line,
+                handler: handler.as_ref().map(NodeRef::from),
+            });
+        }
+        routes.sort_by(|a, b| (&a.path, &a.method).cmp(&(&b.path, &b.method)));
+        let total = routes.len();
+        routes.truncate(limit);
 
-    Ok(json!({
-        "file_path": file_path,
-        "symbol_count": nodes.len(),
-        "by_kind": by_kind,
-        "symbols": symbols,
-    }))
+        to_result(&RoutesResponse { routes, total })
+    }
 }
 
-/// Tool for finding all references to a node
-pub struct FindReferencesTool {
+/// Tool that lists the implementers of a trait or interface
+pub struct ImplementationsTool {
     project_root: PathBuf,
 }
 
-impl FindReferencesTool {
+impl ImplementationsTool {
     pub const fn new(project_root: PathBuf) -> Self {
         Self { project_root }
     }
 }
 
-impl Tool for FindReferencesTool {
+impl Tool for ImplementationsTool {
     fn name(&self) -> &'static str {
-        "coraline_find_references"
+        "coraline_implementations"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
     }
 
     fn description(&self) -> &'static str {
-        "Find all nodes that reference (call, import, extend, implement, etc.) a given symbol."
+        "List the types implementing a trait, interface, protocol, or base class, directly or through a subtype, with the required methods each leaves undefined."
     }
 
     fn input_schema(&self) -> Value {
         json!({
             "type": "object",
             "properties": {
-                "node_id": {
-                    "type": "string",
-                    "description": "ID of the node to find references to"
-                },
                 "name": {
                     "type": "string",
-                    "description": "Symbol name (alternative to node_id). If ambiguous, add 'file'."
+                    "description": "Name of the trait, interface, protocol, or class"
                 },
                 "file": {
                     "type": "string",
-                    "description": "File path to disambiguate when using 'name'"
+                    "description": "File defining it, when several types share the name"
                 },
-                "edge_kind": {
+                "node_id": {
                     "type": "string",
-                    "description": "Filter by edge kind (calls, imports, extends, implements, references)",
-                    "enum": ["calls", "imports", "extends", "implements", "references"]
-                },
-                "limit": {
-                    "type": "number",
-                    "description": "Maximum number of references to return",
-                    "default": 50
+                    "description": "ID of the type, instead of name"
                 }
             }
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
-
-        let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
-
-        let edge_kind = params
-            .get("edge_kind")
-            .and_then(Value::as_str)
-            .and_then(|s| match s {
-                "calls" => Some(EdgeKind::Calls),
-                "imports" => Some(EdgeKind::Imports),
-                "extends" => Some(EdgeKind::Extends),
-                "implements" => Some(EdgeKind::Implements),
-                "references" => Some(EdgeKind::References),
-                _ => None,
-            });
-
-        let limit = params
-            .get("limit")
-            .and_then(Value::as_u64)
-            .and_then(|n| usize::try_from(n).ok())
-            .unwrap_or(50);
-
-        let edges = db::get_edges_by_target(&conn, &node_id, edge_kind, limit)
-            .map_err(|e| ToolError::internal_error(format!("Failed to get edges: {e}")))?;
-
-        let mut references = Vec::new();
-        for edge in &edges {
-            if let Some(node) = db::get_node_by_id(&conn, &edge.source)
-                .map_err(|e| ToolError::internal_error(format!("Failed to get node: {e}")))?
-            {
-                references.push(json!({
-                    "id": node.id,
-                    "kind": node.kind,
-                    "name": node.name,
-                    "qualified_name": node.qualified_name,
-                    "file_path": node.file_path,
-                    "start_line": node.start_line,
-                    "edge_kind": edge.kind,
-                    "edge_line": edge.line,
-                }));
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
+        let target = if let Some(node_id) = params.get("node_id").and_then(Value::as_str) {
+            db::get_node_by_id(&conn, node_id)
+                .map_err(|e| ToolError::failed("Failed to get node", e))?
+                .ok_or_else(|| ToolError::not_found(format!("Node not found: {node_id}")))?
+        } else {
+            let name = params
+                .get("name")
+                .and_then(Value::as_str)
+                .ok_or_else(|| ToolError::invalid_params("name or node_id is required"))?;
+            let file = params.get("file").and_then(Value::as_str);
+            let mut targets = crate::implementations::find_targets(&conn, name, file)
+                .map_err(|e| ToolError::failed("Failed to find type", e))?;
+            match targets.len() {
+                0 => {
+                    return Err(ToolError::not_found(format!(
+                        "No trait or interface named {name}"
+                    )));
+                }
+                1 => targets.remove(0),
+                _ => {
+                    let files: Vec<&str> = targets.iter().map(|n| n.file_path.as_str()).collect();
+                    return Err(ToolError::invalid_params(format!(
+                        "{name} is defined in several files; pass file: {}",
+                        files.join(", ")
+                    )));
+                }
             }
-        }
+        };
 
-        Ok(json!({
-            "node_id": node_id,
-            "references": references,
-            "count": references.len(),
-        }))
+        let report =
+            crate::implementations::find_implementations(&self.project_root, &conn, &target)
+                .map_err(|e| ToolError::failed("Failed to find implementations", e))?;
+        to_result(&ImplementationsResponse {
+            target: NodeRef::from(&report.target),
+            required: report.required,
+            implementations: report
+                .implementations
+                .into_iter()
+                .map(|implementation| ImplementationEntry {
+                    node: NodeRef::from(&implementation.node),
+                    edge_kind: implementation.kind,
+                    via: implementation.via,
+                    missing: implementation.missing,
+                })
+                .collect(),
+        })
     }
 }
 
-/// Tool for getting full node details including source code
-pub struct GetNodeTool {
+/// Tool that gathers what an agent usually looks up about a symbol in one call
+pub struct ExplainTool {
     project_root: PathBuf,
 }
 
-impl GetNodeTool {
+impl ExplainTool {
     pub const fn new(project_root: PathBuf) -> Self {
         Self { project_root }
     }
 }
 
-impl Tool for GetNodeTool {
+impl Tool for ExplainTool {
     fn name(&self) -> &'static str {
-        "coraline_node"
+        "coraline_explain"
+    }
+
+    fn queries_graph(&self) -> bool {
+        true
     }
 
     fn description(&self) -> &'static str {
-        "Get complete details for a specific node by ID, including its source code body."
+        "Explain a symbol in one call: its definition source, signature and docstring, \
+         top callers and callees, the types implementing or extending it (and those it \
+         extends), project memories mentioning it, and the latest commits touching its lines."
     }
 
     fn input_schema(&self) -> Value {
@@ -840,78 +1840,186 @@ impl Tool for GetNodeTool {
             "properties": {
                 "node_id": {
                     "type": "string",
-                    "description": "The node ID to retrieve"
+                    "description": "ID of the symbol to explain"
                 },
                 "name": {
                     "type": "string",
-                    "description": "Symbol name (alternative to node_id). If ambiguous, add 'file'."
+                    "description": "Symbol name (alternative to node_id). If ambiguous, add 'file_path'."
                 },
-                "file": {
+                "file_path": {
                     "type": "string",
-                    "description": "File path to disambiguate when using 'name'"
+                    "description": "File path to disambiguate when using 'name' (alias: 'file')"
                 },
-                "include_edges": {
-                    "type": "boolean",
-                    "description": "Whether to include outgoing and incoming edge counts",
-                    "default": false
+                "limit": {
+                    "type": "number",
+                    "description": "Maximum callers, callees, and related types to list",
+                    "default": 5
+                },
+                "commits": {
+                    "type": "number",
+                    "description": "Maximum recent commits to list (0 skips git)",
+                    "default": 5
                 }
             }
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, ctx: &ToolContext) -> ToolResult {
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
 
         let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
-
-        let include_edges = params
-            .get("include_edges")
-            .and_then(Value::as_bool)
-            .unwrap_or(false);
-
         let node = db::get_node_by_id(&conn, &node_id)
-            .map_err(|e| ToolError::internal_error(format!("Failed to get node: {e}")))?
+            .map_err(|e| ToolError::failed("Failed to get node", e))?
             .ok_or_else(|| ToolError::not_found(format!("Node not found: {node_id}")))?;
 
+        let get = |key: &str, default: usize| {
+            params
+                .get(key)
+                .and_then(Value::as_u64)
+                .and_then(|n| usize::try_from(n).ok())
+                .unwrap_or(default)
+        };
+        let limit = get("limit", 5);
+        let commits = get("commits", 5);
+
+        let incoming_calls = call_tree(
+            &self.project_root,
+            &conn,
+            &node_id,
+            Direction::Callers,
+            1,
+            limit,
+            limit,
+        )?;
+        let outgoing_calls = call_tree(
+            &self.project_root,
+            &conn,
+            &node_id,
+            Direction::Callees,
+            1,
+            limit,
+            limit,
+        )?;
+        ctx.check_cancelled()?;
+
+        let is_type_edge = |e: &Edge| matches!(e.kind, EdgeKind::Implements | EdgeKind::Extends);
+        let incoming = db::get_edges_by_target(&conn, &node_id, None, 500)
+            .map_err(|e| ToolError::failed("Failed to get edges", e))?;
+        let outgoing = db::get_edges_by_source(&conn, &node_id, None, 500)
+            .map_err(|e| ToolError::failed("Failed to get edges", e))?;
+        let related = |ids: Vec<String>| -> Result<Vec<NodeRef>, ToolError> {
+            let mut nodes = db::get_nodes_by_ids(&conn, &ids)
+                .map_err(|e| ToolError::failed("Failed to get nodes", e))?;
+            nodes.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+            Ok(nodes.iter().take(limit).map(NodeRef::from).collect())
+        };
+        let implementors = related(
+            incoming
+                .iter()
+                .filter(|e| is_type_edge(e))
+                .map(|e| e.source.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+        )?;
+        let supertypes = related(
+            outgoing
+                .iter()
+                .filter(|e| is_type_edge(e))
+                .map(|e| e.target.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+        )?;
+
+        let memories = memory_mentions(&self.project_root, &node);
+        let recent_commits = if commits == 0 {
+            Vec::new()
+        } else {
+            recent_commits(&self.project_root, &node, commits)
+        };
+
         let body = read_node_source(&self.project_root, &node);
+        to_result(&ExplainResponse {
+            symbol: NodeDetails::new(&node, body),
+            callers: incoming_calls.sites,
+            callees: outgoing_calls.sites,
+            implementors,
+            supertypes,
+            memories,
+            recent_commits,
+        })
+    }
+}
 
-        let mut result = json!({
-            "id": node.id,
-            "kind": node.kind,
-            "name": node.name,
-            "qualified_name": node.qualified_name,
-            "file_path": node.file_path,
-            "language": node.language,
-            "start_line": node.start_line,
-            "end_line": node.end_line,
-            "start_column": node.start_column,
-            "end_column": node.end_column,
-            "signature": node.signature,
-            "docstring": node.docstring,
-            "visibility": node.visibility,
-            "is_exported": node.is_exported,
-            "is_async": node.is_async,
-            "is_static": node.is_static,
-            "is_abstract": node.is_abstract,
-            "decorators": node.decorators,
-            "type_parameters": node.type_parameters,
-            "body": body,
-        });
+/// Memories naming `node` as a whole word, with the first line that does,
+/// or listing it in `related_symbols`, with their first line.
+fn memory_mentions(project_root: &Path, node: &Node) -> Vec<MemoryMention> {
+    let Ok(manager) = crate::memory::MemoryManager::new(project_root) else {
+        return Vec::new();
+    };
+    manager
+        .list_all_memories()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|memory| {
+            let related = memory
+                .meta
+                .related_symbols
+                .iter()
+                .any(|s| *s == node.name || *s == node.qualified_name);
+            let mut lines = memory.content.lines().map(str::trim);
+            let excerpt = match lines.clone().find(|line| mentions_word(line, &node.name)) {
+                Some(line) => line,
+                None if related => lines.find(|line| !line.is_empty()).unwrap_or_default(),
+                None => return None,
+            };
+            Some(MemoryMention {
+                name: memory.name,
+                namespace: memory.namespace,
+                excerpt: excerpt.to_string(),
+            })
+        })
+        .collect()
+}
 
-        if include_edges {
-            let out_edges = db::get_edges_by_source(&conn, &node_id, None, 200)
-                .map_err(|e| ToolError::internal_error(format!("Failed to get edges: {e}")))?;
-            let in_edges = db::get_edges_by_target(&conn, &node_id, None, 200)
-                .map_err(|e| ToolError::internal_error(format!("Failed to get edges: {e}")))?;
-            if let Some(obj) = result.as_object_mut() {
-                obj.insert("outgoing_edge_count".to_string(), json!(out_edges.len()));
-                obj.insert("incoming_edge_count".to_string(), json!(in_edges.len()));
-            }
-        }
+/// Whether `word` occurs in `text` not as part of a longer identifier.
+fn mentions_word(text: &str, word: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + word.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
 
-        Ok(result)
-    }
+/// The latest `limit` commits touching `node`'s lines, newest first. Empty
+/// when the project is not a git checkout or the file is untracked.
+fn recent_commits(project_root: &Path, node: &Node, limit: usize) -> Vec<CommitSummary> {
+    let range = format!("-L{},{}:{}", node.start_line, node.end_line, node.file_path);
+    let max_count = format!("--max-count={limit}");
+    let args = [
+        "log",
+        max_count.as_str(),
+        "--no-patch",
+        "--format=%H%x1f%an%x1f%at%x1f%s",
+        range.as_str(),
+    ];
+    let Ok(log) = crate::utils::git(project_root, &args) else {
+        return Vec::new();
+    };
+    log.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\x1f');
+            Some(CommitSummary {
+                commit: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                timestamp: fields.next()?.parse().ok()?,
+                summary: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
 }
 
 /// Tool for the outgoing dependency graph — everything a node depends on.
@@ -930,6 +2038,10 @@ impl Tool for DependenciesTool {
         "coraline_dependencies"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Get the outgoing dependency graph for a node — everything this symbol \
          depends on (calls, imports, references, etc.), traversed up to a given depth. \
@@ -966,9 +2078,9 @@ impl Tool for DependenciesTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
 
         let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
 
@@ -988,45 +2100,23 @@ impl Tool for DependenciesTool {
             direction: Some(TraversalDirection::Outgoing),
             limit: limit.or(Some(50)),
             include_start: Some(false),
+            edge_costs: None,
+            incoming_cost_factor: None,
+            min_confidence: None,
         };
 
         let subgraph = graph::build_subgraph(&conn, std::slice::from_ref(&node_id), &options)
-            .map_err(|e| ToolError::internal_error(format!("Graph traversal failed: {e}")))?;
-
-        let nodes: Vec<Value> = subgraph
-            .nodes
-            .values()
-            .map(|n| {
-                json!({
-                    "id": n.id,
-                    "kind": n.kind,
-                    "name": n.name,
-                    "qualified_name": n.qualified_name,
-                    "file_path": n.file_path,
-                    "start_line": n.start_line,
-                })
-            })
-            .collect();
+            .map_err(|e| ToolError::failed("Graph traversal failed", e))?;
 
-        let edges: Vec<Value> = subgraph
-            .edges
-            .iter()
-            .map(|e| {
-                json!({
-                    "source": e.source,
-                    "target": e.target,
-                    "kind": e.kind,
-                    "line": e.line,
-                })
-            })
-            .collect();
+        let nodes: Vec<NodeRef> = subgraph.nodes.values().map(NodeRef::from).collect();
+        let edges: Vec<EdgeRef> = subgraph.edges.iter().map(EdgeRef::from).collect();
 
-        Ok(json!({
-            "node_id": node_id,
-            "dependencies": nodes,
-            "edges": edges,
-            "count": nodes.len(),
-        }))
+        to_result(&DependenciesResponse {
+            node_id,
+            count: nodes.len(),
+            dependencies: nodes,
+            edges,
+        })
     }
 }
 
@@ -1046,6 +2136,10 @@ impl Tool for DependentsTool {
         "coraline_dependents"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Get the incoming dependency graph for a node — everything that depends on this \
          symbol (all callers, importers, referencers), traversed up to a given depth. \
@@ -1082,9 +2176,9 @@ impl Tool for DependentsTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
 
         let node_id = resolve_node_id(&conn, &self.project_root, &params, "node_id")?;
 
@@ -1104,45 +2198,23 @@ impl Tool for DependentsTool {
             direction: Some(TraversalDirection::Incoming),
             limit: limit.or(Some(50)),
             include_start: Some(false),
+            edge_costs: None,
+            incoming_cost_factor: None,
+            min_confidence: None,
         };
 
         let subgraph = graph::build_subgraph(&conn, std::slice::from_ref(&node_id), &options)
-            .map_err(|e| ToolError::internal_error(format!("Graph traversal failed: {e}")))?;
+            .map_err(|e| ToolError::failed("Graph traversal failed", e))?;
 
-        let nodes: Vec<Value> = subgraph
-            .nodes
-            .values()
-            .map(|n| {
-                json!({
-                    "id": n.id,
-                    "kind": n.kind,
-                    "name": n.name,
-                    "qualified_name": n.qualified_name,
-                    "file_path": n.file_path,
-                    "start_line": n.start_line,
-                })
-            })
-            .collect();
-
-        let edges: Vec<Value> = subgraph
-            .edges
-            .iter()
-            .map(|e| {
-                json!({
-                    "source": e.source,
-                    "target": e.target,
-                    "kind": e.kind,
-                    "line": e.line,
-                })
-            })
-            .collect();
+        let nodes: Vec<NodeRef> = subgraph.nodes.values().map(NodeRef::from).collect();
+        let edges: Vec<EdgeRef> = subgraph.edges.iter().map(EdgeRef::from).collect();
 
-        Ok(json!({
-            "node_id": node_id,
-            "dependents": nodes,
-            "edges": edges,
-            "count": nodes.len(),
-        }))
+        to_result(&DependentsResponse {
+            node_id,
+            count: nodes.len(),
+            dependents: nodes,
+            edges,
+        })
     }
 }
 
@@ -1162,6 +2234,10 @@ impl Tool for PathTool {
         "coraline_path"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Find the shortest directed path through the call/reference graph between two symbols. \
          Useful for understanding indirect dependencies — how does symbol A transitively lead to B?"
@@ -1204,11 +2280,11 @@ impl Tool for PathTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
         use std::collections::{HashMap, VecDeque};
 
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
 
         // Resolve from: use from_id directly, or from_name+from_file
         let from_params = {
@@ -1263,7 +2339,7 @@ impl Tool for PathTool {
                 continue;
             }
             let edges = db::get_edges_by_source(&conn, &current, None, 500)
-                .map_err(|e| ToolError::internal_error(format!("Edge query failed: {e}")))?;
+                .map_err(|e| ToolError::failed("Edge query failed", e))?;
             for edge in edges {
                 if parent.contains_key(&edge.target) {
                     continue;
@@ -1278,15 +2354,16 @@ impl Tool for PathTool {
         }
 
         if !found {
-            return Ok(json!({
-                "from_id": from_id,
-                "to_id": to_id,
-                "path_found": false,
-                "path": [],
-                "message": format!(
+            return to_result(&PathResponse {
+                message: Some(format!(
                     "No directed path found from {from_id} to {to_id} within depth {max_depth}"
-                ),
-            }));
+                )),
+                from_id,
+                to_id,
+                path_found: false,
+                path: Vec::new(),
+                length: None,
+            });
         }
 
         // Reconstruct path by walking parents backward from to_id.
@@ -1298,28 +2375,20 @@ impl Tool for PathTool {
         }
         path_ids.reverse();
 
-        let path: Vec<Value> = path_ids
+        let path: Vec<NodeRef> = path_ids
             .iter()
             .filter_map(|id| db::get_node_by_id(&conn, id).ok().flatten())
-            .map(|n| {
-                json!({
-                    "id": n.id,
-                    "kind": n.kind,
-                    "name": n.name,
-                    "qualified_name": n.qualified_name,
-                    "file_path": n.file_path,
-                    "start_line": n.start_line,
-                })
-            })
+            .map(|n| NodeRef::from(&n))
             .collect();
 
-        Ok(json!({
-            "from_id": from_id,
-            "to_id": to_id,
-            "path_found": true,
-            "path": path,
-            "length": path.len(),
-        }))
+        to_result(&PathResponse {
+            from_id,
+            to_id,
+            path_found: true,
+            length: Some(path.len()),
+            path,
+            message: None,
+        })
     }
 }
 
@@ -1339,6 +2408,10 @@ impl Tool for StatsTool {
         "coraline_stats"
     }
 
+    fn queries_graph(&self) -> bool {
+        true
+    }
+
     fn description(&self) -> &'static str {
         "Return detailed graph statistics: total counts, per-language file breakdown, node kind breakdown, and edge kind breakdown."
     }
@@ -1350,113 +2423,352 @@ impl Tool for StatsTool {
         })
     }
 
-    fn execute(&self, _params: Value) -> ToolResult {
+    fn execute(&self, _params: Value, _ctx: &ToolContext) -> ToolResult {
         let conn = db::open_database(&self.project_root)
-            .map_err(|e| ToolError::internal_error(format!("Failed to open database: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to open database", e))?;
 
         // Basic counts
         let node_count: i64 = conn
             .query_row("SELECT COUNT(*) FROM nodes", [], |r| r.get(0))
-            .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+            .map_err(|e| ToolError::failed("Query failed", e))?;
         let edge_count: i64 = conn
             .query_row("SELECT COUNT(*) FROM edges", [], |r| r.get(0))
-            .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+            .map_err(|e| ToolError::failed("Query failed", e))?;
         let file_count: i64 = conn
             .query_row("SELECT COUNT(*) FROM files", [], |r| r.get(0))
-            .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+            .map_err(|e| ToolError::failed("Query failed", e))?;
         let unresolved_count: i64 = conn
             .query_row("SELECT COUNT(*) FROM unresolved_refs", [], |r| r.get(0))
-            .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+            .map_err(|e| ToolError::failed("Query failed", e))?;
         let vector_count: i64 = conn
             .query_row("SELECT COUNT(*) FROM vectors", [], |r| r.get(0))
-            .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+            .map_err(|e| ToolError::failed("Query failed", e))?;
 
         // Files by language
-        let mut by_language = serde_json::Map::new();
+        let mut by_language = std::collections::BTreeMap::new();
         {
             let mut stmt = conn
                 .prepare("SELECT language, COUNT(*) FROM files GROUP BY language ORDER BY 2 DESC")
-                .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+                .map_err(|e| ToolError::failed("Query failed", e))?;
             let rows = stmt
                 .query_map([], |row| {
                     Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
                 })
-                .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+                .map_err(|e| ToolError::failed("Query failed", e))?;
             for row in rows.flatten() {
-                by_language.insert(row.0, Value::Number(row.1.into()));
+                by_language.insert(row.0, row.1);
             }
         }
 
         // Nodes by kind
-        let mut by_kind = serde_json::Map::new();
+        let mut by_kind = std::collections::BTreeMap::new();
         {
             let mut stmt = conn
                 .prepare("SELECT kind, COUNT(*) FROM nodes GROUP BY kind ORDER BY 2 DESC")
-                .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+                .map_err(|e| ToolError::failed("Query failed", e))?;
             let rows = stmt
                 .query_map([], |row| {
                     Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
                 })
-                .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+                .map_err(|e| ToolError::failed("Query failed", e))?;
             for row in rows.flatten() {
-                by_kind.insert(row.0, Value::Number(row.1.into()));
+                by_kind.insert(row.0, row.1);
             }
         }
 
         // Edges by kind
-        let mut by_edge_kind = serde_json::Map::new();
+        let mut by_edge_kind = std::collections::BTreeMap::new();
         {
             let mut stmt = conn
                 .prepare("SELECT kind, COUNT(*) FROM edges GROUP BY kind ORDER BY 2 DESC")
-                .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+                .map_err(|e| ToolError::failed("Query failed", e))?;
             let rows = stmt
                 .query_map([], |row| {
                     Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
                 })
-                .map_err(|e| ToolError::internal_error(format!("Query failed: {e}")))?;
+                .map_err(|e| ToolError::failed("Query failed", e))?;
             for row in rows.flatten() {
-                by_edge_kind.insert(row.0, Value::Number(row.1.into()));
+                by_edge_kind.insert(row.0, row.1);
             }
         }
 
-        Ok(json!({
-            "totals": {
-                "nodes": node_count,
-                "edges": edge_count,
-                "files": file_count,
-                "unresolved_references": unresolved_count,
-                "vectors": vector_count,
+        to_result(&StatsResponse {
+            totals: StatsTotals {
+                nodes: node_count,
+                edges: edge_count,
+                files: file_count,
+                unresolved_references: unresolved_count,
+                vectors: vector_count,
             },
-            "files_by_language": by_language,
-            "nodes_by_kind": by_kind,
-            "edges_by_kind": by_edge_kind,
-        }))
+            files_by_language: by_language,
+            nodes_by_kind: by_kind,
+            edges_by_kind: by_edge_kind,
+        })
     }
 }
 
 // ── Helpers ──────────────────────────────────────────────────────────────────
 
+/// Which way [`call_tree`] follows `calls` edges.
+#[derive(Clone, Copy)]
+enum Direction {
+    Callers,
+    Callees,
+}
+
+/// Result of [`call_tree`].
+struct CallTree {
+    sites: Vec<CallSite>,
+    /// Symbols in the whole tree.
+    total: usize,
+    /// `max_nodes` stopped the walk before `depth` was reached.
+    truncated: bool,
+}
+
+/// `limit`, `depth`, and `max_nodes` for the callers and callees tools.
+fn call_tree_params(params: &Value) -> (usize, usize, usize) {
+    let get = |key: &str, default: usize| {
+        params
+            .get(key)
+            .and_then(Value::as_u64)
+            .and_then(|n| usize::try_from(n).ok())
+            .unwrap_or(default)
+    };
+    (
+        get("limit", 20),
+        get("depth", 1).max(1),
+        get("max_nodes", 50),
+    )
+}
+
+/// Walk `calls` edges from `root_id` breadth-first for up to `depth` levels,
+/// taking at most `limit` neighbours per symbol and `max_nodes` in total.
+/// Each symbol appears once, at the shallowest level it was reached, so
+/// recursion and shared helpers do not repeat.
+fn call_tree(
+    project_root: &Path,
+    conn: &rusqlite::Connection,
+    root_id: &str,
+    direction: Direction,
+    depth: usize,
+    limit: usize,
+    max_nodes: usize,
+) -> Result<CallTree, ToolError> {
+    let root = db::get_node_by_id(conn, root_id)
+        .map_err(|e| ToolError::failed("Failed to get node", e))?;
+
+    // Flat arena of (site, parent index); children are attached afterwards.
+    let mut arena: Vec<(CallSite, Option<usize>)> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::from([root_id.to_string()]);
+    let mut frontier = vec![(root_id.to_string(), root, None)];
+    let mut truncated = false;
+    let mut staleness = StalenessCheck::new(project_root, conn);
+
+    'walk: for _ in 0..depth {
+        let mut next = Vec::new();
+        for (id, node, parent) in frontier {
+            for (neighbor, line) in call_neighbors(conn, &id, node.as_ref(), direction, limit)? {
+                if !seen.insert(neighbor.id.clone()) {
+                    continue;
+                }
+                if arena.len() >= max_nodes {
+                    truncated = true;
+                    break 'walk;
+                }
+                let index = arena.len();
+                arena.push((
+                    CallSite {
+                        node: NodeRef::from(&neighbor),
+                        line,
+                        stale: staleness.is_stale(&neighbor),
+                        children: Vec::new(),
+                    },
+                    parent,
+                ));
+                next.push((neighbor.id.clone(), Some(neighbor), Some(index)));
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+
+    let total = arena.len();
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); total];
+    let mut roots = Vec::new();
+    for (index, (_, parent)) in arena.iter().enumerate() {
+        match parent.and_then(|parent| children.get_mut(parent)) {
+            Some(siblings) => siblings.push(index),
+            None => roots.push(index),
+        }
+    }
+    let mut slots: Vec<Option<CallSite>> = arena.into_iter().map(|(site, _)| Some(site)).collect();
+    let sites = roots
+        .into_iter()
+        .filter_map(|index| assemble_call_site(index, &children, &mut slots))
+        .collect();
+
+    Ok(CallTree {
+        sites,
+        total,
+        truncated,
+    })
+}
+
+fn assemble_call_site(
+    index: usize,
+    children: &[Vec<usize>],
+    slots: &mut [Option<CallSite>],
+) -> Option<CallSite> {
+    let mut site = slots.get_mut(index)?.take()?;
+    site.children = children
+        .get(index)?
+        .iter()
+        .filter_map(|&child| assemble_call_site(child, children, slots))
+        .collect();
+    Some(site)
+}
+
+/// Direct callers or callees of one symbol whose call edges pass the
+/// crate/import boundary check, with the line of each call.
+fn call_neighbors(
+    conn: &rusqlite::Connection,
+    node_id: &str,
+    node: Option<&crate::types::Node>,
+    direction: Direction,
+    limit: usize,
+) -> Result<Vec<(crate::types::Node, Option<i64>)>, ToolError> {
+    let edges = match direction {
+        Direction::Callers => {
+            db::get_edges_by_target(conn, node_id, Some(EdgeKind::Calls), limit * 2)
+        }
+        Direction::Callees => {
+            db::get_edges_by_source(conn, node_id, Some(EdgeKind::Calls), limit * 2)
+        }
+    }
+    .map_err(|e| ToolError::failed("Failed to get edges", e))?;
+
+    let mut neighbors = Vec::new();
+    for edge in edges {
+        let other_id = match direction {
+            Direction::Callers => &edge.source,
+            Direction::Callees => &edge.target,
+        };
+        let Some(other) = db::get_node_by_id(conn, other_id)
+            .map_err(|e| ToolError::failed("Failed to get node", e))?
+        else {
+            continue;
+        };
+
+        // Validate that the call edge has proper crate/import boundaries.
+        // Without the node itself (shouldn't happen) the edge is allowed.
+        let is_valid = match (node, direction) {
+            (Some(target), Direction::Callers) => db::is_valid_call_edge(conn, &other, target),
+            (Some(source), Direction::Callees) => db::is_valid_call_edge(conn, source, &other),
+            (None, _) => Ok(true),
+        }
+        .map_err(|e| ToolError::failed("Failed to validate edge", e))?;
+
+        if is_valid {
+            neighbors.push((other, edge.line));
+            if neighbors.len() >= limit {
+                break;
+            }
+        }
+    }
+    Ok(neighbors)
+}
+
+/// Whether an import node binds a name from `file_path`: its module path
+/// (`./math`, `crate::math`, `app.math`) names the file's stem, or the
+/// directory of an `index`/`mod`/`__init__` file.
+fn imports_from(import: &Node, file_path: &str) -> bool {
+    let signature = import.signature.as_deref().unwrap_or_default();
+    let (module, export_name) = signature
+        .split_once("|export=")
+        .map_or((signature, None), |(module, export)| (module, Some(export)));
+    if export_name.is_some_and(|export| export != import.name) {
+        return false;
+    }
+    let path = std::path::Path::new(file_path);
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let module_name = if matches!(stem, "index" | "mod" | "__init__" | "lib" | "main") {
+        path.parent()
+            .and_then(|p| p.file_name())
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+    } else {
+        stem
+    };
+    !module_name.is_empty()
+        && module
+            .split(['/', ':', '.', '\\'])
+            .any(|segment| segment == module_name)
+}
+
+/// A symbol reference from tool params, resolved against the graph.
+enum Resolved {
+    Node(String),
+    /// Several symbols share the requested name, sorted by file and line.
+    Ambiguous(Vec<crate::types::Node>),
+}
+
 /// Resolve a node ID from tool params.
 ///
 /// Accepts either:
 ///  - `node_id` directly, **or**
-///  - `name` (+ optional `file` for disambiguation)
+///  - `name` (+ optional `file_path`, or its alias `file`, for disambiguation)
 ///
-/// When `name` matches multiple nodes and no `file` is given, returns an error
-/// listing all candidates so the caller can retry with a `file` hint.
+/// When `name` matches multiple nodes and no file narrows them down, returns an
+/// error listing all candidates so the caller can retry with a file hint.
 fn resolve_node_id(
     conn: &rusqlite::Connection,
     project_root: &std::path::Path,
     params: &Value,
     id_field: &str,
 ) -> Result<String, ToolError> {
+    match resolve_symbol(conn, project_root, params, id_field)? {
+        Resolved::Node(id) => Ok(id),
+        Resolved::Ambiguous(candidates) => {
+            let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+            let listing: Vec<String> = candidates
+                .iter()
+                .map(|n| {
+                    format!(
+                        "  {} ({:?}) — {}:{}",
+                        n.id, n.kind, n.file_path, n.start_line
+                    )
+                })
+                .collect();
+            Err(ToolError::invalid_params(format!(
+                "Ambiguous: {count} symbols named '{name}'. \
+                 Supply '{id_field}' or add 'file_path' to disambiguate:\n{list}",
+                count = candidates.len(),
+                list = listing.join("\n"),
+            )))
+        }
+    }
+}
+
+/// Like [`resolve_node_id`], but hands an ambiguous name back to the caller
+/// instead of failing, so tools can return the candidates as data.
+fn resolve_symbol(
+    conn: &rusqlite::Connection,
+    project_root: &std::path::Path,
+    params: &Value,
+    id_field: &str,
+) -> Result<Resolved, ToolError> {
     // Fast path: explicit node_id
     if let Some(id) = params
         .get(id_field)
         .and_then(Value::as_str)
         .filter(|s| !s.is_empty())
     {
-        return Ok(id.to_string());
+        return Ok(Resolved::Node(id.to_string()));
     }
 
     // Slow path: resolve by name (+ optional file)
@@ -1464,20 +2776,14 @@ fn resolve_node_id(
         ToolError::invalid_params(format!("Either '{id_field}' or 'name' must be provided"))
     })?;
 
-    let file_hint = params.get("file").and_then(Value::as_str);
-
-    let mut candidates = db::find_nodes_by_name(conn, name)
-        .map_err(|e| ToolError::internal_error(format!("Name lookup failed: {e}")))?;
+    let file_hint = params
+        .get("file_path")
+        .or_else(|| params.get("file"))
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty());
 
-    // Narrow by file if provided
-    if let Some(file) = file_hint {
-        let abs_hint = if std::path::Path::new(file).is_absolute() {
-            file.to_string()
-        } else {
-            project_root.join(file).to_string_lossy().to_string()
-        };
-        candidates.retain(|n| n.file_path == abs_hint || n.file_path == file);
-    }
+    let candidates = crate::graph::find_symbol(conn, project_root, name, file_hint)
+        .map_err(|e| ToolError::failed("Name lookup failed", e))?;
 
     match candidates.len() {
         0 => Err(ToolError::not_found(format!(
@@ -1487,25 +2793,9 @@ fn resolve_node_id(
         1 => candidates
             .into_iter()
             .next()
-            .map(|n| n.id)
+            .map(|n| Resolved::Node(n.id))
             .ok_or_else(|| ToolError::internal_error("internal: candidate count mismatch")),
-        _ => {
-            let listing: Vec<String> = candidates
-                .iter()
-                .map(|n| {
-                    format!(
-                        "  {} ({:?}) — {}:{}",
-                        n.id, n.kind, n.file_path, n.start_line
-                    )
-                })
-                .collect();
-            Err(ToolError::invalid_params(format!(
-                "Ambiguous: {count} symbols named '{name}'. \
-                 Supply '{id_field}' or add 'file' to disambiguate:\n{list}",
-                count = candidates.len(),
-                list = listing.join("\n"),
-            )))
-        }
+        _ => Ok(Resolved::Ambiguous(candidates)),
     }
 }
 
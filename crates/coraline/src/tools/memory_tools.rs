@@ -9,8 +9,11 @@ use std::path::Path;
 
 use serde_json::{Value, json};
 
-use crate::memory::MemoryManager;
-use crate::tools::{Tool, ToolError, ToolResult};
+use crate::memory::{self, MemoryManager, Namespace};
+use crate::tools::responses::{
+    MemoryContentResponse, MemoryListResponse, MemoryListing, MessageResponse, to_result,
+};
+use crate::tools::{Tool, ToolContext, ToolError, ToolResult, string_list};
 
 /// Tool for writing/updating memories.
 pub struct WriteMemoryTool {
@@ -42,35 +45,87 @@ impl Tool for WriteMemoryTool {
                     "type": "string",
                     "description": "Memory name (without .md extension). Use descriptive names like 'project_overview', 'architecture_notes', etc."
                 },
+                "namespace": {
+                    "type": "string",
+                    "description": "Namespace: 'shared' (committed), 'local' (gitignored), or 'agents/<name>'. Defaults to the configured namespace."
+                },
                 "content": {
                     "type": "string",
                     "description": "Memory content in markdown format."
+                },
+                "tags": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Tags for the memory (replaces existing tags)."
+                },
+                "author": {
+                    "type": "string",
+                    "description": "Who wrote the memory."
+                },
+                "related_symbols": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Symbols the memory is about, by name or qualified name (replaces existing ones)."
                 }
             },
             "required": ["name", "content"]
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
-        let name = params
-            .get("name")
-            .and_then(Value::as_str)
-            .ok_or_else(|| ToolError::invalid_params("Missing or invalid 'name' parameter"))?;
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let name = memory_name(&params)?;
 
         let content = params
             .get("content")
             .and_then(Value::as_str)
             .ok_or_else(|| ToolError::invalid_params("Missing or invalid 'content' parameter"))?;
 
-        let result = self
-            .manager
-            .write_memory(name, content)
-            .map_err(|e| ToolError::internal_error(format!("Failed to write memory: {e}")))?;
+        let tags = string_list(&params, "tags")?;
+        let related_symbols = string_list(&params, "related_symbols")?;
+        let author = params.get("author").and_then(Value::as_str);
 
-        Ok(json!({ "message": result }))
+        let result = scoped(&self.manager, &params)?
+            .write_memory_with(name, content, |meta| {
+                if let Some(tags) = tags {
+                    meta.tags = tags;
+                }
+                if let Some(related) = related_symbols {
+                    meta.related_symbols = related;
+                }
+                if let Some(author) = author {
+                    meta.author = Some(author.to_string()).filter(|a| !a.is_empty());
+                }
+            })
+            .map_err(|e| ToolError::failed("Failed to write memory", e))?;
+
+        to_result(&MessageResponse { message: result })
     }
 }
 
+/// The `name` parameter, checked by [`memory::validate_name`] so it cannot
+/// reach outside the memories directory.
+fn memory_name(params: &Value) -> Result<&str, ToolError> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ToolError::invalid_params("Missing or invalid 'name' parameter"))?;
+    memory::validate_name(name).map_err(ToolError::invalid_params)?;
+    Ok(name)
+}
+
+/// `manager` moved to the `namespace` parameter, when one is given.
+fn scoped(manager: &MemoryManager, params: &Value) -> Result<MemoryManager, ToolError> {
+    params.get("namespace").and_then(Value::as_str).map_or_else(
+        || Ok(manager.clone()),
+        |namespace| {
+            namespace
+                .parse::<Namespace>()
+                .map(|namespace| manager.with_namespace(namespace))
+                .map_err(ToolError::invalid_params)
+        },
+    )
+}
+
 /// Tool for reading memories.
 pub struct ReadMemoryTool {
     manager: MemoryManager,
@@ -100,24 +155,36 @@ impl Tool for ReadMemoryTool {
                 "name": {
                     "type": "string",
                     "description": "Memory name to read (without .md extension)."
+                },
+                "namespace": {
+                    "type": "string",
+                    "description": "Namespace: 'shared' (committed), 'local' (gitignored), or 'agents/<name>'. Defaults to the configured namespace."
                 }
             },
             "required": ["name"]
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
-        let name = params
-            .get("name")
-            .and_then(Value::as_str)
-            .ok_or_else(|| ToolError::invalid_params("Missing or invalid 'name' parameter"))?;
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let name = memory_name(&params)?;
 
-        let content = self
-            .manager
-            .read_memory(name)
-            .map_err(|e| ToolError::internal_error(format!("Failed to read memory: {e}")))?;
+        let memory = scoped(&self.manager, &params)?
+            .get_memory(name)
+            .map_err(|e| ToolError::failed("Failed to read memory", e))?;
 
-        Ok(json!({ "content": content }))
+        let response = match memory {
+            Some(memory) => MemoryContentResponse {
+                content: memory.content,
+                meta: Some(memory.meta),
+            },
+            None => MemoryContentResponse {
+                content: format!(
+                    "Memory '{name}' not found. Consider creating it with write_memory if needed."
+                ),
+                meta: None,
+            },
+        };
+        to_result(&response)
     }
 }
 
@@ -140,23 +207,38 @@ impl Tool for ListMemoriesTool {
     }
 
     fn description(&self) -> &'static str {
-        "List all available project memories. Use to discover what knowledge is stored."
+        "List project memories with their namespace, tags, author, related symbols, and timestamps. Use to discover what knowledge is stored."
     }
 
     fn input_schema(&self) -> Value {
         json!({
             "type": "object",
-            "properties": {}
+            "properties": {
+                "namespace": {
+                    "type": "string",
+                    "description": "Only list this namespace: 'shared', 'local', or 'agents/<name>'. Lists every namespace when omitted."
+                }
+            }
         })
     }
 
-    fn execute(&self, _params: Value) -> ToolResult {
-        let memories = self
-            .manager
-            .list_memories()
-            .map_err(|e| ToolError::internal_error(format!("Failed to list memories: {e}")))?;
-
-        Ok(json!({ "memories": memories }))
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let memories = if params.get("namespace").is_some() {
+            scoped(&self.manager, &params)?.list_memories()
+        } else {
+            self.manager.list_all_memories()
+        };
+        let memories = memories
+            .map_err(|e| ToolError::failed("Failed to list memories", e))?
+            .into_iter()
+            .map(|memory| MemoryListing {
+                name: memory.name,
+                namespace: memory.namespace,
+                meta: memory.meta,
+            })
+            .collect();
+
+        to_result(&MemoryListResponse { memories })
     }
 }
 
@@ -189,24 +271,24 @@ impl Tool for DeleteMemoryTool {
                 "name": {
                     "type": "string",
                     "description": "Memory name to delete (without .md extension)."
+                },
+                "namespace": {
+                    "type": "string",
+                    "description": "Namespace: 'shared' (committed), 'local' (gitignored), or 'agents/<name>'. Defaults to the configured namespace."
                 }
             },
             "required": ["name"]
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
-        let name = params
-            .get("name")
-            .and_then(Value::as_str)
-            .ok_or_else(|| ToolError::invalid_params("Missing or invalid 'name' parameter"))?;
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let name = memory_name(&params)?;
 
-        let result = self
-            .manager
+        let result = scoped(&self.manager, &params)?
             .delete_memory(name)
-            .map_err(|e| ToolError::internal_error(format!("Failed to delete memory: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to delete memory", e))?;
 
-        Ok(json!({ "message": result }))
+        to_result(&MessageResponse { message: result })
     }
 }
 
@@ -240,6 +322,10 @@ impl Tool for EditMemoryTool {
                     "type": "string",
                     "description": "Memory name to edit (without .md extension)."
                 },
+                "namespace": {
+                    "type": "string",
+                    "description": "Namespace: 'shared' (committed), 'local' (gitignored), or 'agents/<name>'. Defaults to the configured namespace."
+                },
                 "pattern": {
                     "type": "string",
                     "description": "Pattern to search for (literal string or regex depending on mode)."
@@ -259,11 +345,8 @@ impl Tool for EditMemoryTool {
         })
     }
 
-    fn execute(&self, params: Value) -> ToolResult {
-        let name = params
-            .get("name")
-            .and_then(Value::as_str)
-            .ok_or_else(|| ToolError::invalid_params("Missing or invalid 'name' parameter"))?;
+    fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
+        let name = memory_name(&params)?;
 
         let pattern = params
             .get("pattern")
@@ -282,11 +365,12 @@ impl Tool for EditMemoryTool {
             .and_then(Value::as_str)
             .unwrap_or("literal");
 
+        let manager = scoped(&self.manager, &params)?;
+
         // Read current content
-        let content = self
-            .manager
+        let content = manager
             .read_memory(name)
-            .map_err(|e| ToolError::internal_error(format!("Failed to read memory: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to read memory", e))?;
 
         // Handle "not found" message
         if content.contains("not found") {
@@ -310,12 +394,11 @@ impl Tool for EditMemoryTool {
         };
 
         // Write updated content
-        let result = self
-            .manager
+        let result = manager
             .write_memory(name, &new_content)
-            .map_err(|e| ToolError::internal_error(format!("Failed to write memory: {e}")))?;
+            .map_err(|e| ToolError::failed("Failed to write memory", e))?;
 
-        Ok(json!({ "message": result }))
+        to_result(&MessageResponse { message: result })
     }
 }
 
@@ -347,7 +430,7 @@ mod tests {
         });
 
         let result = write_tool
-            .execute(params)
+            .execute(params, &ToolContext::default())
             .expect("Failed to execute write_tool");
         assert!(
             result
@@ -359,7 +442,7 @@ mod tests {
 
         let params = json!({ "name": "test_memory" });
         let result = read_tool
-            .execute(params)
+            .execute(params, &ToolContext::default())
             .expect("Failed to execute read_tool");
         assert_eq!(
             result
@@ -380,14 +463,20 @@ mod tests {
             ListMemoriesTool::new(&path_buf).expect("Failed to create ListMemoriesTool");
 
         write_tool
-            .execute(json!({"name": "mem1", "content": "content1"}))
+            .execute(
+                json!({"name": "mem1", "content": "content1"}),
+                &ToolContext::default(),
+            )
             .expect("Failed to write memory mem1");
         write_tool
-            .execute(json!({"name": "mem2", "content": "content2"}))
+            .execute(
+                json!({"name": "mem2", "content": "content2"}),
+                &ToolContext::default(),
+            )
             .expect("Failed to write memory mem2");
 
         let result = list_tool
-            .execute(json!({}))
+            .execute(json!({}), &ToolContext::default())
             .expect("Failed to execute list_tool");
         let memories = result
             .get("memories")
@@ -396,6 +485,66 @@ mod tests {
         assert_eq!(memories.len(), 2);
     }
 
+    #[test]
+    fn test_memory_tools_namespace_param() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let path_buf = temp_dir.path().to_path_buf();
+        init_project_root(&path_buf);
+        let write_tool = WriteMemoryTool::new(&path_buf).expect("Failed to create WriteMemoryTool");
+        let read_tool = ReadMemoryTool::new(&path_buf).expect("Failed to create ReadMemoryTool");
+        let list_tool =
+            ListMemoriesTool::new(&path_buf).expect("Failed to create ListMemoriesTool");
+        let ctx = ToolContext::default();
+
+        write_tool
+            .execute(json!({"name": "notes", "content": "team"}), &ctx)
+            .expect("Failed to write shared memory");
+        write_tool
+            .execute(
+                json!({"name": "notes", "content": "mine", "namespace": "local"}),
+                &ctx,
+            )
+            .expect("Failed to write local memory");
+
+        let read = |params| {
+            read_tool
+                .execute(params, &ctx)
+                .expect("Failed to execute read_tool")
+                .get("content")
+                .cloned()
+                .unwrap_or_default()
+        };
+        assert_eq!(read(json!({"name": "notes"})), "team");
+        assert_eq!(read(json!({"name": "notes", "namespace": "local"})), "mine");
+
+        let all = list_tool
+            .execute(json!({}), &ctx)
+            .expect("Failed to execute list_tool");
+        let namespaces: Vec<&str> = all
+            .get("memories")
+            .and_then(Value::as_array)
+            .expect("Result should contain memories array")
+            .iter()
+            .filter_map(|m| m.get("namespace").and_then(Value::as_str))
+            .collect();
+        assert_eq!(namespaces, vec!["shared", "local"]);
+        let local = list_tool
+            .execute(json!({"namespace": "local"}), &ctx)
+            .expect("Failed to execute list_tool");
+        assert_eq!(
+            local
+                .get("memories")
+                .and_then(Value::as_array)
+                .map(Vec::len),
+            Some(1)
+        );
+
+        let err = read_tool
+            .execute(json!({"name": "notes", "namespace": "agents/../x"}), &ctx)
+            .expect_err("invalid namespaces are rejected");
+        assert_eq!(err.code, "invalid_params");
+    }
+
     #[test]
     fn test_delete_memory_tool() {
         let temp_dir = TempDir::new().expect("Failed to create temp directory");
@@ -406,11 +555,14 @@ mod tests {
             DeleteMemoryTool::new(&path_buf).expect("Failed to create DeleteMemoryTool");
 
         write_tool
-            .execute(json!({"name": "to_delete", "content": "content"}))
+            .execute(
+                json!({"name": "to_delete", "content": "content"}),
+                &ToolContext::default(),
+            )
             .expect("Failed to write memory to_delete");
 
         let result = delete_tool
-            .execute(json!({"name": "to_delete"}))
+            .execute(json!({"name": "to_delete"}), &ToolContext::default())
             .expect("Failed to execute delete_tool");
         assert!(
             result
@@ -431,20 +583,26 @@ mod tests {
         let read_tool = ReadMemoryTool::new(&path_buf).expect("Failed to create ReadMemoryTool");
 
         write_tool
-            .execute(json!({"name": "edit_test", "content": "Hello World"}))
+            .execute(
+                json!({"name": "edit_test", "content": "Hello World"}),
+                &ToolContext::default(),
+            )
             .expect("Failed to write memory edit_test");
 
         edit_tool
-            .execute(json!({
-                "name": "edit_test",
-                "pattern": "World",
-                "replacement": "Rust",
-                "mode": "literal"
-            }))
+            .execute(
+                json!({
+                    "name": "edit_test",
+                    "pattern": "World",
+                    "replacement": "Rust",
+                    "mode": "literal"
+                }),
+                &ToolContext::default(),
+            )
             .expect("Failed to execute edit_tool");
 
         let result = read_tool
-            .execute(json!({"name": "edit_test"}))
+            .execute(json!({"name": "edit_test"}), &ToolContext::default())
             .expect("Failed to execute read_tool");
         assert_eq!(
             result
@@ -465,20 +623,26 @@ mod tests {
         let read_tool = ReadMemoryTool::new(&path_buf).expect("Failed to create ReadMemoryTool");
 
         write_tool
-            .execute(json!({"name": "regex_test", "content": "version: 1.0.0"}))
+            .execute(
+                json!({"name": "regex_test", "content": "version: 1.0.0"}),
+                &ToolContext::default(),
+            )
             .expect("Failed to write memory regex_test");
 
         edit_tool
-            .execute(json!({
-                "name": "regex_test",
-                "pattern": r"version: \d+\.\d+\.\d+",
-                "replacement": "version: 2.0.0",
-                "mode": "regex"
-            }))
+            .execute(
+                json!({
+                    "name": "regex_test",
+                    "pattern": r"version: \d+\.\d+\.\d+",
+                    "replacement": "version: 2.0.0",
+                    "mode": "regex"
+                }),
+                &ToolContext::default(),
+            )
             .expect("Failed to execute edit_tool with regex");
 
         let result = read_tool
-            .execute(json!({"name": "regex_test"}))
+            .execute(json!({"name": "regex_test"}), &ToolContext::default())
             .expect("Failed to execute read_tool");
         assert_eq!(
             result
@@ -488,4 +652,34 @@ mod tests {
             "version: 2.0.0"
         );
     }
+
+    #[test]
+    fn test_memory_tools_reject_path_traversal() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let path_buf = temp_dir.path().to_path_buf();
+        init_project_root(&path_buf);
+        let ctx = ToolContext::default();
+        let tools: Vec<Box<dyn Tool>> = vec![
+            Box::new(WriteMemoryTool::new(&path_buf).expect("Failed to create WriteMemoryTool")),
+            Box::new(ReadMemoryTool::new(&path_buf).expect("Failed to create ReadMemoryTool")),
+            Box::new(DeleteMemoryTool::new(&path_buf).expect("Failed to create DeleteMemoryTool")),
+            Box::new(EditMemoryTool::new(&path_buf).expect("Failed to create EditMemoryTool")),
+        ];
+
+        for tool in &tools {
+            for name in ["../../config", "..", "nested/name"] {
+                let params = json!({
+                    "name": name,
+                    "content": "x",
+                    "pattern": "a",
+                    "replacement": "b"
+                });
+                let err = tool
+                    .execute(params, &ctx)
+                    .expect_err("traversal names are rejected");
+                assert_eq!(err.code, "invalid_params", "{} {name}", tool.name());
+            }
+        }
+        assert!(!path_buf.join("config.md").exists());
+    }
 }
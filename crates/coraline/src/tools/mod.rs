@@ -8,12 +8,15 @@
 
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub mod audit_tools;
 pub mod context_tools;
 pub mod file_tools;
 pub mod graph_tools;
 pub mod memory_tools;
+pub mod responses;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ToolRisk {
@@ -33,8 +36,72 @@ impl ToolRisk {
 /// Result type for tool execution
 pub type ToolResult = Result<Value, ToolError>;
 
+/// Progress sink: `(progress, total, message)`.
+pub type ProgressFn = dyn Fn(f64, Option<f64>, Option<&str>) + Send + Sync;
+
+/// Per-call state passed to [`Tool::execute`]: a cancellation flag the
+/// caller may set while the tool runs, and an optional progress sink.
+///
+/// Long-running tools should call [`ToolContext::check_cancelled`] between
+/// steps and report progress as they go; quick tools can ignore it.
+#[derive(Clone, Default)]
+pub struct ToolContext {
+    cancelled: Arc<AtomicBool>,
+    progress: Option<Arc<ProgressFn>>,
+}
+
+impl ToolContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Route progress reports to `sink`.
+    #[must_use]
+    pub fn with_progress(
+        mut self,
+        sink: impl Fn(f64, Option<f64>, Option<&str>) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(sink));
+        self
+    }
+
+    /// Ask the running tool to stop. Clones share the flag.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// `Err` with code `cancelled` once [`ToolContext::cancel`] was called.
+    pub fn check_cancelled(&self) -> Result<(), ToolError> {
+        if self.is_cancelled() {
+            Err(ToolError::cancelled())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Report progress; a no-op without a sink.
+    pub fn report_progress(&self, progress: f64, total: Option<f64>, message: Option<&str>) {
+        if let Some(sink) = &self.progress {
+            sink(progress, total, message);
+        }
+    }
+}
+
+impl std::fmt::Debug for ToolContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolContext")
+            .field("cancelled", &self.is_cancelled())
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
 /// Error type for tool execution failures
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ToolError {
     pub code: String,
     pub message: String,
@@ -59,6 +126,10 @@ impl ToolError {
     pub fn not_found(message: impl Into<String>) -> Self {
         Self::new("not_found", message)
     }
+
+    pub fn cancelled() -> Self {
+        Self::new("cancelled", "Request cancelled")
+    }
 }
 
 impl std::fmt::Display for ToolError {
@@ -80,14 +151,27 @@ pub trait Tool: Send + Sync {
     /// JSON schema for input parameters
     fn input_schema(&self) -> Value;
 
+    /// Whether results come from the graph, so a registry that checks index
+    /// freshness does so before the call.
+    fn queries_graph(&self) -> bool {
+        false
+    }
+
     /// Execute the tool with given parameters
-    fn execute(&self, params: Value) -> ToolResult;
+    fn execute(&self, params: Value, ctx: &ToolContext) -> ToolResult;
 }
 
 /// Registry for managing available tools
 #[derive(Default)]
 pub struct ToolRegistry {
     tools: HashMap<String, Box<dyn Tool>>,
+    descriptions: HashMap<String, String>,
+    /// Project whose daemon runs calls, when one is serving.
+    daemon_root: Option<std::path::PathBuf>,
+    /// Project whose index is checked before graph queries.
+    freshness_root: Option<std::path::PathBuf>,
+    /// Recent result of the check against `freshness_root`.
+    freshness: crate::staleness::FreshnessCache,
 }
 
 impl ToolRegistry {
@@ -101,6 +185,46 @@ impl ToolRegistry {
         self.tools.insert(name, tool);
     }
 
+    /// Remove a tool, returning it if it was registered
+    pub fn unregister(&mut self, name: &str) -> Option<Box<dyn Tool>> {
+        self.descriptions.remove(name);
+        self.tools.remove(name)
+    }
+
+    /// Replace the description a registered tool reports in `tools/list`.
+    /// Returns false when no tool has that name.
+    pub fn set_description(&mut self, name: &str, description: impl Into<String>) -> bool {
+        if !self.tools.contains_key(name) {
+            return false;
+        }
+        self.descriptions
+            .insert(name.to_string(), description.into());
+        true
+    }
+
+    /// Description of a registered tool, including any override
+    pub fn description(&self, name: &str) -> Option<&str> {
+        self.descriptions
+            .get(name)
+            .map(String::as_str)
+            .or_else(|| self.get(name).map(Tool::description))
+    }
+
+    /// Run calls on the daemon serving `project_root` whenever one is up,
+    /// and in-process otherwise. Calls run by the daemon report no progress
+    /// and cannot be cancelled.
+    pub fn delegate_to_daemon(&mut self, project_root: &std::path::Path) {
+        self.daemon_root = Some(project_root.to_path_buf());
+    }
+
+    /// Apply `[sync] on_stale_index` of `project_root` before each tool that
+    /// [queries the graph](Tool::queries_graph): a stale index adds a
+    /// `stale_index` object to the result, is synced first, or fails the
+    /// call with code `stale_index`.
+    pub fn check_freshness(&mut self, project_root: &std::path::Path) {
+        self.freshness_root = Some(project_root.to_path_buf());
+    }
+
     /// Get a tool by name
     pub fn get(&self, name: &str) -> Option<&dyn Tool> {
         self.tools.get(name).map(AsRef::as_ref)
@@ -118,7 +242,10 @@ impl ToolRegistry {
             .map(|tool| {
                 serde_json::json!({
                     "name": tool.name(),
-                    "description": tool.description(),
+                    "description": self
+                        .descriptions
+                        .get(tool.name())
+                        .map_or_else(|| tool.description(), String::as_str),
                     "inputSchema": tool.input_schema(),
                 })
             })
@@ -127,20 +254,87 @@ impl ToolRegistry {
 
     /// Execute a tool by name
     pub fn execute(&self, name: &str, params: Value) -> ToolResult {
-        if let Some(tool) = self.get(name) {
-            return tool.execute(params);
-        }
+        self.execute_with_context(name, params, &ToolContext::default())
+    }
 
-        if let Some(alias) = normalize_tool_name(name)
-            && let Some(tool) = self.get(&alias)
-        {
-            return tool.execute(params);
-        }
+    /// Execute a tool by name with a caller-controlled [`ToolContext`],
+    /// blocking the calling thread until it finishes. Unknown names fail
+    /// with `not_found`.
+    pub fn execute_with_context(&self, name: &str, params: Value, ctx: &ToolContext) -> ToolResult {
+        let Some(tool) = self.resolve(name) else {
+            return Err(ToolError::not_found(format!("Tool not found: {name}")));
+        };
+        let freshness_root = self
+            .freshness_root
+            .as_deref()
+            .filter(|_| tool.queries_graph());
+        let stale = match freshness_root.map(|root| self.freshness.check(root)) {
+            Some(Ok(crate::staleness::Freshness::Refused(status))) => {
+                return Err(ToolError::new(
+                    "stale_index",
+                    format!(
+                        "{} Run coraline_sync first.",
+                        crate::staleness::describe(&status)
+                    ),
+                ));
+            }
+            Some(Ok(crate::staleness::Freshness::Stale(status))) => Some(status),
+            Some(Err(err)) => {
+                tracing::debug!(error = %err, "index freshness check failed");
+                None
+            }
+            _ => None,
+        };
+        let result = match self.daemon_root.as_deref().and_then(crate::daemon::connect) {
+            Some(mut client) => client.call(&crate::daemon::Request::Tool {
+                name: tool.name().to_string(),
+                arguments: params,
+            }),
+            None => tool.execute(params, ctx),
+        };
+        result.map(|mut value| {
+            if let (Some(status), Some(fields)) = (&stale, value.as_object_mut()) {
+                fields.insert(
+                    "stale_index".to_string(),
+                    serde_json::json!({
+                        "message": crate::staleness::describe(status),
+                        "files_added": status.files_added,
+                        "files_modified": status.files_modified,
+                        "files_removed": status.files_removed,
+                    }),
+                );
+            }
+            value
+        })
+    }
 
-        Err(ToolError::not_found(format!("Tool not found: {name}")))
+    /// Look up a tool by its registered name or an `mcp_`-prefixed alias.
+    fn resolve(&self, name: &str) -> Option<&dyn Tool> {
+        self.get(name).or_else(|| {
+            normalize_tool_name(name)
+                .as_deref()
+                .and_then(|alias| self.get(alias))
+        })
     }
 }
 
+/// An optional array-of-strings parameter.
+pub(crate) fn string_list(params: &Value, key: &str) -> Result<Option<Vec<String>>, ToolError> {
+    let Some(value) = params.get(key) else {
+        return Ok(None);
+    };
+    value
+        .as_array()
+        .and_then(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+        })
+        .map(Some)
+        .ok_or_else(|| ToolError::invalid_params(format!("'{key}' must be an array of strings")))
+}
+
 fn normalize_tool_name(name: &str) -> Option<String> {
     const CORALINE_TOOL_PREFIXES: [&str; 2] = ["mcp_coraline_coraline_", "mcp_coraline_"];
 
@@ -168,11 +362,44 @@ pub fn classify_tool_risk(tool_name: &str) -> ToolRisk {
     ToolRisk::ReadOnly
 }
 
-/// Create a default tool registry with all built-in tools
-pub fn create_default_registry(project_root: &std::path::Path) -> ToolRegistry {
-    let mut registry = ToolRegistry::new();
+/// Whether a tool changes the project (its index, memories, or config), so
+/// that calls to such tools must not overlap.
+pub fn writes_project(tool_name: &str) -> bool {
+    classify_tool_risk(tool_name) == ToolRisk::WriteLike
+        || normalize_tool_name(tool_name)
+            .as_deref()
+            .unwrap_or(tool_name)
+            == "coraline_sync"
+}
+
+/// Drop disabled tools and apply description overrides from `[tools]`.
+pub fn apply_tools_config(registry: &mut ToolRegistry, cfg: &crate::config::ToolsConfig) {
+    if cfg.read_only {
+        let write_like: Vec<String> = registry
+            .list_tools()
+            .into_iter()
+            .filter(|name| classify_tool_risk(name) == ToolRisk::WriteLike)
+            .map(str::to_string)
+            .collect();
+        for name in write_like {
+            registry.unregister(&name);
+        }
+    }
+    for name in &cfg.disabled {
+        let canonical = normalize_tool_name(name).unwrap_or_else(|| name.clone());
+        if registry.unregister(&canonical).is_none() && registry.unregister(name).is_none() {
+            tracing::debug!(tool = %name, "[tools] disabled names a tool that is not registered");
+        }
+    }
+    for (name, description) in &cfg.descriptions {
+        if !registry.set_description(name, description.clone()) {
+            tracing::warn!(tool = %name, "[tools] description for a tool that is not registered");
+        }
+    }
+}
 
-    // Register graph tools
+/// Register the tools that query the code graph.
+fn register_graph_tools(registry: &mut ToolRegistry, project_root: &std::path::Path) {
     registry.register(Box::new(graph_tools::SearchTool::new(
         project_root.to_path_buf(),
     )));
@@ -185,6 +412,12 @@ pub fn create_default_registry(project_root: &std::path::Path) -> ToolRegistry {
     registry.register(Box::new(graph_tools::ImpactTool::new(
         project_root.to_path_buf(),
     )));
+    registry.register(Box::new(graph_tools::OwnersTool::new(
+        project_root.to_path_buf(),
+    )));
+    registry.register(Box::new(graph_tools::RenameImpactTool::new(
+        project_root.to_path_buf(),
+    )));
     registry.register(Box::new(graph_tools::DependenciesTool::new(
         project_root.to_path_buf(),
     )));
@@ -203,12 +436,36 @@ pub fn create_default_registry(project_root: &std::path::Path) -> ToolRegistry {
     registry.register(Box::new(graph_tools::GetSymbolsOverviewTool::new(
         project_root.to_path_buf(),
     )));
+    registry.register(Box::new(graph_tools::FileOverviewTool::new(
+        project_root.to_path_buf(),
+    )));
     registry.register(Box::new(graph_tools::FindReferencesTool::new(
         project_root.to_path_buf(),
     )));
+    registry.register(Box::new(graph_tools::ExplainTool::new(
+        project_root.to_path_buf(),
+    )));
     registry.register(Box::new(graph_tools::GetNodeTool::new(
         project_root.to_path_buf(),
     )));
+    registry.register(Box::new(graph_tools::RoutesTool::new(
+        project_root.to_path_buf(),
+    )));
+    registry.register(Box::new(graph_tools::ImplementationsTool::new(
+        project_root.to_path_buf(),
+    )));
+}
+
+/// Create a default tool registry with all built-in tools, minus those
+/// disabled in the `[tools]` config section.
+pub fn create_default_registry(project_root: &std::path::Path) -> ToolRegistry {
+    let mut registry = ToolRegistry::new();
+    let toml_cfg = crate::config::load_toml_config(project_root).unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "Invalid config.toml; registering tools with defaults");
+        crate::config::CoralineConfig::default()
+    });
+
+    register_graph_tools(&mut registry, project_root);
 
     // Register file tools
     registry.register(Box::new(file_tools::ReadFileTool::new(
@@ -240,11 +497,17 @@ pub fn create_default_registry(project_root: &std::path::Path) -> ToolRegistry {
     registry.register(Box::new(context_tools::BuildContextTool::new(
         project_root.to_path_buf(),
     )));
+    registry.register(Box::new(context_tools::DiffContextTool::new(
+        project_root.to_path_buf(),
+    )));
 
     // Register audit tools
     registry.register(Box::new(audit_tools::AuditDocsTool::new(
         project_root.to_path_buf(),
     )));
+    registry.register(Box::new(audit_tools::DeadCodeTool::new(
+        project_root.to_path_buf(),
+    )));
 
     // Register memory tools (ignore errors if memory system fails to initialize)
     if let Ok(tool) = memory_tools::WriteMemoryTool::new(project_root) {
@@ -263,13 +526,14 @@ pub fn create_default_registry(project_root: &std::path::Path) -> ToolRegistry {
         registry.register(Box::new(tool));
     }
 
-    // Register semantic search only when at least one ONNX model variant is present.
-    #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
+    // Register semantic search only when an HTTP embedding provider is
+    // configured or this build has ONNX support and a model variant is present.
     let model_dir = crate::vectors::default_model_dir(project_root);
-    #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
-    if crate::vectors::MODEL_PREFERENCE_ORDER
-        .iter()
-        .any(|name| model_dir.join(name).exists())
+    if toml_cfg.vectors.provider != crate::config::EmbeddingProvider::Onnx
+        || (cfg!(any(feature = "embeddings", feature = "embeddings-dynamic"))
+            && crate::vectors::MODEL_PREFERENCE_ORDER
+                .iter()
+                .any(|name| model_dir.join(name).exists()))
     {
         registry.register(Box::new(file_tools::SemanticSearchTool::new(
             project_root.to_path_buf(),
@@ -282,11 +546,14 @@ pub fn create_default_registry(project_root: &std::path::Path) -> ToolRegistry {
         );
     }
 
+    apply_tools_config(&mut registry, &toml_cfg.tools);
     registry
 }
 
 #[cfg(test)]
 mod tests {
+    #![allow(clippy::expect_used)]
+
     use super::*;
 
     struct MockTool;
@@ -310,7 +577,7 @@ mod tests {
             })
         }
 
-        fn execute(&self, params: Value) -> ToolResult {
+        fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
             Ok(serde_json::json!({ "result": params }))
         }
     }
@@ -333,11 +600,45 @@ mod tests {
             })
         }
 
-        fn execute(&self, params: Value) -> ToolResult {
+        fn execute(&self, params: Value, _ctx: &ToolContext) -> ToolResult {
             Ok(serde_json::json!({ "result": params }))
         }
     }
 
+    #[test]
+    fn test_tool_context_cancellation_is_shared_by_clones() {
+        let ctx = ToolContext::new();
+        let handle = ctx.clone();
+        assert!(ctx.check_cancelled().is_ok());
+
+        handle.cancel();
+        assert!(ctx.is_cancelled());
+        let err = ctx
+            .check_cancelled()
+            .expect_err("cancelled context should fail");
+        assert_eq!(err.code, "cancelled");
+    }
+
+    #[test]
+    fn test_tool_context_reports_progress_to_sink() {
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        let ctx = ToolContext::new().with_progress(move |progress, total, message| {
+            if let Ok(mut reports) = sink.lock() {
+                reports.push((progress, total, message.map(str::to_string)));
+            }
+        });
+
+        ctx.report_progress(1.0, Some(4.0), Some("src/main.rs"));
+        ToolContext::new().report_progress(2.0, None, None);
+
+        let reports = reports.lock().map(|r| r.clone()).unwrap_or_default();
+        assert_eq!(
+            reports,
+            vec![(1.0, Some(4.0), Some("src/main.rs".to_string()))]
+        );
+    }
+
     #[test]
     fn test_registry_register_and_get() {
         let mut registry = ToolRegistry::new();
@@ -402,4 +703,60 @@ mod tests {
     fn test_classify_tool_risk_read_only_for_read_tool() {
         assert_eq!(classify_tool_risk("coraline_read_file"), ToolRisk::ReadOnly);
     }
+
+    #[test]
+    fn test_writes_project_for_sync_and_write_like_tools() {
+        assert!(writes_project("coraline_sync"));
+        assert!(writes_project("mcp_coraline_sync"));
+        assert!(writes_project("coraline_edit_memory"));
+        assert!(!writes_project("coraline_search"));
+    }
+
+    #[test]
+    fn test_apply_tools_config_read_only_drops_write_like_tools() {
+        let temp = tempfile::TempDir::new().expect("Failed to create temp directory");
+        std::fs::create_dir_all(temp.path().join(".coraline")).expect("Failed to create .coraline");
+        let mut registry = create_default_registry(temp.path());
+        assert!(registry.get("coraline_write_memory").is_some());
+
+        let cfg = crate::config::ToolsConfig {
+            read_only: true,
+            ..Default::default()
+        };
+        apply_tools_config(&mut registry, &cfg);
+
+        for name in registry.list_tools() {
+            assert_eq!(classify_tool_risk(name), ToolRisk::ReadOnly, "{name}");
+        }
+        assert!(registry.get("coraline_read_memory").is_some());
+        assert!(
+            registry
+                .execute("coraline_delete_memory", serde_json::json!({}))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_apply_tools_config_disables_and_describes() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockTool));
+        registry.register(Box::new(CoralineMockTool));
+
+        let cfg = crate::config::ToolsConfig {
+            read_only: false,
+            disabled: vec!["mcp_coraline_mock_tool".to_string()],
+            descriptions: std::iter::once(("mock_tool".to_string(), "Overridden".to_string()))
+                .collect(),
+        };
+        apply_tools_config(&mut registry, &cfg);
+
+        assert!(registry.get("coraline_mock_tool").is_none());
+        assert_eq!(registry.description("mock_tool"), Some("Overridden"));
+        let listed = registry.get_tool_metadata();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(
+            listed.first().and_then(|tool| tool.get("description")),
+            Some(&serde_json::json!("Overridden"))
+        );
+    }
 }
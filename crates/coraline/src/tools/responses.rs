@@ -0,0 +1,852 @@
+#![forbid(unsafe_code)]
+
+//! Typed responses of the built-in tools.
+//!
+//! Every tool serializes one of these as its result; the MCP server returns
+//! it both as `structuredContent` and as JSON text. The field names are the
+//! tools' wire format, so renaming one is a breaking change for clients.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::annotations::{OwnerGroup, PackageGroup};
+use crate::dead_code::Confidence;
+use crate::memory::{MemoryMeta, Namespace};
+use crate::types::{Edge, EdgeKind, Language, Node, NodeKind, SearchResult, Visibility};
+
+use super::{ToolError, ToolResult};
+
+/// Serialize a typed response into a tool result.
+pub fn to_result<T: Serialize>(response: &T) -> ToolResult {
+    serde_json::to_value(response).map_err(|e| ToolError::failed("Failed to serialize response", e))
+}
+
+// ── Shared shapes ────────────────────────────────────────────────────────────
+
+/// A node as listed in graph results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeRef {
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    pub qualified_name: String,
+    pub file_path: String,
+    pub start_line: i64,
+}
+
+impl From<&Node> for NodeRef {
+    fn from(node: &Node) -> Self {
+        Self {
+            id: node.id.clone(),
+            kind: node.kind,
+            name: node.name.clone(),
+            qualified_name: node.qualified_name.clone(),
+            file_path: node.file_path.clone(),
+            start_line: node.start_line,
+        }
+    }
+}
+
+/// An edge as listed in graph results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeRef {
+    pub source: String,
+    pub target: String,
+    pub kind: EdgeKind,
+    pub line: Option<i64>,
+    /// How the resolver chose the target (`method`, `confidence`); absent
+    /// for edges the extractor observed directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<serde_json::Value>,
+}
+
+impl From<&Edge> for EdgeRef {
+    fn from(edge: &Edge) -> Self {
+        Self {
+            source: edge.source.clone(),
+            target: edge.target.clone(),
+            kind: edge.kind,
+            line: edge.line,
+            resolution: edge
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get("resolution"))
+                .cloned(),
+        }
+    }
+}
+
+// ── Search ───────────────────────────────────────────────────────────────────
+
+/// One hit of `coraline_search` or `coraline_semantic_search`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub node: SearchHitNode,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHitNode {
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    pub qualified_name: String,
+    pub file_path: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub language: Language,
+    pub signature: Option<String>,
+    /// The file changed since indexing and this symbol's lines no longer
+    /// match; sync before trusting `start_line` and `end_line`.
+    #[serde(default)]
+    pub stale: bool,
+    /// Annotation tags of the symbol (`coraline annotate`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl From<&SearchResult> for SearchHit {
+    fn from(result: &SearchResult) -> Self {
+        let node = &result.node;
+        Self {
+            node: SearchHitNode {
+                id: node.id.clone(),
+                kind: node.kind,
+                name: node.name.clone(),
+                qualified_name: node.qualified_name.clone(),
+                file_path: node.file_path.clone(),
+                start_line: node.start_line,
+                end_line: node.end_line,
+                language: node.language,
+                signature: node.signature.clone(),
+                stale: false,
+                tags: Vec::new(),
+            },
+            score: result.score,
+        }
+    }
+}
+
+impl SearchHit {
+    /// Set the hit's [`SearchHitNode::stale`] marker.
+    #[must_use]
+    pub const fn with_stale(mut self, stale: bool) -> Self {
+        self.node.stale = stale;
+        self
+    }
+}
+
+/// `coraline_search`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub results: Vec<SearchHit>,
+    pub count: usize,
+}
+
+/// `coraline_semantic_search`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticSearchResponse {
+    pub query: String,
+    pub freshness: Freshness,
+    pub count: usize,
+    pub results: Vec<SearchHit>,
+}
+
+/// What `coraline_semantic_search` refreshed before searching.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Freshness {
+    /// Whether the index was checked on this call (at most once per
+    /// `check_interval_seconds`).
+    pub checked: bool,
+    pub stale_files_added: usize,
+    pub stale_files_modified: usize,
+    pub stale_files_removed: usize,
+    pub synced: bool,
+    pub files_added: usize,
+    pub files_modified: usize,
+    pub files_removed: usize,
+    pub embeddings_refreshed: bool,
+    pub embeddings_refreshed_count: usize,
+    pub check_interval_seconds: u64,
+}
+
+// ── Graph ────────────────────────────────────────────────────────────────────
+
+/// A caller or callee, with the line of the call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallSite {
+    #[serde(flatten)]
+    pub node: NodeRef,
+    pub line: Option<i64>,
+    /// The file changed since indexing and this symbol's lines no longer
+    /// match; sync before trusting `start_line` and `line`.
+    #[serde(default)]
+    pub stale: bool,
+    /// The next level of callers (or callees) of this node, with `depth` > 1.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Self>,
+}
+
+/// `coraline_callers`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallersResponse {
+    pub callers: Vec<CallSite>,
+    /// Direct callers listed at the top level.
+    pub count: usize,
+    /// Symbols in the whole tree, `count` plus all nested `children`.
+    pub total: usize,
+    /// `max_nodes` was reached before the requested `depth`.
+    pub truncated: bool,
+    /// Symbols sharing the requested `name` when it did not identify one;
+    /// `callers` is empty. Retry with a candidate's `id` as `node_id`, or
+    /// narrow with `file_path`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<NodeRef>,
+}
+
+/// `coraline_callees`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalleesResponse {
+    pub callees: Vec<CallSite>,
+    /// Direct callees listed at the top level.
+    pub count: usize,
+    /// Symbols in the whole tree, `count` plus all nested `children`.
+    pub total: usize,
+    /// `max_nodes` was reached before the requested `depth`.
+    pub truncated: bool,
+    /// Symbols sharing the requested `name` when it did not identify one;
+    /// `callees` is empty. Retry with a candidate's `id` as `node_id`, or
+    /// narrow with `file_path`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<NodeRef>,
+}
+
+/// `coraline_impact`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpactResponse {
+    pub nodes: Vec<NodeRef>,
+    pub edges: Vec<EdgeRef>,
+    pub stats: ImpactStats,
+    /// Affected functions and methods whose ingested coverage is zero.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub untested: Vec<NodeRef>,
+    /// Owners of the affected symbols (CODEOWNERS and `owner:` tags).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<OwnerGroup>,
+    /// Workspace packages of the affected symbols.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<PackageGroup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpactStats {
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub file_count: usize,
+    pub max_depth: usize,
+}
+
+/// An HTTP route in `coraline_routes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteEntry {
+    /// Upper-case, or `ANY` for a route serving every method.
+    pub method: String,
+    /// The path template as registered, e.g. `/users/{id}`.
+    pub path: String,
+    pub file_path: String,
+    pub line: i64,
+    /// The function serving the route; absent for an inline closure or a
+    /// handler that did not resolve.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handler: Option<NodeRef>,
+}
+
+/// `coraline_routes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutesResponse {
+    /// Sorted by path, then method; at most `limit`.
+    pub routes: Vec<RouteEntry>,
+    /// Matching routes before `limit` was applied.
+    pub total: usize,
+}
+
+/// A type implementing the trait or interface of
+/// `coraline_implementations`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImplementationEntry {
+    pub node: NodeRef,
+    /// `implements` or `extends`.
+    pub edge_kind: EdgeKind,
+    /// The subclass or subinterface it implements the target through.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub via: Option<String>,
+    /// Required methods it leaves undefined.
+    pub missing: Vec<String>,
+}
+
+/// `coraline_implementations`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImplementationsResponse {
+    pub target: NodeRef,
+    /// Methods of the target without a default body.
+    pub required: Vec<String>,
+    /// Direct implementers first, then by file and line.
+    pub implementations: Vec<ImplementationEntry>,
+}
+
+/// `coraline_owners`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnersResponse {
+    /// The file, or the symbol's qualified name.
+    pub target: String,
+    pub owners: Vec<String>,
+    /// All annotation tags of the target.
+    pub tags: Vec<String>,
+    /// Owners of the code that depends on the target, most affected first.
+    /// Present unless `impact` was false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impacted: Option<Vec<OwnerGroup>>,
+    /// Affected files no rule or tag assigns an owner.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unowned_files: Vec<String>,
+    /// The walk stopped at `max_nodes` before `max_depth`.
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// A position in a source file. Columns are 0-based, as in `coraline_node`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceLocation {
+    pub file_path: String,
+    pub line: i64,
+    pub column: Option<i64>,
+}
+
+/// A use of the symbol in `coraline_rename_impact`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameUsage {
+    #[serde(flatten)]
+    pub location: SourceLocation,
+    pub edge_kind: EdgeKind,
+    /// The symbol whose body holds the use.
+    pub used_by: NodeRef,
+}
+
+/// Every line of one file that names the symbol, in `coraline_rename_impact`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameFile {
+    pub file_path: String,
+    pub lines: Vec<i64>,
+}
+
+/// `coraline_rename_impact`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameImpactResponse {
+    pub symbol: NodeRef,
+    pub definition: SourceLocation,
+    /// Calls, references, and type uses of the symbol, by file and line.
+    pub usages: Vec<RenameUsage>,
+    /// Import statements binding the symbol's name from its file.
+    pub imports: Vec<SourceLocation>,
+    /// Export statements the rename changes: the definition file's own
+    /// and re-exports from importing files.
+    pub exports: Vec<NodeRef>,
+    /// Test functions and test files that reach the symbol through
+    /// `calls` and `references` edges, up to `max_depth`.
+    pub tests: Vec<NodeRef>,
+    /// Files to edit, with every line naming the symbol.
+    pub files: Vec<RenameFile>,
+    /// `limit` cut the usage list short.
+    pub truncated: bool,
+}
+
+/// One match of `coraline_find_symbol`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolMatch {
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    pub qualified_name: String,
+    pub file_path: String,
+    pub language: Language,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub signature: Option<String>,
+    pub docstring: Option<String>,
+    pub is_exported: bool,
+    pub is_async: bool,
+    pub is_static: bool,
+    pub score: f32,
+    /// Source of the symbol, when `include_body` was set.
+    pub body: Option<String>,
+}
+
+/// `coraline_find_symbol`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindSymbolResponse {
+    pub symbols: Vec<SymbolMatch>,
+    pub count: usize,
+}
+
+/// A symbol under its kind in `coraline_get_symbols_overview`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverviewEntry {
+    pub id: String,
+    pub name: String,
+    pub qualified_name: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub signature: Option<String>,
+    pub is_exported: bool,
+}
+
+/// A symbol in line order in `coraline_get_symbols_overview`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverviewSymbol {
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub signature: Option<String>,
+}
+
+/// `coraline_get_symbols_overview`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolsOverviewResponse {
+    pub file_path: String,
+    pub symbol_count: usize,
+    /// Symbols keyed by lowercase kind name.
+    pub by_kind: BTreeMap<String, Vec<OverviewEntry>>,
+    pub symbols: Vec<OverviewSymbol>,
+}
+
+/// A symbol in the outline of `coraline_file_overview`, with the symbols
+/// it contains.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutlineNode {
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub signature: Option<String>,
+    pub is_exported: bool,
+    pub children: Vec<Self>,
+}
+
+/// An import statement of the file in `coraline_file_overview`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileImport {
+    /// Local name the import binds.
+    pub name: String,
+    /// The module or path imported from.
+    pub module: String,
+    /// The exported name, when it differs from the local name.
+    pub export_name: Option<String>,
+    pub line: i64,
+}
+
+/// Another file linked to the file by non-`contains` edges, in
+/// `coraline_file_overview`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileLink {
+    pub file_path: String,
+    pub edge_count: usize,
+    pub edge_kinds: Vec<EdgeKind>,
+    /// Names of the symbols used across the link: the other file's for
+    /// `depends_on`, this file's for `dependents`.
+    pub symbols: Vec<String>,
+}
+
+/// `coraline_file_overview`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileOverviewResponse {
+    pub file_path: String,
+    pub language: Language,
+    /// The file's leading doc comment, when it has one.
+    pub docstring: Option<String>,
+    pub symbol_count: usize,
+    /// Top-level symbols with their nested members (`contains` edges).
+    pub outline: Vec<OutlineNode>,
+    pub imports: Vec<FileImport>,
+    /// Export statements and exported symbols.
+    pub exports: Vec<NodeRef>,
+    /// Files this file's symbols refer to, most edges first.
+    pub depends_on: Vec<FileLink>,
+    /// Files referring to this file's symbols, most edges first.
+    pub dependents: Vec<FileLink>,
+    /// Dependent files before `limit` was applied.
+    pub dependent_count: usize,
+}
+
+/// A node referencing the target of `coraline_find_references`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reference {
+    #[serde(flatten)]
+    pub node: NodeRef,
+    pub edge_kind: EdgeKind,
+    pub edge_line: Option<i64>,
+}
+
+/// `coraline_find_references`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferencesResponse {
+    pub node_id: String,
+    pub references: Vec<Reference>,
+    pub count: usize,
+}
+
+/// `coraline_node`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeDetails {
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    pub qualified_name: String,
+    pub file_path: String,
+    pub language: Language,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub start_column: i64,
+    pub end_column: i64,
+    pub signature: Option<String>,
+    pub docstring: Option<String>,
+    pub visibility: Option<Visibility>,
+    pub is_exported: bool,
+    pub is_async: bool,
+    pub is_static: bool,
+    pub is_abstract: bool,
+    pub decorators: Option<Vec<String>>,
+    pub type_parameters: Option<Vec<String>>,
+    pub body: Option<String>,
+    /// Present when `include_edges` was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outgoing_edge_count: Option<usize>,
+    /// Present when `include_edges` was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub incoming_edge_count: Option<usize>,
+}
+
+impl NodeDetails {
+    pub fn new(node: &Node, body: Option<String>) -> Self {
+        Self {
+            id: node.id.clone(),
+            kind: node.kind,
+            name: node.name.clone(),
+            qualified_name: node.qualified_name.clone(),
+            file_path: node.file_path.clone(),
+            language: node.language,
+            start_line: node.start_line,
+            end_line: node.end_line,
+            start_column: node.start_column,
+            end_column: node.end_column,
+            signature: node.signature.clone(),
+            docstring: node.docstring.clone(),
+            visibility: node.visibility,
+            is_exported: node.is_exported,
+            is_async: node.is_async,
+            is_static: node.is_static,
+            is_abstract: node.is_abstract,
+            decorators: node.decorators.clone(),
+            type_parameters: node.type_parameters.clone(),
+            body,
+            outgoing_edge_count: None,
+            incoming_edge_count: None,
+        }
+    }
+}
+
+/// `coraline_explain`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainResponse {
+    /// Definition, signature, docstring, and source of the symbol.
+    #[serde(flatten)]
+    pub symbol: NodeDetails,
+    pub callers: Vec<CallSite>,
+    pub callees: Vec<CallSite>,
+    /// Types that implement or extend this one.
+    pub implementors: Vec<NodeRef>,
+    /// Types this one implements or extends.
+    pub supertypes: Vec<NodeRef>,
+    /// Project memories that mention the symbol.
+    pub memories: Vec<MemoryMention>,
+    /// Latest commits touching the symbol's lines; empty outside git.
+    pub recent_commits: Vec<CommitSummary>,
+}
+
+/// A memory naming a symbol, with the first line that does.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryMention {
+    pub name: String,
+    pub namespace: Namespace,
+    pub excerpt: String,
+}
+
+/// One commit in a symbol's history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitSummary {
+    pub commit: String,
+    pub author: String,
+    /// Unix seconds.
+    pub timestamp: i64,
+    pub summary: String,
+}
+
+/// `coraline_dependencies`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependenciesResponse {
+    pub node_id: String,
+    pub dependencies: Vec<NodeRef>,
+    pub edges: Vec<EdgeRef>,
+    pub count: usize,
+}
+
+/// `coraline_dependents`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependentsResponse {
+    pub node_id: String,
+    pub dependents: Vec<NodeRef>,
+    pub edges: Vec<EdgeRef>,
+    pub count: usize,
+}
+
+/// `coraline_path`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathResponse {
+    pub from_id: String,
+    pub to_id: String,
+    pub path_found: bool,
+    pub path: Vec<NodeRef>,
+    /// Nodes on the path, when one was found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<usize>,
+    /// Why no path was found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// `coraline_stats`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsResponse {
+    pub totals: StatsTotals,
+    pub files_by_language: BTreeMap<String, i64>,
+    pub nodes_by_kind: BTreeMap<String, i64>,
+    pub edges_by_kind: BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsTotals {
+    pub nodes: i64,
+    pub edges: i64,
+    pub files: i64,
+    pub unresolved_references: i64,
+    pub vectors: i64,
+}
+
+// ── Files and project ────────────────────────────────────────────────────────
+
+/// `coraline_read_file`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadFileResponse {
+    pub path: PathBuf,
+    pub content: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub total_lines: usize,
+    /// More lines follow `end_line`.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirEntry {
+    /// File name; directories end in `/`.
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// `coraline_list_dir`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListDirResponse {
+    pub path: PathBuf,
+    pub entries: Vec<DirEntry>,
+    pub count: usize,
+}
+
+/// A node of `coraline_get_file_nodes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileNode {
+    pub id: String,
+    pub kind: NodeKind,
+    pub name: String,
+    pub qualified_name: String,
+    pub start_line: i64,
+    pub end_line: i64,
+    pub signature: Option<String>,
+    pub is_exported: bool,
+}
+
+/// `coraline_get_file_nodes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileNodesResponse {
+    pub file_path: String,
+    pub nodes: Vec<FileNode>,
+    pub count: usize,
+}
+
+/// `coraline_find_file`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindFileResponse {
+    pub pattern: String,
+    /// Paths relative to the project root.
+    pub matches: Vec<String>,
+    pub count: usize,
+    /// The match limit was reached.
+    pub truncated: bool,
+}
+
+/// `coraline_status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusResponse {
+    pub project_root: PathBuf,
+    pub database: PathBuf,
+    pub database_size_bytes: u64,
+    pub stats: StatusStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusStats {
+    pub nodes: i64,
+    pub edges: i64,
+    pub files: i64,
+    pub unresolved_references: i64,
+}
+
+/// `coraline_get_config`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigResponse {
+    pub config_path: PathBuf,
+    pub config_exists: bool,
+    /// The whole configuration, or the requested section (`null` when
+    /// there is no such section).
+    pub config: serde_json::Value,
+}
+
+/// `coraline_update_config`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateConfigResponse {
+    pub updated: bool,
+    pub section: String,
+    pub key: String,
+    pub new_value: serde_json::Value,
+}
+
+/// `coraline_sync`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub files_checked: usize,
+    pub files_added: usize,
+    pub files_modified: usize,
+    pub files_removed: usize,
+    pub nodes_updated: usize,
+    pub nodes_embedded: usize,
+    pub files_failed: usize,
+    pub duration_ms: u128,
+}
+
+// ── Context, docs, and memories ──────────────────────────────────────────────
+
+/// `coraline_context` with `format: "markdown"`. JSON contexts are returned
+/// as the serialized [`TaskContext`](crate::types::TaskContext) itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkdownContextResponse {
+    pub context: String,
+    pub format: String,
+}
+
+/// `coraline_audit_docs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocAuditResponse {
+    pub summary: DocAuditSummary,
+    pub stale_refs: Vec<StaleRef>,
+    pub undocumented_exports: Vec<UndocumentedSymbol>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocAuditSummary {
+    pub doc_files_indexed: usize,
+    pub doc_sections_indexed: usize,
+    pub stale_refs_count: usize,
+    pub undocumented_exports_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaleRef {
+    pub reference: String,
+    pub doc_file: String,
+    pub section: String,
+    pub line: i64,
+    pub column: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndocumentedSymbol {
+    pub name: String,
+    pub qualified_name: String,
+    pub kind: String,
+    pub file: String,
+    pub line: i64,
+}
+
+/// A symbol nothing appears to use, in `coraline_dead_code`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadCodeEntry {
+    #[serde(flatten)]
+    pub node: NodeRef,
+    pub confidence: Confidence,
+    pub reason: String,
+}
+
+/// `coraline_dead_code`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadCodeResponse {
+    /// Most confident first, at most `limit`.
+    pub candidates: Vec<DeadCodeEntry>,
+    /// Candidates before `limit` was applied.
+    pub total: usize,
+    /// Unreferenced symbols left out as entry points.
+    pub entry_points_skipped: usize,
+}
+
+/// `coraline_write_memory`, `coraline_delete_memory`, and
+/// `coraline_edit_memory`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageResponse {
+    pub message: String,
+}
+
+/// `coraline_read_memory`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryContentResponse {
+    pub content: String,
+    /// Front matter of the memory; absent when it does not exist.
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<MemoryMeta>,
+}
+
+/// `coraline_list_memories`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryListResponse {
+    pub memories: Vec<MemoryListing>,
+}
+
+/// A memory's name and front matter, without its body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryListing {
+    pub name: String,
+    pub namespace: Namespace,
+    #[serde(flatten)]
+    pub meta: MemoryMeta,
+}
@@ -0,0 +1,595 @@
+#![forbid(unsafe_code)]
+
+//! Onboarding tour for `coraline tour`.
+//!
+//! Builds an ordered walkthrough of the codebase purely from the indexed
+//! graph: the entry points, the core modules (files most other files depend
+//! on), the key types (most referenced classes, structs, traits, ...), the
+//! route inventory, and a suggested reading order. Rendered as Markdown with
+//! `file:line` links relative to the project root, or as JSON.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+/// File names conventionally holding a program's or package's entry point.
+const ENTRY_FILE_NAMES: &[&str] = &[
+    "main.rs",
+    "lib.rs",
+    "main.go",
+    "main.py",
+    "__main__.py",
+    "app.py",
+    "manage.py",
+    "index.ts",
+    "index.tsx",
+    "index.js",
+    "main.ts",
+    "main.tsx",
+    "main.js",
+    "app.ts",
+    "app.js",
+    "server.ts",
+    "server.js",
+    "Program.cs",
+    "Main.java",
+    "main.c",
+    "main.cpp",
+    "main.swift",
+    "main.kt",
+    "index.php",
+];
+
+/// Node kinds listed as key types.
+const TYPE_KINDS: &[&str] = &[
+    "class",
+    "struct",
+    "interface",
+    "trait",
+    "protocol",
+    "enum",
+    "type_alias",
+];
+
+/// Uncalled functions need at least this many callees to count as entry
+/// points.
+const MIN_ROOT_CALLEES: i64 = 2;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TourOptions {
+    /// Most entries per section.
+    pub limit: usize,
+}
+
+impl Default for TourOptions {
+    fn default() -> Self {
+        Self { limit: 10 }
+    }
+}
+
+/// A symbol or file the tour points at.
+#[derive(Debug, Clone, Serialize)]
+pub struct TourStop {
+    pub name: String,
+    pub kind: String,
+    pub file: String,
+    pub line: i64,
+    /// Why it is listed, e.g. `calls 7 functions, called by none`.
+    pub detail: String,
+    /// First line of its docstring.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+/// A file ranked by how many other files depend on it.
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreModule {
+    pub file: String,
+    /// Distinct files with an edge into this one.
+    pub dependents: u64,
+    /// Distinct files this one has edges into.
+    pub dependencies: u64,
+    pub symbols: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadingStep {
+    pub file: String,
+    pub line: i64,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Tour {
+    pub files: u64,
+    pub symbols: u64,
+    /// Indexed files per language.
+    pub languages: BTreeMap<String, u64>,
+    pub entry_points: Vec<TourStop>,
+    pub core_modules: Vec<CoreModule>,
+    pub key_types: Vec<TourStop>,
+    pub routes: Vec<TourStop>,
+    pub reading_order: Vec<ReadingStep>,
+}
+
+/// Build the tour from the index.
+pub fn build_tour(conn: &Connection, options: &TourOptions) -> std::io::Result<Tour> {
+    let limit = options.limit.max(1);
+    let mut tour = Tour::default();
+
+    for (language, count) in query_pairs(
+        conn,
+        "SELECT language, COUNT(*) FROM files GROUP BY language ORDER BY language",
+    )? {
+        tour.files += count;
+        tour.languages.insert(language, count);
+    }
+    tour.symbols = conn
+        .query_row(
+            "SELECT COUNT(*) FROM nodes WHERE kind <> 'file'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(std::io::Error::other)
+        .map(|n| u64::try_from(n).unwrap_or(0))?;
+
+    tour.entry_points = entry_points(conn, limit)?;
+    tour.core_modules = core_modules(conn, limit)?;
+    tour.key_types = key_types(conn, limit)?;
+    tour.routes = routes(conn)?;
+    tour.reading_order = reading_order(&tour, limit);
+    Ok(tour)
+}
+
+/// `main` functions, conventional entry files, and uncalled functions that
+/// call the most others.
+fn entry_points(conn: &Connection, limit: usize) -> std::io::Result<Vec<TourStop>> {
+    let mut stops = Vec::new();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, kind, file_path, start_line, docstring FROM nodes
+             WHERE kind = 'function' AND name = 'main'
+             ORDER BY file_path",
+        )
+        .map_err(std::io::Error::other)?;
+    let mains = stmt
+        .query_map([], |row| {
+            Ok(stop_from_row(row, "program entry point".to_string()))
+        })
+        .map_err(std::io::Error::other)?;
+    for main in mains {
+        stops.push(main.map_err(std::io::Error::other)?);
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT path FROM files")
+        .map_err(std::io::Error::other)?;
+    let mut entry_files: Vec<String> = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(std::io::Error::other)?
+        .filter_map(Result::ok)
+        .filter(|path| {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            ENTRY_FILE_NAMES.contains(&name)
+        })
+        .collect();
+    // Shallow files first: `src/main.rs` before `tests/fixtures/x/main.rs`.
+    entry_files.sort_by_key(|path| (path.matches('/').count(), path.clone()));
+    for path in entry_files {
+        if stops.iter().any(|s: &TourStop| s.file == path) {
+            continue;
+        }
+        let summary = conn
+            .query_row(
+                "SELECT docstring FROM nodes WHERE kind = 'file' AND file_path = ?1",
+                [&path],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .ok()
+            .flatten();
+        stops.push(TourStop {
+            name: path.rsplit('/').next().unwrap_or(&path).to_string(),
+            kind: "file".to_string(),
+            file: path,
+            line: 1,
+            detail: "conventional entry file".to_string(),
+            summary: first_line(summary.as_deref()),
+        });
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.name, n.kind, n.file_path, n.start_line, n.docstring,
+                    COUNT(DISTINCT e.target) AS callees
+             FROM nodes n JOIN edges e ON e.source = n.id AND e.kind = 'calls'
+             WHERE n.kind IN ('function', 'method')
+               AND NOT EXISTS (
+                   SELECT 1 FROM edges c WHERE c.target = n.id AND c.kind = 'calls'
+               )
+             GROUP BY n.id
+             HAVING callees >= ?1
+             ORDER BY callees DESC, n.file_path, n.start_line
+             LIMIT ?2",
+        )
+        .map_err(std::io::Error::other)?;
+    let roots = stmt
+        .query_map(
+            rusqlite::params![MIN_ROOT_CALLEES, i64::try_from(limit).unwrap_or(i64::MAX)],
+            |row| {
+                let callees = count_column(row, 5)?;
+                Ok(stop_from_row(
+                    row,
+                    format!("calls {}, called by none", plural(callees, "function")),
+                ))
+            },
+        )
+        .map_err(std::io::Error::other)?;
+    for root in roots {
+        let root = root.map_err(std::io::Error::other)?;
+        if !stops
+            .iter()
+            .any(|s| s.file == root.file && s.line == root.line && s.name == root.name)
+        {
+            stops.push(root);
+        }
+    }
+
+    stops.truncate(limit);
+    Ok(stops)
+}
+
+/// Files ranked by how many distinct other files have edges into them.
+fn core_modules(conn: &Connection, limit: usize) -> std::io::Result<Vec<CoreModule>> {
+    let mut dependents: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut dependencies: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT s.file_path, t.file_path
+             FROM edges e
+             JOIN nodes s ON s.id = e.source
+             JOIN nodes t ON t.id = e.target
+             WHERE e.kind <> 'contains' AND s.file_path <> t.file_path",
+        )
+        .map_err(std::io::Error::other)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(std::io::Error::other)?;
+    for row in rows {
+        let (source, target) = row.map_err(std::io::Error::other)?;
+        dependencies
+            .entry(source.clone())
+            .or_default()
+            .insert(target.clone());
+        dependents.entry(target).or_default().insert(source);
+    }
+
+    let symbols: HashMap<String, u64> = query_pairs(
+        conn,
+        "SELECT file_path, COUNT(*) FROM nodes WHERE kind <> 'file' GROUP BY file_path",
+    )?
+    .into_iter()
+    .collect();
+
+    let mut modules: Vec<CoreModule> = dependents
+        .into_iter()
+        .map(|(file, from)| CoreModule {
+            dependents: from.len() as u64,
+            dependencies: dependencies.get(&file).map_or(0, |d| d.len() as u64),
+            symbols: symbols.get(&file).copied().unwrap_or(0),
+            summary: None,
+            file,
+        })
+        .collect();
+    modules.sort_by(|a, b| {
+        b.dependents
+            .cmp(&a.dependents)
+            .then(b.symbols.cmp(&a.symbols))
+            .then(a.file.cmp(&b.file))
+    });
+    modules.truncate(limit);
+    for module in &mut modules {
+        module.summary = first_line(
+            conn.query_row(
+                "SELECT docstring FROM nodes WHERE kind = 'file' AND file_path = ?1",
+                [&module.file],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .ok()
+            .flatten()
+            .as_deref(),
+        );
+    }
+    Ok(modules)
+}
+
+/// Type declarations ranked by the files importing them (by name) plus the
+/// outside nodes referring to them or their members, then by member count.
+fn key_types(conn: &Connection, limit: usize) -> std::io::Result<Vec<TourStop>> {
+    let kinds = TYPE_KINDS
+        .iter()
+        .map(|k| format!("'{k}'"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        "SELECT n.name, n.kind, n.file_path, n.start_line, n.docstring,
+                (SELECT COUNT(DISTINCT e.source)
+                 FROM nodes t
+                 JOIN edges e ON e.target = t.id AND e.kind NOT IN ('contains', 'exports')
+                 JOIN nodes s ON s.id = e.source
+                 WHERE t.file_path = n.file_path
+                   AND t.start_line >= n.start_line AND t.end_line <= n.end_line
+                   AND NOT (s.file_path = n.file_path
+                            AND s.start_line >= n.start_line
+                            AND s.end_line <= n.end_line)) AS users,
+                (SELECT COUNT(*) FROM nodes m
+                 WHERE m.file_path = n.file_path AND m.id <> n.id
+                   AND m.start_line >= n.start_line AND m.end_line <= n.end_line
+                   AND m.kind IN ('method', 'function', 'property', 'field', 'enum_member'))
+                    AS members,
+                (SELECT COUNT(DISTINCT i.file_path) FROM nodes i
+                 WHERE i.kind = 'import' AND i.name = n.name
+                   AND i.file_path <> n.file_path) AS importers
+         FROM nodes n
+         WHERE n.kind IN ({kinds})
+         ORDER BY importers + users DESC, members DESC, n.file_path, n.start_line
+         LIMIT ?1"
+    );
+    let mut stmt = conn.prepare(&sql).map_err(std::io::Error::other)?;
+    let rows = stmt
+        .query_map([i64::try_from(limit).unwrap_or(i64::MAX)], |row| {
+            let users = count_column(row, 5)?;
+            let members = count_column(row, 6)?;
+            let importers = count_column(row, 7)?;
+            let detail = [
+                (importers, "imported by", "file"),
+                (users, "used by", "symbol"),
+                (members, "has", "member"),
+            ]
+            .iter()
+            .filter(|(count, _, _)| *count > 0)
+            .map(|(count, verb, noun)| format!("{verb} {}", plural(*count, noun)))
+            .collect::<Vec<_>>()
+            .join(", ");
+            Ok(stop_from_row(
+                row,
+                if detail.is_empty() {
+                    "not referenced elsewhere".to_string()
+                } else {
+                    detail
+                },
+            ))
+        })
+        .map_err(std::io::Error::other)?;
+    rows.collect::<Result<_, _>>()
+        .map_err(std::io::Error::other)
+}
+
+/// Every `route` node, by file and line.
+fn routes(conn: &Connection) -> std::io::Result<Vec<TourStop>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, kind, file_path, start_line, docstring FROM nodes
+             WHERE kind = 'route' ORDER BY file_path, start_line",
+        )
+        .map_err(std::io::Error::other)?;
+    let rows = stmt
+        .query_map([], |row| Ok(stop_from_row(row, String::new())))
+        .map_err(std::io::Error::other)?;
+    rows.collect::<Result<_, _>>()
+        .map_err(std::io::Error::other)
+}
+
+/// `main` functions and entry files first (up to a third of the steps),
+/// then the core modules (up to two thirds), then the files defining key
+/// types and the remaining entry points; each file once.
+fn reading_order(tour: &Tour, limit: usize) -> Vec<ReadingStep> {
+    let mut steps: Vec<ReadingStep> = Vec::new();
+    let mut push = |file: &str, line: i64, reason: String, cap: usize| {
+        if steps.len() < cap.min(limit) && !steps.iter().any(|s| s.file == file) {
+            steps.push(ReadingStep {
+                file: file.to_string(),
+                line,
+                reason,
+            });
+        }
+    };
+    let (primary, roots): (Vec<&TourStop>, Vec<&TourStop>) = tour
+        .entry_points
+        .iter()
+        .partition(|stop| stop.kind == "file" || stop.name == "main");
+    let entry = |stop: &TourStop| format!("entry point `{}`", stop.name);
+    let core =
+        |module: &CoreModule| format!("core module, used by {}", plural(module.dependents, "file"));
+
+    for stop in &primary {
+        push(&stop.file, stop.line, entry(stop), limit.div_ceil(3));
+    }
+    for module in &tour.core_modules {
+        push(&module.file, 1, core(module), (limit * 2).div_ceil(3));
+    }
+    for stop in &tour.key_types {
+        let reason = format!("defines {} `{}`", stop.kind, stop.name);
+        push(&stop.file, stop.line, reason, limit);
+    }
+    for stop in primary.iter().chain(&roots) {
+        push(&stop.file, stop.line, entry(stop), limit);
+    }
+    for module in &tour.core_modules {
+        push(&module.file, 1, core(module), limit);
+    }
+    steps
+}
+
+/// Render the tour as Markdown.
+pub fn render_markdown(tour: &Tour, project_name: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Tour of {project_name}\n");
+    let languages = tour
+        .languages
+        .iter()
+        .map(|(language, count)| format!("{language} ({count})"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _ = writeln!(
+        out,
+        "{} files and {} symbols indexed. Languages: {}.\n",
+        tour.files,
+        tour.symbols,
+        if languages.is_empty() {
+            "none"
+        } else {
+            &languages
+        }
+    );
+
+    let _ = writeln!(out, "## Entry Points\n");
+    if tour.entry_points.is_empty() {
+        let _ = writeln!(out, "No entry points found.\n");
+    }
+    for stop in &tour.entry_points {
+        write_stop(&mut out, stop);
+    }
+    if !tour.entry_points.is_empty() {
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "## Core Modules\n");
+    if tour.core_modules.is_empty() {
+        let _ = writeln!(out, "No cross-file dependencies found.\n");
+    } else {
+        let _ = writeln!(
+            out,
+            "| File | Used by (files) | Uses (files) | Symbols | Summary |"
+        );
+        let _ = writeln!(out, "|---|---|---|---|---|");
+        for module in &tour.core_modules {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} |",
+                link(&module.file, 1),
+                module.dependents,
+                module.dependencies,
+                module.symbols,
+                module.summary.as_deref().unwrap_or("").replace('|', "\\|"),
+            );
+        }
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "## Key Types\n");
+    if tour.key_types.is_empty() {
+        let _ = writeln!(out, "No referenced types found.\n");
+    }
+    for stop in &tour.key_types {
+        write_stop(&mut out, stop);
+    }
+    if !tour.key_types.is_empty() {
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "## Routes\n");
+    if tour.routes.is_empty() {
+        let _ = writeln!(out, "No routes indexed.\n");
+    }
+    for route in &tour.routes {
+        let _ = writeln!(
+            out,
+            "- `{}` — {}",
+            route.name,
+            link(&route.file, route.line)
+        );
+    }
+    if !tour.routes.is_empty() {
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "## Suggested Reading Order\n");
+    if tour.reading_order.is_empty() {
+        let _ = writeln!(out, "Nothing to suggest yet; index the project first.");
+    }
+    for (number, step) in tour.reading_order.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "{}. {} — {}",
+            number + 1,
+            link(&step.file, step.line),
+            step.reason
+        );
+    }
+    out
+}
+
+fn write_stop(out: &mut String, stop: &TourStop) {
+    let _ = write!(
+        out,
+        "- **{}** ({}) — {} — {}",
+        stop.name,
+        stop.kind,
+        link(&stop.file, stop.line),
+        stop.detail
+    );
+    if let Some(summary) = &stop.summary {
+        let _ = write!(out, ": {summary}");
+    }
+    out.push('\n');
+}
+
+/// `[file:line](file#Lline)`, relative to the project root.
+fn link(file: &str, line: i64) -> String {
+    format!("[{file}:{line}]({file}#L{line})")
+}
+
+/// Row of `name, kind, file_path, start_line, docstring`.
+fn stop_from_row(row: &rusqlite::Row<'_>, detail: String) -> TourStop {
+    TourStop {
+        name: row.get(0).unwrap_or_default(),
+        kind: row.get(1).unwrap_or_default(),
+        file: row.get(2).unwrap_or_default(),
+        line: row.get(3).unwrap_or_default(),
+        detail,
+        summary: first_line(row.get::<_, Option<String>>(4).ok().flatten().as_deref()),
+    }
+}
+
+fn count_column(row: &rusqlite::Row<'_>, index: usize) -> rusqlite::Result<u64> {
+    Ok(u64::try_from(row.get::<_, i64>(index)?).unwrap_or(0))
+}
+
+fn plural(count: u64, word: &str) -> String {
+    if count == 1 {
+        format!("1 {word}")
+    } else {
+        format!("{count} {word}s")
+    }
+}
+
+fn first_line(docstring: Option<&str>) -> Option<String> {
+    docstring
+        .and_then(|doc| {
+            doc.lines()
+                .map(|l| l.trim().trim_start_matches(['/', '*', '#', '!']).trim())
+                .find(|l| !l.is_empty())
+        })
+        .map(str::to_string)
+}
+
+fn query_pairs(conn: &Connection, sql: &str) -> std::io::Result<Vec<(String, u64)>> {
+    let mut stmt = conn.prepare(sql).map_err(std::io::Error::other)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                u64::try_from(row.get::<_, i64>(1)?).unwrap_or(0),
+            ))
+        })
+        .map_err(std::io::Error::other)?;
+    rows.collect::<Result<_, _>>()
+        .map_err(std::io::Error::other)
+}
@@ -0,0 +1,425 @@
+#![forbid(unsafe_code)]
+
+//! Terminal explorer for `coraline tui`.
+//!
+//! A search box over the full-text index, the matching symbols, and a detail
+//! pane for the selected one with its callers and callees. Enter on a caller
+//! or callee moves the detail pane to that symbol and Esc walks back, so a
+//! call chain can be followed without copying node IDs between commands.
+//!
+//! [`Explorer`] holds the state and draws into any ratatui backend; [`run`]
+//! drives it from the real terminal.
+
+use std::path::Path;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use rusqlite::Connection;
+
+use crate::config::{self, SearchConfig};
+use crate::db;
+use crate::types::{EdgeKind, Node, SearchOptions};
+
+const SEARCH_LIMIT: usize = 50;
+const EDGE_LIMIT: usize = 100;
+const HELP: &str =
+    "type to search · ↑↓ select · Tab switch pane · Enter open · Esc back · Ctrl+C quit";
+
+/// The list that arrow keys and Enter act on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Results,
+    Callers,
+    Callees,
+}
+
+impl Pane {
+    const fn next(self) -> Self {
+        match self {
+            Self::Results => Self::Callers,
+            Self::Callers => Self::Callees,
+            Self::Callees => Self::Results,
+        }
+    }
+
+    const fn previous(self) -> Self {
+        match self {
+            Self::Results => Self::Callees,
+            Self::Callers => Self::Results,
+            Self::Callees => Self::Callers,
+        }
+    }
+}
+
+/// State of the explorer: the query, its results, and the symbol in the
+/// detail pane with the trail of symbols visited to reach it.
+pub struct Explorer {
+    conn: Connection,
+    search: SearchConfig,
+    query: String,
+    results: Vec<Node>,
+    selected: usize,
+    focus: Pane,
+    current: Option<Node>,
+    callers: Vec<Node>,
+    callees: Vec<Node>,
+    caller_index: usize,
+    callee_index: usize,
+    history: Vec<Node>,
+    status: Option<String>,
+    quit: bool,
+}
+
+impl Explorer {
+    /// Open the project database with an empty search.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the database cannot be opened.
+    pub fn open(project_root: &Path) -> std::io::Result<Self> {
+        let conn = db::open_database(project_root)?;
+        let search = config::load_toml_config(project_root)
+            .unwrap_or_default()
+            .search;
+        Ok(Self {
+            conn,
+            search,
+            query: String::new(),
+            results: Vec::new(),
+            selected: 0,
+            focus: Pane::Results,
+            current: None,
+            callers: Vec::new(),
+            callees: Vec::new(),
+            caller_index: 0,
+            callee_index: 0,
+            history: Vec::new(),
+            status: None,
+            quit: false,
+        })
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn results(&self) -> &[Node] {
+        &self.results
+    }
+
+    pub const fn focus(&self) -> Pane {
+        self.focus
+    }
+
+    /// The symbol in the detail pane.
+    pub const fn current(&self) -> Option<&Node> {
+        self.current.as_ref()
+    }
+
+    pub fn callers(&self) -> &[Node] {
+        &self.callers
+    }
+
+    pub fn callees(&self) -> &[Node] {
+        &self.callees
+    }
+
+    pub const fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Apply one key press. Printable characters always edit the search box;
+    /// Esc steps back through visited symbols, then to the result list, then
+    /// quits.
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            if matches!(key.code, KeyCode::Char('c' | 'd')) {
+                self.quit = true;
+            }
+            return;
+        }
+        match key.code {
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.refresh_results();
+            }
+            KeyCode::Backspace if self.query.pop().is_some() => self.refresh_results(),
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Tab | KeyCode::Right => self.focus = self.focus.next(),
+            KeyCode::BackTab | KeyCode::Left => self.focus = self.focus.previous(),
+            KeyCode::Enter => self.open_selected(),
+            KeyCode::Esc => self.back(),
+            _ => {}
+        }
+    }
+
+    fn refresh_results(&mut self) {
+        let options = SearchOptions {
+            limit: Some(SEARCH_LIMIT),
+            ..SearchOptions::default()
+        };
+        match db::search_nodes_with_options(&self.conn, &self.query, &options, &self.search) {
+            Ok(results) => {
+                self.results = results.into_iter().map(|r| r.node).collect();
+                self.status = None;
+            }
+            Err(err) => {
+                self.results.clear();
+                self.status = Some(format!("Search failed: {err}"));
+            }
+        }
+        self.focus = Pane::Results;
+        self.selected = 0;
+        self.show_selected_result();
+    }
+
+    fn show_selected_result(&mut self) {
+        self.history.clear();
+        self.current = self.results.get(self.selected).cloned();
+        self.load_neighbours();
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (index, len) = match self.focus {
+            Pane::Results => (&mut self.selected, self.results.len()),
+            Pane::Callers => (&mut self.caller_index, self.callers.len()),
+            Pane::Callees => (&mut self.callee_index, self.callees.len()),
+        };
+        if len == 0 {
+            return;
+        }
+        let moved = index.saturating_add_signed(delta).min(len - 1);
+        if moved == *index {
+            return;
+        }
+        *index = moved;
+        if self.focus == Pane::Results {
+            self.show_selected_result();
+        }
+    }
+
+    fn open_selected(&mut self) {
+        let target = match self.focus {
+            Pane::Results => {
+                self.focus = Pane::Callers;
+                return;
+            }
+            Pane::Callers => self.callers.get(self.caller_index),
+            Pane::Callees => self.callees.get(self.callee_index),
+        };
+        let Some(target) = target.cloned() else {
+            return;
+        };
+        if let Some(previous) = self.current.replace(target) {
+            self.history.push(previous);
+        }
+        self.load_neighbours();
+    }
+
+    fn back(&mut self) {
+        if let Some(previous) = self.history.pop() {
+            self.current = Some(previous);
+            self.load_neighbours();
+        } else if self.focus == Pane::Results {
+            self.quit = true;
+        } else {
+            self.focus = Pane::Results;
+        }
+    }
+
+    fn load_neighbours(&mut self) {
+        self.caller_index = 0;
+        self.callee_index = 0;
+        self.callers.clear();
+        self.callees.clear();
+        let Some(node) = &self.current else {
+            return;
+        };
+        let loaded = calls_of(&self.conn, node, true)
+            .and_then(|incoming| Ok((incoming, calls_of(&self.conn, node, false)?)));
+        match loaded {
+            Ok((incoming, outgoing)) => {
+                self.callers = incoming;
+                self.callees = outgoing;
+            }
+            Err(err) => self.status = Some(format!("Failed to load calls: {err}")),
+        }
+    }
+
+    /// Draw the explorer over the whole frame.
+    pub fn draw(&self, frame: &mut Frame) {
+        let [search_area, body, help_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [results_area, side] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(body);
+        let [detail_area, calls_area] =
+            Layout::vertical([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(side);
+        let [incoming_area, outgoing_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(calls_area);
+
+        frame.render_widget(
+            Paragraph::new(format!("{}_", self.query)).block(Block::bordered().title(" Search ")),
+            search_area,
+        );
+        let results_title = format!(" Results ({}) ", self.results.len());
+        self.draw_list(frame, results_area, &results_title, Pane::Results);
+        self.draw_detail(frame, detail_area);
+        let incoming_title = format!(" Callers ({}) ", self.callers.len());
+        self.draw_list(frame, incoming_area, &incoming_title, Pane::Callers);
+        let outgoing_title = format!(" Callees ({}) ", self.callees.len());
+        self.draw_list(frame, outgoing_area, &outgoing_title, Pane::Callees);
+        frame.render_widget(
+            Paragraph::new(self.status.as_deref().unwrap_or(HELP)),
+            help_area,
+        );
+    }
+
+    fn draw_list(&self, frame: &mut Frame, area: Rect, title: &str, pane: Pane) {
+        let (nodes, index) = match pane {
+            Pane::Results => (&self.results, self.selected),
+            Pane::Callers => (&self.callers, self.caller_index),
+            Pane::Callees => (&self.callees, self.callee_index),
+        };
+        let focused = self.focus == pane;
+        let items: Vec<ListItem> = nodes
+            .iter()
+            .map(|node| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:?} ", node.kind),
+                        Style::new().add_modifier(Modifier::DIM),
+                    ),
+                    Span::raw(node.name.clone()),
+                    Span::styled(
+                        format!("  {}:{}", node.file_path, node.start_line),
+                        Style::new().add_modifier(Modifier::DIM),
+                    ),
+                ]))
+            })
+            .collect();
+        let mut block = Block::bordered().title(title.to_string());
+        if focused {
+            block = block.border_style(Style::new().add_modifier(Modifier::BOLD));
+        }
+        let highlight = if focused {
+            Style::new().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::new().add_modifier(Modifier::BOLD)
+        };
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(highlight)
+            .highlight_symbol("> ");
+        let mut state = ListState::default().with_selected((!nodes.is_empty()).then_some(index));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame, area: Rect) {
+        let title = if self.history.is_empty() {
+            " Symbol ".to_string()
+        } else {
+            format!(" Symbol ({} back with Esc) ", self.history.len())
+        };
+        let Some(node) = &self.current else {
+            frame.render_widget(
+                Paragraph::new("No symbol selected.").block(Block::bordered().title(title)),
+                area,
+            );
+            return;
+        };
+        let mut lines = vec![
+            Line::from(vec![
+                Span::raw(format!("{:?} ", node.kind)),
+                Span::styled(node.name.clone(), Style::new().add_modifier(Modifier::BOLD)),
+            ]),
+            Line::raw(format!(
+                "{}:{}-{}",
+                node.file_path, node.start_line, node.end_line
+            )),
+        ];
+        if node.qualified_name != node.name {
+            lines.push(Line::raw(node.qualified_name.clone()));
+        }
+        if let Some(signature) = &node.signature {
+            lines.push(Line::raw(""));
+            lines.push(Line::raw(signature.clone()));
+        }
+        if let Some(docstring) = &node.docstring {
+            lines.push(Line::raw(""));
+            lines.extend(docstring.lines().map(|line| Line::raw(line.to_string())));
+        }
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::bordered().title(title))
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                self.handle_key(key);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The confirmed callers (`incoming`) or callees of `node`, without
+/// duplicates, in source order.
+fn calls_of(conn: &Connection, node: &Node, incoming: bool) -> std::io::Result<Vec<Node>> {
+    let edges = if incoming {
+        db::get_edges_by_target(conn, &node.id, Some(EdgeKind::Calls), EDGE_LIMIT)?
+    } else {
+        db::get_edges_by_source(conn, &node.id, Some(EdgeKind::Calls), EDGE_LIMIT)?
+    };
+    let mut nodes: Vec<Node> = Vec::new();
+    for edge in edges {
+        let id = if incoming { edge.source } else { edge.target };
+        if nodes.iter().any(|n| n.id == id) {
+            continue;
+        }
+        let Some(other) = db::get_node_by_id(conn, &id)? else {
+            continue;
+        };
+        let (from, to) = if incoming {
+            (&other, node)
+        } else {
+            (node, &other)
+        };
+        if db::is_valid_call_edge(conn, from, to)? {
+            nodes.push(other);
+        }
+    }
+    Ok(nodes)
+}
+
+/// Run the explorer on the current terminal until the user quits.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the database cannot be opened or the terminal
+/// cannot be set up or read.
+pub fn run(project_root: &Path) -> std::io::Result<()> {
+    let mut explorer = Explorer::open(project_root)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = explorer.event_loop(&mut terminal);
+    let restored = ratatui::try_restore();
+    result?;
+    restored
+}
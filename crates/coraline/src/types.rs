@@ -1,10 +1,10 @@
 #![forbid(unsafe_code)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, juniper::GraphQLEnum)]
 #[serde(rename_all = "snake_case")]
 pub enum NodeKind {
     File,
@@ -29,9 +29,23 @@ pub enum NodeKind {
     Export,
     Route,
     Component,
+    Model,
+    Todo,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    juniper::GraphQLEnum,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum EdgeKind {
     Contains,
@@ -67,6 +81,7 @@ pub enum Language {
     Swift,
     Kotlin,
     Liquid,
+    Prisma,
     Blazor,
     // New languages (tree-sitter support)
     Bash,
@@ -135,6 +150,93 @@ pub struct Edge {
     pub column: Option<i64>,
 }
 
+/// Line coverage of one node, stored by `coraline ingest-coverage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeCoverage {
+    /// Instrumented lines of the node that ran at least once.
+    pub covered_lines: i64,
+    /// Instrumented lines of the node.
+    pub total_lines: i64,
+}
+
+impl NodeCoverage {
+    #[allow(clippy::cast_precision_loss)]
+    pub fn percent(&self) -> f64 {
+        if self.total_lines == 0 {
+            return 0.0;
+        }
+        self.covered_lines as f64 * 100.0 / self.total_lines as f64
+    }
+
+    /// No instrumented line of the node ran.
+    pub const fn is_untested(&self) -> bool {
+        self.covered_lines == 0
+    }
+}
+
+/// Where an issue id linked to a node was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSource {
+    /// The message of a commit that last changed lines of the node.
+    Commit,
+    /// A comment or docstring of the node.
+    Comment,
+}
+
+/// An issue id linked to a node by `coraline link-issues`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueLink {
+    pub node_id: String,
+    pub issue_id: String,
+    pub source: IssueSource,
+    /// The commit hash, or `path:line` of the comment.
+    pub reference: String,
+    /// The commit subject, or the comment text.
+    pub summary: String,
+}
+
+/// Change history of a node, recorded by `coraline churn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeChurn {
+    /// Scanned commits that changed the node's lines (a file's: the file).
+    pub commits: i64,
+    /// Distinct author emails of those commits.
+    pub authors: i64,
+    /// Unix time of the newest of them.
+    pub last_modified: i64,
+}
+
+/// Where an annotation came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationSource {
+    /// `coraline annotate add`.
+    User,
+    /// A rule of the project's CODEOWNERS file.
+    Codeowners,
+    /// A git submodule of the project, tagged `project:<name>`.
+    Submodule,
+    /// A workspace member, tagged `package:<name>`.
+    Package,
+}
+
+/// A tag attached to files or a symbol by `coraline annotate`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// A path glob (`src/payments/**`), a file or directory, or with
+    /// `symbol` a qualified name.
+    pub target: String,
+    pub symbol: bool,
+    /// `owner:@team`, `domain:payments`, `stability:experimental`, or a
+    /// bare label.
+    pub tag: String,
+    pub source: AnnotationSource,
+    /// Line of the CODEOWNERS rule; later rules override earlier ones.
+    #[serde(default)]
+    pub position: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileRecord {
     pub path: String,
@@ -180,16 +282,29 @@ pub struct UnresolvedReference {
     pub line: i64,
     pub column: i64,
     pub candidates: Option<Vec<String>>,
+    /// Arguments passed at the call site, for `calls` references whose
+    /// argument count is known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arity: Option<i64>,
+    /// Last segment of the expression a call is made on: `self` for
+    /// `self.save()`, `items` for `this.items.push(x)`, `Vec` for
+    /// `Vec::new()`. Rust paths and Python dotted names are kept whole
+    /// (`crate::db` for `crate::db::open()`, `app.util` for
+    /// `app.util.clean()`). `None` for a bare call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receiver: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Subgraph {
-    pub nodes: HashMap<String, Node>,
+    /// Nodes keyed by id; ordered so iteration is deterministic.
+    pub nodes: BTreeMap<String, Node>,
+    /// Unique edges in canonical order (source, target, kind, line, column).
     pub edges: Vec<Edge>,
     pub roots: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, juniper::GraphQLEnum)]
 #[serde(rename_all = "snake_case")]
 pub enum TraversalDirection {
     Outgoing,
@@ -197,7 +312,7 @@ pub enum TraversalDirection {
     Both,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TraversalOptions {
     pub max_depth: Option<usize>,
     pub edge_kinds: Option<Vec<EdgeKind>>,
@@ -205,17 +320,33 @@ pub struct TraversalOptions {
     pub direction: Option<TraversalDirection>,
     pub limit: Option<usize>,
     pub include_start: Option<bool>,
+    /// Per-edge-kind traversal cost. Lower-cost edges are expanded first, so
+    /// they are the ones kept when `limit` cuts the traversal short. Kinds not
+    /// listed fall back to the built-in defaults (`Calls` cheapest, `Contains`
+    /// most expensive).
+    pub edge_costs: Option<HashMap<EdgeKind, f64>>,
+    /// Multiplier applied to the cost of an edge walked from target to source
+    /// (defaults to `1.0`).
+    pub incoming_cost_factor: Option<f64>,
+    /// Skip edges the resolver picked with a lower confidence (see
+    /// [`crate::graph::edge_confidence`]). Observed edges always pass.
+    pub min_confidence: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SearchOptions {
     pub kinds: Option<Vec<NodeKind>>,
     pub languages: Option<Vec<Language>>,
+    /// File path globs; a result must match one of them.
     pub include_patterns: Option<Vec<String>>,
     pub exclude_patterns: Option<Vec<String>>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
+    /// Only results whose name contains a query term with the same case.
     pub case_sensitive: Option<bool>,
+    /// Only symbols that are exported, public, or named by an export
+    /// statement in their file.
+    pub exported_only: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -250,6 +381,14 @@ pub struct CodeBlock {
     pub end_line: i64,
     pub language: Language,
     pub node: Option<Node>,
+    /// The file changed after indexing and could not be re-synced, and the
+    /// node's lines no longer match, so the span may not match the content
+    /// shown.
+    #[serde(default)]
+    pub stale: bool,
+    /// The span was longer than `max_code_block_size` and was cut.
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -273,6 +412,28 @@ pub struct CustomPattern {
     pub kind: NodeKind,
 }
 
+/// How tracked file paths are compared when detecting changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathCaseSensitivity {
+    /// Case-insensitive on macOS and Windows, case-sensitive elsewhere.
+    #[default]
+    Auto,
+    Sensitive,
+    Insensitive,
+}
+
+impl PathCaseSensitivity {
+    /// Whether paths differing only in ASCII case refer to the same file.
+    pub const fn is_case_insensitive(self) -> bool {
+        match self {
+            Self::Auto => cfg!(any(target_os = "macos", windows)),
+            Self::Sensitive => false,
+            Self::Insensitive => true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeGraphConfig {
     pub version: i64,
@@ -286,6 +447,8 @@ pub struct CodeGraphConfig {
     pub track_call_sites: bool,
     pub enable_embeddings: bool,
     pub custom_patterns: Option<Vec<CustomPattern>>,
+    #[serde(default)]
+    pub path_case: PathCaseSensitivity,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -295,10 +458,51 @@ pub enum TaskInput {
     Detailed {
         title: String,
         description: Option<String>,
+        /// Aspects of the task searched separately, their contexts merged;
+        /// when empty, the title and description are searched as one query.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        queries: Vec<String>,
     },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl TaskInput {
+    /// `task`, searched as `queries` instead when there are any.
+    pub fn with_queries(task: String, queries: Vec<String>) -> Self {
+        if queries.is_empty() {
+            Self::Text(task)
+        } else {
+            Self::Detailed {
+                title: task,
+                description: None,
+                queries,
+            }
+        }
+    }
+
+    /// The text, or the title, plus the description when no sub-queries
+    /// stand in for it.
+    pub fn query(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Detailed {
+                title,
+                description: Some(description),
+                queries,
+            } if queries.is_empty() => format!("{title} {description}"),
+            Self::Detailed { title, .. } => title.clone(),
+        }
+    }
+
+    /// The sub-queries to search, empty for a single query.
+    pub fn sub_queries(&self) -> &[String] {
+        match self {
+            Self::Text(_) => &[],
+            Self::Detailed { queries, .. } => queries,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BuildContextOptions {
     pub max_nodes: Option<usize>,
     pub max_code_blocks: Option<usize>,
@@ -315,6 +519,30 @@ pub struct BuildContextOptions {
 pub enum ContextFormat {
     Markdown,
     Json,
+    /// Tagged sections (`<entry_points>`, `<document>`) for prompts that
+    /// address parts of the context by tag.
+    Xml,
+    /// File paths with the signatures of their symbols as a tree, no code.
+    RepoMap,
+}
+
+impl ContextFormat {
+    pub const ALL: [Self; 4] = [Self::Markdown, Self::Json, Self::Xml, Self::RepoMap];
+
+    /// The format named `name` (`repo-map` is accepted for `repo_map`).
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase().replace('-', "_");
+        Self::ALL.into_iter().find(|format| format.as_str() == name)
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Json => "json",
+            Self::Xml => "xml",
+            Self::RepoMap => "repo_map",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -326,6 +554,44 @@ pub struct TaskContext {
     pub related_files: Vec<String>,
     pub summary: String,
     pub stats: ContextStats,
+    /// Ids of entry points whose ingested coverage is zero.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub untested: Vec<String>,
+    /// Ids of entry points whose lines changed since indexing and could not
+    /// be re-synced.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stale: Vec<String>,
+    /// Annotation tags of entry points, by id; untagged ones are absent.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, Vec<String>>,
+    /// Imports and types entry points refer to, by id; entry points with
+    /// neither are absent.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, Dependencies>,
+    /// Import nodes of the entry points' files, by path; files without
+    /// imports are absent.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub imports: BTreeMap<String, Vec<Node>>,
+    /// `todo` nodes of entry points, by id, in line order; entry points
+    /// with none are absent.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub todos: BTreeMap<String, Vec<Node>>,
+    /// Sub-queries searched in place of `query`, when the task had any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sub_queries: Vec<String>,
+    /// The sub-queries whose subgraph reached each node, by node id.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attribution: BTreeMap<String, Vec<String>>,
+}
+
+/// What an entry point of a [`TaskContext`] depends on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Dependencies {
+    /// Import nodes of the entry point's file naming a symbol it, or a
+    /// symbol it contains, refers to.
+    pub imports: Vec<Node>,
+    /// Types the entry point, or a symbol it contains, refers to.
+    pub types: Vec<Node>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,5 +1,10 @@
 #![forbid(unsafe_code)]
 
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::types::{Language, Node, NodeKind};
+
 pub const fn version() -> &'static str {
     "0.1.0"
 }
@@ -13,6 +18,27 @@ pub fn hash_sha256(input: &str) -> String {
     hex::encode(result)
 }
 
+/// Hash of lines `start_line..=end_line` (1-based) of `content`: the source
+/// span of a node. Compared against the hash stored at indexing time to
+/// tell whether the node's text still sits at those lines.
+pub fn span_hash(content: &str, start_line: i64, end_line: i64) -> String {
+    let skip = usize::try_from(start_line.saturating_sub(1)).unwrap_or(0);
+    let take = usize::try_from(end_line.saturating_sub(start_line).saturating_add(1)).unwrap_or(0);
+    let span: Vec<&str> = content.lines().skip(skip).take(take).collect();
+    hash_sha256(&span.join("\n"))
+}
+
+/// Canonical comparison key for a project-relative path: forward slashes
+/// and, when `case_insensitive` is set, ASCII-lowercased.
+pub fn path_key(path: &str, case_insensitive: bool) -> String {
+    let normalized = path.replace('\\', "/");
+    if case_insensitive {
+        normalized.to_ascii_lowercase()
+    } else {
+        normalized
+    }
+}
+
 pub fn node_id_for_symbol(
     file_path: &str,
     kind: &str,
@@ -23,3 +49,99 @@ pub fn node_id_for_symbol(
     let seed = format!("{file_path}|{kind}|{qualified_name}|{start_line}|{start_column}");
     hash_sha256(&seed)
 }
+
+/// Heuristic for test code.
+///
+/// It matches a file under a test directory or named like a test
+/// (`*_test.go`, `*.spec.ts`, `test_*.py`, `FooTest.java`), a function
+/// named `test_*` or `Test*`, and anything inside a Rust `tests` module.
+pub fn is_test_node(node: &Node) -> bool {
+    let mut segments = node.file_path.split('/');
+    let file = segments.next_back().unwrap_or_default();
+    let in_test_dir =
+        segments.any(|dir| matches!(dir, "test" | "tests" | "__tests__" | "spec" | "specs"));
+    let stem = file.split('.').next().unwrap_or_default();
+    let test_file = file.contains(".test.")
+        || file.contains(".spec.")
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || stem.ends_with("Test")
+        || stem.ends_with("Tests");
+    let test_fn = matches!(node.kind, NodeKind::Function | NodeKind::Method)
+        && (node.name.starts_with("test_") || node.name.starts_with("Test"));
+    let in_test_module = node
+        .qualified_name
+        .split("::")
+        .any(|segment| segment == "tests");
+    in_test_dir || test_file || test_fn || in_test_module
+}
+
+/// Languages a word in free-form query text points at ("the React
+/// component", "the Rust service"). Words are matched case-insensitively,
+/// except `Go`, which must be capitalised to avoid matching the verb.
+pub fn language_hint(word: &str) -> &'static [Language] {
+    use Language::{
+        Bash, Blazor, CSharp, Cpp, Dart, Elixir, Elm, Erlang, Fortran, Go, Groovy, Haskell, Java,
+        JavaScript, Jsx, Julia, Kotlin, Liquid, Lua, Matlab, Nix, Perl, Php, Powershell, Python,
+        Ruby, Rust, Scala, Swift, Tsx, TypeScript, Zig,
+    };
+
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '+' && c != '#');
+    if word == "Go" {
+        return &[Go];
+    }
+    match word.to_lowercase().as_str() {
+        "rust" | "cargo" | "tokio" => &[Rust],
+        "typescript" | "ts" | "angular" | "nestjs" | "deno" => &[TypeScript, Tsx],
+        "react" | "jsx" | "tsx" | "nextjs" => &[Tsx, Jsx],
+        "javascript" | "js" | "nodejs" | "express" => &[JavaScript, Jsx],
+        "vue" | "svelte" => &[JavaScript, TypeScript],
+        "python" | "py" | "django" | "flask" | "fastapi" | "pytest" => &[Python],
+        "golang" => &[Go],
+        "java" | "jvm" => &[Java],
+        "kotlin" => &[Kotlin],
+        "scala" => &[Scala],
+        "groovy" | "gradle" => &[Groovy],
+        "c++" | "cpp" => &[Cpp],
+        "c#" | "csharp" | "dotnet" => &[CSharp],
+        "blazor" | "razor" => &[Blazor],
+        "php" | "laravel" | "symfony" => &[Php],
+        "ruby" | "rails" => &[Ruby],
+        "swift" | "swiftui" => &[Swift],
+        "dart" | "flutter" => &[Dart],
+        "elixir" | "phoenix" => &[Elixir],
+        "erlang" => &[Erlang],
+        "elm" => &[Elm],
+        "haskell" => &[Haskell],
+        "julia" => &[Julia],
+        "lua" => &[Lua],
+        "zig" => &[Zig],
+        "perl" => &[Perl],
+        "nix" => &[Nix],
+        "bash" | "shell" => &[Bash],
+        "powershell" => &[Powershell],
+        "fortran" => &[Fortran],
+        "matlab" => &[Matlab],
+        "liquid" | "shopify" => &[Liquid],
+        _ => &[],
+    }
+}
+
+/// Run `git -C project_root <args>` and return its stdout.
+pub(crate) fn git(project_root: &Path, args: &[&str]) -> std::io::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(project_root)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
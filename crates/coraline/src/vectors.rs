@@ -12,8 +12,13 @@
 
 //! Vector embeddings for semantic code search.
 //!
-//! Generates 768-dimensional embeddings using a locally-stored ONNX model
-//! (nomic-embed-text-v1.5) via the `ort` ONNX Runtime bindings.
+//! By default generates 768-dimensional embeddings using a locally-stored ONNX
+//! model (nomic-embed-text-v1.5) via the `ort` ONNX Runtime bindings. The
+//! backend is pluggable through the [`Embedder`] trait: `vectors.provider`
+//! selects [`OnnxEmbedder`], [`OpenAiEmbedder`] (any OpenAI-compatible
+//! `/embeddings` endpoint), or [`OllamaEmbedder`]. Only [`OnnxEmbedder`]
+//! needs the `embeddings` (or `embeddings-dynamic`) feature; the HTTP
+//! backends and vector storage are always built.
 //!
 //! ## Quick start
 //!
@@ -24,25 +29,41 @@
 //! 2. Run `coraline embed` to generate embeddings for all indexed nodes.
 //! 3. Use the `coraline_semantic_search` MCP tool to search by natural language.
 //!
+//! ## Body chunks
+//!
+//! A node's own vector covers only its name, docstring and signature. The
+//! bodies of functions, methods and type definitions are additionally split
+//! into chunks of at most `context.max_code_block_size` characters, each
+//! embedded into the `vector_chunks` table. At query time a node scores the
+//! best similarity among its own vector and its chunks (max-pooling).
+//!
 //! ## Model variant preference order
 //!
 //! When `model_file` is not configured, Coraline picks the first file found
 //! from [`MODEL_PREFERENCE_ORDER`].  This prefers well-quantized variants
 //! (137 MB) over the full f32 model (547 MB).
 
+use std::collections::HashMap;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 use ndarray::Array2;
+#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 use ort::{
     inputs,
     session::{Session, builder::GraphOptimizationLevel},
     value::TensorRef,
 };
 use rusqlite::{Connection, params};
+use serde_json::{Value, json};
+#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 use tokenizers::Tokenizer;
+use tracing::{debug, warn};
 
-use crate::types::SearchResult;
+use crate::config::{ContextConfig, EmbeddingProvider, VectorsConfig};
+use crate::types::{Node, NodeKind, SearchResult};
 
 /// Model identifier for nomic-embed-text-v1.5
 pub const DEFAULT_MODEL: &str = "nomic-embed-text-v1.5";
@@ -54,6 +75,22 @@ pub const EMBEDDING_DIM: usize = 768;
 /// nomic-embed-text-v1.5 supports up to 8192, but 512 covers most code snippets.
 pub const MAX_SEQ_LEN: usize = 512;
 
+/// Upper bound for `vectors.max_seq_len`; the model's context window.
+pub const MODEL_MAX_SEQ_LEN: usize = 8192;
+
+/// Task prefix nomic-embed-text expects on indexed documents.
+pub const DOCUMENT_PREFIX: &str = "search_document: ";
+
+/// Task prefix nomic-embed-text expects on search queries.
+pub const QUERY_PREFIX: &str = "search_query: ";
+
+/// Whether `model` is a nomic-embed-text model, which expects
+/// [`DOCUMENT_PREFIX`] and [`QUERY_PREFIX`]. Other models embed texts as
+/// they are.
+pub fn uses_task_prefixes(model: &str) -> bool {
+    model.to_ascii_lowercase().contains("nomic-embed")
+}
+
 /// ONNX model file names tried in order when `model_file` is not configured.
 ///
 /// Preference is given to quantized variants (smaller on disk, faster to load)
@@ -237,10 +274,72 @@ pub fn download_model(
     Ok(())
 }
 
+#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 type AnyError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
-/// ONNX-based vector embedding manager.
-pub struct VectorManager {
+// ── Embedding backends ────────────────────────────────────────────────────────
+
+/// A backend that turns text into embedding vectors.
+///
+/// Texts are embedded verbatim; task prefixes ([`DOCUMENT_PREFIX`],
+/// [`QUERY_PREFIX`]) are added by [`VectorManager`] for the models that
+/// expect them, so every backend sees the same input.
+pub trait Embedder: Send {
+    /// Model identifier recorded alongside stored vectors.
+    fn model_name(&self) -> &str;
+
+    /// Embed a single text.
+    fn embed(&mut self, text: &str) -> io::Result<Vec<f32>>;
+
+    /// Embed several texts, returning one result per input, in order.
+    ///
+    /// The default embeds one text at a time; HTTP backends override it to
+    /// send the whole batch in one request.
+    fn embed_batch(&mut self, texts: &[String]) -> Vec<io::Result<Vec<f32>>> {
+        texts.iter().map(|text| self.embed(text)).collect()
+    }
+}
+
+/// Load the ONNX Runtime library, once. `ort` would otherwise load it on
+/// first use and panic when it is missing; this reports that as an error.
+#[cfg(feature = "embeddings-dynamic")]
+fn load_runtime() -> io::Result<()> {
+    static LOADED: std::sync::OnceLock<Result<(), String>> = std::sync::OnceLock::new();
+    LOADED
+        .get_or_init(|| {
+            let path = std::env::var("ORT_DYLIB_PATH")
+                .ok()
+                .filter(|path| !path.is_empty())
+                .unwrap_or_else(|| {
+                    if cfg!(windows) {
+                        "onnxruntime.dll"
+                    } else if cfg!(target_os = "macos") {
+                        "libonnxruntime.dylib"
+                    } else {
+                        "libonnxruntime.so"
+                    }
+                    .to_string()
+                });
+            ort::init_from(&path)
+                .map(|env| {
+                    env.commit();
+                })
+                .map_err(|err| format!("failed to load ONNX Runtime from {path}: {err}"))
+        })
+        .clone()
+        .map_err(io::Error::other)
+}
+
+/// The runtime is linked in; nothing to load.
+#[cfg(all(feature = "embeddings", not(feature = "embeddings-dynamic")))]
+#[allow(clippy::unnecessary_wraps)]
+const fn load_runtime() -> io::Result<()> {
+    Ok(())
+}
+
+/// Local ONNX embedding model.
+#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
+pub struct OnnxEmbedder {
     session: Session,
     tokenizer: Tokenizer,
     model_name: String,
@@ -249,18 +348,22 @@ pub struct VectorManager {
     output_name: String,
     /// Whether the model accepts a `token_type_ids` input.
     has_token_type_ids: bool,
+    /// Token budget per input; longer inputs are truncated.
+    max_seq_len: usize,
 }
 
-impl VectorManager {
-    /// Load the manager from an ONNX model file.
+#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
+impl OnnxEmbedder {
+    /// Load an ONNX model file.
     ///
     /// Expects `tokenizer.json` in the same directory as `model_path`.
     pub fn new(model_path: &Path) -> io::Result<Self> {
+        load_runtime()?;
         let session = Session::builder()
             .map_err(io::Error::other)?
             .with_optimization_level(GraphOptimizationLevel::Level3)
             .map_err(io::Error::other)?
-            .with_intra_threads(4)
+            .with_intra_threads(crate::sync::thread_limit().map_or(4, |n| n.min(4)))
             .map_err(io::Error::other)?
             .commit_from_file(model_path)
             .map_err(io::Error::other)?;
@@ -295,9 +398,24 @@ impl VectorManager {
             model_name: DEFAULT_MODEL.to_string(),
             output_name,
             has_token_type_ids,
+            max_seq_len: MAX_SEQ_LEN,
         })
     }
 
+    /// Record vectors under `model_name` instead of [`DEFAULT_MODEL`].
+    #[must_use]
+    pub fn with_model_name(mut self, model_name: &str) -> Self {
+        self.model_name = model_name.to_string();
+        self
+    }
+
+    /// Override the token budget per input (clamped to `1..=MODEL_MAX_SEQ_LEN`).
+    #[must_use]
+    pub fn with_max_seq_len(mut self, max_seq_len: usize) -> Self {
+        self.max_seq_len = max_seq_len.clamp(1, MODEL_MAX_SEQ_LEN);
+        self
+    }
+
     /// Load from a directory, auto-detecting the best available model variant.
     ///
     /// Uses [`find_model_file`] with no preference, so it picks the first file
@@ -307,32 +425,23 @@ impl VectorManager {
         Self::new(&model_path)
     }
 
-    /// Load using the project's config (falls back to default model dir).
-    ///
-    /// Respects `vectors.model_dir` and `vectors.model_file` from config.toml.
-    pub fn from_project(project_root: &Path) -> io::Result<Self> {
-        let cfg = crate::config::load_toml_config(project_root).unwrap_or_default();
+    /// Load the model configured in `vectors` (falls back to the default
+    /// model dir), respecting `model`, `model_dir`, `model_file` and
+    /// `max_seq_len`.
+    pub fn from_config(project_root: &Path, cfg: &VectorsConfig) -> io::Result<Self> {
         let model_dir = cfg
-            .vectors
             .model_dir
+            .as_ref()
             .map_or_else(|| default_model_dir(project_root), PathBuf::from);
-        let model_path = find_model_file(&model_dir, cfg.vectors.model_file.as_deref())?;
-        Self::new(&model_path)
-    }
-
-    /// Generate a normalised embedding vector for `text`.
-    pub fn embed(&mut self, text: &str) -> io::Result<Vec<f32>> {
-        self.embed_impl(text).map_err(io::Error::other)
-    }
-
-    /// Get the model name.
-    pub fn model_name(&self) -> &str {
-        &self.model_name
+        let model_path = find_model_file(&model_dir, cfg.model_file.as_deref())?;
+        Ok(Self::new(&model_path)?
+            .with_model_name(&cfg.model)
+            .with_max_seq_len(cfg.max_seq_len))
     }
 
     fn embed_impl(&mut self, text: &str) -> Result<Vec<f32>, AnyError> {
         let encoding = self.tokenizer.encode(text, true)?;
-        let seq_len = encoding.get_ids().len().min(MAX_SEQ_LEN);
+        let seq_len = encoding.get_ids().len().min(self.max_seq_len);
 
         let input_ids: Vec<i64> = encoding.get_ids()[..seq_len]
             .iter()
@@ -377,6 +486,380 @@ impl VectorManager {
     }
 }
 
+#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
+impl Embedder for OnnxEmbedder {
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    fn embed(&mut self, text: &str) -> io::Result<Vec<f32>> {
+        self.embed_impl(text).map_err(io::Error::other)
+    }
+}
+
+/// Timeout for a single embedding request to an HTTP provider.
+pub const HTTP_REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// Default base URL for the `openai` provider.
+pub const OPENAI_DEFAULT_API_BASE: &str = "https://api.openai.com/v1";
+
+/// Environment variable read for the `openai` API key unless
+/// `vectors.api_key_env` names another.
+pub const OPENAI_DEFAULT_API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+/// Default base URL for the `ollama` provider.
+pub const OLLAMA_DEFAULT_API_BASE: &str = "http://localhost:11434";
+
+/// Embeddings from an OpenAI-compatible `POST {api_base}/embeddings` endpoint
+/// (OpenAI, Azure-style proxies, vLLM, llama.cpp server, LiteLLM, ...).
+pub struct OpenAiEmbedder {
+    agent: ureq::Agent,
+    url: String,
+    api_key: Option<String>,
+    model: String,
+    dimension: usize,
+}
+
+impl OpenAiEmbedder {
+    /// `api_key` is optional so unauthenticated local servers work.
+    pub fn new(api_base: &str, api_key: Option<String>, model: &str, dimension: usize) -> Self {
+        Self {
+            agent: http_agent(),
+            url: format!("{}/embeddings", api_base.trim_end_matches('/')),
+            api_key,
+            model: model.to_string(),
+            dimension,
+        }
+    }
+
+    fn request(&self, texts: &[String]) -> io::Result<Vec<Vec<f32>>> {
+        let mut request = self.agent.post(&self.url);
+        if let Some(key) = &self.api_key {
+            request = request.header("Authorization", &format!("Bearer {key}"));
+        }
+        let body = post_json(
+            request,
+            &self.url,
+            &json!({ "model": self.model, "input": texts }),
+        )?;
+
+        let mut data: Vec<(u64, Vec<f32>)> = body
+            .get("data")
+            .and_then(Value::as_array)
+            .ok_or_else(|| io::Error::other(format!("{}: response has no `data` array", self.url)))?
+            .iter()
+            .enumerate()
+            .map(|(pos, item)| {
+                let index = item
+                    .get("index")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(pos as u64);
+                parse_vector(item.get("embedding"), &self.url).map(|v| (index, v))
+            })
+            .collect::<io::Result<_>>()?;
+        data.sort_by_key(|(index, _)| *index);
+
+        check_vectors(
+            data.into_iter().map(|(_, v)| v).collect(),
+            texts.len(),
+            self.dimension,
+            &self.url,
+        )
+    }
+}
+
+impl Embedder for OpenAiEmbedder {
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&mut self, text: &str) -> io::Result<Vec<f32>> {
+        single(self.request(std::slice::from_ref(&text.to_string()))?)
+    }
+
+    fn embed_batch(&mut self, texts: &[String]) -> Vec<io::Result<Vec<f32>>> {
+        spread(self.request(texts), texts.len())
+    }
+}
+
+/// Embeddings from an Ollama server's `POST {api_base}/api/embed` endpoint.
+pub struct OllamaEmbedder {
+    agent: ureq::Agent,
+    url: String,
+    model: String,
+    dimension: usize,
+}
+
+impl OllamaEmbedder {
+    pub fn new(api_base: &str, model: &str, dimension: usize) -> Self {
+        Self {
+            agent: http_agent(),
+            url: format!("{}/api/embed", api_base.trim_end_matches('/')),
+            model: model.to_string(),
+            dimension,
+        }
+    }
+
+    fn request(&self, texts: &[String]) -> io::Result<Vec<Vec<f32>>> {
+        let body = post_json(
+            self.agent.post(&self.url),
+            &self.url,
+            &json!({ "model": self.model, "input": texts }),
+        )?;
+
+        let vectors = body
+            .get("embeddings")
+            .and_then(Value::as_array)
+            .ok_or_else(|| {
+                io::Error::other(format!("{}: response has no `embeddings` array", self.url))
+            })?
+            .iter()
+            .map(|item| parse_vector(Some(item), &self.url))
+            .collect::<io::Result<_>>()?;
+
+        check_vectors(vectors, texts.len(), self.dimension, &self.url)
+    }
+}
+
+impl Embedder for OllamaEmbedder {
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&mut self, text: &str) -> io::Result<Vec<f32>> {
+        single(self.request(std::slice::from_ref(&text.to_string()))?)
+    }
+
+    fn embed_batch(&mut self, texts: &[String]) -> Vec<io::Result<Vec<f32>>> {
+        spread(self.request(texts), texts.len())
+    }
+}
+
+fn http_agent() -> ureq::Agent {
+    ureq::Agent::new_with_config(
+        ureq::config::Config::builder()
+            .timeout_global(Some(Duration::from_secs(HTTP_REQUEST_TIMEOUT_SECS)))
+            .http_status_as_error(false)
+            .user_agent("coraline-embed")
+            .build(),
+    )
+}
+
+/// Send `payload` and parse the JSON response, surfacing the server's error
+/// body on non-2xx statuses.
+fn post_json(
+    request: ureq::RequestBuilder<ureq::typestate::WithBody>,
+    url: &str,
+    payload: &Value,
+) -> io::Result<Value> {
+    let mut response = request
+        .send_json(payload)
+        .map_err(|e| io::Error::other(format!("POST {url} failed: {e}")))?;
+    let status = response.status();
+    let body = response
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| io::Error::other(format!("{url}: failed to read response: {e}")))?;
+    if !status.is_success() {
+        return Err(io::Error::other(format!(
+            "POST {url} returned {status}: {}",
+            body.trim()
+        )));
+    }
+    serde_json::from_str(&body)
+        .map_err(|e| io::Error::other(format!("{url}: invalid JSON response: {e}")))
+}
+
+fn parse_vector(value: Option<&Value>, url: &str) -> io::Result<Vec<f32>> {
+    value
+        .and_then(Value::as_array)
+        .and_then(|items| {
+            items
+                .iter()
+                .map(|x| x.as_f64().map(|f| f as f32))
+                .collect::<Option<Vec<f32>>>()
+        })
+        .ok_or_else(|| io::Error::other(format!("{url}: embedding is not an array of numbers")))
+}
+
+/// Verify count and dimension, then normalise so cosine scores are
+/// comparable with locally generated vectors.
+fn check_vectors(
+    vectors: Vec<Vec<f32>>,
+    expected: usize,
+    dimension: usize,
+    url: &str,
+) -> io::Result<Vec<Vec<f32>>> {
+    if vectors.len() != expected {
+        return Err(io::Error::other(format!(
+            "{url}: expected {expected} embeddings, got {}",
+            vectors.len()
+        )));
+    }
+    if let Some(v) = vectors.iter().find(|v| v.len() != dimension) {
+        return Err(io::Error::other(format!(
+            "{url}: returned {}-dimensional vectors but vectors.dimension is {dimension}",
+            v.len()
+        )));
+    }
+    Ok(vectors.into_iter().map(l2_normalize).collect())
+}
+
+fn single(mut vectors: Vec<Vec<f32>>) -> io::Result<Vec<f32>> {
+    vectors
+        .pop()
+        .ok_or_else(|| io::Error::other("empty embedding response"))
+}
+
+/// Fan a whole-batch result out to one result per input.
+fn spread(result: io::Result<Vec<Vec<f32>>>, len: usize) -> Vec<io::Result<Vec<f32>>> {
+    match result {
+        Ok(vectors) => vectors.into_iter().map(Ok).collect(),
+        Err(err) => {
+            let message = err.to_string();
+            (0..len)
+                .map(|_| Err(io::Error::other(message.clone())))
+                .collect()
+        }
+    }
+}
+
+// ── Manager ───────────────────────────────────────────────────────────────────
+
+/// Embedding manager wrapping the configured [`Embedder`].
+pub struct VectorManager {
+    embedder: Box<dyn Embedder>,
+    batch_size: usize,
+    chunk_chars: usize,
+}
+
+impl VectorManager {
+    /// Load a local ONNX model file.
+    ///
+    /// Expects `tokenizer.json` in the same directory as `model_path`.
+    #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
+    pub fn new(model_path: &Path) -> io::Result<Self> {
+        Ok(Self::from_embedder(Box::new(OnnxEmbedder::new(
+            model_path,
+        )?)))
+    }
+
+    /// Wrap an arbitrary backend.
+    pub fn from_embedder(embedder: Box<dyn Embedder>) -> Self {
+        Self {
+            embedder,
+            batch_size: VectorsConfig::default().batch_size,
+            chunk_chars: ContextConfig::default().max_code_block_size,
+        }
+    }
+
+    /// Set the maximum characters per body chunk; `0` disables body chunks.
+    #[must_use]
+    pub const fn with_chunk_chars(mut self, chunk_chars: usize) -> Self {
+        self.chunk_chars = chunk_chars;
+        self
+    }
+
+    /// Set how many texts are sent to the backend per batch (at least 1).
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Build the backend selected by `cfg.provider`.
+    pub fn from_config(project_root: &Path, cfg: &VectorsConfig) -> io::Result<Self> {
+        let embedder: Box<dyn Embedder> = match cfg.provider {
+            #[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
+            EmbeddingProvider::Onnx => Box::new(OnnxEmbedder::from_config(project_root, cfg)?),
+            #[cfg(not(any(feature = "embeddings", feature = "embeddings-dynamic")))]
+            EmbeddingProvider::Onnx => {
+                let _ = project_root;
+                return Err(io::Error::other(
+                    "this build has no ONNX Runtime; rebuild with the `embeddings` feature \
+                     or set vectors.provider to \"openai\" or \"ollama\"",
+                ));
+            }
+            EmbeddingProvider::OpenAi => {
+                let key_env = cfg
+                    .api_key_env
+                    .as_deref()
+                    .unwrap_or(OPENAI_DEFAULT_API_KEY_ENV);
+                Box::new(OpenAiEmbedder::new(
+                    cfg.api_base.as_deref().unwrap_or(OPENAI_DEFAULT_API_BASE),
+                    std::env::var(key_env).ok().filter(|k| !k.is_empty()),
+                    &cfg.model,
+                    cfg.dimension,
+                ))
+            }
+            EmbeddingProvider::Ollama => Box::new(OllamaEmbedder::new(
+                cfg.api_base.as_deref().unwrap_or(OLLAMA_DEFAULT_API_BASE),
+                &cfg.model,
+                cfg.dimension,
+            )),
+        };
+        Ok(Self::from_embedder(embedder).with_batch_size(cfg.batch_size))
+    }
+
+    /// Load using the project's `[vectors]` config, chunking bodies by
+    /// `context.max_code_block_size`.
+    pub fn from_project(project_root: &Path) -> io::Result<Self> {
+        let cfg = crate::config::load_toml_config(project_root).unwrap_or_default();
+        Ok(Self::from_config(project_root, &cfg.vectors)?
+            .with_chunk_chars(cfg.context.max_code_block_size))
+    }
+
+    /// Generate a normalised embedding vector for `text`.
+    ///
+    /// `text` is embedded verbatim; pass documents through
+    /// [`Self::document_text`] and embed search queries with
+    /// [`Self::embed_query`] so the task prefixes match.
+    pub fn embed(&mut self, text: &str) -> io::Result<Vec<f32>> {
+        self.embedder.embed(text)
+    }
+
+    /// Embed several texts; see [`Embedder::embed_batch`].
+    pub fn embed_batch(&mut self, texts: &[String]) -> Vec<io::Result<Vec<f32>>> {
+        self.embedder.embed_batch(texts)
+    }
+
+    /// Embed a natural-language search query, with the query task prefix
+    /// when the model expects one.
+    pub fn embed_query(&mut self, query: &str) -> io::Result<Vec<f32>> {
+        let text = self.prefixed(QUERY_PREFIX, query);
+        self.embed(&text)
+    }
+
+    /// `text` as a document to index, with the document task prefix when
+    /// the model expects one.
+    pub fn document_text(&self, text: &str) -> String {
+        self.prefixed(DOCUMENT_PREFIX, text)
+    }
+
+    fn prefixed(&self, prefix: &str, text: &str) -> String {
+        if uses_task_prefixes(self.model_name()) {
+            format!("{prefix}{text}")
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Get the model name.
+    pub fn model_name(&self) -> &str {
+        self.embedder.model_name()
+    }
+
+    /// Texts per backend request.
+    pub const fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Maximum characters per body chunk (`0` = chunking disabled).
+    pub const fn chunk_chars(&self) -> usize {
+        self.chunk_chars
+    }
+}
+
 /// Default model directory: `.coraline/models/nomic-embed-text-v1.5/`.
 pub fn default_model_dir(project_root: &Path) -> PathBuf {
     project_root
@@ -388,6 +871,7 @@ pub fn default_model_dir(project_root: &Path) -> PathBuf {
 /// Mean-pool the last hidden state over non-masked positions.
 ///
 /// `slice` is the flat row-major data of a `[1, seq_len, hidden_dim]` tensor.
+#[cfg(any(feature = "embeddings", feature = "embeddings-dynamic"))]
 fn mean_pool(slice: &[f32], shape: &[usize], attention_mask: &[i64]) -> Vec<f32> {
     let (seq_len, hidden_dim) = (shape[1], shape[2]);
     let mut pooled = vec![0.0f32; hidden_dim];
@@ -428,7 +912,8 @@ fn l2_normalize(mut v: Vec<f32>) -> Vec<f32> {
     v
 }
 
-/// Build the text to embed for a node: name + qualified name + docstring + signature.
+/// Build the text to embed for a node: name + qualified name + docstring +
+/// signature. [`VectorManager::document_text`] adds the task prefix.
 pub fn node_embed_text(
     name: &str,
     qualified_name: &str,
@@ -448,6 +933,169 @@ pub fn node_embed_text(
     parts.join(" | ")
 }
 
+/// Upper bound on body chunks embedded per node; the tail of a longer body
+/// is not embedded.
+pub const MAX_CHUNKS_PER_NODE: usize = 8;
+
+/// Whether the body of a `kind` node is worth embedding in chunks. Containers
+/// (files, modules, classes) are skipped: their members are embedded on their
+/// own.
+pub const fn has_embeddable_body(kind: NodeKind) -> bool {
+    matches!(
+        kind,
+        NodeKind::Function
+            | NodeKind::Method
+            | NodeKind::Component
+            | NodeKind::Struct
+            | NodeKind::Enum
+            | NodeKind::Interface
+            | NodeKind::TypeAlias
+    )
+}
+
+/// Split lines `start_line..=end_line` (1-based) of `source` into at most
+/// [`MAX_CHUNKS_PER_NODE`] chunks of up to `max_chars` characters, breaking
+/// between lines where possible. Blank chunks are dropped.
+pub fn body_chunks(source: &str, start_line: i64, end_line: i64, max_chars: usize) -> Vec<String> {
+    if max_chars == 0 || end_line < start_line {
+        return Vec::new();
+    }
+    let skip = usize::try_from(start_line.saturating_sub(1)).unwrap_or(0);
+    let take = usize::try_from(end_line - start_line + 1).unwrap_or(0);
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0usize;
+    for line in source.lines().skip(skip).take(take) {
+        // Hard-split lines that alone exceed the budget.
+        let mut pieces = Vec::new();
+        let mut rest = line;
+        while rest.chars().count() > max_chars {
+            let cut = rest
+                .char_indices()
+                .nth(max_chars)
+                .map_or(rest.len(), |(i, _)| i);
+            pieces.push(&rest[..cut]);
+            rest = &rest[cut..];
+        }
+        pieces.push(rest);
+
+        for piece in pieces {
+            let piece_chars = piece.chars().count();
+            if current_chars > 0 && current_chars + 1 + piece_chars > max_chars {
+                chunks.push(std::mem::take(&mut current));
+                current_chars = 0;
+                if chunks.len() == MAX_CHUNKS_PER_NODE {
+                    break;
+                }
+            }
+            if current_chars > 0 {
+                current.push('\n');
+                current_chars += 1;
+            }
+            current.push_str(piece);
+            current_chars += piece_chars;
+        }
+        if chunks.len() == MAX_CHUNKS_PER_NODE {
+            break;
+        }
+    }
+    if !current.is_empty() && chunks.len() < MAX_CHUNKS_PER_NODE {
+        chunks.push(current);
+    }
+    chunks.retain(|chunk| !chunk.trim().is_empty());
+    chunks
+}
+
+/// Embed the body chunks of `nodes`, replacing any chunks stored for them.
+///
+/// Bodies are read from the files under `project_root`; unreadable files and
+/// chunks that fail to embed are skipped. Returns the number of chunks stored.
+pub fn embed_body_chunks(
+    project_root: &Path,
+    conn: &Connection,
+    vm: &mut VectorManager,
+    nodes: &[Node],
+) -> io::Result<usize> {
+    if vm.chunk_chars() == 0 {
+        return Ok(0);
+    }
+
+    let mut sources: HashMap<&str, Option<String>> = HashMap::new();
+    let mut pending: Vec<(&str, usize, String)> = Vec::new();
+    for node in nodes.iter().filter(|n| has_embeddable_body(n.kind)) {
+        delete_chunk_embeddings(conn, &node.id)?;
+        let source = sources
+            .entry(node.file_path.as_str())
+            .or_insert_with(|| std::fs::read_to_string(project_root.join(&node.file_path)).ok());
+        let Some(source) = source else { continue };
+        for (index, chunk) in body_chunks(source, node.start_line, node.end_line, vm.chunk_chars())
+            .into_iter()
+            .enumerate()
+        {
+            let text = vm.document_text(&format!("{}\n{chunk}", node.qualified_name));
+            pending.push((node.id.as_str(), index, text));
+        }
+    }
+
+    let mut stored = 0usize;
+    for batch in pending.chunks(vm.batch_size()) {
+        let texts: Vec<String> = batch.iter().map(|(_, _, text)| text.clone()).collect();
+        for ((node_id, index, _), result) in batch.iter().zip(vm.embed_batch(&texts)) {
+            match result {
+                Ok(embedding) => {
+                    store_chunk_embedding(conn, node_id, *index, &embedding, vm.model_name())?;
+                    stored += 1;
+                }
+                Err(err) => {
+                    debug!(node = %node_id, chunk = index, error = %err, "embed failed for chunk");
+                }
+            }
+        }
+    }
+    Ok(stored)
+}
+
+/// Store one body-chunk embedding for a node.
+pub fn store_chunk_embedding(
+    conn: &Connection,
+    node_id: &str,
+    chunk_index: usize,
+    embedding: &[f32],
+    model_name: &str,
+) -> io::Result<()> {
+    let embedding_bytes: Vec<u8> = embedding.iter().flat_map(|&f| f.to_le_bytes()).collect();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| io::Error::other(format!("Failed to get system time: {}", e)))?
+        .as_millis() as i64;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO vector_chunks (node_id, chunk_index, embedding, model, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            node_id,
+            i64::try_from(chunk_index).unwrap_or(i64::MAX),
+            embedding_bytes,
+            model_name,
+            now
+        ],
+    )
+    .map_err(|e| io::Error::other(format!("Failed to store chunk embedding: {}", e)))?;
+
+    Ok(())
+}
+
+/// Remove all body-chunk embeddings of a node.
+pub fn delete_chunk_embeddings(conn: &Connection, node_id: &str) -> io::Result<()> {
+    conn.execute(
+        "DELETE FROM vector_chunks WHERE node_id = ?1",
+        params![node_id],
+    )
+    .map_err(|e| io::Error::other(format!("Failed to delete chunk embeddings: {}", e)))?;
+    Ok(())
+}
+
 /// Store an embedding vector for a node in the database.
 ///
 /// # Arguments
@@ -480,6 +1128,147 @@ pub fn store_embedding(
     Ok(())
 }
 
+/// Embed `nodes` (in one backend batch), returning one result per node.
+pub fn embed_node_batch(vm: &mut VectorManager, nodes: &[Node]) -> Vec<io::Result<Vec<f32>>> {
+    let texts: Vec<String> = nodes
+        .iter()
+        .map(|node| {
+            vm.document_text(&node_embed_text(
+                &node.name,
+                &node.qualified_name,
+                node.docstring.as_deref(),
+                node.signature.as_deref(),
+            ))
+        })
+        .collect();
+    vm.embed_batch(&texts)
+}
+
+/// Embed every node that has no vector yet, plus its body chunks, in a
+/// single transaction.
+///
+/// Nodes whose text fails to embed are skipped; a batch in which every node
+/// fails (e.g. an unreachable HTTP provider) stops the run, keeping what was
+/// stored so far. Nothing is embedded while the index holds vectors from
+/// another model. Returns the number of node embeddings stored.
+pub fn embed_missing_nodes(
+    project_root: &Path,
+    conn: &Connection,
+    vm: &mut VectorManager,
+) -> io::Result<usize> {
+    let nodes = crate::db::get_unembedded_nodes(conn)?;
+    if nodes.is_empty() {
+        return Ok(0);
+    }
+    // Adding vectors from a different model would leave a mixed index.
+    if let Err(err) = check_vector_models(conn, vm.model_name(), None) {
+        warn!("Skipping embedding refresh: {err}");
+        return Ok(0);
+    }
+
+    let tx = conn.unchecked_transaction().map_err(io::Error::other)?;
+    let mut stored = 0usize;
+    for batch in nodes.chunks(vm.batch_size()) {
+        let results = embed_node_batch(vm, batch);
+        if let Some(Err(err)) = results.first()
+            && results.iter().all(Result::is_err)
+        {
+            warn!(error = %err, "embedding batch failed; stopping");
+            break;
+        }
+        for (node, result) in batch.iter().zip(results) {
+            match result {
+                Ok(embedding) => {
+                    store_embedding(&tx, &node.id, &embedding, vm.model_name())?;
+                    stored += 1;
+                }
+                Err(err) => debug!(node = %node.name, error = %err, "embed failed for node"),
+            }
+        }
+        embed_body_chunks(project_root, &tx, vm, batch)?;
+    }
+    tx.commit().map_err(io::Error::other)?;
+
+    Ok(stored)
+}
+
+/// Stored vectors grouped by the model and dimension that produced them.
+///
+/// The dimension is derived from each row's blob length (4 bytes per `f32`).
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ModelVectorCount {
+    pub model: String,
+    pub dimension: usize,
+    /// Node vectors plus body chunks.
+    pub vectors: usize,
+}
+
+/// Summarise which models (and dimensions) the stored embeddings come from.
+pub fn vector_models(conn: &Connection) -> io::Result<Vec<ModelVectorCount>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT model, dim, COUNT(*) FROM (
+                 SELECT model, length(embedding) / 4 AS dim FROM vectors
+                 UNION ALL
+                 SELECT model, length(embedding) / 4 AS dim FROM vector_chunks
+             )
+             GROUP BY model, dim
+             ORDER BY COUNT(*) DESC, model ASC",
+        )
+        .map_err(|e| io::Error::other(format!("Failed to prepare query: {}", e)))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ModelVectorCount {
+                model: row.get(0)?,
+                dimension: usize::try_from(row.get::<_, i64>(1)?).unwrap_or_default(),
+                vectors: usize::try_from(row.get::<_, i64>(2)?).unwrap_or_default(),
+            })
+        })
+        .map_err(|e| io::Error::other(format!("Failed to execute query: {}", e)))?;
+    rows.collect::<Result<_, _>>().map_err(io::Error::other)
+}
+
+/// Fail when stored embeddings were produced by a model other than `model`
+/// (or, when given, with a dimension other than `dimension`), since vectors
+/// from different models are not comparable.
+pub fn check_vector_models(
+    conn: &Connection,
+    model: &str,
+    dimension: Option<usize>,
+) -> io::Result<()> {
+    let foreign: Vec<ModelVectorCount> = vector_models(conn)?
+        .into_iter()
+        .filter(|m| m.model != model || dimension.is_some_and(|d| d != m.dimension))
+        .collect();
+    if foreign.is_empty() {
+        return Ok(());
+    }
+    let found = foreign
+        .iter()
+        .map(|m| {
+            format!(
+                "{} vectors from `{}` ({}-d)",
+                m.vectors, m.model, m.dimension
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let expected = dimension.map_or_else(|| format!("`{model}`"), |d| format!("`{model}` ({d}-d)"));
+    Err(io::Error::other(format!(
+        "Embedding index does not match the configured model {expected}: found {found}. \
+         Run `coraline embed --rebuild` to re-embed all nodes."
+    )))
+}
+
+/// Delete every stored node vector and body chunk. Returns the number of
+/// node vectors removed.
+pub fn clear_embeddings(conn: &Connection) -> io::Result<usize> {
+    conn.execute("DELETE FROM vector_chunks", [])
+        .map_err(|e| io::Error::other(format!("Failed to clear chunk embeddings: {}", e)))?;
+    conn.execute("DELETE FROM vectors", [])
+        .map_err(|e| io::Error::other(format!("Failed to clear embeddings: {}", e)))
+}
+
 /// Load an embedding vector from the database.
 ///
 /// # Arguments
@@ -559,16 +1348,24 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 /// * `query_embedding` - The query embedding vector
 /// * `limit` - Maximum number of results to return
 /// * `min_similarity` - Minimum cosine similarity threshold (0.0 to 1.0)
+/// * `kind` - Only return nodes of this kind
 ///
 /// # Returns
 ///
-/// A vector of SearchResult ordered by similarity (highest first).
+/// A vector of SearchResult ordered by similarity (highest first). A node's
+/// similarity is the best among its own vector and its body chunks. Vectors
+/// whose dimension differs from the query are ignored; use
+/// [`check_vector_models`] to reject such an index up front.
 pub fn search_similar(
     conn: &Connection,
     query_embedding: &[f32],
     limit: usize,
     min_similarity: f32,
+    kind: Option<NodeKind>,
 ) -> io::Result<Vec<SearchResult>> {
+    let best_chunks = best_chunk_similarities(conn, query_embedding)?;
+    let kind = kind.map(crate::db::kind_to_string);
+
     let mut stmt = conn
         .prepare(
             "SELECT v.node_id, v.embedding,
@@ -578,12 +1375,13 @@ pub fn search_similar(
                          n.is_exported, n.is_async, n.is_static, n.is_abstract,
                          n.decorators, n.type_parameters
                   FROM vectors v
-                  JOIN nodes n ON v.node_id = n.id",
+                  JOIN nodes n ON v.node_id = n.id
+                  WHERE ?1 IS NULL OR n.kind = ?1",
         )
         .map_err(|e| io::Error::other(format!("Failed to prepare query: {}", e)))?;
 
     let rows = stmt
-        .query_map([], |row| {
+        .query_map([&kind], |row| {
             let embedding_bytes: Vec<u8> = row.get(1)?;
 
             // Convert bytes to f32 vector
@@ -592,10 +1390,17 @@ pub fn search_similar(
                 .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
                 .collect();
 
-            let similarity = cosine_similarity(query_embedding, &embedding);
+            let node_id: String = row.get(0)?;
+            if embedding.len() != query_embedding.len() {
+                return Ok(None);
+            }
+            let similarity = best_chunks.get(&node_id).map_or_else(
+                || cosine_similarity(query_embedding, &embedding),
+                |&chunk| chunk.max(cosine_similarity(query_embedding, &embedding)),
+            );
 
             // Parse node from row (offset by 2 since we have node_id and embedding first)
-            use crate::types::{Language, Node, NodeKind};
+            use crate::types::Language;
 
             let node = Node {
                 id: row.get(2)?,
@@ -631,12 +1436,12 @@ pub fn search_similar(
                     .as_millis() as i64,
             };
 
-            Ok((similarity, node))
+            Ok(Some((similarity, node)))
         })
         .map_err(|e| io::Error::other(format!("Failed to execute query: {}", e)))?;
 
     let mut results: Vec<_> = rows
-        .filter_map(|r| r.ok())
+        .filter_map(|r| r.ok().flatten())
         .filter(|(sim, _)| *sim >= min_similarity)
         .collect();
 
@@ -655,6 +1460,39 @@ pub fn search_similar(
         .collect())
 }
 
+/// Highest chunk similarity to `query_embedding` per node id.
+fn best_chunk_similarities(
+    conn: &Connection,
+    query_embedding: &[f32],
+) -> io::Result<HashMap<String, f32>> {
+    let mut stmt = conn
+        .prepare("SELECT node_id, embedding FROM vector_chunks")
+        .map_err(|e| io::Error::other(format!("Failed to prepare query: {}", e)))?;
+    let rows = stmt
+        .query_map([], |row| {
+            let node_id: String = row.get(0)?;
+            let bytes: Vec<u8> = row.get(1)?;
+            Ok((node_id, bytes))
+        })
+        .map_err(|e| io::Error::other(format!("Failed to execute query: {}", e)))?;
+
+    let mut best: HashMap<String, f32> = HashMap::new();
+    for (node_id, bytes) in rows.filter_map(|r| r.ok()) {
+        let embedding: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        if embedding.len() != query_embedding.len() {
+            continue;
+        }
+        let similarity = cosine_similarity(query_embedding, &embedding);
+        best.entry(node_id)
+            .and_modify(|b| *b = b.max(similarity))
+            .or_insert(similarity);
+    }
+    Ok(best)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -785,4 +1623,245 @@ mod tests {
         let sim = cosine_similarity(&a, &b);
         assert_eq!(sim, 0.0);
     }
+
+    /// Serve one canned JSON response on a random local port, returning the
+    /// base URL and a handle yielding the request body and `Authorization`.
+    fn serve_once(
+        status: u16,
+        body: &'static str,
+    ) -> (String, std::thread::JoinHandle<(String, Option<String>)>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut received = String::new();
+            request.as_reader().read_to_string(&mut received).unwrap();
+            let auth = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Authorization"))
+                .map(|h| h.value.to_string());
+            request
+                .respond(tiny_http::Response::from_string(body).with_status_code(status))
+                .unwrap();
+            (received, auth)
+        });
+        (base, handle)
+    }
+
+    #[test]
+    fn test_openai_embedder_batches_and_orders_by_index() {
+        let (base, handle) = serve_once(
+            200,
+            r#"{"data":[{"index":1,"embedding":[0.0,2.0]},{"index":0,"embedding":[3.0,4.0]}]}"#,
+        );
+        let mut embedder = OpenAiEmbedder::new(&base, Some("sk-test".into()), "tiny", 2);
+
+        let results = embedder.embed_batch(&["a".to_string(), "b".to_string()]);
+        let (received, auth) = handle.join().unwrap();
+
+        let request: Value = serde_json::from_str(&received).unwrap();
+        assert_eq!(request["model"], "tiny");
+        assert_eq!(request["input"], json!(["a", "b"]));
+        assert_eq!(auth.as_deref(), Some("Bearer sk-test"));
+        assert_eq!(results[0].as_ref().unwrap(), &vec![0.6, 0.8]);
+        assert_eq!(results[1].as_ref().unwrap(), &vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_ollama_embedder_rejects_wrong_dimension() {
+        let (base, handle) = serve_once(200, r#"{"embeddings":[[1.0,0.0,0.0]]}"#);
+        let mut embedder = OllamaEmbedder::new(&base, "nomic-embed-text", 768);
+
+        let err = embedder.embed("hello").unwrap_err();
+        handle.join().unwrap();
+        assert!(err.to_string().contains("3-dimensional"), "{err}");
+    }
+
+    #[test]
+    fn test_http_embedder_failure_reports_server_error_per_input() {
+        let (base, handle) = serve_once(404, r#"{"error":"model not found"}"#);
+        let mut embedder = OllamaEmbedder::new(&base, "missing", 768);
+
+        let results = embedder.embed_batch(&["a".to_string(), "b".to_string()]);
+        handle.join().unwrap();
+        assert_eq!(results.len(), 2);
+        for result in results {
+            let err = result.unwrap_err().to_string();
+            assert!(
+                err.contains("404") && err.contains("model not found"),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_body_chunks_respect_line_range_and_size() {
+        let source = "header\nfn f() {\n    let a = 1;\n    let b = 2;\n}\ntrailer\n";
+        let chunks = body_chunks(source, 2, 5, 24);
+        assert_eq!(
+            chunks,
+            vec!["fn f() {\n    let a = 1;", "    let b = 2;\n}"]
+        );
+        assert!(chunks.iter().all(|c| c.chars().count() <= 24));
+        assert!(body_chunks(source, 2, 5, 0).is_empty());
+    }
+
+    #[test]
+    fn test_body_chunks_split_long_lines_and_cap_count() {
+        let long_line = "x".repeat(25);
+        assert_eq!(
+            body_chunks(&long_line, 1, 1, 10),
+            vec!["x".repeat(10), "x".repeat(10), "x".repeat(5)]
+        );
+
+        let many_lines = "line\n".repeat(100);
+        assert_eq!(
+            body_chunks(&many_lines, 1, 100, 4).len(),
+            MAX_CHUNKS_PER_NODE
+        );
+    }
+
+    /// Scores 1 on the first axis for texts mentioning "backoff".
+    struct KeywordEmbedder;
+
+    impl Embedder for KeywordEmbedder {
+        fn model_name(&self) -> &'static str {
+            "keyword"
+        }
+
+        fn embed(&mut self, text: &str) -> io::Result<Vec<f32>> {
+            let hit = if text.contains("backoff") { 1.0 } else { 0.0 };
+            Ok(l2_normalize(vec![hit, 1.0]))
+        }
+    }
+
+    #[test]
+    fn test_search_max_pools_body_chunks() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        crate::db::initialize_database(root).unwrap();
+        std::fs::write(
+            root.join("retry.ts"),
+            "export function alpha(x: number): number {\n    // exponential backoff with jitter\n    return x;\n}\n\nexport function beta(y: number): number {\n    return y;\n}\n",
+        )
+        .unwrap();
+        let cfg = crate::config::create_default_config(root);
+        crate::extraction::index_all(root, &cfg, false, None).unwrap();
+
+        let conn = crate::db::open_database(root).unwrap();
+        let mut vm = VectorManager::from_embedder(Box::new(KeywordEmbedder));
+        embed_missing_nodes(root, &conn, &mut vm).unwrap();
+
+        let chunk_count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM vector_chunks", [], |row| row.get(0))
+            .unwrap();
+        assert!(chunk_count >= 2, "functions should have body chunks");
+
+        let query = vm.embed_query("backoff").unwrap();
+        let results = search_similar(&conn, &query, 10, 0.0, None).unwrap();
+        let score = |name: &str| {
+            results
+                .iter()
+                .find(|r| r.node.name == name)
+                .map(|r| r.score)
+                .unwrap()
+        };
+        assert!((score("alpha") - 1.0).abs() < 1e-5);
+        assert!(score("beta") < 0.8);
+
+        let functions = search_similar(&conn, &query, 10, 0.0, Some(NodeKind::Function)).unwrap();
+        assert!(!functions.is_empty());
+        assert!(functions.iter().all(|r| r.node.kind == NodeKind::Function));
+        let classes = search_similar(&conn, &query, 10, 0.0, Some(NodeKind::Class)).unwrap();
+        assert!(classes.is_empty());
+    }
+
+    /// Same vectors as [`KeywordEmbedder`] under a different model name.
+    struct RenamedEmbedder;
+
+    impl Embedder for RenamedEmbedder {
+        fn model_name(&self) -> &'static str {
+            "renamed"
+        }
+
+        fn embed(&mut self, text: &str) -> io::Result<Vec<f32>> {
+            KeywordEmbedder.embed(text)
+        }
+    }
+
+    #[test]
+    fn test_mixed_model_index_is_flagged() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path();
+        crate::db::initialize_database(root).unwrap();
+        std::fs::write(
+            root.join("lib.ts"),
+            "export function alpha(x: number): number {\n    return x;\n}\n",
+        )
+        .unwrap();
+        let cfg = crate::config::create_default_config(root);
+        crate::extraction::index_all(root, &cfg, false, None).unwrap();
+
+        let conn = crate::db::open_database(root).unwrap();
+        let mut vm = VectorManager::from_embedder(Box::new(KeywordEmbedder));
+        let stored = embed_missing_nodes(root, &conn, &mut vm).unwrap();
+        assert!(stored > 0);
+
+        let models = vector_models(&conn).unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].model, "keyword");
+        assert_eq!(models[0].dimension, 2);
+        assert!(check_vector_models(&conn, "keyword", Some(2)).is_ok());
+        assert!(check_vector_models(&conn, "keyword", Some(768)).is_err());
+        let err = check_vector_models(&conn, "renamed", None).unwrap_err();
+        assert!(err.to_string().contains("coraline embed --rebuild"));
+
+        // A different model must not add vectors to the existing index.
+        conn.execute(
+            "DELETE FROM vectors WHERE rowid = (SELECT MIN(rowid) FROM vectors)",
+            [],
+        )
+        .unwrap();
+        let mut renamed = VectorManager::from_embedder(Box::new(RenamedEmbedder));
+        assert_eq!(embed_missing_nodes(root, &conn, &mut renamed).unwrap(), 0);
+
+        assert_eq!(clear_embeddings(&conn).unwrap(), stored - 1);
+        assert!(vector_models(&conn).unwrap().is_empty());
+        assert!(embed_missing_nodes(root, &conn, &mut renamed).unwrap() > 0);
+        assert!(check_vector_models(&conn, "renamed", Some(2)).is_ok());
+    }
+
+    /// [`KeywordEmbedder`] under a nomic model name.
+    struct NomicEmbedder;
+
+    impl Embedder for NomicEmbedder {
+        fn model_name(&self) -> &'static str {
+            "nomic-embed-text-v1.5"
+        }
+
+        fn embed(&mut self, text: &str) -> io::Result<Vec<f32>> {
+            KeywordEmbedder.embed(text)
+        }
+    }
+
+    #[test]
+    fn test_task_prefixes_only_for_nomic_models() {
+        let text = node_embed_text("parse", "config::parse", Some("Parse a file."), None);
+        assert_eq!(text, "parse | config::parse | Parse a file.");
+
+        let nomic = VectorManager::from_embedder(Box::new(NomicEmbedder));
+        assert_eq!(
+            nomic.document_text(&text),
+            "search_document: parse | config::parse | Parse a file."
+        );
+        assert_eq!(nomic.prefixed(QUERY_PREFIX, "parse"), "search_query: parse");
+
+        let other = VectorManager::from_embedder(Box::new(KeywordEmbedder));
+        assert_eq!(other.document_text(&text), text);
+        assert_eq!(other.prefixed(QUERY_PREFIX, "parse"), "parse");
+
+        assert!(uses_task_prefixes("nomic-ai/nomic-embed-text-v1.5"));
+        assert!(!uses_task_prefixes("text-embedding-3-small"));
+    }
 }
@@ -0,0 +1,451 @@
+#![forbid(unsafe_code)]
+
+//! Local graph explorer for `coraline viz`.
+//!
+//! Serves a single-page explorer (search box plus a canvas graph whose node
+//! neighbourhoods expand on click), its `GET /graph.js` renderer, and the
+//! JSON endpoints it is built on:
+//!
+//! - `GET /api/search?q=<text>[&kind=<node kind>][&limit=<n>]`
+//! - `GET /api/nodes/<id>`
+//! - `GET /api/nodes/<id>/neighbors[?depth=<n>][&direction=outgoing|incoming|both][&edge_kinds=calls,contains]`
+//!
+//! Requests are served one at a time on the calling thread over a single
+//! read-only use of the project database. Requests whose `Host` is not the
+//! bound address (or `localhost`), or whose `Origin` is not a local page,
+//! get 403, so a DNS-rebound page cannot read the graph.
+
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use rusqlite::Connection;
+use serde_json::{Value, json};
+use tracing::{debug, info};
+
+use crate::config::{self, SearchConfig};
+use crate::db;
+use crate::graph;
+use crate::mcp_http::origin_allowed;
+use crate::types::{EdgeKind, NodeKind, TraversalDirection, TraversalOptions};
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+const INDEX_HTML: &str = include_str!("viz/index.html");
+const GRAPH_JS: &str = include_str!("viz/graph.js");
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 200;
+const MAX_NEIGHBOR_DEPTH: usize = 3;
+const NEIGHBOR_NODE_LIMIT: usize = 200;
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+/// A rendered HTTP response, independent of the server implementation.
+#[derive(Debug, Clone)]
+pub struct VizResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl VizResponse {
+    pub(crate) fn json(status: u16, body: &Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+
+    pub(crate) fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, &json!({ "error": message.into() }))
+    }
+}
+
+/// Serve the explorer on `addr` (e.g. `127.0.0.1:7878`) until the process
+/// is interrupted.
+pub fn serve(project_root: &Path, addr: &str) -> std::io::Result<()> {
+    let conn = db::open_database(project_root)?;
+    let search = config::load_toml_config(project_root)
+        .unwrap_or_default()
+        .search;
+    info!(addr, "graph explorer listening");
+    serve_get(addr, |url| route(&conn, &search, url))
+}
+
+/// Answer GET requests on `addr` with `route` (given the path and query),
+/// one at a time, until the process is interrupted. Other methods get 405.
+pub(crate) fn serve_get(
+    addr: &str,
+    mut route: impl FnMut(&str) -> VizResponse,
+) -> std::io::Result<()> {
+    serve_http(addr, |method, url, _| {
+        if method == "GET" {
+            route(url)
+        } else {
+            VizResponse::error(405, "Only GET is supported")
+        }
+    })
+}
+
+/// Answer requests on `addr` with `handle` (given the method, the path and
+/// query, and the body), one at a time, until the process is interrupted.
+/// A foreign `Host` or `Origin` gets 403 before the body is read; bodies
+/// over `MAX_BODY_BYTES` get 413.
+pub(crate) fn serve_http(
+    addr: &str,
+    handle: impl FnMut(&str, &str, &str) -> VizResponse,
+) -> std::io::Result<()> {
+    let server = tiny_http::Server::http(addr).map_err(|e| std::io::Error::other(e.to_string()))?;
+    run_http(&server, handle);
+    Ok(())
+}
+
+fn run_http(server: &tiny_http::Server, mut handle: impl FnMut(&str, &str, &str) -> VizResponse) {
+    let local = server.server_addr().to_ip();
+    for mut request in server.incoming_requests() {
+        let host = request_header(&request, "Host");
+        let origin = request_header(&request, "Origin");
+        let response = if !host_allowed(host.as_deref(), local) {
+            VizResponse::error(403, "Host not allowed")
+        } else if !origin_allowed(origin.as_deref())
+            && !same_origin(origin.as_deref(), host.as_deref(), local)
+        {
+            VizResponse::error(403, "Origin not allowed")
+        } else {
+            let mut body = String::new();
+            match request
+                .as_reader()
+                .take(MAX_BODY_BYTES + 1)
+                .read_to_string(&mut body)
+            {
+                Ok(_) if body.len() as u64 > MAX_BODY_BYTES => {
+                    VizResponse::error(413, "Request body too large")
+                }
+                Ok(_) => handle(request.method().as_str(), request.url(), &body),
+                Err(err) => VizResponse::error(400, format!("Unreadable request body: {err}")),
+            }
+        };
+        debug!(
+            url = request.url(),
+            status = response.status,
+            "http request"
+        );
+
+        let mut http =
+            tiny_http::Response::from_string(response.body).with_status_code(response.status);
+        if let Ok(header) =
+            tiny_http::Header::from_bytes(&b"Content-Type"[..], response.content_type.as_bytes())
+        {
+            http = http.with_header(header);
+        }
+        if let Err(err) = request.respond(http) {
+            debug!(error = %err, "failed to write http response");
+        }
+    }
+}
+
+/// Whether `host` (a `Host` header) names the server at `local`: its own
+/// address or a loopback name, on its port. A server bound to an unspecified
+/// address (`0.0.0.0`, `::`) is meant to be reached by whatever name the
+/// network gives it, so any name on its port passes. Requests without one
+/// (HTTP/1.0) are let through; browsers always send it.
+fn host_allowed(host: Option<&str>, local: Option<SocketAddr>) -> bool {
+    let (Some(host), Some(local)) = (host, local) else {
+        return true;
+    };
+    let (name, port) = match host.strip_prefix('[') {
+        Some(rest) => match rest.split_once(']') {
+            Some((name, port)) => (name, port.strip_prefix(':')),
+            None => return false,
+        },
+        None => match host.rsplit_once(':') {
+            Some((name, port)) => (name, Some(port)),
+            None => (host, None),
+        },
+    };
+    let port_matches = port.map_or_else(
+        || local.port() == 80,
+        |port| port.parse::<u16>().ok() == Some(local.port()),
+    );
+    let name_matches = local.ip().is_unspecified()
+        || matches!(name, "localhost" | "127.0.0.1" | "::1")
+        || name.parse::<std::net::IpAddr>().ok() == Some(local.ip());
+    port_matches && name_matches
+}
+
+/// Whether `origin` is the page the server at `local` itself served under
+/// `host`. Only a server bound to an unspecified address trusts this: its
+/// pages are loaded from a network name, which then shows up as the
+/// `Origin` of their requests.
+fn same_origin(origin: Option<&str>, host: Option<&str>, local: Option<SocketAddr>) -> bool {
+    let (Some(origin), Some(host), Some(local)) = (origin, host, local) else {
+        return false;
+    };
+    local.ip().is_unspecified()
+        && origin
+            .split_once("://")
+            .is_some_and(|(_, authority)| authority.eq_ignore_ascii_case(host))
+}
+
+fn request_header(request: &tiny_http::Request, name: &str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str().to_string())
+}
+
+/// Dispatch a request for `url` (path plus optional query string).
+pub fn route(conn: &Connection, search: &SearchConfig, url: &str) -> VizResponse {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    match path {
+        "/" | "/index.html" => VizResponse {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: INDEX_HTML.to_string(),
+        },
+        "/graph.js" => VizResponse {
+            status: 200,
+            content_type: "application/javascript; charset=utf-8",
+            body: GRAPH_JS.to_string(),
+        },
+        "/api/search" => api_search(conn, search, query),
+        _ => {
+            let Some(rest) = path.strip_prefix("/api/nodes/") else {
+                return VizResponse::error(404, "Not found");
+            };
+            match rest.split_once('/') {
+                None => api_node(conn, &percent_decode(rest)),
+                Some((id, "neighbors")) => api_neighbors(conn, &percent_decode(id), query),
+                Some(_) => VizResponse::error(404, "Not found"),
+            }
+        }
+    }
+}
+
+/// `GET ?q=<text>[&kind=<node kind>][&limit=<n>]`: ranked symbol search.
+pub(crate) fn api_search(conn: &Connection, search: &SearchConfig, query: &str) -> VizResponse {
+    let Some(text) = query_param(query, "q").filter(|q| !q.trim().is_empty()) else {
+        return VizResponse::error(400, "Missing query parameter 'q'");
+    };
+    let kind = match query_param(query, "kind") {
+        Some(raw) => match parse_enum::<NodeKind>(&raw) {
+            Some(kind) => Some(kind),
+            None => return VizResponse::error(400, format!("Unknown node kind '{raw}'")),
+        },
+        None => None,
+    };
+    let limit = query_param(query, "limit")
+        .and_then(|raw| raw.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+
+    match db::search_nodes_with_config(conn, &text, kind, limit, search) {
+        Ok(results) => VizResponse::json(200, &json!({ "query": text, "results": results })),
+        Err(err) => VizResponse::error(500, format!("Search failed: {err}")),
+    }
+}
+
+pub(crate) fn api_node(conn: &Connection, id: &str) -> VizResponse {
+    match db::get_node_by_id(conn, id) {
+        Ok(Some(node)) => VizResponse::json(200, &json!(node)),
+        Ok(None) => VizResponse::error(404, format!("Node '{id}' not found")),
+        Err(err) => VizResponse::error(500, format!("Lookup failed: {err}")),
+    }
+}
+
+fn api_neighbors(conn: &Connection, id: &str, query: &str) -> VizResponse {
+    match db::get_node_by_id(conn, id) {
+        Ok(Some(_)) => {}
+        Ok(None) => return VizResponse::error(404, format!("Node '{id}' not found")),
+        Err(err) => return VizResponse::error(500, format!("Lookup failed: {err}")),
+    }
+
+    let depth = query_param(query, "depth")
+        .and_then(|raw| raw.parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, MAX_NEIGHBOR_DEPTH);
+    let direction = match query_param(query, "direction") {
+        Some(raw) => match parse_enum::<TraversalDirection>(&raw) {
+            Some(direction) => direction,
+            None => return VizResponse::error(400, format!("Unknown direction '{raw}'")),
+        },
+        None => TraversalDirection::Both,
+    };
+    let edge_kinds = match query_param(query, "edge_kinds").filter(|raw| !raw.is_empty()) {
+        Some(raw) => {
+            let mut kinds = Vec::new();
+            for part in raw.split(',') {
+                match parse_enum::<EdgeKind>(part.trim()) {
+                    Some(kind) => kinds.push(kind),
+                    None => return VizResponse::error(400, format!("Unknown edge kind '{part}'")),
+                }
+            }
+            Some(kinds)
+        }
+        None => None,
+    };
+
+    let options = TraversalOptions {
+        max_depth: Some(depth),
+        edge_kinds,
+        node_kinds: None,
+        direction: Some(direction),
+        limit: Some(NEIGHBOR_NODE_LIMIT),
+        include_start: Some(true),
+        edge_costs: None,
+        incoming_cost_factor: None,
+        min_confidence: None,
+    };
+
+    match graph::build_subgraph(conn, &[id.to_string()], &options) {
+        Ok(subgraph) => VizResponse::json(
+            200,
+            &json!({
+                "root": id,
+                "nodes": subgraph.nodes.values().collect::<Vec<_>>(),
+                "edges": subgraph.edges,
+            }),
+        ),
+        Err(err) => VizResponse::error(500, format!("Traversal failed: {err}")),
+    }
+}
+
+/// Parse a `snake_case` enum value the way it is serialised.
+pub(crate) fn parse_enum<T: serde::de::DeserializeOwned>(raw: &str) -> Option<T> {
+    serde_json::from_value(Value::String(raw.to_string())).ok()
+}
+
+/// First value of `key` in a URL query string, percent-decoded.
+pub(crate) fn query_param(query: &str, key: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| percent_decode(&v.replace('+', " ")))
+}
+
+pub(crate) fn percent_decode(raw: &str) -> String {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut rest = raw.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%'
+            && let Some((hex, after)) = tail.split_at_checked(2)
+            && let Ok(hex) = std::str::from_utf8(hex)
+            && let Ok(decoded) = u8::from_str_radix(hex, 16)
+        {
+            out.push(decoded);
+            rest = after;
+            continue;
+        }
+        out.push(byte);
+        rest = tail;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::expect_used)]
+
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+
+    use super::{VizResponse, host_allowed, percent_decode, query_param, run_http, same_origin};
+
+    /// Send a raw GET with the given headers and return the status code.
+    fn status_for(addr: SocketAddr, headers: &str) -> u16 {
+        let mut stream = TcpStream::connect(addr).expect("Failed to connect");
+        write!(
+            stream,
+            "GET / HTTP/1.1\r\n{headers}Connection: close\r\n\r\n"
+        )
+        .expect("Failed to send request");
+        let mut reply = String::new();
+        stream
+            .read_to_string(&mut reply)
+            .expect("Failed to read reply");
+        reply
+            .split(' ')
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .expect("Reply should have a status line")
+    }
+
+    #[test]
+    fn query_param_decodes_values() {
+        let query = "q=get%20user&kind=function&edge_kinds=calls%2Ccontains&x=a+b";
+        assert_eq!(query_param(query, "q").as_deref(), Some("get user"));
+        assert_eq!(query_param(query, "kind").as_deref(), Some("function"));
+        assert_eq!(
+            query_param(query, "edge_kinds").as_deref(),
+            Some("calls,contains")
+        );
+        assert_eq!(query_param(query, "x").as_deref(), Some("a b"));
+        assert_eq!(query_param(query, "missing"), None);
+    }
+
+    #[test]
+    fn host_allowed_only_for_the_bound_address() {
+        let local = "127.0.0.1:7878".parse().ok();
+        assert!(host_allowed(None, local));
+        assert!(host_allowed(Some("127.0.0.1:7878"), local));
+        assert!(host_allowed(Some("localhost:7878"), local));
+        assert!(host_allowed(Some("[::1]:7878"), local));
+        assert!(!host_allowed(Some("localhost:9999"), local));
+        assert!(!host_allowed(Some("evil.example:7878"), local));
+        assert!(!host_allowed(Some("localhost"), local));
+
+        let any = "0.0.0.0:7878".parse().ok();
+        assert!(host_allowed(Some("192.168.1.20:7878"), any));
+        assert!(host_allowed(Some("devbox.lan:7878"), any));
+        assert!(!host_allowed(Some("devbox.lan:9999"), any));
+    }
+
+    #[test]
+    fn same_origin_only_on_an_unspecified_address() {
+        let any = "0.0.0.0:7878".parse().ok();
+        let host = Some("devbox.lan:7878");
+        assert!(same_origin(Some("http://devbox.lan:7878"), host, any));
+        assert!(!same_origin(Some("http://evil.example"), host, any));
+        assert!(!same_origin(None, host, any));
+        let local = "127.0.0.1:7878".parse().ok();
+        assert!(!same_origin(Some("http://devbox.lan:7878"), host, local));
+    }
+
+    #[test]
+    fn server_rejects_foreign_host_and_origin() {
+        let server = tiny_http::Server::http("127.0.0.1:0").expect("Failed to bind");
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .expect("Server should have an IP address");
+        std::thread::spawn(move || {
+            run_http(&server, |_, _, _| {
+                VizResponse::json(200, &serde_json::json!({}))
+            });
+        });
+
+        let host = format!("Host: {addr}\r\n");
+        assert_eq!(status_for(addr, &host), 200);
+        assert_eq!(
+            status_for(addr, &format!("Host: evil.example:{}\r\n", addr.port())),
+            403
+        );
+        assert_eq!(
+            status_for(addr, &format!("{host}Origin: https://evil.example\r\n")),
+            403
+        );
+        assert_eq!(
+            status_for(addr, &format!("{host}Origin: http://localhost:3000\r\n")),
+            200
+        );
+    }
+
+    #[test]
+    fn percent_decode_keeps_malformed_escapes() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
@@ -0,0 +1,135 @@
+// A small canvas graph view for the explorer page: nodes, directed edges, a
+// force-directed layout, kind filtering, and click-to-select. It is served
+// by the embedded server, so the page needs no network access.
+"use strict";
+
+class GraphView {
+  constructor(container) {
+    this.nodes = new Map();
+    this.edges = new Map();
+    this.hidden = () => false;
+    this.onTap = () => {};
+    this.canvas = document.createElement("canvas");
+    this.canvas.style.cssText = "display: block; width: 100%; height: 100%";
+    container.appendChild(this.canvas);
+    this.ctx = this.canvas.getContext("2d");
+    new ResizeObserver(() => this.resize()).observe(container);
+    this.canvas.addEventListener("click", evt => {
+      const node = this.nodeAt(evt.offsetX, evt.offsetY);
+      if (node) this.onTap(node);
+    });
+    this.resize();
+  }
+
+  addNode(id, label, kind, data) {
+    if (this.nodes.has(id)) return;
+    const w = this.canvas.clientWidth, h = this.canvas.clientHeight;
+    this.nodes.set(id, { id, label, kind, data, root: false, x: w / 2 + (Math.random() - 0.5) * 200, y: h / 2 + (Math.random() - 0.5) * 200 });
+  }
+
+  addEdge(id, source, target, kind) {
+    if (this.edges.has(id) || !this.nodes.has(source) || !this.nodes.has(target)) return;
+    this.edges.set(id, { source, target, kind });
+  }
+
+  setRoot(id) {
+    const node = this.nodes.get(id);
+    if (node) node.root = true;
+  }
+
+  setHidden(hidden) {
+    this.hidden = node => hidden(node.kind);
+    this.draw();
+  }
+
+  clear() {
+    this.nodes.clear();
+    this.edges.clear();
+    this.draw();
+  }
+
+  visible() {
+    return [...this.nodes.values()].filter(n => !this.hidden(n));
+  }
+
+  visibleEdges() {
+    return [...this.edges.values()].filter(e => !this.hidden(this.nodes.get(e.source)) && !this.hidden(this.nodes.get(e.target)));
+  }
+
+  // Fruchterman–Reingold, run to rest before drawing.
+  layout() {
+    const nodes = this.visible();
+    const edges = this.visibleEdges();
+    const w = this.canvas.clientWidth, h = this.canvas.clientHeight;
+    const k = Math.sqrt((w * h) / Math.max(nodes.length, 1)) * 0.6;
+    let temperature = w / 10;
+    for (let step = 0; step < 300; step++) {
+      for (const n of nodes) { n.dx = 0; n.dy = 0; }
+      for (let i = 0; i < nodes.length; i++) {
+        for (let j = i + 1; j < nodes.length; j++) {
+          const a = nodes[i], b = nodes[j];
+          const dx = a.x - b.x || 0.01, dy = a.y - b.y || 0.01;
+          const dist = Math.hypot(dx, dy);
+          const force = (k * k) / dist;
+          a.dx += (dx / dist) * force; a.dy += (dy / dist) * force;
+          b.dx -= (dx / dist) * force; b.dy -= (dy / dist) * force;
+        }
+      }
+      for (const e of edges) {
+        const a = this.nodes.get(e.source), b = this.nodes.get(e.target);
+        const dx = a.x - b.x, dy = a.y - b.y;
+        const dist = Math.hypot(dx, dy) || 0.01;
+        const force = (dist * dist) / k;
+        a.dx -= (dx / dist) * force; a.dy -= (dy / dist) * force;
+        b.dx += (dx / dist) * force; b.dy += (dy / dist) * force;
+      }
+      for (const n of nodes) {
+        const len = Math.hypot(n.dx, n.dy) || 1;
+        n.x = Math.min(w - 20, Math.max(20, n.x + (n.dx / len) * Math.min(len, temperature)));
+        n.y = Math.min(h - 20, Math.max(20, n.y + (n.dy / len) * Math.min(len, temperature)));
+      }
+      temperature *= 0.98;
+    }
+    this.draw();
+  }
+
+  resize() {
+    const ratio = window.devicePixelRatio || 1;
+    this.canvas.width = this.canvas.clientWidth * ratio;
+    this.canvas.height = this.canvas.clientHeight * ratio;
+    this.ctx.setTransform(ratio, 0, 0, ratio, 0, 0);
+    this.draw();
+  }
+
+  nodeAt(x, y) {
+    return this.visible().find(n => Math.hypot(n.x - x, n.y - y) <= 10);
+  }
+
+  draw() {
+    const ctx = this.ctx;
+    ctx.clearRect(0, 0, this.canvas.clientWidth, this.canvas.clientHeight);
+    ctx.lineWidth = 1.5;
+    ctx.font = "8px system-ui, sans-serif";
+    for (const e of this.visibleEdges()) {
+      const a = this.nodes.get(e.source), b = this.nodes.get(e.target);
+      const angle = Math.atan2(b.y - a.y, b.x - a.x);
+      const tipX = b.x - Math.cos(angle) * 9, tipY = b.y - Math.sin(angle) * 9;
+      ctx.strokeStyle = ctx.fillStyle = "#bbb";
+      ctx.beginPath(); ctx.moveTo(a.x, a.y); ctx.lineTo(tipX, tipY); ctx.stroke();
+      ctx.beginPath();
+      ctx.moveTo(tipX, tipY);
+      ctx.lineTo(tipX - Math.cos(angle - 0.4) * 7, tipY - Math.sin(angle - 0.4) * 7);
+      ctx.lineTo(tipX - Math.cos(angle + 0.4) * 7, tipY - Math.sin(angle + 0.4) * 7);
+      ctx.fill();
+      ctx.fillStyle = "#888";
+      ctx.fillText(e.kind, (a.x + b.x) / 2, (a.y + b.y) / 2);
+    }
+    ctx.font = "10px system-ui, sans-serif";
+    for (const n of this.visible()) {
+      ctx.fillStyle = n.root ? "#d9822b" : "#4a7ebb";
+      ctx.beginPath(); ctx.arc(n.x, n.y, 9, 0, 2 * Math.PI); ctx.fill();
+      ctx.fillStyle = "#222";
+      ctx.fillText(n.label, n.x + 11, n.y + 4);
+    }
+  }
+}
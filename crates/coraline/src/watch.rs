@@ -0,0 +1,120 @@
+#![forbid(unsafe_code)]
+
+//! Continuous sync from filesystem events.
+//!
+//! `coraline watch`, and the MCP server when `[sync] watch_mode` is set,
+//! subscribe to change events for the whole project and run an incremental
+//! sync once `[sync] debounce_ms` pass without a new relevant event, so a
+//! save, a branch switch, or a formatter run over many files costs one
+//! sync. Events under `.coraline/` and `.git/`, in excluded paths, or for
+//! files no `include` pattern matches are ignored.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tracing::{debug, warn};
+
+use crate::annotations::CODEOWNERS_PATHS;
+use crate::config;
+use crate::extraction::{self, SyncResult};
+use crate::packages::MANIFEST_FILES;
+use crate::types::CodeGraphConfig;
+
+/// How often a quiet watcher checks for shutdown.
+const SHUTDOWN_POLL: Duration = Duration::from_millis(500);
+
+/// The project configuration a sync runs with, as `coraline sync` loads it.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the project configuration cannot be read.
+pub fn sync_config(project_root: &Path) -> std::io::Result<CodeGraphConfig> {
+    config::load_config(project_root)
+}
+
+/// Whether a change to `relative_path` (forward slashes) can affect the
+/// graph. Paths without an extension count, since a removed or renamed
+/// directory is reported only by its own path.
+pub fn is_relevant(relative_path: &str, config: &CodeGraphConfig) -> bool {
+    let first = relative_path.split('/').next().unwrap_or_default();
+    if first == ".coraline" || first == ".git" {
+        return false;
+    }
+    if extraction::is_excluded(relative_path, config) {
+        return false;
+    }
+    extraction::should_include_file(relative_path, config)
+        || CODEOWNERS_PATHS.contains(&relative_path)
+        || relative_path
+            .rsplit('/')
+            .next()
+            .is_some_and(|name| MANIFEST_FILES.contains(&name))
+        || Path::new(relative_path).extension().is_none()
+}
+
+fn is_relevant_event(project_root: &Path, event: &Event, config: &CodeGraphConfig) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    event.paths.iter().any(|path| {
+        path.strip_prefix(project_root).is_ok_and(|rel| {
+            let rel = rel
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            !rel.is_empty() && is_relevant(&rel, config)
+        })
+    })
+}
+
+/// Watch `project_root` and sync after each burst of relevant changes,
+/// passing every outcome to `on_sync`, until `shutdown` is set.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the configuration cannot be loaded or the
+/// project cannot be watched.
+pub fn watch(
+    project_root: &Path,
+    debounce: Duration,
+    shutdown: &AtomicBool,
+    mut on_sync: impl FnMut(&crate::error::Result<SyncResult>),
+) -> std::io::Result<()> {
+    let project_root = project_root.canonicalize()?;
+    let cfg = sync_config(&project_root)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(std::io::Error::other)?;
+    watcher
+        .watch(&project_root, RecursiveMode::Recursive)
+        .map_err(std::io::Error::other)?;
+    debug!(root = %project_root.display(), "watching for changes");
+
+    // When the pending sync is due: `debounce` after the last relevant event.
+    let mut due: Option<Instant> = None;
+    while !shutdown.load(Ordering::Relaxed) {
+        if due.is_some_and(|at| at <= Instant::now()) {
+            due = None;
+            on_sync(&extraction::sync(&project_root, &cfg, None));
+            continue;
+        }
+        let timeout = due.map_or(SHUTDOWN_POLL, |at| {
+            at.saturating_duration_since(Instant::now())
+        });
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(event)) => {
+                if is_relevant_event(&project_root, &event, &cfg) {
+                    due = Some(Instant::now() + debounce);
+                }
+            }
+            Ok(Err(err)) => warn!(error = %err, "filesystem watch error"),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Ok(())
+}
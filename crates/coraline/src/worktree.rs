@@ -0,0 +1,307 @@
+#![forbid(unsafe_code)]
+
+//! Git worktree and submodule detection, and the shared parse cache.
+//!
+//! Linked worktrees (`git worktree add`) of one repository share a git
+//! common directory. When a project has linked worktrees, `index` and `sync`
+//! keep a content-addressed cache of extraction results in
+//! `<git-common-dir>/coraline/parse-cache/`, so a file that another worktree
+//! already parsed is stored without running tree-sitter again. Each
+//! worktree keeps its own `.coraline/coraline.db`; the cache lives outside
+//! every database and is shared by all of them.
+//!
+//! Entries are keyed by the file's path, its content hash, and the coraline
+//! version, so an upgrade starts a fresh cache and a branch that changed a
+//! file gets its own entry. Writes go through a temporary file and a rename,
+//! so worktrees indexing in parallel never read a partial entry.
+//!
+//! Submodules are read from `.gitmodules`. They are indexed with the
+//! project unless `[indexing] index_submodules` is off, and each one's files
+//! carry a `project:<name>` tag.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::config;
+use crate::types::{Edge, Node, UnresolvedReference};
+use crate::utils::hash_sha256;
+
+/// Directory under the git common directory holding coraline's shared state.
+const SHARED_DIR: &str = "coraline";
+const CACHE_DIR: &str = "parse-cache";
+
+/// A git working tree of the project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Worktree {
+    /// Root of the working tree.
+    pub root: PathBuf,
+    /// The repository's shared git directory (the main worktree's `.git`).
+    pub git_common_dir: PathBuf,
+    /// Created by `git worktree add` rather than the main checkout.
+    pub is_linked: bool,
+}
+
+/// Detect the worktree rooted at `project_root` from its `.git` entry: a
+/// directory for the main worktree, a `gitdir:` file for a linked one.
+/// `None` outside a git checkout or below its root.
+pub fn detect(project_root: &Path) -> Option<Worktree> {
+    let dot_git = project_root.join(".git");
+    let meta = fs::metadata(&dot_git).ok()?;
+    if meta.is_dir() {
+        return Some(Worktree {
+            root: project_root.to_path_buf(),
+            git_common_dir: fs::canonicalize(&dot_git).unwrap_or(dot_git),
+            is_linked: false,
+        });
+    }
+
+    let git_dir = read_gitdir_file(&dot_git, project_root)?;
+    // `<common>/worktrees/<name>/commondir` points back at the common dir.
+    let common = fs::read_to_string(git_dir.join("commondir")).ok()?;
+    let common = git_dir.join(common.trim());
+    Some(Worktree {
+        root: project_root.to_path_buf(),
+        git_common_dir: fs::canonicalize(&common).unwrap_or(common),
+        is_linked: true,
+    })
+}
+
+/// Every worktree of the repository `project_root` belongs to.
+///
+/// The main checkout comes first, then the linked worktrees sorted by
+/// path. Linked worktrees whose directory was deleted without
+/// `git worktree prune` are skipped.
+pub fn list(project_root: &Path) -> Vec<Worktree> {
+    let Some(current) = detect(project_root) else {
+        return Vec::new();
+    };
+    let common = current.git_common_dir;
+    let mut worktrees = Vec::new();
+
+    if common.file_name().is_some_and(|name| name == ".git")
+        && let Some(main_root) = common.parent()
+    {
+        worktrees.push(Worktree {
+            root: main_root.to_path_buf(),
+            git_common_dir: common.clone(),
+            is_linked: false,
+        });
+    }
+
+    let mut linked: Vec<Worktree> = fs::read_dir(common.join("worktrees"))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            // `gitdir` holds the path of the linked worktree's `.git` file.
+            let dot_git = fs::read_to_string(entry.path().join("gitdir")).ok()?;
+            let root = Path::new(dot_git.trim()).parent()?.to_path_buf();
+            root.join(".git").is_file().then(|| Worktree {
+                root,
+                git_common_dir: common.clone(),
+                is_linked: true,
+            })
+        })
+        .collect();
+    linked.sort_by(|a, b| a.root.cmp(&b.root));
+    worktrees.extend(linked);
+    worktrees
+}
+
+/// The git directory that holds the hooks and config of the checkout at
+/// `project_root`: `.git` itself, the common directory of a linked
+/// worktree, or the `gitdir:` target of a submodule.
+pub fn git_dir(project_root: &Path) -> Option<PathBuf> {
+    if let Some(worktree) = detect(project_root) {
+        return Some(worktree.git_common_dir);
+    }
+    read_gitdir_file(&project_root.join(".git"), project_root).filter(|dir| dir.is_dir())
+}
+
+/// A git submodule declared in `.gitmodules`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Submodule {
+    pub name: String,
+    /// Forward-slash path relative to the project root.
+    pub path: String,
+}
+
+/// The submodules of `project_root` that are checked out, in `.gitmodules`
+/// order.
+pub fn submodules(project_root: &Path) -> Vec<Submodule> {
+    let Ok(text) = fs::read_to_string(project_root.join(".gitmodules")) else {
+        return Vec::new();
+    };
+    let mut submodules = Vec::new();
+    let mut name: Option<String> = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix("[submodule") {
+            name = Some(
+                header
+                    .trim_end_matches(']')
+                    .trim()
+                    .trim_matches('"')
+                    .to_string(),
+            );
+        } else if line.starts_with('[') {
+            name = None;
+        } else if let (Some(name), Some((key, value))) = (&name, line.split_once('='))
+            && key.trim() == "path"
+        {
+            let path = value.trim().trim_matches('"').trim_end_matches('/');
+            if project_root.join(path).join(".git").exists() {
+                submodules.push(Submodule {
+                    name: name.clone(),
+                    path: path.replace('\\', "/"),
+                });
+            }
+        }
+    }
+    submodules
+}
+
+fn read_gitdir_file(dot_git: &Path, project_root: &Path) -> Option<PathBuf> {
+    let text = fs::read_to_string(dot_git).ok()?;
+    let target = text.lines().find_map(|l| l.strip_prefix("gitdir:"))?.trim();
+    let path = project_root.join(target);
+    Some(fs::canonicalize(&path).unwrap_or(path))
+}
+
+/// Extraction output for one file, as stored in the parse cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedParse {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    pub unresolved_refs: Vec<UnresolvedReference>,
+}
+
+/// Size of the shared parse cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+/// Content-addressed cache of extraction results shared by the worktrees of
+/// one repository.
+#[derive(Debug, Clone)]
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    /// Cache rooted at `dir`.
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache for `project_root`, or `None` when it is off: outside a
+    /// git checkout, in a repository with neither linked worktrees nor
+    /// `[indexing] branch_graphs`, or with `[indexing] shared_parse_cache =
+    /// false`.
+    pub fn for_project(project_root: &Path) -> Option<Self> {
+        let indexing = config::load_toml_config(project_root)
+            .map(|cfg| cfg.indexing)
+            .unwrap_or_default();
+        if !indexing.shared_parse_cache {
+            return None;
+        }
+        let worktrees = list(project_root);
+        if !indexing.branch_graphs && !worktrees.iter().any(|w| w.is_linked) {
+            return None;
+        }
+        let common = &worktrees.first()?.git_common_dir;
+        Some(Self::at(shared_cache_dir(common)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The cached extraction of `relative_path` with this content hash.
+    pub fn get(&self, relative_path: &str, content_hash: &str) -> Option<CachedParse> {
+        let path = self.entry_path(relative_path, content_hash);
+        let bytes = fs::read(&path).ok()?;
+        match serde_json::from_slice(&bytes) {
+            Ok(parsed) => Some(parsed),
+            Err(err) => {
+                debug!(entry = %path.display(), error = %err, "ignoring unreadable parse cache entry");
+                None
+            }
+        }
+    }
+
+    /// Store an extraction, replacing any earlier entry. Best-effort: a
+    /// failed write only costs a later re-parse.
+    pub fn put(&self, relative_path: &str, content_hash: &str, parsed: &CachedParse) {
+        let path = self.entry_path(relative_path, content_hash);
+        let Ok(bytes) = serde_json::to_vec(parsed) else {
+            return;
+        };
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        let written = fs::create_dir_all(parent)
+            .and_then(|()| fs::write(&tmp, bytes))
+            .and_then(|()| fs::rename(&tmp, &path));
+        if let Err(err) = written {
+            let _ = fs::remove_file(&tmp);
+            debug!(entry = %path.display(), error = %err, "failed to write parse cache entry");
+        }
+    }
+
+    /// Entry count and total size.
+    pub fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats::default();
+        for (_, meta) in self.entries() {
+            stats.entries += 1;
+            stats.bytes += meta.len();
+        }
+        stats
+    }
+
+    /// Delete entries not written within `max_age`; `None` deletes all.
+    /// Returns how many were removed.
+    pub fn prune(&self, max_age: Option<Duration>) -> usize {
+        let cutoff = max_age.and_then(|age| SystemTime::now().checked_sub(age));
+        let mut removed = 0;
+        for (path, meta) in self.entries() {
+            let stale = cutoff.is_none_or(|cutoff| meta.modified().is_ok_and(|m| m < cutoff));
+            if stale && fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    fn entries(&self) -> Vec<(PathBuf, fs::Metadata)> {
+        fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .flat_map(|shard| fs::read_dir(shard.path()).into_iter().flatten())
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| Some((entry.path(), entry.metadata().ok()?)))
+            .collect()
+    }
+
+    fn entry_path(&self, relative_path: &str, content_hash: &str) -> PathBuf {
+        let key = hash_sha256(&format!(
+            "{}\0{relative_path}\0{content_hash}",
+            env!("CARGO_PKG_VERSION")
+        ));
+        self.dir.join(&key[..2]).join(format!("{key}.json"))
+    }
+}
+
+/// Where the parse cache of the repository with this git common directory
+/// lives.
+pub fn shared_cache_dir(git_common_dir: &Path) -> PathBuf {
+    git_common_dir.join(SHARED_DIR).join(CACHE_DIR)
+}
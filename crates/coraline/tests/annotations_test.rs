@@ -0,0 +1,297 @@
+//! Integration tests for annotations and CODEOWNERS import
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::annotations::{self, Annotations};
+use coraline::tools::responses::{ImpactResponse, OwnersResponse, SearchResponse};
+use coraline::types::{
+    Annotation, AnnotationSource, BuildContextOptions, ContextFormat, Node, NodeKind, TaskContext,
+};
+use coraline::{config, context, db, extraction, tools};
+use serde_json::json;
+
+fn node(project: &Path, file: &str, name: &str) -> Node {
+    let conn = db::open_database(project).expect("Failed to open database");
+    db::get_nodes_by_file(&conn, file, None)
+        .expect("Failed to read nodes")
+        .into_iter()
+        .find(|n| n.name == name && n.kind != NodeKind::Export)
+        .expect("the symbol should be indexed in the file")
+}
+
+fn user_tag(target: &str, symbol: bool, tag: &str) -> Annotation {
+    Annotation {
+        target: target.to_string(),
+        symbol,
+        tag: tag.to_string(),
+        source: AnnotationSource::User,
+        position: 0,
+    }
+}
+
+#[test]
+fn test_codeowners_last_matching_rule_wins() {
+    let rules = annotations::parse_codeowners(
+        "# Default owners\n* @everyone\n\n/src/user.ts @users-team @lead # reviewers\nsrc/math.ts\n",
+    );
+    let tags: Vec<(&str, &str)> = rules
+        .iter()
+        .map(|a| (a.target.as_str(), a.tag.as_str()))
+        .collect();
+    assert_eq!(
+        tags,
+        [
+            ("**/*", "owner:@everyone"),
+            ("src/user.ts", "owner:@users-team"),
+            ("src/user.ts", "owner:@lead"),
+            ("src/math.ts", ""),
+        ]
+    );
+
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+    std::fs::create_dir_all(project.join(".github")).expect("Failed to create .github");
+    std::fs::write(
+        project.join(".github/CODEOWNERS"),
+        "* @everyone\n/src/user.ts @users-team\nsrc/math.ts\n",
+    )
+    .expect("Failed to write CODEOWNERS");
+
+    let mut conn = db::open_database(project).expect("Failed to open database");
+    let (_, imported) = annotations::import_codeowners(project, &mut conn)
+        .expect("Failed to import CODEOWNERS")
+        .expect("CODEOWNERS should be found");
+    assert_eq!(imported, 3);
+
+    let loaded = Annotations::load(&conn).expect("Failed to load annotations");
+    assert_eq!(
+        loaded.tags_for(&node(project, "src/user.ts", "createUser")),
+        ["owner:@users-team"]
+    );
+    assert_eq!(
+        loaded.tags_for(&node(project, "src/index.ts", "quickMath")),
+        ["owner:@everyone"]
+    );
+    // An owner-less rule un-assigns the file.
+    assert!(
+        loaded
+            .tags_for(&node(project, "src/math.ts", "multiply"))
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_tags_filter_search_and_appear_in_context() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+    let mut conn = db::open_database(project).expect("Failed to open database");
+    db::add_annotations(
+        &mut conn,
+        &[
+            user_tag("src/math.ts", false, "domain:math"),
+            user_tag("UserService", true, "stability:experimental"),
+        ],
+    )
+    .expect("Failed to add annotations");
+
+    let loaded = Annotations::load(&conn).expect("Failed to load annotations");
+    // A symbol's tags apply to its members.
+    assert_eq!(
+        loaded.tags_for(&node(project, "src/user.ts", "createUser")),
+        ["stability:experimental"]
+    );
+
+    let registry = tools::create_default_registry(project);
+    let result = registry
+        .execute("coraline_search", json!({ "query": "add", "tag": "math" }))
+        .expect("coraline_search failed");
+    let search: SearchResponse = serde_json::from_value(result).expect("valid response");
+    assert!(!search.results.is_empty());
+    assert!(
+        search
+            .results
+            .iter()
+            .all(|r| r.node.file_path == "src/math.ts")
+    );
+    assert!(
+        search
+            .results
+            .iter()
+            .all(|r| r.node.tags == ["domain:math"])
+    );
+
+    let options = BuildContextOptions {
+        max_nodes: Some(5),
+        max_code_blocks: Some(0),
+        max_code_block_size: None,
+        include_code: Some(false),
+        format: Some(ContextFormat::Json),
+        search_limit: None,
+        traversal_depth: Some(0),
+        min_score: None,
+    };
+    let built =
+        context::build_context(project, "multiply", &options).expect("Failed to build context");
+    let ctx: TaskContext = serde_json::from_str(&built).expect("context should be JSON");
+    let multiply = ctx
+        .entry_points
+        .iter()
+        .find(|n| n.name == "multiply")
+        .expect("multiply should be an entry point");
+    assert_eq!(
+        ctx.tags.get(&multiply.id),
+        Some(&vec!["domain:math".to_string()])
+    );
+
+    assert_eq!(
+        db::remove_annotations(&conn, "src/math.ts", false, &[]).expect("Failed to remove"),
+        1
+    );
+}
+
+#[test]
+fn test_indexing_imports_codeowners_for_owners_and_impact() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+    std::fs::write(
+        project.join("CODEOWNERS"),
+        "/src/math.ts @math-team\n/src/index.ts @app-team @lead\n",
+    )
+    .expect("Failed to write CODEOWNERS");
+    let cfg = config::create_default_config(project);
+    extraction::sync(project, &cfg, None).expect("Failed to sync project");
+
+    let registry = tools::create_default_registry(project);
+    let result = registry
+        .execute("coraline_owners", json!({ "path": "src/math.ts" }))
+        .expect("coraline_owners failed");
+    let owners: OwnersResponse = serde_json::from_value(result).expect("valid response");
+    assert_eq!(owners.owners, ["@math-team"]);
+    let impacted = owners.impacted.expect("impact should be included");
+    let app = impacted
+        .iter()
+        .find(|g| g.owner == "@app-team")
+        .expect("index.ts depends on math.ts");
+    assert_eq!(app.files, ["src/index.ts"]);
+    assert!(impacted.iter().any(|g| g.owner == "@lead"));
+    assert!(owners.unowned_files.is_empty());
+
+    let multiply = node(project, "src/math.ts", "multiply").id;
+    let result = registry
+        .execute("coraline_impact", json!({ "node_id": multiply }))
+        .expect("coraline_impact failed");
+    let impact: ImpactResponse = serde_json::from_value(result).expect("valid response");
+    assert!(impact.owners.iter().any(|g| g.owner == "@math-team"));
+    assert!(impact.owners.iter().any(|g| g.owner == "@app-team"));
+
+    // Deleting CODEOWNERS drops its owners on the next sync.
+    std::fs::remove_file(project.join("CODEOWNERS")).expect("Failed to remove CODEOWNERS");
+    extraction::sync(project, &cfg, None).expect("Failed to sync project");
+    let result = registry
+        .execute(
+            "coraline_owners",
+            json!({ "name": "multiply", "file_path": "src/math.ts", "impact": false }),
+        )
+        .expect("coraline_owners failed");
+    let owners: OwnersResponse = serde_json::from_value(result).expect("valid response");
+    assert!(owners.owners.is_empty());
+    assert!(owners.impacted.is_none());
+}
+
+#[test]
+fn test_cli_annotate_and_query_by_tag() {
+    let temp_dir = common::setup_indexed_project();
+    let path = temp_dir.path().to_str().expect("utf-8 path");
+
+    common::coraline(&[
+        "annotate",
+        "-p",
+        path,
+        "add",
+        "src/user.ts",
+        "payments",
+        "owner:@alice",
+    ]);
+    let listed = common::coraline(&["annotate", "-p", path, "list"]);
+    let listed = String::from_utf8_lossy(&listed.stdout);
+    assert!(
+        listed.contains("path src/user.ts  owner:@alice"),
+        "{listed}"
+    );
+
+    let tagged = common::coraline(&["query", "-p", path, "User", "--tag", "payments"]);
+    let tagged = String::from_utf8_lossy(&tagged.stdout);
+    assert!(tagged.contains("src/user.ts"), "{tagged}");
+    assert!(tagged.contains("tags: owner:@alice, payments"), "{tagged}");
+    assert!(!tagged.contains("src/index.ts"), "{tagged}");
+
+    common::coraline(&["annotate", "-p", path, "remove", "src/user.ts", "payments"]);
+    let none = common::coraline(&["query", "-p", path, "User", "--tag", "payments"]);
+    assert!(String::from_utf8_lossy(&none.stdout).contains("No results found"));
+}
+
+#[test]
+fn test_workspace_packages_scope_search_and_impact() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+    std::fs::write(
+        project.join("package.json"),
+        r#"{ "private": true, "workspaces": ["src", "tools/*"] }"#,
+    )
+    .expect("Failed to write package.json");
+    std::fs::write(project.join("src/package.json"), r#"{ "name": "core" }"#)
+        .expect("Failed to write package.json");
+    let cfg = config::create_default_config(project);
+    extraction::sync(project, &cfg, None).expect("Failed to sync project");
+
+    let registry = tools::create_default_registry(project);
+    let search = |package: &str| -> SearchResponse {
+        let result = registry
+            .execute(
+                "coraline_search",
+                json!({ "query": "multiply", "package": package }),
+            )
+            .expect("coraline_search failed");
+        serde_json::from_value(result).expect("valid response")
+    };
+    let core = search("core");
+    assert!(core.count > 0);
+    assert!(
+        core.results
+            .iter()
+            .all(|hit| hit.node.tags.contains(&"package:core".to_string()))
+    );
+    assert_eq!(search("other").count, 0);
+
+    let multiply = node(project, "src/math.ts", "multiply").id;
+    let impact = |params: serde_json::Value| -> ImpactResponse {
+        let result = registry
+            .execute("coraline_impact", params)
+            .expect("coraline_impact failed");
+        serde_json::from_value(result).expect("valid response")
+    };
+    let all = impact(json!({ "node_id": multiply }));
+    assert!(all.stats.node_count > 1);
+    assert_eq!(all.packages.len(), 1);
+    let package = all.packages.first().expect("one package");
+    assert_eq!(package.package, "core");
+    assert_eq!(package.nodes, all.stats.node_count);
+    let scoped = impact(json!({ "node_id": multiply, "package": "other" }));
+    assert_eq!(scoped.stats.node_count, 1, "only the target remains");
+    assert!(scoped.edges.is_empty());
+
+    let path = project.to_str().expect("utf-8 path");
+    let listed = common::coraline(&["annotate", "-p", path, "list"]);
+    let listed = String::from_utf8_lossy(&listed.stdout);
+    assert!(
+        listed.contains("path src  package:core (package)"),
+        "{listed}"
+    );
+    let queried = common::coraline(&["query", "-p", path, "multiply", "--package", "core"]);
+    assert!(String::from_utf8_lossy(&queried.stdout).contains("src/math.ts"));
+    let none = common::coraline(&["query", "-p", path, "multiply", "--package", "other"]);
+    assert!(String::from_utf8_lossy(&none.stdout).contains("No results found"));
+}
@@ -0,0 +1,151 @@
+//! Integration tests for architecture rules and `coraline analyze arch`
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use coraline::config::{self, ArchitectureRule};
+use serde_json::Value;
+
+const PROJECT_FILES: &[(&str, &str)] = &[
+    (
+        "web/db/query.ts",
+        "export function loadUsers() {\n  return [];\n}\n",
+    ),
+    ("web/db/schema.ts", "export const USERS = 'users';\n"),
+    (
+        "web/ui/page.ts",
+        "import { loadUsers } from '../db/query';\nimport { USERS } from '../db/schema';\n\nexport function render() {\n  return loadUsers();\n}\n",
+    ),
+    (
+        "web/api/users.ts",
+        "import { loadUsers } from '../db/query';\n\nexport function list() {\n  return loadUsers();\n}\n",
+    ),
+    ("app/db.py", "def connect():\n    pass\n"),
+    (
+        "app/views.py",
+        "from app.db import connect\n\ndef index():\n    connect()\n",
+    ),
+];
+
+fn set_rules(project: &Path, rules: Vec<ArchitectureRule>) {
+    let mut cfg = config::load_toml_config(project).expect("load config");
+    cfg.architecture.rules = rules;
+    config::save_toml_config(project, &cfg).expect("save config");
+}
+
+fn rule(name: Option<&str>, from: &str, forbid: &[&str], allow: &[&str]) -> ArchitectureRule {
+    let owned = |patterns: &[&str]| patterns.iter().map(|p| (*p).to_string()).collect();
+    ArchitectureRule {
+        name: name.map(str::to_string),
+        from: vec![from.to_string()],
+        forbid: owned(forbid),
+        allow: owned(allow),
+    }
+}
+
+fn analyze_arch(project: &Path, json: bool) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_coraline"));
+    command.args(["analyze", "-p"]).arg(project).arg("arch");
+    if json {
+        command.arg("--json");
+    }
+    command
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run coraline")
+}
+
+#[test]
+fn test_violations_fail_with_the_offending_edges() {
+    let temp = common::with_files(PROJECT_FILES);
+    set_rules(
+        temp.path(),
+        vec![
+            rule(Some("ui-not-db"), "web/ui/**", &["web/db/**"], &[]),
+            rule(None, "app/views.py", &[], &["app/models/**"]),
+        ],
+    );
+
+    let output = analyze_arch(temp.path(), true);
+    assert_eq!(output.status.code(), Some(1));
+    let report: Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    let mut violations: Vec<(String, String, String, String)> = report
+        .get("violations")
+        .and_then(Value::as_array)
+        .expect("an array of violations")
+        .iter()
+        .map(|v| {
+            (
+                v["rule"].as_str().unwrap_or_default().to_string(),
+                v["kind"].as_str().unwrap_or_default().to_string(),
+                v.pointer("/source/file_path")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                v.pointer("/target/file_path")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            )
+        })
+        .collect();
+    violations.sort();
+    let expected = |rule: &str, kind: &str, from: &str, to: &str| {
+        (
+            rule.to_string(),
+            kind.to_string(),
+            from.to_string(),
+            to.to_string(),
+        )
+    };
+    assert_eq!(
+        violations,
+        [
+            expected(
+                "app/views.py may only depend on app/models/**",
+                "calls",
+                "app/views.py",
+                "app/db.py"
+            ),
+            expected(
+                "app/views.py may only depend on app/models/**",
+                "imports",
+                "app/views.py",
+                "app/db.py"
+            ),
+            expected("ui-not-db", "calls", "web/ui/page.ts", "web/db/query.ts"),
+            expected("ui-not-db", "imports", "web/ui/page.ts", "web/db/query.ts"),
+            expected("ui-not-db", "imports", "web/ui/page.ts", "web/db/schema.ts"),
+        ]
+    );
+
+    let output = analyze_arch(temp.path(), false);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ui-not-db"), "{stdout}");
+    assert!(stdout.contains("web/ui/page.ts:1"), "{stdout}");
+}
+
+#[test]
+fn test_rules_that_hold_pass() {
+    let temp = common::with_files(PROJECT_FILES);
+    let output = analyze_arch(temp.path(), false);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("nothing to check"));
+
+    set_rules(
+        temp.path(),
+        vec![rule(None, "web/db/**", &["web/ui/**", "web/api/**"], &[])],
+    );
+    let output = analyze_arch(temp.path(), true);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let report: Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(report.get("rules"), Some(&Value::from(1)));
+    assert_eq!(report.get("violations"), Some(&Value::Array(Vec::new())));
+}
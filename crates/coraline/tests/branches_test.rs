@@ -0,0 +1,137 @@
+//! Integration tests for per-branch graphs
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::branches::{self, BranchSwitch};
+use coraline::types::NodeKind;
+use coraline::worktree::ParseCache;
+use coraline::{config, db, extraction};
+use tempfile::TempDir;
+
+fn setup_repository() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp_dir.path();
+    common::copy_fixture(project_path);
+    std::fs::write(project_path.join(".gitignore"), ".coraline/\n").expect("Failed to write");
+    common::git(project_path, &["init", "-q", "-b", "main"]);
+    common::git(project_path, &["add", "-A"]);
+    common::git(project_path, &["commit", "-q", "-m", "Add fixture"]);
+
+    db::initialize_database(project_path).expect("Failed to initialize database");
+    std::fs::write(
+        project_path.join(".coraline/config.toml"),
+        "[indexing]\nbranch_graphs = true\n",
+    )
+    .expect("Failed to write config");
+    temp_dir
+}
+
+fn has_triple(project_path: &Path) -> bool {
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    db::get_nodes_by_file(&conn, "src/extra.ts", Some(NodeKind::Function))
+        .expect("Failed to read nodes")
+        .iter()
+        .any(|n| n.name == "triple")
+}
+
+fn graph_branch(project_path: &Path) -> Option<String> {
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    db::get_graph_branch(&conn).expect("Failed to read graph branch")
+}
+
+#[test]
+fn test_switching_branches_restores_their_graphs() {
+    let temp_dir = setup_repository();
+    let project = temp_dir.path();
+    let cfg = config::create_default_config(project);
+    assert!(branches::enabled(project));
+    assert!(ParseCache::for_project(project).is_some());
+
+    extraction::index_all(project, &cfg, false, None).expect("Failed to index project");
+    assert_eq!(graph_branch(project).as_deref(), Some("main"));
+
+    common::git(project, &["checkout", "-q", "-b", "feature/extra"]);
+    std::fs::write(
+        project.join("src/extra.ts"),
+        "export function triple(x: number): number {\n    return x * 3;\n}\n",
+    )
+    .expect("Failed to write file");
+    common::git(project, &["add", "-A"]);
+    common::git(project, &["commit", "-q", "-m", "Add triple"]);
+    let synced = extraction::sync(project, &cfg, None).expect("Failed to sync");
+    assert_eq!(synced.files_added, 1);
+    assert!(has_triple(project));
+    assert_eq!(graph_branch(project).as_deref(), Some("feature/extra"));
+
+    // Back on main, its saved graph already matches the checkout.
+    common::git(project, &["checkout", "-q", "main"]);
+    let conn = &mut db::open_database(project).expect("Failed to open database");
+    assert_eq!(
+        branches::switch(project, conn).expect("Failed to switch"),
+        Some(BranchSwitch {
+            from: "feature/extra".to_string(),
+            to: "main".to_string(),
+            restored: true,
+        })
+    );
+    assert!(!has_triple(project));
+    let synced = extraction::sync(project, &cfg, None).expect("Failed to sync");
+    assert_eq!(
+        (
+            synced.files_added,
+            synced.files_modified,
+            synced.files_removed
+        ),
+        (0, 0, 0)
+    );
+    let saved = branches::list(project);
+    assert_eq!(
+        saved.iter().map(|g| g.branch.as_str()).collect::<Vec<_>>(),
+        vec!["feature/extra"]
+    );
+    let graph = saved.first().expect("one saved graph");
+    assert_eq!(graph.path, branches::graph_path(project, "feature/extra"));
+    assert!(graph.path.ends_with("feature%2Fextra.db"));
+
+    common::git(project, &["checkout", "-q", "feature/extra"]);
+    let synced = extraction::sync(project, &cfg, None).expect("Failed to sync");
+    assert_eq!(
+        (
+            synced.files_added,
+            synced.files_modified,
+            synced.files_removed
+        ),
+        (0, 0, 0)
+    );
+    assert!(has_triple(project));
+
+    // A detached HEAD keeps the graph filed under the last branch.
+    common::git(project, &["checkout", "-q", "--detach", "main"]);
+    extraction::sync(project, &cfg, None).expect("Failed to sync");
+    assert!(!has_triple(project));
+    assert_eq!(graph_branch(project).as_deref(), Some("feature/extra"));
+    common::git(project, &["checkout", "-q", "feature/extra"]);
+
+    common::git(project, &["branch", "-q", "-D", "main"]);
+    assert_eq!(
+        branches::prune(project).expect("Failed to prune"),
+        vec!["main".to_string()]
+    );
+    assert!(branches::list(project).is_empty());
+}
+
+#[test]
+fn test_branch_graphs_are_off_by_default() {
+    let temp_dir = setup_repository();
+    let project = temp_dir.path();
+    std::fs::remove_file(project.join(".coraline/config.toml")).expect("Failed to remove config");
+    let cfg = config::create_default_config(project);
+    extraction::index_all(project, &cfg, false, None).expect("Failed to index project");
+    common::git(project, &["checkout", "-q", "-b", "feature"]);
+    extraction::sync(project, &cfg, None).expect("Failed to sync");
+    assert_eq!(graph_branch(project), None);
+    assert!(branches::list(project).is_empty());
+}
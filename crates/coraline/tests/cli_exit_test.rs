@@ -0,0 +1,158 @@
+//! Integration tests for CLI exit codes and `--json-errors`
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::process::{Command, Output};
+
+use coraline::output::exit;
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+fn envelope(output: &Output) -> Value {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let line = stderr.lines().last().expect("stderr should not be empty");
+    serde_json::from_str(line).expect("stderr should end with a JSON envelope")
+}
+
+#[test]
+fn test_not_initialized_exit_code_and_envelope() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let path = temp_dir.path().to_str().expect("utf-8 path");
+
+    let plain = common::run_coraline(&["stats", path]);
+    assert_eq!(plain.status.code(), Some(exit::NOT_INITIALIZED));
+    assert!(String::from_utf8_lossy(&plain.stderr).contains("not initialized"));
+
+    let json = common::run_coraline(&["--json-errors", "stats", path]);
+    assert_eq!(json.status.code(), Some(exit::NOT_INITIALIZED));
+    let value = envelope(&json);
+    assert_eq!(
+        value.pointer("/error/class"),
+        Some(&json!("not_initialized"))
+    );
+    assert_eq!(
+        value.pointer("/error/exit_code"),
+        Some(&json!(exit::NOT_INITIALIZED))
+    );
+    assert!(
+        value
+            .pointer("/error/message")
+            .and_then(Value::as_str)
+            .is_some_and(|m| m.contains(path))
+    );
+}
+
+#[test]
+fn test_usage_errors_exit_with_usage_code() {
+    let unknown = common::run_coraline(&["stats", "--no-such-flag"]);
+    assert_eq!(unknown.status.code(), Some(exit::USAGE));
+
+    let json = common::run_coraline(&["--json-errors", "stats", "--no-such-flag"]);
+    assert_eq!(json.status.code(), Some(exit::USAGE));
+    assert_eq!(
+        envelope(&json).pointer("/error/class"),
+        Some(&json!("usage"))
+    );
+
+    assert_eq!(
+        common::run_coraline(&["--help"]).status.code(),
+        Some(exit::SUCCESS)
+    );
+}
+
+#[test]
+fn test_environment_overrides_apply_to_index() {
+    let temp_dir = common::fixture_project();
+    let project = temp_dir.path();
+    let path = project.to_str().expect("utf-8 path");
+    let index = |var: &str, value: &str| {
+        Command::new(env!("CARGO_BIN_EXE_coraline"))
+            .args(["index", path])
+            .env("NO_COLOR", "1")
+            .env(var, value)
+            .output()
+            .expect("Failed to run coraline")
+    };
+
+    let invalid = index("CORALINE_MAX_FILE_SIZE", "lots");
+    assert_eq!(invalid.status.code(), Some(exit::CONFIG));
+    assert!(String::from_utf8_lossy(&invalid.stderr).contains("CORALINE_MAX_FILE_SIZE"));
+
+    let excluded = index("CORALINE_EXCLUDE", "**/math.ts, **/user.ts");
+    assert_eq!(excluded.status.code(), Some(exit::SUCCESS));
+    let conn = coraline::db::open_database(project).expect("Failed to open database");
+    let files = coraline::db::list_files(&conn).expect("Failed to list files");
+    assert!(!files.is_empty());
+    assert!(
+        files
+            .iter()
+            .all(|f| !f.path.ends_with("math.ts") && !f.path.ends_with("user.ts"))
+    );
+
+    std::fs::write(
+        project.join(".coraline/config.toml"),
+        "[indexing]\nmax_file_size = 1\n",
+    )
+    .expect("Failed to write config.toml");
+    let raised = index("CORALINE_MAX_FILE_SIZE", "1048576");
+    assert_eq!(raised.status.code(), Some(exit::SUCCESS));
+    let files = coraline::db::list_files(&conn).expect("Failed to list files");
+    assert!(files.iter().any(|f| f.path.ends_with("math.ts")));
+}
+
+#[test]
+fn test_call_graph_commands_take_symbol_names() {
+    let temp_dir = common::fixture_project();
+    let project = temp_dir.path();
+    let path = project.to_str().expect("utf-8 path");
+    assert_eq!(
+        common::run_coraline(&["index", path]).status.code(),
+        Some(exit::SUCCESS)
+    );
+
+    let callees = common::run_coraline(&["callees", "quickMath", "-p", path]);
+    assert_eq!(callees.status.code(), Some(exit::SUCCESS));
+    let table = String::from_utf8_lossy(&callees.stdout);
+    assert!(
+        table.contains("kind") && table.contains("multiply"),
+        "{table}"
+    );
+
+    let by_file =
+        common::run_coraline(&["callers", "multiply", "--file", "math.ts", "-p", path, "-j"]);
+    assert_eq!(by_file.status.code(), Some(exit::SUCCESS));
+    let listed: Value = serde_json::from_slice(&by_file.stdout).expect("callers prints JSON");
+    assert!(
+        listed
+            .as_array()
+            .is_some_and(|a| a.iter().any(|c| c["name"] == "quickMath"))
+    );
+
+    // `add` is both a function and a Calculator method.
+    let ambiguous = common::run_coraline(&["impact", "add", "-p", path, "--depth", "2"]);
+    assert_eq!(ambiguous.status.code(), Some(exit::USAGE));
+    assert!(String::from_utf8_lossy(&ambiguous.stderr).contains("Ambiguous: 2 symbols"));
+    let missing = common::run_coraline(&["callers", "nothing_by_this_name", "-p", path]);
+    assert_eq!(missing.status.code(), Some(exit::FAILURE));
+}
+
+#[test]
+fn test_output_format_json_prints_results() {
+    let temp_dir = common::fixture_project();
+    let project = temp_dir.path();
+    let path = project.to_str().expect("utf-8 path");
+    let json = |args: &[&str]| -> Value {
+        let output = common::run_coraline(&[args, &["--output-format", "json"]].concat());
+        assert_eq!(output.status.code(), Some(exit::SUCCESS), "{args:?}");
+        serde_json::from_slice(&output.stdout).expect("the command should print JSON")
+    };
+
+    assert_eq!(json(&["index", path]).get("files_indexed"), Some(&json!(3)));
+    assert_eq!(json(&["sync", path]).get("files_added"), Some(&json!(0)));
+    let status = json(&["status", path]);
+    assert_eq!(status.get("initialized"), Some(&Value::Bool(true)));
+    assert_eq!(status.get("hooks"), Some(&Value::Array(Vec::new())));
+    assert!(json(&["stats", path]).is_object());
+    assert!(json(&["context", "multiply numbers", "-p", path]).is_object());
+}
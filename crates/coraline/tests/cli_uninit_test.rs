@@ -0,0 +1,48 @@
+//! Integration tests for `coraline uninit`
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use tempfile::TempDir;
+
+const PREVIOUS_HOOK: &str = "#!/bin/sh\necho previous hook\n";
+
+#[test]
+fn test_uninit_removes_coraline_and_restores_previous_hook() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project = temp_dir.path();
+    let path = project.to_str().expect("utf-8 path");
+    common::git(project, &["init", "-q"]);
+    let hook = project.join(".git/hooks/post-commit");
+    std::fs::create_dir_all(project.join(".git/hooks")).expect("Failed to create hooks dir");
+    std::fs::write(&hook, PREVIOUS_HOOK).expect("Failed to write hook");
+
+    common::coraline(&["init", path]);
+    assert!(project.join(".coraline/coraline.db").is_file());
+    assert_ne!(std::fs::read_to_string(&hook).expect("hook"), PREVIOUS_HOOK);
+
+    // A dry run lists everything and changes nothing.
+    let dry = common::coraline(&["uninit", path, "--dry-run"]);
+    let listed = String::from_utf8_lossy(&dry.stdout);
+    assert!(listed.contains(".coraline"), "{listed}");
+    assert!(listed.contains("post-commit hook"), "{listed}");
+    assert!(project.join(".coraline").exists());
+
+    // Without a terminal or --force nothing is removed.
+    common::coraline(&["uninit", path]);
+    assert!(project.join(".coraline").exists());
+
+    let removed = common::coraline(&["uninit", path, "--force"]);
+    let report = String::from_utf8_lossy(&removed.stdout);
+    assert!(report.contains("previous hook restored"), "{report}");
+    assert!(!project.join(".coraline").exists());
+    assert_eq!(std::fs::read_to_string(&hook).expect("hook"), PREVIOUS_HOOK);
+    assert!(
+        !project
+            .join(".git/hooks/post-commit.coraline-backup")
+            .exists()
+    );
+
+    let again = common::coraline(&["uninit", path, "--force"]);
+    assert!(String::from_utf8_lossy(&again.stdout).contains("nothing to remove"));
+}
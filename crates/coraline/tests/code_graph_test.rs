@@ -0,0 +1,88 @@
+//! Integration tests for the `CodeGraph` library facade
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::types::{
+    BuildContextOptions, ContextFormat, NodeKind, SearchOptions, TraversalDirection,
+    TraversalOptions,
+};
+use coraline::{CodeGraph, CoralineError};
+use tempfile::TempDir;
+
+#[test]
+fn test_code_graph_index_search_and_traverse() {
+    let temp = TempDir::new().expect("Failed to create temp directory");
+    common::copy_fixture(temp.path());
+
+    assert!(matches!(
+        CodeGraph::open(temp.path()),
+        Err(CoralineError::NotInitialized(_))
+    ));
+
+    let mut graph = CodeGraph::init(temp.path()).expect("Failed to initialize project");
+    let indexed = graph.index().expect("Failed to index project");
+    assert_eq!(indexed.files_indexed, 3);
+    assert_eq!(graph.stats().expect("Failed to get stats").file_count, 3);
+
+    let hits = graph
+        .search(
+            "multiply",
+            &SearchOptions {
+                kinds: Some(vec![NodeKind::Function]),
+                limit: Some(5),
+                ..SearchOptions::default()
+            },
+        )
+        .expect("Search failed");
+    let multiply = &hits.first().expect("Expected a search result").node;
+    assert_eq!(multiply.name, "multiply");
+    assert_eq!(
+        graph
+            .node(&multiply.id)
+            .expect("Lookup failed")
+            .map(|node| node.file_path),
+        Some("src/math.ts".to_string())
+    );
+
+    let subgraph = graph
+        .subgraph(
+            std::slice::from_ref(&multiply.id),
+            &TraversalOptions {
+                direction: Some(TraversalDirection::Incoming),
+                ..TraversalOptions::default()
+            },
+        )
+        .expect("Traversal failed");
+    assert!(subgraph.nodes.values().any(|node| node.name == "quickMath"));
+    assert!(matches!(
+        graph.subgraph(&["missing".to_string()], &TraversalOptions::default()),
+        Err(CoralineError::NotFound(_))
+    ));
+
+    let context = graph
+        .context(
+            "multiply numbers",
+            &BuildContextOptions {
+                format: Some(ContextFormat::Markdown),
+                ..BuildContextOptions::default()
+            },
+        )
+        .expect("Context failed");
+    assert!(context.contains("multiply"));
+
+    std::fs::write(
+        temp.path().join("src/extra.ts"),
+        "export function divide(a: number, b: number) { return a / b; }\n",
+    )
+    .expect("Failed to write file");
+    let synced = graph.sync().expect("Sync failed");
+    assert_eq!(synced.files_added, 1);
+    let reopened = CodeGraph::open(temp.path()).expect("Failed to reopen project");
+    assert!(
+        !reopened
+            .search("divide", &SearchOptions::default())
+            .expect("Search failed")
+            .is_empty()
+    );
+}
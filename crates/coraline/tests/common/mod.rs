@@ -0,0 +1,116 @@
+//! Helpers shared by the integration tests
+#![allow(clippy::expect_used, dead_code)]
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use coraline::{config, db, extraction};
+use tempfile::TempDir;
+
+/// The fixture copied into `src/` by [`setup_indexed_project`].
+const FIXTURE: &str = "tests/fixtures/typescript-simple";
+
+/// An indexed project holding the `typescript-simple` fixture under `src/`.
+pub fn setup_indexed_project() -> TempDir {
+    with_fixture(&[])
+}
+
+/// An indexed project holding the `typescript-simple` fixture under `src/`
+/// plus `files`, given as `(path, source)` pairs.
+pub fn with_fixture(files: &[(&str, &str)]) -> TempDir {
+    index(fixture_project(), files)
+}
+
+/// An initialized but unindexed project holding the `typescript-simple`
+/// fixture under `src/`.
+pub fn fixture_project() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    db::initialize_database(temp_dir.path()).expect("Failed to initialize database");
+    copy_fixture(temp_dir.path());
+    temp_dir
+}
+
+/// Copy the `typescript-simple` fixture into `project/src`.
+pub fn copy_fixture(project: &Path) {
+    let src = project.join("src");
+    std::fs::create_dir_all(&src).expect("Failed to create src");
+    for entry in std::fs::read_dir(FIXTURE).expect("Failed to read fixture directory") {
+        let entry = entry.expect("Failed to read directory entry");
+        std::fs::copy(entry.path(), src.join(entry.file_name()))
+            .expect("Failed to copy fixture file");
+    }
+}
+
+/// An indexed project holding just `files`, given as `(path, source)` pairs.
+pub fn with_files(files: &[(&str, &str)]) -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    db::initialize_database(temp_dir.path()).expect("Failed to initialize database");
+    index(temp_dir, files)
+}
+
+/// Write `files`, given as `(path, source)` pairs, into `temp_dir` and index it.
+pub fn index(temp_dir: TempDir, files: &[(&str, &str)]) -> TempDir {
+    let project = temp_dir.path();
+    for (file, source) in files {
+        write(project, file, source);
+    }
+    let cfg = config::create_default_config(project);
+    extraction::index_all(project, &cfg, false, None).expect("Failed to index project");
+    temp_dir
+}
+
+/// Write `source` to `file` under `project`, creating its directories.
+pub fn write(project: &Path, file: &str, source: &str) {
+    let path = project.join(file);
+    std::fs::create_dir_all(path.parent().expect("file has a parent")).expect("create dir");
+    std::fs::write(path, source).expect("write file");
+}
+
+/// Run `git` in `project` as the test user.
+pub fn git(project: &Path, args: &[&str]) {
+    git_as(project, "Test", args);
+}
+
+/// Run `git` in `project` as `author` (`<author>@example.com`), without
+/// commit signing.
+pub fn git_as(project: &Path, author: &str, args: &[&str]) {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(project)
+        .args([
+            "-c",
+            &format!("user.name={author}"),
+            "-c",
+            &format!("user.email={author}@example.com"),
+            "-c",
+            "commit.gpgsign=false",
+        ])
+        .args(args)
+        .output()
+        .expect("Failed to run git");
+    assert!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Run the `coraline` binary with `args`.
+pub fn run_coraline(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_coraline"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run coraline")
+}
+
+/// Run the `coraline` binary with `args`, asserting it succeeds.
+pub fn coraline(args: &[&str]) -> Output {
+    let output = run_coraline(args);
+    assert!(
+        output.status.success(),
+        "coraline {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
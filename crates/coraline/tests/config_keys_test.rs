@@ -0,0 +1,73 @@
+//! Integration tests for the environment variable and config-key index
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::tools::responses::ReferencesResponse;
+use coraline::types::EdgeKind;
+use coraline::{db, tools};
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+fn project() -> TempDir {
+    common::with_files(&[
+        (
+            "src/client.ts",
+            "export function client() {\n  return new Api(process.env.API_KEY, config.get('api.timeout'));\n}\n",
+        ),
+        (
+            "worker/main.py",
+            "import os\n\nKEY = os.environ[\"API_KEY\"]\n\ndef region():\n    return os.getenv(\"AWS_REGION\")\n",
+        ),
+    ])
+}
+
+#[test]
+fn test_query_kind_constant_lists_every_reader() {
+    let temp = project();
+    let path = temp.path().to_str().expect("utf-8 path");
+    let output = common::coraline(&[
+        "query", "API_KEY", "--kind", "constant", "--json", "-p", path,
+    ]);
+    let hits: Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    let mut files: Vec<&str> = hits
+        .as_array()
+        .expect("an array of hits")
+        .iter()
+        .filter(|hit| hit.pointer("/node/signature") == Some(&json!("env API_KEY")))
+        .filter_map(|hit| hit.pointer("/node/file_path").and_then(Value::as_str))
+        .collect();
+    files.sort_unstable();
+    assert_eq!(files, ["src/client.ts", "worker/main.py"]);
+}
+
+#[test]
+fn test_reads_reference_the_key_from_their_function() {
+    let temp = project();
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+    let keys: Vec<(String, String)> = db::get_nodes_by_file(&conn, "src/client.ts", None)
+        .expect("Failed to read nodes")
+        .into_iter()
+        .filter_map(|node| node.signature.map(|signature| (node.id, signature)))
+        .filter(|(_, signature)| signature.starts_with("env ") || signature.starts_with("config "))
+        .collect();
+    assert_eq!(
+        keys.iter().map(|(_, s)| s.as_str()).collect::<Vec<_>>(),
+        ["env API_KEY", "config api.timeout"]
+    );
+
+    let result = tools::create_default_registry(temp.path())
+        .execute(
+            "coraline_find_references",
+            json!({ "node_id": keys.first().map(|(id, _)| id) }),
+        )
+        .expect("coraline_find_references failed");
+    let references: ReferencesResponse = serde_json::from_value(result).expect("valid response");
+    let reads: Vec<(&str, Option<i64>)> = references
+        .references
+        .iter()
+        .filter(|r| r.edge_kind == EdgeKind::References)
+        .map(|r| (r.node.name.as_str(), r.edge_line))
+        .collect();
+    assert_eq!(reads, [("client", Some(2))]);
+}
@@ -0,0 +1,113 @@
+//! Integration tests for reading, editing, and validating configuration
+#![allow(clippy::expect_used)]
+
+use std::io::ErrorKind;
+
+use coraline::config::{self, ConfigProblem};
+use coraline::db;
+use serde_json::json;
+use tempfile::TempDir;
+
+fn setup_project() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    db::initialize_database(temp_dir.path()).expect("Failed to initialize database");
+    config::save_config(
+        temp_dir.path(),
+        &config::create_default_config(temp_dir.path()),
+    )
+    .expect("Failed to save config");
+    temp_dir
+}
+
+#[test]
+fn test_get_and_set_resolve_keys_in_either_file() {
+    let temp_dir = setup_project();
+    let project = temp_dir.path();
+
+    assert_eq!(
+        config::get_value(project, "indexing.max_file_size").expect("Failed to get"),
+        json!(1_048_576)
+    );
+    assert_eq!(
+        config::set_value(project, "indexing.batch_size", "50").expect("Failed to set"),
+        json!(50)
+    );
+    assert_eq!(
+        config::load_toml_config(project)
+            .expect("Failed to load")
+            .indexing
+            .batch_size,
+        50
+    );
+    config::set_value(project, "sync.post_sync_command", "make docs").expect("Failed to set");
+    assert_eq!(
+        config::get_value(project, "sync.post_sync_command").expect("Failed to get"),
+        json!("make docs")
+    );
+
+    assert_eq!(
+        config::set_value(project, "languages", r#"["rust", "python"]"#).expect("Failed to set"),
+        json!(["rust", "python"])
+    );
+    assert_eq!(
+        config::load_config(project)
+            .expect("Failed to load")
+            .languages
+            .len(),
+        2
+    );
+
+    for (key, value) in [
+        ("indexing.max_file_sise", "1"),
+        ("root_dir", "/elsewhere"),
+        ("indexing.batch_size", "many"),
+        ("languages", r#"["rustt"]"#),
+        ("indexing.exclude_patterns", r#"["src/[a"]"#),
+    ] {
+        let err = config::set_value(project, key, value).expect_err("the set is rejected");
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{key}: {err}");
+    }
+    assert!(config::validate(project).is_empty());
+}
+
+#[test]
+fn test_validate_reports_each_problem() {
+    let temp_dir = setup_project();
+    let project = temp_dir.path();
+    std::fs::write(
+        project.join(".coraline/config.toml"),
+        "[indexing]\nmax_file_sise = 10\nexclude_patterns = [\"**/gen/**\", \"src/{a\"]\n",
+    )
+    .expect("Failed to write config");
+    let path = config::config_path(project);
+    let mut raw: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).expect("Failed to read config"))
+            .expect("Failed to parse config");
+    raw.as_object_mut()
+        .expect("config.json is an object")
+        .insert("languages".to_string(), json!(["rust", "typescript"]));
+    std::fs::write(&path, raw.to_string()).expect("Failed to write config");
+
+    let problems = config::validate(project);
+    let keys: Vec<(&str, &str)> = problems.iter().map(|p| (p.file, p.key.as_str())).collect();
+    assert_eq!(
+        keys,
+        vec![
+            ("config.toml", "indexing.max_file_sise"),
+            ("config.toml", "indexing.exclude_patterns[1]"),
+            ("config.json", "languages[1]"),
+        ]
+    );
+    assert!(
+        problems
+            .get(2)
+            .is_some_and(|p| p.message.contains("type_script"))
+    );
+
+    std::fs::write(project.join(".coraline/config.toml"), "[indexing\n")
+        .expect("Failed to write config");
+    assert!(matches!(
+        config::validate(project).first(),
+        Some(ConfigProblem { file: "config.toml", key, .. }) if key.is_empty()
+    ));
+}
@@ -3,8 +3,9 @@
 
 use std::path::Path;
 
-use coraline::types::{BuildContextOptions, ContextFormat};
+use coraline::types::{BuildContextOptions, ContextFormat, Edge, EdgeKind, NodeKind, TaskInput};
 use coraline::{config, context, db, extraction};
+use serde_json::{Value, json};
 use tempfile::TempDir;
 
 fn setup_indexed_project() -> (TempDir, String) {
@@ -87,7 +88,7 @@ fn test_build_context_json() {
     assert!(!context_str.is_empty(), "Context should not be empty");
 
     // Should be valid JSON
-    let parsed: Result<serde_json::Value, _> = serde_json::from_str(&context_str);
+    let parsed: Result<Value, _> = serde_json::from_str(&context_str);
     assert!(parsed.is_ok(), "Context should be valid JSON");
 }
 
@@ -186,3 +187,444 @@ fn test_context_max_nodes_limit() {
         "Large context should not be empty"
     );
 }
+
+#[test]
+fn test_context_resyncs_files_edited_after_indexing() {
+    let (_temp, project_root) = setup_indexed_project();
+    let project_path = Path::new(&project_root);
+
+    // Drop the header comment so every indexed span in math.ts is now wrong.
+    std::fs::write(
+        project_path.join("src").join("math.ts"),
+        "export function add(a: number, b: number): number {\n    return b + a;\n}\n",
+    )
+    .expect("Failed to rewrite math.ts");
+    // Edited too, but holds none of the matches: left for the next sync.
+    let user_ts = project_path.join("src").join("user.ts");
+    let user_source = std::fs::read_to_string(&user_ts).expect("Failed to read user.ts");
+    std::fs::write(&user_ts, format!("// edited\n{user_source}"))
+        .expect("Failed to rewrite user.ts");
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let user_hash = db::get_file_record(&conn, "src/user.ts")
+        .expect("Failed to read file record")
+        .expect("user.ts should be indexed")
+        .content_hash;
+
+    let options = BuildContextOptions {
+        max_nodes: Some(10),
+        max_code_blocks: Some(5),
+        max_code_block_size: Some(1000),
+        include_code: Some(true),
+        format: Some(ContextFormat::Json),
+        search_limit: None,
+        traversal_depth: Some(1),
+        min_score: None,
+    };
+
+    let context_str =
+        context::build_context(project_path, "add", &options).expect("Failed to build context");
+    let json: Value = serde_json::from_str(&context_str).expect("Context should be valid JSON");
+
+    let block = json
+        .get("code_blocks")
+        .and_then(Value::as_array)
+        .expect("code_blocks should be an array")
+        .iter()
+        .find(|b| b.pointer("/node/name") == Some(&json!("add")))
+        .expect("Expected a code block for add");
+    assert_eq!(block["start_line"], 1);
+    assert_eq!(block["stale"], false);
+    assert!(
+        block["content"]
+            .as_str()
+            .is_some_and(|c| c.contains("return b + a")),
+        "Code block should show the current content: {block}"
+    );
+    let record = db::get_file_record(&conn, "src/user.ts")
+        .expect("Failed to read file record")
+        .expect("user.ts should be indexed");
+    assert_eq!(record.content_hash, user_hash);
+}
+
+#[test]
+fn test_context_ranks_entry_points_by_relevance() {
+    let (_temp, project_root) = setup_indexed_project();
+    let project_path = Path::new(&project_root);
+
+    let entry_points = |min_score| {
+        let options = BuildContextOptions {
+            max_nodes: Some(10),
+            max_code_blocks: Some(0),
+            max_code_block_size: None,
+            include_code: Some(false),
+            format: Some(ContextFormat::Json),
+            search_limit: None,
+            traversal_depth: Some(0),
+            min_score,
+        };
+        let context_str =
+            context::build_context(project_path, "add", &options).expect("Failed to build context");
+        let json: Value = serde_json::from_str(&context_str).expect("Context should be valid JSON");
+        json.get("entry_points")
+            .and_then(Value::as_array)
+            .expect("entry_points should be an array")
+            .iter()
+            .map(|n| n["kind"].as_str().unwrap_or_default().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let kinds = entry_points(None);
+    assert!(
+        kinds.iter().any(|k| k == "import"),
+        "imports of add still match: {kinds:?}"
+    );
+    let first_import = kinds.iter().position(|k| k == "import").unwrap_or(0);
+    assert!(
+        kinds
+            .iter()
+            .take(first_import)
+            .any(|k| k == "function" || k == "method"),
+        "definitions rank above imports: {kinds:?}"
+    );
+
+    let strict = entry_points(Some(0.9));
+    assert!(!strict.is_empty());
+    assert!(strict.len() < kinds.len());
+    assert!(!strict.iter().any(|k| k == "import"), "{strict:?}");
+}
+
+#[test]
+fn test_context_xml_and_repo_map_formats() {
+    let (_temp, project_root) = setup_indexed_project();
+    let project_path = Path::new(&project_root);
+    let render = |format| {
+        let options = BuildContextOptions {
+            max_nodes: Some(10),
+            max_code_blocks: Some(2),
+            max_code_block_size: Some(1000),
+            include_code: Some(true),
+            format: Some(format),
+            search_limit: None,
+            traversal_depth: Some(1),
+            min_score: None,
+        };
+        context::build_context(project_path, "user service", &options)
+            .expect("Failed to build context")
+    };
+
+    let xml = render(ContextFormat::Xml);
+    assert!(xml.starts_with("<context>\n<query>user service</query>"));
+    assert!(xml.contains("<symbol kind=\"class\" file=\"src/user.ts\""));
+    assert!(xml.contains("<document index=\"1\">"));
+    assert!(xml.contains("<document_content>"));
+    assert!(xml.trim_end().ends_with("</context>"));
+
+    let map = render(ContextFormat::RepoMap);
+    assert!(map.contains("src/user.ts:"), "{map}");
+    assert!(map.contains("*│  export class UserService"), "{map}");
+    assert!(
+        map.contains("*│    createUser(name: string, email: string): User"),
+        "{map}"
+    );
+    assert!(!map.contains("const user"), "locals are left out: {map}");
+    assert!(!map.contains("return"), "no code bodies: {map}");
+
+    assert_eq!(
+        ContextFormat::parse("repo-map"),
+        Some(ContextFormat::RepoMap)
+    );
+    assert_eq!(ContextFormat::parse("XML"), Some(ContextFormat::Xml));
+    assert_eq!(ContextFormat::parse("yaml"), None);
+}
+
+#[test]
+fn test_context_lists_dependencies_of_entry_points() {
+    let (_temp, project_root) = setup_indexed_project();
+    let project_path = Path::new(&project_root);
+    let mut conn = db::open_database(project_path).expect("Failed to open database");
+    let node = |file: &str, name: &str, kind: NodeKind| {
+        db::get_nodes_by_file(&conn, file, Some(kind))
+            .expect("Failed to get nodes")
+            .into_iter()
+            .find(|n| n.name == name)
+            .expect("node should exist")
+    };
+    let quick_math = node("src/index.ts", "quickMath", NodeKind::Function);
+    let user = node("src/user.ts", "User", NodeKind::Interface);
+    db::insert_edges(
+        &mut conn,
+        &[Edge {
+            source: quick_math.id.clone(),
+            target: user.id.clone(),
+            kind: EdgeKind::References,
+            metadata: None,
+            line: Some(quick_math.start_line),
+            column: None,
+        }],
+    )
+    .expect("Failed to insert edge");
+
+    let render = |format| {
+        let options = BuildContextOptions {
+            max_nodes: Some(3),
+            max_code_blocks: Some(0),
+            max_code_block_size: None,
+            include_code: Some(false),
+            format: Some(format),
+            search_limit: None,
+            traversal_depth: Some(1),
+            min_score: None,
+        };
+        context::build_context(project_path, "quickMath", &options)
+            .expect("Failed to build context")
+    };
+
+    let markdown = render(ContextFormat::Markdown);
+    assert!(markdown.contains("### Dependencies"), "{markdown}");
+    assert!(
+        markdown.contains(
+            "- Imports of src/index.ts: `Calculator`, `add`, `multiply` from `./math`; `UserService`, `User` from `./user`"
+        ),
+        "{markdown}"
+    );
+    assert_eq!(markdown.matches("- Imports of ").count(), 1, "{markdown}");
+    assert!(
+        markdown.contains("- **quickMath** (src/index.ts)\n  - Imports: `User` from `./user`\n"),
+        "{markdown}"
+    );
+    assert!(
+        markdown.contains("  - Types: `User` (src/user.ts:"),
+        "{markdown}"
+    );
+
+    let json: Value = serde_json::from_str(&render(ContextFormat::Json)).expect("context is JSON");
+    let deps = json
+        .get("dependencies")
+        .and_then(|deps| deps.get(&quick_math.id))
+        .expect("QuickMath has dependencies");
+    assert_eq!(deps.pointer("/imports/0/name"), Some(&json!("User")));
+    assert_eq!(deps["imports"].as_array().map(Vec::len), Some(1));
+    assert_eq!(deps.pointer("/types/0/id"), Some(&json!(user.id)));
+    assert_eq!(
+        json.pointer("/imports/src~1index.ts")
+            .and_then(Value::as_array)
+            .map(Vec::len),
+        Some(5)
+    );
+
+    let xml = render(ContextFormat::Xml);
+    assert!(xml.contains(
+        "<file path=\"src/index.ts\">\n<import module=\"./math\">Calculator, add, multiply</import>"
+    ));
+    assert!(xml.contains(
+        "<symbol name=\"quickMath\" file=\"src/index.ts\">\n<import module=\"./user\">User</import>"
+    ));
+    assert!(xml.contains("<type kind=\"interface\" file=\"src/user.ts\""));
+}
+
+#[test]
+fn test_context_merges_nested_code_blocks() {
+    let (_temp, project_root) = setup_indexed_project();
+    let project_path = Path::new(&project_root);
+    let options = BuildContextOptions {
+        max_nodes: Some(10),
+        max_code_blocks: Some(10),
+        max_code_block_size: Some(5000),
+        include_code: Some(true),
+        format: Some(ContextFormat::Json),
+        search_limit: None,
+        traversal_depth: Some(1),
+        min_score: None,
+    };
+    let json: Value = serde_json::from_str(
+        &context::build_context(project_path, "calculator history", &options)
+            .expect("Failed to build context"),
+    )
+    .expect("context is JSON");
+    let entry_points = json
+        .get("entry_points")
+        .and_then(Value::as_array)
+        .expect("entry points");
+    assert!(
+        entry_points.iter().any(|n| n["name"] == "Calculator")
+            && entry_points.iter().any(|n| n["name"] == "getHistory"),
+        "class and method both match: {entry_points:?}"
+    );
+
+    let blocks = json
+        .get("code_blocks")
+        .and_then(Value::as_array)
+        .expect("code blocks");
+    for (i, a) in blocks.iter().enumerate() {
+        for b in blocks.iter().skip(i + 1) {
+            assert!(
+                a["file_path"] != b["file_path"]
+                    || a["end_line"].as_i64() < b["start_line"].as_i64()
+                    || b["end_line"].as_i64() < a["start_line"].as_i64(),
+                "blocks overlap: {a} {b}"
+            );
+        }
+    }
+    let class_block = blocks
+        .iter()
+        .find(|b| b.pointer("/node/name") == Some(&json!("Calculator")))
+        .expect("the class block is labelled by the class");
+    assert!(
+        class_block["content"]
+            .as_str()
+            .is_some_and(|c| c.contains("getHistory()") && c.contains("clearHistory()"))
+    );
+    assert!(
+        !blocks
+            .iter()
+            .any(|b| b.pointer("/node/name") == Some(&json!("getHistory")))
+    );
+}
+
+#[test]
+fn test_context_composes_sub_queries() {
+    let (_temp, project_root) = setup_indexed_project();
+    let project_path = Path::new(&project_root);
+    let task = TaskInput::Detailed {
+        title: "math and users".to_string(),
+        description: Some("ignored when sub-queries are given".to_string()),
+        queries: vec!["divide".to_string(), "createUser".to_string()],
+    };
+    let render = |format| {
+        let options = BuildContextOptions {
+            max_nodes: Some(4),
+            max_code_blocks: Some(4),
+            max_code_block_size: Some(2000),
+            include_code: Some(true),
+            format: Some(format),
+            search_limit: None,
+            traversal_depth: Some(1),
+            min_score: None,
+        };
+        context::build_task_context(project_path, &task, &options).expect("Failed to build context")
+    };
+
+    let json: Value = serde_json::from_str(&render(ContextFormat::Json)).expect("context is JSON");
+    assert_eq!(json.get("query"), Some(&json!("math and users")));
+    assert_eq!(
+        json.get("sub_queries"),
+        Some(&json!(["divide", "createUser"]))
+    );
+    let entry_points = json
+        .get("entry_points")
+        .and_then(Value::as_array)
+        .expect("entry points");
+    let entry = |name: &str| {
+        entry_points
+            .iter()
+            .find(|n| n["name"] == name)
+            .expect("the sub-query's match is an entry point")
+    };
+    // The best match of each sub-query comes first.
+    let names: Vec<&str> = entry_points
+        .iter()
+        .filter_map(|n| n["name"].as_str())
+        .collect();
+    assert_eq!(names.get(..2), Some(&["divide", "createUser"][..]));
+    let divide = entry("divide")["id"].as_str().expect("id");
+    let create_user = entry("createUser")["id"].as_str().expect("id");
+    let attribution = json.get("attribution").expect("attribution");
+    assert_eq!(attribution.get(divide), Some(&json!(["divide"])));
+    assert_eq!(attribution.get(create_user), Some(&json!(["createUser"])));
+    let subgraph_nodes = json
+        .pointer("/subgraph/nodes")
+        .and_then(Value::as_object)
+        .expect("nodes");
+    assert!(subgraph_nodes.contains_key(divide) && subgraph_nodes.contains_key(create_user));
+    assert!(
+        subgraph_nodes
+            .keys()
+            .all(|id| attribution.get(id).is_some()),
+        "every node is attributed"
+    );
+
+    let markdown = render(ContextFormat::Markdown);
+    assert!(markdown.contains("**Sub-queries:** divide; createUser"));
+    assert!(markdown.contains("(for: divide)"), "{markdown}");
+
+    // A plain task has no sub-queries or attribution.
+    let plain: Value = serde_json::from_str(
+        &context::build_context(
+            project_path,
+            "divide",
+            &BuildContextOptions {
+                max_nodes: Some(4),
+                max_code_blocks: Some(1),
+                max_code_block_size: None,
+                include_code: Some(false),
+                format: Some(ContextFormat::Json),
+                search_limit: None,
+                traversal_depth: Some(1),
+                min_score: None,
+            },
+        )
+        .expect("Failed to build context"),
+    )
+    .expect("context is JSON");
+    assert!(plain.get("sub_queries").is_none() && plain.get("attribution").is_none());
+}
+
+#[test]
+fn test_context_log_records_and_replays_contexts() {
+    let (_temp_dir, project_root) = setup_indexed_project();
+    let project_path = Path::new(&project_root);
+
+    let options = BuildContextOptions {
+        max_nodes: Some(10),
+        max_code_blocks: Some(1),
+        max_code_block_size: Some(20),
+        include_code: Some(true),
+        format: Some(ContextFormat::Markdown),
+        search_limit: None,
+        traversal_depth: Some(1),
+        min_score: None,
+    };
+
+    context::build_context(project_path, "calculator", &options).expect("Failed to build context");
+    assert!(
+        context::read_context_log(project_path)
+            .expect("Failed to read context log")
+            .is_empty(),
+        "Contexts are not logged unless log_contexts is enabled"
+    );
+
+    let mut cfg = config::load_toml_config(project_path).unwrap_or_default();
+    cfg.context.log_contexts = true;
+    config::save_toml_config(project_path, &cfg).expect("Failed to save config");
+
+    let output = context::build_context(project_path, "calculator", &options)
+        .expect("Failed to build context");
+    let entries = context::read_context_log(project_path).expect("Failed to read context log");
+    assert_eq!(entries.len(), 1);
+
+    let entry = entries.first().expect("one entry");
+    assert_eq!(entry.query, "calculator");
+    assert_eq!(entry.tokens, context::estimate_tokens(&output));
+    assert!(!entry.entry_points.is_empty());
+    assert_eq!(entry.code_blocks.len(), 1);
+    assert_eq!(entry.truncated_blocks, 1, "20-char blocks should be cut");
+    // The one block shows every entry point its span covers.
+    let elsewhere = entry
+        .entry_points
+        .iter()
+        .filter(|n| Some(&n.file_path) != entry.code_blocks.first().map(|b| &b.file_path))
+        .count();
+    assert!(entry.omitted_blocks >= elsewhere);
+    assert!(entry.omitted_blocks < entry.entry_points.len());
+
+    let replay = context::replay_context(project_path, entry).expect("Failed to replay context");
+    assert_eq!(replay.kept, entry.entry_points.len());
+    assert!(replay.added.is_empty() && replay.removed.is_empty());
+    assert_eq!(
+        context::read_context_log(project_path)
+            .expect("Failed to read context log")
+            .len(),
+        1,
+        "Replays are not appended to the log"
+    );
+}
@@ -0,0 +1,173 @@
+//! Integration tests for coverage ingestion
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::coverage::{self, CoverageFormat};
+use coraline::tools::responses::ImpactResponse;
+use coraline::types::{BuildContextOptions, ContextFormat, NodeKind};
+use coraline::{context, db, tools};
+use serde_json::json;
+
+/// `add` and `Calculator.getHistory` ran; `subtract`, `Calculator.add`, and
+/// `quickMath` did not.
+fn write_lcov(project_path: &Path) -> std::path::PathBuf {
+    let report = format!(
+        "TN:\nSF:{}/src/math.ts\nDA:5,4\nDA:6,4\nDA:9,0\nDA:10,0\nDA:28,0\nDA:29,0\nDA:30,0\nDA:34,1\nend_of_record\nSF:./src/index.ts\nDA:40,0\nDA:41,0\nend_of_record\nSF:vendor/lib.ts\nDA:1,1\nend_of_record\n",
+        project_path.display()
+    );
+    let path = project_path.join("lcov.info");
+    std::fs::write(&path, report).expect("Failed to write report");
+    path
+}
+
+fn node_id(project_path: &Path, name: &str, kind: NodeKind) -> String {
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    ["src/math.ts", "src/index.ts"]
+        .into_iter()
+        .flat_map(|file| {
+            db::get_nodes_by_file(&conn, file, Some(kind)).expect("Failed to read nodes")
+        })
+        .find(|n| n.name == name)
+        .expect("the symbol should be indexed")
+        .id
+}
+
+fn coverage_of(project_path: &Path, id: &str) -> Option<coraline::types::NodeCoverage> {
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    db::get_coverage(&conn, &[id])
+        .expect("Failed to read coverage")
+        .remove(id)
+}
+
+#[test]
+fn test_ingest_lcov_maps_lines_to_functions() {
+    let temp = common::setup_indexed_project();
+    let project_path = temp.path();
+    let report = write_lcov(project_path);
+
+    let summary =
+        coverage::ingest_coverage(project_path, &report, None).expect("Failed to ingest report");
+    assert_eq!(summary.format, CoverageFormat::Lcov);
+    assert_eq!(summary.files_in_report, 3);
+    assert_eq!(summary.files_matched, 2);
+    assert_eq!(summary.unmatched_files, ["vendor/lib.ts"]);
+    assert_eq!(summary.lines_total, 10);
+    assert_eq!(summary.lines_covered, 3);
+
+    let add = coverage_of(
+        project_path,
+        &node_id(project_path, "add", NodeKind::Function),
+    )
+    .expect("add should be measured");
+    assert_eq!((add.covered_lines, add.total_lines), (2, 2));
+    let subtract = coverage_of(
+        project_path,
+        &node_id(project_path, "subtract", NodeKind::Function),
+    )
+    .expect("subtract should be measured");
+    assert!(subtract.is_untested());
+    assert!(
+        coverage_of(
+            project_path,
+            &node_id(project_path, "multiply", NodeKind::Function)
+        )
+        .is_none(),
+        "functions without instrumented lines are not measured"
+    );
+
+    // A new report replaces the previous one.
+    let empty = project_path.join("empty.info");
+    std::fs::write(&empty, "").expect("Failed to write report");
+    coverage::ingest_coverage(project_path, &empty, Some(CoverageFormat::Lcov))
+        .expect("Failed to ingest report");
+    assert!(
+        coverage_of(
+            project_path,
+            &node_id(project_path, "add", NodeKind::Function)
+        )
+        .is_none()
+    );
+}
+
+#[test]
+fn test_ingest_cobertura_resolves_paths_against_sources() {
+    let temp = common::setup_indexed_project();
+    let project_path = temp.path();
+    let report = format!(
+        r#"<?xml version="1.0" ?>
+<coverage line-rate="0.5">
+  <sources><source>{}/src</source></sources>
+  <packages><package name="src"><classes>
+    <class name="math.ts" filename="math.ts">
+      <lines>
+        <line number="10" hits="3"/>
+        <line number="14" hits="0"/>
+      </lines>
+    </class>
+  </classes></package></packages>
+</coverage>"#,
+        project_path.display()
+    );
+    let path = project_path.join("coverage.xml");
+    std::fs::write(&path, report).expect("Failed to write report");
+
+    let summary =
+        coverage::ingest_coverage(project_path, &path, None).expect("Failed to ingest report");
+    assert_eq!(summary.format, CoverageFormat::Cobertura);
+    assert_eq!(summary.files_matched, 1);
+    assert_eq!(summary.nodes_measured, 2);
+    assert_eq!(summary.nodes_untested, 1);
+
+    let multiply = coverage_of(
+        project_path,
+        &node_id(project_path, "multiply", NodeKind::Function),
+    )
+    .expect("multiply should be measured");
+    assert!(multiply.is_untested());
+}
+
+#[test]
+fn test_impact_and_context_flag_untested_symbols() {
+    let temp = common::setup_indexed_project();
+    let project_path = temp.path();
+    let report = write_lcov(project_path);
+    coverage::ingest_coverage(project_path, &report, None).expect("Failed to ingest report");
+
+    let multiply = node_id(project_path, "multiply", NodeKind::Function);
+    let caller = node_id(project_path, "quickMath", NodeKind::Function);
+    let registry = tools::create_default_registry(project_path);
+    let result = registry
+        .execute("coraline_impact", json!({ "node_id": multiply }))
+        .expect("coraline_impact should succeed");
+    let impact: ImpactResponse = serde_json::from_value(result).expect("Impact response");
+    assert!(impact.nodes.iter().any(|n| n.id == caller));
+    let untested: Vec<&str> = impact.untested.iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(untested, [caller.as_str()]);
+
+    let options = BuildContextOptions {
+        max_nodes: Some(20),
+        max_code_blocks: Some(10),
+        max_code_block_size: Some(1000),
+        include_code: Some(true),
+        format: Some(ContextFormat::Markdown),
+        search_limit: None,
+        traversal_depth: Some(1),
+        min_score: None,
+    };
+    let markdown = context::build_context(project_path, "subtract", &options)
+        .expect("Failed to build context");
+    assert!(
+        markdown
+            .lines()
+            .any(|l| l.contains("**subtract**") && l.ends_with("(untested)")),
+        "subtract should be flagged untested:\n{markdown}"
+    );
+    assert!(
+        !markdown
+            .lines()
+            .any(|l| l.contains("**getHistory**") && l.ends_with("(untested)"))
+    );
+}
@@ -0,0 +1,107 @@
+//! Integration tests for the daemon
+#![allow(clippy::expect_used)]
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+use std::time::Duration;
+
+use coraline::daemon::{self, QueryHit, Request};
+use coraline::extraction::SyncResult;
+use coraline::tools::create_default_registry;
+use coraline::types::{NodeKind, SearchOptions};
+
+fn wait_for_daemon(project: &Path) -> daemon::Client {
+    (0..100)
+        .find_map(|_| {
+            let client = daemon::connect(project);
+            if client.is_none() {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            client
+        })
+        .expect("daemon did not start")
+}
+
+#[test]
+fn test_daemon_serves_requests_until_stopped() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path().to_path_buf();
+    // A socket left behind by a daemon that died.
+    drop(UnixListener::bind(daemon::socket_path(&project)).expect("Failed to bind"));
+    assert!(daemon::connect(&project).is_none());
+
+    let server = {
+        let project = project.clone();
+        std::thread::spawn(move || daemon::serve(&project))
+    };
+    let mut client = wait_for_daemon(&project);
+    assert!(daemon::serve(&project).is_err(), "one daemon per project");
+
+    let info = daemon::status(&project).expect("daemon should report status");
+    assert_eq!(info.pid, std::process::id());
+
+    let options = SearchOptions {
+        kinds: Some(vec![NodeKind::Function]),
+        limit: Some(5),
+        ..SearchOptions::default()
+    };
+    let hits: Vec<QueryHit> = client
+        .call_as(&Request::Query {
+            search: "multiply".to_string(),
+            options: options.clone(),
+            tags: Vec::new(),
+        })
+        .expect("query should succeed");
+    let local =
+        daemon::query(&project, "multiply", &options, &[]).expect("local query should succeed");
+    assert!(!hits.is_empty());
+    assert_eq!(
+        hits.iter().map(|h| &h.result.node.id).collect::<Vec<_>>(),
+        local.iter().map(|h| &h.result.node.id).collect::<Vec<_>>()
+    );
+
+    std::fs::write(
+        project.join("src/extra.ts"),
+        "export function triple(x: number): number {\n    return x * 3;\n}\n",
+    )
+    .expect("Failed to write file");
+    let synced: SyncResult = client.call_as(&Request::Sync).expect("sync should succeed");
+    assert_eq!(synced.files_added, 1);
+
+    // Tool calls from a delegating registry run on the daemon.
+    let mut registry = create_default_registry(&project);
+    registry.delegate_to_daemon(&project);
+    let found = registry
+        .execute(
+            "coraline_search",
+            serde_json::json!({ "query": "triple", "kind": "function" }),
+        )
+        .expect("search should succeed");
+    assert!(found.to_string().contains("triple"));
+
+    let err = client
+        .call(&Request::Tool {
+            name: "coraline_no_such_tool".to_string(),
+            arguments: serde_json::Value::Null,
+        })
+        .expect_err("unknown tools are an error");
+    assert_eq!(err.code, "not_found");
+
+    assert!(daemon::stop(&project));
+    server
+        .join()
+        .expect("daemon thread panicked")
+        .expect("daemon should stop cleanly");
+    assert!(!daemon::socket_path(&project).exists());
+    assert!(daemon::connect(&project).is_none());
+
+    // With no daemon the registry runs the call itself.
+    assert!(
+        registry
+            .execute("coraline_search", serde_json::json!({ "query": "triple" }))
+            .is_ok()
+    );
+}
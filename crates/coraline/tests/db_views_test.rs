@@ -0,0 +1,53 @@
+//! Integration tests for the analytical SQL views
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::db;
+
+fn count(conn: &rusqlite::Connection, sql: &str) -> i64 {
+    conn.query_row(sql, [], |row| row.get(0))
+        .expect("Failed to run count query")
+}
+
+#[test]
+fn test_analytical_views_summarise_the_graph() {
+    let temp = common::setup_indexed_project();
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+
+    db::create_analytical_views(&conn).expect("Failed to create views");
+    // Re-running replaces the definitions instead of failing.
+    db::create_analytical_views(&conn).expect("Failed to recreate views");
+
+    assert_eq!(
+        count(&conn, "SELECT COUNT(*) FROM call_pairs"),
+        count(&conn, "SELECT COUNT(*) FROM edges WHERE kind = 'calls'")
+    );
+    assert_eq!(
+        count(&conn, "SELECT COUNT(*) FROM symbol_metrics"),
+        count(&conn, "SELECT COUNT(*) FROM nodes")
+    );
+    assert!(
+        count(
+            &conn,
+            "SELECT COUNT(*) FROM file_dependencies
+              WHERE source_file = 'src/index.ts' AND target_file = 'src/math.ts'"
+        ) == 1,
+        "index.ts depends on math.ts"
+    );
+    assert!(
+        count(
+            &conn,
+            "SELECT child_count FROM symbol_metrics WHERE name = 'Calculator' AND kind = 'class'"
+        ) > 0
+    );
+
+    db::drop_analytical_views(&conn).expect("Failed to drop views");
+    assert_eq!(
+        count(
+            &conn,
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'view'"
+        ),
+        0
+    );
+}
@@ -0,0 +1,90 @@
+//! Integration tests for dead code detection
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::config::AnalysisConfig;
+use coraline::dead_code::{self, Confidence};
+use coraline::tools::responses::DeadCodeResponse;
+use coraline::{db, tools};
+use serde_json::json;
+
+fn candidates(project: &Path, cfg: &AnalysisConfig) -> Vec<(String, Confidence)> {
+    let conn = db::open_database(project).expect("Failed to open database");
+    dead_code::find_dead_code(&conn, cfg, Confidence::Low)
+        .expect("Failed to find dead code")
+        .candidates
+        .into_iter()
+        .map(|c| (c.node.name, c.confidence))
+        .collect()
+}
+
+#[test]
+fn test_dead_code_candidates_and_confidence() {
+    let temp_dir = common::with_files(&[
+        (
+            "src/app.ts",
+            "import { used } from './lib';\n\
+             function main() { return used() + view.render(); }\n\
+             function forgotten() { return 1; }\n\
+             function handleRequest() { return 2; }\n",
+        ),
+        (
+            "src/lib.ts",
+            "export function used() { return helper(); }\n\
+             function helper() { return 4; }\n\
+             export function unusedExport() { return 5; }\n",
+        ),
+        ("src/table.ts", "function render() { return 3; }\n"),
+        ("src/chart.ts", "function render() { return 7; }\n"),
+        (
+            "src/app.test.ts",
+            "function testForgotten() { return 6; }\n",
+        ),
+    ]);
+    let project = temp_dir.path();
+
+    let found = candidates(project, &AnalysisConfig::default());
+    let confidence = |name: &str| found.iter().find(|(n, _)| n == name).map(|(_, c)| *c);
+    assert_eq!(confidence("forgotten"), Some(Confidence::High), "{found:?}");
+    assert_eq!(confidence("handleRequest"), Some(Confidence::High));
+    assert_eq!(confidence("unusedExport"), Some(Confidence::Medium));
+    // `view.render()` matches either `render`, so it stays unresolved and
+    // both may be used.
+    assert_eq!(confidence("render"), Some(Confidence::Low));
+    assert_eq!(found.iter().filter(|(n, _)| n == "render").count(), 2);
+    // Used, imported, entry point, or test code.
+    for name in ["used", "helper", "main", "testForgotten"] {
+        assert_eq!(confidence(name), None, "{name} listed: {found:?}");
+    }
+    assert_eq!(found.first().map(|(_, c)| *c), Some(Confidence::High));
+
+    let found = candidates(
+        project,
+        &AnalysisConfig {
+            entry_points: vec!["handle*".to_string()],
+            public_api: vec!["src/lib.ts".to_string()],
+        },
+    );
+    assert!(found.iter().all(|(n, _)| n != "handleRequest"));
+    assert!(found.iter().all(|(n, _)| n != "unusedExport"));
+    assert!(found.iter().any(|(n, _)| n == "forgotten"));
+
+    let registry = tools::create_default_registry(project);
+    let result = registry
+        .execute(
+            "coraline_dead_code",
+            json!({ "min_confidence": "high", "limit": 1 }),
+        )
+        .expect("coraline_dead_code failed");
+    let response: DeadCodeResponse = serde_json::from_value(result).expect("response");
+    assert_eq!(response.total, 2, "{response:?}");
+    assert_eq!(response.candidates.len(), 1);
+    assert_eq!(
+        response.candidates.first().map(|c| c.confidence),
+        Some(Confidence::High)
+    );
+    assert!(response.entry_points_skipped >= 2);
+}
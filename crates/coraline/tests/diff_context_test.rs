@@ -0,0 +1,100 @@
+//! Integration tests for review context of a git diff
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::diff_context::{self, DiffContextOptions};
+use coraline::tools::create_default_registry;
+use coraline::types::ContextFormat;
+use coraline::{config, db, extraction};
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+fn setup_repository() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp_dir.path();
+    common::copy_fixture(project_path);
+    std::fs::write(project_path.join(".gitignore"), ".coraline/\n").expect("Failed to write");
+    common::git(project_path, &["init", "-q", "-b", "main"]);
+    common::git(project_path, &["add", "-A"]);
+    common::git(project_path, &["commit", "-q", "-m", "Add fixture"]);
+
+    db::initialize_database(project_path).expect("Failed to initialize database");
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+    temp_dir
+}
+
+#[test]
+fn test_diff_context_maps_hunks_to_symbols() {
+    let temp_dir = setup_repository();
+    let project = temp_dir.path();
+    let clean = diff_context::build_diff_context(project, &DiffContextOptions::default())
+        .expect("Failed to build diff context");
+    assert!(clean.files.is_empty());
+    assert!(clean.changed.is_empty());
+
+    let math = project.join("src/math.ts");
+    let source = std::fs::read_to_string(&math).expect("Failed to read math.ts");
+    std::fs::write(&math, source.replace("return a * b;", "return b * a;"))
+        .expect("Failed to write math.ts");
+    std::fs::write(
+        project.join("src/extra.ts"),
+        "export function triple(x: number): number {\n    return x * 3;\n}\n",
+    )
+    .expect("Failed to write file");
+    let cfg = config::create_default_config(project);
+    extraction::sync(project, &cfg, None).expect("Failed to sync");
+
+    let context = diff_context::build_diff_context(project, &DiffContextOptions::default())
+        .expect("Failed to build diff context");
+    assert_eq!(context.base, "HEAD");
+    assert_eq!(context.files, vec!["src/extra.ts", "src/math.ts"]);
+    let names: Vec<&str> = context
+        .changed
+        .iter()
+        .map(|s| s.node.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["triple", "multiply"]);
+
+    let multiply = context.changed.get(1).expect("multiply changed");
+    assert_eq!(multiply.node.file_path, "src/math.ts");
+    let callers: Vec<&str> = multiply.callers.iter().map(|n| n.name.as_str()).collect();
+    assert!(callers.contains(&"quickMath"), "callers: {callers:?}");
+    assert_eq!(context.code_blocks.len(), 2);
+    assert!(
+        context
+            .code_blocks
+            .get(1)
+            .is_some_and(|block| block.content.contains("return b * a;"))
+    );
+
+    let markdown = diff_context::render_diff_context(&context, ContextFormat::Markdown);
+    assert!(markdown.contains("### Changed Symbols"));
+    assert!(markdown.contains("Called by:"));
+
+    let registry = create_default_registry(project);
+    let result = registry
+        .execute(
+            "coraline_diff_context",
+            json!({ "format": "json", "include_code": false }),
+        )
+        .expect("diff context should succeed");
+    assert_eq!(
+        result
+            .get("changed")
+            .and_then(Value::as_array)
+            .map(Vec::len),
+        Some(2)
+    );
+    assert!(
+        result
+            .get("code_blocks")
+            .and_then(Value::as_array)
+            .is_some_and(Vec::is_empty)
+    );
+    let err = registry
+        .execute("coraline_diff_context", json!({ "rev": "no-such-rev" }))
+        .expect_err("an unknown revision is an error");
+    assert!(err.message.contains("Failed to build diff context"));
+}
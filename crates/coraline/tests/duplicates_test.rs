@@ -0,0 +1,176 @@
+//! Integration tests for `coraline analyze duplicates`
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use coraline::db;
+use coraline::duplicates::{self, DuplicateOptions};
+use serde_json::Value;
+
+const PROJECT_FILES: &[(&str, &str)] = &[
+    (
+        "src/orders.rs",
+        r#"
+pub fn order_total(items: &[Item], discount: u32) -> u64 {
+    let mut total = 0;
+    for item in items {
+        if item.quantity == 0 {
+            continue;
+        }
+        total += item.price * u64::from(item.quantity);
+    }
+    if discount > 0 {
+        total -= total * u64::from(discount) / 100;
+    }
+    // Orders never go negative.
+    total.max(0)
+}
+
+pub fn greet(name: &str) -> String {
+    format!("hello {name}")
+}
+"#,
+    ),
+    (
+        "src/invoices.rs",
+        r"
+pub fn invoice_sum(lines: &[Line], rebate: u32) -> u64 {
+    let mut sum = 0;
+    for line in lines {
+        if line.count == 0 {
+            continue;
+        }
+        sum += line.cost * u64::from(line.count);
+    }
+    if rebate > 0 {
+        sum -= sum * u64::from(rebate) / 100;
+    }
+    sum.max(7)
+}
+",
+    ),
+    (
+        "src/report.rs",
+        r"
+pub fn summarize(rows: &[Row]) -> String {
+    let mut out = String::new();
+    while let Some(row) = rows.iter().next() {
+        match row.kind {
+            Kind::Header => out.push_str(&row.title),
+            Kind::Body => out.push('\n'),
+        }
+    }
+    out
+}
+",
+    ),
+    (
+        "web/cart.ts",
+        r"
+export function cartTotal(items: Item[], discount: number): number {
+  let total = 0;
+  for (const item of items) {
+    if (item.quantity === 0) {
+      continue;
+    }
+    total += item.price * item.quantity;
+  }
+  return total;
+}
+",
+    ),
+    (
+        "web/wishlist.ts",
+        r"
+export function wishlistTotal(entries: Entry[], off: number): number {
+  let sum = 0;
+  for (const entry of entries) {
+    if (entry.amount === 0) {
+      continue;
+    }
+    sum += entry.cost * entry.amount;
+  }
+  return sum;
+}
+",
+    ),
+];
+
+fn analyze_duplicates(project: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_coraline"))
+        .args(["analyze", "-p"])
+        .arg(project)
+        .arg("duplicates")
+        .args(args)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run coraline")
+}
+
+#[test]
+fn test_renamed_copies_cluster_per_language() {
+    let temp = common::with_files(PROJECT_FILES);
+    let conn = db::open_database(temp.path()).expect("open database");
+    let report = duplicates::find_duplicates(
+        temp.path(),
+        &conn,
+        DuplicateOptions {
+            min_tokens: 30,
+            threshold: 0.8,
+        },
+    )
+    .expect("duplicate analysis");
+
+    let clusters: Vec<Vec<&str>> = report
+        .clusters
+        .iter()
+        .map(|c| c.members.iter().map(|n| n.name.as_str()).collect())
+        .collect();
+    assert_eq!(
+        clusters,
+        [
+            vec!["invoice_sum", "order_total"],
+            vec!["cartTotal", "wishlistTotal"]
+        ]
+    );
+    assert!(report.clusters.iter().all(|c| c.similarity >= 0.8));
+}
+
+#[test]
+fn test_cli_threshold_and_min_tokens() {
+    let temp = common::with_files(PROJECT_FILES);
+    let output = analyze_duplicates(temp.path(), &["--min-tokens", "30", "--json"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(report.get("total"), Some(&Value::from(2)));
+    assert_eq!(
+        report.pointer("/clusters/0/members/0/file_path"),
+        Some(&Value::from("src/invoices.rs"))
+    );
+
+    let output = analyze_duplicates(temp.path(), &["--min-tokens", "30", "--limit", "1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("2 copies"), "{stdout}");
+    assert!(
+        stdout.contains("src/orders.rs:2-15  order_total"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("2 cluster(s), 1 shown"), "{stdout}");
+
+    let output = analyze_duplicates(temp.path(), &["--min-tokens", "500"]);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("No duplicates"),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let output = analyze_duplicates(temp.path(), &["--threshold", "2"]);
+    assert!(!output.status.success());
+}
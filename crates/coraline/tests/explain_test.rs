@@ -0,0 +1,120 @@
+//! Integration tests for `coraline_explain`
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::memory::MemoryManager;
+use coraline::tools::responses::ExplainResponse;
+use coraline::types::{Edge, EdgeKind, NodeKind};
+use coraline::{db, tools};
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+fn setup_project() -> TempDir {
+    let temp_dir = common::fixture_project();
+    let project_path = temp_dir.path();
+    common::write(
+        project_path,
+        "src/shapes.ts",
+        "export class Shape {\n  area(): number {\n    return 0;\n  }\n}\n\n\
+         export class Square extends Shape {\n  side = 1;\n}\n",
+    );
+
+    common::git(project_path, &["init", "-q"]);
+    common::git(project_path, &["add", "src"]);
+    common::git(project_path, &["commit", "-q", "-m", "Initial import"]);
+
+    common::index(temp_dir, &[])
+}
+
+fn explain(project: &Path, params: Value) -> ExplainResponse {
+    let registry = tools::create_default_registry(project);
+    let result = registry
+        .execute("coraline_explain", params)
+        .expect("coraline_explain failed");
+    serde_json::from_value(result).expect("response should match ExplainResponse")
+}
+
+#[test]
+fn test_explain_bundles_source_callers_memories_and_commits() {
+    let temp_dir = setup_project();
+    let project = temp_dir.path();
+    MemoryManager::new(project)
+        .expect("Failed to open memories")
+        .write_memory(
+            "math_notes",
+            "# Notes\n\nmultiply() is the hot path; keep it branch-free.\nmultiplyAll is unrelated.\n",
+        )
+        .expect("Failed to write memory");
+
+    let response = explain(project, json!({ "name": "multiply", "file": "math.ts" }));
+    assert_eq!(response.symbol.name, "multiply");
+    assert!(
+        response
+            .symbol
+            .body
+            .as_deref()
+            .is_some_and(|b| b.contains("return a * b")),
+        "{:?}",
+        response.symbol.body
+    );
+    assert!(response.callers.iter().any(|c| c.node.name == "quickMath"));
+
+    assert_eq!(response.memories.len(), 1);
+    let memory = response.memories.first().expect("one memory");
+    assert_eq!(memory.name, "math_notes");
+    assert!(memory.excerpt.starts_with("multiply()"));
+
+    assert_eq!(response.recent_commits.len(), 1);
+    let commit = response.recent_commits.first().expect("one commit");
+    assert_eq!(commit.summary, "Initial import");
+    assert_eq!(commit.author, "Test");
+
+    let skipped = explain(
+        project,
+        json!({ "name": "multiply", "file": "math.ts", "commits": 0 }),
+    );
+    assert!(skipped.recent_commits.is_empty());
+}
+
+#[test]
+fn test_explain_lists_implementors_and_supertypes() {
+    let temp_dir = setup_project();
+    let project = temp_dir.path();
+
+    // Extraction does not record heritage yet; add the edge as a resolver
+    // would.
+    let mut conn = db::open_database(project).expect("Failed to open database");
+    let class_id = |name: &str| {
+        db::get_nodes_by_file(&conn, "src/shapes.ts", Some(NodeKind::Class))
+            .expect("Failed to read nodes")
+            .into_iter()
+            .find(|n| n.name == name)
+            .expect("the symbol should be indexed")
+            .id
+    };
+    let (shape_id, square_id) = (class_id("Shape"), class_id("Square"));
+    db::insert_edges(
+        &mut conn,
+        &[Edge {
+            source: square_id,
+            target: shape_id,
+            kind: EdgeKind::Extends,
+            metadata: None,
+            line: Some(7),
+            column: Some(0),
+        }],
+    )
+    .expect("Failed to insert edge");
+
+    let shape = explain(project, json!({ "name": "Shape" }));
+    let implementors: Vec<&str> = shape.implementors.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(implementors, ["Square"]);
+    assert!(shape.supertypes.is_empty());
+
+    let square = explain(project, json!({ "name": "Square" }));
+    let supertypes: Vec<&str> = square.supertypes.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(supertypes, ["Shape"]);
+}
@@ -0,0 +1,329 @@
+//! Integration tests for incremental graph export
+#![allow(clippy::expect_used)]
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use coraline::export::GraphDelta;
+use coraline::sensitive::SensitiveFiles;
+use coraline::types::{EdgeKind, Node};
+use coraline::{config, db, export, extraction, scip};
+use tempfile::TempDir;
+
+#[test]
+fn test_export_since_checkpoint_only_contains_changes() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp_dir.path();
+    db::initialize_database(project_path).expect("Failed to initialize database");
+
+    let fixture_src = Path::new("tests/fixtures/typescript-simple");
+    let fixture_dst = project_path.join("src");
+    std::fs::create_dir_all(&fixture_dst).expect("Failed to create fixture directory");
+    for entry in std::fs::read_dir(fixture_src).expect("Failed to read fixture directory") {
+        let entry = entry.expect("Failed to read directory entry");
+        std::fs::copy(entry.path(), fixture_dst.join(entry.file_name()))
+            .expect("Failed to copy fixture file");
+    }
+    std::fs::write(fixture_dst.join("extra.ts"), "export const extra = 1;\n")
+        .expect("Failed to write extra.ts");
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to do initial index");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let full = export::export_delta(&conn, 0, None, &SensitiveFiles::default())
+        .expect("Failed to export full graph");
+    let checkpoint = export::record_checkpoint(project_path, &conn, "nightly", full.until)
+        .expect("Failed to record checkpoint");
+    assert!(checkpoint.files.contains(&"src/extra.ts".to_string()));
+
+    std::thread::sleep(std::time::Duration::from_millis(10));
+
+    let math_file = fixture_dst.join("math.ts");
+    let mut content = std::fs::read_to_string(&math_file).expect("Failed to read math.ts");
+    content.push_str("\nexport function cube(x: number): number {\n    return x * x * x;\n}\n");
+    std::fs::write(&math_file, content).expect("Failed to write math.ts");
+    std::fs::remove_file(fixture_dst.join("extra.ts")).expect("Failed to remove extra.ts");
+    extraction::sync(project_path, &cfg, None).expect("Failed to sync project");
+
+    let stored = export::get_checkpoint(project_path, "nightly")
+        .expect("Failed to load checkpoints")
+        .expect("Checkpoint should exist");
+    let delta = export::export_delta(
+        &conn,
+        stored.created_at,
+        Some(&stored.files),
+        &SensitiveFiles::default(),
+    )
+    .expect("Failed to export delta");
+
+    let changed: Vec<&str> = delta.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(changed, vec!["src/math.ts"]);
+    assert_eq!(delta.removed_files, vec!["src/extra.ts".to_string()]);
+    assert!(delta.nodes.iter().all(|n| n.file_path == "src/math.ts"));
+    assert!(delta.nodes.iter().any(|n| n.name == "cube"));
+    assert!(delta.nodes.len() < full.nodes.len());
+}
+
+/// A graph database fed with deltas as [`export::to_cypher`] applies them.
+#[derive(Default)]
+struct Replica {
+    nodes: HashMap<String, Node>,
+    edges: BTreeSet<(String, String, EdgeKind)>,
+}
+
+impl Replica {
+    fn apply(&mut self, delta: &GraphDelta) {
+        let replaced: BTreeSet<&str> = delta
+            .files
+            .iter()
+            .map(|f| f.path.as_str())
+            .chain(delta.removed_files.iter().map(String::as_str))
+            .collect();
+        // DETACH DELETE takes the edges on both ends with the nodes.
+        self.nodes
+            .retain(|_, node| !replaced.contains(node.file_path.as_str()));
+        let nodes = &self.nodes;
+        self.edges
+            .retain(|(source, target, _)| nodes.contains_key(source) && nodes.contains_key(target));
+        for node in &delta.nodes {
+            self.nodes.insert(node.id.clone(), node.clone());
+        }
+        // MATCH ... CREATE only links nodes that exist.
+        for edge in &delta.edges {
+            if self.nodes.contains_key(&edge.source) && self.nodes.contains_key(&edge.target) {
+                self.edges
+                    .insert((edge.source.clone(), edge.target.clone(), edge.kind));
+            }
+        }
+    }
+
+    fn calls(&self, from: &str, to: &str) -> bool {
+        self.edges.iter().any(|(source, target, kind)| {
+            *kind == EdgeKind::Calls
+                && self.nodes.get(source).is_some_and(|node| node.name == from)
+                && self.nodes.get(target).is_some_and(|node| node.name == to)
+        })
+    }
+}
+
+#[test]
+fn test_delta_keeps_edges_into_modified_files() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp_dir.path();
+    db::initialize_database(project_path).expect("Failed to initialize database");
+    let src = project_path.join("src");
+    std::fs::create_dir_all(&src).expect("Failed to create src");
+    std::fs::write(
+        src.join("a.ts"),
+        "import { helper } from './b';\n\nexport function run(): number {\n    return helper();\n}\n",
+    )
+    .expect("Failed to write a.ts");
+    std::fs::write(
+        src.join("b.ts"),
+        "export function helper(): number {\n    return 1;\n}\n",
+    )
+    .expect("Failed to write b.ts");
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let full = export::export_delta(&conn, 0, None, &SensitiveFiles::default())
+        .expect("Failed to export full graph");
+    let mut replica = Replica::default();
+    replica.apply(&full);
+    assert!(replica.calls("run", "helper"));
+
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    std::fs::write(
+        src.join("b.ts"),
+        "// Shared helpers.\n\nexport function helper(): number {\n    return 2;\n}\n",
+    )
+    .expect("Failed to write b.ts");
+    extraction::sync(project_path, &cfg, None).expect("Failed to sync project");
+
+    let delta = export::export_delta(&conn, full.until, None, &SensitiveFiles::default())
+        .expect("Failed to export delta");
+    let changed: Vec<&str> = delta.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(changed, vec!["src/b.ts"]);
+    replica.apply(&delta);
+    assert!(replica.calls("run", "helper"), "run -> helper was dropped");
+}
+
+#[test]
+fn test_export_cypher_labels_nodes_and_edges_by_kind() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp_dir.path();
+    db::initialize_database(project_path).expect("Failed to initialize database");
+
+    let fixture_src = Path::new("tests/fixtures/typescript-simple");
+    let fixture_dst = project_path.join("src");
+    std::fs::create_dir_all(&fixture_dst).expect("Failed to create fixture directory");
+    for entry in std::fs::read_dir(fixture_src).expect("Failed to read fixture directory") {
+        let entry = entry.expect("Failed to read directory entry");
+        std::fs::copy(entry.path(), fixture_dst.join(entry.file_name()))
+            .expect("Failed to copy fixture file");
+    }
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let mut delta = export::export_delta(&conn, 0, None, &SensitiveFiles::default())
+        .expect("Failed to export graph");
+    let quoted = delta
+        .nodes
+        .iter_mut()
+        .find(|n| n.name == "add")
+        .expect("Expected the add function");
+    quoted.docstring = Some("Adds 'a' and\nb".to_string());
+
+    let cypher = export::to_cypher(&delta);
+    let statements: Vec<&str> = cypher.lines().filter(|l| !l.starts_with("//")).collect();
+
+    let first = statements.first().expect("a statement");
+    assert!(first.starts_with("MATCH (n:CodeNode) WHERE n.file_path IN ["));
+    assert!(first.contains("'src/math.ts'"));
+    assert!(statements.iter().all(|s| s.ends_with(';')));
+    assert_eq!(
+        statements
+            .iter()
+            .filter(|s| s.starts_with("CREATE (:CodeNode:"))
+            .count(),
+        delta.nodes.len()
+    );
+    assert!(
+        statements
+            .iter()
+            .any(|s| s.starts_with("CREATE (:CodeNode:Class {") && s.contains("name: 'Calculator'"))
+    );
+    assert!(cypher.contains(r"docstring: 'Adds \'a\' and\nb'"));
+    assert!(
+        statements
+            .iter()
+            .any(|s| s.contains("CREATE (a)-[:CONTAINS"))
+    );
+}
+
+#[test]
+fn test_export_scip_places_definitions_and_references() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp_dir.path();
+    db::initialize_database(project_path).expect("Failed to initialize database");
+
+    let fixture_src = Path::new("tests/fixtures/typescript-simple");
+    let fixture_dst = project_path.join("src");
+    std::fs::create_dir_all(&fixture_dst).expect("Failed to create fixture directory");
+    for entry in std::fs::read_dir(fixture_src).expect("Failed to read fixture directory") {
+        let entry = entry.expect("Failed to read directory entry");
+        std::fs::copy(entry.path(), fixture_dst.join(entry.file_name()))
+            .expect("Failed to copy fixture file");
+    }
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let index = scip::build_scip_index(project_path, &conn, &SensitiveFiles::default())
+        .expect("Failed to build SCIP index");
+    let math = index
+        .documents
+        .iter()
+        .find(|d| d.relative_path == "src/math.ts")
+        .expect("Expected a document for math.ts");
+    assert_eq!(math.language, "TypeScript");
+
+    let add = "coraline . . . src/`math.ts`/add().";
+    let definition = math
+        .occurrences
+        .iter()
+        .find(|o| o.symbol == add && o.symbol_roles == scip::ROLE_DEFINITION)
+        .expect("Expected a definition of add");
+    // `export function add(` on the fifth line.
+    assert_eq!(definition.range, vec![4, 16, 19]);
+    assert!(
+        math.symbols
+            .iter()
+            .any(|s| s.symbol == add && s.display_name == "add")
+    );
+    assert!(
+        math.occurrences
+            .iter()
+            .any(|o| o.symbol == add && o.symbol_roles == 0),
+        "Calculator.add calls add"
+    );
+    assert!(math.symbols.iter().any(|s| s.symbol
+        == "coraline . . . src/`math.ts`/Calculator#add()."
+        && s.enclosing_symbol == "coraline . . . src/`math.ts`/Calculator#"));
+
+    let encoded = index.encode();
+    assert_eq!(encoded.first(), Some(&0x0a), "Index.metadata comes first");
+    assert!(
+        encoded
+            .windows(add.len())
+            .any(|window| window == add.as_bytes())
+    );
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_export_parquet_writes_readable_tables() {
+    use arrow_array::RecordBatch;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp_dir.path();
+    db::initialize_database(project_path).expect("Failed to initialize database");
+
+    let fixture_src = Path::new("tests/fixtures/typescript-simple");
+    let fixture_dst = project_path.join("src");
+    std::fs::create_dir_all(&fixture_dst).expect("Failed to create fixture directory");
+    for entry in std::fs::read_dir(fixture_src).expect("Failed to read fixture directory") {
+        let entry = entry.expect("Failed to read directory entry");
+        std::fs::copy(entry.path(), fixture_dst.join(entry.file_name()))
+            .expect("Failed to copy fixture file");
+    }
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let delta = export::export_delta(&conn, 0, None, &SensitiveFiles::default())
+        .expect("Failed to export graph");
+    let out = project_path.join("parquet");
+    let written = export::write_parquet(&delta, &out).expect("Failed to write Parquet");
+    assert_eq!(written.len(), export::PARQUET_TABLES.len());
+
+    let read = |name: &str| {
+        let file = std::fs::File::open(out.join(name)).expect("Failed to open table");
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .expect("Failed to read Parquet metadata")
+            .build()
+            .expect("Failed to build Parquet reader");
+        reader
+            .map(|batch| batch.expect("Failed to read batch"))
+            .collect::<Vec<_>>()
+    };
+
+    let nodes = read("nodes.parquet");
+    assert_eq!(
+        nodes.iter().map(RecordBatch::num_rows).sum::<usize>(),
+        delta.nodes.len()
+    );
+    let schema = nodes.first().expect("a batch of nodes").schema();
+    assert!(schema.field_with_name("qualified_name").is_ok());
+    assert!(
+        schema
+            .field_with_name("docstring")
+            .expect("docstring column")
+            .is_nullable()
+    );
+
+    let edges = read("edges.parquet");
+    assert_eq!(
+        edges.iter().map(RecordBatch::num_rows).sum::<usize>(),
+        delta.edges.len()
+    );
+    let files = read("files.parquet");
+    assert_eq!(files.iter().map(RecordBatch::num_rows).sum::<usize>(), 3);
+}
@@ -1,8 +1,12 @@
 //! Integration tests for code extraction
 #![allow(clippy::expect_used)]
 
+mod common;
+
 use std::path::Path;
 
+use coraline::config::SearchConfig;
+use coraline::types::{Language, NodeKind, PathCaseSensitivity, SearchOptions};
 use coraline::{config, db, extraction};
 use tempfile::TempDir;
 
@@ -170,6 +174,342 @@ fn test_incremental_sync() {
     );
 }
 
+#[test]
+fn test_index_reports_progress_per_phase() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+    common::copy_fixture(project_path);
+
+    let events = std::cell::RefCell::new(Vec::new());
+    let record = |p: extraction::IndexProgress| events.borrow_mut().push(p);
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, Some(&record)).expect("Failed to index");
+
+    let events = events.into_inner();
+    let parsed: Vec<(usize, usize)> = events
+        .iter()
+        .filter(|p| p.phase == extraction::IndexPhase::Parsing && p.current_file.is_some())
+        .map(|p| (p.current, p.total))
+        .collect();
+    assert_eq!(parsed, vec![(1, 3), (2, 3), (3, 3)]);
+    let phases: Vec<extraction::IndexPhase> = events.iter().map(|p| p.phase).collect();
+    assert_eq!(phases.first(), Some(&extraction::IndexPhase::Scanning));
+    assert_eq!(phases.last(), Some(&extraction::IndexPhase::Resolving));
+}
+
+#[test]
+fn test_sync_ignores_touched_but_unchanged_files() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+
+    let fixture_src = Path::new("tests/fixtures/typescript-simple");
+    let fixture_dst = project_path.join("src");
+    std::fs::create_dir_all(&fixture_dst).expect("Failed to create fixture directory");
+
+    for entry in std::fs::read_dir(fixture_src).expect("Failed to read fixture directory") {
+        let entry = entry.expect("Failed to read directory entry");
+        let dest = fixture_dst.join(entry.file_name());
+        std::fs::copy(entry.path(), dest).expect("Failed to copy fixture file");
+    }
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to do initial index");
+
+    std::thread::sleep(std::time::Duration::from_millis(10));
+
+    // Rewrite a file with identical content: mtime changes, hash does not.
+    let math_file = fixture_dst.join("math.ts");
+    let content = std::fs::read_to_string(&math_file).expect("Failed to read math.ts file");
+    std::fs::write(&math_file, content).expect("Failed to rewrite math.ts file");
+
+    let first = extraction::sync(project_path, &cfg, None).expect("Failed to sync project");
+    assert_eq!(
+        first.files_modified, 0,
+        "Touch-only change is not a modification"
+    );
+    assert_eq!(first.files_hashed, 1, "Only the touched file is hashed");
+
+    // The refreshed metadata lets the follow-up status check and sync take
+    // the fast path.
+    let status = extraction::needs_sync(project_path, &cfg).expect("Failed to check sync state");
+    assert!(!status.is_stale(), "Index should be fresh after sync");
+    let second = extraction::sync(project_path, &cfg, None).expect("Failed to sync project");
+    assert_eq!(second.files_hashed, 0, "No file is hashed again");
+}
+
+#[test]
+fn test_sync_case_only_rename_replaces_record() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+
+    let src = project_path.join("src");
+    std::fs::create_dir_all(&src).expect("Failed to create src directory");
+    std::fs::write(
+        src.join("Math.ts"),
+        "export function add(a: number, b: number): number {\n    return a + b;\n}\n",
+    )
+    .expect("Failed to write Math.ts");
+
+    let mut cfg = config::create_default_config(project_path);
+    cfg.path_case = PathCaseSensitivity::Insensitive;
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to do initial index");
+
+    std::fs::rename(src.join("Math.ts"), src.join("math.ts")).expect("Failed to rename file");
+
+    let status = extraction::needs_sync(project_path, &cfg).expect("Failed to check sync state");
+    assert_eq!(status.files_added, 0, "Case-only rename is not a new file");
+    assert_eq!(
+        status.files_modified, 1,
+        "Case-only rename is a modification"
+    );
+
+    let result = extraction::sync(project_path, &cfg, None).expect("Failed to sync project");
+    assert_eq!(result.files_removed, 0);
+    assert_eq!(result.files_modified, 1);
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let paths: Vec<String> = db::list_files(&conn)
+        .expect("Failed to list files")
+        .into_iter()
+        .map(|f| f.path)
+        .collect();
+    assert_eq!(paths, vec!["src/math.ts".to_string()]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sync_runs_post_sync_command() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+
+    let src = project_path.join("src");
+    std::fs::create_dir_all(&src).expect("Failed to create src directory");
+    std::fs::write(src.join("a.ts"), "export function a() {}\n").expect("Failed to write a.ts");
+
+    let mut toml_cfg = config::CoralineConfig::default_config();
+    toml_cfg.sync.post_sync_command = Some("cat > .coraline/post-sync.json".to_string());
+    config::save_toml_config(project_path, &toml_cfg).expect("Failed to save config");
+
+    let cfg = config::create_default_config(project_path);
+    let payload_path = project_path.join(".coraline").join("post-sync.json");
+
+    // An empty sync leaves the graph untouched and must not fire the hook.
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to do initial index");
+    extraction::sync(project_path, &cfg, None).expect("Failed to sync project");
+    assert!(
+        !payload_path.exists(),
+        "Hook should not run for a no-op sync"
+    );
+
+    std::fs::write(src.join("b.ts"), "export function b() {}\n").expect("Failed to write b.ts");
+    extraction::sync(project_path, &cfg, None).expect("Failed to sync project");
+
+    let raw = std::fs::read_to_string(&payload_path).expect("Hook should write its stdin");
+    let payload: serde_json::Value = serde_json::from_str(&raw).expect("Payload should be JSON");
+    assert_eq!(
+        payload.get("event"),
+        Some(&serde_json::Value::from("sync_completed"))
+    );
+    assert_eq!(
+        payload.pointer("/result/files_added"),
+        Some(&serde_json::Value::from(1))
+    );
+}
+
+#[test]
+fn test_search_down_weights_noise_words() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+
+    let src = project_path.join("src");
+    std::fs::create_dir_all(&src).expect("Failed to create src directory");
+    std::fs::write(
+        src.join("store.ts"),
+        "export function get(key: string) { return key; }\n\
+         export function get_user(id: string) { return id; }\n\
+         export function load_user_profile(id: string) { return id; }\n",
+    )
+    .expect("Failed to write store.ts");
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let names: Vec<String> = db::search_nodes(&conn, "get user", None, 10)
+        .expect("Failed to search")
+        .into_iter()
+        .map(|r| r.node.name)
+        .collect();
+
+    assert_eq!(names.first().map(String::as_str), Some("get_user"));
+    let pos = |name: &str| {
+        names
+            .iter()
+            .position(|n| n == name)
+            .expect("Expected symbol in results")
+    };
+    assert!(
+        pos("load_user_profile") < pos("get"),
+        "A noise-only match should rank below an informative one: {names:?}"
+    );
+}
+
+#[test]
+fn test_search_prefers_language_named_in_query() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+
+    let src = project_path.join("src");
+    std::fs::create_dir_all(&src).expect("Failed to create src directory");
+    std::fs::write(
+        src.join("users.ts"),
+        "export function load_user(id: string) { return id; }\n",
+    )
+    .expect("Failed to write users.ts");
+    std::fs::write(
+        src.join("users.py"),
+        "def load_user(user_id):\n    return user_id\n",
+    )
+    .expect("Failed to write users.py");
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    for (query, expected) in [
+        ("load_user in the Python service", Language::Python),
+        ("TypeScript load_user", Language::TypeScript),
+    ] {
+        let top = db::search_nodes(&conn, query, None, 5)
+            .expect("Failed to search")
+            .into_iter()
+            .next()
+            .expect("Expected a search result");
+        assert_eq!(top.node.name, "load_user");
+        assert_eq!(top.node.language, expected, "query: {query}");
+    }
+}
+
+#[test]
+fn test_search_with_options_scopes_results() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+
+    for (path, source) in [
+        (
+            "src/users.ts",
+            "export function load_user(id: string) { return id; }\n",
+        ),
+        (
+            "src/users.py",
+            "def load_user(user_id):\n    return user_id\n",
+        ),
+        (
+            "lib/users.ts",
+            "function load_user(id: string) { return id; }\n",
+        ),
+    ] {
+        let file = project_path.join(path);
+        std::fs::create_dir_all(file.parent().expect("a parent")).expect("Failed to create dir");
+        std::fs::write(file, source).expect("Failed to write file");
+    }
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let search = |options: SearchOptions| -> Vec<String> {
+        let mut files: Vec<String> =
+            db::search_nodes_with_options(&conn, "load_user", &options, &SearchConfig::default())
+                .expect("Failed to search")
+                .into_iter()
+                .map(|r| r.node.file_path)
+                .collect();
+        files.sort();
+        files
+    };
+
+    let functions = SearchOptions {
+        kinds: Some(vec![NodeKind::Function]),
+        ..SearchOptions::default()
+    };
+    assert_eq!(search(functions.clone()).len(), 3);
+    assert_eq!(
+        search(SearchOptions {
+            languages: Some(vec![Language::Python]),
+            ..functions.clone()
+        }),
+        vec!["src/users.py"]
+    );
+    assert_eq!(
+        search(SearchOptions {
+            include_patterns: Some(vec!["lib/**".to_string()]),
+            ..functions.clone()
+        }),
+        vec!["lib/users.ts"]
+    );
+    assert_eq!(
+        search(SearchOptions {
+            languages: Some(vec![Language::TypeScript]),
+            exported_only: Some(true),
+            ..functions.clone()
+        }),
+        vec!["src/users.ts"]
+    );
+
+    let page = |offset| {
+        search(SearchOptions {
+            limit: Some(2),
+            offset: Some(offset),
+            ..functions.clone()
+        })
+    };
+    let (first, second) = (page(0), page(2));
+    assert_eq!((first.len(), second.len()), (2, 1));
+    assert!(!second.iter().any(|result| first.contains(result)));
+}
+
+#[test]
+fn test_sync_drops_vectors_of_replaced_nodes() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+
+    let src = project_path.join("src");
+    std::fs::create_dir_all(&src).expect("Failed to create src directory");
+    std::fs::write(src.join("a.ts"), "export function a() {}\n").expect("Failed to write a.ts");
+    std::fs::write(src.join("b.ts"), "export function b() {}\n").expect("Failed to write b.ts");
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to do initial index");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    conn.execute(
+        "INSERT INTO vectors (node_id, embedding, model, created_at)
+         SELECT id, x'00000000', 'test', 0 FROM nodes",
+        [],
+    )
+    .expect("Failed to seed vectors");
+    let count_vectors = || -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM vectors", [], |row| row.get(0))
+            .expect("Failed to count vectors")
+    };
+    let seeded = count_vectors();
+
+    // Shifting `a` down a line gives its nodes new IDs; removing `b` drops its nodes.
+    std::fs::write(src.join("a.ts"), "\nexport function a() {}\n").expect("Failed to rewrite a.ts");
+    std::fs::remove_file(src.join("b.ts")).expect("Failed to remove b.ts");
+    extraction::sync(project_path, &cfg, None).expect("Failed to sync project");
+
+    let orphaned: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM vectors WHERE node_id NOT IN (SELECT id FROM nodes)",
+            [],
+            |row| row.get(0),
+        )
+        .expect("Failed to count orphaned vectors");
+    assert_eq!(orphaned, 0, "Sync should drop vectors of vanished nodes");
+    assert!(count_vectors() < seeded);
+}
+
 #[test]
 fn test_cross_file_references() {
     let (_temp, project_root) = setup_test_db();
@@ -206,3 +546,124 @@ fn test_cross_file_references() {
 
     assert!(!edges.is_empty(), "Should have import edges");
 }
+
+#[test]
+fn test_sync_recovers_files_with_inconsistent_records() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+
+    let fixture_src = Path::new("tests/fixtures/typescript-simple");
+    let fixture_dst = project_path.join("src");
+    std::fs::create_dir_all(&fixture_dst).expect("Failed to create fixture directory");
+    for entry in std::fs::read_dir(fixture_src).expect("Failed to read fixture directory") {
+        let entry = entry.expect("Failed to read directory entry");
+        std::fs::copy(entry.path(), fixture_dst.join(entry.file_name()))
+            .expect("Failed to copy fixture file");
+    }
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to do initial index");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    assert!(
+        db::find_inconsistent_files(&conn)
+            .expect("Failed to check consistency")
+            .is_empty()
+    );
+    let nodes_before = db::get_all_nodes(&conn)
+        .expect("Failed to list nodes")
+        .len();
+
+    // Simulate interrupted writes: nodes without a file record, and a file
+    // record whose nodes were only partly stored.
+    conn.execute("DELETE FROM files WHERE path = 'src/math.ts'", [])
+        .expect("Failed to drop file record");
+    conn.execute(
+        "DELETE FROM nodes WHERE file_path = 'src/user.ts' AND kind != 'file'",
+        [],
+    )
+    .expect("Failed to drop nodes");
+    assert_eq!(
+        db::find_inconsistent_files(&conn).expect("Failed to check consistency"),
+        vec!["src/math.ts".to_string(), "src/user.ts".to_string()]
+    );
+
+    extraction::sync(project_path, &cfg, None).expect("Failed to sync project");
+
+    assert!(
+        db::find_inconsistent_files(&conn)
+            .expect("Failed to check consistency")
+            .is_empty()
+    );
+    assert_eq!(
+        db::get_all_nodes(&conn)
+            .expect("Failed to list nodes")
+            .len(),
+        nodes_before
+    );
+}
+
+#[test]
+fn test_sync_since_only_visits_files_git_reports() {
+    let (_temp, project_root) = setup_test_db();
+    let project_path = Path::new(&project_root);
+    common::copy_fixture(project_path);
+    let src = project_path.join("src");
+    std::fs::write(project_path.join(".gitignore"), ".coraline/\n").expect("Failed to write");
+    common::git(project_path, &["init", "-q"]);
+    common::git(project_path, &["add", "-A"]);
+    common::git(project_path, &["commit", "-q", "-m", "Add fixture"]);
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to do initial index");
+
+    let math_file = src.join("math.ts");
+    let mut content = std::fs::read_to_string(&math_file).expect("Failed to read math.ts file");
+    content.push_str(
+        "\nexport function power(x: number, y: number): number {\n    return x ** y;\n}\n",
+    );
+    std::fs::write(&math_file, content).expect("Failed to write math.ts file");
+    std::fs::remove_file(src.join("user.ts")).expect("Failed to remove user.ts");
+    common::git(project_path, &["add", "-A"]);
+    common::git(
+        project_path,
+        &["commit", "-q", "-m", "Add power, drop user"],
+    );
+    std::fs::write(src.join("extra.ts"), "export function extra() {}\n").expect("Failed to write");
+
+    // A range lists only what its commits changed.
+    let ranged = extraction::sync_since(project_path, &cfg, "HEAD~1..HEAD", None)
+        .expect("Failed to sync range");
+    assert_eq!(ranged.files_checked, 1);
+    assert_eq!(
+        (
+            ranged.files_added,
+            ranged.files_modified,
+            ranged.files_removed
+        ),
+        (0, 1, 1)
+    );
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    assert!(
+        db::get_file_record(&conn, "src/extra.ts")
+            .expect("Failed to read file record")
+            .is_none()
+    );
+
+    // A single revision is compared with the working tree, untracked files
+    // included.
+    let since_head =
+        extraction::sync_since(project_path, &cfg, "HEAD", None).expect("Failed to sync");
+    assert_eq!(since_head.files_checked, 1);
+    assert_eq!(since_head.files_added, 1);
+    assert!(
+        !db::search_nodes(&conn, "power", None, 10)
+            .expect("Failed to search")
+            .is_empty()
+    );
+
+    for bad in ["--output=leak", "no-such-rev"] {
+        assert!(extraction::sync_since(project_path, &cfg, bad, None).is_err());
+    }
+    assert!(!project_path.join("leak").exists());
+}
@@ -1,9 +1,12 @@
 //! Integration tests for graph traversal operations
 #![allow(clippy::expect_used)]
 
+use std::collections::HashMap;
 use std::path::Path;
 
-use coraline::types::TraversalOptions;
+use coraline::types::{
+    Edge, EdgeKind, Language, Node, NodeKind, TraversalDirection, TraversalOptions,
+};
 use coraline::{config, db, extraction, graph};
 use tempfile::TempDir;
 
@@ -61,6 +64,9 @@ fn test_graph_traversal_basic() {
         direction: None,
         limit: None,
         include_start: Some(true),
+        edge_costs: None,
+        incoming_cost_factor: None,
+        min_confidence: None,
     };
 
     let subgraph = graph::build_subgraph(&conn, std::slice::from_ref(calculator_id), &options)
@@ -97,6 +103,9 @@ fn test_subgraph_with_depth_limit() {
         direction: None,
         limit: None,
         include_start: Some(true),
+        edge_costs: None,
+        incoming_cost_factor: None,
+        min_confidence: None,
     };
     let subgraph_1 = graph::build_subgraph(&conn, std::slice::from_ref(root_id), &options_1)
         .expect("Failed to build subgraph with depth 1");
@@ -110,6 +119,9 @@ fn test_subgraph_with_depth_limit() {
         direction: None,
         limit: None,
         include_start: Some(true),
+        edge_costs: None,
+        incoming_cost_factor: None,
+        min_confidence: None,
     };
     let subgraph_2 = graph::build_subgraph(&conn, std::slice::from_ref(root_id), &options_2)
         .expect("Failed to build subgraph with depth 2");
@@ -174,6 +186,9 @@ fn test_multiple_roots_subgraph() {
             direction: None,
             limit: None,
             include_start: Some(true),
+            edge_costs: None,
+            incoming_cost_factor: None,
+            min_confidence: None,
         };
 
         let subgraph = graph::build_subgraph(&conn, &roots, &options)
@@ -183,3 +198,311 @@ fn test_multiple_roots_subgraph() {
         assert!(subgraph.roots.len() >= 2, "Should have multiple roots");
     }
 }
+
+fn synthetic_node(id: &str, kind: NodeKind, line: i64) -> Node {
+    Node {
+        id: id.to_string(),
+        kind,
+        name: id.to_string(),
+        qualified_name: id.to_string(),
+        file_path: "src/lib.rs".to_string(),
+        language: Language::Rust,
+        start_line: line,
+        end_line: line,
+        start_column: 0,
+        end_column: 0,
+        docstring: None,
+        signature: None,
+        visibility: None,
+        is_exported: false,
+        is_async: false,
+        is_static: false,
+        is_abstract: false,
+        decorators: None,
+        type_parameters: None,
+        updated_at: 0,
+    }
+}
+
+fn synthetic_edge(source: &str, target: &str, kind: EdgeKind, line: i64) -> Edge {
+    Edge {
+        source: source.to_string(),
+        target: target.to_string(),
+        kind,
+        metadata: None,
+        line: Some(line),
+        column: Some(0),
+    }
+}
+
+#[test]
+fn test_tight_limit_prefers_call_edges() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    db::initialize_database(temp_dir.path()).expect("Failed to initialize database");
+    let mut conn = db::open_database(temp_dir.path()).expect("Failed to open database");
+
+    db::insert_nodes(
+        &mut conn,
+        &[
+            synthetic_node("root", NodeKind::Module, 1),
+            synthetic_node("child", NodeKind::Struct, 2),
+            synthetic_node("callee", NodeKind::Function, 3),
+        ],
+    )
+    .expect("Failed to insert nodes");
+    // The containment edge sorts first in the database; the call must still win.
+    db::insert_edges(
+        &mut conn,
+        &[
+            synthetic_edge("root", "child", EdgeKind::Contains, 1),
+            synthetic_edge("root", "callee", EdgeKind::Calls, 5),
+        ],
+    )
+    .expect("Failed to insert edges");
+
+    let options = TraversalOptions {
+        max_depth: Some(1),
+        edge_kinds: None,
+        node_kinds: None,
+        direction: Some(TraversalDirection::Outgoing),
+        limit: Some(1),
+        include_start: Some(true),
+        edge_costs: None,
+        incoming_cost_factor: None,
+        min_confidence: None,
+    };
+    let subgraph = graph::build_subgraph(&conn, &["root".to_string()], &options)
+        .expect("Failed to build subgraph");
+    assert_eq!(subgraph.edges.len(), 1);
+    assert_eq!(
+        subgraph.edges.first().map(|e| e.kind),
+        Some(EdgeKind::Calls)
+    );
+
+    // Custom costs can invert the preference.
+    let options = TraversalOptions {
+        edge_costs: Some(HashMap::from([(EdgeKind::Contains, 0.5)])),
+        ..options
+    };
+    let subgraph = graph::build_subgraph(&conn, &["root".to_string()], &options)
+        .expect("Failed to build subgraph");
+    assert_eq!(
+        subgraph.edges.first().map(|e| e.kind),
+        Some(EdgeKind::Contains)
+    );
+}
+
+#[test]
+fn test_cheap_path_expands_before_costly_sibling() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    db::initialize_database(temp_dir.path()).expect("Failed to initialize database");
+    let mut conn = db::open_database(temp_dir.path()).expect("Failed to open database");
+
+    db::insert_nodes(
+        &mut conn,
+        &[
+            synthetic_node("root", NodeKind::Module, 1),
+            synthetic_node("a", NodeKind::Function, 2),
+            synthetic_node("b", NodeKind::Function, 3),
+            synthetic_node("c", NodeKind::Struct, 4),
+            synthetic_node("d", NodeKind::Function, 5),
+            synthetic_node("e", NodeKind::Function, 6),
+        ],
+    )
+    .expect("Failed to insert nodes");
+    // `b` (cost 2, found through `a`) must be expanded before `c` (cost 3),
+    // even though `c` was reached first.
+    db::insert_edges(
+        &mut conn,
+        &[
+            synthetic_edge("root", "a", EdgeKind::Calls, 1),
+            synthetic_edge("root", "c", EdgeKind::Contains, 2),
+            synthetic_edge("a", "b", EdgeKind::Calls, 3),
+            synthetic_edge("b", "e", EdgeKind::Calls, 4),
+            synthetic_edge("c", "d", EdgeKind::Calls, 5),
+        ],
+    )
+    .expect("Failed to insert edges");
+
+    let options = TraversalOptions {
+        max_depth: Some(3),
+        edge_kinds: None,
+        node_kinds: None,
+        direction: Some(TraversalDirection::Outgoing),
+        limit: Some(4),
+        include_start: Some(true),
+        edge_costs: None,
+        incoming_cost_factor: None,
+        min_confidence: None,
+    };
+    let subgraph = graph::build_subgraph(&conn, &["root".to_string()], &options)
+        .expect("Failed to build subgraph");
+    let edges: Vec<(&str, &str)> = subgraph
+        .edges
+        .iter()
+        .map(|e| (e.source.as_str(), e.target.as_str()))
+        .collect();
+    assert!(edges.contains(&("b", "e")), "edges: {edges:?}");
+    assert!(!edges.contains(&("c", "d")), "edges: {edges:?}");
+}
+
+#[test]
+fn test_both_directions_yields_unique_edges() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    db::initialize_database(temp_dir.path()).expect("Failed to initialize database");
+    let mut conn = db::open_database(temp_dir.path()).expect("Failed to open database");
+
+    db::insert_nodes(
+        &mut conn,
+        &[
+            synthetic_node("a", NodeKind::Function, 1),
+            synthetic_node("b", NodeKind::Function, 2),
+        ],
+    )
+    .expect("Failed to insert nodes");
+    db::insert_edges(
+        &mut conn,
+        &[
+            synthetic_edge("b", "a", EdgeKind::Calls, 2),
+            synthetic_edge("a", "b", EdgeKind::Calls, 1),
+        ],
+    )
+    .expect("Failed to insert edges");
+
+    let options = TraversalOptions {
+        max_depth: Some(3),
+        edge_kinds: None,
+        node_kinds: None,
+        direction: Some(TraversalDirection::Both),
+        limit: None,
+        include_start: Some(true),
+        edge_costs: None,
+        incoming_cost_factor: None,
+        min_confidence: None,
+    };
+    let subgraph = graph::build_subgraph(&conn, &["a".to_string()], &options)
+        .expect("Failed to build subgraph");
+
+    let pairs: Vec<(&str, &str)> = subgraph
+        .edges
+        .iter()
+        .map(|e| (e.source.as_str(), e.target.as_str()))
+        .collect();
+    assert_eq!(pairs, vec![("a", "b"), ("b", "a")]);
+}
+
+#[test]
+fn test_find_symbol_by_name_and_file() {
+    let (_temp, project_root) = setup_indexed_project();
+    let project_path = Path::new(&project_root);
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let found = |name: &str, file: Option<&str>| -> Vec<(NodeKind, String, i64)> {
+        graph::find_symbol(&conn, project_path, name, file)
+            .expect("Failed to find symbol")
+            .into_iter()
+            .map(|n| (n.kind, n.file_path, n.start_line))
+            .collect()
+    };
+
+    // The function and the Calculator method, in line order.
+    let add = found("add", None);
+    assert_eq!(
+        add.iter().map(|(kind, _, _)| *kind).collect::<Vec<_>>(),
+        vec![NodeKind::Function, NodeKind::Method]
+    );
+    assert!(add.is_sorted_by(|a, b| a.2 < b.2));
+    assert_eq!(found("add", Some("math.ts")), add);
+    let absolute = project_path.join("src/math.ts");
+    assert_eq!(found("add", absolute.to_str()), add);
+    // Only the import is left to find in a file that uses `add`.
+    assert!(
+        found("add", Some("index.ts"))
+            .iter()
+            .all(|(kind, _, _)| *kind == NodeKind::Import)
+    );
+    assert!(found("add", Some("user.ts")).is_empty());
+    assert!(found("add", Some("th.ts")).is_empty());
+    assert_eq!(found("quickMath", None).len(), 1);
+}
+
+#[test]
+fn test_traversal_skips_low_confidence_edges() {
+    let (_temp, project_root) = setup_indexed_project();
+    let conn = db::open_database(Path::new(&project_root)).expect("Failed to open database");
+    let add = db::find_nodes_by_name(&conn, "add")
+        .expect("Failed to find nodes")
+        .into_iter()
+        .find(|n| n.kind == NodeKind::Function)
+        .expect("add should exist");
+
+    let options = TraversalOptions {
+        max_depth: Some(1),
+        edge_kinds: Some(vec![EdgeKind::Calls]),
+        node_kinds: None,
+        direction: Some(TraversalDirection::Incoming),
+        limit: None,
+        include_start: Some(true),
+        edge_costs: None,
+        incoming_cost_factor: None,
+        min_confidence: None,
+    };
+    let subgraph = graph::build_subgraph(&conn, std::slice::from_ref(&add.id), &options)
+        .expect("Failed to build subgraph");
+    // `add` is also a Calculator method, so both calls were tie-broken.
+    let calls: Vec<&Edge> = subgraph
+        .edges
+        .iter()
+        .filter(|e| e.target == add.id)
+        .collect();
+    assert_eq!(calls.len(), 2);
+    assert!(calls.iter().all(|e| graph::edge_confidence(e) < 0.8));
+
+    let options = TraversalOptions {
+        min_confidence: Some(0.8),
+        ..options
+    };
+    let subgraph = graph::build_subgraph(&conn, std::slice::from_ref(&add.id), &options)
+        .expect("Failed to build subgraph");
+    assert!(subgraph.edges.is_empty());
+}
+
+#[test]
+fn test_edges_for_nodes_caps_each_node() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    db::initialize_database(temp_dir.path()).expect("Failed to initialize database");
+    let mut conn = db::open_database(temp_dir.path()).expect("Failed to open database");
+
+    db::insert_nodes(
+        &mut conn,
+        &[
+            synthetic_node("a", NodeKind::Function, 1),
+            synthetic_node("b", NodeKind::Function, 2),
+            synthetic_node("x", NodeKind::Function, 3),
+            synthetic_node("y", NodeKind::Function, 4),
+            synthetic_node("z", NodeKind::Function, 5),
+        ],
+    )
+    .expect("Failed to insert nodes");
+    db::insert_edges(
+        &mut conn,
+        &[
+            synthetic_edge("a", "x", EdgeKind::Calls, 3),
+            synthetic_edge("a", "y", EdgeKind::Calls, 1),
+            synthetic_edge("a", "z", EdgeKind::Calls, 2),
+            synthetic_edge("b", "x", EdgeKind::Calls, 4),
+        ],
+    )
+    .expect("Failed to insert edges");
+
+    let ids = ["a".to_string(), "b".to_string()];
+    let all = db::get_edges_for_nodes(&conn, &ids, true, None, None).expect("edges");
+    assert_eq!(all.len(), 4);
+
+    // The cap applies per node, keeping each node's first edges.
+    let capped = db::get_edges_for_nodes(&conn, &ids, true, None, Some(1)).expect("edges");
+    let pairs: Vec<(&str, &str)> = capped
+        .iter()
+        .map(|e| (e.source.as_str(), e.target.as_str()))
+        .collect();
+    assert_eq!(pairs, [("a", "y"), ("b", "x")]);
+}
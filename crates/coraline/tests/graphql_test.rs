@@ -0,0 +1,174 @@
+//! Integration tests for the GraphQL endpoint
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::graphql::{self, Context};
+use serde_json::{Value, json};
+
+fn execute(context: &Context, query: &str, variables: Option<&Value>) -> Value {
+    graphql::execute(context, query, variables, None)
+}
+
+#[test]
+fn test_graphql_search_node_and_traversal() {
+    let temp = common::setup_indexed_project();
+    let context = Context::open(temp.path()).expect("Failed to open database");
+
+    let response = execute(
+        &context,
+        r"query Find($q: String!) {
+            results: search(query: $q, kind: FUNCTION, limit: 5) {
+                score
+                node { ...Summary }
+            }
+        }
+        fragment Summary on Node { __typename id name kind filePath }",
+        Some(&json!({ "q": "multiply" })),
+    );
+    assert!(response.get("errors").is_none(), "{response}");
+    let multiply = response
+        .pointer("/data/results/0/node")
+        .expect("multiply should be found");
+    assert_eq!(multiply["__typename"], "Node");
+    assert_eq!(multiply["name"], "multiply");
+    assert_eq!(multiply["kind"], "FUNCTION");
+    assert_eq!(multiply["filePath"], "src/math.ts");
+    let id = multiply["id"].as_str().expect("id should be a string");
+
+    let response = execute(
+        &context,
+        "query($id: ID!, $code: Boolean = false) {
+            node(id: $id) {
+                name
+                signature @include(if: $code)
+                callers(limit: 5) { name }
+                edges(direction: INCOMING, kinds: [CALLS]) { kind sourceNode { name } confidence }
+            }
+            subgraph(roots: [$id], depth: 1, direction: INCOMING, edgeKinds: CALLS) {
+                roots
+                nodes { name }
+                edges { source target kind }
+            }
+        }",
+        Some(&json!({ "id": id })),
+    );
+    assert!(response.get("errors").is_none(), "{response}");
+    let node = response.pointer("/data/node").expect("node should resolve");
+    assert!(node.get("signature").is_none(), "@include(if: false) skips");
+    assert!(
+        node["callers"]
+            .as_array()
+            .expect("callers should be a list")
+            .iter()
+            .any(|caller| caller["name"] == "quickMath"),
+        "quickMath calls multiply: {node}"
+    );
+    let edges = node["edges"].as_array().expect("edges should be a list");
+    assert!(!edges.is_empty());
+    assert!(edges.iter().all(|edge| edge["kind"] == "CALLS"));
+    assert!(
+        edges
+            .iter()
+            .any(|edge| edge.pointer("/sourceNode/name") == Some(&json!("quickMath")))
+    );
+
+    let subgraph = response
+        .pointer("/data/subgraph")
+        .expect("subgraph should resolve");
+    assert_eq!(subgraph["roots"], json!([id]));
+    let nodes = subgraph["nodes"]
+        .as_array()
+        .expect("nodes should be a list");
+    assert!(nodes.iter().any(|node| node["name"] == "quickMath"));
+    assert!(
+        subgraph["edges"]
+            .as_array()
+            .expect("edges should be a list")
+            .iter()
+            .all(|edge| edge["kind"] == "CALLS" && edge["target"] == id)
+    );
+}
+
+#[test]
+fn test_graphql_errors_and_http_routes() {
+    let temp = common::setup_indexed_project();
+    let context = Context::open(temp.path()).expect("Failed to open database");
+
+    let response = execute(
+        &context,
+        "{ stats { fileCount } node(id: \"missing\") { id } }",
+        None,
+    );
+    assert!(response.get("errors").is_none(), "{response}");
+    assert_eq!(response.pointer("/data/stats/fileCount"), Some(&json!(3)));
+    assert_eq!(response.pointer("/data/node"), Some(&Value::Null));
+
+    // Resolver failures null the field and are reported with its path.
+    let mut nested = String::from("id");
+    for _ in 0..8 {
+        nested = format!("edges(direction: BOTH, limit: 1) {{ sourceNode {{ {nested} }} }}");
+    }
+    let nested = format!("{{ search(query: \"multiply\", limit: 1) {{ node {{ {nested} }} }} }}");
+    let response = execute(&context, &nested, None);
+    assert!(
+        response.get("data").is_some_and(Value::is_object),
+        "{response}"
+    );
+    let errors = response
+        .get("errors")
+        .and_then(Value::as_array)
+        .expect("errors should be a list");
+    assert!(!errors.is_empty(), "{response}");
+    assert!(errors.iter().all(|error| error["path"].is_array()));
+
+    // Invalid documents are rejected before execution.
+    let response = execute(&context, "{ stats { fileCount } nope }", None);
+    assert!(response.get("data").is_none());
+    let response = execute(&context, "{ stats { fileCount }", None);
+    assert!(response.get("data").is_none());
+    assert!(
+        response
+            .pointer("/errors/0/message")
+            .is_some_and(Value::is_string)
+    );
+    let response = execute(&context, "query($id: ID!) { node(id: $id) { id } }", None);
+    assert!(response.get("data").is_none(), "required variable missing");
+    let response = execute(&context, "mutation { stats { fileCount } }", None);
+    assert!(response.get("data").is_none());
+
+    let post = graphql::route(
+        &context,
+        "POST",
+        "/graphql",
+        r#"{"query": "{ stats { nodeCount edgeCount } }"}"#,
+    );
+    assert_eq!(post.status, 200);
+    let body: Value = serde_json::from_str(&post.body).expect("Response should be valid JSON");
+    assert!(
+        body.pointer("/data/stats/nodeCount")
+            .and_then(Value::as_i64)
+            > Some(0)
+    );
+
+    let get = graphql::route(
+        &context,
+        "GET",
+        "/graphql?query=%7B%20stats%20%7B%20fileCount%20%7D%20%7D",
+        "",
+    );
+    assert_eq!(get.status, 200);
+    assert!(get.body.contains("\"fileCount\":3"), "{}", get.body);
+
+    let bad = graphql::route(&context, "POST", "/graphql", "not json");
+    assert_eq!(bad.status, 400);
+    let schema = graphql::route(&context, "GET", "/graphql/schema", "");
+    assert_eq!(schema.status, 200);
+    assert!(schema.body.contains("type Query"));
+    assert!(schema.body.contains("TYPE_ALIAS"));
+    assert_eq!(
+        graphql::route(&context, "DELETE", "/graphql", "").status,
+        405
+    );
+    assert_eq!(graphql::route(&context, "GET", "/nope", "").status, 404);
+}
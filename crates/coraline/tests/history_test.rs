@@ -0,0 +1,92 @@
+//! Integration tests for churn from git history
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::config::{self, HistoryConfig};
+use coraline::types::{NodeChurn, NodeKind};
+use coraline::{db, extraction, history};
+use tempfile::TempDir;
+
+fn commit(project_path: &Path, author: &str, message: &str) {
+    common::git_as(project_path, author, &["add", "-A"]);
+    common::git_as(project_path, author, &["commit", "-q", "-m", message]);
+}
+
+fn edit(project_path: &Path, from: &str, to: &str) {
+    let path = project_path.join("src/math.ts");
+    let source = std::fs::read_to_string(&path).expect("Failed to read fixture");
+    assert!(source.contains(from));
+    std::fs::write(&path, source.replacen(from, to, 1)).expect("Failed to edit fixture");
+}
+
+fn churn(project_path: &Path, name: &str) -> Option<NodeChurn> {
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let node = db::get_nodes_by_file(&conn, "src/math.ts", None)
+        .expect("Failed to read nodes")
+        .into_iter()
+        .find(|n| n.name == name && n.kind != NodeKind::Export)
+        .expect("the symbol should be indexed");
+    db::get_churn(&conn, &node.id).expect("Failed to read churn")
+}
+
+#[test]
+fn test_churn_follows_symbols_through_history() {
+    let temp = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp.path();
+    db::initialize_database(project_path).expect("Failed to initialize database");
+    common::copy_fixture(project_path);
+    std::fs::write(project_path.join(".gitignore"), ".coraline/\n").expect("Failed to write");
+
+    common::git_as(project_path, "alice", &["init", "-q"]);
+    commit(project_path, "alice", "Add math");
+    edit(project_path, "return a * b;", "return b * a;");
+    commit(project_path, "bob", "Swap operands");
+    // Moves every function down without changing it.
+    edit(
+        project_path,
+        " */\n",
+        " */\n\nexport function square(x: number): number {\n    return x * x;\n}\n",
+    );
+    commit(project_path, "alice", "Add square");
+    // Uncommitted edits shift nothing and count for nothing.
+    edit(project_path, "Division by zero", "Cannot divide by zero");
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+    let summary =
+        history::record_churn(project_path, &HistoryConfig::default()).expect("Failed to record");
+    assert_eq!(summary.commits_scanned, 3);
+
+    let multiply = churn(project_path, "multiply").expect("multiply has churn");
+    assert_eq!((multiply.commits, multiply.authors), (2, 2));
+    assert_eq!(churn(project_path, "add").map(|c| c.commits), Some(1));
+    assert_eq!(churn(project_path, "divide").map(|c| c.commits), Some(1));
+    assert_eq!(churn(project_path, "square").map(|c| c.commits), Some(1));
+    let file = churn(project_path, "math.ts").expect("the file has churn");
+    assert_eq!(file.commits, 3);
+    assert!(file.last_modified >= multiply.last_modified);
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let hotspots = history::hotspots(&conn).expect("Failed to rank hotspots");
+    assert!(hotspots.iter().all(|h| h.node.kind != NodeKind::File));
+    let rank = |name: &str| {
+        hotspots
+            .iter()
+            .position(|h| h.node.name == name && h.node.kind == NodeKind::Function)
+            .expect("the symbol should be ranked")
+    };
+    // Changed twice and called from index.ts.
+    assert!(rank("multiply") < rank("subtract"));
+    let top = hotspots.first().expect("a hotspot");
+    assert_eq!(top.score, top.churn.commits * (top.fan_in + 1));
+}
+
+#[test]
+fn test_churn_fails_outside_git() {
+    let temp = TempDir::new().expect("Failed to create temp directory");
+    db::initialize_database(temp.path()).expect("Failed to initialize database");
+    assert!(history::record_churn(temp.path(), &HistoryConfig::default()).is_err());
+}
@@ -0,0 +1,99 @@
+//! Integration tests for git hook installation
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::sync::{self, GitHook, GitHooksManager, HookManager};
+use tempfile::TempDir;
+
+const PREVIOUS_HOOK: &str = "#!/bin/sh\necho previous hook\n";
+
+#[test]
+fn test_every_hook_installs_and_restores_what_it_replaced() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project = temp_dir.path();
+    common::git(project, &["init", "-q"]);
+    let hooks_dir = project.join(".git/hooks");
+    std::fs::create_dir_all(&hooks_dir).expect("Failed to create hooks dir");
+    std::fs::write(hooks_dir.join("post-merge"), PREVIOUS_HOOK).expect("Failed to write hook");
+
+    let hooks = GitHooksManager::new(project);
+    assert!(hooks.installed_hooks().is_empty());
+    for hook in GitHook::ALL {
+        let result = hooks.install(hook);
+        assert!(result.success, "{}", result.message);
+        assert_eq!(result.previous_hook_backed_up, hook == GitHook::PostMerge);
+        let script = std::fs::read_to_string(&result.hook_path).expect("hook");
+        assert!(script.contains("coraline sync --quiet --throttle"));
+        // Commits sync only what they changed.
+        assert_eq!(
+            script.contains("--since HEAD~1 ||"),
+            hook == GitHook::PostCommit
+        );
+        // Only a checkout can be a no-op branch switch.
+        assert_eq!(
+            script.contains(r#""$1" = "$2""#),
+            hook == GitHook::PostCheckout
+        );
+    }
+    assert_eq!(hooks.installed_hooks(), GitHook::ALL.to_vec());
+    // Reinstalling keeps the original backup.
+    assert!(!hooks.install(GitHook::PostMerge).previous_hook_backed_up);
+
+    for hook in GitHook::ALL {
+        let result = hooks.remove(hook);
+        assert!(result.success, "{}", result.message);
+        assert_eq!(result.restored_from_backup, hook == GitHook::PostMerge);
+    }
+    assert!(hooks.installed_hooks().is_empty());
+    assert_eq!(
+        std::fs::read_to_string(hooks_dir.join("post-merge")).expect("hook"),
+        PREVIOUS_HOOK
+    );
+    assert!(!hooks_dir.join("post-commit").exists());
+    assert!(!hooks.remove(GitHook::PostMerge).success);
+}
+
+#[test]
+fn test_hooks_follow_core_hooks_path() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project = temp_dir.path();
+    common::git(project, &["init", "-q"]);
+    common::git(project, &["config", "core.hooksPath", "tools/hooks"]);
+
+    let hooks = GitHooksManager::new(project);
+    assert_eq!(hooks.hooks_dir(), project.join("tools/hooks"));
+    assert!(!hooks.is_default_hooks_dir());
+    assert_eq!(hooks.manager(), None);
+    assert!(hooks.install_hook().success);
+    assert!(project.join("tools/hooks/post-commit").is_file());
+    assert!(!project.join(".git/hooks/post-commit").exists());
+
+    // Husky's wrappers call the scripts in `.husky/`.
+    common::git(project, &["config", "core.hooksPath", ".husky/_"]);
+    let hooks = GitHooksManager::new(project);
+    assert_eq!(hooks.hooks_dir(), project.join(".husky"));
+    assert_eq!(hooks.manager(), Some(HookManager::Husky));
+    assert!(hooks.install(GitHook::PostMerge).success);
+    assert!(project.join(".husky/post-merge").is_file());
+}
+
+#[test]
+fn test_lefthook_gets_a_config_snippet() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project = temp_dir.path();
+    common::git(project, &["init", "-q"]);
+    std::fs::write(project.join("lefthook.yml"), "pre-commit:\n").expect("Failed to write");
+    let hooks = GitHooksManager::new(project);
+    assert!(hooks.is_default_hooks_dir());
+    assert_eq!(hooks.manager(), Some(HookManager::Lefthook));
+
+    let snippet = sync::hook_snippet(HookManager::Lefthook, &GitHook::ALL);
+    for hook in GitHook::ALL {
+        assert!(snippet.contains(&format!(
+            "{}:\n  commands:\n    coraline-sync:",
+            hook.name()
+        )));
+    }
+    assert!(snippet.contains("coraline sync --quiet --throttle"));
+}
@@ -0,0 +1,198 @@
+//! Integration tests for inheritance edges and `coraline analyze implementations`
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+use coraline::tools;
+use coraline::tools::responses::ImplementationsResponse;
+use coraline::types::EdgeKind;
+use serde_json::{Value, json};
+
+const PROJECT_FILES: &[(&str, &str)] = &[
+    (
+        "src/storage.rs",
+        r#"
+pub trait Storage: Send {
+    fn get(&self, key: &str) -> Option<String>;
+    fn put(&mut self, key: &str, value: String);
+    fn name(&self) -> &str {
+        "storage"
+    }
+}
+
+pub struct Memory;
+
+impl Storage for Memory {
+    fn get(&self, key: &str) -> Option<String> {
+        None
+    }
+    fn put(&mut self, key: &str, value: String) {}
+}
+"#,
+    ),
+    (
+        "src/disk.rs",
+        r"
+use crate::storage::Storage;
+
+pub struct Disk;
+
+impl Storage for Disk {
+    fn get(&self, key: &str) -> Option<String> {
+        None
+    }
+}
+",
+    ),
+    (
+        "web/shape.ts",
+        "export interface Shape {\n  area(): number;\n  label?(): string;\n}\n",
+    ),
+    (
+        "web/shapes.ts",
+        r"
+import { Shape } from './shape';
+
+export abstract class Polygon implements Shape {
+  abstract sides(): number;
+  area(): number {
+    return 0;
+  }
+}
+
+export class Square extends Polygon {
+  sides(): number {
+    return 4;
+  }
+}
+
+export class Triangle extends Polygon {}
+",
+    ),
+    (
+        "api/payments.py",
+        r"
+from abc import ABC, abstractmethod
+
+class Gateway(ABC):
+    @abstractmethod
+    def charge(self, amount):
+        ...
+
+    @abstractmethod
+    def refund(self, amount):
+        ...
+
+class Stripe(Gateway):
+    def charge(self, amount):
+        return True
+",
+    ),
+];
+
+fn implementations(project: &Path, name: &str) -> ImplementationsResponse {
+    let result = tools::create_default_registry(project)
+        .execute("coraline_implementations", json!({ "name": name }))
+        .expect("coraline_implementations failed");
+    serde_json::from_value(result).expect("valid response")
+}
+
+fn listed(response: &ImplementationsResponse) -> Vec<(&str, Option<&str>, Vec<&str>)> {
+    response
+        .implementations
+        .iter()
+        .map(|i| {
+            (
+                i.node.name.as_str(),
+                i.via.as_deref(),
+                i.missing.iter().map(String::as_str).collect(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_rust_trait_impls_and_missing_methods() {
+    let temp = common::with_files(PROJECT_FILES);
+    let response = implementations(temp.path(), "Storage");
+    assert_eq!(response.required, ["get", "put"]);
+    assert_eq!(
+        listed(&response),
+        [("Disk", None, vec!["put"]), ("Memory", None, vec![])]
+    );
+    assert!(
+        response
+            .implementations
+            .iter()
+            .all(|i| i.edge_kind == EdgeKind::Implements)
+    );
+}
+
+#[test]
+fn test_typescript_interface_through_abstract_class() {
+    let temp = common::with_files(PROJECT_FILES);
+    let response = implementations(temp.path(), "Shape");
+    // `label` is optional.
+    assert_eq!(response.required, ["area"]);
+    assert_eq!(
+        listed(&response),
+        [
+            ("Polygon", None, vec![]),
+            ("Square", Some("Polygon"), vec![]),
+            ("Triangle", Some("Polygon"), vec![]),
+        ]
+    );
+
+    let response = implementations(temp.path(), "Polygon");
+    assert_eq!(response.required, ["sides"]);
+    assert_eq!(
+        listed(&response),
+        [("Square", None, vec![]), ("Triangle", None, vec!["sides"])]
+    );
+    assert!(
+        response
+            .implementations
+            .iter()
+            .all(|i| i.edge_kind == EdgeKind::Extends)
+    );
+}
+
+#[test]
+fn test_cli_reports_python_abstract_methods() {
+    let temp = common::with_files(PROJECT_FILES);
+    let output = Command::new(env!("CARGO_BIN_EXE_coraline"))
+        .args(["analyze", "-p"])
+        .arg(temp.path())
+        .args(["implementations", "Gateway", "--json"])
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run coraline");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    assert_eq!(report.get("required"), Some(&json!(["charge", "refund"])));
+    assert_eq!(
+        report.pointer("/implementations/0/name"),
+        Some(&json!("Stripe"))
+    );
+    assert_eq!(
+        report.pointer("/implementations/0/missing"),
+        Some(&json!(["refund"]))
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_coraline"))
+        .args(["analyze", "-p"])
+        .arg(temp.path())
+        .args(["implementations", "Gateway"])
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run coraline");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("missing: refund"), "{stdout}");
+}
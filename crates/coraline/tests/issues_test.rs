@@ -0,0 +1,149 @@
+//! Integration tests for issue linkage
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::config::{self, CoralineConfig, IssuesConfig};
+use coraline::types::{IssueSource, Node, NodeKind};
+use coraline::{db, extraction, issues};
+
+fn edit(project_path: &Path, from: &str, to: &str) {
+    let path = project_path.join("src/math.ts");
+    let source = std::fs::read_to_string(&path).expect("Failed to read fixture");
+    assert!(source.contains(from));
+    std::fs::write(&path, source.replacen(from, to, 1)).expect("Failed to edit fixture");
+}
+
+fn index(project_path: &Path) {
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+}
+
+fn function(project_path: &Path, name: &str) -> Node {
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    db::get_nodes_by_file(&conn, "src/math.ts", Some(NodeKind::Function))
+        .expect("Failed to read nodes")
+        .into_iter()
+        .find(|n| n.name == name)
+        .expect("the symbol should be indexed")
+}
+
+fn issue_ids(project_path: &Path, name: &str) -> Vec<(String, IssueSource)> {
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    db::get_issue_links_for_node(&conn, &function(project_path, name))
+        .expect("Failed to read issue links")
+        .into_iter()
+        .map(|l| (l.issue_id, l.source))
+        .collect()
+}
+
+#[test]
+fn test_link_issues_from_commits_and_comments() {
+    let temp = common::fixture_project();
+    let project_path = temp.path();
+
+    common::git(project_path, &["init", "-q"]);
+    common::git(project_path, &["add", "src"]);
+    common::git(project_path, &["commit", "-q", "-m", "Initial import"]);
+    edit(
+        project_path,
+        "throw new Error(\"Division by zero\");",
+        "throw new RangeError(\"Division by zero\");",
+    );
+    common::git(
+        project_path,
+        &[
+            "commit",
+            "-q",
+            "-am",
+            "Reject zero divisors (#12)\n\nCloses #12.",
+        ],
+    );
+    edit(
+        project_path,
+        "export function subtract",
+        "// Keeps negative results, see #34\nexport function subtract",
+    );
+    common::git(project_path, &["commit", "-q", "-am", "Document subtract"]);
+    index(project_path);
+
+    let summary = issues::link_issues(project_path).expect("Failed to link issues");
+    assert!(summary.git);
+    assert_eq!(summary.commits_scanned, 3);
+    assert_eq!(summary.commits_with_issues, 1);
+    assert_eq!(summary.comment_mentions, 1);
+    assert_eq!(summary.issues, 2);
+
+    assert_eq!(
+        issue_ids(project_path, "divide"),
+        [("#12".to_string(), IssueSource::Commit)]
+    );
+    assert_eq!(
+        issue_ids(project_path, "subtract"),
+        [("#34".to_string(), IssueSource::Comment)]
+    );
+    assert!(issue_ids(project_path, "add").is_empty());
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let links = db::get_issue_links_for_node(&conn, &function(project_path, "divide"))
+        .expect("Failed to read issue links");
+    let link = links.first().expect("divide links an issue");
+    assert_eq!(link.summary, "Reject zero divisors (#12)");
+    assert_eq!(link.reference.len(), 40);
+}
+
+#[test]
+fn test_link_issues_uses_configured_pattern_without_git() {
+    let temp = common::fixture_project();
+    let project_path = temp.path();
+    edit(
+        project_path,
+        "return a * b;",
+        "return a * b; // CORE-7 overflow is the caller's problem (#99)",
+    );
+    let toml_cfg = CoralineConfig {
+        issues: IssuesConfig {
+            pattern: r"\b([A-Z][A-Z0-9]+-\d+)\b".into(),
+            ..IssuesConfig::default()
+        },
+        ..CoralineConfig::default()
+    };
+    config::save_toml_config(project_path, &toml_cfg).expect("Failed to save config");
+    index(project_path);
+
+    let summary = issues::link_issues(project_path).expect("Failed to link issues");
+    assert!(!summary.git);
+    assert_eq!(summary.links, 1);
+    assert_eq!(
+        issue_ids(project_path, "multiply"),
+        [("CORE-7".to_string(), IssueSource::Comment)]
+    );
+
+    // Re-linking replaces the earlier links.
+    let toml_cfg = CoralineConfig {
+        issues: IssuesConfig {
+            pattern: r"#\d+".into(),
+            ..IssuesConfig::default()
+        },
+        ..CoralineConfig::default()
+    };
+    config::save_toml_config(project_path, &toml_cfg).expect("Failed to save config");
+    issues::link_issues(project_path).expect("Failed to link issues");
+    assert_eq!(
+        issue_ids(project_path, "multiply"),
+        [("#99".to_string(), IssueSource::Comment)]
+    );
+
+    let toml_cfg = CoralineConfig {
+        issues: IssuesConfig {
+            pattern: "(".into(),
+            ..IssuesConfig::default()
+        },
+        ..CoralineConfig::default()
+    };
+    config::save_toml_config(project_path, &toml_cfg).expect("Failed to save config");
+    let err = issues::link_issues(project_path).expect_err("Invalid pattern should fail");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,157 @@
+//! Integration tests for indexing lifecycle hooks
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use coraline::config::{self, CoralineConfig, LifecycleConfig};
+use coraline::lifecycle::{HookEvent, IndexMode, LifecycleEvent, LifecycleHooks};
+use coraline::{db, extraction};
+
+/// Hooks recording every event as `<event>` or `<event>:<file>`.
+fn recording_hooks(log: &Arc<Mutex<Vec<String>>>) -> LifecycleHooks {
+    let mut hooks = LifecycleHooks::new();
+    for event in [
+        LifecycleEvent::PreScan,
+        LifecycleEvent::PostFileExtract,
+        LifecycleEvent::PostIndex,
+        LifecycleEvent::PostResolve,
+    ] {
+        let log = Arc::clone(log);
+        hooks = hooks.with_callback(event, move |event, _conn| {
+            let entry = match event {
+                HookEvent::PostFileExtract { file, .. } => {
+                    format!("{}:{}", event.kind().as_str(), file.path)
+                }
+                _ => event.kind().as_str().to_string(),
+            };
+            log.lock().expect("log lock").push(entry);
+            Ok(())
+        });
+    }
+    hooks
+}
+
+#[test]
+fn test_lifecycle_hooks_fire_in_order_during_index_and_sync() {
+    let temp = common::fixture_project();
+    let project_path = temp.path();
+    let cfg = config::create_default_config(project_path);
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let hooks = recording_hooks(&log);
+    extraction::index_all_with_hooks(project_path, &cfg, false, None, &hooks)
+        .expect("Failed to index project");
+
+    let events = log.lock().expect("log lock").clone();
+    assert_eq!(events.first().map(String::as_str), Some("pre_scan"));
+    assert_eq!(events.last().map(String::as_str), Some("post_resolve"));
+    assert_eq!(
+        events.iter().rev().nth(1).map(String::as_str),
+        Some("post_index")
+    );
+    let extracted: Vec<&String> = events
+        .iter()
+        .filter(|e| e.starts_with("post_file_extract:"))
+        .collect();
+    assert!(!extracted.is_empty());
+    assert_eq!(extracted.len(), events.len() - 3);
+
+    // A sync re-extracts only the edited file.
+    log.lock().expect("log lock").clear();
+    let edited = project_path.join("src/math.ts");
+    let mut source = std::fs::read_to_string(&edited).expect("Failed to read fixture");
+    source.push_str("\nexport function lifecycleProbe(): number { return 1; }\n");
+    std::fs::write(&edited, source).expect("Failed to edit fixture");
+
+    extraction::sync_with_hooks(project_path, &cfg, None, &hooks).expect("Failed to sync");
+    let events = log.lock().expect("log lock").clone();
+    assert_eq!(
+        events,
+        [
+            "pre_scan",
+            "post_file_extract:src/math.ts",
+            "post_index",
+            "post_resolve"
+        ]
+    );
+}
+
+#[test]
+fn test_lifecycle_callback_enriches_nodes_after_extraction() {
+    let temp = common::fixture_project();
+    let project_path = temp.path();
+    let cfg = config::create_default_config(project_path);
+
+    let hooks = LifecycleHooks::new()
+        .with_callback(LifecycleEvent::PreScan, |_, conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS node_lint (node_id TEXT PRIMARY KEY, checked INTEGER)",
+            )
+            .map_err(std::io::Error::other)
+        })
+        .with_callback(LifecycleEvent::PostFileExtract, |event, conn| {
+            if let HookEvent::PostFileExtract { nodes, mode, .. } = event {
+                assert_eq!(*mode, IndexMode::Full);
+                for node in *nodes {
+                    conn.execute(
+                        "INSERT OR REPLACE INTO node_lint (node_id, checked) VALUES (?1, 1)",
+                        [&node.id],
+                    )
+                    .map_err(std::io::Error::other)?;
+                }
+            }
+            Ok(())
+        });
+    extraction::index_all_with_hooks(project_path, &cfg, false, None, &hooks)
+        .expect("Failed to index project");
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let uncovered: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM nodes WHERE id NOT IN (SELECT node_id FROM node_lint)",
+            [],
+            |row| row.get(0),
+        )
+        .expect("Failed to count nodes");
+    assert_eq!(uncovered, 0, "every stored node should be enriched");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_lifecycle_command_receives_event_json() {
+    let temp = common::fixture_project();
+    let project_path = temp.path();
+    let toml_cfg = CoralineConfig {
+        lifecycle: LifecycleConfig {
+            post_index: Some("cat > post_index.json; echo $CORALINE_HOOK_EVENT > event.txt".into()),
+            ..LifecycleConfig::default()
+        },
+        ..CoralineConfig::default()
+    };
+    config::save_toml_config(project_path, &toml_cfg).expect("Failed to save config");
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+
+    let body = std::fs::read_to_string(project_path.join("post_index.json"))
+        .expect("Hook command should have run");
+    let event: serde_json::Value = serde_json::from_str(&body).expect("Event should be JSON");
+    assert_eq!(
+        event.get("event").and_then(serde_json::Value::as_str),
+        Some("post_index")
+    );
+    assert_eq!(
+        event.get("mode").and_then(serde_json::Value::as_str),
+        Some("full")
+    );
+    assert!(
+        event
+            .get("files")
+            .and_then(serde_json::Value::as_u64)
+            .is_some_and(|n| n > 0)
+    );
+    let name = std::fs::read_to_string(project_path.join("event.txt")).expect("event.txt");
+    assert_eq!(name.trim(), "post_index");
+}
@@ -0,0 +1,69 @@
+//! Integration tests for the per-directory code map
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::db;
+use coraline::map::{self, DirectoryStats, MapMetric};
+use coraline::output::OutputStyle;
+use tempfile::TempDir;
+
+/// Index the fixture as `src/*.ts` plus a copy of `math.ts` in `src/lib/`.
+fn setup_indexed_project() -> TempDir {
+    common::with_fixture(&[(
+        "src/lib/math.ts",
+        include_str!("fixtures/typescript-simple/math.ts"),
+    )])
+}
+
+fn child<'a>(dir: &'a DirectoryStats, path: &str, loose: bool) -> &'a DirectoryStats {
+    dir.children
+        .iter()
+        .find(|c| c.path == path && c.loose_files == loose)
+        .expect("the child directory should be listed")
+}
+
+#[test]
+fn test_map_aggregates_per_directory() {
+    let temp = setup_indexed_project();
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+    let root = map::build_map(&conn, 3, MapMetric::Nodes).expect("Failed to build map");
+
+    assert_eq!(root.files, 4);
+    assert!(root.nodes > 0);
+    assert!(!root.kinds.contains_key("file"));
+
+    let src = child(&root, "src", false);
+    assert_eq!(src.files, 4);
+    let lib = child(src, "src/lib", false);
+    let loose = child(src, "src", true);
+    assert_eq!(lib.files, 1);
+    assert_eq!(loose.files, 3);
+    assert_eq!(lib.nodes + loose.nodes, src.nodes);
+    assert_eq!(lib.bytes + loose.bytes, src.bytes);
+    assert_eq!(lib.kinds.get("class"), Some(&1));
+
+    // Depth 1 folds src/lib into src.
+    let shallow = map::build_map(&conn, 1, MapMetric::Nodes).expect("Failed to build map");
+    let src = child(&shallow, "src", false);
+    assert!(src.children.is_empty());
+    assert_eq!(src.files, 4);
+}
+
+#[test]
+fn test_map_renders_text_and_html() {
+    let temp = setup_indexed_project();
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+    let root = map::build_map(&conn, 3, MapMetric::Bytes).expect("Failed to build map");
+
+    let text = map::render_text(&root, MapMetric::Bytes, &OutputStyle::from_env(true, false));
+    assert!(text.contains("sized by bytes"));
+    assert!(text.contains("  lib/"));
+    assert!(text.contains("(files)"));
+    assert!(text.contains("100.0%  ####"));
+    assert!(text.is_ascii(), "plain output should be ASCII");
+
+    let html = map::render_html(&root, MapMetric::Bytes);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("title=\"src/lib: 1 files"));
+}
@@ -0,0 +1,219 @@
+//! Integration tests for the streamable-HTTP MCP transport
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::mcp_http::{ENDPOINT_PATH, HttpServer, SESSION_HEADER};
+use serde_json::{Value, json};
+
+/// Start a server on an ephemeral port; returns the endpoint URL.
+fn start_server(project_root: &Path) -> String {
+    let server = HttpServer::bind(project_root, "127.0.0.1:0").expect("Failed to bind");
+    let addr = server
+        .local_addr()
+        .expect("Server should have an IP address");
+    std::thread::spawn(move || server.run());
+    format!("http://{addr}{ENDPOINT_PATH}")
+}
+
+fn agent() -> ureq::Agent {
+    ureq::Agent::new_with_config(
+        ureq::config::Config::builder()
+            .http_status_as_error(false)
+            .build(),
+    )
+}
+
+struct Reply {
+    status: u16,
+    session: Option<String>,
+    content_type: String,
+    body: String,
+}
+
+fn post(url: &str, session: Option<&str>, accept: &str, message: &Value) -> Reply {
+    let mut request = agent()
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header("Accept", accept);
+    if let Some(session) = session {
+        request = request.header(SESSION_HEADER, session);
+    }
+    let mut response = request
+        .send(message.to_string())
+        .expect("POST should complete");
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+    let session = header(SESSION_HEADER);
+    let content_type = header("Content-Type").unwrap_or_default();
+    Reply {
+        status: response.status().as_u16(),
+        session,
+        content_type,
+        body: response
+            .body_mut()
+            .read_to_string()
+            .expect("Body should be readable"),
+    }
+}
+
+fn initialize(url: &str) -> String {
+    let reply = post(
+        url,
+        None,
+        "application/json, text/event-stream",
+        &json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": "2025-11-25", "capabilities": {} },
+        }),
+    );
+    assert_eq!(reply.status, 200, "{}", reply.body);
+    let body: Value = serde_json::from_str(&reply.body).expect("Initialize should return JSON");
+    assert_eq!(
+        body.pointer("/result/protocolVersion"),
+        Some(&json!("2025-11-25"))
+    );
+    let session = reply
+        .session
+        .expect("Initialize should return a session id");
+
+    let initialized = post(
+        url,
+        Some(&session),
+        "application/json",
+        &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+    );
+    assert_eq!(initialized.status, 202);
+    session
+}
+
+#[test]
+fn test_mcp_http_session_lifecycle() {
+    let temp = common::setup_indexed_project();
+    let url = start_server(temp.path());
+
+    let unsessioned = post(
+        &url,
+        None,
+        "application/json",
+        &json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+    );
+    assert_eq!(unsessioned.status, 400);
+
+    let session = initialize(&url);
+
+    let list = post(
+        &url,
+        Some(&session),
+        "application/json",
+        &json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+    );
+    assert_eq!(list.status, 200);
+    assert!(list.content_type.starts_with("application/json"));
+    let list: Value = serde_json::from_str(&list.body).expect("tools/list should return JSON");
+    assert_eq!(list.get("id"), Some(&json!(2)));
+    assert!(
+        list.pointer("/result/tools")
+            .and_then(Value::as_array)
+            .is_some_and(|tools| !tools.is_empty())
+    );
+
+    // A second session is independent of the first.
+    let other = initialize(&url);
+    assert_ne!(session, other);
+
+    let deleted = agent()
+        .delete(&url)
+        .header(SESSION_HEADER, &session)
+        .call()
+        .expect("DELETE should complete");
+    assert_eq!(deleted.status().as_u16(), 204);
+
+    let after_delete = post(
+        &url,
+        Some(&session),
+        "application/json",
+        &json!({ "jsonrpc": "2.0", "id": 3, "method": "ping" }),
+    );
+    assert_eq!(after_delete.status, 404);
+
+    let ping = post(
+        &url,
+        Some(&other),
+        "application/json",
+        &json!({ "jsonrpc": "2.0", "id": 3, "method": "ping" }),
+    );
+    assert_eq!(ping.status, 200);
+}
+
+#[test]
+fn test_mcp_http_streams_tool_calls_with_progress_tokens() {
+    let temp = common::setup_indexed_project();
+    let url = start_server(temp.path());
+    let session = initialize(&url);
+
+    let reply = post(
+        &url,
+        Some(&session),
+        "application/json, text/event-stream",
+        &json!({
+            "jsonrpc": "2.0",
+            "id": "search-1",
+            "method": "tools/call",
+            "params": {
+                "name": "coraline_search",
+                "arguments": { "query": "getHistory" },
+                "_meta": { "progressToken": "p1" },
+            },
+        }),
+    );
+    assert_eq!(reply.status, 200);
+    assert!(reply.content_type.starts_with("text/event-stream"));
+
+    let messages: Vec<Value> = reply
+        .body
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).expect("Each event should be JSON"))
+        .collect();
+    let (response, progress) = messages
+        .split_last()
+        .expect("Stream should end with the response");
+    assert_eq!(response["id"], "search-1");
+    assert_eq!(response.pointer("/result/isError"), Some(&json!(false)));
+    assert!(
+        progress
+            .iter()
+            .all(|m| m["method"] == "notifications/progress")
+    );
+}
+
+#[test]
+fn test_mcp_http_rejects_foreign_origins_and_batches() {
+    let temp = common::setup_indexed_project();
+    let url = start_server(temp.path());
+
+    let foreign = agent()
+        .post(&url)
+        .header("Origin", "https://example.com")
+        .send(json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" }).to_string())
+        .expect("POST should complete");
+    assert_eq!(foreign.status().as_u16(), 403);
+
+    let batch = post(
+        &url,
+        None,
+        "application/json",
+        &json!([{ "jsonrpc": "2.0", "id": 1, "method": "ping" }]),
+    );
+    assert_eq!(batch.status, 400);
+}
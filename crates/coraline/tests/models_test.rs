@@ -0,0 +1,169 @@
+//! Integration tests for ORM model extraction and query linking
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::tools::responses::ImpactResponse;
+use coraline::types::{EdgeKind, Node, NodeKind};
+use coraline::{config, db, extraction, tools};
+use serde_json::json;
+
+const PROJECT_FILES: &[(&str, &str)] = &[
+    (
+        "server/src/models.rs",
+        r"
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = crate::schema::users)]
+pub struct User {
+    pub id: i32,
+}
+
+pub struct Settings {
+    pub verbose: bool,
+}
+",
+    ),
+    (
+        "server/src/repo.rs",
+        r#"
+pub fn list_users(conn: &mut PgConnection) -> Vec<User> {
+    users::table.load::<User>(conn).unwrap()
+}
+
+pub fn purge(conn: &mut PgConnection) {
+    sql_query("DELETE FROM users WHERE deleted").execute(conn).unwrap();
+}
+
+pub fn unrelated() -> i32 {
+    // Reads from users in the docs only.
+    1
+}
+"#,
+    ),
+    (
+        "api/models.py",
+        r#"
+class Base(DeclarativeBase):
+    pass
+
+class Invoice(Base):
+    __tablename__ = "invoices"
+
+def unpaid(session):
+    return session.query(Invoice).filter_by(paid=False).all()
+"#,
+    ),
+    (
+        "app/models/line_item.rb",
+        "class LineItem < ApplicationRecord\nend\n",
+    ),
+    (
+        "app/services/checkout.rb",
+        "class Checkout\n  def place(items)\n    LineItem.create(items)\n  end\nend\n",
+    ),
+    (
+        "prisma/schema.prisma",
+        "model Post {\n  id Int @id\n  @@map(\"posts\")\n}\n",
+    ),
+    (
+        "web/posts.ts",
+        "export async function recent() {\n  return prisma.post.findMany({ take: 10 });\n}\n",
+    ),
+];
+
+fn models(project: &Path) -> Vec<Node> {
+    let conn = db::open_database(project).expect("Failed to open database");
+    let mut models = db::get_nodes_by_kind(&conn, NodeKind::Model).expect("Failed to read nodes");
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    models
+}
+
+fn model(project: &Path, name: &str) -> Node {
+    models(project)
+        .into_iter()
+        .find(|m| m.name == name)
+        .expect("the model should be detected")
+}
+
+fn queried_by(project: &Path, model: &Node) -> Vec<String> {
+    let conn = db::open_database(project).expect("Failed to open database");
+    let mut names: Vec<String> =
+        db::get_edges_by_target(&conn, &model.id, Some(EdgeKind::TypeOf), 100)
+            .expect("Failed to read edges")
+            .into_iter()
+            .filter_map(|edge| db::get_node_by_id(&conn, &edge.source).expect("lookup"))
+            .map(|node| node.name)
+            .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_orm_models_and_their_tables() {
+    let temp = common::with_files(PROJECT_FILES);
+    let found: Vec<(String, Option<String>)> = models(temp.path())
+        .into_iter()
+        .map(|m| (m.name, m.signature))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("Invoice".to_string(), Some("table invoices".to_string())),
+            ("LineItem".to_string(), Some("table line_items".to_string())),
+            ("Post".to_string(), Some("table posts".to_string())),
+            ("User".to_string(), Some("table users".to_string())),
+        ]
+    );
+
+    // The model links to the struct defining it.
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+    let user = model(temp.path(), "User");
+    let edges =
+        db::get_edges_by_source(&conn, &user.id, Some(EdgeKind::References), 10).expect("edges");
+    let edge = edges.first().expect("User references its definition");
+    let definition = db::get_node_by_id(&conn, &edge.target)
+        .expect("lookup")
+        .expect("definition exists");
+    assert_eq!(
+        (definition.kind, definition.name.as_str()),
+        (NodeKind::Struct, "User")
+    );
+}
+
+#[test]
+fn test_queries_link_to_models_and_show_in_impact() {
+    let temp = common::with_files(PROJECT_FILES);
+    let project = temp.path();
+    assert_eq!(
+        queried_by(project, &model(project, "User")),
+        ["list_users", "purge"]
+    );
+    assert_eq!(queried_by(project, &model(project, "Invoice")), ["unpaid"]);
+    assert_eq!(queried_by(project, &model(project, "LineItem")), ["place"]);
+    assert_eq!(queried_by(project, &model(project, "Post")), ["recent"]);
+
+    let result = tools::create_default_registry(project)
+        .execute(
+            "coraline_impact",
+            json!({ "node_id": model(project, "User").id }),
+        )
+        .expect("coraline_impact failed");
+    let impact: ImpactResponse = serde_json::from_value(result).expect("valid response");
+    assert!(impact.nodes.iter().any(|n| n.name == "list_users"));
+    assert!(!impact.nodes.iter().any(|n| n.name == "unrelated"));
+
+    // A sync relinks the queries of changed files.
+    common::write(
+        project,
+        "server/src/audit.rs",
+        "pub fn recent_signups() {\n    sql_query(\"SELECT id FROM users\");\n}\n",
+    );
+    let cfg = config::create_default_config(project);
+    extraction::sync(project, &cfg, None).expect("Sync failed");
+    assert_eq!(
+        queried_by(project, &model(project, "User")),
+        ["list_users", "purge", "recent_signups"]
+    );
+}
@@ -0,0 +1,103 @@
+//! Integration tests for MCP prompts
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::{memory, prompts, tools};
+use serde_json::{Map, Value, json};
+use tempfile::TempDir;
+
+/// The indexed fixture with the initial project memories.
+fn setup_indexed_project() -> TempDir {
+    let temp_dir = common::setup_indexed_project();
+    memory::create_initial_memories(temp_dir.path(), "fixture").expect("Failed to write memories");
+    temp_dir
+}
+
+fn arguments(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+fn prompt_text(project: &Path, name: &str, args: Value) -> String {
+    let registry = tools::create_default_registry(project);
+    let prompt = prompts::get_prompt(project, &registry, name, &arguments(args))
+        .expect("Prompt should render")
+        .expect("Prompt should exist");
+    assert_eq!(prompt.messages.len(), 1);
+    let message = prompt.messages.first().expect("one message");
+    assert_eq!(message.role, "user");
+    message.content.text.clone()
+}
+
+#[test]
+fn test_prompts_list_declares_arguments() {
+    let listed = prompts::list_prompts();
+    let review = listed
+        .iter()
+        .find(|p| p.name == "review_impact")
+        .expect("review_impact should be listed");
+    assert!(
+        review
+            .arguments
+            .iter()
+            .any(|a| a.name == "symbol" && a.required)
+    );
+    assert!(listed.iter().any(|p| p.name == "plan_change"));
+    assert!(listed.iter().any(|p| p.name == "onboarding"));
+}
+
+#[test]
+fn test_prompts_are_prefilled_with_graph_data() {
+    let temp = setup_indexed_project();
+
+    let review = prompt_text(
+        temp.path(),
+        "review_impact",
+        json!({ "symbol": "getHistory" }),
+    );
+    assert!(review.contains("`getHistory`"));
+    assert!(review.contains("## Impact analysis"));
+    assert!(review.contains("\"node_count\""));
+
+    let plan = prompt_text(
+        temp.path(),
+        "plan_change",
+        json!({ "task": "Add a modulo operation to the calculator" }),
+    );
+    assert!(plan.contains("## Relevant code"));
+
+    let onboarding = prompt_text(temp.path(), "onboarding", json!({ "focus": "math" }));
+    assert!(onboarding.contains("Focus on: math."));
+    assert!(onboarding.contains("# Code graph summary"));
+    assert!(onboarding.contains("## Memory: "));
+}
+
+#[test]
+fn test_prompts_reject_bad_requests() {
+    let temp = setup_indexed_project();
+    let registry = tools::create_default_registry(temp.path());
+
+    let missing = prompts::get_prompt(temp.path(), &registry, "review_impact", &Map::new())
+        .expect_err("symbol is required");
+    assert_eq!(missing.code, "invalid_params");
+
+    let unknown_symbol = prompts::get_prompt(
+        temp.path(),
+        &registry,
+        "review_impact",
+        &arguments(json!({ "symbol": "noSuchSymbol" })),
+    )
+    .expect_err("unknown symbol should fail");
+    assert_eq!(unknown_symbol.code, "not_found");
+
+    assert!(
+        prompts::get_prompt(temp.path(), &registry, "nope", &Map::new())
+            .expect("Lookup should not fail")
+            .is_none()
+    );
+}
@@ -0,0 +1,139 @@
+//! Integration tests for remote project federation
+#![allow(clippy::expect_used)]
+
+use std::path::Path;
+use std::process::Command;
+
+use coraline::error::CoralineError;
+use coraline::{config, db, extraction, remotes};
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+fn indexed_project(files: &[(&str, &str)]) -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    for (path, content) in files {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().expect("path has a parent"))
+            .expect("Failed to create directory");
+        std::fs::write(path, content).expect("Failed to write file");
+    }
+    db::initialize_database(root).expect("Failed to initialize database");
+    let cfg = config::create_default_config(root);
+    config::save_config(root, &cfg).expect("Failed to save config");
+    extraction::index_all(root, &cfg, false, None).expect("Failed to index project");
+    temp_dir
+}
+
+fn shared_and_app() -> (TempDir, TempDir) {
+    let shared = indexed_project(&[
+        ("package.json", r#"{ "name": "@acme/shared" }"#),
+        (
+            "src/parse.ts",
+            "export function parseConfig(text: string) { return JSON.parse(text); }\n\nexport function loadConfig() { return parseConfig('{}'); }\n",
+        ),
+    ]);
+    let app = indexed_project(&[(
+        "src/main.ts",
+        "import { parseConfig } from '@acme/shared';\n\nexport function loadConfig() { return parseConfig('{}'); }\n",
+    )]);
+    (shared, app)
+}
+
+fn coraline(root: &Path, args: &[&str]) -> String {
+    let (command, rest) = args.split_first().expect("a subcommand");
+    let output = Command::new(env!("CARGO_BIN_EXE_coraline"))
+        .arg(command)
+        .arg("-p")
+        .arg(root)
+        .args(rest)
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run coraline");
+    assert!(
+        output.status.success(),
+        "coraline {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_remote_registry_and_cross_repo_imports() {
+    let (shared, app) = shared_and_app();
+    let shared_path = shared.path().to_str().expect("utf-8 path");
+
+    let remote = remotes::add(app.path(), shared_path, None).expect("Failed to add remote");
+    assert_eq!(remote.name, "@acme/shared");
+    assert!(matches!(
+        remotes::add(app.path(), shared_path, None),
+        Err(CoralineError::InvalidInput(_))
+    ));
+    let empty = TempDir::new().expect("Failed to create temp directory");
+    assert!(matches!(
+        remotes::add(app.path(), empty.path().to_str().expect("utf-8"), None),
+        Err(CoralineError::NotInitialized(_))
+    ));
+    remotes::add(app.path(), "http://127.0.0.1:7880/", Some("api")).expect("Failed to add url");
+    let listed = remotes::list(app.path()).expect("Failed to list remotes");
+    assert_eq!(listed.len(), 2);
+    assert_eq!(
+        listed.get(1).map(|remote| remote.location.as_str()),
+        Some("http://127.0.0.1:7880")
+    );
+    assert!(remotes::remove(app.path(), "api").expect("Failed to remove"));
+    assert!(!remotes::remove(app.path(), "api").expect("Failed to remove"));
+
+    let conn = db::open_database(app.path()).expect("Failed to open database");
+    let links = remotes::resolve_imports(app.path(), &conn, listed.get(..1).unwrap_or_default())
+        .expect("Failed to resolve imports");
+    assert_eq!(links.len(), 1, "{links:?}");
+    let link = links.first().expect("one link");
+    assert_eq!(link.file_path, "src/main.ts");
+    assert_eq!(link.module, "@acme/shared");
+    assert_eq!(link.symbol.as_deref(), Some("parseConfig"));
+    assert_eq!(link.package, "@acme/shared");
+    let target = link.target.as_ref().expect("parseConfig should resolve");
+    assert_eq!(target.file_path, "src/parse.ts");
+}
+
+#[test]
+fn test_cli_query_merges_remote_results() {
+    let (shared, app) = shared_and_app();
+    let shared_path = shared.path().to_str().expect("utf-8 path");
+
+    let added = coraline(
+        app.path(),
+        &["remote", "add", shared_path, "--name", "shared"],
+    );
+    assert!(added.contains("Added remote shared"), "{added}");
+    let listed = coraline(app.path(), &["remote", "list"]);
+    assert!(listed.starts_with("shared  "), "{listed}");
+
+    let merged = coraline(app.path(), &["query", "loadConfig", "--kind", "function"]);
+    assert!(merged.contains("  src/main.ts:"), "{merged}");
+    assert!(merged.contains("  shared:src/parse.ts:"), "{merged}");
+    let local = coraline(
+        app.path(),
+        &["query", "loadConfig", "--kind", "function", "--local-only"],
+    );
+    assert!(!local.contains("shared:"), "{local}");
+
+    let json = coraline(
+        app.path(),
+        &["query", "parseConfig", "--kind", "function", "--json"],
+    );
+    let hits: Vec<Value> = serde_json::from_str(&json).expect("valid JSON");
+    assert!(
+        hits.iter().any(|hit| hit["remote"] == "shared"
+            && hit.pointer("/node/name") == Some(&json!("parseConfig"))),
+        "{json}"
+    );
+
+    let resolved = coraline(app.path(), &["remote", "resolve"]);
+    assert!(
+        resolved
+            .contains("parseConfig from @acme/shared  ->  shared [@acme/shared] src/parse.ts:1"),
+        "{resolved}"
+    );
+}
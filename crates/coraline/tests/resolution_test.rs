@@ -0,0 +1,379 @@
+//! Integration tests for reference resolution across passes and syncs
+#![allow(clippy::expect_used)]
+
+use std::path::Path;
+
+use coraline::resolution::{self, ReferenceResolver};
+use coraline::types::{Edge, EdgeKind, NodeKind};
+use coraline::{config, db, extraction};
+use tempfile::TempDir;
+
+const CALLER: &str =
+    "export function useIt(): number {\n    return missingA() + missingB() + triple(2);\n}\n";
+const CALLEE: &str = "export function triple(x: number): number {\n    return x * 3;\n}\n";
+
+fn setup_project(files: &[(&str, &str)]) -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp_dir.path();
+    db::initialize_database(project_path).expect("Failed to initialize database");
+    std::fs::create_dir_all(project_path.join("src")).expect("Failed to create src");
+    for (name, content) in files {
+        std::fs::write(project_path.join("src").join(name), content).expect("Failed to write");
+    }
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+    temp_dir
+}
+
+fn node_id(conn: &rusqlite::Connection, file: &str, name: &str) -> String {
+    db::get_nodes_by_file(conn, file, Some(NodeKind::Function))
+        .expect("Failed to get nodes")
+        .into_iter()
+        .find(|n| n.name == name)
+        .map(|n| n.id)
+        .expect("node should exist")
+}
+
+fn calls_triple(project: &Path) -> bool {
+    let conn = db::open_database(project).expect("Failed to open database");
+    let triple = node_id(&conn, "src/b.ts", "triple");
+    let use_it = node_id(&conn, "src/a.ts", "useIt");
+    db::get_edges_by_target(&conn, &triple, Some(EdgeKind::Calls), 10)
+        .expect("Failed to get edges")
+        .iter()
+        .any(|e| e.source == use_it)
+}
+
+#[test]
+fn test_sync_resolves_references_to_added_symbols() {
+    let temp_dir = setup_project(&[("a.ts", CALLER)]);
+    let project = temp_dir.path();
+    let conn = db::open_database(project).expect("Failed to open database");
+    let before = db::count_unresolved_refs(&conn).expect("Failed to count");
+    assert!(before >= 3, "missingA, missingB, and triple are unresolved");
+
+    std::fs::write(project.join("src/b.ts"), CALLEE).expect("Failed to write");
+    let cfg = config::create_default_config(project);
+    extraction::sync(project, &cfg, None).expect("Failed to sync");
+    assert!(calls_triple(project), "the old reference now names triple");
+    assert_eq!(
+        db::count_unresolved_refs(&conn).expect("Failed to count"),
+        before - 1
+    );
+}
+
+#[test]
+fn test_resolution_pages_past_unresolvable_references() {
+    let temp_dir = setup_project(&[("a.ts", CALLER), ("b.ts", CALLEE)]);
+    let project = temp_dir.path();
+    assert!(calls_triple(project));
+
+    // Put the call back behind the two references that cannot resolve.
+    let mut conn = db::open_database(project).expect("Failed to open database");
+    let use_it = node_id(&conn, "src/a.ts", "useIt");
+    conn.execute(
+        "DELETE FROM edges WHERE source = ?1 AND kind = 'calls'",
+        [&use_it],
+    )
+    .expect("Failed to delete edge");
+    conn.execute(
+        "INSERT INTO unresolved_refs (from_node_id, reference_name, reference_kind, line, col)
+         VALUES (?1, 'triple', 'calls', 2, 40)",
+        [&use_it],
+    )
+    .expect("Failed to insert reference");
+    let pending = db::count_unresolved_refs(&conn).expect("Failed to count");
+    assert!(!calls_triple(project));
+
+    let result =
+        ReferenceResolver::resolve_unresolved(&mut conn, project, 1).expect("Failed to resolve");
+    assert_eq!(result.scanned, pending);
+    assert_eq!(result.resolved, 1);
+    assert_eq!(result.remaining, pending - 1);
+    assert_eq!(result.passes, 2, "a second pass finds nothing more");
+    assert!(calls_triple(project));
+}
+
+#[test]
+fn test_resolution_limit_caps_references_scanned() {
+    let temp_dir = setup_project(&[("a.ts", CALLER), ("b.ts", CALLEE)]);
+    let project = temp_dir.path();
+
+    // The call becomes the newest unresolved reference.
+    let mut conn = db::open_database(project).expect("Failed to open database");
+    let use_it = node_id(&conn, "src/a.ts", "useIt");
+    conn.execute(
+        "DELETE FROM edges WHERE source = ?1 AND kind = 'calls'",
+        [&use_it],
+    )
+    .expect("Failed to delete edge");
+    conn.execute(
+        "INSERT INTO unresolved_refs (from_node_id, reference_name, reference_kind, line, col)
+         VALUES (?1, 'triple', 'calls', 2, 40)",
+        [&use_it],
+    )
+    .expect("Failed to insert reference");
+    let pending = db::count_unresolved_refs(&conn).expect("Failed to count");
+
+    let result = ReferenceResolver::resolve_first(&mut conn, project, pending - 1, 1)
+        .expect("Failed to resolve");
+    assert_eq!(result.scanned, pending - 1);
+    assert_eq!(result.resolved, 0);
+    assert!(!calls_triple(project));
+
+    let result = ReferenceResolver::resolve_first(&mut conn, project, pending, 1)
+        .expect("Failed to resolve");
+    assert_eq!(result.scanned, pending);
+    assert_eq!(result.resolved, 1);
+    assert!(calls_triple(project));
+}
+
+/// A project of `files`, by path from the project root, indexed.
+fn index_files(files: &[(&str, &str)]) -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project = temp_dir.path();
+    db::initialize_database(project).expect("Failed to initialize database");
+    for (path, content) in files {
+        let path = project.join(path);
+        std::fs::create_dir_all(path.parent().expect("file has a parent"))
+            .expect("Failed to create directory");
+        std::fs::write(path, content).expect("Failed to write");
+    }
+    let cfg = config::create_default_config(project);
+    extraction::index_all(project, &cfg, false, None).expect("Failed to index project");
+    temp_dir
+}
+
+/// The call edge from `source` to `target`, by their qualified names.
+fn call_edge(conn: &rusqlite::Connection, source: &str, target: &str) -> Option<Edge> {
+    let id = |qualified: &str| {
+        db::find_nodes_by_name(conn, qualified.rsplit("::").next().unwrap_or(qualified))
+            .expect("Failed to find nodes")
+            .into_iter()
+            .find(|n| n.qualified_name == qualified)
+            .map(|n| n.id)
+            .expect("node should exist")
+    };
+    let target = id(target);
+    db::get_edges_by_source(conn, &id(source), Some(EdgeKind::Calls), 50)
+        .expect("Failed to get edges")
+        .into_iter()
+        .find(|e| e.target == target)
+}
+
+/// The `method` and `confidence` the resolver recorded on `edge`.
+fn resolution(edge: &Edge) -> Option<(String, f64)> {
+    let resolution = edge.metadata.as_ref()?.get("resolution")?;
+    Some((
+        resolution.get("method")?.as_str()?.to_string(),
+        resolution.get("confidence")?.as_f64()?,
+    ))
+}
+
+#[test]
+fn test_resolution_breaks_ties_by_call_site() {
+    let temp_dir = setup_project(&[
+        (
+            "short.ts",
+            "export function pad(s: string): string {\n    return s;\n}\n",
+        ),
+        (
+            "long.ts",
+            "export function pad(s: string, width: number, fill = \" \"): string {\n    return s.padStart(width, fill);\n}\n",
+        ),
+        (
+            "shapes.ts",
+            "export class Square {\n    area(): number {\n        return 1;\n    }\n    describe(): string {\n        return `${this.area()}`;\n    }\n}\n\nexport class Circle {\n    area(): number {\n        return 2;\n    }\n}\n",
+        ),
+        (
+            "report.ts",
+            "export function report(): string {\n    return pad(\"x\", 3) + pad(\"y\", 3, \"-\");\n}\n",
+        ),
+    ]);
+    let conn = db::open_database(temp_dir.path()).expect("Failed to open database");
+
+    let by_arity = call_edge(&conn, "src/report.ts::report", "src/long.ts::pad")
+        .expect("two and three arguments fit only the longer pad");
+    assert_eq!(
+        resolution(&by_arity),
+        Some(("arity".to_string(), 0.75)),
+        "the tie-break keeps the lower confidence of a same-directory match"
+    );
+    assert!(call_edge(&conn, "src/report.ts::report", "src/short.ts::pad").is_none());
+
+    let by_receiver = call_edge(
+        &conn,
+        "src/shapes.ts::Square::describe",
+        "src/shapes.ts::Square::area",
+    )
+    .expect("this.area() calls the method of the caller's class");
+    assert_eq!(
+        resolution(&by_receiver),
+        Some(("self_receiver".to_string(), 0.9))
+    );
+    assert!(
+        call_edge(
+            &conn,
+            "src/shapes.ts::Square::describe",
+            "src/shapes.ts::Circle::area"
+        )
+        .is_none()
+    );
+}
+
+#[test]
+fn test_unresolved_names_rank_by_references() {
+    let temp_dir = setup_project(&[
+        ("a.ts", CALLER),
+        (
+            "c.ts",
+            "export function again(): number {\n    return missingA();\n}\n",
+        ),
+    ]);
+    let conn = db::open_database(temp_dir.path()).expect("Failed to open database");
+
+    let names = resolution::unresolved_names(&conn, 2).expect("Failed to list names");
+    let listed: Vec<(&str, EdgeKind, usize, usize)> = names
+        .iter()
+        .map(|n| (n.name.as_str(), n.kind, n.references, n.candidates))
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            ("missingA", EdgeKind::Calls, 2, 0),
+            ("missingB", EdgeKind::Calls, 1, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_resolution_follows_tsconfig_path_aliases() {
+    let temp_dir = index_files(&[
+        (
+            "tsconfig.json",
+            "{\n  // Aliases for the app\n  \"compilerOptions\": {\n    \"baseUrl\": \".\",\n    \"paths\": { \"@lib/*\": [\"src/lib/*\"], },\n  },\n}\n",
+        ),
+        (
+            "src/lib/math.ts",
+            "export function scale(x: number): number {\n    return x * 2;\n}\n",
+        ),
+        (
+            "src/legacy/math.ts",
+            "export function scale(x: number): number {\n    return x * 3;\n}\n",
+        ),
+        (
+            "src/app/main.ts",
+            "import { scale } from '@lib/math';\n\nexport function run(): number {\n    return scale(1);\n}\n",
+        ),
+        (
+            "src/app/old.ts",
+            "import { scale } from '../legacy/math.js';\n\nexport function runOld(): number {\n    return scale(1);\n}\n",
+        ),
+    ]);
+    let conn = db::open_database(temp_dir.path()).expect("Failed to open database");
+
+    let aliased = call_edge(&conn, "src/app/main.ts::run", "src/lib/math.ts::scale")
+        .expect("@lib/math maps to src/lib/math.ts");
+    assert_eq!(
+        resolution(&aliased).map(|(method, _)| method).as_deref(),
+        Some("import_hint")
+    );
+    assert!(call_edge(&conn, "src/app/main.ts::run", "src/legacy/math.ts::scale").is_none());
+    assert!(
+        call_edge(&conn, "src/app/old.ts::runOld", "src/legacy/math.ts::scale").is_some(),
+        "a relative specifier from another directory resolves too"
+    );
+}
+
+#[test]
+fn test_resolution_follows_rust_module_paths() {
+    let temp_dir = index_files(&[
+        (
+            "Cargo.toml",
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n",
+        ),
+        (
+            "src/lib.rs",
+            "pub mod db;\nmod util;\n\npub fn run() {\n    db::query::run_query();\n    crate::util::helper();\n}\n",
+        ),
+        ("src/db.rs", "pub mod query;\n\npub fn open_database() {}\n"),
+        (
+            "src/db/query.rs",
+            "use super::open_database;\n\npub fn run_query() {\n    open_database();\n}\n",
+        ),
+        (
+            "src/util/mod.rs",
+            "pub fn helper() {}\npub fn open_database() {}\npub fn run_query() {}\n",
+        ),
+        ("src/other/mod.rs", "pub fn helper() {}\n"),
+    ]);
+    let conn = db::open_database(temp_dir.path()).expect("Failed to open database");
+
+    let method = |source: &str, target: &str| {
+        call_edge(&conn, source, target)
+            .as_ref()
+            .and_then(resolution)
+            .map(|(method, _)| method)
+    };
+    assert_eq!(
+        method("src/lib.rs::run", "src/db/query.rs::run_query").as_deref(),
+        Some("module_path"),
+        "db::query lives in src/db/query.rs, declared from src/db.rs"
+    );
+    assert!(call_edge(&conn, "src/lib.rs::run", "src/util/mod.rs::run_query").is_none());
+    assert_eq!(
+        method("src/lib.rs::run", "src/util/mod.rs::helper").as_deref(),
+        Some("module_path")
+    );
+    assert_eq!(
+        method("src/db/query.rs::run_query", "src/db.rs::open_database").as_deref(),
+        Some("module_path"),
+        "super:: from db::query is db"
+    );
+}
+
+#[test]
+fn test_resolution_follows_python_imports() {
+    let temp_dir = index_files(&[
+        ("app/__init__.py", ""),
+        ("app/services/__init__.py", "def make():\n    return 1\n"),
+        (
+            "app/services/mailer.py",
+            "from ..util import clean as tidy\nfrom . import make\n\ndef send(to):\n    tidy(to)\n    return make()\n",
+        ),
+        ("app/util.py", "def clean(x):\n    return x\n"),
+        (
+            "lib/util.py",
+            "def clean(x):\n    return x\n\ndef send(to):\n    return to\n",
+        ),
+        (
+            "main.py",
+            "import app.services.mailer\nimport app.util as u\nfrom lib import util\n\ndef run():\n    app.services.mailer.send(1)\n    u.clean(2)\n    util.clean(3)\n",
+        ),
+    ]);
+    let conn = db::open_database(temp_dir.path()).expect("Failed to open database");
+
+    let method = |source: &str, target: &str| {
+        call_edge(&conn, source, target)
+            .as_ref()
+            .and_then(resolution)
+            .map(|(method, _)| method)
+    };
+    for (source, target) in [
+        ("main.py::run", "app/services/mailer.py::send"),
+        ("main.py::run", "app/util.py::clean"),
+        ("main.py::run", "lib/util.py::clean"),
+        ("app/services/mailer.py::send", "app/util.py::clean"),
+        (
+            "app/services/mailer.py::send",
+            "app/services/__init__.py::make",
+        ),
+    ] {
+        assert_eq!(
+            method(source, target).as_deref(),
+            Some("module_path"),
+            "{source} calls {target}"
+        );
+    }
+    assert!(call_edge(&conn, "main.py::run", "lib/util.py::send").is_none());
+}
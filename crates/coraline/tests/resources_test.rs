@@ -0,0 +1,68 @@
+//! Integration tests for MCP resources
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::{config, memory, resources};
+use tempfile::TempDir;
+
+/// The indexed fixture with the initial project memories.
+fn setup_indexed_project() -> TempDir {
+    let temp_dir = common::setup_indexed_project();
+    config::write_toml_template(temp_dir.path()).expect("Failed to write config.toml");
+    memory::create_initial_memories(temp_dir.path(), "fixture").expect("Failed to write memories");
+    temp_dir
+}
+
+#[test]
+fn test_resources_list_memories_config_and_summary() {
+    let temp = setup_indexed_project();
+    let listed = resources::list_resources(temp.path());
+    let uris: Vec<&str> = listed.iter().map(|r| r.uri.as_str()).collect();
+
+    assert!(uris.contains(&resources::GRAPH_SUMMARY_URI));
+    assert!(uris.contains(&resources::CONFIG_URI));
+    assert!(
+        uris.contains(&"coraline://memories/shared/project_overview"),
+        "memories should be listed: {uris:?}"
+    );
+
+    for resource in &listed {
+        let contents = resources::read_resource(temp.path(), &resource.uri)
+            .expect("Failed to read resource")
+            .expect("Listed resource should be readable");
+        assert_eq!(contents.mime_type, resource.mime_type);
+        assert!(!contents.text.is_empty(), "{} is empty", resource.uri);
+    }
+}
+
+#[test]
+fn test_graph_summary_describes_index() {
+    let temp = setup_indexed_project();
+    let summary = resources::read_resource(temp.path(), resources::GRAPH_SUMMARY_URI)
+        .expect("Failed to read summary")
+        .expect("Summary should exist");
+
+    assert!(summary.text.contains("- Files: 3"));
+    assert!(summary.text.contains("## Symbol kinds"));
+    assert!(summary.text.contains("`src/math.ts`"));
+}
+
+#[test]
+fn test_read_resource_rejects_unknown_uris() {
+    let temp = setup_indexed_project();
+    for uri in [
+        "coraline://memories/../config.toml",
+        "coraline://memories/missing",
+        "coraline://memories/project_overview",
+        "coraline://nowhere",
+        "file:///etc/passwd",
+    ] {
+        assert!(
+            resources::read_resource(temp.path(), uri)
+                .expect("Lookup should not fail")
+                .is_none(),
+            "{uri} should not resolve"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Integration tests for the REST API endpoints
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::config::SearchConfig;
+use coraline::{db, rest};
+use serde_json::{Value, json};
+
+fn get_json(project: &Path, conn: &rusqlite::Connection, url: &str) -> (u16, Value) {
+    let response = rest::route(project, conn, &SearchConfig::default(), url);
+    assert_eq!(response.content_type, "application/json", "url: {url}");
+    let body = serde_json::from_str(&response.body).expect("Response should be valid JSON");
+    (response.status, body)
+}
+
+#[test]
+fn test_rest_search_node_and_callers() {
+    let temp = common::setup_indexed_project();
+    let project = temp.path();
+    let conn = db::open_database(project).expect("Failed to open database");
+
+    let (status, body) = get_json(project, &conn, "/search?q=multiply&kind=function");
+    assert_eq!(status, 200);
+    let multiply = body
+        .pointer("/results/0/node")
+        .expect("Expected a search result");
+    assert_eq!(multiply.get("name"), Some(&json!("multiply")));
+    let id = multiply
+        .get("id")
+        .and_then(Value::as_str)
+        .expect("Node id should be a string");
+
+    let (status, node) = get_json(project, &conn, &format!("/nodes/{id}"));
+    assert_eq!(status, 200);
+    assert_eq!(node.get("file_path"), Some(&json!("src/math.ts")));
+
+    let (status, response) = get_json(project, &conn, &format!("/callers/{id}?limit=5"));
+    assert_eq!(status, 200);
+    assert_eq!(response.pointer("/node/id"), Some(&json!(id)));
+    let callers = response
+        .get("callers")
+        .and_then(Value::as_array)
+        .expect("callers should be an array");
+    assert!(
+        callers
+            .iter()
+            .any(|c| c.pointer("/node/name") == Some(&json!("quickMath"))),
+        "quickMath calls multiply"
+    );
+    assert!(
+        callers
+            .iter()
+            .all(|c| c.get("line").is_some_and(Value::is_number))
+    );
+
+    let (status, _) = get_json(project, &conn, "/callers/missing");
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn test_rest_stats_context_and_openapi() {
+    let temp = common::setup_indexed_project();
+    let project = temp.path();
+    let conn = db::open_database(project).expect("Failed to open database");
+
+    let (status, counts) = get_json(project, &conn, "/stats");
+    assert_eq!(status, 200);
+    assert_eq!(counts.get("file_count"), Some(&json!(3)));
+
+    let (status, context) = get_json(project, &conn, "/context?task=calculator%20add");
+    assert_eq!(status, 200);
+    assert!(context.is_object());
+    let markdown = rest::route(
+        project,
+        &conn,
+        &SearchConfig::default(),
+        "/context?task=calculator&format=markdown",
+    );
+    assert_eq!(markdown.status, 200);
+    assert!(markdown.content_type.starts_with("text/markdown"));
+    let (status, _) = get_json(project, &conn, "/context");
+    assert_eq!(status, 400);
+
+    let (status, openapi) = get_json(project, &conn, "/openapi.json");
+    assert_eq!(status, 200);
+    assert_eq!(openapi.get("openapi"), Some(&json!("3.0.3")));
+    for path in [
+        "/search",
+        "/nodes/{id}",
+        "/callers/{id}",
+        "/context",
+        "/stats",
+    ] {
+        assert!(
+            openapi
+                .get("paths")
+                .and_then(|paths| paths.get(path))
+                .and_then(|path| path.get("get"))
+                .is_some_and(Value::is_object),
+            "{path} documented"
+        );
+    }
+
+    let (status, _) = get_json(project, &conn, "/nope");
+    assert_eq!(status, 404);
+}
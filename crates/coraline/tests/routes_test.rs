@@ -0,0 +1,164 @@
+//! Integration tests for HTTP route extraction and `coraline_routes`
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::tools;
+use coraline::tools::responses::RoutesResponse;
+use serde_json::{Value, json};
+
+const PROJECT_FILES: &[(&str, &str)] = &[
+    (
+        "server/src/main.rs",
+        r#"
+use axum::{Router, routing::get};
+
+fn app() -> Router {
+    Router::new()
+        .route("/users", get(list_users).post(create_user))
+        .route("/users/{id}", get(get_user))
+}
+
+async fn list_users() {}
+async fn create_user() {}
+async fn get_user() {}
+
+#[actix_web::delete("/users/{id}")]
+async fn delete_user() {}
+"#,
+    ),
+    (
+        "web/app.ts",
+        r#"
+import express from "express";
+import { listOrders } from "./orders";
+
+const app = express();
+app.get("/orders", auth, listOrders);
+app.post("/orders/:id/cancel", (req, res) => res.send("ok"));
+
+function auth(req, res, next) { next(); }
+"#,
+    ),
+    (
+        "web/orders.ts",
+        "export function listOrders(req, res) { res.json([]); }\n",
+    ),
+    (
+        "api/main.py",
+        r#"
+from fastapi import FastAPI
+
+app = FastAPI()
+
+@app.get("/items/{item_id}")
+async def read_item(item_id: int):
+    return {"id": item_id}
+
+@app.route("/health", methods=["GET", "HEAD"])
+def health():
+    return "ok"
+"#,
+    ),
+];
+
+fn routes(project: &Path, params: Value) -> RoutesResponse {
+    let result = tools::create_default_registry(project)
+        .execute("coraline_routes", params)
+        .expect("coraline_routes failed");
+    serde_json::from_value(result).expect("valid response")
+}
+
+fn handler(response: &RoutesResponse, method: &str, path: &str) -> Option<String> {
+    response
+        .routes
+        .iter()
+        .find(|r| r.method == method && r.path == path)
+        .expect("the route should be detected")
+        .handler
+        .as_ref()
+        .map(|h| h.name.clone())
+}
+
+#[test]
+fn test_routes_link_to_their_handlers() {
+    let temp = common::with_files(PROJECT_FILES);
+    let all = routes(temp.path(), json!({}));
+    assert_eq!(all.total, 9);
+
+    assert_eq!(
+        handler(&all, "GET", "/users").as_deref(),
+        Some("list_users")
+    );
+    assert_eq!(
+        handler(&all, "POST", "/users").as_deref(),
+        Some("create_user")
+    );
+    assert_eq!(
+        handler(&all, "GET", "/users/{id}").as_deref(),
+        Some("get_user")
+    );
+    assert_eq!(
+        handler(&all, "DELETE", "/users/{id}").as_deref(),
+        Some("delete_user")
+    );
+    // Resolved across files; the middleware before it is not the handler.
+    assert_eq!(
+        handler(&all, "GET", "/orders").as_deref(),
+        Some("listOrders")
+    );
+    assert_eq!(handler(&all, "POST", "/orders/:id/cancel"), None);
+    assert_eq!(
+        handler(&all, "GET", "/items/{item_id}").as_deref(),
+        Some("read_item")
+    );
+    assert_eq!(handler(&all, "HEAD", "/health").as_deref(), Some("health"));
+}
+
+#[test]
+fn test_routes_filter_by_method_and_concrete_path() {
+    let temp = common::with_files(PROJECT_FILES);
+    let served = routes(temp.path(), json!({ "method": "get", "path": "/users/42" }));
+    assert_eq!(served.total, 1);
+    assert_eq!(
+        handler(&served, "GET", "/users/{id}").as_deref(),
+        Some("get_user")
+    );
+
+    let cancel = routes(temp.path(), json!({ "path": "/orders/7/cancel" }));
+    assert_eq!(cancel.total, 1);
+    assert_eq!(
+        cancel.routes.first().map(|r| r.file_path.as_str()),
+        Some("web/app.ts")
+    );
+
+    assert_eq!(routes(temp.path(), json!({ "method": "PUT" })).total, 0);
+}
+
+#[test]
+fn test_routes_skip_comments_and_repeated_methods() {
+    let temp = common::with_files(&[(
+        "server/src/lib.rs",
+        r#"
+//! Register routes with `.route("/docs", get(docs_page)`, for example.
+
+use axum::{Router, routing::get};
+
+/// ```
+/// let app = Router::new().route("/users", get(list_users);
+/// ```
+fn app() -> Router {
+    // .route("/commented", get(list_users))
+    Router::new().route("/feed", get(latest).get(oldest))
+}
+
+async fn latest() {}
+async fn oldest() {}
+"#,
+    )]);
+    let all = routes(temp.path(), json!({}));
+    assert_eq!(all.total, 1);
+    assert_eq!(handler(&all, "GET", "/feed").as_deref(), Some("latest"));
+}
@@ -0,0 +1,100 @@
+//! Integration tests for keeping sensitive files out of the index
+#![allow(clippy::expect_used)]
+
+use std::path::Path;
+
+use coraline::sensitive::SensitiveFiles;
+use coraline::types::{BuildContextOptions, ContextFormat};
+use coraline::{config, context, db, export, extraction};
+use tempfile::TempDir;
+
+const SECRET: &str = "sk-live-0123456789";
+
+fn write_toml(project_path: &Path, exclude_sensitive: bool) {
+    let toml = format!(
+        "[indexing]\nexclude_sensitive_files = {exclude_sensitive}\n\
+         sensitive_patterns = [\"**/secret_*.ts\"]\n"
+    );
+    std::fs::write(config::toml_config_path(project_path), toml).expect("Failed to write config");
+}
+
+fn indexed_files(project_path: &Path) -> Vec<String> {
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let mut files: Vec<String> = db::list_files(&conn)
+        .expect("Failed to list files")
+        .into_iter()
+        .map(|f| f.path)
+        .collect();
+    files.sort();
+    files
+}
+
+fn sync(project_path: &Path) {
+    let cfg = config::create_default_config(project_path);
+    extraction::sync(project_path, &cfg, None).expect("Failed to sync");
+}
+
+#[test]
+fn test_sensitive_files_stay_out_of_index_context_and_export() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project_path = temp_dir.path();
+    db::initialize_database(project_path).expect("Failed to initialize database");
+
+    let src = project_path.join("src");
+    std::fs::create_dir_all(&src).expect("Failed to create src");
+    std::fs::write(src.join("app.ts"), "export function startApp() {}\n")
+        .expect("Failed to write app.ts");
+    std::fs::write(
+        src.join("secret_token.ts"),
+        format!("export function loadSecretToken() {{\n  return \"{SECRET}\";\n}}\n"),
+    )
+    .expect("Failed to write secret_token.ts");
+    write_toml(project_path, true);
+
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None).expect("Failed to index project");
+    assert_eq!(indexed_files(project_path), vec!["src/app.ts"]);
+
+    // Opting out indexes the file like any other.
+    write_toml(project_path, false);
+    sync(project_path);
+    assert_eq!(
+        indexed_files(project_path),
+        vec!["src/app.ts", "src/secret_token.ts"]
+    );
+
+    // With the check back on, the already-indexed file is neither quoted in
+    // code blocks nor exported.
+    write_toml(project_path, true);
+    let options = BuildContextOptions {
+        max_nodes: Some(10),
+        max_code_blocks: Some(5),
+        max_code_block_size: Some(500),
+        include_code: Some(true),
+        format: Some(ContextFormat::Json),
+        search_limit: None,
+        traversal_depth: Some(1),
+        min_score: None,
+    };
+    let built = context::build_context(project_path, "loadSecretToken", &options)
+        .expect("Failed to build context");
+    assert!(built.contains("loadSecretToken"), "{built}");
+    assert!(
+        !built.contains(SECRET),
+        "secret leaked into context: {built}"
+    );
+
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let delta = export::export_delta(&conn, 0, None, &SensitiveFiles::for_project(project_path))
+        .expect("Failed to export graph");
+    assert!(delta.files.iter().all(|f| f.path == "src/app.ts"));
+    assert!(!delta.nodes.is_empty());
+    assert!(delta.nodes.iter().all(|n| n.file_path == "src/app.ts"));
+    let unfiltered = export::export_delta(&conn, 0, None, &SensitiveFiles::disabled())
+        .expect("Failed to export graph");
+    assert!(unfiltered.nodes.len() > delta.nodes.len());
+
+    // The next sync drops it from the index.
+    sync(project_path);
+    assert_eq!(indexed_files(project_path), vec!["src/app.ts"]);
+}
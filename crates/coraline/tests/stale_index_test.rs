@@ -0,0 +1,152 @@
+//! Integration tests for stale-index detection before queries
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::staleness::{self, Freshness};
+use coraline::tools::create_default_registry;
+use serde_json::json;
+
+fn set_policy(project: &Path, policy: &str) {
+    std::fs::write(
+        project.join(".coraline/config.toml"),
+        format!("[sync]\non_stale_index = \"{policy}\"\n"),
+    )
+    .expect("Failed to write config");
+}
+
+fn add_file(project: &Path) {
+    std::fs::write(
+        project.join("src/extra.ts"),
+        "export function triple(x: number): number {\n    return x * 3;\n}\n",
+    )
+    .expect("Failed to write file");
+}
+
+#[test]
+fn test_stale_index_warns_by_default() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+    assert!(matches!(
+        staleness::ensure_fresh(project).expect("Failed to check"),
+        Freshness::Fresh
+    ));
+
+    add_file(project);
+    std::fs::remove_file(project.join("src/user.ts")).expect("Failed to remove file");
+    let freshness = staleness::ensure_fresh(project).expect("Failed to check");
+    assert!(
+        matches!(freshness, Freshness::Stale(_)),
+        "a changed project is stale"
+    );
+    let Freshness::Stale(status) = freshness else {
+        return;
+    };
+    assert_eq!(
+        staleness::describe(&status),
+        "The index is behind the working tree: 1 file added, 1 removed."
+    );
+
+    let mut registry = create_default_registry(project);
+    registry.check_freshness(project);
+    let found = registry
+        .execute("coraline_search", json!({ "query": "add" }))
+        .expect("search should succeed");
+    assert_eq!(found.pointer("/stale_index/files_added"), Some(&json!(1)));
+    let memories = registry
+        .execute("coraline_list_memories", json!({}))
+        .expect("listing memories should succeed");
+    assert!(memories.get("stale_index").is_none());
+
+    set_policy(project, "off");
+    assert!(matches!(
+        staleness::ensure_fresh(project).expect("Failed to check"),
+        Freshness::Fresh
+    ));
+}
+
+#[test]
+fn test_stale_index_syncs_or_fails_by_policy() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+
+    set_policy(project, "fail");
+    add_file(project);
+    assert!(matches!(
+        staleness::ensure_fresh(project).expect("Failed to check"),
+        Freshness::Refused(_)
+    ));
+    let mut registry = create_default_registry(project);
+    registry.check_freshness(project);
+    let err = registry
+        .execute("coraline_search", json!({ "query": "triple" }))
+        .expect_err("a stale index fails graph queries");
+    assert_eq!(err.code, "stale_index");
+
+    set_policy(project, "sync");
+    let freshness = staleness::ensure_fresh(project).expect("Failed to check");
+    assert!(
+        matches!(freshness, Freshness::Synced(_)),
+        "the sync policy syncs a stale index"
+    );
+    let Freshness::Synced(result) = freshness else {
+        return;
+    };
+    assert_eq!(result.files_added, 1);
+    assert!(matches!(
+        staleness::ensure_fresh(project).expect("Failed to check"),
+        Freshness::Fresh
+    ));
+    assert!(
+        registry
+            .execute("coraline_search", json!({ "query": "triple" }))
+            .expect("search should succeed")
+            .to_string()
+            .contains("triple")
+    );
+}
+
+#[test]
+fn test_freshness_check_is_reused_until_the_next_sync() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+    let mut registry = create_default_registry(project);
+    registry.check_freshness(project);
+
+    let search = json!({ "query": "add" });
+    let first = registry
+        .execute("coraline_search", search.clone())
+        .expect("search should succeed");
+    assert!(first.get("stale_index").is_none());
+
+    add_file(project);
+    let second = registry
+        .execute("coraline_search", search.clone())
+        .expect("search should succeed");
+    assert!(
+        second.get("stale_index").is_none(),
+        "a back-to-back call reuses the first scan"
+    );
+    assert!(matches!(
+        staleness::ensure_fresh(project).expect("Failed to check"),
+        Freshness::Stale(_)
+    ));
+
+    set_policy(project, "sync");
+    assert!(matches!(
+        staleness::ensure_fresh(project).expect("Failed to check"),
+        Freshness::Synced(_)
+    ));
+    std::fs::remove_file(project.join("src/extra.ts")).expect("Failed to remove file");
+    set_policy(project, "warn");
+    let third = registry
+        .execute("coraline_search", search)
+        .expect("search should succeed");
+    assert_eq!(
+        third.pointer("/stale_index/files_removed"),
+        Some(&json!(1)),
+        "a sync discards the cached check"
+    );
+}
@@ -0,0 +1,183 @@
+//! Integration tests for TODO comment nodes
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::process::Command;
+
+use coraline::context;
+use coraline::db;
+use coraline::types::{BuildContextOptions, ContextFormat, EdgeKind, NodeKind, TaskContext};
+use serde_json::Value;
+
+const PROJECT_FILES: &[(&str, &str)] = &[
+    (
+        "src/fetch.rs",
+        r#"
+// TODO: pick the timeout from the config
+pub fn fetch_invoice(id: u64) -> String {
+    let url = format!("/invoices/{id}"); // FIXME(ana): escape the id
+    // We keep the TODO list short here.
+    url
+}
+
+pub struct Client {
+    /* HACK - until the v2 API ships */
+    base: String,
+}
+"#,
+    ),
+    (
+        "app/jobs.py",
+        "# TODO: move to a queue\n\nclass Worker:\n    def run(self):\n        # FIXME: retry on failure\n        # with a backoff\n        pass  # see TODO: below\n",
+    ),
+];
+
+#[test]
+fn test_todos_attach_to_their_symbol() {
+    let temp = common::with_files(PROJECT_FILES);
+    let conn = db::open_database(temp.path()).expect("open database");
+    let mut todos = db::get_nodes_by_kind(&conn, NodeKind::Todo).expect("todo nodes");
+    todos.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+
+    let parent_of = |id: &str| {
+        let edges = db::get_edges_by_target(&conn, id, Some(EdgeKind::Contains), 10)
+            .expect("contains edges");
+        let edge = edges.first().expect("a contains edge");
+        let parent = db::get_node_by_id(&conn, &edge.source)
+            .expect("query parent")
+            .expect("parent node");
+        parent.name
+    };
+    let listed: Vec<(&str, i64, Option<&str>, String)> = todos
+        .iter()
+        .map(|t| {
+            (
+                t.name.as_str(),
+                t.start_line,
+                t.signature.as_deref(),
+                parent_of(&t.id),
+            )
+        })
+        .collect();
+    let retry = todos.get(1).expect("the FIXME in run");
+    assert_eq!(
+        (retry.docstring.as_deref(), retry.end_line),
+        (Some("retry on failure\nwith a backoff"), 6)
+    );
+    assert_eq!(
+        listed,
+        [
+            (
+                "move to a queue",
+                1,
+                Some("TODO: move to a queue"),
+                "jobs.py".to_string()
+            ),
+            (
+                "retry on failure",
+                5,
+                Some("FIXME: retry on failure"),
+                "run".to_string()
+            ),
+            (
+                "pick the timeout from the config",
+                2,
+                Some("TODO: pick the timeout from the config"),
+                "fetch_invoice".to_string()
+            ),
+            (
+                "escape the id",
+                4,
+                Some("FIXME(ana): escape the id"),
+                "fetch_invoice".to_string()
+            ),
+            (
+                "until the v2 API ships",
+                10,
+                Some("HACK: until the v2 API ships"),
+                "Client".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_query_by_kind_and_context_of_parent() {
+    let temp = common::with_files(PROJECT_FILES);
+    let output = Command::new(env!("CARGO_BIN_EXE_coraline"))
+        .args(["query", "FIXME", "--kind", "todo", "--json", "-p"])
+        .arg(temp.path())
+        .env("NO_COLOR", "1")
+        .output()
+        .expect("Failed to run coraline");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let hits: Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+    let mut files: Vec<&str> = hits
+        .as_array()
+        .expect("an array of hits")
+        .iter()
+        .filter_map(|hit| hit.pointer("/node/file_path").and_then(Value::as_str))
+        .collect();
+    files.sort_unstable();
+    assert_eq!(files, ["app/jobs.py", "src/fetch.rs"]);
+
+    let options = BuildContextOptions {
+        max_nodes: Some(1),
+        max_code_blocks: Some(1),
+        max_code_block_size: Some(2000),
+        include_code: Some(true),
+        format: Some(ContextFormat::Json),
+        search_limit: None,
+        traversal_depth: Some(1),
+        min_score: None,
+    };
+    let built = context::build_context(temp.path(), "fetch_invoice", &options)
+        .expect("Failed to build context");
+    let built: TaskContext = serde_json::from_str(&built).expect("valid context");
+    let entry = built.entry_points.first().expect("an entry point");
+    assert_eq!(entry.name, "fetch_invoice");
+    let notes: Vec<&str> = built
+        .todos
+        .get(&entry.id)
+        .expect("the entry point has TODOs")
+        .iter()
+        .filter_map(|t| t.signature.as_deref())
+        .collect();
+    assert_eq!(
+        notes,
+        [
+            "TODO: pick the timeout from the config",
+            "FIXME(ana): escape the id"
+        ]
+    );
+
+    let options = BuildContextOptions {
+        format: Some(ContextFormat::Markdown),
+        ..options
+    };
+    let built = context::build_context(temp.path(), "fetch_invoice", &options)
+        .expect("Failed to build context");
+    assert!(
+        built.contains("  - FIXME(ana): escape the id (line 4)"),
+        "{built}"
+    );
+
+    let options = BuildContextOptions {
+        max_nodes: Some(5),
+        format: Some(ContextFormat::Json),
+        ..options
+    };
+    let built = context::build_context(temp.path(), "retry on failure", &options)
+        .expect("Failed to build context");
+    let built: TaskContext = serde_json::from_str(&built).expect("valid context");
+    assert!(
+        built.entry_points.iter().all(|n| n.kind != NodeKind::Todo),
+        "{:?}",
+        built.entry_points
+    );
+}
@@ -0,0 +1,363 @@
+//! Integration tests for the typed tool responses
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::tools::responses::{
+    CalleesResponse, CallersResponse, FileOverviewResponse, FindSymbolResponse, NodeDetails,
+    PathResponse, RenameImpactResponse, SearchResponse, StatsResponse,
+};
+use coraline::types::NodeKind;
+use coraline::{config, db, extraction, tools};
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+fn call<T: DeserializeOwned>(project: &Path, tool: &str, params: Value) -> T {
+    let registry = tools::create_default_registry(project);
+    let result = registry
+        .execute(tool, params)
+        .expect("the tool should succeed");
+    assert!(result.is_object(), "{tool} should return a JSON object");
+    serde_json::from_value(result).expect("the response should deserialize")
+}
+
+#[test]
+fn test_graph_tools_return_their_typed_responses() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+
+    let search: SearchResponse = call(project, "coraline_search", json!({ "query": "getHistory" }));
+    assert_eq!(search.count, search.results.len());
+    let hit = search
+        .results
+        .iter()
+        .find(|r| r.node.name == "getHistory")
+        .expect("getHistory should be found");
+
+    let node: NodeDetails = call(
+        project,
+        "coraline_node",
+        json!({ "node_id": hit.node.id, "include_edges": true }),
+    );
+    assert_eq!(node.name, "getHistory");
+    assert!(node.body.is_some_and(|b| b.contains("getHistory")));
+    assert!(node.incoming_edge_count.is_some());
+
+    let symbols: FindSymbolResponse = call(
+        project,
+        "coraline_find_symbol",
+        json!({ "name_pattern": "getHistory" }),
+    );
+    assert!(symbols.symbols.iter().all(|s| s.body.is_none()));
+
+    let callers: CallersResponse = call(
+        project,
+        "coraline_callers",
+        json!({ "node_id": hit.node.id }),
+    );
+    assert_eq!(callers.count, callers.callers.len());
+
+    let stats: StatsResponse = call(project, "coraline_stats", json!({}));
+    assert!(stats.totals.nodes > 0);
+    assert!(!stats.files_by_language.is_empty());
+}
+
+#[test]
+fn test_callers_and_callees_resolve_names() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+
+    // `add` is both a function and a `Calculator` method in math.ts.
+    let ambiguous: CallersResponse = call(project, "coraline_callers", json!({ "name": "add" }));
+    assert!(ambiguous.callers.is_empty());
+    assert!(ambiguous.candidates.len() >= 2);
+    assert!(ambiguous.candidates.iter().all(|c| c.name == "add"));
+
+    let absolute = project.join("src/math.ts");
+    for file_path in [
+        "math.ts",
+        "src/math.ts",
+        absolute.to_str().expect("utf-8 path"),
+    ] {
+        let callers: CallersResponse = call(
+            project,
+            "coraline_callers",
+            json!({ "name": "multiply", "file_path": file_path }),
+        );
+        assert!(callers.candidates.is_empty(), "{file_path}");
+        assert!(
+            callers.callers.iter().any(|c| c.node.name == "quickMath"),
+            "{file_path}"
+        );
+    }
+
+    let callees: CalleesResponse = call(
+        project,
+        "coraline_callees",
+        json!({ "name": "quickMath", "file": "index.ts" }),
+    );
+    assert!(callees.candidates.is_empty());
+    assert_eq!(callees.count, callees.callees.len());
+
+    let registry = tools::create_default_registry(project);
+    let err = registry
+        .execute(
+            "coraline_callers",
+            json!({ "name": "multiply", "file_path": "user.ts" }),
+        )
+        .expect_err("no multiply in user.ts");
+    assert_eq!(err.code, "not_found");
+}
+
+#[test]
+fn test_optional_fields_are_omitted_rather_than_null() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+    let registry = tools::create_default_registry(project);
+
+    let search: SearchResponse = call(project, "coraline_search", json!({ "query": "getHistory" }));
+    let id = &search.results.first().expect("getHistory is found").node.id;
+
+    let node = registry
+        .execute("coraline_node", json!({ "node_id": id }))
+        .expect("coraline_node should succeed");
+    assert!(node.get("incoming_edge_count").is_none());
+
+    let path: PathResponse = call(
+        project,
+        "coraline_path",
+        json!({ "from_id": id, "to_id": id, "max_depth": 0 }),
+    );
+    assert!(!path.path_found);
+    assert!(path.length.is_none() && path.message.is_some());
+}
+
+#[test]
+fn test_file_overview_nests_symbols_and_lists_dependencies() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+
+    let overview: FileOverviewResponse = call(
+        project,
+        "coraline_file_overview",
+        json!({ "file_path": project.join("src/math.ts") }),
+    );
+    assert_eq!(overview.file_path, "src/math.ts");
+    let calculator = overview
+        .outline
+        .iter()
+        .find(|n| n.name == "Calculator")
+        .expect("Calculator should be top-level");
+    assert!(
+        calculator.children.iter().any(|c| c.name == "getHistory"),
+        "methods nest under their class"
+    );
+    assert!(overview.outline.iter().all(|n| n.name != "getHistory"));
+    assert!(overview.exports.iter().any(|e| e.name == "add"));
+
+    let dependent = overview
+        .dependents
+        .iter()
+        .find(|d| d.file_path == "src/index.ts")
+        .expect("index.ts depends on math.ts");
+    assert!(dependent.edge_count > 0 && !dependent.symbols.is_empty());
+    assert_eq!(overview.dependent_count, overview.dependents.len());
+
+    let index: FileOverviewResponse = call(
+        project,
+        "coraline_file_overview",
+        json!({ "file_path": "src/index.ts" }),
+    );
+    assert!(
+        index
+            .imports
+            .iter()
+            .any(|i| i.name == "Calculator" && i.module.contains("math"))
+    );
+    assert!(
+        index
+            .depends_on
+            .iter()
+            .any(|d| d.file_path == "src/math.ts")
+    );
+
+    let registry = tools::create_default_registry(project);
+    let missing = registry
+        .execute(
+            "coraline_file_overview",
+            json!({ "file_path": "src/missing.ts" }),
+        )
+        .expect_err("unindexed file");
+    assert_eq!(missing.code, "not_found");
+}
+
+#[test]
+fn test_callers_and_callees_walk_the_call_graph_to_depth() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let project = temp_dir.path();
+    db::initialize_database(project).expect("Failed to initialize database");
+    std::fs::create_dir_all(project.join("src")).expect("Failed to create src");
+    std::fs::write(
+        project.join("src/chain.ts"),
+        "export function leaf(): number { return 1; }\n\
+         export function middle(): number { return leaf() + leaf(); }\n\
+         export function top(): number { return middle(); }\n\
+         export function entry(): number { return top() + middle(); }\n",
+    )
+    .expect("Failed to write chain.ts");
+    let cfg = config::create_default_config(project);
+    extraction::index_all(project, &cfg, false, None).expect("Failed to index project");
+
+    let direct: CallersResponse = call(project, "coraline_callers", json!({ "name": "leaf" }));
+    assert_eq!(direct.total, 1);
+    assert!(direct.callers.iter().all(|c| c.children.is_empty()));
+
+    // `entry` calls `middle` directly and through `top`; it is listed once,
+    // at the shallowest level.
+    let tree: CallersResponse = call(
+        project,
+        "coraline_callers",
+        json!({ "name": "leaf", "depth": 3 }),
+    );
+    assert_eq!((tree.count, tree.total, tree.truncated), (1, 3, false));
+    let middle = tree.callers.first().expect("leaf has a caller");
+    assert_eq!(middle.node.name, "middle");
+    let mut names: Vec<&str> = middle
+        .children
+        .iter()
+        .map(|c| c.node.name.as_str())
+        .collect();
+    names.sort_unstable();
+    assert_eq!(names, ["entry", "top"]);
+    assert!(middle.children.iter().all(|c| c.children.is_empty()));
+
+    let capped: CallersResponse = call(
+        project,
+        "coraline_callers",
+        json!({ "name": "leaf", "depth": 3, "max_nodes": 2 }),
+    );
+    assert_eq!((capped.total, capped.truncated), (2, true));
+
+    let callees: CalleesResponse = call(
+        project,
+        "coraline_callees",
+        json!({ "name": "entry", "depth": 2 }),
+    );
+    assert_eq!((callees.count, callees.total), (2, 3));
+    let middle = callees
+        .callees
+        .iter()
+        .find(|c| c.node.name == "middle")
+        .expect("entry calls middle");
+    let children: Vec<&str> = middle
+        .children
+        .iter()
+        .map(|c| c.node.name.as_str())
+        .collect();
+    assert_eq!(children, ["leaf"]);
+}
+
+#[test]
+fn test_rename_impact_lists_usages_exports_and_tests() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+    std::fs::write(
+        project.join("src/math.test.ts"),
+        "import { multiply } from './math';\n\
+         import { quickMath } from './index';\n\
+         export function testMultiply(): number { return multiply(2, 3); }\n\
+         export function testQuickMath(): number { return quickMath(); }\n",
+    )
+    .expect("Failed to write math.test.ts");
+    let cfg = config::create_default_config(project);
+    extraction::sync(project, &cfg, None).expect("Failed to sync");
+
+    let impact: RenameImpactResponse = call(
+        project,
+        "coraline_rename_impact",
+        json!({ "name": "multiply", "file_path": "math.ts" }),
+    );
+    assert_eq!(impact.symbol.name, "multiply");
+    assert_eq!(
+        (impact.definition.file_path.as_str(), impact.definition.line),
+        ("src/math.ts", 13)
+    );
+
+    let users: Vec<&str> = impact
+        .usages
+        .iter()
+        .map(|u| u.used_by.name.as_str())
+        .collect();
+    assert!(users.contains(&"quickMath"), "{users:?}");
+    assert!(users.contains(&"testMultiply"), "{users:?}");
+    assert!(impact.usages.iter().all(|u| u.location.column.is_some()));
+
+    let importers: Vec<&str> = impact
+        .imports
+        .iter()
+        .map(|i| i.file_path.as_str())
+        .collect();
+    assert_eq!(importers, ["src/index.ts", "src/math.test.ts"]);
+    assert!(
+        impact
+            .exports
+            .iter()
+            .any(|e| e.file_path == "src/math.ts" && e.kind == NodeKind::Export)
+    );
+
+    // `testQuickMath` reaches `multiply` only through `quickMath`.
+    let tests: Vec<&str> = impact.tests.iter().map(|t| t.name.as_str()).collect();
+    assert!(tests.contains(&"testMultiply"), "{tests:?}");
+    assert!(tests.contains(&"testQuickMath"), "{tests:?}");
+    assert!(!tests.contains(&"quickMath"), "{tests:?}");
+
+    let files: Vec<&str> = impact.files.iter().map(|f| f.file_path.as_str()).collect();
+    assert_eq!(files, ["src/index.ts", "src/math.test.ts", "src/math.ts"]);
+}
+
+#[test]
+fn test_results_are_marked_stale_when_their_lines_changed() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path();
+
+    let search = |query: &str| -> SearchResponse {
+        call(project, "coraline_search", json!({ "query": query }))
+    };
+    assert!(search("multiply").results.iter().all(|r| !r.node.stale));
+
+    // A line inserted above `multiply` shifts it; `subtract` above the
+    // edit keeps its lines.
+    let math = project.join("src/math.ts");
+    let source = std::fs::read_to_string(&math).expect("Failed to read math.ts");
+    let edited = source.replace(
+        "export function multiply",
+        "// Scales a by b.\nexport function multiply",
+    );
+    std::fs::write(&math, edited).expect("Failed to write math.ts");
+
+    let multiply = search("multiply");
+    let hit = multiply
+        .results
+        .iter()
+        .find(|r| r.node.name == "multiply")
+        .expect("multiply should be found");
+    assert!(hit.node.stale);
+    let subtract = search("subtract");
+    assert!(subtract.results.iter().any(|r| r.node.name == "subtract"));
+    assert!(subtract.results.iter().all(|r| !r.node.stale));
+
+    let callees: CalleesResponse = call(
+        project,
+        "coraline_callees",
+        json!({ "name": "quickMath", "file": "index.ts" }),
+    );
+    let stale: Vec<(&str, bool)> = callees
+        .callees
+        .iter()
+        .map(|c| (c.node.name.as_str(), c.stale))
+        .collect();
+    assert!(stale.contains(&("multiply", true)), "{stale:?}");
+}
@@ -0,0 +1,95 @@
+//! Integration tests for the onboarding tour
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::collections::HashSet;
+
+use coraline::db;
+use coraline::tour::{self, TourOptions};
+use tempfile::TempDir;
+
+/// Index the fixture as `src/*.ts` plus a `src/main.ts` starting the app.
+fn setup_indexed_project() -> TempDir {
+    common::with_fixture(&[(
+        "src/main.ts",
+        "import { App } from './index';\n\nexport function main(): void {\n    new App().run();\n}\n",
+    )])
+}
+
+#[test]
+fn test_tour_lists_entry_points_modules_and_types() {
+    let temp = setup_indexed_project();
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+    let walkthrough = tour::build_tour(&conn, &TourOptions::default()).expect("Failed to build");
+
+    assert_eq!(walkthrough.files, 4);
+    assert!(walkthrough.symbols > 0);
+
+    let main = walkthrough.entry_points.first().expect("an entry point");
+    assert_eq!(
+        (main.name.as_str(), main.kind.as_str(), main.file.as_str()),
+        ("main", "function", "src/main.ts")
+    );
+    assert!(
+        walkthrough
+            .entry_points
+            .iter()
+            .any(|s| s.file == "src/index.ts" && s.kind == "file"),
+        "index.ts is a conventional entry file"
+    );
+
+    let math = walkthrough
+        .core_modules
+        .iter()
+        .find(|m| m.file == "src/math.ts")
+        .expect("math.ts is used by index.ts");
+    assert!(math.dependents >= 1);
+    assert!(math.symbols > 0);
+
+    assert_eq!(
+        walkthrough.key_types.first().map(|t| t.name.as_str()),
+        Some("UserService")
+    );
+    assert!(walkthrough.key_types.iter().any(|t| t.name == "Calculator"));
+    assert!(walkthrough.routes.is_empty());
+
+    let order: Vec<&str> = walkthrough
+        .reading_order
+        .iter()
+        .map(|s| s.file.as_str())
+        .collect();
+    assert_eq!(order.first(), Some(&"src/main.ts"));
+    assert!(order.contains(&"src/math.ts"));
+    assert_eq!(
+        order.iter().collect::<HashSet<_>>().len(),
+        order.len(),
+        "each file is suggested once"
+    );
+}
+
+#[test]
+fn test_tour_markdown_links_and_limit() {
+    let temp = setup_indexed_project();
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+
+    let walkthrough = tour::build_tour(&conn, &TourOptions::default()).expect("Failed to build");
+    let markdown = tour::render_markdown(&walkthrough, "demo");
+    assert!(markdown.starts_with("# Tour of demo\n"));
+    for heading in [
+        "## Entry Points",
+        "## Core Modules",
+        "## Key Types",
+        "## Routes",
+        "## Suggested Reading Order",
+    ] {
+        assert!(markdown.contains(heading), "missing {heading}");
+    }
+    assert!(markdown.contains("[src/main.ts:3](src/main.ts#L3)"));
+    assert!(markdown.contains("1. [src/main.ts:3]"));
+
+    let short = tour::build_tour(&conn, &TourOptions { limit: 1 }).expect("Failed to build");
+    assert_eq!(short.entry_points.len(), 1);
+    assert_eq!(short.key_types.len(), 1);
+    assert_eq!(short.reading_order.len(), 1);
+}
@@ -0,0 +1,74 @@
+//! Integration tests for the terminal explorer
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::tui::{Explorer, Pane};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+fn press(explorer: &mut Explorer, code: KeyCode) {
+    explorer.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+}
+
+fn screen(explorer: &Explorer) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).expect("Failed to create terminal");
+    terminal
+        .draw(|frame| explorer.draw(frame))
+        .expect("Failed to draw");
+    terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(ratatui::buffer::Cell::symbol)
+        .collect()
+}
+
+#[test]
+fn test_explorer_follows_calls_and_walks_back() {
+    let temp_dir = common::setup_indexed_project();
+    let mut explorer = Explorer::open(temp_dir.path()).expect("Failed to open explorer");
+    assert!(screen(&explorer).contains("No symbol selected."));
+
+    for c in "quickMath".chars() {
+        press(&mut explorer, KeyCode::Char(c));
+    }
+    assert_eq!(explorer.query(), "quickMath");
+    let current = explorer.current().expect("a result is selected");
+    assert_eq!(current.name, "quickMath");
+    let callees: Vec<&str> = explorer.callees().iter().map(|n| n.name.as_str()).collect();
+    assert!(callees.contains(&"multiply"), "callees: {callees:?}");
+    assert!(screen(&explorer).contains(&format!("Callees ({})", callees.len())));
+
+    press(&mut explorer, KeyCode::Tab);
+    press(&mut explorer, KeyCode::Tab);
+    assert_eq!(explorer.focus(), Pane::Callees);
+    let position = explorer
+        .callees()
+        .iter()
+        .position(|n| n.name == "multiply")
+        .expect("multiply is a callee");
+    for _ in 0..position {
+        press(&mut explorer, KeyCode::Down);
+    }
+    press(&mut explorer, KeyCode::Enter);
+    assert_eq!(
+        explorer.current().map(|n| n.name.as_str()),
+        Some("multiply")
+    );
+    assert!(explorer.callers().iter().any(|n| n.name == "quickMath"));
+    assert!(screen(&explorer).contains("1 back with Esc"));
+
+    press(&mut explorer, KeyCode::Esc);
+    assert_eq!(
+        explorer.current().map(|n| n.name.as_str()),
+        Some("quickMath")
+    );
+    press(&mut explorer, KeyCode::Esc);
+    assert_eq!(explorer.focus(), Pane::Results);
+    assert!(!explorer.should_quit());
+    press(&mut explorer, KeyCode::Esc);
+    assert!(explorer.should_quit());
+}
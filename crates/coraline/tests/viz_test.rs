@@ -0,0 +1,78 @@
+//! Integration tests for the graph explorer endpoints
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use coraline::config::SearchConfig;
+use coraline::{db, viz};
+use serde_json::{Value, json};
+
+fn get_json(conn: &rusqlite::Connection, url: &str) -> (u16, Value) {
+    let response = viz::route(conn, &SearchConfig::default(), url);
+    assert_eq!(response.content_type, "application/json", "url: {url}");
+    let body = serde_json::from_str(&response.body).expect("Response should be valid JSON");
+    (response.status, body)
+}
+
+#[test]
+fn test_viz_serves_explorer_page() {
+    let temp = common::setup_indexed_project();
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+
+    let response = viz::route(&conn, &SearchConfig::default(), "/");
+    assert_eq!(response.status, 200);
+    assert!(response.content_type.starts_with("text/html"));
+    assert!(response.body.contains("/graph.js"));
+    assert!(!response.body.contains("https://"));
+
+    let script = viz::route(&conn, &SearchConfig::default(), "/graph.js");
+    assert_eq!(script.status, 200);
+    assert!(script.content_type.starts_with("application/javascript"));
+    assert!(script.body.contains("class GraphView"));
+}
+
+#[test]
+fn test_viz_search_and_neighbors() {
+    let temp = common::setup_indexed_project();
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+
+    let (status, body) = get_json(&conn, "/api/search?q=Calculator&kind=class&limit=5");
+    assert_eq!(status, 200);
+    let calculator = body
+        .pointer("/results/0/node")
+        .expect("Expected a search result");
+    assert_eq!(calculator.get("name"), Some(&json!("Calculator")));
+    let id = calculator
+        .get("id")
+        .and_then(Value::as_str)
+        .expect("Node id should be a string");
+
+    let (status, node) = get_json(&conn, &format!("/api/nodes/{id}"));
+    assert_eq!(status, 200);
+    assert_eq!(node.get("name"), Some(&json!("Calculator")));
+
+    let (status, graph) = get_json(
+        &conn,
+        &format!("/api/nodes/{id}/neighbors?direction=outgoing&edge_kinds=contains"),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(graph.get("root"), Some(&json!(id)));
+    let edges = graph
+        .get("edges")
+        .and_then(Value::as_array)
+        .expect("edges should be an array");
+    assert!(!edges.is_empty(), "Calculator should contain its methods");
+    assert!(edges.iter().all(|e| e["kind"] == "contains"));
+}
+
+#[test]
+fn test_viz_rejects_bad_requests() {
+    let temp = common::setup_indexed_project();
+    let conn = db::open_database(temp.path()).expect("Failed to open database");
+
+    assert_eq!(get_json(&conn, "/api/search").0, 400);
+    assert_eq!(get_json(&conn, "/api/search?q=add&kind=nope").0, 400);
+    assert_eq!(get_json(&conn, "/api/nodes/missing").0, 404);
+    assert_eq!(get_json(&conn, "/api/nodes/missing/neighbors").0, 404);
+    assert_eq!(get_json(&conn, "/nowhere").0, 404);
+}
@@ -0,0 +1,87 @@
+//! Integration tests for watch mode
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use coraline::types::NodeKind;
+use coraline::{config, db, watch};
+use tempfile::TempDir;
+
+fn has_function(project: &Path, file: &str, name: &str) -> bool {
+    let conn = db::open_database(project).expect("Failed to open database");
+    db::get_nodes_by_file(&conn, file, Some(NodeKind::Function))
+        .expect("Failed to read nodes")
+        .iter()
+        .any(|n| n.name == name)
+}
+
+#[test]
+fn test_only_graph_inputs_are_relevant() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let cfg = config::create_default_config(temp_dir.path());
+    assert!(watch::is_relevant("src/math.ts", &cfg));
+    assert!(watch::is_relevant(".github/CODEOWNERS", &cfg));
+    // A removed directory is only reported by its own path.
+    assert!(watch::is_relevant("src/old", &cfg));
+    assert!(!watch::is_relevant(".coraline/coraline.db", &cfg));
+    assert!(!watch::is_relevant(".git/index", &cfg));
+    assert!(!watch::is_relevant("node_modules/pkg/index.js", &cfg));
+    assert!(!watch::is_relevant("notes.bin", &cfg));
+}
+
+#[test]
+fn test_watch_syncs_after_changes() {
+    let temp_dir = common::setup_indexed_project();
+    let project = temp_dir.path().to_path_buf();
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+    let watcher = {
+        let project = project.clone();
+        let shutdown = Arc::clone(&shutdown);
+        std::thread::spawn(move || {
+            watch::watch(&project, Duration::from_millis(100), &shutdown, |result| {
+                let changed = result
+                    .as_ref()
+                    .map(|r| r.files_added + r.files_modified + r.files_removed)
+                    .unwrap_or_default();
+                let _ = tx.send(changed);
+            })
+        })
+    };
+    // Let the watcher subscribe before changing anything.
+    std::thread::sleep(Duration::from_millis(500));
+
+    std::fs::write(
+        project.join("src/extra.ts"),
+        "export function triple(x: number): number {\n    return x * 3;\n}\n",
+    )
+    .expect("Failed to write file");
+    std::fs::remove_file(project.join("src/user.ts")).expect("Failed to remove file");
+
+    let mut changed = 0;
+    while changed < 2 {
+        changed += rx
+            .recv_timeout(Duration::from_secs(20))
+            .expect("watch should sync within 20 seconds");
+    }
+    assert!(has_function(&project, "src/extra.ts", "triple"));
+    let conn = db::open_database(&project).expect("Failed to open database");
+    assert!(
+        db::get_file_record(&conn, "src/user.ts")
+            .expect("Failed to read file record")
+            .is_none()
+    );
+
+    shutdown.store(true, Ordering::Relaxed);
+    watcher
+        .join()
+        .expect("watch thread panicked")
+        .expect("watch should stop cleanly");
+}
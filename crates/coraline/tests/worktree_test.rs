@@ -0,0 +1,199 @@
+//! Integration tests for worktree detection and the shared parse cache
+#![allow(clippy::expect_used)]
+
+mod common;
+
+use std::path::Path;
+
+use coraline::annotations::Annotations;
+use coraline::sync::GitHooksManager;
+use coraline::types::NodeKind;
+use coraline::utils::hash_sha256;
+use coraline::worktree::{self, ParseCache};
+use coraline::{config, db, extraction};
+use tempfile::TempDir;
+
+fn copy_fixture(project_path: &Path) {
+    let fixture_src = Path::new("tests/fixtures/typescript-simple");
+    let fixture_dst = project_path.join("src");
+    std::fs::create_dir_all(&fixture_dst).expect("Failed to create fixture directory");
+    for entry in std::fs::read_dir(fixture_src).expect("Failed to read fixture directory") {
+        let entry = entry.expect("Failed to read directory entry");
+        std::fs::copy(entry.path(), fixture_dst.join(entry.file_name()))
+            .expect("Failed to copy fixture file");
+    }
+}
+
+fn index(project_path: &Path) -> usize {
+    db::initialize_database(project_path).expect("Failed to initialize database");
+    let cfg = config::create_default_config(project_path);
+    extraction::index_all(project_path, &cfg, false, None)
+        .expect("Failed to index project")
+        .files_indexed
+}
+
+fn node_ids(project_path: &Path) -> Vec<String> {
+    let conn = db::open_database(project_path).expect("Failed to open database");
+    let mut ids: Vec<String> = db::list_files(&conn)
+        .expect("Failed to list files")
+        .iter()
+        .flat_map(|f| db::get_nodes_by_file(&conn, &f.path, None).expect("Failed to read nodes"))
+        .map(|n| n.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_linked_worktree_reuses_parse_cache() {
+    let temp = TempDir::new().expect("Failed to create temp directory");
+    let main = temp.path().join("main");
+    let linked = temp.path().join("feature");
+    copy_fixture(&main);
+    std::fs::write(main.join(".gitignore"), ".coraline/\n").expect("Failed to write .gitignore");
+    common::git(&main, &["init", "-q"]);
+    common::git(&main, &["add", "."]);
+    common::git(&main, &["commit", "-q", "-m", "initial"]);
+
+    // Without linked worktrees the cache stays off.
+    assert!(ParseCache::for_project(&main).is_none());
+    let main_worktree = worktree::detect(&main).expect("main checkout");
+    assert!(!main_worktree.is_linked);
+
+    common::git(
+        &main,
+        &[
+            "worktree",
+            "add",
+            "-q",
+            "-b",
+            "feature",
+            linked.to_str().expect("utf-8 path"),
+        ],
+    );
+    let linked_worktree = worktree::detect(&linked).expect("linked worktree");
+    assert!(linked_worktree.is_linked);
+    assert_eq!(linked_worktree.git_common_dir, main_worktree.git_common_dir);
+
+    let listed = worktree::list(&linked);
+    assert_eq!(listed.len(), 2);
+    assert_eq!(
+        listed.iter().map(|w| w.is_linked).collect::<Vec<_>>(),
+        [false, true]
+    );
+
+    let files = index(&main);
+    assert!(files > 0);
+    let cache = ParseCache::for_project(&linked).expect("cache is on with a linked worktree");
+    assert_eq!(cache.stats().entries, files);
+
+    // Identical content in the other worktree is served from the cache:
+    // mark one cached node and look for the mark in the linked index.
+    let source = std::fs::read_to_string(linked.join("src/math.ts")).expect("Failed to read");
+    let hash = hash_sha256(&source);
+    let mut cached = cache.get("src/math.ts", &hash).expect("math.ts is cached");
+    let marked = cached
+        .nodes
+        .iter_mut()
+        .find(|n| n.kind == NodeKind::Function)
+        .expect("a cached function");
+    marked.docstring = Some("from the parse cache".to_string());
+    let marked_id = marked.id.clone();
+    cache.put("src/math.ts", &hash, &cached);
+
+    assert_eq!(index(&linked), files);
+    assert_eq!(cache.stats().entries, files);
+    assert_eq!(node_ids(&linked), node_ids(&main));
+    let conn = db::open_database(&linked).expect("Failed to open database");
+    let node = db::get_node_by_id(&conn, &marked_id)
+        .expect("Failed to read node")
+        .expect("marked node is stored");
+    assert_eq!(node.docstring.as_deref(), Some("from the parse cache"));
+
+    assert_eq!(cache.prune(None), files);
+    assert_eq!(cache.stats().entries, 0);
+
+    // Git runs the common directory's hooks in every worktree.
+    let hooks = GitHooksManager::new(&linked);
+    assert!(hooks.is_git_repository());
+    assert!(hooks.install_hook().success);
+    assert!(
+        main_worktree
+            .git_common_dir
+            .join("hooks/post-commit")
+            .is_file()
+    );
+    assert!(GitHooksManager::new(&main).is_hook_installed());
+}
+
+#[test]
+fn test_submodules_are_indexed_as_tagged_sub_projects() {
+    let temp = TempDir::new().expect("Failed to create temp directory");
+    let library = temp.path().join("library");
+    std::fs::create_dir_all(&library).expect("Failed to create library");
+    std::fs::write(
+        library.join("shapes.ts"),
+        "export function area(w: number, h: number): number {\n    return w * h;\n}\n",
+    )
+    .expect("Failed to write library file");
+    common::git(&library, &["init", "-q"]);
+    common::git(&library, &["add", "."]);
+    common::git(&library, &["commit", "-q", "-m", "library"]);
+
+    let main = temp.path().join("main");
+    copy_fixture(&main);
+    std::fs::write(main.join(".gitignore"), ".coraline/\n").expect("Failed to write .gitignore");
+    common::git(&main, &["init", "-q"]);
+    common::git(
+        &main,
+        &[
+            "-c",
+            "protocol.file.allow=always",
+            "submodule",
+            "add",
+            "-q",
+            library.to_str().expect("utf-8 path"),
+            "packages/shapes",
+        ],
+    );
+    assert_eq!(
+        worktree::submodules(&main),
+        vec![worktree::Submodule {
+            name: "packages/shapes".to_string(),
+            path: "packages/shapes".to_string(),
+        }]
+    );
+    // A submodule's `.git` is a `gitdir:` file into the parent's modules.
+    let hooks = GitHooksManager::new(&main.join("packages/shapes"));
+    assert!(hooks.is_git_repository());
+    assert!(hooks.hooks_dir().starts_with(main.join(".git/modules")));
+
+    index(&main);
+    let conn = db::open_database(&main).expect("Failed to open database");
+    let area = db::get_nodes_by_file(&conn, "packages/shapes/shapes.ts", Some(NodeKind::Function))
+        .expect("Failed to read nodes");
+    assert_eq!(area.len(), 1);
+    let annotations = Annotations::load(&conn).expect("Failed to load annotations");
+    assert_eq!(
+        annotations.tags_for(area.first().expect("one function")),
+        vec!["project:packages/shapes"]
+    );
+
+    std::fs::write(
+        main.join(".coraline/config.toml"),
+        "[indexing]\nindex_submodules = false\n",
+    )
+    .expect("Failed to write config");
+    let cfg = config::create_default_config(&main);
+    extraction::sync(&main, &cfg, None).expect("Failed to sync");
+    assert!(
+        db::get_file_record(&conn, "packages/shapes/shapes.ts")
+            .expect("Failed to read file record")
+            .is_none()
+    );
+    assert!(
+        Annotations::load(&conn)
+            .expect("Failed to load annotations")
+            .is_empty()
+    );
+}